@@ -0,0 +1,15 @@
+-- Tracing IDs from the producer (NULL for events published before them)
+--
+-- event_id: the Kafka event the row came from (a transfer's two rows share it)
+-- correlation_id: every event of one user action (one API request)
+-- causation_id: the event or request that caused this one
+
+ALTER TABLE transaction_events
+    ADD COLUMN IF NOT EXISTS event_id VARCHAR(36),
+    ADD COLUMN IF NOT EXISTS correlation_id VARCHAR(128),
+    ADD COLUMN IF NOT EXISTS causation_id VARCHAR(128);
+
+-- "Everything that happened because of request X"
+CREATE INDEX IF NOT EXISTS idx_transaction_events_correlation_id
+    ON transaction_events(correlation_id)
+    WHERE correlation_id IS NOT NULL;
@@ -0,0 +1,12 @@
+-- Idempotency enforced by the database: one row per (transaction_id, event_type)
+--
+-- The old index was unique on transaction_id alone, but a transfer's two legs
+-- (TRANSFER_OUT / TRANSFER_IN) share the transfer's reference_id. Inserts now
+-- use ON CONFLICT DO NOTHING against this index instead of checking first,
+-- so concurrent workers can't both store the same event.
+
+DROP INDEX IF EXISTS idx_transaction_events_transaction_id;
+
+CREATE UNIQUE INDEX IF NOT EXISTS idx_transaction_events_transaction_id_type
+    ON transaction_events(transaction_id, event_type)
+    WHERE transaction_id IS NOT NULL;
@@ -0,0 +1,27 @@
+-- Per-wallet event numbering, as seen by the consumer
+--
+-- wallet_sequences: the highest `sequence` stored per wallet (partition key)
+-- sequence_gaps: numbers skipped over when a higher one arrived first;
+--   resolved_at is set when the missing event turns up late
+--
+-- Events published before numbering existed carry no sequence and aren't
+-- tracked.
+
+CREATE TABLE IF NOT EXISTS wallet_sequences (
+    wallet_id VARCHAR(36) PRIMARY KEY,
+    last_sequence BIGINT NOT NULL,
+    updated_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE TABLE IF NOT EXISTS sequence_gaps (
+    wallet_id VARCHAR(36) NOT NULL,
+    sequence BIGINT NOT NULL,
+    detected_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    resolved_at TIMESTAMP WITH TIME ZONE,
+    PRIMARY KEY (wallet_id, sequence)
+);
+
+-- "Which events are still missing?"
+CREATE INDEX IF NOT EXISTS idx_sequence_gaps_open
+    ON sequence_gaps(detected_at)
+    WHERE resolved_at IS NULL;
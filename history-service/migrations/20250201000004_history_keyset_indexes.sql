@@ -0,0 +1,13 @@
+-- Indexes for keyset pagination of the history endpoints
+-- Pages are read as (created_at, id) < cursor ORDER BY created_at DESC, id DESC,
+-- so the ID is part of the index; these replace the plain per-wallet and
+-- per-user-by-time indexes
+
+CREATE INDEX IF NOT EXISTS idx_transaction_events_wallet_page
+    ON transaction_events(wallet_id, created_at DESC, id DESC);
+
+CREATE INDEX IF NOT EXISTS idx_transaction_events_user_page
+    ON transaction_events(user_id, created_at DESC, id DESC);
+
+DROP INDEX IF EXISTS idx_transaction_events_wallet_id;
+DROP INDEX IF EXISTS idx_transaction_events_user_created;
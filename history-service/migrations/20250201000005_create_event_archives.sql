@@ -0,0 +1,27 @@
+-- Months of transaction_events moved to cold storage (see cold_storage.rs)
+--
+-- event_archives: one row per exported month - where the file is and what
+--   it must hash to. range_end of the newest row is the archive horizon:
+--   history before it is no longer in Postgres
+-- archived_balances: per wallet, the credits minus debits of every event
+--   pruned so far, so balances can still be rebuilt from what's left
+
+CREATE TABLE IF NOT EXISTS event_archives (
+    period CHAR(7) PRIMARY KEY,              -- "2024-01"
+    range_start TIMESTAMP WITH TIME ZONE NOT NULL,
+    range_end TIMESTAMP WITH TIME ZONE NOT NULL,
+    object_key TEXT NOT NULL,
+    format VARCHAR(16) NOT NULL,
+    event_count BIGINT NOT NULL,
+    byte_size BIGINT NOT NULL,
+    sha256 CHAR(64) NOT NULL,
+    archived_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE TABLE IF NOT EXISTS archived_balances (
+    wallet_id VARCHAR(36) PRIMARY KEY,
+    balance DECIMAL(19, 4) NOT NULL,
+    events BIGINT NOT NULL,
+    last_event_at TIMESTAMP WITH TIME ZONE,
+    archived_through TIMESTAMP WITH TIME ZONE NOT NULL
+);
@@ -0,0 +1,30 @@
+-- Daily totals per user, wallet and event type (see summary.rs)
+--
+-- Kept up to date by the statement that stores events, so it only ever
+-- counts rows actually inserted (duplicates add nothing). Summaries read a
+-- row per day and type here instead of every event.
+--
+-- day is the UTC date of created_at, the same grouping the summaries used
+-- on transaction_events. Rows outlive the events they count: months moved
+-- to cold storage keep their totals.
+
+CREATE TABLE IF NOT EXISTS user_daily_summary (
+    user_id VARCHAR(100) NOT NULL,
+    wallet_id VARCHAR(36) NOT NULL,
+    day DATE NOT NULL,
+    event_type VARCHAR(30) NOT NULL,
+    event_count BIGINT NOT NULL,
+    amount DECIMAL(19, 4) NOT NULL,
+    PRIMARY KEY (user_id, day, wallet_id, event_type)
+);
+
+-- Wallet summaries
+CREATE INDEX IF NOT EXISTS idx_user_daily_summary_wallet_day
+    ON user_daily_summary (wallet_id, day);
+
+-- Everything stored so far
+INSERT INTO user_daily_summary (user_id, wallet_id, day, event_type, event_count, amount)
+SELECT user_id, wallet_id, (created_at AT TIME ZONE 'UTC')::DATE, event_type, COUNT(*), SUM(amount)
+FROM transaction_events
+GROUP BY 1, 2, 3, 4
+ON CONFLICT DO NOTHING;
@@ -0,0 +1,24 @@
+-- Covering indexes for the history reads
+--
+-- 1. The per-wallet and per-user keyset indexes also carry event_type and
+--    amount, so summaries over part of a day (grouped straight from
+--    transaction_events) are answered from the index alone
+-- 2. Cold storage streams a month as created_at, id: with the ID in the
+--    index it comes out in order instead of being sorted
+-- 3. The plain user_id index is a prefix of the per-user one
+
+CREATE INDEX IF NOT EXISTS idx_transaction_events_wallet_time
+    ON transaction_events(wallet_id, created_at DESC, id DESC)
+    INCLUDE (event_type, amount);
+
+CREATE INDEX IF NOT EXISTS idx_transaction_events_user_time
+    ON transaction_events(user_id, created_at DESC, id DESC)
+    INCLUDE (event_type, amount);
+
+CREATE INDEX IF NOT EXISTS idx_transaction_events_created_id
+    ON transaction_events(created_at, id);
+
+DROP INDEX IF EXISTS idx_transaction_events_wallet_page;
+DROP INDEX IF EXISTS idx_transaction_events_user_page;
+DROP INDEX IF EXISTS idx_transaction_events_created_at;
+DROP INDEX IF EXISTS idx_transaction_events_user_id;
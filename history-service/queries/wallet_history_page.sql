@@ -0,0 +1,10 @@
+SELECT id, wallet_id, user_id, amount, event_type, transaction_id, created_at, event_data,
+    event_id, correlation_id, causation_id
+FROM transaction_events
+WHERE wallet_id = $1
+  AND (created_at, id) < (COALESCE($2::TIMESTAMPTZ, 'infinity'), COALESCE($3::VARCHAR, ''))
+  AND created_at >= COALESCE($4::TIMESTAMPTZ, '-infinity')
+  AND created_at < COALESCE($5::TIMESTAMPTZ, 'infinity')
+  AND ($6::TEXT[] IS NULL OR event_type = ANY($6))
+ORDER BY created_at DESC, id DESC
+LIMIT $7
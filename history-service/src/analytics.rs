@@ -0,0 +1,355 @@
+use crate::errors::{HistoryError, HistoryResult};
+use crate::filter::{HistoryFilter, WalletSelection};
+use crate::models::TransactionEvent;
+use crate::repository::EventRepository;
+use crate::summary::{SummaryPeriod, SummaryRow};
+use chrono::{DateTime, NaiveDateTime, Utc};
+use futures_util::StreamExt;
+use rust_decimal::Decimal;
+use serde::Deserialize;
+use std::time::Duration;
+
+/// Events copied per INSERT when backfilling
+const BACKFILL_BATCH: usize = 10_000;
+
+/// Where the analytical copy of the events lives (CLICKHOUSE_*)
+///
+/// Why?
+/// - Summaries over partial days group every event of the window in
+///   Postgres; ClickHouse scans a column of them instead
+/// - Postgres keeps the point lookups (history pages, sequences, balances)
+///   and stays the source of truth: the copy can be dropped and backfilled
+#[derive(Debug, Clone)]
+pub struct ClickHouseConfig {
+    /// CLICKHOUSE_URL - the HTTP interface, e.g. `http://clickhouse:8123`
+    pub url: String,
+    /// CLICKHOUSE_DATABASE (default `default`)
+    pub database: String,
+    /// CLICKHOUSE_USER / CLICKHOUSE_PASSWORD (default: the server's
+    /// default user)
+    pub user: Option<String>,
+    pub password: Option<String>,
+    /// CLICKHOUSE_TIMEOUT_MS - per request (default 10000)
+    pub timeout: Duration,
+}
+
+impl ClickHouseConfig {
+    /// None when CLICKHOUSE_URL is unset: everything stays on Postgres
+    pub fn from_env() -> Option<Self> {
+        let non_empty = |name: &str| std::env::var(name).ok().filter(|v| !v.is_empty());
+
+        let url = non_empty("CLICKHOUSE_URL")?;
+        Some(Self {
+            url: url.trim_end_matches('/').to_string(),
+            database: non_empty("CLICKHOUSE_DATABASE").unwrap_or_else(|| "default".to_string()),
+            user: non_empty("CLICKHOUSE_USER"),
+            password: non_empty("CLICKHOUSE_PASSWORD"),
+            timeout: Duration::from_millis(
+                non_empty("CLICKHOUSE_TIMEOUT_MS")
+                    .and_then(|v| v.parse().ok())
+                    .unwrap_or(10_000),
+            ),
+        })
+    }
+}
+
+/// The events in ClickHouse, for the aggregate queries
+///
+/// Written by the statement path that stores events: only rows Postgres
+/// actually inserted are copied, after its commit. A failed copy is logged
+/// and skipped rather than failing the message (Postgres already has it,
+/// so a retry would copy nothing) - `--analytics-backfill` repairs any
+/// drift.
+///
+/// The table is a ReplacingMergeTree keyed by the Postgres row ID, so a
+/// row copied twice (a backfill racing the consumer) counts once: queries
+/// read it with FINAL. Only what the summaries need is copied - no
+/// event_data - and USER_ANONYMIZED rewrites user_id here too.
+#[derive(Clone)]
+pub struct ClickHouse {
+    client: reqwest::Client,
+    config: ClickHouseConfig,
+}
+
+impl ClickHouse {
+    /// Connect and create the events table if it's missing
+    pub async fn connect(config: ClickHouseConfig) -> HistoryResult<Self> {
+        let client = reqwest::Client::builder()
+            .timeout(config.timeout)
+            .build()
+            .map_err(|e| HistoryError::AnalyticsError(e.to_string()))?;
+        let analytics = Self { client, config };
+
+        analytics
+            .execute(
+                r#"
+                CREATE TABLE IF NOT EXISTS transaction_events (
+                    id String,
+                    wallet_id String,
+                    user_id String,
+                    event_type LowCardinality(String),
+                    amount Decimal(19, 4),
+                    created_at DateTime64(6, 'UTC'),
+                    INDEX idx_user_id user_id TYPE bloom_filter GRANULARITY 4
+                )
+                ENGINE = ReplacingMergeTree
+                PARTITION BY toYYYYMM(created_at)
+                ORDER BY (wallet_id, event_type, id)
+                "#,
+                &[],
+                None,
+            )
+            .await?;
+
+        Ok(analytics)
+    }
+
+    /// Run `query` with `{name:Type}` parameters bound from `params`;
+    /// `data` is the body of an INSERT ... FORMAT
+    async fn execute(
+        &self,
+        query: &str,
+        params: &[(&str, String)],
+        data: Option<String>,
+    ) -> HistoryResult<String> {
+        let mut url_params: Vec<(String, String)> = vec![
+            ("database".to_string(), self.config.database.clone()),
+            ("query".to_string(), query.to_string()),
+        ];
+        url_params.extend(params.iter().map(|(name, value)| (format!("param_{}", name), value.clone())));
+
+        let mut request = self.client.post(&self.config.url).query(&url_params);
+        if let Some(user) = &self.config.user {
+            request = request.header("X-ClickHouse-User", user);
+        }
+        if let Some(password) = &self.config.password {
+            request = request.header("X-ClickHouse-Key", password);
+        }
+        if let Some(data) = data {
+            request = request.body(data);
+        }
+
+        let response = request
+            .send()
+            .await
+            .map_err(|e| HistoryError::AnalyticsError(e.to_string()))?;
+        let status = response.status();
+        let body = response
+            .text()
+            .await
+            .map_err(|e| HistoryError::AnalyticsError(e.to_string()))?;
+        if !status.is_success() {
+            return Err(HistoryError::AnalyticsError(format!("{}: {}", status, body.trim())));
+        }
+        Ok(body)
+    }
+
+    /// Copy stored rows (no-op for none)
+    pub async fn insert_events(&self, events: &[TransactionEvent]) -> HistoryResult<()> {
+        if events.is_empty() {
+            return Ok(());
+        }
+
+        self.execute(
+            "INSERT INTO transaction_events FORMAT JSONEachRow",
+            &[],
+            Some(event_rows(events)),
+        )
+        .await?;
+        Ok(())
+    }
+
+    /// Move a user's rows to their pseudonym (waits for the mutation)
+    pub async fn anonymize_user(&self, user_id: &str, anonymized_user_id: &str) -> HistoryResult<()> {
+        self.execute(
+            r#"
+            ALTER TABLE transaction_events
+            UPDATE user_id = {anonymized_user_id:String}
+            WHERE user_id = {user_id:String}
+            SETTINGS mutations_sync = 1
+            "#,
+            &[
+                ("user_id", user_id.to_string()),
+                ("anonymized_user_id", anonymized_user_id.to_string()),
+            ],
+            None,
+        )
+        .await?;
+        Ok(())
+    }
+
+    /// Empty the copy (a history rebuild, or before a backfill)
+    pub async fn truncate(&self) -> HistoryResult<()> {
+        self.execute("TRUNCATE TABLE transaction_events", &[], None).await?;
+        Ok(())
+    }
+
+    /// Replace the copy with every event Postgres holds; returns how many
+    /// were copied
+    ///
+    /// Months already moved to cold storage aren't in Postgres any more,
+    /// so they drop out of the copy too.
+    pub async fn backfill(&self, repository: &EventRepository) -> HistoryResult<u64> {
+        self.truncate().await?;
+
+        let Some(oldest) = repository.oldest_event_at().await? else {
+            return Ok(0);
+        };
+        // Rows stored from here on reach ClickHouse through the consumer
+        let mut events = repository.stream_events_between(oldest, Utc::now());
+        let mut batch = Vec::with_capacity(BACKFILL_BATCH);
+        let mut copied = 0u64;
+        while let Some(event) = events.next().await {
+            batch.push(event?);
+            if batch.len() == BACKFILL_BATCH {
+                self.insert_events(&batch).await?;
+                copied += batch.len() as u64;
+                batch.clear();
+            }
+        }
+        self.insert_events(&batch).await?;
+        copied += batch.len() as u64;
+
+        Ok(copied)
+    }
+
+    /// `EventRepository::get_wallet_summary` from the copy
+    pub async fn wallet_summary(
+        &self,
+        wallet_id: &str,
+        period: SummaryPeriod,
+        filter: &HistoryFilter,
+    ) -> HistoryResult<Vec<SummaryRow>> {
+        self.summary("wallet_id", wallet_id, period, filter, &WalletSelection::default())
+            .await
+    }
+
+    /// `EventRepository::get_user_summary` from the copy
+    pub async fn user_summary(
+        &self,
+        user_id: &str,
+        period: SummaryPeriod,
+        filter: &HistoryFilter,
+        wallets: &WalletSelection,
+    ) -> HistoryResult<Vec<SummaryRow>> {
+        self.summary("user_id", user_id, period, filter, wallets).await
+    }
+
+    /// Events and amounts per (period, type) where `column` = `id`
+    async fn summary(
+        &self,
+        column: &str,
+        id: &str,
+        period: SummaryPeriod,
+        filter: &HistoryFilter,
+        wallets: &WalletSelection,
+    ) -> HistoryResult<Vec<SummaryRow>> {
+        let mut conditions = vec![format!("{} = {{id:String}}", column)];
+        let mut params = vec![("id", id.to_string())];
+        if let Some(from) = filter.from {
+            conditions.push("created_at >= {from:DateTime64(6, 'UTC')}".to_string());
+            params.push(("from", datetime_param(from)));
+        }
+        if let Some(to) = filter.to {
+            conditions.push("created_at < {to:DateTime64(6, 'UTC')}".to_string());
+            params.push(("to", datetime_param(to)));
+        }
+        if let Some(types) = filter.event_types() {
+            conditions.push("event_type IN {event_types:Array(String)}".to_string());
+            params.push(("event_types", array_param(&types)));
+        }
+        if let Some(wallet_ids) = wallets.wallet_ids() {
+            conditions.push("wallet_id IN {wallet_ids:Array(String)}".to_string());
+            params.push(("wallet_ids", array_param(&wallet_ids)));
+        }
+
+        let query = format!(
+            r#"
+            SELECT
+                toString(toDateTime({}(created_at), 'UTC')) AS period_start,
+                event_type,
+                toString(count()) AS events,
+                toString(sum(amount)) AS amount
+            FROM transaction_events FINAL
+            WHERE {}
+            GROUP BY period_start, event_type
+            ORDER BY period_start DESC, event_type
+            FORMAT JSONEachRow
+            "#,
+            period_start_function(period),
+            conditions.join(" AND ")
+        );
+
+        let body = self.execute(&query, &params, None).await?;
+        parse_summary_rows(&body)
+    }
+}
+
+/// The ClickHouse function truncating a time to `period` (UTC; weeks start
+/// on Monday, as in Postgres' date_trunc)
+pub fn period_start_function(period: SummaryPeriod) -> &'static str {
+    match period {
+        SummaryPeriod::Day => "toStartOfDay",
+        SummaryPeriod::Week => "toMonday",
+        SummaryPeriod::Month => "toStartOfMonth",
+        SummaryPeriod::Year => "toStartOfYear",
+    }
+}
+
+/// Rows for `INSERT ... FORMAT JSONEachRow`, one JSON object per line
+pub fn event_rows(events: &[TransactionEvent]) -> String {
+    let mut rows = String::new();
+    for event in events {
+        let row = serde_json::json!({
+            "id": event.id,
+            "wallet_id": event.wallet_id,
+            "user_id": event.user_id,
+            "event_type": event.event_type,
+            "amount": event.amount.to_string(),
+            "created_at": datetime_param(event.created_at),
+        });
+        rows.push_str(&row.to_string());
+        rows.push('\n');
+    }
+    rows
+}
+
+/// A time as ClickHouse parses it by default (UTC, microseconds)
+fn datetime_param(at: DateTime<Utc>) -> String {
+    at.format("%Y-%m-%d %H:%M:%S%.6f").to_string()
+}
+
+/// An `Array(String)` query parameter: `['a','b']`, quotes escaped
+pub fn array_param(values: &[String]) -> String {
+    let quoted: Vec<String> = values
+        .iter()
+        .map(|v| format!("'{}'", v.replace('\\', "\\\\").replace('\'', "\\'")))
+        .collect();
+    format!("[{}]", quoted.join(","))
+}
+
+#[derive(Deserialize)]
+struct SummaryLine {
+    period_start: String,
+    event_type: String,
+    events: String,
+    amount: String,
+}
+
+/// The JSONEachRow output of a summary query as `SummaryRow`s
+pub fn parse_summary_rows(body: &str) -> HistoryResult<Vec<SummaryRow>> {
+    let invalid = |e: String| HistoryError::AnalyticsError(format!("Unexpected summary row: {}", e));
+
+    body.lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| {
+            let row: SummaryLine = serde_json::from_str(line).map_err(|e| invalid(e.to_string()))?;
+            let period_start = NaiveDateTime::parse_from_str(&row.period_start, "%Y-%m-%d %H:%M:%S")
+                .map_err(|e| invalid(e.to_string()))?
+                .and_utc();
+            let events: i64 = row.events.parse().map_err(|_| invalid(row.events.clone()))?;
+            let amount: Decimal = row.amount.parse().map_err(|_| invalid(row.amount.clone()))?;
+            Ok((period_start, row.event_type, events, amount))
+        })
+        .collect()
+}
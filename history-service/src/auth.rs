@@ -0,0 +1,568 @@
+use crate::errors::{HistoryError, HistoryResult};
+use crate::handlers::AppState;
+use axum::{
+    extract::{FromRequestParts, RawPathParams, Request, State},
+    http::{header, HeaderValue, Method},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use jsonwebtoken::errors::ErrorKind;
+use jsonwebtoken::jwk::{AlgorithmParameters, EllipticCurve, JwkSet};
+use jsonwebtoken::{Algorithm, DecodingKey, Validation};
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::str::FromStr;
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, Instant};
+use tokio::sync::OnceCell;
+
+/// Routes served without a token (probes, scrapers, the API description)
+pub const PUBLIC_PATHS: &[&str] = &["/health", "/metrics", "/openapi.json", "/docs"];
+
+/// Where tokens come from and what they must say
+///
+/// The issuer is an OpenID Connect provider (Keycloak, Auth0...). Its
+/// signing keys are found through its discovery document unless
+/// AUTH_JWKS_URL names them directly.
+#[derive(Debug, Clone)]
+pub struct AuthConfig {
+    /// AUTH_ISSUER - the `iss` every token must carry
+    pub issuer: String,
+    /// AUTH_AUDIENCE - the `aud` tokens must include (unset = not checked)
+    pub audience: Option<String>,
+    /// AUTH_JWKS_URL - the issuer's signing keys (unset = from
+    /// `<issuer>/.well-known/openid-configuration`)
+    pub jwks_url: Option<String>,
+    /// AUTH_LEEWAY_SECS - clock skew allowed on `exp` / `nbf`
+    pub leeway_secs: u64,
+    pub claims: ClaimMapping,
+}
+
+impl AuthConfig {
+    /// None when AUTH=off (local development only); otherwise AUTH_ISSUER
+    /// is required - a typo must not switch auth off
+    pub fn from_env() -> HistoryResult<Option<Self>> {
+        if std::env::var("AUTH").as_deref() == Ok("off") {
+            return Ok(None);
+        }
+        let required = |name: &str| {
+            std::env::var(name).map_err(|_| {
+                HistoryError::InternalError(format!("{} must be set (or AUTH=off)", name))
+            })
+        };
+
+        Ok(Some(Self {
+            issuer: required("AUTH_ISSUER")?,
+            audience: std::env::var("AUTH_AUDIENCE").ok(),
+            jwks_url: std::env::var("AUTH_JWKS_URL").ok(),
+            leeway_secs: std::env::var("AUTH_LEEWAY_SECS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(30),
+            claims: ClaimMapping::from_env(),
+        }))
+    }
+}
+
+/// How a token's claims become a `Principal`
+///
+/// Providers put things in different places, e.g.
+/// - Keycloak: roles in `realm_access.roles`
+/// - Auth0: roles in a namespaced claim (`https://wallet.example.com/roles`)
+///   added by an Action
+///
+/// A claim name is first looked up as it is, then as a dotted path into
+/// nested objects.
+#[derive(Debug, Clone)]
+pub struct ClaimMapping {
+    /// AUTH_USER_ID_CLAIM - the claim holding our user_id (default `sub`)
+    pub user_id_claim: String,
+    /// AUTH_ROLES_CLAIM - where the token lists its roles (default `roles`)
+    pub roles_claim: String,
+    /// AUTH_ADMIN_ROLE - the provider's name for admins (default `admin`)
+    pub admin_role: String,
+    /// AUTH_SUPPORT_ROLE - ...and for support staff (default `support`)
+    pub support_role: String,
+}
+
+impl Default for ClaimMapping {
+    fn default() -> Self {
+        Self {
+            user_id_claim: "sub".to_string(),
+            roles_claim: "roles".to_string(),
+            admin_role: "admin".to_string(),
+            support_role: "support".to_string(),
+        }
+    }
+}
+
+impl ClaimMapping {
+    pub fn from_env() -> Self {
+        let defaults = Self::default();
+        let var = |name: &str, default: String| std::env::var(name).unwrap_or(default);
+
+        Self {
+            user_id_claim: var("AUTH_USER_ID_CLAIM", defaults.user_id_claim),
+            roles_claim: var("AUTH_ROLES_CLAIM", defaults.roles_claim),
+            admin_role: var("AUTH_ADMIN_ROLE", defaults.admin_role),
+            support_role: var("AUTH_SUPPORT_ROLE", defaults.support_role),
+        }
+    }
+
+    /// The user_id the claims name (None if the claim is missing or empty)
+    pub fn user_id(&self, claims: &Claims) -> Option<String> {
+        claim(claims, &self.user_id_claim)
+            .and_then(|value| value.as_str())
+            .filter(|user_id| !user_id.is_empty())
+            .map(str::to_string)
+    }
+
+    /// The highest role the claims list - an array of names, or one
+    /// space-separated string (like `scope`). Other names are ignored.
+    pub fn role(&self, claims: &Claims) -> Role {
+        let names: Vec<&str> = match claim(claims, &self.roles_claim) {
+            Some(serde_json::Value::Array(names)) => {
+                names.iter().filter_map(|name| name.as_str()).collect()
+            }
+            Some(serde_json::Value::String(names)) => names.split_whitespace().collect(),
+            _ => Vec::new(),
+        };
+
+        names
+            .into_iter()
+            .filter_map(|name| {
+                if name == self.admin_role {
+                    Some(Role::Admin)
+                } else if name == self.support_role {
+                    Some(Role::Support)
+                } else {
+                    None
+                }
+            })
+            .max()
+            .unwrap_or(Role::User)
+    }
+}
+
+/// A token's claims
+pub type Claims = serde_json::Map<String, serde_json::Value>;
+
+/// The claim called `name`, or else at the dotted path `name`
+fn claim<'a>(claims: &'a Claims, name: &str) -> Option<&'a serde_json::Value> {
+    if let Some(value) = claims.get(name) {
+        return Some(value);
+    }
+    let mut segments = name.split('.');
+    let first = segments.next().and_then(|segment| claims.get(segment));
+    segments.fold(first, |value, segment| value.and_then(|v| v.get(segment)))
+}
+
+/// What a person may do, highest last (same roles as wallet-service)
+///
+/// - User: their own history
+/// - Support: anyone's history, and the `/admin/*` status pages
+/// - Admin: also pause, seek and replay
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Role {
+    User,
+    Support,
+    Admin,
+}
+
+impl std::fmt::Display for Role {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Role::User => write!(f, "user"),
+            Role::Support => write!(f, "support"),
+            Role::Admin => write!(f, "admin"),
+        }
+    }
+}
+
+/// Who made the request - the token's `sub`, which is the user_id in the
+/// events, and the role from its roles claim
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Principal {
+    pub subject: String,
+    pub role: Role,
+}
+
+impl Principal {
+    pub fn user(subject: impl Into<String>) -> Self {
+        Self {
+            subject: subject.into(),
+            role: Role::User,
+        }
+    }
+
+    pub fn with_role(mut self, role: Role) -> Self {
+        self.role = role;
+        self
+    }
+
+    /// May this caller see the history of `user_id`? (Everything here is a
+    /// read, so support staff may see anyone's)
+    pub fn can_read(&self, user_id: &str) -> bool {
+        self.role >= Role::Support || self.subject == user_id
+    }
+}
+
+/// `can_read`, for handlers that run with auth off too (no principal = allowed)
+pub fn may_read(principal: Option<&Principal>, user_id: &str) -> bool {
+    principal.is_none_or(|p| p.can_read(user_id))
+}
+
+/// What an OpenID provider's discovery document tells us
+#[derive(Debug, Deserialize)]
+struct ProviderMetadata {
+    issuer: String,
+    jwks_uri: String,
+}
+
+/// The role a person needs for this request: support for `GET /admin/*`,
+/// admin for the other `/admin/*` calls (they change what the consumer
+/// does), any user elsewhere
+pub fn required_role(method: &Method, path: &str) -> Role {
+    if !path.starts_with("/admin/") {
+        Role::User
+    } else if method == Method::GET || method == Method::HEAD {
+        Role::Support
+    } else {
+        Role::Admin
+    }
+}
+
+/// One signing key, and the only algorithm it may be used with
+///
+/// Pinning the algorithm per key is what stops a token "signed" with HS256
+/// using an RSA public key as the secret.
+#[derive(Clone)]
+pub struct VerifyingKey {
+    pub key: DecodingKey,
+    pub algorithm: Algorithm,
+}
+
+struct KeyCache {
+    keys: HashMap<String, VerifyingKey>,
+    fetched_at: Option<Instant>,
+}
+
+/// Verifies bearer tokens against the issuer's JWKS
+///
+/// How it works:
+/// 1. The token's `kid` picks the key; the key's algorithm must match the
+///    token's `alg`
+/// 2. Signature, `exp`, `nbf`, `iss` (and `aud` if configured) are checked
+/// 3. An unknown `kid` refetches the JWKS - that's how key rotation shows
+///    up - but at most once per JWKS_REFETCH, so junk tokens can't make us
+///    hammer the issuer
+pub struct Authenticator {
+    config: AuthConfig,
+    client: Option<reqwest::Client>,
+    cache: RwLock<KeyCache>,
+    discovered_jwks_url: OnceCell<String>,
+}
+
+/// Shortest time between two JWKS fetches
+const JWKS_REFETCH: Duration = Duration::from_secs(60);
+
+impl Authenticator {
+    pub fn new(config: AuthConfig) -> HistoryResult<Self> {
+        let client = crate::tls::client_builder()
+            .map_err(|e| HistoryError::InternalError(format!("JWKS client TLS: {}", e)))?
+            .timeout(Duration::from_secs(5))
+            .build()
+            .map_err(|e| HistoryError::InternalError(format!("JWKS client: {}", e)))?;
+
+        Ok(Self {
+            config,
+            client: Some(client),
+            cache: RwLock::new(KeyCache {
+                keys: HashMap::new(),
+                fetched_at: None,
+            }),
+            discovered_jwks_url: OnceCell::new(),
+        })
+    }
+
+    /// A fixed key set that is never fetched (tests, or keys from a file)
+    pub fn with_keys(config: AuthConfig, keys: HashMap<String, VerifyingKey>) -> Self {
+        Self {
+            config,
+            client: None,
+            cache: RwLock::new(KeyCache {
+                keys,
+                fetched_at: Some(Instant::now()),
+            }),
+            discovered_jwks_url: OnceCell::new(),
+        }
+    }
+
+    /// The caller behind `token`, or Unauthorized saying why not
+    pub async fn verify(&self, token: &str) -> HistoryResult<Principal> {
+        let unauthorized = |reason: &str| HistoryError::Unauthorized(reason.to_string());
+
+        let header =
+            jsonwebtoken::decode_header(token).map_err(|_| unauthorized("malformed token"))?;
+        let kid = header.kid.ok_or_else(|| unauthorized("token has no kid"))?;
+
+        let key = match self.key(&kid) {
+            Some(key) => key,
+            None => {
+                self.refresh().await;
+                self.key(&kid).ok_or_else(|| unauthorized("unknown signing key"))?
+            }
+        };
+        if header.alg != key.algorithm {
+            return Err(unauthorized("wrong algorithm for this key"));
+        }
+
+        let mut validation = Validation::new(key.algorithm);
+        validation.leeway = self.config.leeway_secs;
+        validation.validate_nbf = true;
+        validation.set_issuer(&[&self.config.issuer]);
+        validation.set_required_spec_claims(&["exp", "iss", "sub"]);
+        match &self.config.audience {
+            Some(audience) => validation.set_audience(&[audience]),
+            None => validation.validate_aud = false,
+        }
+
+        let claims = jsonwebtoken::decode::<Claims>(token, &key.key, &validation)
+            .map_err(|e| {
+                tracing::debug!(error = %e, "Token rejected");
+                match e.kind() {
+                    ErrorKind::ExpiredSignature => unauthorized("token expired"),
+                    _ => unauthorized("invalid token"),
+                }
+            })?
+            .claims;
+
+        let mapping = &self.config.claims;
+        let user_id = mapping
+            .user_id(&claims)
+            .ok_or_else(|| unauthorized("token names no user"))?;
+        Ok(Principal::user(user_id).with_role(mapping.role(&claims)))
+    }
+
+    fn key(&self, kid: &str) -> Option<VerifyingKey> {
+        let cache = self.cache.read().unwrap_or_else(|e| e.into_inner());
+        cache.keys.get(kid).cloned()
+    }
+
+    /// Refetch the JWKS, unless we did so recently; failures keep the old keys
+    async fn refresh(&self) {
+        let Some(client) = &self.client else {
+            return;
+        };
+        {
+            let mut cache = self.cache.write().unwrap_or_else(|e| e.into_inner());
+            if cache.fetched_at.is_some_and(|at| at.elapsed() < JWKS_REFETCH) {
+                return;
+            }
+            // Claim this refresh so concurrent misses don't all fetch
+            cache.fetched_at = Some(Instant::now());
+        }
+
+        let fetched = match self.jwks_url(client).await {
+            Ok(url) => fetch_jwks(client, url).await,
+            Err(e) => Err(e),
+        };
+        match fetched {
+            Ok(keys) => {
+                tracing::info!(keys = keys.len(), "Loaded JWKS");
+                self.cache.write().unwrap_or_else(|e| e.into_inner()).keys = keys;
+            }
+            Err(e) => tracing::warn!(error = %e, issuer = %self.config.issuer, "JWKS fetch failed"),
+        }
+    }
+
+    /// AUTH_JWKS_URL, or the provider's `jwks_uri` (discovered once)
+    async fn jwks_url(&self, client: &reqwest::Client) -> Result<&str, String> {
+        if let Some(url) = &self.config.jwks_url {
+            return Ok(url);
+        }
+        self.discovered_jwks_url
+            .get_or_try_init(|| discover_jwks_url(client, &self.config.issuer))
+            .await
+            .map(String::as_str)
+    }
+}
+
+/// The provider's JWKS URL, from its OpenID Connect discovery document
+///
+/// The document must be for the issuer we asked about (OIDC Discovery
+/// 4.3) - otherwise we'd trust another issuer's keys.
+pub async fn discover_jwks_url(client: &reqwest::Client, issuer: &str) -> Result<String, String> {
+    let url = format!("{}/.well-known/openid-configuration", issuer.trim_end_matches('/'));
+    let metadata: ProviderMetadata = client
+        .get(&url)
+        .send()
+        .await
+        .and_then(|r| r.error_for_status())
+        .map_err(|e| e.to_string())?
+        .json()
+        .await
+        .map_err(|e| e.to_string())?;
+
+    if metadata.issuer != issuer {
+        return Err(format!("{} describes issuer {}, not {}", url, metadata.issuer, issuer));
+    }
+    tracing::info!(jwks_uri = %metadata.jwks_uri, "Discovered OpenID provider keys");
+    Ok(metadata.jwks_uri)
+}
+
+async fn fetch_jwks(
+    client: &reqwest::Client,
+    url: &str,
+) -> Result<HashMap<String, VerifyingKey>, String> {
+    let jwks: JwkSet = client
+        .get(url)
+        .send()
+        .await
+        .and_then(|r| r.error_for_status())
+        .map_err(|e| e.to_string())?
+        .json()
+        .await
+        .map_err(|e| e.to_string())?;
+
+    Ok(verifying_keys(&jwks))
+}
+
+/// The usable keys of a JWKS, by `kid`
+///
+/// Keys without a `kid`, symmetric keys and keys we can't parse are
+/// skipped. A key without `alg` gets its type's usual one (RS256, ES256
+/// or ES384 by curve, EdDSA).
+pub fn verifying_keys(jwks: &JwkSet) -> HashMap<String, VerifyingKey> {
+    jwks.keys
+        .iter()
+        .filter_map(|jwk| {
+            let kid = jwk.common.key_id.clone()?;
+            let algorithm = match (&jwk.common.key_algorithm, &jwk.algorithm) {
+                (Some(alg), _) => Algorithm::from_str(&alg.to_string()).ok()?,
+                (None, AlgorithmParameters::RSA(_)) => Algorithm::RS256,
+                (None, AlgorithmParameters::EllipticCurve(ec)) => match ec.curve {
+                    EllipticCurve::P256 => Algorithm::ES256,
+                    EllipticCurve::P384 => Algorithm::ES384,
+                    _ => return None,
+                },
+                (None, AlgorithmParameters::OctetKeyPair(_)) => Algorithm::EdDSA,
+                (None, AlgorithmParameters::OctetKey(_)) => return None,
+            };
+            if matches!(algorithm, Algorithm::HS256 | Algorithm::HS384 | Algorithm::HS512) {
+                return None;
+            }
+            let key = DecodingKey::from_jwk(jwk).ok()?;
+            Some((kid, VerifyingKey { key, algorithm }))
+        })
+        .collect()
+}
+
+/// The token from `Authorization: Bearer <token>`
+pub fn bearer_token(headers: &axum::http::HeaderMap) -> Option<&str> {
+    let value = headers.get(header::AUTHORIZATION)?.to_str().ok()?;
+    let (scheme, token) = value.split_once(' ')?;
+    scheme.eq_ignore_ascii_case("bearer").then_some(token.trim())
+}
+
+/// Middleware: every route outside PUBLIC_PATHS needs a valid bearer token
+///
+/// The caller is put in the request extensions as a `Principal`. A role
+/// too low for the route is a 403.
+pub async fn require_auth(
+    State(authenticator): State<Arc<Authenticator>>,
+    mut request: Request,
+    next: Next,
+) -> Response {
+    if PUBLIC_PATHS.contains(&request.uri().path()) {
+        return next.run(request).await;
+    }
+
+    let verified = match bearer_token(request.headers()) {
+        Some(token) => authenticator.verify(token).await,
+        None => Err(HistoryError::Unauthorized("missing bearer token".to_string())),
+    };
+    match verified {
+        Ok(principal) => {
+            let required = required_role(request.method(), request.uri().path());
+            if principal.role < required {
+                return HistoryError::Forbidden(format!("needs the {} role", required))
+                    .into_response();
+            }
+            request.extensions_mut().insert(principal);
+            next.run(request).await
+        }
+        Err(e) => {
+            let mut response = e.into_response();
+            response.headers_mut().insert(
+                header::WWW_AUTHENTICATE,
+                HeaderValue::from_static("Bearer error=\"invalid_token\""),
+            );
+            response
+        }
+    }
+}
+
+/// Middleware: a caller only reads their own history
+///
+/// Looks at the route's path parameters:
+/// - `wallet_id`: the wallet's events must belong to the caller
+/// - `user_id`: must be the caller
+///
+/// Someone else's wallet is a 404, like one without events. A wrong user_id
+/// is a 403. A wallet with no events yet has no owner we know of and just
+/// returns its empty history.
+///
+/// Support staff and admins are not checked, nor is `/admin/*` (roles
+/// decide, see `required_role`); GraphQL checks in its resolvers. Without
+/// a `Principal` (AUTH=off) this does nothing.
+pub async fn enforce_ownership(
+    State(state): State<AppState>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let (mut parts, body) = request.into_parts();
+    let Some(principal) = parts.extensions.get::<Principal>().cloned() else {
+        return next.run(Request::from_parts(parts, body)).await;
+    };
+    if parts.uri.path().starts_with("/admin/") || principal.role >= Role::Support {
+        return next.run(Request::from_parts(parts, body)).await;
+    }
+
+    if let Ok(params) = RawPathParams::from_request_parts(&mut parts, &()).await {
+        for (name, value) in params.iter() {
+            let checked = match name {
+                "user_id" => check_user(&principal, value),
+                "wallet_id" => check_wallet(&state, &principal, value).await,
+                _ => Ok(()),
+            };
+            if let Err(e) = checked {
+                return e.into_response();
+            }
+        }
+    }
+
+    next.run(Request::from_parts(parts, body)).await
+}
+
+/// `user_id` must be the caller, or the caller support staff (Forbidden
+/// otherwise)
+pub fn check_user(principal: &Principal, user_id: &str) -> HistoryResult<()> {
+    if principal.can_read(user_id) {
+        Ok(())
+    } else {
+        Err(HistoryError::Forbidden("you can only access your own user".to_string()))
+    }
+}
+
+/// The wallet's events must be the caller's (NotFound otherwise)
+pub async fn check_wallet(
+    state: &AppState,
+    principal: &Principal,
+    wallet_id: &str,
+) -> HistoryResult<()> {
+    match state.repository.find_wallet_owner(wallet_id).await? {
+        Some(owner) if !principal.can_read(&owner) => Err(HistoryError::NotFound),
+        _ => Ok(()),
+    }
+}
@@ -0,0 +1,222 @@
+use crate::errors::{HistoryError, HistoryResult};
+use chrono::{SecondsFormat, TimeZone, Utc};
+use serde_json::{Map, Value};
+
+/// The wallet event schema this service reads with (shared with wallet-service)
+pub const WALLET_EVENT_SCHEMA: &str = include_str!("../../schemas/wallet-event.avsc");
+
+/// Magic byte that starts every Confluent-framed message
+pub const CONFLUENT_MAGIC: u8 = 0;
+
+/// The subset of Avro the wallet event schema uses
+///
+/// Mirrors wallet-service's encoder: nulls, booleans, int/long (with
+/// timestamp-micros), doubles, strings, records and unions.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Schema {
+    Null,
+    Boolean,
+    Int,
+    Long { timestamp_micros: bool },
+    Double,
+    String,
+    Record(RecordSchema),
+    Union(Vec<Schema>),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct RecordSchema {
+    pub name: String,
+    /// Our `eventType` attribute: the JSON tag this record stands for
+    pub event_type: Option<String>,
+    pub fields: Vec<Field>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Field {
+    pub name: String,
+    pub schema: Schema,
+    pub default: Option<Value>,
+}
+
+impl Schema {
+    /// Parse a schema from its JSON text
+    pub fn parse(text: &str) -> HistoryResult<Self> {
+        let json: Value = serde_json::from_str(text)
+            .map_err(|e| HistoryError::SerializationError(format!("Invalid Avro schema: {}", e)))?;
+        Self::from_json(&json)
+    }
+
+    fn from_json(json: &Value) -> HistoryResult<Self> {
+        match json {
+            Value::String(name) => Self::primitive(name, None),
+            Value::Array(branches) => Ok(Schema::Union(
+                branches.iter().map(Self::from_json).collect::<HistoryResult<_>>()?,
+            )),
+            Value::Object(obj) => match obj.get("type").and_then(Value::as_str) {
+                Some("record") => {
+                    let fields = obj
+                        .get("fields")
+                        .and_then(Value::as_array)
+                        .ok_or_else(|| unsupported("record without fields"))?
+                        .iter()
+                        .map(|field| {
+                            Ok(Field {
+                                name: field
+                                    .get("name")
+                                    .and_then(Value::as_str)
+                                    .ok_or_else(|| unsupported("field without a name"))?
+                                    .to_string(),
+                                schema: Self::from_json(
+                                    field.get("type").ok_or_else(|| unsupported("field without a type"))?,
+                                )?,
+                                default: field.get("default").cloned(),
+                            })
+                        })
+                        .collect::<HistoryResult<_>>()?;
+
+                    Ok(Schema::Record(RecordSchema {
+                        name: obj
+                            .get("name")
+                            .and_then(Value::as_str)
+                            .unwrap_or_default()
+                            .to_string(),
+                        event_type: obj
+                            .get("eventType")
+                            .and_then(Value::as_str)
+                            .map(str::to_string),
+                        fields,
+                    }))
+                }
+                Some(name) => Self::primitive(name, obj.get("logicalType").and_then(Value::as_str)),
+                None => Err(unsupported("schema without a type")),
+            },
+            _ => Err(unsupported("schema must be a string, array or object")),
+        }
+    }
+
+    fn primitive(name: &str, logical_type: Option<&str>) -> HistoryResult<Self> {
+        match name {
+            "null" => Ok(Schema::Null),
+            "boolean" => Ok(Schema::Boolean),
+            "int" => Ok(Schema::Int),
+            "long" => Ok(Schema::Long {
+                timestamp_micros: logical_type == Some("timestamp-micros"),
+            }),
+            "double" => Ok(Schema::Double),
+            "string" => Ok(Schema::String),
+            other => Err(unsupported(&format!("type '{}'", other))),
+        }
+    }
+
+    /// Decode one value written with THIS schema (the writer's)
+    ///
+    /// Produces the JSON shape serde expects for a WalletEvent: records
+    /// become objects tagged with `eventType`, timestamps become RFC 3339.
+    pub fn decode(&self, input: &mut &[u8]) -> HistoryResult<Value> {
+        match self {
+            Schema::Null => Ok(Value::Null),
+            Schema::Boolean => Ok(Value::Bool(take(input, 1)?[0] != 0)),
+            Schema::Int | Schema::Long { timestamp_micros: false } => Ok(Value::from(read_long(input)?)),
+            Schema::Long { timestamp_micros: true } => {
+                let micros = read_long(input)?;
+                let ts = Utc
+                    .timestamp_micros(micros)
+                    .single()
+                    .ok_or_else(|| malformed(&format!("timestamp {} out of range", micros)))?;
+                Ok(Value::String(ts.to_rfc3339_opts(SecondsFormat::AutoSi, true)))
+            }
+            Schema::Double => {
+                let bytes: [u8; 8] = take(input, 8)?.try_into().unwrap_or_default();
+                Ok(Value::from(f64::from_le_bytes(bytes)))
+            }
+            Schema::String => {
+                let len = usize::try_from(read_long(input)?)
+                    .map_err(|_| malformed("negative string length"))?;
+                let bytes = take(input, len)?;
+                String::from_utf8(bytes.to_vec())
+                    .map(Value::String)
+                    .map_err(|_| malformed("string is not UTF-8"))
+            }
+            Schema::Record(record) => {
+                let mut obj = Map::new();
+                if let Some(event_type) = &record.event_type {
+                    obj.insert("eventType".to_string(), Value::String(event_type.clone()));
+                }
+                for field in &record.fields {
+                    obj.insert(field.name.clone(), field.schema.decode(input)?);
+                }
+                Ok(Value::Object(obj))
+            }
+            Schema::Union(branches) => {
+                let index = read_long(input)?;
+                let branch = usize::try_from(index)
+                    .ok()
+                    .and_then(|i| branches.get(i))
+                    .ok_or_else(|| malformed(&format!("union branch {} out of range", index)))?;
+                branch.decode(input)
+            }
+        }
+    }
+
+    /// Schema resolution (the part of it a record-of-primitives needs)
+    ///
+    /// The value was decoded with the writer's schema. Fields the writer
+    /// didn't know about but this (reader) schema declares with a default
+    /// get the default; fields only the writer has are left for serde to ignore.
+    pub fn fill_defaults(&self, value: &mut Value) {
+        let Value::Object(obj) = value else {
+            return;
+        };
+
+        let record = match self {
+            Schema::Record(record) => Some(record),
+            Schema::Union(branches) => branches.iter().find_map(|branch| match branch {
+                Schema::Record(record)
+                    if record.event_type.as_deref()
+                        == obj.get("eventType").and_then(Value::as_str) =>
+                {
+                    Some(record)
+                }
+                _ => None,
+            }),
+            _ => None,
+        };
+
+        for field in record.map(|r| r.fields.as_slice()).unwrap_or_default() {
+            if let (false, Some(default)) = (obj.contains_key(&field.name), &field.default) {
+                obj.insert(field.name.clone(), default.clone());
+            }
+        }
+    }
+}
+
+/// Avro long: base-128 varint, then undo the zig-zag
+fn read_long(input: &mut &[u8]) -> HistoryResult<i64> {
+    let mut z: u64 = 0;
+    for shift in (0..64).step_by(7) {
+        let byte = take(input, 1)?[0];
+        z |= u64::from(byte & 0x7f) << shift;
+        if byte & 0x80 == 0 {
+            return Ok((z >> 1) as i64 ^ -((z & 1) as i64));
+        }
+    }
+    Err(malformed("varint longer than 10 bytes"))
+}
+
+fn take<'a>(input: &mut &'a [u8], n: usize) -> HistoryResult<&'a [u8]> {
+    if input.len() < n {
+        return Err(malformed("unexpected end of message"));
+    }
+    let (head, rest) = input.split_at(n);
+    *input = rest;
+    Ok(head)
+}
+
+fn unsupported(what: &str) -> HistoryError {
+    HistoryError::SerializationError(format!("Unsupported Avro schema: {}", what))
+}
+
+fn malformed(what: &str) -> HistoryError {
+    HistoryError::SerializationError(format!("Malformed Avro message: {}", what))
+}
@@ -0,0 +1,62 @@
+use async_graphql::SimpleObject;
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, ToSchema};
+
+/// Stored event types that add money to their wallet
+pub const CREDIT_TYPES: &[&str] = &[
+    "WALLET_FUNDED",
+    "VOUCHER_REDEEMED",
+    "TRANSFER_IN",
+    "ROUND_UP_IN",
+    "POT_TRANSFER_IN",
+    "ADJUSTMENT_IN",
+];
+
+/// Stored event types that take money out of their wallet
+pub const DEBIT_TYPES: &[&str] = &[
+    "TRANSFER_OUT",
+    "ROUND_UP_OUT",
+    "POT_TRANSFER_OUT",
+    "ADJUSTMENT_OUT",
+];
+
+// Anything else (WALLET_CREATED) doesn't move the balance
+
+/// `?at=` on GET /wallets/:id/balance (default: now)
+#[derive(Debug, Clone, Default, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct BalanceQuery {
+    pub at: Option<DateTime<Utc>>,
+}
+
+/// A wallet's balance at a point in time, rebuilt from its history
+///
+/// Why from events rather than wallet-service?
+/// - wallet-service only knows the balance NOW; audits and disputes ask
+///   "what was it when the customer called on the 3rd?"
+/// - Every balance change is an event here, so the sum of the credits and
+///   debits up to `at` is the balance at `at`
+///
+/// Events are placed by their own timestamp (when wallet-service made the
+/// change), not when we stored them, so consumer lag doesn't shift them.
+///
+/// The sum is only as complete as the history: `open_sequence_gaps` > 0
+/// means events for this wallet are missing and the figure may be off.
+///
+/// Events moved to cold storage are counted through `archived_balances`;
+/// `at` can't be before `archived_through`.
+#[derive(Debug, Clone, Serialize, ToSchema, SimpleObject)]
+pub struct BalanceAsOf {
+    pub wallet_id: String,
+    pub at: DateTime<Utc>,
+    pub balance: Decimal,
+    pub events_counted: i64,
+    /// The newest event included (None: the wallet had no events yet)
+    pub last_event_at: Option<DateTime<Utc>>,
+    pub open_sequence_gaps: usize,
+    /// Events stored before this are in cold storage and were counted from
+    /// their archived sum (None: none archived)
+    pub archived_through: Option<DateTime<Utc>>,
+}
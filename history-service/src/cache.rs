@@ -0,0 +1,182 @@
+use serde::Serialize;
+use std::any::Any;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, Instant};
+use utoipa::ToSchema;
+
+/// What a cached response is ABOUT - the unit of invalidation
+///
+/// A new event for a wallet drops every cached query scoped to that wallet
+/// (and to its owner), no matter what parameters the query used.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum CacheScope {
+    Wallet(String),
+    User(String),
+}
+
+struct CacheEntry {
+    value: Arc<dyn Any + Send + Sync>,
+    expires_at: Instant,
+}
+
+/// In-memory response cache for read endpoints
+///
+/// Why?
+/// - Dashboards poll the same wallet/user queries every few seconds
+/// - Between two events the answer can't change, so re-running the query is waste
+///
+/// Invalidation:
+/// - The consumer calls `invalidate` for every wallet/user it stores an event for
+/// - The TTL is a backstop: with several instances in one consumer group, each
+///   instance only sees the events for ITS partitions, so another instance's
+///   cache can be stale for up to one TTL
+///
+/// A shared store (Redis) would close that gap; it isn't wired in yet.
+pub struct ResponseCache {
+    ttl: Option<Duration>,
+    entries: RwLock<HashMap<CacheScope, HashMap<String, CacheEntry>>>,
+    hits: AtomicU64,
+    misses: AtomicU64,
+}
+
+/// Cache counters exposed on /cache/stats
+#[derive(Debug, Serialize, ToSchema)]
+pub struct CacheStats {
+    pub enabled: bool,
+    pub ttl_secs: u64,
+    pub entries: usize,
+    pub hits: u64,
+    pub misses: u64,
+    pub hit_rate: f64,
+}
+
+impl ResponseCache {
+    /// Create a cache whose entries live for `ttl`
+    pub fn new(ttl: Duration) -> Self {
+        Self {
+            ttl: Some(ttl),
+            entries: RwLock::new(HashMap::new()),
+            hits: AtomicU64::new(0),
+            misses: AtomicU64::new(0),
+        }
+    }
+
+    /// A cache that never stores anything (every lookup is a miss)
+    pub fn disabled() -> Self {
+        Self {
+            ttl: None,
+            entries: RwLock::new(HashMap::new()),
+            hits: AtomicU64::new(0),
+            misses: AtomicU64::new(0),
+        }
+    }
+
+    /// Configure from CACHE_TTL_SECS (unset or 0 = caching off)
+    pub fn from_env() -> Self {
+        match std::env::var("CACHE_TTL_SECS")
+            .ok()
+            .and_then(|v| v.parse::<u64>().ok())
+        {
+            Some(secs) if secs > 0 => Self::new(Duration::from_secs(secs)),
+            _ => Self::disabled(),
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.ttl.is_some()
+    }
+
+    /// Look up a cached response
+    ///
+    /// `query` identifies the endpoint AND its parameters
+    /// (e.g. "history" or "activity?limit=50")
+    pub fn get<T>(&self, scope: &CacheScope, query: &str) -> Option<T>
+    where
+        T: Clone + Send + Sync + 'static,
+    {
+        if !self.is_enabled() {
+            return None;
+        }
+
+        let found = self
+            .entries
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .get(scope)
+            .and_then(|queries| queries.get(query))
+            .filter(|entry| entry.expires_at > Instant::now())
+            .and_then(|entry| entry.value.downcast_ref::<T>().cloned());
+
+        if found.is_some() {
+            self.hits.fetch_add(1, Ordering::Relaxed);
+        } else {
+            self.misses.fetch_add(1, Ordering::Relaxed);
+        }
+
+        found
+    }
+
+    /// Store a response (no-op when caching is off)
+    pub fn insert<T>(&self, scope: CacheScope, query: &str, value: T)
+    where
+        T: Send + Sync + 'static,
+    {
+        let Some(ttl) = self.ttl else {
+            return;
+        };
+
+        let entry = CacheEntry {
+            value: Arc::new(value),
+            expires_at: Instant::now() + ttl,
+        };
+
+        self.entries
+            .write()
+            .unwrap_or_else(|e| e.into_inner())
+            .entry(scope)
+            .or_default()
+            .insert(query.to_string(), entry);
+    }
+
+    /// Drop every cached query for a wallet or user
+    pub fn invalidate(&self, scope: &CacheScope) {
+        if !self.is_enabled() {
+            return;
+        }
+
+        self.entries
+            .write()
+            .unwrap_or_else(|e| e.into_inner())
+            .remove(scope);
+    }
+
+    /// Current counters
+    pub fn stats(&self) -> CacheStats {
+        let hits = self.hits.load(Ordering::Relaxed);
+        let misses = self.misses.load(Ordering::Relaxed);
+        let lookups = hits + misses;
+
+        let entries = self
+            .entries
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .values()
+            .map(HashMap::len)
+            .sum();
+
+        CacheStats {
+            enabled: self.is_enabled(),
+            ttl_secs: self.ttl.map(|ttl| ttl.as_secs()).unwrap_or(0),
+            entries,
+            hits,
+            misses,
+            hit_rate: if lookups == 0 {
+                0.0
+            } else {
+                hits as f64 / lookups as f64
+            },
+        }
+    }
+}
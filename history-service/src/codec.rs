@@ -0,0 +1,115 @@
+use crate::avro::{Schema, CONFLUENT_MAGIC, WALLET_EVENT_SCHEMA};
+use crate::errors::{HistoryError, HistoryResult};
+use crate::models::EventEnvelope;
+use crate::protobuf::{ProtoSchema, WALLET_EVENT_PROTO};
+use crate::schema_registry::SchemaRegistryClient;
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+
+/// Turns a Kafka payload into a WalletEvent (with its tracing IDs), whatever
+/// codec wrote it
+///
+/// Detection is per message, by the first byte:
+/// - `{`: JSON (the default, and what local dev uses)
+/// - 0x00: Confluent Avro - the next 4 bytes are the writer's schema ID
+/// - anything else: Protobuf (a field tag - never 0x00, and `{` would be a
+///   deprecated group tag that proto/wallet_event.proto can't produce)
+///
+/// So wallet-service can switch EVENT_CODEC without a coordinated deploy;
+/// both formats can even sit on the topic at once.
+///
+/// Avro schema evolution:
+/// - The writer schema is fetched from the registry by ID (cached forever -
+///   registered schemas are immutable)
+/// - Fields only the writer has are skipped; fields only our reader schema
+///   has are filled from their defaults (BACKWARD compatibility)
+pub struct EventDecoder {
+    registry: Option<SchemaRegistryClient>,
+    reader: Schema,
+    proto: ProtoSchema,
+    writers: RwLock<HashMap<u32, Arc<Schema>>>,
+}
+
+impl EventDecoder {
+    /// JSON and Protobuf - Avro messages fail to decode (and go to the DLQ)
+    pub fn without_registry() -> HistoryResult<Self> {
+        Ok(Self {
+            registry: None,
+            reader: Schema::parse(WALLET_EVENT_SCHEMA)?,
+            proto: ProtoSchema::parse(WALLET_EVENT_PROTO)?,
+            writers: RwLock::new(HashMap::new()),
+        })
+    }
+
+    /// Configure from SCHEMA_REGISTRY_URL (unset = no Avro)
+    pub fn from_env() -> HistoryResult<Self> {
+        let mut decoder = Self::without_registry()?;
+        if let Ok(url) = std::env::var("SCHEMA_REGISTRY_URL") {
+            decoder.registry = Some(SchemaRegistryClient::new(&url)?);
+        }
+        Ok(decoder)
+    }
+
+    /// Pre-load a writer schema (skips the registry lookup for that ID)
+    pub fn register_writer_schema(&self, id: u32, schema: Schema) {
+        self.writers
+            .write()
+            .unwrap_or_else(|e| e.into_inner())
+            .insert(id, Arc::new(schema));
+    }
+
+    pub async fn decode(&self, payload: &[u8]) -> HistoryResult<EventEnvelope> {
+        match payload.first() {
+            Some(&CONFLUENT_MAGIC) => self.decode_avro(payload).await,
+            Some(b'{') | None => serde_json::from_slice(payload)
+                .map_err(|e| HistoryError::SerializationError(e.to_string())),
+            Some(_) => serde_json::from_value(self.proto.decode(payload)?)
+                .map_err(|e| HistoryError::SerializationError(e.to_string())),
+        }
+    }
+
+    async fn decode_avro(&self, payload: &[u8]) -> HistoryResult<EventEnvelope> {
+        let (id, mut body) = match payload {
+            [_, a, b, c, d, body @ ..] => (u32::from_be_bytes([*a, *b, *c, *d]), body),
+            _ => {
+                return Err(HistoryError::SerializationError(
+                    "Avro message shorter than its header".to_string(),
+                ))
+            }
+        };
+
+        let writer = self.writer_schema(id).await?;
+        let mut value = writer.decode(&mut body)?;
+        self.reader.fill_defaults(&mut value);
+
+        serde_json::from_value(value).map_err(|e| HistoryError::SerializationError(e.to_string()))
+    }
+
+    async fn writer_schema(&self, id: u32) -> HistoryResult<Arc<Schema>> {
+        let cached = self
+            .writers
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .get(&id)
+            .cloned();
+        if let Some(schema) = cached {
+            return Ok(schema);
+        }
+
+        let registry = self.registry.as_ref().ok_or_else(|| {
+            HistoryError::SerializationError(format!(
+                "Avro message (schema id {}) but SCHEMA_REGISTRY_URL is not set",
+                id
+            ))
+        })?;
+
+        let schema = Arc::new(Schema::parse(&registry.schema_by_id(id).await?)?);
+        tracing::info!(schema_id = id, "Loaded writer schema from registry");
+        self.writers
+            .write()
+            .unwrap_or_else(|e| e.into_inner())
+            .insert(id, schema.clone());
+
+        Ok(schema)
+    }
+}
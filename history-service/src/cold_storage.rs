@@ -0,0 +1,322 @@
+use crate::errors::{HistoryError, HistoryResult};
+use crate::export::{ExportEncoder, ExportFormat, CHUNK_BYTES};
+use crate::repository::EventRepository;
+use crate::shutdown::Shutdown;
+use chrono::{DateTime, Datelike, Months, TimeZone, Utc};
+use futures_util::StreamExt;
+use object_store::aws::AmazonS3Builder;
+use object_store::local::LocalFileSystem;
+use object_store::path::Path;
+use object_store::{ObjectStore, WriteMultipart};
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use std::sync::Arc;
+use std::time::Duration;
+use utoipa::ToSchema;
+
+/// Where old events go, how old they must be, and in what format
+///
+/// Why?
+/// - transaction_events only grows; years-old events are almost never
+///   read, yet every index and vacuum pays for them
+/// - A month of events becomes one file in object storage, verified before
+///   it's pruned from Postgres; `archived_balances` keeps the sums so
+///   balances still add up
+#[derive(Debug, Clone)]
+pub struct ColdStorageConfig {
+    /// COLD_STORAGE_URL - `s3://bucket/prefix` (credentials from the usual
+    /// AWS_* variables) or `file:///dir`
+    pub url: String,
+    /// COLD_STORAGE_AFTER_MONTHS - whole months older than this are
+    /// archived (default 12)
+    pub after_months: u32,
+    /// COLD_STORAGE_FORMAT - parquet (default) or ndjson
+    pub format: ExportFormat,
+    /// COLD_STORAGE_INTERVAL_SECS - time between passes (default a day)
+    pub interval: Duration,
+}
+
+impl ColdStorageConfig {
+    /// None when COLD_STORAGE_URL is unset: nothing is archived
+    pub fn from_env() -> Result<Option<Self>, String> {
+        fn env_or<T: std::str::FromStr>(name: &str, default: T) -> T {
+            std::env::var(name)
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(default)
+        }
+
+        let Some(url) = std::env::var("COLD_STORAGE_URL").ok().filter(|v| !v.is_empty()) else {
+            return Ok(None);
+        };
+        let format = match std::env::var("COLD_STORAGE_FORMAT").ok().as_deref() {
+            None | Some("") | Some("parquet") => ExportFormat::Parquet,
+            Some("ndjson") => ExportFormat::Ndjson,
+            Some(other) => {
+                return Err(format!(
+                    "COLD_STORAGE_FORMAT must be parquet or ndjson, got {}",
+                    other
+                ))
+            }
+        };
+
+        Ok(Some(Self {
+            url,
+            after_months: env_or("COLD_STORAGE_AFTER_MONTHS", 12u32).max(1),
+            format,
+            interval: Duration::from_secs(env_or("COLD_STORAGE_INTERVAL_SECS", 24 * 60 * 60).max(60)),
+        }))
+    }
+
+    /// Events stored before this are old enough: the start of the month
+    /// `after_months` before `now`'s
+    pub fn cutoff(&self, now: DateTime<Utc>) -> DateTime<Utc> {
+        month_start(now) - Months::new(self.after_months)
+    }
+}
+
+/// Midnight UTC on the first of `at`'s month
+pub fn month_start(at: DateTime<Utc>) -> DateTime<Utc> {
+    Utc.with_ymd_and_hms(at.year(), at.month(), 1, 0, 0, 0).unwrap()
+}
+
+/// The store and key prefix for COLD_STORAGE_URL
+pub fn open_store(url: &str) -> HistoryResult<(Arc<dyn ObjectStore>, Path)> {
+    if let Some(rest) = url.strip_prefix("s3://") {
+        let (bucket, prefix) = rest.split_once('/').unwrap_or((rest, ""));
+        let store = AmazonS3Builder::from_env()
+            .with_bucket_name(bucket)
+            .build()
+            .map_err(storage_error)?;
+        Ok((Arc::new(store), Path::from(prefix)))
+    } else if let Some(dir) = url.strip_prefix("file://") {
+        std::fs::create_dir_all(dir)
+            .map_err(|e| HistoryError::InternalError(format!("Cold storage {}: {}", dir, e)))?;
+        let store = LocalFileSystem::new_with_prefix(dir).map_err(storage_error)?;
+        Ok((Arc::new(store), Path::default()))
+    } else {
+        Err(HistoryError::InternalError(format!(
+            "COLD_STORAGE_URL must start with s3:// or file://, got {}",
+            url
+        )))
+    }
+}
+
+/// One month of events in cold storage (GET /admin/event-archives)
+#[derive(Debug, Clone, Serialize, sqlx::FromRow, ToSchema)]
+pub struct EventArchive {
+    /// "2024-01"
+    pub period: String,
+    /// Events stored in [range_start, range_end)
+    pub range_start: DateTime<Utc>,
+    pub range_end: DateTime<Utc>,
+    /// Key in the bucket, under the configured prefix
+    pub object_key: String,
+    /// parquet or ndjson
+    pub format: String,
+    pub event_count: i64,
+    pub byte_size: i64,
+    /// Hex SHA-256 of the file - check it before restoring
+    pub sha256: String,
+    pub archived_at: DateTime<Utc>,
+}
+
+/// Moves months of old events to object storage, a month at a time
+///
+/// Per month:
+/// 1. Stream its events (oldest first) into one file, hashing as it's
+///    uploaded
+/// 2. Read the file back - size and hash must match what was written
+/// 3. Prune: delete the month's events, add their sums to
+///    `archived_balances` and record the archive, in one transaction that
+///    rolls back unless exactly the exported events are deleted
+///
+/// A month that fails stops the pass; the next pass retries it (the
+/// object is simply overwritten).
+pub struct EventArchiver {
+    repository: EventRepository,
+    store: Arc<dyn ObjectStore>,
+    prefix: Path,
+    config: ColdStorageConfig,
+}
+
+impl EventArchiver {
+    pub fn new(
+        repository: EventRepository,
+        store: Arc<dyn ObjectStore>,
+        prefix: Path,
+        config: ColdStorageConfig,
+    ) -> Self {
+        Self {
+            repository,
+            store,
+            prefix,
+            config,
+        }
+    }
+
+    /// Archive every whole month older than the cutoff; returns what was
+    /// archived
+    pub async fn run_once(&self, now: DateTime<Utc>) -> HistoryResult<Vec<EventArchive>> {
+        let cutoff = self.config.cutoff(now);
+        let mut archived = Vec::new();
+
+        let Some(oldest) = self.repository.oldest_event_at().await? else {
+            return Ok(archived);
+        };
+        let mut month = month_start(oldest);
+        while month < cutoff {
+            let next = month + Months::new(1);
+            if let Some(archive) = self.archive_month(month, next).await? {
+                archived.push(archive);
+            }
+            month = next;
+        }
+
+        Ok(archived)
+    }
+
+    /// Export, verify and prune the events stored in [from, to) (None: there
+    /// were none)
+    async fn archive_month(
+        &self,
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
+    ) -> HistoryResult<Option<EventArchive>> {
+        let event_count = self.repository.count_events_between(from, to).await?;
+        if event_count == 0 {
+            return Ok(None);
+        }
+
+        let period = from.format("%Y-%m").to_string();
+        let key = self
+            .prefix
+            .child("transaction_events")
+            .child(from.format("%Y").to_string())
+            .child(format!("events-{}.{}", period, self.config.format.extension()));
+
+        let upload = self.store.put_multipart(&key).await.map_err(storage_error)?;
+        let mut writer = WriteMultipart::new(upload);
+        let written = match self.write_events(&mut writer, from, to).await {
+            Ok(written) => written,
+            Err(e) => {
+                let _ = writer.abort().await;
+                return Err(e);
+            }
+        };
+        writer.finish().await.map_err(storage_error)?;
+        let (exported, byte_size, sha256) = written;
+
+        if exported != event_count {
+            // Rows were deleted meanwhile (a history rebuild?) - the
+            // next pass exports the month again
+            return Err(HistoryError::InternalError(format!(
+                "{} changed during export ({} events counted, {} written)",
+                period, event_count, exported
+            )));
+        }
+        self.verify(&key, byte_size, &sha256).await?;
+
+        let archive = EventArchive {
+            period,
+            range_start: from,
+            range_end: to,
+            object_key: key.to_string(),
+            format: self.config.format.extension().to_string(),
+            event_count,
+            byte_size: byte_size as i64,
+            sha256,
+            archived_at: Utc::now(),
+        };
+        self.repository.prune_archived_events(&archive).await?;
+        tracing::info!(
+            period = %archive.period,
+            events = archive.event_count,
+            bytes = archive.byte_size,
+            key = %archive.object_key,
+            "Archived events to cold storage"
+        );
+
+        Ok(Some(archive))
+    }
+
+    /// Encode [from, to) into `writer`: (events, bytes, hex SHA-256)
+    async fn write_events(
+        &self,
+        writer: &mut WriteMultipart,
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
+    ) -> HistoryResult<(i64, u64, String)> {
+        let mut encoder = ExportEncoder::new(self.config.format)?;
+        let mut hasher = Sha256::new();
+        let mut events = 0i64;
+        let mut bytes = 0u64;
+        let mut write = |chunk: Vec<u8>| {
+            hasher.update(&chunk);
+            bytes += chunk.len() as u64;
+            writer.write(&chunk);
+        };
+
+        let mut rows = self.repository.stream_events_between(from, to);
+        while let Some(event) = rows.next().await {
+            encoder.push(event?)?;
+            events += 1;
+            if let Some(chunk) = encoder.take_chunk(CHUNK_BYTES) {
+                write(chunk);
+            }
+        }
+        write(encoder.finish()?);
+
+        Ok((events, bytes, format!("{:x}", hasher.finalize())))
+    }
+
+    /// Read the object back and check it's exactly what was written
+    async fn verify(&self, key: &Path, byte_size: u64, sha256: &str) -> HistoryResult<()> {
+        let mut stored = self
+            .store
+            .get(key)
+            .await
+            .map_err(storage_error)?
+            .into_stream();
+        let mut hasher = Sha256::new();
+        let mut stored_size = 0u64;
+        while let Some(chunk) = stored.next().await {
+            let chunk = chunk.map_err(storage_error)?;
+            hasher.update(&chunk);
+            stored_size += chunk.len() as u64;
+        }
+
+        let stored_sha256 = format!("{:x}", hasher.finalize());
+        if stored_size != byte_size || stored_sha256 != sha256 {
+            return Err(HistoryError::InternalError(format!(
+                "Cold storage object {} doesn't match the export ({} bytes, sha256 {}; expected {} bytes, sha256 {})",
+                key, stored_size, stored_sha256, byte_size, sha256
+            )));
+        }
+        Ok(())
+    }
+
+    /// Run a pass every `interval` in the background, until shutdown
+    pub fn spawn(self, shutdown: Shutdown) {
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(self.config.interval);
+
+            loop {
+                tokio::select! {
+                    _ = ticker.tick() => {}
+                    () = shutdown.wait() => return,
+                }
+
+                match self.run_once(Utc::now()).await {
+                    Ok(archived) if archived.is_empty() => {}
+                    Ok(archived) => tracing::info!(months = archived.len(), "Cold storage pass complete"),
+                    Err(e) => tracing::error!(error = %e, "Failed to archive events to cold storage"),
+                }
+            }
+        });
+    }
+}
+
+fn storage_error(e: object_store::Error) -> HistoryError {
+    HistoryError::InternalError(format!("Cold storage: {}", e))
+}
@@ -1,11 +1,65 @@
-use crate::errors::HistoryResult;
-use crate::models::WalletEvent;
+use crate::cache::{CacheScope, ResponseCache};
+use crate::codec::EventDecoder;
+use crate::control::{ConsumerControl, PartitionSeekResult, SeekCommand, SeekScope, SeekTarget};
+use crate::dlq::{DeadLetter, DeadLetterProducer};
+use crate::errors::{HistoryError, HistoryResult};
+use crate::kafka_security::client_config;
+use crate::metrics::{ConsumerLag, PartitionLag};
+use crate::models::{EventEnvelope, TransactionEvent, WalletEvent};
 use crate::repository::EventRepository;
-use rdkafka::config::ClientConfig;
-use rdkafka::consumer::{Consumer, StreamConsumer};
-use rdkafka::message::Message;
+use crate::retry::{RetryInfo, RetryProducer};
+use crate::sequence::SequenceOutcome;
+use crate::shutdown::Shutdown;
+use crate::signing::{self, EventVerifier};
+use chrono::Utc;
+use rdkafka::consumer::{CommitMode, Consumer, StreamConsumer};
+use rdkafka::error::{KafkaError, RDKafkaErrorCode};
+use rdkafka::message::{BorrowedMessage, Message, OwnedMessage};
+use rdkafka::{Offset, TopicPartitionList};
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+use tokio::sync::mpsc::{self, error::TrySendError};
+use tokio::task::JoinHandle;
 use tokio::time::{sleep, Duration};
 
+/// Where failed messages go: retry tiers first, then the dead-letter topic
+pub struct FailureRouting {
+    pub retries: RetryProducer,
+    pub dead_letters: DeadLetterProducer,
+}
+
+/// Messages a partition worker may have queued before its partition is paused
+const PARTITION_QUEUE_DEPTH: usize = 64;
+
+/// Most messages a worker stores in one INSERT (CONSUMER_BATCH_SIZE can
+/// only lower it - a batch is what's queued, at most PARTITION_QUEUE_DEPTH)
+pub const MAX_BATCH_SIZE: usize = PARTITION_QUEUE_DEPTH;
+
+/// Longest wait between attempts while the database is unreachable
+const MAX_DATABASE_BACKOFF: Duration = Duration::from_secs(30);
+
+/// How often each consumer samples its lag (committed offset vs high watermark)
+const LAG_SAMPLE_INTERVAL: Duration = Duration::from_secs(15);
+
+/// How long a worker may keep working on its message after shutdown starts
+/// (within Kubernetes' default 30s grace period)
+const SHUTDOWN_DRAIN_TIMEOUT: Duration = Duration::from_secs(20);
+
+/// What a rebuild replayed
+#[derive(Debug, Clone, Serialize)]
+pub struct RebuildReport {
+    pub partitions: usize,
+    pub messages: u64,
+}
+
+/// A partition's worker task and its queue
+struct PartitionWorker {
+    queue: mpsc::Sender<OwnedMessage>,
+    task: JoinHandle<()>,
+}
+
 /// Kafka consumer for wallet events
 /// 
 /// Key concepts:
@@ -15,6 +69,22 @@ use tokio::time::{sleep, Duration};
 pub struct EventConsumer {
     consumer: StreamConsumer,
     repository: EventRepository,
+    cache: Arc<ResponseCache>,
+    failures: Arc<FailureRouting>,
+    decoder: Arc<EventDecoder>,
+    control: Arc<ConsumerControl>,
+    shutdown: Shutdown,
+    /// The topic we read (the main topic or a retry tier's)
+    topic: String,
+    lag: Arc<ConsumerLag>,
+    /// Partitions paused because their worker's queue is full
+    throttled: Mutex<HashSet<i32>>,
+    /// Which retry tier this consumer reads (None = the main topic)
+    tier: Option<usize>,
+    /// Checks event signatures (None = accept everything)
+    verifier: Option<Arc<EventVerifier>>,
+    /// Most queued messages a worker stores together (1 = one at a time)
+    batch_size: usize,
 }
 
 impl EventConsumer {
@@ -24,122 +94,979 @@ impl EventConsumer {
     /// - group.id: Consumer group name (for parallel processing)
     /// - auto.offset.reset: Where to start if no offset exists
     /// - enable.auto.commit: Automatically save progress
+    /// 
+    /// `tier`: None consumes `topic` (the main topic), Some(i) consumes
+    /// retry tier i instead. Tier consumers sleep until each message is due,
+    /// so their poll interval is stretched past the tier delay.
     pub fn new(
         brokers: &str,
         group_id: &str,
         topic: &str,
         repository: EventRepository,
+        cache: Arc<ResponseCache>,
+        failures: Arc<FailureRouting>,
+        tier: Option<usize>,
     ) -> HistoryResult<Self> {
-        let consumer: StreamConsumer = ClientConfig::new()
-            .set("bootstrap.servers", brokers)
-            .set("group.id", group_id)
-            .set("auto.offset.reset", "earliest") // Start from beginning if no offset
-            .set("enable.auto.commit", "true") // Auto-commit offsets
-            .set("auto.commit.interval.ms", "5000") // Commit every 5 seconds
-            .set("session.timeout.ms", "30000")
-            .set("enable.partition.eof", "false")
-            .create()
-            .map_err(|e| crate::errors::HistoryError::KafkaError(format!("Failed to create consumer: {}", e)))?;
+        let (topic, max_poll_interval) = match tier {
+            Some(i) => {
+                let retry_tier = failures.retries.tiers().get(i).ok_or_else(|| {
+                    HistoryError::InternalError(format!("No retry tier {}", i))
+                })?;
+                (
+                    retry_tier.topic.clone(),
+                    retry_tier.delay + Duration::from_secs(300),
+                )
+            }
+            None => (topic.to_string(), Duration::from_secs(300)),
+        };
+
+        let consumer = create_consumer(brokers, group_id, max_poll_interval, false)?;
 
         consumer
-            .subscribe(&[topic])
-            .map_err(|e| crate::errors::HistoryError::KafkaError(format!("Failed to subscribe: {}", e)))?;
+            .subscribe(&[&topic])
+            .map_err(|e| HistoryError::KafkaError(format!("Failed to subscribe: {}", e)))?;
 
         Ok(Self {
             consumer,
             repository,
+            cache,
+            failures,
+            decoder: Arc::new(EventDecoder::without_registry()?),
+            control: Arc::new(ConsumerControl::new()),
+            shutdown: Shutdown::new(),
+            lag: Arc::new(ConsumerLag::new()),
+            topic: topic.to_string(),
+            throttled: Mutex::new(HashSet::new()),
+            tier,
+            verifier: None,
+            batch_size: 1,
+        })
+    }
+
+    /// A consumer for `rebuild`: same group, but not subscribed - it assigns
+    /// itself every partition of the main topic instead
+    pub fn for_rebuild(
+        brokers: &str,
+        group_id: &str,
+        topic: &str,
+        repository: EventRepository,
+        cache: Arc<ResponseCache>,
+        failures: Arc<FailureRouting>,
+    ) -> HistoryResult<Self> {
+        Ok(Self {
+            consumer: create_consumer(brokers, group_id, Duration::from_secs(300), true)?,
+            repository,
+            cache,
+            failures,
+            decoder: Arc::new(EventDecoder::without_registry()?),
+            control: Arc::new(ConsumerControl::new()),
+            shutdown: Shutdown::new(),
+            lag: Arc::new(ConsumerLag::new()),
+            topic: topic.to_string(),
+            throttled: Mutex::new(HashSet::new()),
+            tier: None,
+            verifier: None,
+            batch_size: 1,
         })
     }
 
-    /// Start consuming events - this runs forever
+    /// Store up to `batch_size` queued messages with one INSERT (default 1;
+    /// capped at MAX_BATCH_SIZE). Retry tiers always go one at a time.
+    pub fn with_batch_size(mut self, batch_size: usize) -> Self {
+        self.batch_size = batch_size.clamp(1, MAX_BATCH_SIZE);
+        self
+    }
+
+    /// Decode payloads with `decoder` (default: no Avro registry)
+    pub fn with_decoder(mut self, decoder: Arc<EventDecoder>) -> Self {
+        self.decoder = decoder;
+        self
+    }
+
+    /// Follow `control`'s pause/resume switch (default: always running)
+    pub fn with_control(mut self, control: Arc<ConsumerControl>) -> Self {
+        self.control = control;
+        self
+    }
+
+    /// Report lag into `lag` (default: a private one nobody reads)
+    pub fn with_lag(mut self, lag: Arc<ConsumerLag>) -> Self {
+        self.lag = lag;
+        self
+    }
+
+    /// Stop (see `start`) when `shutdown` triggers (default: never)
+    pub fn with_shutdown(mut self, shutdown: Shutdown) -> Self {
+        self.shutdown = shutdown;
+        self
+    }
+
+    /// Reject messages `verifier` doesn't accept (default/None: no checks)
+    pub fn with_verifier(mut self, verifier: Option<Arc<EventVerifier>>) -> Self {
+        self.verifier = verifier;
+        self
+    }
+
+    /// Start consuming events - this runs until shutdown (paused or not)
     /// 
     /// Flow:
     /// 1. Poll Kafka for new messages
-    /// 2. Deserialize JSON to WalletEvent
-    /// 3. Store in database (with idempotency check)
-    /// 4. Auto-commit happens in background
+    /// 2. Hand each to its partition's worker task (see below)
+    /// 3. The worker decodes it (JSON, Avro or Protobuf) to a WalletEvent
+    /// 4. Stores it in the database (with idempotency check)
+    /// 5. Stores the offset - auto-commit picks it up in the background
     /// 
-    /// Error handling:
-    /// - Deserialization errors: Log and skip (don't crash)
-    /// - Database errors: Log and retry (transient failures)
+    /// Parallelism:
+    /// - One worker per partition, so partitions are processed concurrently
+    ///   while each partition (and so each wallet) stays strictly in order
+    /// - A worker that falls behind stores everything queued for its
+    ///   partition in one INSERT (see handle_batch) instead of a round trip
+    ///   per event
+    /// - Offsets are only stored after processing, so a crash never commits
+    ///   past a message a worker hadn't finished
+    /// - A worker whose queue is full gets its partition paused (and rewound
+    ///   to the message that didn't fit) until it catches up, so one slow
+    ///   partition never holds up the others
+    /// 
+    /// Error handling (see handle_message):
+    /// - Deserialization errors: Straight to the dead-letter topic
+    /// - Database errors: Retry topics with growing delays, then dead-letter
     /// - Fatal errors: Return and let service restart
+    /// 
+    /// Shutdown (see `drain`): stop polling, let each worker finish the
+    /// message in hand, commit what's done, leave the group
     pub async fn start(self) -> HistoryResult<()> {
         tracing::info!("Starting Kafka consumer...");
 
+        let this = Arc::new(self);
+        this.clone().spawn_lag_sampler();
+        let mut workers: HashMap<i32, PartitionWorker> = HashMap::new();
+        let mut control = this.control.subscribe();
+        let mut paused = *control.borrow_and_update();
+        // Admin seeks only ever target the main topic
+        let mut seeks = match this.tier {
+            None => this.control.take_seek_requests(),
+            Some(_) => None,
+        };
+        if paused {
+            this.pause_assignment();
+        }
+
         loop {
+            tokio::select! {
+                () = this.shutdown.wait() => break,
+                Ok(()) = control.changed() => {
+                    paused = *control.borrow_and_update();
+                    if paused {
+                        this.pause_assignment();
+                    } else {
+                        this.resume_assignment();
+                    }
+                }
+                Some(command) = next_seek(&mut seeks) => {
+                    let SeekCommand { scope, reply } = command;
+                    let _ = reply.send(this.apply_seek(scope));
+                }
+                received = this.consumer.recv() => match received {
+                    Ok(message) if paused => {
+                        // Only partitions assigned after the pause get here
+                        // (a rebalance) - rewind so nothing is skipped on resume
+                        this.hold(&message);
+                    }
+                    Ok(message) => {
+                        let partition = message.partition();
+                        let worker = workers
+                            .entry(partition)
+                            .or_insert_with(|| this.clone().spawn_worker(partition));
+
+                        match worker.queue.try_send(message.detach()) {
+                            Ok(()) => {}
+                            Err(TrySendError::Full(_)) => this.throttle(&message),
+                            Err(TrySendError::Closed(owned)) => {
+                                // The worker died (a panic) - start a fresh one
+                                let worker = this.clone().spawn_worker(partition);
+                                let _ = worker.queue.try_send(owned);
+                                workers.insert(partition, worker);
+                            }
+                        }
+                    }
+                    Err(e) => {
+                        tracing::error!(error = %e, "Kafka error");
+                        // Sleep briefly before retrying
+                        sleep(Duration::from_secs(1)).await;
+                    }
+                },
+            }
+        }
+
+        this.drain(workers).await;
+        Ok(())
+    }
+
+    /// Wind down after the shutdown signal
+    ///
+    /// - Workers finish the message they're on (up to SHUTDOWN_DRAIN_TIMEOUT)
+    ///   and skip whatever is still queued - it's not committed, so the
+    ///   partition's next owner reads it again
+    /// - The offsets stored so far are committed synchronously, instead of
+    ///   waiting for an auto-commit that would never come
+    /// - Unsubscribing leaves the group now, so the rebalance happens at once
+    ///   rather than after the session timeout
+    async fn drain(self: Arc<Self>, workers: HashMap<i32, PartitionWorker>) {
+        tracing::info!(topic = %self.topic, workers = workers.len(), "Draining Kafka consumer");
+
+        for (partition, worker) in workers {
+            drop(worker.queue);
+            if let Err(e) = worker.task.await {
+                tracing::error!(error = %e, partition = partition, "Partition worker failed");
+            }
+        }
+
+        let consumer = self.clone();
+        let committed = tokio::task::spawn_blocking(move || {
+            consumer.consumer.commit_consumer_state(CommitMode::Sync)
+        })
+        .await;
+        match committed {
+            Ok(Ok(())) => tracing::info!(topic = %self.topic, "Committed offsets"),
+            // Nothing processed since the last commit
+            Ok(Err(KafkaError::ConsumerCommit(RDKafkaErrorCode::NoOffset))) => {
+                tracing::debug!(topic = %self.topic, "No offsets to commit")
+            }
+            Ok(Err(e)) => tracing::error!(error = %e, topic = %self.topic, "Failed to commit offsets"),
+            Err(e) => tracing::error!(error = %e, "Commit task failed"),
+        }
+
+        self.consumer.unsubscribe();
+        tracing::info!(topic = %self.topic, "Kafka consumer stopped");
+    }
+
+    /// Sample committed offsets and watermarks for our assignment, until shutdown
+    ///
+    /// Both calls block on the brokers, so they run on the blocking pool.
+    fn spawn_lag_sampler(self: Arc<Self>) {
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(LAG_SAMPLE_INTERVAL);
+            loop {
+                tokio::select! {
+                    _ = ticker.tick() => {}
+                    () = self.shutdown.wait() => return,
+                }
+                let consumer = self.clone();
+                match tokio::task::spawn_blocking(move || consumer.sample_lag()).await {
+                    Ok(Ok(sample)) => self.lag.record(&self.topic, sample),
+                    Ok(Err(e)) => tracing::warn!(error = %e, topic = %self.topic, "Failed to sample consumer lag"),
+                    Err(e) => tracing::error!(error = %e, "Lag sampling task failed"),
+                }
+            }
+        });
+    }
+
+    fn sample_lag(&self) -> HistoryResult<Vec<PartitionLag>> {
+        let timeout = Duration::from_secs(5);
+        let committed = self
+            .consumer
+            .committed(timeout)
+            .map_err(|e| HistoryError::KafkaError(format!("Failed to fetch committed offsets: {}", e)))?;
+
+        committed
+            .elements_for_topic(&self.topic)
+            .iter()
+            .map(|element| {
+                let (low, high) = self
+                    .consumer
+                    .fetch_watermarks(&self.topic, element.partition(), timeout)
+                    .map_err(|e| HistoryError::KafkaError(format!("Failed to fetch watermarks: {}", e)))?;
+                let committed_offset = match element.offset() {
+                    Offset::Offset(offset) => Some(offset),
+                    _ => None, // Nothing committed yet
+                };
+                Ok(PartitionLag::new(&self.topic, element.partition(), committed_offset, low, high))
+            })
+            .collect()
+    }
+
+    /// Process one partition's messages in order, in their own task
+    fn spawn_worker(self: Arc<Self>, partition: i32) -> PartitionWorker {
+        let (sender, mut queue) = mpsc::channel::<OwnedMessage>(PARTITION_QUEUE_DEPTH);
+        tracing::debug!(topic = %self.topic, partition = partition, "Starting partition worker");
+
+        let task = tokio::spawn(async move {
+            while let Some(message) = queue.recv().await {
+                if self.shutdown.is_triggered() {
+                    // Not started - left for redelivery
+                    break;
+                }
+                // Whatever else is already waiting goes in the same batch
+                let mut batch = vec![message];
+                while batch.len() < self.batch_size {
+                    match queue.try_recv() {
+                        Ok(message) => batch.push(message),
+                        Err(_) => break,
+                    }
+                }
+
+                if !self.before_drain_deadline(self.handle_batch(&batch)).await {
+                    tracing::warn!(
+                        partition = partition,
+                        offset = batch[0].offset(),
+                        messages = batch.len(),
+                        "Shutting down mid-batch, it will be redelivered"
+                    );
+                    break;
+                }
+                for message in &batch {
+                    self.store_offset(message);
+                }
+
+                if queue.is_empty() {
+                    self.unthrottle(partition);
+                }
+            }
+        });
+
+        PartitionWorker {
+            queue: sender,
+            task,
+        }
+    }
+
+    /// Run `work` to completion - unless shutdown started more than
+    /// SHUTDOWN_DRAIN_TIMEOUT ago (a retry tier waiting minutes for a
+    /// message to be due, or a database outage). False if it was cut off.
+    async fn before_drain_deadline(&self, work: impl Future<Output = ()>) -> bool {
+        tokio::select! {
+            () = work => true,
+            () = async {
+                self.shutdown.wait().await;
+                sleep(SHUTDOWN_DRAIN_TIMEOUT).await;
+            } => false,
+        }
+    }
+
+    /// Mark a message done, so the next auto-commit moves past it
+    ///
+    /// Fails harmlessly if the partition was revoked meanwhile: the new
+    /// owner redelivers from the last commit and the duplicate is skipped.
+    fn store_offset<M: Message>(&self, message: &M) {
+        if let Err(e) = self
+            .consumer
+            .store_offset(message.topic(), message.partition(), message.offset())
+        {
+            tracing::debug!(error = %e, partition = message.partition(), "Failed to store offset");
+        }
+    }
+
+    /// A partition's worker is full: pause it and rewind to `message`
+    fn throttle(&self, message: &BorrowedMessage<'_>) {
+        let partition = message.partition();
+        self.throttled
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .insert(partition);
+
+        self.rewind(message);
+        let mut list = TopicPartitionList::new();
+        list.add_partition(&self.topic, partition);
+        if let Err(e) = self.consumer.pause(&list) {
+            tracing::error!(error = %e, partition = partition, "Failed to pause partition");
+        }
+        tracing::debug!(partition = partition, "Partition worker busy, pausing partition");
+    }
+
+    /// A throttled partition's worker caught up: fetch again (unless an
+    /// admin paused everything meanwhile - admin resume covers it then)
+    fn unthrottle(&self, partition: i32) {
+        let was_throttled = self
+            .throttled
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .remove(&partition);
+        if !was_throttled || self.control.is_paused() {
+            return;
+        }
+
+        let mut list = TopicPartitionList::new();
+        list.add_partition(&self.topic, partition);
+        if let Err(e) = self.consumer.resume(&list) {
+            tracing::error!(error = %e, partition = partition, "Failed to resume partition");
+        }
+    }
+
+    /// Stop fetching on every assigned partition (we keep polling, so the
+    /// group membership survives)
+    fn pause_assignment(&self) {
+        match self.consumer.assignment() {
+            Ok(assignment) => {
+                if let Err(e) = self.consumer.pause(&assignment) {
+                    tracing::error!(error = %e, "Failed to pause partitions");
+                }
+            }
+            Err(e) => tracing::error!(error = %e, "Failed to read partition assignment"),
+        }
+    }
+
+    fn resume_assignment(&self) {
+        match self.consumer.assignment() {
+            Ok(assignment) => {
+                if let Err(e) = self.consumer.resume(&assignment) {
+                    tracing::error!(error = %e, "Failed to resume partitions");
+                }
+            }
+            Err(e) => tracing::error!(error = %e, "Failed to read partition assignment"),
+        }
+    }
+
+    /// Re-consume the whole topic, from the beginning to where it ends
+    ///
+    /// Disaster recovery for the read model (`history-service --rebuild`):
+    /// the caller truncates transaction_events first, then this replays
+    /// every event through the normal processing path. Duplicates on the
+    /// topic are still skipped by transaction_id, so the rebuilt history
+    /// matches what live consumption would have stored. Failures go to the
+    /// retry tiers / DLQ as usual.
+    ///
+    /// A partition is done when we reach its end (partition EOF - counted
+    /// after aborted transactions and their markers, unlike offsets). Events
+    /// published after that are left to the regular consumer, which
+    /// continues from the offsets committed here.
+    ///
+    /// Other instances of the group must be stopped - the assignment here is
+    /// manual, so the group coordinator can't keep them off our partitions.
+    pub async fn rebuild(self) -> HistoryResult<RebuildReport> {
+        let metadata = self
+            .consumer
+            .fetch_metadata(Some(&self.topic), Duration::from_secs(10))
+            .map_err(|e| HistoryError::KafkaError(format!("Failed to fetch metadata: {}", e)))?;
+
+        let mut remaining = HashSet::new();
+        let mut assignment = TopicPartitionList::new();
+        for topic in metadata.topics().iter().filter(|t| t.name() == self.topic) {
+            for partition in topic.partitions() {
+                remaining.insert(partition.id());
+                assignment
+                    .add_partition_offset(&self.topic, partition.id(), Offset::Beginning)
+                    .map_err(|e| HistoryError::KafkaError(e.to_string()))?;
+            }
+        }
+
+        let mut report = RebuildReport {
+            partitions: remaining.len(),
+            messages: 0,
+        };
+        if remaining.is_empty() {
+            return Err(HistoryError::KafkaError(format!("Topic {} has no partitions", self.topic)));
+        }
+
+        self.consumer
+            .assign(&assignment)
+            .map_err(|e| HistoryError::KafkaError(format!("Failed to assign partitions: {}", e)))?;
+        tracing::warn!(
+            topic = %self.topic,
+            partitions = report.partitions,
+            "Rebuilding history from the beginning of the topic"
+        );
+
+        while !remaining.is_empty() {
             match self.consumer.recv().await {
                 Ok(message) => {
                     if let Some(payload) = message.payload() {
-                        match self.process_message(payload).await {
-                            Ok(_) => {
-                                tracing::debug!("Message processed successfully");
-                            }
-                            Err(e) => {
-                                tracing::error!(
-                                    error = %e,
-                                    "Failed to process message, will retry on next poll"
-                                );
-                                // Don't crash - log and continue
-                                // Message will be reprocessed if we haven't committed yet
-                            }
-                        }
+                        self.handle_message(&message, payload).await;
+                    }
+                    self.store_offset(&message);
+                    report.messages += 1;
+                    if report.messages.is_multiple_of(10_000) {
+                        tracing::info!(messages = report.messages, "Rebuild progress");
+                    }
+                }
+                Err(KafkaError::PartitionEOF(partition)) => {
+                    if remaining.remove(&partition) {
+                        tracing::info!(partition = partition, "Partition rebuilt");
                     }
                 }
                 Err(e) => {
-                    tracing::error!(error = %e, "Kafka error");
-                    // Sleep briefly before retrying
+                    tracing::error!(error = %e, "Kafka error during rebuild");
                     sleep(Duration::from_secs(1)).await;
                 }
             }
         }
+
+        // Hand our position to the group so live consumption picks up here
+        self.consumer
+            .commit_consumer_state(CommitMode::Sync)
+            .map_err(|e| HistoryError::KafkaError(format!("Failed to commit offsets: {}", e)))?;
+
+        tracing::warn!(messages = report.messages, "History rebuild complete");
+        Ok(report)
     }
 
-    /// Process a single message from Kafka
-    async fn process_message(&self, payload: &[u8]) -> HistoryResult<()> {
-        // Deserialize JSON to WalletEvent
-        let event: WalletEvent = serde_json::from_slice(payload)
-            .map_err(|e| {
+    /// Move partitions we own to the requested offsets (POST /admin/consumer/seek)
+    fn apply_seek(&self, scope: SeekScope) -> Vec<PartitionSeekResult> {
+        let assigned: Vec<i32> = match self.consumer.assignment() {
+            Ok(assignment) => assignment
+                .elements_for_topic(&self.topic)
+                .iter()
+                .map(|element| element.partition())
+                .collect(),
+            Err(e) => {
+                tracing::error!(error = %e, "Failed to read partition assignment");
+                Vec::new()
+            }
+        };
+
+        let targets = match scope {
+            SeekScope::Partitions(targets) => targets,
+            SeekScope::AllAssigned(timestamp) => assigned
+                .iter()
+                .map(|partition| (*partition, SeekTarget::Timestamp(timestamp)))
+                .collect(),
+        };
+
+        targets
+            .into_iter()
+            .map(|(partition, target)| {
+                if !assigned.contains(&partition) {
+                    return PartitionSeekResult {
+                        partition,
+                        assigned: false,
+                        offset: None,
+                        error: None,
+                    };
+                }
+
+                match self.seek_partition(partition, target) {
+                    Ok(offset) => {
+                        tracing::warn!(partition = partition, offset = ?offset, "Consumer moved by admin seek");
+                        PartitionSeekResult {
+                            partition,
+                            assigned: true,
+                            offset: offset.to_raw().filter(|raw| *raw >= 0),
+                            error: None,
+                        }
+                    }
+                    Err(e) => PartitionSeekResult {
+                        partition,
+                        assigned: true,
+                        offset: None,
+                        error: Some(e.to_string()),
+                    },
+                }
+            })
+            .collect()
+    }
+
+    /// Timestamps resolve to the first offset at or after them (the end if
+    /// the partition has nothing that recent)
+    fn seek_partition(&self, partition: i32, target: SeekTarget) -> HistoryResult<Offset> {
+        let offset = match target {
+            SeekTarget::Offset(offset) => Offset::Offset(offset),
+            SeekTarget::Timestamp(timestamp) => {
+                let mut query = TopicPartitionList::new();
+                query
+                    .add_partition_offset(&self.topic, partition, Offset::Offset(timestamp.timestamp_millis()))
+                    .map_err(|e| HistoryError::KafkaError(e.to_string()))?;
+                self.consumer
+                    .offsets_for_times(query, Duration::from_secs(10))
+                    .map_err(|e| HistoryError::KafkaError(format!("Failed to look up offsets: {}", e)))?
+                    .find_partition(&self.topic, partition)
+                    .map(|element| element.offset())
+                    .unwrap_or(Offset::End)
+            }
+        };
+
+        self.consumer
+            .seek(&self.topic, partition, offset, Duration::from_secs(5))
+            .map_err(|e| HistoryError::KafkaError(format!("Failed to seek: {}", e)))?;
+
+        Ok(offset)
+    }
+
+    /// A message arrived while paused: seek back to it and pause again
+    fn hold(&self, message: &BorrowedMessage<'_>) {
+        self.rewind(message);
+        self.pause_assignment();
+    }
+
+    /// Make `message` the next one fetched from its partition
+    fn rewind(&self, message: &BorrowedMessage<'_>) {
+        let offset = Offset::Offset(message.offset());
+        if let Err(e) = self
+            .consumer
+            .seek(message.topic(), message.partition(), offset, Duration::from_secs(5))
+        {
+            tracing::error!(error = %e, partition = message.partition(), "Failed to rewind partition");
+        }
+    }
+
+    /// Process a partition's queued messages, in order
+    ///
+    /// Messages that verify and decode are stored together: one INSERT for
+    /// all their rows, waiting out database outages like a single message
+    /// would. Then each one's sequence is checked, in order.
+    ///
+    /// The one-at-a-time path (handle_message) takes over, in order, for:
+    /// - Messages that don't verify or decode - they're routed on their own
+    /// - USER_ANONYMIZED, which rewrites earlier rows: the batch before it
+    ///   is stored first
+    /// - Every message of a batch the database rejected, so only the bad one
+    ///   goes to a retry tier (nothing of the batch was stored)
+    /// - A message whose sequence check failed (its rows are already
+    ///   stored, so the retry only repeats the check)
+    async fn handle_batch(&self, batch: &[OwnedMessage]) {
+        if batch.len() == 1 || self.tier.is_some() {
+            for message in batch {
+                if let Some(payload) = message.payload() {
+                    self.handle_message(message, payload).await;
+                }
+            }
+            return;
+        }
+
+        let mut pending = Vec::new();
+        for message in batch {
+            let Some(payload) = message.payload() else {
+                continue;
+            };
+            match self.decode_verified(message, payload).await {
+                Some(envelope) if !matches!(envelope.event, WalletEvent::UserAnonymized { .. }) => {
+                    pending.push((message, envelope));
+                }
+                _ => {
+                    self.store_pending(std::mem::take(&mut pending)).await;
+                    self.handle_message(message, payload).await;
+                }
+            }
+        }
+        self.store_pending(pending).await;
+    }
+
+    /// A message's envelope, if it verifies and decodes (None: leave it to
+    /// handle_message, which routes the failure)
+    async fn decode_verified(&self, message: &OwnedMessage, payload: &[u8]) -> Option<EventEnvelope> {
+        let signature = message.headers().and_then(signing::signature);
+        self.verify(message.key(), payload, signature).ok()?;
+        self.decoder.decode(payload).await.ok()
+    }
+
+    /// Store decoded messages with one INSERT, then check their sequences
+    async fn store_pending(&self, pending: Vec<(&OwnedMessage, EventEnvelope)>) {
+        let Some((first, _)) = pending.first() else {
+            return;
+        };
+        let envelopes: Vec<&EventEnvelope> = pending.iter().map(|(_, envelope)| envelope).collect();
+
+        let stored = self
+            .until_database_reachable(*first, || self.repository.store_envelopes(&envelopes))
+            .await;
+        let stored = match stored {
+            Ok(stored) => stored,
+            Err(e) => {
                 tracing::warn!(
                     error = %e,
-                    payload = ?String::from_utf8_lossy(payload),
-                    "Failed to deserialize event"
+                    partition = first.partition(),
+                    offset = first.offset(),
+                    messages = pending.len(),
+                    "Batch insert failed, processing its messages one at a time"
                 );
-                crate::errors::HistoryError::SerializationError(e.to_string())
-            })?;
+                for (message, _) in &pending {
+                    if let Some(payload) = message.payload() {
+                        self.handle_message(*message, payload).await;
+                    }
+                }
+                return;
+            }
+        };
+        self.invalidate_cached(&stored);
+
+        for (message, envelope) in &pending {
+            let Some(sequence) = envelope.sequence() else {
+                continue;
+            };
+            let checked = self
+                .until_database_reachable(*message, || self.check_sequence(envelope.event.wallet_id(), sequence))
+                .await;
+            if checked.is_err() {
+                if let Some(payload) = message.payload() {
+                    self.handle_message(*message, payload).await;
+                }
+            }
+        }
+    }
+
+    /// Process one message; route failures to the next retry tier or the DLQ
+    /// 
+    /// - Database unreachable: wait and try again in place (see
+    ///   process_until_database_reachable) - no tier, no DLQ
+    /// - Retryable errors (DB, Kafka): main -> retry-5s -> retry-1m -> retry-10m
+    /// - Anything else (bad JSON, unexpected shape, bad signature): straight
+    ///   to the DLQ
+    /// - Out of tiers: DLQ
+    /// 
+    /// Either way the message is dealt with before we move on, so auto-commit
+    /// never skips past a failure silently.
+    async fn handle_message<M: Message>(&self, message: &M, payload: &[u8]) {
+        let retry = message.headers().and_then(RetryInfo::from_headers);
+
+        // Retry tiers hold messages until they're due
+        if let Some(info) = &retry {
+            if let Ok(wait) = (info.not_before - Utc::now()).to_std() {
+                sleep(wait).await;
+            }
+        }
+
+        let signature = message.headers().and_then(signing::signature);
+        let result = match self.verify(message.key(), payload, signature) {
+            Ok(()) => {
+                self.until_database_reachable(message, || self.process_message(payload))
+                    .await
+            }
+            Err(e) => {
+                tracing::warn!(
+                    error = %e,
+                    partition = message.partition(),
+                    offset = message.offset(),
+                    "Rejecting unverified message"
+                );
+                Err(e)
+            }
+        };
+
+        let error = match result {
+            Ok(()) => {
+                tracing::debug!("Message processed successfully");
+                return;
+            }
+            Err(e) => e,
+        };
+
+        let attempt = retry.as_ref().map(|r| r.attempt).unwrap_or(0) + 1;
+        let original_topic = retry
+            .map(|r| r.original_topic)
+            .unwrap_or_else(|| message.topic().to_string());
+        let next_tier = self.tier.map(|t| t + 1).unwrap_or(0);
+
+        if error.is_retryable() {
+            if let Some(target) = self.failures.retries.tiers().get(next_tier) {
+                let info = RetryInfo {
+                    attempt,
+                    not_before: Utc::now()
+                        + chrono::Duration::from_std(target.delay).unwrap_or_default(),
+                    original_topic: original_topic.clone(),
+                    last_error: error.to_string(),
+                };
+
+                match self
+                    .failures
+                    .retries
+                    .schedule(next_tier, message.key(), payload, signature, &info)
+                    .await
+                {
+                    Ok(()) => {
+                        tracing::warn!(
+                            error = %error,
+                            attempt = attempt,
+                            retry_topic = %target.topic,
+                            "Failed to process message, scheduled retry"
+                        );
+                        return;
+                    }
+                    Err(e) => {
+                        // Can't park it for retry - the DLQ is the safe fallback
+                        tracing::error!(error = %e, "Failed to schedule retry");
+                    }
+                }
+            }
+        }
+
+        let letter = DeadLetter::new(
+            &original_topic,
+            message.partition(),
+            message.offset(),
+            message.key(),
+            payload,
+            &error,
+            attempt,
+        );
+        self.dead_letter(&letter).await;
+    }
+
+    fn verify(
+        &self,
+        key: Option<&[u8]>,
+        payload: &[u8],
+        signature: Option<&[u8]>,
+    ) -> HistoryResult<()> {
+        match &self.verifier {
+            Some(verifier) => verifier.verify(key, payload, signature),
+            None => Ok(()),
+        }
+    }
+
+    /// Run `attempt` (storing `message`, or a batch starting with it),
+    /// waiting out database outages
+    ///
+    /// Why not the retry tiers?
+    /// - When the database is down EVERY message fails, so the tiers would
+    ///   just march the whole topic into the DLQ within ~11 minutes
+    /// - Blocking here is backpressure instead: the worker stops, its queue
+    ///   fills, the partition gets paused and nothing is committed past the
+    ///   message, so the outage loses nothing and needs no DLQ replay
+    ///
+    /// Backoff doubles from 1s up to 30s. Other errors are returned at once.
+    async fn until_database_reachable<M, T, F, Fut>(&self, message: &M, attempt: F) -> HistoryResult<T>
+    where
+        M: Message,
+        F: Fn() -> Fut,
+        Fut: Future<Output = HistoryResult<T>>,
+    {
+        let mut backoff = Duration::from_secs(1);
+        let mut failures = 0u32;
+
+        loop {
+            match attempt().await {
+                Err(e) if e.is_connection_error() => {
+                    failures += 1;
+                    tracing::warn!(
+                        error = %e,
+                        partition = message.partition(),
+                        offset = message.offset(),
+                        failures = failures,
+                        retry_in_secs = backoff.as_secs(),
+                        "Database unreachable, holding partition until it's back"
+                    );
+                    sleep(backoff).await;
+                    backoff = (backoff * 2).min(MAX_DATABASE_BACKOFF);
+                }
+                result => {
+                    if failures > 0 && result.is_ok() {
+                        tracing::info!(failures = failures, "Database reachable again");
+                    }
+                    return result;
+                }
+            }
+        }
+    }
+
+    /// Park a message on the dead-letter topic
+    /// 
+    /// Keeps trying until the DLQ accepts it - blocking the partition for a
+    /// while beats losing the event.
+    async fn dead_letter(&self, letter: &DeadLetter) {
+        tracing::error!(
+            partition = letter.partition,
+            offset = letter.offset,
+            attempts = letter.attempts,
+            error = %letter.error,
+            "Giving up on message, sending to dead-letter topic"
+        );
+
+        while let Err(e) = self.failures.dead_letters.publish(letter).await {
+            tracing::error!(error = %e, "Failed to publish dead letter, retrying");
+            sleep(Duration::from_secs(1)).await;
+        }
+    }
+
+    /// Process a single message from Kafka
+    async fn process_message(&self, payload: &[u8]) -> HistoryResult<()> {
+        // Deserialize JSON, Avro or Protobuf to WalletEvent
+        let envelope: EventEnvelope = self.decoder.decode(payload).await.inspect_err(|e| {
+            tracing::warn!(
+                error = %e,
+                payload = ?String::from_utf8_lossy(payload),
+                "Failed to deserialize event"
+            );
+        })?;
+        let event = &envelope.event;
 
         tracing::info!(
             event_type = %event.event_type(),
             wallet_id = %event.wallet_id(),
+            event_id = %envelope.ids.event_id,
+            correlation_id = %envelope.ids.correlation_id,
+            request_id = %envelope.ids.request_id,
             "Processing event"
         );
 
         // Store in database based on event type
-        match &event {
-            WalletEvent::TransferCompleted { .. } => {
-                // Transfers create TWO events (sender + receiver)
-                let events = self.repository.store_transfer_events(&event).await?;
-                tracing::info!(
-                    event_count = events.len(),
-                    "Transfer events stored"
-                );
-            }
-            _ => {
-                // Other events create ONE event
-                if let Some(stored_event) = self.repository.store_event(&event).await? {
-                    tracing::info!(
-                        event_id = %stored_event.id,
-                        "Event stored"
-                    );
-                } else {
-                    tracing::debug!("Event already processed (duplicate)");
-                }
-            }
+        let stored = self.repository.store_envelope(&envelope).await?;
+        self.invalidate_cached(&stored);
+        // Its rows moved to the pseudonym - the old ID's responses are stale too
+        if let WalletEvent::UserAnonymized { user_id, .. } = event {
+            self.cache.invalidate(&CacheScope::User(user_id.clone()));
         }
 
+        // After storing, so a failure here retries the whole message (the
+        // store is then a no-op) rather than losing the check
+        if let Some(sequence) = envelope.sequence() {
+            self.check_sequence(event.wallet_id(), sequence).await?;
+        }
+
+        Ok(())
+    }
+
+    /// Cached responses for the affected wallets/users are now stale
+    /// (duplicates stored nothing, so they invalidate nothing)
+    fn invalidate_cached(&self, stored: &[TransactionEvent]) {
+        for stored_event in stored {
+            self.cache
+                .invalidate(&CacheScope::Wallet(stored_event.wallet_id.clone()));
+            self.cache
+                .invalidate(&CacheScope::User(stored_event.user_id.clone()));
+        }
+    }
+
+    /// Track the event's per-wallet number and flag gaps / late arrivals
+    async fn check_sequence(&self, wallet_id: &str, sequence: i64) -> HistoryResult<()> {
+        match self.repository.track_sequence(wallet_id, sequence).await? {
+            SequenceOutcome::InOrder | SequenceOutcome::Duplicate => {}
+            SequenceOutcome::GapOpened { first_missing, last_missing } => tracing::warn!(
+                wallet_id = %wallet_id,
+                sequence = sequence,
+                first_missing = first_missing,
+                last_missing = last_missing,
+                "Sequence gap: events missing for wallet"
+            ),
+            SequenceOutcome::GapFilled => tracing::info!(
+                wallet_id = %wallet_id,
+                sequence = sequence,
+                "Late event filled a sequence gap"
+            ),
+        }
         Ok(())
     }
 }
 
+/// Consumer settings shared by live consumption and rebuilds
+///
+/// `partition_eof`: report reaching the end of a partition (rebuilds stop there)
+fn create_consumer(
+    brokers: &str,
+    group_id: &str,
+    max_poll_interval: Duration,
+    partition_eof: bool,
+) -> HistoryResult<StreamConsumer> {
+    client_config(brokers)
+        .set("group.id", group_id)
+        .set("auto.offset.reset", "earliest") // Start from beginning if no offset
+        .set("enable.auto.commit", "true") // Auto-commit offsets
+        .set("enable.auto.offset.store", "false") // ...but only those we've stored (processed)
+        .set("auto.commit.interval.ms", "5000") // Commit every 5 seconds
+        .set("session.timeout.ms", "30000")
+        .set("enable.partition.eof", partition_eof.to_string())
+        .set("isolation.level", "read_committed") // Skip aborted outbox-relay transactions
+        .set("max.poll.interval.ms", max_poll_interval.as_millis().to_string())
+        .create()
+        .map_err(|e| HistoryError::KafkaError(format!("Failed to create consumer: {}", e)))
+}
+
+/// The next admin seek (never resolves for consumers that don't take them)
+async fn next_seek(seeks: &mut Option<mpsc::Receiver<SeekCommand>>) -> Option<SeekCommand> {
+    match seeks {
+        Some(seeks) => seeks.recv().await,
+        None => std::future::pending().await,
+    }
+}
+
 // Why consumer group?
 // 
 // Multiple History Service instances can run in parallel:
@@ -0,0 +1,210 @@
+use crate::errors::{HistoryError, HistoryResult};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::sync::Mutex;
+use std::time::Duration;
+use tokio::sync::{mpsc, oneshot, watch};
+use utoipa::ToSchema;
+
+/// How long an admin seek waits for the consumer to pick it up
+const SEEK_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Runtime switch for the Kafka consumers (POST /admin/consumer/pause|resume)
+///
+/// Why?
+/// - Stop writing history during a migration of transaction_events, or
+///   while an incident is investigated, without restarting the service
+/// - Restarting would also rebalance the group; pausing keeps our partitions
+///
+/// How it works:
+/// - Every consumer (main topic and retry tiers) watches the same flag
+/// - Paused consumers pause fetching on their assigned partitions but keep
+///   polling, so they stay in the group and Kafka doesn't reassign them
+/// - The message being processed when the pause arrives is finished first
+/// - Nothing is skipped: on resume consumption continues from where it stopped
+///
+/// It also carries seek requests (POST /admin/consumer/seek) to the
+/// main-topic consumer - see `SeekRequest`.
+pub struct ConsumerControl {
+    paused: watch::Sender<bool>,
+    changed_at: Mutex<Option<DateTime<Utc>>>,
+    seek_tx: mpsc::Sender<SeekCommand>,
+    seek_rx: Mutex<Option<mpsc::Receiver<SeekCommand>>>,
+}
+
+/// Consumer state shown by the admin endpoints
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct ConsumerStatus {
+    pub paused: bool,
+    pub changed_at: Option<DateTime<Utc>>,
+}
+
+/// Move the main-topic consumer to an offset or a point in time
+///
+/// Used to re-ingest a window of events after fixing a processing bug.
+/// Already-stored events are skipped as duplicates, so replaying too much
+/// is harmless; events the bug dropped (e.g. dead-lettered) get stored.
+///
+/// ```json
+/// {"partitions": [{"partition": 0, "offset": 1200},
+///                 {"partition": 1, "timestamp": "2025-01-29T10:00:00Z"}]}
+/// {"timestamp": "2025-01-29T10:00:00Z"}   // every partition we own
+/// ```
+///
+/// Only partitions assigned to THIS instance can be moved; others are
+/// reported as not assigned (send the request to the instance owning them).
+#[derive(Debug, Clone, Default, Deserialize, ToSchema)]
+pub struct SeekRequest {
+    #[serde(default)]
+    pub partitions: Vec<PartitionSeek>,
+    pub timestamp: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Clone, Deserialize, ToSchema)]
+pub struct PartitionSeek {
+    pub partition: i32,
+    pub offset: Option<i64>,
+    pub timestamp: Option<DateTime<Utc>>,
+}
+
+/// Where one partition should go
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SeekTarget {
+    Offset(i64),
+    Timestamp(DateTime<Utc>),
+}
+
+/// What happened to one partition
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct PartitionSeekResult {
+    pub partition: i32,
+    pub assigned: bool,
+    /// The offset consumption continues from (None: not moved, or moved to
+    /// the end because nothing is as recent as the timestamp)
+    pub offset: Option<i64>,
+    pub error: Option<String>,
+}
+
+/// A validated seek: listed partitions, or every assigned one to one time
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SeekScope {
+    Partitions(Vec<(i32, SeekTarget)>),
+    AllAssigned(DateTime<Utc>),
+}
+
+/// A seek on its way to the consumer, with a reply channel
+pub struct SeekCommand {
+    pub scope: SeekScope,
+    pub reply: oneshot::Sender<Vec<PartitionSeekResult>>,
+}
+
+impl SeekRequest {
+    /// Check the request: partitions or a timestamp, each partition one target
+    pub fn scope(&self) -> HistoryResult<SeekScope> {
+        match (self.partitions.is_empty(), self.timestamp) {
+            (true, Some(timestamp)) => Ok(SeekScope::AllAssigned(timestamp)),
+            (true, None) => Err(HistoryError::InvalidRequest(
+                "give partitions or a timestamp".to_string(),
+            )),
+            (false, Some(_)) => Err(HistoryError::InvalidRequest(
+                "give partitions or a timestamp, not both".to_string(),
+            )),
+            (false, None) => self
+                .partitions
+                .iter()
+                .map(|p| match (p.offset, p.timestamp) {
+                    (Some(offset), None) if offset >= 0 => Ok((p.partition, SeekTarget::Offset(offset))),
+                    (None, Some(timestamp)) => Ok((p.partition, SeekTarget::Timestamp(timestamp))),
+                    _ => Err(HistoryError::InvalidRequest(format!(
+                        "partition {} needs exactly one of a non-negative offset or a timestamp",
+                        p.partition
+                    ))),
+                })
+                .collect::<HistoryResult<Vec<_>>>()
+                .map(SeekScope::Partitions),
+        }
+    }
+}
+
+impl ConsumerControl {
+    /// Consumers start running
+    pub fn new() -> Self {
+        let (paused, _) = watch::channel(false);
+        let (seek_tx, seek_rx) = mpsc::channel(8);
+        Self {
+            paused,
+            changed_at: Mutex::new(None),
+            seek_tx,
+            seek_rx: Mutex::new(Some(seek_rx)),
+        }
+    }
+
+    pub fn pause(&self) -> ConsumerStatus {
+        self.set_paused(true)
+    }
+
+    pub fn resume(&self) -> ConsumerStatus {
+        self.set_paused(false)
+    }
+
+    pub fn is_paused(&self) -> bool {
+        *self.paused.borrow()
+    }
+
+    pub fn status(&self) -> ConsumerStatus {
+        ConsumerStatus {
+            paused: self.is_paused(),
+            changed_at: *self.changed_at.lock().unwrap_or_else(|e| e.into_inner()),
+        }
+    }
+
+    /// Notified whenever the flag flips (what the consumer loops wait on)
+    pub fn subscribe(&self) -> watch::Receiver<bool> {
+        self.paused.subscribe()
+    }
+
+    /// Hand a seek to the main-topic consumer and wait for the outcome
+    ///
+    /// It's applied between two messages, paused or not.
+    pub async fn seek(&self, request: &SeekRequest) -> HistoryResult<Vec<PartitionSeekResult>> {
+        let scope = request.scope()?;
+        let (reply, outcome) = oneshot::channel();
+
+        self.seek_tx
+            .send(SeekCommand { scope, reply })
+            .await
+            .map_err(|_| HistoryError::InternalError("Consumer is not running".to_string()))?;
+
+        tokio::time::timeout(SEEK_TIMEOUT, outcome)
+            .await
+            .map_err(|_| HistoryError::InternalError("Consumer did not apply the seek in time".to_string()))?
+            .map_err(|_| HistoryError::InternalError("Consumer dropped the seek".to_string()))
+    }
+
+    /// The seek requests (only one consumer - the main topic's - takes them)
+    pub fn take_seek_requests(&self) -> Option<mpsc::Receiver<SeekCommand>> {
+        self.seek_rx.lock().unwrap_or_else(|e| e.into_inner()).take()
+    }
+
+    /// Pausing twice (or resuming a running consumer) is a no-op
+    fn set_paused(&self, paused: bool) -> ConsumerStatus {
+        let changed = self.paused.send_if_modified(|current| {
+            let changed = *current != paused;
+            *current = paused;
+            changed
+        });
+
+        if changed {
+            *self.changed_at.lock().unwrap_or_else(|e| e.into_inner()) = Some(Utc::now());
+            tracing::warn!(paused = paused, "Kafka consumers {}", if paused { "paused" } else { "resumed" });
+        }
+
+        self.status()
+    }
+}
+
+impl Default for ConsumerControl {
+    fn default() -> Self {
+        Self::new()
+    }
+}
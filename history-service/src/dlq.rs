@@ -0,0 +1,98 @@
+use crate::errors::{HistoryError, HistoryResult};
+use crate::kafka_security::client_config;
+use chrono::{DateTime, Utc};
+use rdkafka::producer::{FutureProducer, FutureRecord};
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+/// A message the consumer gave up on, plus where it came from and why
+///
+/// Everything needed to inspect and replay it later:
+/// - original topic / partition / offset to find it in the source topic
+/// - the raw payload: as-is for JSON events, hex for binary (Avro) ones
+/// - the last error and how many attempts were made
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DeadLetter {
+    pub original_topic: String,
+    pub partition: i32,
+    pub offset: i64,
+    pub key: Option<String>,
+    pub payload: String,
+    /// "utf8" or "hex"
+    pub payload_encoding: String,
+    pub error: String,
+    pub retryable: bool,
+    pub attempts: u32,
+    pub failed_at: DateTime<Utc>,
+}
+
+impl DeadLetter {
+    pub fn new(
+        original_topic: &str,
+        partition: i32,
+        offset: i64,
+        key: Option<&[u8]>,
+        payload: &[u8],
+        error: &HistoryError,
+        attempts: u32,
+    ) -> Self {
+        let (payload, payload_encoding) = match std::str::from_utf8(payload) {
+            Ok(text) => (text.to_string(), "utf8"),
+            Err(_) => (hex::encode(payload), "hex"),
+        };
+
+        Self {
+            original_topic: original_topic.to_string(),
+            partition,
+            offset,
+            key: key.map(|k| String::from_utf8_lossy(k).into_owned()),
+            payload,
+            payload_encoding: payload_encoding.to_string(),
+            error: error.to_string(),
+            retryable: error.is_retryable(),
+            attempts,
+            failed_at: Utc::now(),
+        }
+    }
+}
+
+/// Producer for the dead-letter topic (wallet-events-dlq)
+///
+/// Why a DLQ?
+/// - With auto-commit, a message that fails is skipped on the next poll
+///   and silently lost
+/// - Retrying it forever blocks its partition instead
+/// - Parking it on a side topic keeps the partition moving AND keeps the message
+pub struct DeadLetterProducer {
+    producer: FutureProducer,
+    topic: String,
+}
+
+impl DeadLetterProducer {
+    pub fn new(brokers: &str, topic: String) -> HistoryResult<Self> {
+        let producer: FutureProducer = client_config(brokers)
+            .set("message.timeout.ms", "5000")
+            .set("acks", "all")
+            .create()
+            .map_err(|e| HistoryError::KafkaError(format!("Failed to create DLQ producer: {}", e)))?;
+
+        Ok(Self { producer, topic })
+    }
+
+    /// Publish a dead letter, keyed like the original so per-wallet order holds
+    pub async fn publish(&self, letter: &DeadLetter) -> HistoryResult<()> {
+        let payload = serde_json::to_string(letter)
+            .map_err(|e| HistoryError::SerializationError(e.to_string()))?;
+
+        let mut record = FutureRecord::to(&self.topic).payload(&payload);
+        if let Some(key) = &letter.key {
+            record = record.key(key);
+        }
+
+        self.producer
+            .send(record, Duration::from_secs(5))
+            .await
+            .map(|_| ())
+            .map_err(|(e, _)| HistoryError::KafkaError(format!("Failed to publish dead letter: {}", e)))
+    }
+}
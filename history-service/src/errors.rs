@@ -3,8 +3,9 @@ use axum::{
     response::{IntoResponse, Response},
     Json,
 };
-use serde_json::json;
+use serde::Serialize;
 use thiserror::Error;
+use utoipa::ToSchema;
 
 #[derive(Debug, Error)]
 pub enum HistoryError {
@@ -14,22 +15,81 @@ pub enum HistoryError {
     #[error("Database error: {0}")]
     DatabaseError(#[from] sqlx::Error),
 
+    #[error("Invalid request: {0}")]
+    InvalidRequest(String),
+
+    /// No valid bearer token (401)
+    #[error("Unauthorized: {0}")]
+    Unauthorized(String),
+
+    /// Authenticated, but not allowed to see this (403)
+    #[error("Forbidden: {0}")]
+    Forbidden(String),
+
     #[error("Kafka error: {0}")]
     KafkaError(String),
 
     #[error("Serialization error: {0}")]
     SerializationError(String),
 
+    /// A message without a valid signature from a known key (tampered,
+    /// or not from wallet-service)
+    #[error("Invalid event signature: {0}")]
+    InvalidSignature(String),
+
+    /// The ClickHouse copy refused or didn't answer (see analytics.rs)
+    #[error("Analytics error: {0}")]
+    AnalyticsError(String),
+
     #[error("Internal server error: {0}")]
     InternalError(String),
 }
 
-impl IntoResponse for HistoryError {
-    fn into_response(self) -> Response {
-        let (status, error_message) = match self {
+impl HistoryError {
+    /// Could trying the same message again succeed?
+    /// 
+    /// - Database / Kafka / ClickHouse hiccups: yes (transient)
+    /// - Bad payloads and logic errors: no - retrying a poison message
+    ///   just fails the same way again
+    pub fn is_retryable(&self) -> bool {
+        matches!(
+            self,
+            HistoryError::DatabaseError(_)
+                | HistoryError::KafkaError(_)
+                | HistoryError::AnalyticsError(_)
+        )
+    }
+
+    /// Is the database unreachable (rather than rejecting this one query)?
+    ///
+    /// These fail every message alike, so the consumer waits them out
+    /// instead of retrying message by message.
+    pub fn is_connection_error(&self) -> bool {
+        matches!(
+            self,
+            HistoryError::DatabaseError(
+                sqlx::Error::Io(_)
+                    | sqlx::Error::Tls(_)
+                    | sqlx::Error::PoolTimedOut
+                    | sqlx::Error::PoolClosed
+                    | sqlx::Error::WorkerCrashed
+            )
+        )
+    }
+
+    /// Status and client-facing message (internal details are logged, not
+    /// returned) - shared by the REST responses and GraphQL errors
+    pub fn public_parts(&self) -> (StatusCode, String) {
+        match self {
             HistoryError::NotFound => (StatusCode::NOT_FOUND, self.to_string()),
+
+            HistoryError::InvalidRequest(_) => (StatusCode::BAD_REQUEST, self.to_string()),
+
+            HistoryError::Unauthorized(_) => (StatusCode::UNAUTHORIZED, self.to_string()),
+
+            HistoryError::Forbidden(_) => (StatusCode::FORBIDDEN, self.to_string()),
             
-            HistoryError::DatabaseError(ref e) => {
+            HistoryError::DatabaseError(e) => {
                 tracing::error!("Database error: {:?}", e);
                 (
                     StatusCode::INTERNAL_SERVER_ERROR,
@@ -37,7 +97,7 @@ impl IntoResponse for HistoryError {
                 )
             }
             
-            HistoryError::KafkaError(ref e) => {
+            HistoryError::KafkaError(e) => {
                 tracing::error!("Kafka error: {}", e);
                 (
                     StatusCode::INTERNAL_SERVER_ERROR,
@@ -45,30 +105,59 @@ impl IntoResponse for HistoryError {
                 )
             }
             
-            HistoryError::SerializationError(ref e) => {
+            HistoryError::SerializationError(e) => {
                 tracing::error!("Serialization error: {}", e);
                 (
                     StatusCode::INTERNAL_SERVER_ERROR,
                     "Failed to process event".to_string(),
                 )
             }
+
+            HistoryError::InvalidSignature(e) => {
+                tracing::error!("Invalid event signature: {}", e);
+                (
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    "Failed to process event".to_string(),
+                )
+            }
             
-            HistoryError::InternalError(ref e) => {
+            HistoryError::AnalyticsError(e) => {
+                tracing::error!("Analytics error: {}", e);
+                (
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    "Analytics query failed".to_string(),
+                )
+            }
+
+            HistoryError::InternalError(e) => {
                 tracing::error!("Internal error: {}", e);
                 (
                     StatusCode::INTERNAL_SERVER_ERROR,
                     "An unexpected error occurred".to_string(),
                 )
             }
-        };
+        }
+    }
+}
+
+impl IntoResponse for HistoryError {
+    fn into_response(self) -> Response {
+        let (status, error_message) = self.public_parts();
 
-        let body = Json(json!({
-            "success": false,
-            "error": error_message,
-        }));
+        let body = Json(ErrorResponse {
+            success: false,
+            error: error_message,
+        });
 
         (status, body).into_response()
     }
 }
 
+/// Body of every error response: `{"success": false, "error": "..."}`
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ErrorResponse {
+    pub success: bool,
+    pub error: String,
+}
+
 pub type HistoryResult<T> = Result<T, HistoryError>;
@@ -0,0 +1,297 @@
+use crate::errors::{HistoryError, HistoryResult};
+use crate::filter::HistoryFilter;
+use crate::models::{EventResponse, TransactionEvent};
+use crate::parquet_export::ParquetEncoder;
+use crate::repository::EventRepository;
+use axum::{
+    body::Body,
+    http::{header, HeaderValue},
+    response::{IntoResponse, Response},
+};
+use chrono::{DateTime, Utc};
+use futures_util::{stream, StreamExt};
+use serde::Deserialize;
+use std::borrow::Cow;
+use std::fmt::Write;
+use tokio::sync::mpsc;
+use utoipa::{IntoParams, ToSchema};
+
+/// Rows are sent to the client in chunks of about this many bytes
+pub const CHUNK_BYTES: usize = 64 * 1024;
+
+/// Response header on exports that cold storage cut short: events stored
+/// before this (RFC 3339) aren't in the file
+pub const HISTORY_TRUNCATED_HEADER: &str = "x-history-truncated-before";
+
+/// Chunks buffered between the database and a slow client
+const CHUNKS_IN_FLIGHT: usize = 4;
+
+const CSV_COLUMNS: &str =
+    "id,wallet_id,user_id,event_type,amount,transaction_id,event_id,correlation_id,created_at\n";
+
+/// Around the events of a JSON export: the page endpoints' envelope
+const JSON_START: &str = r#"{"success":true,"data":["#;
+const JSON_END: &str = "]}";
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum ExportFormat {
+    #[default]
+    Csv,
+    /// One event per line as JSON, `event_data` included (for data-lake
+    /// ingestion: every line parses on its own)
+    Ndjson,
+    /// Typed columns for Spark/DuckDB (see parquet_export.rs)
+    Parquet,
+    /// One JSON document, `{"success": true, "data": [...]}` with the
+    /// history pages' event objects - for API clients that want every
+    /// event in one response
+    Json,
+}
+
+impl ExportFormat {
+    pub fn content_type(self) -> &'static str {
+        match self {
+            ExportFormat::Csv => "text/csv; charset=utf-8",
+            ExportFormat::Ndjson => "application/x-ndjson",
+            ExportFormat::Parquet => "application/vnd.apache.parquet",
+            ExportFormat::Json => "application/json",
+        }
+    }
+
+    pub fn extension(self) -> &'static str {
+        match self {
+            ExportFormat::Csv => "csv",
+            ExportFormat::Ndjson => "ndjson",
+            ExportFormat::Parquet => "parquet",
+            ExportFormat::Json => "json",
+        }
+    }
+}
+
+
+/// Turns events into the bytes of one export
+///
+/// CSV, NDJSON and JSON are written a row at a time; Parquet is encoded a
+/// row group at a time, so its bytes come out in bigger, less frequent
+/// chunks.
+pub enum ExportEncoder {
+    Csv(String),
+    Ndjson(String),
+    Parquet(Box<ParquetEncoder>),
+    /// The output so far, and whether an event has been written (the next
+    /// one needs a comma)
+    Json(String, bool),
+}
+
+impl ExportEncoder {
+    pub fn new(format: ExportFormat) -> HistoryResult<Self> {
+        Ok(match format {
+            ExportFormat::Csv => ExportEncoder::Csv(String::from(CSV_COLUMNS)),
+            ExportFormat::Ndjson => ExportEncoder::Ndjson(String::new()),
+            ExportFormat::Parquet => ExportEncoder::Parquet(Box::new(ParquetEncoder::new()?)),
+            ExportFormat::Json => ExportEncoder::Json(String::from(JSON_START), false),
+        })
+    }
+
+    pub fn push(&mut self, event: TransactionEvent) -> HistoryResult<()> {
+        match self {
+            ExportEncoder::Csv(out) => {
+                let _ = writeln!(
+                    out,
+                    "{},{},{},{},{},{},{},{},{}",
+                    csv_field(&event.id),
+                    csv_field(&event.wallet_id),
+                    csv_field(&event.user_id),
+                    csv_field(&event.event_type),
+                    event.amount,
+                    csv_field(event.transaction_id.as_deref().unwrap_or("")),
+                    csv_field(event.event_id.as_deref().unwrap_or("")),
+                    csv_field(event.correlation_id.as_deref().unwrap_or("")),
+                    event.created_at.to_rfc3339(),
+                );
+                Ok(())
+            }
+            ExportEncoder::Ndjson(out) => {
+                let line = serde_json::to_string(&event)
+                    .map_err(|e| HistoryError::SerializationError(e.to_string()))?;
+                out.push_str(&line);
+                out.push('\n');
+                Ok(())
+            }
+            ExportEncoder::Parquet(encoder) => encoder.push(event),
+            ExportEncoder::Json(out, written) => {
+                let object = serde_json::to_string(&EventResponse::from(event))
+                    .map_err(|e| HistoryError::SerializationError(e.to_string()))?;
+                if *written {
+                    out.push(',');
+                }
+                out.push_str(&object);
+                *written = true;
+                Ok(())
+            }
+        }
+    }
+
+    /// Bytes to send now, once at least `min_bytes` have built up
+    pub fn take_chunk(&mut self, min_bytes: usize) -> Option<Vec<u8>> {
+        match self {
+            ExportEncoder::Csv(out) | ExportEncoder::Ndjson(out) | ExportEncoder::Json(out, _)
+                if out.len() >= min_bytes =>
+            {
+                Some(std::mem::take(out).into_bytes())
+            }
+            ExportEncoder::Parquet(encoder) if encoder.buffered() >= min_bytes => Some(encoder.take()),
+            _ => None,
+        }
+    }
+
+    /// Everything not yet taken (for Parquet, including the footer)
+    pub fn finish(self) -> HistoryResult<Vec<u8>> {
+        match self {
+            ExportEncoder::Csv(out) | ExportEncoder::Ndjson(out) => Ok(out.into_bytes()),
+            ExportEncoder::Parquet(encoder) => encoder.finish(),
+            ExportEncoder::Json(mut out, _) => {
+                out.push_str(JSON_END);
+                Ok(out.into_bytes())
+            }
+        }
+    }
+}
+
+/// `?format=csv|ndjson|parquet|json` on the export endpoint (default csv)
+#[derive(Debug, Clone, Default, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct ExportQuery {
+    #[serde(default)]
+    pub format: ExportFormat,
+}
+
+/// Quote a CSV field if it needs it (RFC 4180: commas, quotes, newlines)
+pub fn csv_field(value: &str) -> Cow<'_, str> {
+    if value.contains([',', '"', '\n', '\r']) {
+        Cow::Owned(format!("\"{}\"", value.replace('"', "\"\"")))
+    } else {
+        Cow::Borrowed(value)
+    }
+}
+
+/// Whose history an export covers
+#[derive(Debug, Clone)]
+pub enum ExportScope {
+    Wallet(String),
+    /// Every wallet the user owns
+    User(String),
+}
+
+impl ExportScope {
+    fn id(&self) -> &str {
+        match self {
+            ExportScope::Wallet(id) | ExportScope::User(id) => id,
+        }
+    }
+
+    fn name(&self) -> &'static str {
+        match self {
+            ExportScope::Wallet(_) => "history",
+            ExportScope::User(_) => "activity",
+        }
+    }
+}
+
+/// `<id>-history.<ext>` (or `-activity` for a user), keeping only
+/// characters safe in a header
+pub fn export_filename(scope: &ExportScope, format: ExportFormat) -> String {
+    let safe: String = scope
+        .id()
+        .chars()
+        .filter(|c| c.is_ascii_alphanumeric() || *c == '-' || *c == '_')
+        .collect();
+    format!("{}-{}.{}", safe, scope.name(), format.extension())
+}
+
+/// Stream a wallet's (or user's) whole history, oldest first, as a download
+///
+/// Why stream?
+/// - A multi-year history can be millions of rows; the page endpoints cap at
+///   500 and building one big body would hold it all in memory
+/// - Here rows go from Postgres to the client a chunk at a time - a JSON
+///   export too: the array is written as the rows arrive, never built as a
+///   `Vec`
+///
+/// How it works:
+/// 1. A task reads the rows from the database as they arrive, encodes them
+///    and sends ~64KB chunks down a small channel
+/// 2. The response body is the other end of the channel, so a slow client
+///    slows the query down instead of us buffering for it
+/// 3. The client hanging up closes the channel and the task stops
+///
+/// The status and headers go out before the first row, so a database error
+/// part-way can't become a 500: the body is cut off instead, and the client
+/// sees a failed (incomplete) download.
+pub fn stream_history(
+    repository: EventRepository,
+    scope: ExportScope,
+    filter: HistoryFilter,
+    format: ExportFormat,
+) -> HistoryResult<Response> {
+    let filename = export_filename(&scope, format);
+    let mut encoder = ExportEncoder::new(format)?;
+    let (tx, rx) = mpsc::channel::<HistoryResult<Vec<u8>>>(CHUNKS_IN_FLIGHT);
+
+    tokio::spawn(async move {
+        let mut rows = match &scope {
+            ExportScope::Wallet(wallet_id) => repository.stream_wallet_history(wallet_id, &filter),
+            ExportScope::User(user_id) => repository.stream_user_activity(user_id, &filter),
+        };
+        let mut exported = 0u64;
+
+        while let Some(row) = rows.next().await {
+            match row.and_then(|event| encoder.push(event)) {
+                Ok(()) => {
+                    exported += 1;
+                    if let Some(chunk) = encoder.take_chunk(CHUNK_BYTES) {
+                        if tx.send(Ok(chunk)).await.is_err() {
+                            tracing::info!(scope = ?scope, exported, "Export cancelled by client");
+                            return;
+                        }
+                    }
+                }
+                Err(e) => {
+                    tracing::error!(scope = ?scope, exported, error = %e, "Export failed part-way");
+                    let _ = tx.send(Err(e)).await;
+                    return;
+                }
+            }
+        }
+        // Give the connection back before encoding the footer
+        drop(rows);
+
+        let _ = tx.send(encoder.finish()).await;
+        tracing::info!(scope = ?scope, exported, format = ?format, "Exported history");
+    });
+
+    let chunks = stream::unfold(rx, |mut rx| async move { rx.recv().await.map(|chunk| (chunk, rx)) });
+
+    Ok((
+        [
+            (header::CONTENT_TYPE, format.content_type().to_string()),
+            (
+                header::CONTENT_DISPOSITION,
+                format!("attachment; filename=\"{}\"", filename),
+            ),
+        ],
+        Body::from_stream(chunks),
+    )
+        .into_response())
+}
+
+/// Add `X-History-Truncated-Before` when part of the export is in cold storage
+pub fn flag_truncated(mut response: Response, horizon: Option<DateTime<Utc>>) -> Response {
+    if let Some(horizon) = horizon {
+        if let Ok(value) = HeaderValue::from_str(&horizon.to_rfc3339()) {
+            response.headers_mut().insert(HISTORY_TRUNCATED_HEADER, value);
+        }
+    }
+    response
+}
@@ -0,0 +1,131 @@
+use crate::errors::{HistoryError, HistoryResult};
+use chrono::{DateTime, Utc};
+use serde::Deserialize;
+use utoipa::IntoParams;
+
+/// Event types as stored in transaction_events (transfers, round-ups and
+/// pot moves are split into their two legs; adjustments by direction)
+pub const STORED_EVENT_TYPES: &[&str] = &[
+    "WALLET_CREATED",
+    "WALLET_FUNDED",
+    "VOUCHER_REDEEMED",
+    "TRANSFER_OUT",
+    "TRANSFER_IN",
+    "ROUND_UP_OUT",
+    "ROUND_UP_IN",
+    "POT_TRANSFER_OUT",
+    "POT_TRANSFER_IN",
+    "ADJUSTMENT_OUT",
+    "ADJUSTMENT_IN",
+];
+
+/// `?from=&to=&event_type=` on the history endpoints
+///
+/// - `from` inclusive, `to` exclusive (RFC 3339)
+/// - `event_type`: one type or several, comma-separated
+///   (e.g. `TRANSFER_IN,TRANSFER_OUT` for "transfers")
+///
+/// All of it goes into the WHERE clause - nothing is filtered in memory,
+/// so pagination still returns full pages.
+#[derive(Debug, Clone, Default, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct HistoryFilter {
+    pub from: Option<DateTime<Utc>>,
+    pub to: Option<DateTime<Utc>>,
+    pub event_type: Option<String>,
+}
+
+impl HistoryFilter {
+    /// Check the window and the types (unknown types are a 400, not an
+    /// empty page)
+    pub fn validate(&self) -> HistoryResult<()> {
+        if let (Some(from), Some(to)) = (self.from, self.to) {
+            if from >= to {
+                return Err(HistoryError::InvalidRequest(
+                    "from must be before to".to_string(),
+                ));
+            }
+        }
+        for event_type in self.event_types().into_iter().flatten() {
+            if !STORED_EVENT_TYPES.contains(&event_type.as_str()) {
+                return Err(HistoryError::InvalidRequest(format!(
+                    "unknown event_type {} (expected one of {})",
+                    event_type,
+                    STORED_EVENT_TYPES.join(", ")
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    /// The requested types, upper-cased (None = all types)
+    pub fn event_types(&self) -> Option<Vec<String>> {
+        let types: Vec<String> = self
+            .event_type
+            .as_deref()?
+            .split(',')
+            .map(|t| t.trim().to_ascii_uppercase())
+            .filter(|t| !t.is_empty())
+            .collect();
+        (!types.is_empty()).then_some(types)
+    }
+
+    /// Appended to the page's cache key
+    pub fn cache_key(&self) -> String {
+        format!(
+            "&from={}&to={}&event_type={}",
+            self.from.map(|t| t.to_rfc3339()).unwrap_or_default(),
+            self.to.map(|t| t.to_rfc3339()).unwrap_or_default(),
+            self.event_types().map(|t| t.join(",")).unwrap_or_default()
+        )
+    }
+}
+
+/// Most wallets one `?wallet_ids=` may name
+pub const MAX_WALLET_IDS: usize = 50;
+
+/// `?wallet_ids=a,b,c` on the user activity endpoint: only these wallets
+///
+/// Goes into the WHERE clause next to the user ID, so wallets the user
+/// doesn't own simply match nothing - no need to check ownership first.
+#[derive(Debug, Clone, Default, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct WalletSelection {
+    pub wallet_ids: Option<String>,
+}
+
+impl WalletSelection {
+    pub fn validate(&self) -> HistoryResult<()> {
+        let count = self.wallet_ids().map_or(0, |ids| ids.len());
+        if count > MAX_WALLET_IDS {
+            return Err(HistoryError::InvalidRequest(format!(
+                "wallet_ids names {} wallets (at most {})",
+                count, MAX_WALLET_IDS
+            )));
+        }
+        Ok(())
+    }
+
+    /// The selected wallets, sorted and deduplicated (None = all wallets)
+    pub fn wallet_ids(&self) -> Option<Vec<String>> {
+        let mut ids: Vec<String> = self
+            .wallet_ids
+            .as_deref()?
+            .split(',')
+            .map(|id| id.trim().to_string())
+            .filter(|id| !id.is_empty())
+            .collect();
+        ids.sort();
+        ids.dedup();
+        (!ids.is_empty()).then_some(ids)
+    }
+
+    /// Appended to the page's cache key
+    pub fn cache_key(&self) -> String {
+        format!(
+            "&wallet_ids={}",
+            self.wallet_ids().map(|ids| ids.join(",")).unwrap_or_default()
+        )
+    }
+}
+
@@ -0,0 +1,290 @@
+use crate::auth::{self, Principal};
+use crate::balance::BalanceAsOf;
+use crate::errors::HistoryError;
+use crate::filter::{HistoryFilter, WalletSelection};
+use crate::handlers::{self, AppState};
+use crate::models::{EventResponse, TransactionEvent};
+use crate::pagination::{Page, PageParams};
+use crate::summary::{PeriodSummary, SummaryPeriod};
+use async_graphql::http::GraphiQLSource;
+use async_graphql::{
+    Context, EmptyMutation, EmptySubscription, ErrorExtensions, InputObject, Object, Schema,
+    SchemaBuilder, SimpleObject,
+};
+use axum::{response::Html, Extension, Json};
+use chrono::{DateTime, Utc};
+
+/// Deepest query accepted (`user { wallets { events { items { id } } } }` is 5)
+pub const MAX_DEPTH: usize = 8;
+
+/// Most fields one query may select, counting each nested field once
+pub const MAX_COMPLEXITY: usize = 250;
+
+pub type HistorySchema = Schema<QueryRoot, EmptyMutation, EmptySubscription>;
+
+/// The schema with its limits, before the state is attached
+///
+/// Why limits?
+/// - Every `wallets { ... }` field is more queries per wallet; without a cap
+///   one request could fan out into thousands
+pub fn schema_builder() -> SchemaBuilder<QueryRoot, EmptyMutation, EmptySubscription> {
+    Schema::build(QueryRoot, EmptyMutation, EmptySubscription)
+        .limit_depth(MAX_DEPTH)
+        .limit_complexity(MAX_COMPLEXITY)
+}
+
+pub fn build_schema(state: AppState) -> HistorySchema {
+    schema_builder().data(state).finish()
+}
+
+/// POST /graphql
+///
+/// Takes and answers plain JSON (one query per request, no batches). The
+/// caller's `Principal` (if auth is on) goes along as request data, so
+/// `user` / `wallet` only return the caller's own.
+pub async fn graphql_handler(
+    Extension(schema): Extension<HistorySchema>,
+    principal: Option<Extension<Principal>>,
+    Json(mut request): Json<async_graphql::Request>,
+) -> Json<async_graphql::Response> {
+    if let Some(Extension(principal)) = principal {
+        request = request.data(principal);
+    }
+    Json(schema.execute(request).await)
+}
+
+/// GET /graphql - GraphiQL, to try queries in a browser (scripts come from a CDN)
+pub async fn graphiql() -> Html<String> {
+    Html(GraphiQLSource::build().endpoint("/graphql").finish())
+}
+
+/// Read-only queries over the history, one round trip per screen
+///
+/// ```graphql
+/// {
+///   user(id: "user-1") {
+///     wallets {
+///       id
+///       balance { balance openSequenceGaps }
+///       summary(period: MONTH) { periodStart totalIn totalOut }
+///       events(first: 5, filter: { eventTypes: ["TRANSFER_IN"] }) {
+///         items { amount createdAt }
+///         nextCursor
+///       }
+///     }
+///   }
+/// }
+/// ```
+///
+/// Only the fields asked for are resolved, so a query without `summary`
+/// never runs the summary query. Resolvers go through the same helpers
+/// (and cache entries) as the REST endpoints.
+pub struct QueryRoot;
+
+#[Object]
+impl QueryRoot {
+    /// A user and whatever of their history is selected (only yourself)
+    async fn user(&self, ctx: &Context<'_>, id: String) -> async_graphql::Result<User> {
+        if let Some(principal) = ctx.data_opt::<Principal>() {
+            auth::check_user(principal, &id).map_err(graphql_error)?;
+        }
+        Ok(User { id })
+    }
+
+    /// One wallet (or pot) by ID (only your own)
+    async fn wallet(&self, ctx: &Context<'_>, id: String) -> async_graphql::Result<Wallet> {
+        if let Some(principal) = ctx.data_opt::<Principal>() {
+            let state = ctx.data::<AppState>()?;
+            auth::check_wallet(state, principal, &id)
+                .await
+                .map_err(graphql_error)?;
+        }
+        Ok(Wallet { id })
+    }
+}
+
+pub struct User {
+    id: String,
+}
+
+#[Object]
+impl User {
+    async fn id(&self) -> &str {
+        &self.id
+    }
+
+    /// Wallets with events in the history, pots included, oldest first
+    async fn wallets(&self, ctx: &Context<'_>) -> async_graphql::Result<Vec<Wallet>> {
+        let state = ctx.data::<AppState>()?;
+        let wallet_ids = state
+            .repository
+            .get_user_wallet_ids(&self.id)
+            .await
+            .map_err(graphql_error)?;
+
+        Ok(wallet_ids.into_iter().map(|id| Wallet { id }).collect())
+    }
+
+    /// Events across the user's wallets (or just `walletIds`), newest first
+    async fn activity(
+        &self,
+        ctx: &Context<'_>,
+        filter: Option<EventFilter>,
+        wallet_ids: Option<Vec<String>>,
+        first: Option<i64>,
+        after: Option<String>,
+    ) -> async_graphql::Result<EventPage> {
+        let (params, filter) = page_args(filter, first, after)?;
+        let wallets = WalletSelection {
+            wallet_ids: wallet_ids.map(|ids| ids.join(",")),
+        };
+        wallets.validate().map_err(graphql_error)?;
+
+        let state = ctx.data::<AppState>()?;
+        let page = handlers::user_activity_page(state, &self.id, &params, &filter, &wallets)
+            .await
+            .map_err(graphql_error)?;
+        Ok(EventPage::from(page))
+    }
+
+    /// Money in/out per period across the user's wallets (or just
+    /// `walletIds`), newest first
+    async fn summary(
+        &self,
+        ctx: &Context<'_>,
+        #[graphql(default)] period: SummaryPeriod,
+        filter: Option<EventFilter>,
+        wallet_ids: Option<Vec<String>>,
+    ) -> async_graphql::Result<Vec<PeriodSummary>> {
+        let filter = HistoryFilter::from(filter.unwrap_or_default());
+        let wallets = WalletSelection {
+            wallet_ids: wallet_ids.map(|ids| ids.join(",")),
+        };
+
+        let state = ctx.data::<AppState>()?;
+        handlers::user_summary_periods(state, &self.id, period, &filter, &wallets)
+            .await
+            .map_err(graphql_error)
+    }
+}
+
+pub struct Wallet {
+    id: String,
+}
+
+#[Object]
+impl Wallet {
+    async fn id(&self) -> &str {
+        &self.id
+    }
+
+    /// The wallet's events, newest first (`first` defaults to 50, at most 500)
+    async fn events(
+        &self,
+        ctx: &Context<'_>,
+        filter: Option<EventFilter>,
+        first: Option<i64>,
+        after: Option<String>,
+    ) -> async_graphql::Result<EventPage> {
+        let (params, filter) = page_args(filter, first, after)?;
+
+        let state = ctx.data::<AppState>()?;
+        let page = handlers::wallet_history_page(state, &self.id, &params, &filter)
+            .await
+            .map_err(graphql_error)?;
+        Ok(EventPage::from(page))
+    }
+
+    /// Money in/out per period, newest first
+    async fn summary(
+        &self,
+        ctx: &Context<'_>,
+        #[graphql(default)] period: SummaryPeriod,
+        filter: Option<EventFilter>,
+    ) -> async_graphql::Result<Vec<PeriodSummary>> {
+        let filter = HistoryFilter::from(filter.unwrap_or_default());
+        filter.validate().map_err(graphql_error)?;
+
+        let state = ctx.data::<AppState>()?;
+        handlers::wallet_summary_periods(state, &self.id, period, &filter)
+            .await
+            .map_err(graphql_error)
+    }
+
+    /// Balance at `at` (default now), rebuilt from the events
+    async fn balance(
+        &self,
+        ctx: &Context<'_>,
+        at: Option<DateTime<Utc>>,
+    ) -> async_graphql::Result<BalanceAsOf> {
+        let state = ctx.data::<AppState>()?;
+        handlers::balance_as_of(state, self.id.clone(), at.unwrap_or_else(Utc::now))
+            .await
+            .map_err(graphql_error)
+    }
+}
+
+/// Same filters as `?from=&to=&event_type=` on the REST endpoints
+#[derive(Debug, Clone, Default, InputObject)]
+pub struct EventFilter {
+    /// Inclusive
+    pub from: Option<DateTime<Utc>>,
+    /// Exclusive
+    pub to: Option<DateTime<Utc>>,
+    /// Stored types, e.g. ["TRANSFER_IN", "TRANSFER_OUT"] (absent = all)
+    pub event_types: Option<Vec<String>>,
+}
+
+impl From<EventFilter> for HistoryFilter {
+    fn from(filter: EventFilter) -> Self {
+        HistoryFilter {
+            from: filter.from,
+            to: filter.to,
+            event_type: filter.event_types.map(|types| types.join(",")),
+        }
+    }
+}
+
+/// One page of events; pass `nextCursor` as `after` for the next one
+#[derive(Debug, SimpleObject)]
+pub struct EventPage {
+    pub items: Vec<EventResponse>,
+    /// Null on the last page
+    pub next_cursor: Option<String>,
+}
+
+impl From<Page<TransactionEvent>> for EventPage {
+    fn from(page: Page<TransactionEvent>) -> Self {
+        Self {
+            items: page.items.into_iter().map(EventResponse::from).collect(),
+            next_cursor: page.next_cursor,
+        }
+    }
+}
+
+/// Page arguments as the REST query parameters, checked before any query runs
+fn page_args(
+    filter: Option<EventFilter>,
+    first: Option<i64>,
+    after: Option<String>,
+) -> async_graphql::Result<(PageParams, HistoryFilter)> {
+    let params = PageParams { limit: first, cursor: after };
+    let filter = HistoryFilter::from(filter.unwrap_or_default());
+    params.limit().map_err(graphql_error)?;
+    params.cursor().map_err(graphql_error)?;
+    filter.validate().map_err(graphql_error)?;
+    Ok((params, filter))
+}
+
+/// A GraphQL error with the same message the REST API would send, and the
+/// status as `extensions.code` (BAD_REQUEST, NOT_FOUND, INTERNAL_SERVER_ERROR)
+pub fn graphql_error(e: HistoryError) -> async_graphql::Error {
+    let (status, message) = e.public_parts();
+    let code = status
+        .canonical_reason()
+        .unwrap_or("Internal Server Error")
+        .to_ascii_uppercase()
+        .replace(' ', "_");
+
+    async_graphql::Error::new(message).extend_with(|_, extensions| extensions.set("code", code))
+}
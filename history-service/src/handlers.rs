@@ -1,79 +1,631 @@
-use crate::errors::HistoryResult;
-use crate::models::{ApiResponse, EventResponse};
+use crate::balance::{BalanceAsOf, BalanceQuery};
+use crate::cache::{CacheScope, CacheStats, ResponseCache};
+use crate::cold_storage::EventArchive;
+use crate::control::{ConsumerControl, ConsumerStatus, PartitionSeekResult, SeekRequest};
+use crate::db_pool;
+use crate::errors::{ErrorResponse, HistoryError, HistoryResult};
+use crate::export::{self, ExportQuery, ExportScope};
+use crate::filter::{HistoryFilter, WalletSelection};
+use crate::health::{self, HealthReport, HealthStatus};
+use crate::metrics::{ConsumerLag, OPENMETRICS_CONTENT_TYPE};
+use crate::models::{ApiResponse, EventResponse, TransactionEvent};
+use crate::pagination::{Page, PageParams};
+use crate::replay::{EventReplayer, ReplayReport, ReplayRequest};
 use crate::repository::EventRepository;
+use crate::sequence::{SequenceGap, WalletSequenceStatus};
+use crate::summary::{
+    summarize, whole_days, PeriodSummary, SummaryPeriod, SummaryQuery, UserSummary, WalletSummary,
+};
 use axum::{
-    extract::{Path, State},
-    http::StatusCode,
+    extract::{Path, Query, State},
+    http::{header, StatusCode},
+    response::Response,
     Json,
 };
+use chrono::{DateTime, Utc};
+use std::sync::Arc;
 
 #[derive(Clone)]
 pub struct AppState {
     pub repository: EventRepository,
+    pub cache: Arc<ResponseCache>,
+    pub consumer_control: Arc<ConsumerControl>,
+    pub consumer_lag: Arc<ConsumerLag>,
+    pub replayer: Arc<EventReplayer>,
 }
 
 /// Get transaction history for a specific wallet
 /// 
-/// Returns all events affecting this wallet in reverse chronological order
+/// Returns events affecting this wallet in reverse chronological order,
+/// one page at a time (`?limit=50&cursor=...`), optionally only from a time
+/// window or of some types (`?from=&to=&event_type=`)
 /// 
 /// Example response:
-/// [
-///   {
-///     "event_type": "TRANSFER_IN",
-///     "amount": "30.0000",
-///     "created_at": "2025-01-29T10:30:00Z"
-///   },
-///   {
-///     "event_type": "WALLET_FUNDED",
-///     "amount": "100.0000",
-///     "created_at": "2025-01-29T10:00:00Z"
-///   }
-/// ]
+/// {
+///   "data": [
+///     {"event_type": "TRANSFER_IN", "amount": "30.0000", "created_at": "2025-01-29T10:30:00Z"},
+///     {"event_type": "WALLET_FUNDED", "amount": "100.0000", "created_at": "2025-01-29T10:00:00Z"}
+///   ],
+///   "next_cursor": "3137..."   // absent on the last page
+/// }
+#[utoipa::path(
+    get,
+    path = "/wallets/{wallet_id}/history",
+    tag = "history",
+    params(("wallet_id" = String, Path, description = "Wallet ID"), PageParams, HistoryFilter),
+    responses(
+        (status = 200, description = "One page, newest first; next_cursor is absent on the last page", body = ApiResponse<Vec<EventResponse>>),
+        (status = 400, description = "Invalid limit, cursor or filter", body = ErrorResponse)
+    )
+)]
 pub async fn get_wallet_history(
     State(state): State<AppState>,
     Path(wallet_id): Path<String>,
+    Query(params): Query<PageParams>,
+    Query(filter): Query<HistoryFilter>,
 ) -> HistoryResult<Json<ApiResponse<Vec<EventResponse>>>> {
     tracing::debug!(wallet_id = %wallet_id, "Fetching wallet history");
+    let page = wallet_history_page(&state, &wallet_id, &params, &filter).await?;
+    let truncated = history_truncated_before(&state, filter.from).await?;
 
-    let events = state.repository.get_wallet_history(&wallet_id).await?;
-
-    if events.is_empty() {
-        tracing::info!(wallet_id = %wallet_id, "No events found for wallet");
-    }
-
-    let response: Vec<EventResponse> = events
+    let response: Vec<EventResponse> = page
+        .items
         .into_iter()
         .map(EventResponse::from)
         .collect();
 
-    Ok(Json(ApiResponse::success(response)))
+    Ok(Json(ApiResponse::page(response, page.next_cursor).truncated_before(truncated)))
+}
+
+/// Download a wallet's whole history (`?format=csv|ndjson|parquet|json`), oldest first
+///
+/// Takes the same `from`/`to`/`event_type` filters as the history page, but
+/// no limit - rows are streamed (see export.rs) and never cached.
+#[utoipa::path(
+    get,
+    path = "/wallets/{wallet_id}/history/export",
+    tag = "export",
+    params(("wallet_id" = String, Path, description = "Wallet ID"), ExportQuery, HistoryFilter),
+    responses(
+        (status = 200, description = "The file, streamed (text/csv, application/x-ndjson, application/vnd.apache.parquet or application/json)"),
+        (status = 400, description = "Invalid limit, cursor or filter", body = ErrorResponse)
+    )
+)]
+pub async fn export_wallet_history(
+    State(state): State<AppState>,
+    Path(wallet_id): Path<String>,
+    Query(export): Query<ExportQuery>,
+    Query(filter): Query<HistoryFilter>,
+) -> HistoryResult<Response> {
+    filter.validate()?;
+    tracing::info!(wallet_id = %wallet_id, format = ?export.format, "Exporting wallet history");
+
+    let truncated = history_truncated_before(&state, filter.from).await?;
+
+    let response = export::stream_history(
+        state.repository.clone(),
+        ExportScope::Wallet(wallet_id),
+        filter,
+        export.format,
+    )?;
+    Ok(export::flag_truncated(response, truncated))
+}
+
+/// Download all of a user's activity, every wallet, oldest first (same
+/// formats and filters as the wallet export)
+#[utoipa::path(
+    get,
+    path = "/users/{user_id}/activity/export",
+    tag = "export",
+    params(("user_id" = String, Path, description = "User ID"), ExportQuery, HistoryFilter),
+    responses(
+        (status = 200, description = "The file, streamed (text/csv, application/x-ndjson, application/vnd.apache.parquet or application/json)"),
+        (status = 400, description = "Invalid limit, cursor or filter", body = ErrorResponse)
+    )
+)]
+pub async fn export_user_activity(
+    State(state): State<AppState>,
+    Path(user_id): Path<String>,
+    Query(export): Query<ExportQuery>,
+    Query(filter): Query<HistoryFilter>,
+) -> HistoryResult<Response> {
+    filter.validate()?;
+    tracing::info!(user_id = %user_id, format = ?export.format, "Exporting user activity");
+
+    let truncated = history_truncated_before(&state, filter.from).await?;
+
+    let response = export::stream_history(
+        state.repository.clone(),
+        ExportScope::User(user_id),
+        filter,
+        export.format,
+    )?;
+    Ok(export::flag_truncated(response, truncated))
 }
 
 /// Get all activity for a specific user
 /// 
-/// Returns events across ALL wallets owned by this user
+/// Returns events across ALL wallets owned by this user, paginated like
+/// the wallet history
 /// Useful for showing "My Activity" page in a mobile app
+/// `?wallet_ids=a,b` narrows it to some of the user's wallets
+#[utoipa::path(
+    get,
+    path = "/users/{user_id}/activity",
+    tag = "history",
+    params(("user_id" = String, Path, description = "User ID"), PageParams, HistoryFilter, WalletSelection),
+    responses(
+        (status = 200, description = "One page of the user's events across wallets", body = ApiResponse<Vec<EventResponse>>),
+        (status = 400, description = "Invalid limit, cursor or filter", body = ErrorResponse)
+    )
+)]
 pub async fn get_user_activity(
     State(state): State<AppState>,
     Path(user_id): Path<String>,
+    Query(params): Query<PageParams>,
+    Query(filter): Query<HistoryFilter>,
+    Query(wallets): Query<WalletSelection>,
 ) -> HistoryResult<Json<ApiResponse<Vec<EventResponse>>>> {
     tracing::debug!(user_id = %user_id, "Fetching user activity");
+    let page = user_activity_page(&state, &user_id, &params, &filter, &wallets).await?;
+    let truncated = history_truncated_before(&state, filter.from).await?;
 
-    let events = state.repository.get_user_activity(&user_id).await?;
-
-    if events.is_empty() {
-        tracing::info!(user_id = %user_id, "No activity found for user");
-    }
-
-    let response: Vec<EventResponse> = events
+    let response: Vec<EventResponse> = page
+        .items
         .into_iter()
         .map(EventResponse::from)
         .collect();
 
-    Ok(Json(ApiResponse::success(response)))
+    Ok(Json(ApiResponse::page(response, page.next_cursor).truncated_before(truncated)))
+}
+
+/// Money in/out, net change and event counts per day/week/month/year
+///
+/// Grouped in the database, so a client showing "spent this month" doesn't
+/// page through raw events. Takes the history filters too.
+#[utoipa::path(
+    get,
+    path = "/wallets/{wallet_id}/summary",
+    tag = "history",
+    params(("wallet_id" = String, Path, description = "Wallet ID"), SummaryQuery, HistoryFilter),
+    responses(
+        (status = 200, description = "Totals per period, newest first", body = ApiResponse<WalletSummary>),
+        (status = 400, description = "Invalid limit, cursor or filter", body = ErrorResponse)
+    )
+)]
+pub async fn get_wallet_summary(
+    State(state): State<AppState>,
+    Path(wallet_id): Path<String>,
+    Query(query): Query<SummaryQuery>,
+    Query(filter): Query<HistoryFilter>,
+) -> HistoryResult<Json<ApiResponse<WalletSummary>>> {
+    let periods = wallet_summary_periods(&state, &wallet_id, query.period, &filter).await?;
+    let truncated = summary_truncated_before(&state, &filter).await?;
+
+    Ok(Json(
+        ApiResponse::success(WalletSummary {
+            wallet_id,
+            period: query.period,
+            periods,
+        })
+        .truncated_before(truncated),
+    ))
+}
+
+/// A user's totals per period across their wallets
+///
+/// Like the wallet summary; `?wallet_ids=a,b` narrows it to some of the
+/// user's wallets.
+#[utoipa::path(
+    get,
+    path = "/users/{user_id}/summary",
+    tag = "history",
+    params(("user_id" = String, Path, description = "User ID"), SummaryQuery, HistoryFilter, WalletSelection),
+    responses(
+        (status = 200, description = "Totals per period, newest first", body = ApiResponse<UserSummary>),
+        (status = 400, description = "Invalid filter or wallet_ids", body = ErrorResponse)
+    )
+)]
+pub async fn get_user_summary(
+    State(state): State<AppState>,
+    Path(user_id): Path<String>,
+    Query(query): Query<SummaryQuery>,
+    Query(filter): Query<HistoryFilter>,
+    Query(wallets): Query<WalletSelection>,
+) -> HistoryResult<Json<ApiResponse<UserSummary>>> {
+    let periods = user_summary_periods(&state, &user_id, query.period, &filter, &wallets).await?;
+    let truncated = summary_truncated_before(&state, &filter).await?;
+
+    Ok(Json(
+        ApiResponse::success(UserSummary {
+            user_id,
+            period: query.period,
+            periods,
+        })
+        .truncated_before(truncated),
+    ))
+}
+
+/// A wallet's balance at `?at=` (default now), summed from its events
+///
+/// For audits and disputes; check `open_sequence_gaps` before trusting it.
+#[utoipa::path(
+    get,
+    path = "/wallets/{wallet_id}/balance",
+    tag = "history",
+    params(("wallet_id" = String, Path, description = "Wallet ID"), BalanceQuery),
+    responses(
+        (status = 200, description = "The balance at `at`, rebuilt from events", body = ApiResponse<BalanceAsOf>)
+    )
+)]
+pub async fn get_balance_at(
+    State(state): State<AppState>,
+    Path(wallet_id): Path<String>,
+    Query(query): Query<BalanceQuery>,
+) -> HistoryResult<Json<ApiResponse<BalanceAsOf>>> {
+    let balance = balance_as_of(&state, wallet_id, query.at.unwrap_or_else(Utc::now)).await?;
+    Ok(Json(ApiResponse::success(balance)))
+}
+
+/// A wallet's event numbering: highest sequence stored, numbers missing
+#[utoipa::path(
+    get,
+    path = "/wallets/{wallet_id}/sequence",
+    tag = "consistency",
+    params(("wallet_id" = String, Path, description = "Wallet ID")),
+    responses(
+        (status = 200, description = "Highest sequence stored and open gaps", body = ApiResponse<WalletSequenceStatus>)
+    )
+)]
+pub async fn get_wallet_sequence(
+    State(state): State<AppState>,
+    Path(wallet_id): Path<String>,
+) -> HistoryResult<Json<ApiResponse<WalletSequenceStatus>>> {
+    let status = state.repository.get_wallet_sequence(&wallet_id).await?;
+    Ok(Json(ApiResponse::success(status)))
+}
+
+/// Events missing from the read model, across all wallets (oldest 500)
+///
+/// Gaps still open after the retry tiers (~11 minutes) usually mean a
+/// dead-lettered or lost event - replay it, or re-ingest the window with
+/// POST /admin/consumer/seek.
+#[utoipa::path(
+    get,
+    path = "/admin/sequence-gaps",
+    tag = "consistency",
+    responses(
+        (status = 200, description = "Open gaps, oldest first", body = ApiResponse<Vec<SequenceGap>>)
+    )
+)]
+pub async fn get_sequence_gaps(
+    State(state): State<AppState>,
+) -> HistoryResult<Json<ApiResponse<Vec<SequenceGap>>>> {
+    let gaps = state.repository.get_open_sequence_gaps(500).await?;
+    Ok(Json(ApiResponse::success(gaps)))
+}
+
+/// Months of events moved to cold storage, oldest first
+///
+/// Each row says where the file is and the SHA-256 it must have - what's
+/// needed to restore or audit a month no longer in Postgres.
+#[utoipa::path(
+    get,
+    path = "/admin/event-archives",
+    tag = "admin",
+    responses(
+        (status = 200, description = "Archived months, oldest first", body = ApiResponse<Vec<EventArchive>>)
+    )
+)]
+pub async fn get_event_archives(
+    State(state): State<AppState>,
+) -> HistoryResult<Json<ApiResponse<Vec<EventArchive>>>> {
+    let archives = state.repository.get_event_archives().await?;
+    Ok(Json(ApiResponse::success(archives)))
+}
+
+/// Republish or reprocess the events stored in a time window
+///
+/// One page per call (at most 10 000 events): repeat with `from` set to the
+/// report's `next_from` until it's null.
+#[utoipa::path(
+    post,
+    path = "/admin/replay",
+    tag = "admin",
+    request_body = ReplayRequest,
+    responses(
+        (status = 200, description = "What was replayed; resend with next_from for more", body = ApiResponse<ReplayReport>),
+        (status = 400, description = "Invalid window or limit", body = ErrorResponse)
+    )
+)]
+pub async fn replay_events(
+    State(state): State<AppState>,
+    Json(request): Json<ReplayRequest>,
+) -> HistoryResult<Json<ApiResponse<ReplayReport>>> {
+    let report = state.replayer.replay(&request).await?;
+    Ok(Json(ApiResponse::success(report)))
+}
+
+/// Response cache hit/miss counters
+#[utoipa::path(
+    get,
+    path = "/cache/stats",
+    tag = "service",
+    responses(
+        (status = 200, description = "Response cache counters", body = ApiResponse<CacheStats>)
+    )
+)]
+pub async fn get_cache_stats(State(state): State<AppState>) -> Json<ApiResponse<CacheStats>> {
+    Json(ApiResponse::success(state.cache.stats()))
+}
+
+/// Whether the Kafka consumers are paused
+#[utoipa::path(
+    get,
+    path = "/admin/consumer",
+    tag = "admin",
+    responses(
+        (status = 200, description = "Whether the consumers are paused", body = ApiResponse<ConsumerStatus>)
+    )
+)]
+pub async fn get_consumer_status(State(state): State<AppState>) -> Json<ApiResponse<ConsumerStatus>> {
+    Json(ApiResponse::success(state.consumer_control.status()))
+}
+
+/// Pause the Kafka consumers (history stops updating; queries keep working)
+#[utoipa::path(
+    post,
+    path = "/admin/consumer/pause",
+    tag = "admin",
+    responses(
+        (status = 200, description = "Paused", body = ApiResponse<ConsumerStatus>)
+    )
+)]
+pub async fn pause_consumer(State(state): State<AppState>) -> Json<ApiResponse<ConsumerStatus>> {
+    Json(ApiResponse::success(state.consumer_control.pause()))
+}
+
+/// Resume the Kafka consumers from where they stopped
+#[utoipa::path(
+    post,
+    path = "/admin/consumer/resume",
+    tag = "admin",
+    responses(
+        (status = 200, description = "Running", body = ApiResponse<ConsumerStatus>)
+    )
+)]
+pub async fn resume_consumer(State(state): State<AppState>) -> Json<ApiResponse<ConsumerStatus>> {
+    Json(ApiResponse::success(state.consumer_control.resume()))
+}
+
+/// Move the main-topic consumer to offsets or a timestamp (re-ingest a window)
+#[utoipa::path(
+    post,
+    path = "/admin/consumer/seek",
+    tag = "admin",
+    request_body = SeekRequest,
+    responses(
+        (status = 200, description = "Where each partition now reads from", body = ApiResponse<Vec<PartitionSeekResult>>),
+        (status = 400, description = "Invalid seek", body = ErrorResponse)
+    )
+)]
+pub async fn seek_consumer(
+    State(state): State<AppState>,
+    Json(request): Json<SeekRequest>,
+) -> HistoryResult<Json<ApiResponse<Vec<PartitionSeekResult>>>> {
+    let results = state.consumer_control.seek(&request).await?;
+    Ok(Json(ApiResponse::success(results)))
+}
+
+/// Consumer lag per partition and pool usage in OpenMetrics format
+#[utoipa::path(
+    get,
+    path = "/metrics",
+    tag = "service",
+    responses(
+        (status = 200, description = "Consumer lag per partition and pool gauges", content_type = "application/openmetrics-text")
+    )
+)]
+pub async fn get_metrics(
+    State(state): State<AppState>,
+) -> ([(header::HeaderName, &'static str); 1], String) {
+    // Pool gauges first - the lag render ends with "# EOF"
+    let body = db_pool::render_metrics(&state.repository.pool_stats()) + &state.consumer_lag.render();
+
+    ([(header::CONTENT_TYPE, OPENMETRICS_CONTENT_TYPE)], body)
 }
 
 /// Health check endpoint
-pub async fn health_check() -> (StatusCode, &'static str) {
-    (StatusCode::OK, "OK")
+///
+/// Checks the database, migrations and Kafka (see `health::check`):
+/// - 200 "healthy": everything is up
+/// - 200 "degraded": history is served but may be stale - Kafka is
+///   unreachable or the consumer is paused
+/// - 503 "unhealthy": the database is down or the schema is behind
+#[utoipa::path(
+    get,
+    path = "/health",
+    tag = "service",
+    responses(
+        (status = 200, description = "Healthy or degraded, with per-dependency checks", body = HealthReport),
+        (status = 503, description = "Unhealthy - a critical dependency is down", body = HealthReport)
+    )
+)]
+pub async fn health_check(State(state): State<AppState>) -> (StatusCode, Json<HealthReport>) {
+    let report = health::check(&state.repository, &state.replayer, &state.consumer_control).await;
+    let status = match report.status {
+        HealthStatus::Unhealthy => StatusCode::SERVICE_UNAVAILABLE,
+        HealthStatus::Healthy | HealthStatus::Degraded => StatusCode::OK,
+    };
+
+    (status, Json(report))
+}
+
+// Shared with the GraphQL resolvers (graphql.rs), so both APIs validate the
+// same way and read through the same cache entries
+
+/// One page of a wallet's history, from the cache if it's there
+pub(crate) async fn wallet_history_page(
+    state: &AppState,
+    wallet_id: &str,
+    params: &PageParams,
+    filter: &HistoryFilter,
+) -> HistoryResult<Page<TransactionEvent>> {
+    let limit = params.limit()?;
+    let cursor = params.cursor()?;
+    filter.validate()?;
+
+    let scope = CacheScope::Wallet(wallet_id.to_string());
+    let query = params.cache_key("history") + &filter.cache_key();
+    if let Some(page) = state.cache.get::<Page<TransactionEvent>>(&scope, &query) {
+        return Ok(page);
+    }
+
+    let page = state
+        .repository
+        .get_wallet_history(wallet_id, filter, cursor.as_ref(), limit)
+        .await?;
+    if page.items.is_empty() && cursor.is_none() {
+        tracing::info!(wallet_id = %wallet_id, "No events found for wallet");
+    }
+    state.cache.insert(scope, &query, page.clone());
+    Ok(page)
+}
+
+/// One page of a user's activity across wallets, from the cache if it's there
+pub(crate) async fn user_activity_page(
+    state: &AppState,
+    user_id: &str,
+    params: &PageParams,
+    filter: &HistoryFilter,
+    wallets: &WalletSelection,
+) -> HistoryResult<Page<TransactionEvent>> {
+    let limit = params.limit()?;
+    let cursor = params.cursor()?;
+    filter.validate()?;
+    wallets.validate()?;
+
+    let scope = CacheScope::User(user_id.to_string());
+    let query = params.cache_key("activity") + &filter.cache_key() + &wallets.cache_key();
+    if let Some(page) = state.cache.get::<Page<TransactionEvent>>(&scope, &query) {
+        return Ok(page);
+    }
+
+    let page = state
+        .repository
+        .get_user_activity(user_id, filter, wallets, cursor.as_ref(), limit)
+        .await?;
+    if page.items.is_empty() && cursor.is_none() {
+        tracing::info!(user_id = %user_id, "No activity found for user");
+    }
+    state.cache.insert(scope, &query, page.clone());
+    Ok(page)
+}
+
+/// A wallet's totals per period, from the cache if they're there
+pub(crate) async fn wallet_summary_periods(
+    state: &AppState,
+    wallet_id: &str,
+    period: SummaryPeriod,
+    filter: &HistoryFilter,
+) -> HistoryResult<Vec<PeriodSummary>> {
+    filter.validate()?;
+
+    let scope = CacheScope::Wallet(wallet_id.to_string());
+    let key = format!("summary?period={}{}", period.as_sql(), filter.cache_key());
+    if let Some(periods) = state.cache.get::<Vec<PeriodSummary>>(&scope, &key) {
+        return Ok(periods);
+    }
+
+    let rows = state.repository.get_wallet_summary(wallet_id, period, filter).await?;
+    let periods = summarize(rows);
+    state.cache.insert(scope, &key, periods.clone());
+    Ok(periods)
+}
+
+/// A user's totals per period, from the cache if they're there
+pub(crate) async fn user_summary_periods(
+    state: &AppState,
+    user_id: &str,
+    period: SummaryPeriod,
+    filter: &HistoryFilter,
+    wallets: &WalletSelection,
+) -> HistoryResult<Vec<PeriodSummary>> {
+    filter.validate()?;
+    wallets.validate()?;
+
+    let scope = CacheScope::User(user_id.to_string());
+    let key = format!(
+        "summary?period={}{}{}",
+        period.as_sql(),
+        filter.cache_key(),
+        wallets.cache_key()
+    );
+    if let Some(periods) = state.cache.get::<Vec<PeriodSummary>>(&scope, &key) {
+        return Ok(periods);
+    }
+
+    let rows = state
+        .repository
+        .get_user_summary(user_id, period, filter, wallets)
+        .await?;
+    let periods = summarize(rows);
+    state.cache.insert(scope, &key, periods.clone());
+    Ok(periods)
+}
+
+/// Where cold storage cuts off a query starting at `from` (None: nothing
+/// it asks for was archived)
+pub(crate) async fn history_truncated_before(
+    state: &AppState,
+    from: Option<DateTime<Utc>>,
+) -> HistoryResult<Option<DateTime<Utc>>> {
+    let horizon = state.repository.get_archive_horizon().await?;
+    Ok(horizon.filter(|horizon| from.is_none_or(|from| from < *horizon)))
+}
+
+/// Like `history_truncated_before`, for a summary: whole-day summaries come
+/// from the daily totals, which keep archived months
+pub(crate) async fn summary_truncated_before(
+    state: &AppState,
+    filter: &HistoryFilter,
+) -> HistoryResult<Option<DateTime<Utc>>> {
+    if whole_days(filter).is_some() {
+        return Ok(None);
+    }
+    history_truncated_before(state, filter.from).await
+}
+
+/// A wallet's balance at `at`, with the gap count that says how far to trust it
+pub(crate) async fn balance_as_of(
+    state: &AppState,
+    wallet_id: String,
+    at: DateTime<Utc>,
+) -> HistoryResult<BalanceAsOf> {
+    let archived_through = state.repository.get_archive_horizon().await?;
+    if let Some(horizon) = archived_through.filter(|horizon| at < *horizon) {
+        return Err(HistoryError::InvalidRequest(format!(
+            "history before {} is in cold storage - at must be later",
+            horizon.to_rfc3339()
+        )));
+    }
+
+    let (mut balance, mut events_counted, mut last_event_at) =
+        state.repository.get_balance_at(&wallet_id, at).await?;
+    if let Some((carried, events, newest)) = state.repository.get_archived_balance(&wallet_id).await? {
+        balance += carried;
+        events_counted += events;
+        last_event_at = last_event_at.max(newest);
+    }
+    let sequence = state.repository.get_wallet_sequence(&wallet_id).await?;
+
+    Ok(BalanceAsOf {
+        wallet_id,
+        at,
+        balance,
+        events_counted,
+        last_event_at,
+        open_sequence_gaps: sequence.open_gaps.len(),
+        archived_through,
+    })
 }
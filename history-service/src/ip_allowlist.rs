@@ -0,0 +1,172 @@
+use crate::errors::ErrorResponse;
+use axum::{
+    extract::{ConnectInfo, Request, State},
+    http::{HeaderMap, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+    Json,
+};
+use std::fmt;
+use std::net::{IpAddr, SocketAddr};
+use std::str::FromStr;
+use std::sync::Arc;
+
+/// An IPv4 or IPv6 network in CIDR notation (`10.0.0.0/8`, `fd00::/8`);
+/// a bare address is a network of one
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct IpNetwork {
+    addr: IpAddr,
+    prefix: u8,
+}
+
+impl IpNetwork {
+    pub fn contains(&self, ip: IpAddr) -> bool {
+        // IPv4 clients on a dual-stack listener show up as ::ffff:a.b.c.d
+        match (self.addr, ip.to_canonical()) {
+            (IpAddr::V4(network), IpAddr::V4(ip)) => {
+                same_prefix(u32::from(network).into(), u32::from(ip).into(), 32, self.prefix)
+            }
+            (IpAddr::V6(network), IpAddr::V6(ip)) => {
+                same_prefix(u128::from(network), u128::from(ip), 128, self.prefix)
+            }
+            _ => false,
+        }
+    }
+}
+
+impl FromStr for IpNetwork {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || format!("'{}' is not an IP address or CIDR network", s);
+        let (addr, prefix) = match s.trim().split_once('/') {
+            Some((addr, prefix)) => (addr, Some(prefix)),
+            None => (s.trim(), None),
+        };
+        let addr: IpAddr = addr.parse().map_err(|_| invalid())?;
+        let max = if addr.is_ipv4() { 32 } else { 128 };
+        let prefix = match prefix {
+            Some(prefix) => prefix.parse::<u8>().ok().filter(|p| *p <= max).ok_or_else(invalid)?,
+            None => max,
+        };
+        Ok(Self { addr, prefix })
+    }
+}
+
+impl fmt::Display for IpNetwork {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}/{}", self.addr, self.prefix)
+    }
+}
+
+/// Do the top `prefix` of `bits` bits match?
+fn same_prefix(a: u128, b: u128, bits: u32, prefix: u8) -> bool {
+    prefix == 0 || (a ^ b) >> (bits - u32::from(prefix)) == 0
+}
+
+/// Where `/admin/*` may be called from
+///
+/// Why on top of roles?
+/// - Replays and consumer seeks rewrite the read model; a stolen admin
+///   token is then only useful from inside the operators' network
+///
+/// Checked before authentication, so a refused address learns nothing
+/// about which credentials would work. Refusals are a 403 and a warning
+/// in the log.
+#[derive(Debug, Clone)]
+pub struct AdminAllowlist {
+    networks: Vec<IpNetwork>,
+    /// ADMIN_TRUST_FORWARDED=on: take the client IP from the last
+    /// X-Forwarded-For entry (only behind a proxy that sets it)
+    trust_forwarded_for: bool,
+}
+
+impl AdminAllowlist {
+    /// ADMIN_ALLOWED_CIDRS=`10.0.0.0/8,192.168.1.7,...`; None when unset
+    /// (admin routes are reachable from anywhere)
+    pub fn from_env() -> Result<Option<Self>, String> {
+        let Some(value) = std::env::var("ADMIN_ALLOWED_CIDRS").ok().filter(|v| !v.trim().is_empty())
+        else {
+            return Ok(None);
+        };
+        let networks = value
+            .split(',')
+            .filter(|entry| !entry.trim().is_empty())
+            .map(IpNetwork::from_str)
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| format!("ADMIN_ALLOWED_CIDRS: {}", e))?;
+
+        let trust_forwarded_for = std::env::var("ADMIN_TRUST_FORWARDED").as_deref() == Ok("on");
+        Ok(Some(Self::new(networks, trust_forwarded_for)))
+    }
+
+    pub fn new(networks: Vec<IpNetwork>, trust_forwarded_for: bool) -> Self {
+        Self {
+            networks,
+            trust_forwarded_for,
+        }
+    }
+
+    pub fn networks(&self) -> &[IpNetwork] {
+        &self.networks
+    }
+
+    pub fn allows(&self, ip: IpAddr) -> bool {
+        self.networks.iter().any(|network| network.contains(ip))
+    }
+
+    /// The client address: the peer, or the proxy's X-Forwarded-For entry
+    fn client_ip(&self, headers: &HeaderMap, peer: Option<SocketAddr>) -> Option<IpAddr> {
+        if self.trust_forwarded_for {
+            let forwarded = headers
+                .get("x-forwarded-for")
+                .and_then(|v| v.to_str().ok())
+                .and_then(|v| v.rsplit(',').next())
+                .and_then(|v| v.trim().parse().ok());
+            if forwarded.is_some() {
+                return forwarded;
+            }
+        }
+        peer.map(|addr| addr.ip())
+    }
+}
+
+/// Middleware: `/admin/*` only from the allowlist (add with `layer`)
+///
+/// Needs the peer address (`into_make_service_with_connect_info`); a
+/// request without one is refused.
+pub async fn restrict_admin(
+    State(allowlist): State<Arc<AdminAllowlist>>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let path = request.uri().path();
+    if path != "/admin" && !path.starts_with("/admin/") {
+        return next.run(request).await;
+    }
+
+    let peer = request
+        .extensions()
+        .get::<ConnectInfo<SocketAddr>>()
+        .map(|ConnectInfo(addr)| *addr);
+    let client_ip = allowlist.client_ip(request.headers(), peer);
+    if client_ip.is_some_and(|ip| allowlist.allows(ip)) {
+        return next.run(request).await;
+    }
+
+    tracing::warn!(
+        client_ip = ?client_ip,
+        peer = ?peer,
+        method = %request.method(),
+        path = %path,
+        "Admin request from outside ADMIN_ALLOWED_CIDRS refused"
+    );
+    (
+        StatusCode::FORBIDDEN,
+        Json(ErrorResponse {
+            success: false,
+            error: "Admin routes can't be reached from this address".to_string(),
+        }),
+    )
+        .into_response()
+}
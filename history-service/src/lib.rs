@@ -0,0 +1,38 @@
+#[cfg(feature = "clickhouse")]
+pub mod analytics;
+pub mod auth;
+pub mod avro;
+pub mod balance;
+pub mod body_limit;
+pub mod cache;
+pub mod codec;
+pub mod cold_storage;
+pub mod consumer;
+pub mod control;
+pub mod db_pool;
+pub mod dlq;
+pub mod errors;
+pub mod export;
+pub mod filter;
+pub mod graphql;
+pub mod handlers;
+pub mod health;
+pub mod ip_allowlist;
+pub mod kafka_security;
+pub mod metrics;
+pub mod models;
+pub mod openapi;
+pub mod pagination;
+pub mod parquet_export;
+pub mod protobuf;
+pub mod read_replica;
+pub mod replay;
+pub mod repository;
+pub mod retry;
+pub mod schema_registry;
+pub mod sequence;
+pub mod shutdown;
+pub mod signing;
+pub mod summary;
+pub mod timeout;
+pub mod tls;
@@ -1,17 +1,34 @@
-mod consumer;
-mod errors;
-mod handlers;
-mod models;
-mod repository;
-
-use crate::consumer::EventConsumer;
-use crate::handlers::AppState;
-use crate::repository::EventRepository;
 use axum::{
-    routing::get,
-    Router,
+    middleware,
+    routing::{get, post},
+    Extension, Router,
 };
-use sqlx::postgres::PgPoolOptions;
+#[cfg(feature = "clickhouse")]
+use history_service::analytics::{ClickHouse, ClickHouseConfig};
+use history_service::auth::{enforce_ownership, require_auth, AuthConfig, Authenticator};
+use history_service::body_limit::{require_json_body, BodyLimits};
+use history_service::cache::ResponseCache;
+use history_service::codec::EventDecoder;
+use history_service::cold_storage::{self, ColdStorageConfig, EventArchiver};
+use history_service::consumer::{EventConsumer, FailureRouting, MAX_BATCH_SIZE};
+use history_service::control::ConsumerControl;
+use history_service::db_pool::PoolConfig;
+use history_service::dlq::DeadLetterProducer;
+use history_service::metrics::ConsumerLag;
+use history_service::replay::{EventReplayer, ReplayProducer};
+use history_service::retry::{retry_tiers, RetryProducer};
+use history_service::graphql;
+use history_service::handlers::{self, AppState};
+use history_service::ip_allowlist::{restrict_admin, AdminAllowlist};
+use history_service::openapi;
+use history_service::read_replica::{ReadReplica, ReplicaConfig};
+use history_service::repository::EventRepository;
+use history_service::shutdown::Shutdown;
+use history_service::signing::EventVerifier;
+use history_service::timeout::{enforce_timeout, TimeoutConfig};
+use history_service::tls::{graceful_handle, ServerTls};
+use std::net::SocketAddr;
+use std::sync::Arc;
 use tower_http::trace::TraceLayer;
 use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
 
@@ -42,6 +59,13 @@ async fn main() -> anyhow::Result<()> {
     let kafka_group_id = std::env::var("KAFKA_GROUP_ID")
         .unwrap_or_else(|_| "history-service-group".to_string());
 
+    let dlq_topic = std::env::var("KAFKA_DLQ_TOPIC")
+        .unwrap_or_else(|_| format!("{}-dlq", kafka_topic));
+
+    // Where POST /admin/replay republishes stored events
+    let replay_topic = std::env::var("KAFKA_REPLAY_TOPIC")
+        .unwrap_or_else(|_| format!("{}-replay", kafka_topic));
+
     let server_port = std::env::var("PORT")
         .unwrap_or_else(|_| "3001".to_string())
         .parse::<u16>()?;
@@ -51,66 +75,345 @@ async fn main() -> anyhow::Result<()> {
     tracing::info!("Kafka brokers: {}", kafka_brokers);
     tracing::info!("Kafka topic: {}", kafka_topic);
     tracing::info!("Consumer group: {}", kafka_group_id);
+    tracing::info!("Dead-letter topic: {}", dlq_topic);
+    tracing::info!("Replay topic: {}", replay_topic);
+    let client_tls = history_service::tls::ClientTls::from_env();
+    if let Some(cert) = &client_tls.cert_path {
+        tracing::info!("Outbound mTLS client certificate: {}", cert);
+    }
 
     // Set up database connection pool
-    tracing::info!("Connecting to database...");
-    let pool = PgPoolOptions::new()
-        .max_connections(10)
-        .connect(&database_url)
-        .await?;
+    // (DATABASE_MAX_CONNECTIONS etc. - 10 connections by default)
+    let pool_config = PoolConfig::from_env();
+    tracing::info!(
+        "Connecting to database ({}-{} connections, {}s acquire timeout, statement timeout {})...",
+        pool_config.min_connections,
+        pool_config.max_connections,
+        pool_config.acquire_timeout.as_secs(),
+        pool_config
+            .statement_timeout
+            .map_or("off".to_string(), |t| format!("{}ms", t.as_millis()))
+    );
+    let pool = pool_config.connect(&database_url).await?;
+    // Read-only queries get a pool of their own (DATABASE_QUERY_* - sized
+    // like the mutation pool by default)
+    let query_pool_config = pool_config.queries_from_env();
+    tracing::info!(
+        "Query pool: {}-{} connections, statement timeout {}",
+        query_pool_config.min_connections,
+        query_pool_config.max_connections,
+        query_pool_config
+            .statement_timeout
+            .map_or("off".to_string(), |t| format!("{}ms", t.as_millis()))
+    );
+    let query_pool = query_pool_config.connect(&database_url).await?;
 
     // Run migrations
     tracing::info!("Running database migrations...");
     sqlx::migrate!("./migrations").run(&pool).await?;
     tracing::info!("Migrations completed successfully");
 
-    // Create repository
-    let repository = EventRepository::new(pool.clone());
+    // Create repository - history queries on a read replica when
+    // DATABASE_READ_URL is set (the consumer always writes to the primary)
+    let mut repository = EventRepository::new(pool.clone()).with_query_pool(query_pool.clone());
+    if let Some(config) = ReplicaConfig::from_env() {
+        tracing::info!(
+            "Read replica configured (queries fall back to the primary over {}ms lag)",
+            config.max_lag.as_millis()
+        );
+        let replica = ReadReplica::connect(&config, &query_pool_config).await?;
+        replica.clone().spawn_probe();
+        repository = repository.with_read_replica(replica);
+    }
 
-    // Create Kafka consumer
-    tracing::info!("Initializing Kafka consumer...");
-    let consumer = EventConsumer::new(
-        &kafka_brokers,
-        &kafka_group_id,
-        &kafka_topic,
-        repository.clone(),
-    )?;
-    tracing::info!("Kafka consumer initialized");
-
-    // Spawn Kafka consumer in background task
-    // This runs forever, processing events as they arrive
-    tokio::spawn(async move {
-        if let Err(e) = consumer.start().await {
-            tracing::error!(error = %e, "Kafka consumer failed");
+    // Summaries from ClickHouse when built with the clickhouse feature and
+    // CLICKHOUSE_URL is set (stored events are copied there too)
+    #[cfg(feature = "clickhouse")]
+    if let Some(config) = ClickHouseConfig::from_env() {
+        tracing::info!("ClickHouse analytics: {} (database {})", config.url, config.database);
+        let analytics = ClickHouse::connect(config).await?;
+        if std::env::args().any(|arg| arg == "--analytics-backfill") {
+            tracing::warn!("--analytics-backfill: replacing the ClickHouse copy with the stored events");
+            let copied = analytics.backfill(&repository).await?;
+            tracing::info!("Copied {} events to ClickHouse", copied);
         }
+        repository = repository.with_analytics(analytics);
+    }
+    #[cfg(not(feature = "clickhouse"))]
+    if std::env::var("CLICKHOUSE_URL").is_ok_and(|v| !v.is_empty()) {
+        tracing::warn!("CLICKHOUSE_URL is set but this build has no clickhouse feature - ignored");
+    }
+
+    // Response cache (shared by the HTTP handlers and the consumer, which invalidates it)
+    let cache = Arc::new(ResponseCache::from_env());
+    if cache.is_enabled() {
+        tracing::info!("Response cache enabled (TTL {}s)", cache.stats().ttl_secs);
+    }
+
+    // Failed messages: retry-5s -> retry-1m -> retry-10m -> DLQ
+    let tiers = retry_tiers(&kafka_topic);
+    for tier in &tiers {
+        tracing::info!("Retry topic: {} ({}s)", tier.topic, tier.delay.as_secs());
+    }
+    let tier_count = tiers.len();
+
+    // JSON and Protobuf always; Avro too when SCHEMA_REGISTRY_URL is set
+    let decoder = Arc::new(EventDecoder::from_env()?);
+    let failures = Arc::new(FailureRouting {
+        retries: RetryProducer::new(&kafka_brokers, tiers)?,
+        dead_letters: DeadLetterProducer::new(&kafka_brokers, dlq_topic)?,
     });
 
+    // Signed events (EVENT_VERIFY_KEYS): unverified messages go to the DLQ
+    let verifier = EventVerifier::from_env().map_err(anyhow::Error::msg)?.map(Arc::new);
+    match &verifier {
+        Some(verifier) => {
+            tracing::info!("Verifying event signatures (keys: {})", verifier.key_ids().join(", "));
+            if verifier.accepts_unsigned() {
+                tracing::warn!("EVENT_ACCEPT_UNSIGNED=true - unsigned events are still accepted");
+            }
+        }
+        None => tracing::warn!("EVENT_VERIFY_KEYS unset - event signatures are not checked"),
+    }
+
+    // Disaster recovery: rebuild the read model from the topic, then carry on
+    if std::env::args().any(|arg| arg == "--rebuild") {
+        tracing::warn!("--rebuild: truncating history and replaying {}", kafka_topic);
+        repository.truncate_history().await?;
+        let rebuilder = EventConsumer::for_rebuild(
+            &kafka_brokers,
+            &kafka_group_id,
+            &kafka_topic,
+            repository.clone(),
+            cache.clone(),
+            failures.clone(),
+        )?
+        .with_decoder(decoder.clone())
+        .with_verifier(verifier.clone());
+        let report = rebuilder.rebuild().await?;
+        tracing::info!(
+            "Rebuilt history from {} messages on {} partitions",
+            report.messages,
+            report.partitions
+        );
+    }
+
+    // SIGTERM / Ctrl-C: stop the HTTP server and drain the consumers
+    let shutdown = Shutdown::new();
+    shutdown.trigger_on_signal();
+
+    // Shared pause/resume switch (POST /admin/consumer/pause|resume)
+    let consumer_control = Arc::new(ConsumerControl::new());
+    // Lag per partition, sampled by each consumer (GET /metrics)
+    let consumer_lag = Arc::new(ConsumerLag::new());
+
+    // Create Kafka consumers - one for the main topic, one per retry tier
+    // (separate consumers so a tier waiting 10 minutes never holds up the others)
+    // Queued events stored together, one INSERT per batch (1 = one at a time)
+    let batch_size = std::env::var("CONSUMER_BATCH_SIZE")
+        .ok()
+        .and_then(|v| v.parse::<usize>().ok())
+        .unwrap_or(MAX_BATCH_SIZE);
+    tracing::info!("Initializing Kafka consumers (batches of up to {})...", batch_size.clamp(1, MAX_BATCH_SIZE));
+    let consumers = std::iter::once(None)
+        .chain((0..tier_count).map(Some))
+        .map(|tier| {
+            EventConsumer::new(
+                &kafka_brokers,
+                &kafka_group_id,
+                &kafka_topic,
+                repository.clone(),
+                cache.clone(),
+                failures.clone(),
+                tier,
+            )
+            .map(|consumer| {
+                consumer
+                    .with_decoder(decoder.clone())
+                    .with_control(consumer_control.clone())
+                    .with_lag(consumer_lag.clone())
+                    .with_shutdown(shutdown.clone())
+                    .with_verifier(verifier.clone())
+                    .with_batch_size(batch_size)
+            })
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    tracing::info!("Kafka consumers initialized");
+
+    // Spawn Kafka consumers in background tasks
+    // These run until shutdown, processing events as they arrive
+    let consumer_tasks: Vec<_> = consumers
+        .into_iter()
+        .map(|consumer| {
+            tokio::spawn(async move {
+                if let Err(e) = consumer.start().await {
+                    tracing::error!(error = %e, "Kafka consumer failed");
+                }
+            })
+        })
+        .collect();
+
+    // Old events to object storage, pruned from Postgres once verified
+    if let Some(config) = ColdStorageConfig::from_env().map_err(anyhow::Error::msg)? {
+        let (store, prefix) = cold_storage::open_store(&config.url)?;
+        tracing::info!(
+            "Cold storage: events older than {} months to {} ({})",
+            config.after_months,
+            config.url,
+            config.format.extension()
+        );
+        EventArchiver::new(repository.clone(), store, prefix, config).spawn(shutdown.clone());
+    }
+
+    let replayer = Arc::new(EventReplayer::new(
+        repository.clone(),
+        cache.clone(),
+        ReplayProducer::new(&kafka_brokers, replay_topic)?,
+    ));
+
     // Create application state
-    let state = AppState { repository };
+    let state = AppState {
+        repository,
+        cache,
+        consumer_control,
+        consumer_lag,
+        replayer,
+    };
+    let graphql_schema = graphql::build_schema(state.clone());
 
     // Build the router with all routes
     let app = Router::new()
         // Health check
         .route("/health", get(handlers::health_check))
+        // API description
+        .route("/openapi.json", get(openapi::openapi_json))
+        .route("/docs", get(openapi::swagger_ui))
         // History endpoints
         .route("/wallets/:wallet_id/history", get(handlers::get_wallet_history))
+        .route("/wallets/:wallet_id/history/export", get(handlers::export_wallet_history))
         .route("/users/:user_id/activity", get(handlers::get_user_activity))
+        .route("/users/:user_id/activity/export", get(handlers::export_user_activity))
+        .route("/wallets/:wallet_id/sequence", get(handlers::get_wallet_sequence))
+        .route("/wallets/:wallet_id/balance", get(handlers::get_balance_at))
+        .route("/wallets/:wallet_id/summary", get(handlers::get_wallet_summary))
+        .route("/users/:user_id/summary", get(handlers::get_user_summary))
+        // Several history queries in one round trip
+        .route("/graphql", get(graphql::graphiql).post(graphql::graphql_handler))
+        // Cache metrics
+        .route("/cache/stats", get(handlers::get_cache_stats))
+        // Consumer lag (OpenMetrics)
+        .route("/metrics", get(handlers::get_metrics))
+        // Consumer control (migrations, incident response)
+        .route("/admin/consumer", get(handlers::get_consumer_status))
+        .route("/admin/consumer/pause", post(handlers::pause_consumer))
+        .route("/admin/consumer/resume", post(handlers::resume_consumer))
+        .route("/admin/consumer/seek", post(handlers::seek_consumer))
+        // Targeted backfills from the stored events
+        .route("/admin/replay", post(handlers::replay_events))
+        // Read-model consistency (per-wallet event numbering)
+        .route("/admin/sequence-gaps", get(handlers::get_sequence_gaps))
+        // Months moved to cold storage
+        .route("/admin/event-archives", get(handlers::get_event_archives))
+        // Callers only read their own history (does nothing with AUTH=off)
+        .route_layer(middleware::from_fn_with_state(state.clone(), enforce_ownership));
+    // Bearer tokens from AUTH_ISSUER, checked against its JWKS (AUTH=off to disable)
+    let app = match AuthConfig::from_env()? {
+        Some(config) => {
+            tracing::info!(issuer = %config.issuer, "JWT authentication on");
+            let authenticator = Arc::new(Authenticator::new(config)?);
+            app.route_layer(middleware::from_fn_with_state(authenticator, require_auth))
+        }
+        None => {
+            tracing::warn!("AUTH=off - any caller can read any history");
+            app
+        }
+    };
+    // /admin/* only from ADMIN_ALLOWED_CIDRS - checked before authentication
+    let app = match AdminAllowlist::from_env().map_err(anyhow::Error::msg)? {
+        Some(allowlist) => {
+            let networks: Vec<String> = allowlist.networks().iter().map(ToString::to_string).collect();
+            tracing::info!(networks = %networks.join(", "), "Admin routes restricted by address");
+            app.layer(middleware::from_fn_with_state(Arc::new(allowlist), restrict_admin))
+        }
+        None => {
+            tracing::info!("ADMIN_ALLOWED_CIDRS unset - admin routes reachable from any address");
+            app
+        }
+    };
+    let app = app
+        // 504 for any route slower than its timeout (REQUEST_TIMEOUT_*)
+        .route_layer(middleware::from_fn_with_state(
+            Arc::new(TimeoutConfig::from_env()),
+            enforce_timeout,
+        ))
         // Add state and middleware
         .with_state(state)
+        .layer(Extension(graphql_schema))
+        // JSON bodies only, at most MAX_BODY_BYTES (415 / 413)
+        .layer(middleware::from_fn_with_state(
+            Arc::new(BodyLimits::from_env()),
+            require_json_body,
+        ))
         .layer(TraceLayer::new_for_http());
 
     // Start the HTTP server
     let addr = format!("0.0.0.0:{}", server_port);
     let listener = tokio::net::TcpListener::bind(&addr).await?;
+    let server_tls = ServerTls::from_env().map_err(anyhow::Error::msg)?;
 
-    tracing::info!("🚀 History Service listening on {}", addr);
+    let scheme = if server_tls.is_some() { "https" } else { "http" };
+    tracing::info!("🚀 History Service listening on {}://{}", scheme, addr);
     tracing::info!("📝 API Documentation:");
     tracing::info!("  GET    /wallets/:wallet_id/history - Get wallet transaction history");
+    tracing::info!("  GET    /wallets/:wallet_id/history/export?format=csv|ndjson|parquet|json - Download full history");
     tracing::info!("  GET    /users/:user_id/activity    - Get user activity");
+    tracing::info!("  GET    /users/:user_id/activity/export - Download all of a user's activity");
+    tracing::info!("  GET    /wallets/:wallet_id/sequence - Last event number and gaps");
+    tracing::info!("  GET    /wallets/:wallet_id/balance?at= - Balance at a point in time");
+    tracing::info!("  GET    /wallets/:wallet_id/summary?period= - Money in/out per period");
+    tracing::info!("  GET    /users/:user_id/summary?period= - Money in/out across a user's wallets");
+    tracing::info!("  POST   /graphql                    - GraphQL queries (GraphiQL on GET)");
+    tracing::info!("  GET    /cache/stats                - Response cache hit/miss counters");
+    tracing::info!("  GET    /metrics                    - Consumer lag per partition");
+    tracing::info!("  GET    /admin/consumer              - Consumer paused/running");
+    tracing::info!("  POST   /admin/consumer/pause        - Pause the Kafka consumers");
+    tracing::info!("  POST   /admin/consumer/resume       - Resume the Kafka consumers");
+    tracing::info!("  POST   /admin/consumer/seek         - Re-read from an offset/timestamp");
+    tracing::info!("  POST   /admin/replay                - Republish/reprocess stored events");
+    tracing::info!("  GET    /admin/sequence-gaps         - Events missing from the history");
+    tracing::info!("  GET    /admin/event-archives        - Months moved to cold storage");
     tracing::info!("  GET    /health                      - Health check");
+    tracing::info!("  GET    /openapi.json                - OpenAPI spec (Swagger UI at /docs)");
     tracing::info!("🎧 Kafka consumer running in background...");
 
-    axum::serve(listener, app).await?;
+    // Peer addresses for the admin allowlist
+    let make_service = app.into_make_service_with_connect_info::<SocketAddr>();
+    // Stops accepting on shutdown, then finishes open requests
+    match server_tls {
+        Some(tls) => {
+            let config = tls.load().await.map_err(anyhow::Error::msg)?;
+            tls.spawn_reloader(config.clone(), shutdown.clone());
+            axum_server::from_tcp_rustls(listener.into_std()?, config)
+                .handle(graceful_handle(shutdown.clone()))
+                .serve(make_service)
+                .await?;
+        }
+        None => {
+            axum::serve(listener, make_service)
+                .with_graceful_shutdown(async move { shutdown.wait().await })
+                .await?;
+        }
+    }
+
+    // Workers finish their message and the consumers commit before we exit
+    for task in consumer_tasks {
+        if let Err(e) = task.await {
+            tracing::error!(error = %e, "Kafka consumer task failed");
+        }
+    }
+    pool.close().await;
+    query_pool.close().await;
+    tracing::info!("History Service stopped");
 
     Ok(())
 }
@@ -0,0 +1,131 @@
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::sync::RwLock;
+
+/// Content type for the OpenMetrics text format
+pub const OPENMETRICS_CONTENT_TYPE: &str = "application/openmetrics-text; version=1.0.0; charset=utf-8";
+
+/// One partition's position, as last sampled
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct PartitionLag {
+    pub topic: String,
+    pub partition: i32,
+    /// None until the group has committed anything for the partition
+    pub committed_offset: Option<i64>,
+    pub high_watermark: i64,
+    /// Messages not yet processed (the whole partition if nothing is committed)
+    pub lag: i64,
+}
+
+impl PartitionLag {
+    pub fn new(topic: &str, partition: i32, committed_offset: Option<i64>, low: i64, high: i64) -> Self {
+        // Retention may have deleted what we never got to: count from `low`
+        let from = committed_offset.unwrap_or(low).max(low);
+        Self {
+            topic: topic.to_string(),
+            partition,
+            committed_offset,
+            high_watermark: high,
+            lag: (high - from).max(0),
+        }
+    }
+}
+
+/// Consumer lag per partition, exposed on /metrics
+///
+/// Why committed offset rather than our in-memory position?
+/// - The committed offset is what a restart resumes from, so it's the
+///   honest measure of how far behind the read model can be
+/// - It trails the position by up to the auto-commit interval (5s)
+///
+/// Each consumer (main topic and retry tiers) samples its own assignment
+/// every few seconds and replaces its topic's rows, so revoked partitions
+/// drop out. Lag is per instance - sum across instances in the dashboard.
+pub struct ConsumerLag {
+    partitions: RwLock<BTreeMap<(String, i32), PartitionLag>>,
+}
+
+impl ConsumerLag {
+    pub fn new() -> Self {
+        Self {
+            partitions: RwLock::new(BTreeMap::new()),
+        }
+    }
+
+    /// Replace everything known about `topic` with a fresh sample
+    pub fn record(&self, topic: &str, sample: Vec<PartitionLag>) {
+        let mut partitions = self.partitions.write().unwrap_or_else(|e| e.into_inner());
+        partitions.retain(|(t, _), _| t != topic);
+        for lag in sample {
+            partitions.insert((lag.topic.clone(), lag.partition), lag);
+        }
+    }
+
+    pub fn snapshot(&self) -> Vec<PartitionLag> {
+        self.partitions
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .values()
+            .cloned()
+            .collect()
+    }
+
+    /// Render the lag gauges in the OpenMetrics text format
+    pub fn render(&self) -> String {
+        let partitions = self.snapshot();
+        let mut out = String::new();
+
+        write_gauges(
+            &mut out,
+            "history_consumer_lag",
+            "Messages behind the high watermark (by committed offset)",
+            &partitions,
+            |p| Some(p.lag),
+        );
+        write_gauges(
+            &mut out,
+            "history_consumer_committed_offset",
+            "Committed offset of the consumer group",
+            &partitions,
+            |p| p.committed_offset,
+        );
+        write_gauges(
+            &mut out,
+            "history_consumer_high_watermark",
+            "Offset the next produced message will get",
+            &partitions,
+            |p| Some(p.high_watermark),
+        );
+
+        out.push_str("# EOF\n");
+        out
+    }
+}
+
+impl Default for ConsumerLag {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// One gauge family with a sample per partition (skipping unknown values)
+fn write_gauges(
+    out: &mut String,
+    name: &str,
+    help: &str,
+    partitions: &[PartitionLag],
+    value: impl Fn(&PartitionLag) -> Option<i64>,
+) {
+    let _ = writeln!(out, "# TYPE {} gauge", name);
+    let _ = writeln!(out, "# HELP {} {}", name, help);
+    for partition in partitions {
+        if let Some(value) = value(partition) {
+            let _ = writeln!(
+                out,
+                "{}{{topic=\"{}\",partition=\"{}\"}} {}",
+                name, partition.topic, partition.partition, value
+            );
+        }
+    }
+}
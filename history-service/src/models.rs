@@ -1,7 +1,9 @@
+use async_graphql::SimpleObject;
 use chrono::{DateTime, Utc};
 use rust_decimal::Decimal;
 use serde::{Deserialize, Serialize};
 use sqlx::FromRow;
+use utoipa::ToSchema;
 
 /// Transaction event stored in the database
 /// This is our event-sourced history
@@ -15,6 +17,9 @@ pub struct TransactionEvent {
     pub transaction_id: Option<String>, // For idempotency - ensures we don't process same event twice
     pub created_at: DateTime<Utc>,
     pub event_data: serde_json::Value, // JSONB - stores the full event for debugging
+    pub event_id: Option<String>, // The Kafka event this row came from (not unique: transfers store two rows)
+    pub correlation_id: Option<String>,
+    pub causation_id: Option<String>,
 }
 
 /// Wallet events from Kafka (matches what Wallet Service publishes)
@@ -51,6 +56,201 @@ pub enum WalletEvent {
         reference_id: String,
         timestamp: DateTime<Utc>,
     },
+
+    #[serde(rename = "ROUND_UP_APPLIED")]
+    RoundUpApplied {
+        wallet_id: String,
+        user_id: String,
+        savings_wallet_id: String,
+        savings_user_id: String,
+        amount: Decimal,
+        out_transaction_id: String,
+        in_transaction_id: String,
+        reference_id: String,
+        timestamp: DateTime<Utc>,
+    },
+
+    #[serde(rename = "POT_TRANSFER_COMPLETED")]
+    PotTransferCompleted {
+        from_wallet_id: String,
+        to_wallet_id: String,
+        user_id: String,
+        amount: Decimal,
+        out_transaction_id: String,
+        in_transaction_id: String,
+        reference_id: String,
+        timestamp: DateTime<Utc>,
+    },
+
+    #[serde(rename = "VOUCHER_REDEEMED")]
+    VoucherRedeemed {
+        wallet_id: String,
+        user_id: String,
+        voucher_id: String, // NOT the code - the code is a bearer secret
+        amount: Decimal,
+        new_balance: Decimal,
+        transaction_id: String,
+        timestamp: DateTime<Utc>,
+    },
+
+    /// A manual admin correction (stored as ADJUSTMENT_IN / _OUT)
+    #[serde(rename = "WALLET_ADJUSTED")]
+    WalletAdjusted {
+        wallet_id: String,
+        user_id: String,
+        direction: String, // CREDIT or DEBIT; amount is always positive
+        amount: Decimal,
+        new_balance: Decimal,
+        reason_code: String,
+        actor: String,
+        adjustment_id: String,
+        transaction_id: String,
+        timestamp: DateTime<Utc>,
+    },
+
+    /// A declined funding (for analytics - nothing changed)
+    #[serde(rename = "FUNDING_FAILED")]
+    FundingFailed {
+        wallet_id: String,
+        user_id: String,
+        amount: Decimal,
+        reason: String,
+        timestamp: DateTime<Utc>,
+    },
+
+    /// A declined transfer (for analytics - nothing moved)
+    #[serde(rename = "TRANSFER_FAILED")]
+    TransferFailed {
+        from_wallet_id: String,
+        from_user_id: String,
+        to_wallet_id: String,
+        amount: Decimal,
+        reason: String,
+        timestamp: DateTime<Utc>,
+    },
+
+    /// A fraud rule fired (for the fraud team - moves no money itself)
+    #[serde(rename = "FRAUD_ALERT")]
+    FraudAlert {
+        wallet_id: String,
+        user_id: String,
+        operation: String,
+        counterparty_wallet_id: String,
+        amount: Decimal,
+        action: String,
+        rules: String,
+        reason: String,
+        timestamp: DateTime<Utc>,
+    },
+
+    /// A transfer broke a velocity limit (its TRANSFER_FAILED comes too)
+    #[serde(rename = "VELOCITY_LIMIT_EXCEEDED")]
+    VelocityLimitExceeded {
+        wallet_id: String,
+        user_id: String,
+        to_wallet_id: String,
+        amount: Decimal,
+        measure: String,
+        limit: Decimal,
+        attempted: Decimal,
+        window_secs: u64,
+        timestamp: DateTime<Utc>,
+    },
+
+    /// A wallet or user was blocklisted (moves no money)
+    #[serde(rename = "BLOCKLIST_ENTRY_ADDED")]
+    BlocklistEntryAdded {
+        entry_id: String,
+        target_type: String,
+        target_id: String,
+        reason: String,
+        actor: String,
+        timestamp: DateTime<Utc>,
+    },
+
+    /// A wallet or user was taken off the blocklist
+    #[serde(rename = "BLOCKLIST_ENTRY_REMOVED")]
+    BlocklistEntryRemoved {
+        entry_id: String,
+        target_type: String,
+        target_id: String,
+        actor: String,
+        timestamp: DateTime<Utc>,
+    },
+
+    /// A user's data was erased: their stored rows move to the pseudonym
+    #[serde(rename = "USER_ANONYMIZED")]
+    UserAnonymized {
+        user_id: String,
+        anonymized_user_id: String,
+        timestamp: DateTime<Utc>,
+    },
+
+    /// Wrong PINs or step-up codes locked a wallet's checks (moves no money)
+    #[serde(rename = "VERIFICATION_LOCKOUT")]
+    VerificationLockout {
+        wallet_id: String,
+        user_id: String,
+        factor: String,
+        failed_attempts: u32,
+        lockouts: u32,
+        locked_until: DateTime<Utc>,
+        timestamp: DateTime<Utc>,
+    },
+}
+
+/// Tracing IDs the producer stamps on every event
+///
+/// Events from before they were added have none (empty strings here,
+/// NULL in the database - `request_id` is only logged, not stored).
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct EventIds {
+    pub event_id: String,
+    pub correlation_id: String, // Shared by every event of one user action
+    pub causation_id: String, // The event (or request) that caused this one
+    pub request_id: String, // X-Request-ID of the wallet-service request
+}
+
+impl EventIds {
+    pub fn event_id(&self) -> Option<&str> {
+        non_empty(&self.event_id)
+    }
+
+    pub fn correlation_id(&self) -> Option<&str> {
+        non_empty(&self.correlation_id)
+    }
+
+    pub fn causation_id(&self) -> Option<&str> {
+        non_empty(&self.causation_id)
+    }
+
+    pub fn request_id(&self) -> Option<&str> {
+        non_empty(&self.request_id)
+    }
+}
+
+fn non_empty(id: &str) -> Option<&str> {
+    (!id.is_empty()).then_some(id)
+}
+
+/// A decoded Kafka message: the event plus its tracing IDs (flat on the wire)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EventEnvelope {
+    #[serde(flatten)]
+    pub ids: EventIds,
+    /// The event's number among its wallet's events (0 = not numbered)
+    #[serde(default)]
+    pub sequence: i64,
+    #[serde(flatten)]
+    pub event: WalletEvent,
+}
+
+impl EventEnvelope {
+    /// None for events published before numbering (or without it)
+    pub fn sequence(&self) -> Option<i64> {
+        (self.sequence > 0).then_some(self.sequence)
+    }
 }
 
 impl WalletEvent {
@@ -60,6 +260,30 @@ impl WalletEvent {
             WalletEvent::WalletCreated { .. } => "WALLET_CREATED",
             WalletEvent::WalletFunded { .. } => "WALLET_FUNDED",
             WalletEvent::TransferCompleted { .. } => "TRANSFER_COMPLETED",
+            WalletEvent::RoundUpApplied { .. } => "ROUND_UP_APPLIED",
+            WalletEvent::PotTransferCompleted { .. } => "POT_TRANSFER_COMPLETED",
+            WalletEvent::VoucherRedeemed { .. } => "VOUCHER_REDEEMED",
+            WalletEvent::WalletAdjusted { .. } => "WALLET_ADJUSTED",
+            WalletEvent::FundingFailed { .. } => "FUNDING_FAILED",
+            WalletEvent::TransferFailed { .. } => "TRANSFER_FAILED",
+            WalletEvent::FraudAlert { .. } => "FRAUD_ALERT",
+            WalletEvent::VelocityLimitExceeded { .. } => "VELOCITY_LIMIT_EXCEEDED",
+            WalletEvent::BlocklistEntryAdded { .. } => "BLOCKLIST_ENTRY_ADDED",
+            WalletEvent::BlocklistEntryRemoved { .. } => "BLOCKLIST_ENTRY_REMOVED",
+            WalletEvent::UserAnonymized { .. } => "USER_ANONYMIZED",
+            WalletEvent::VerificationLockout { .. } => "VERIFICATION_LOCKOUT",
+        }
+    }
+
+    /// The event type its row is stored under
+    ///
+    /// Same as `event_type` except for adjustments, whose row says which
+    /// way the money went (balances and summaries go by stored type).
+    pub fn stored_event_type(&self) -> &str {
+        match self {
+            WalletEvent::WalletAdjusted { direction, .. } if direction == "DEBIT" => "ADJUSTMENT_OUT",
+            WalletEvent::WalletAdjusted { .. } => "ADJUSTMENT_IN",
+            _ => self.event_type(),
         }
     }
 
@@ -69,6 +293,19 @@ impl WalletEvent {
             WalletEvent::WalletCreated { wallet_id, .. } => wallet_id,
             WalletEvent::WalletFunded { wallet_id, .. } => wallet_id,
             WalletEvent::TransferCompleted { from_wallet_id, .. } => from_wallet_id,
+            WalletEvent::RoundUpApplied { wallet_id, .. } => wallet_id,
+            WalletEvent::PotTransferCompleted { from_wallet_id, .. } => from_wallet_id,
+            WalletEvent::VoucherRedeemed { wallet_id, .. } => wallet_id,
+            WalletEvent::WalletAdjusted { wallet_id, .. } => wallet_id,
+            WalletEvent::FundingFailed { wallet_id, .. } => wallet_id,
+            WalletEvent::TransferFailed { from_wallet_id, .. } => from_wallet_id,
+            WalletEvent::FraudAlert { wallet_id, .. } => wallet_id,
+            WalletEvent::VelocityLimitExceeded { wallet_id, .. } => wallet_id,
+            // The blocked wallet or user - what the producer keys them by
+            WalletEvent::BlocklistEntryAdded { target_id, .. }
+            | WalletEvent::BlocklistEntryRemoved { target_id, .. } => target_id,
+            WalletEvent::UserAnonymized { user_id, .. } => user_id,
+            WalletEvent::VerificationLockout { wallet_id, .. } => wallet_id,
         }
     }
 
@@ -78,35 +315,82 @@ impl WalletEvent {
             WalletEvent::WalletCreated { user_id, .. } => user_id,
             WalletEvent::WalletFunded { user_id, .. } => user_id,
             WalletEvent::TransferCompleted { from_user_id, .. } => from_user_id,
+            WalletEvent::RoundUpApplied { user_id, .. } => user_id,
+            WalletEvent::PotTransferCompleted { user_id, .. } => user_id,
+            WalletEvent::VoucherRedeemed { user_id, .. } => user_id,
+            WalletEvent::WalletAdjusted { user_id, .. } => user_id,
+            WalletEvent::FundingFailed { user_id, .. } => user_id,
+            WalletEvent::TransferFailed { from_user_id, .. } => from_user_id,
+            WalletEvent::FraudAlert { user_id, .. } => user_id,
+            WalletEvent::VelocityLimitExceeded { user_id, .. } => user_id,
+            WalletEvent::BlocklistEntryAdded { target_id, .. }
+            | WalletEvent::BlocklistEntryRemoved { target_id, .. } => target_id,
+            WalletEvent::UserAnonymized { user_id, .. } => user_id,
+            WalletEvent::VerificationLockout { user_id, .. } => user_id,
         }
     }
 
     /// Get the transaction ID for idempotency (if available)
     pub fn transaction_id(&self) -> Option<String> {
         match self {
-            WalletEvent::WalletCreated { .. } => None,
-            WalletEvent::WalletFunded { transaction_id, .. } => Some(transaction_id.clone()),
+            WalletEvent::WalletCreated { .. }
+            | WalletEvent::FundingFailed { .. }
+            | WalletEvent::TransferFailed { .. }
+            | WalletEvent::FraudAlert { .. }
+            | WalletEvent::VelocityLimitExceeded { .. }
+            | WalletEvent::BlocklistEntryAdded { .. }
+            | WalletEvent::BlocklistEntryRemoved { .. }
+            | WalletEvent::UserAnonymized { .. }
+            | WalletEvent::VerificationLockout { .. } => None,
+            WalletEvent::WalletFunded { transaction_id, .. }
+            | WalletEvent::VoucherRedeemed { transaction_id, .. }
+            | WalletEvent::WalletAdjusted { transaction_id, .. } => Some(transaction_id.clone()),
             WalletEvent::TransferCompleted { reference_id, .. } => Some(reference_id.clone()),
+            WalletEvent::RoundUpApplied { out_transaction_id, .. }
+            | WalletEvent::PotTransferCompleted { out_transaction_id, .. } => {
+                Some(out_transaction_id.clone())
+            }
         }
     }
 
-    /// Get the amount (0 for wallet creation)
+    /// Get the amount (0 for wallet creation, blocklist changes, erasures
+    /// and lockouts, the attempted amount for declines)
     pub fn amount(&self) -> Decimal {
         match self {
             WalletEvent::WalletCreated { .. } => Decimal::ZERO,
             WalletEvent::WalletFunded { amount, .. } => *amount,
             WalletEvent::TransferCompleted { amount, .. } => *amount,
+            WalletEvent::RoundUpApplied { amount, .. } => *amount,
+            WalletEvent::PotTransferCompleted { amount, .. } => *amount,
+            WalletEvent::VoucherRedeemed { amount, .. } => *amount,
+            WalletEvent::WalletAdjusted { amount, .. } => *amount,
+            WalletEvent::FundingFailed { amount, .. } => *amount,
+            WalletEvent::TransferFailed { amount, .. } => *amount,
+            WalletEvent::FraudAlert { amount, .. } => *amount,
+            WalletEvent::VelocityLimitExceeded { amount, .. } => *amount,
+            WalletEvent::BlocklistEntryAdded { .. }
+            | WalletEvent::BlocklistEntryRemoved { .. }
+            | WalletEvent::UserAnonymized { .. }
+            | WalletEvent::VerificationLockout { .. } => Decimal::ZERO,
         }
     }
 }
 
 // API Response models
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, ToSchema)]
 pub struct ApiResponse<T> {
     pub success: bool,
     pub data: Option<T>,
     pub message: Option<String>,
+    /// Paginated endpoints: pass as `?cursor=` for the next page (left out
+    /// on the last one)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub next_cursor: Option<String>,
+    /// History endpoints: events stored before this were moved to cold
+    /// storage and aren't included (left out when nothing was cut off)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub history_truncated_before: Option<DateTime<Utc>>,
 }
 
 impl<T> ApiResponse<T> {
@@ -115,6 +399,24 @@ impl<T> ApiResponse<T> {
             success: true,
             data: Some(data),
             message: None,
+            next_cursor: None,
+            history_truncated_before: None,
+        }
+    }
+
+    /// A page of a paginated list
+    pub fn page(data: T, next_cursor: Option<String>) -> Self {
+        Self {
+            next_cursor,
+            ..Self::success(data)
+        }
+    }
+
+    /// Flag that history before `horizon` is in cold storage
+    pub fn truncated_before(self, horizon: Option<DateTime<Utc>>) -> Self {
+        Self {
+            history_truncated_before: horizon,
+            ..self
         }
     }
 
@@ -123,11 +425,14 @@ impl<T> ApiResponse<T> {
             success: false,
             data: None,
             message: Some(message),
+            next_cursor: None,
+            history_truncated_before: None,
         }
     }
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, ToSchema, SimpleObject)]
+#[graphql(name = "Event")]
 pub struct EventResponse {
     pub id: String,
     pub wallet_id: String,
@@ -135,6 +440,8 @@ pub struct EventResponse {
     pub amount: Decimal,
     pub event_type: String,
     pub created_at: DateTime<Utc>,
+    pub correlation_id: Option<String>,
+    pub causation_id: Option<String>,
 }
 
 impl From<TransactionEvent> for EventResponse {
@@ -146,6 +453,8 @@ impl From<TransactionEvent> for EventResponse {
             amount: event.amount,
             event_type: event.event_type,
             created_at: event.created_at,
+            correlation_id: event.correlation_id,
+            causation_id: event.causation_id,
         }
     }
 }
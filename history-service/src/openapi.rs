@@ -0,0 +1,71 @@
+use crate::handlers;
+use axum::{response::Html, Json};
+use utoipa::OpenApi;
+
+/// The API description served at /openapi.json
+///
+/// Generated from `#[utoipa::path]` on each handler and the types they take
+/// and return, like wallet-service's. Success bodies are wrapped in
+/// `{"success": true, "data": ...}`; errors are ErrorResponse.
+#[derive(OpenApi)]
+#[openapi(
+    info(
+        title = "History Service",
+        description = "Read model of wallet events: history, exports, summaries and consumer operations."
+    ),
+    paths(
+        handlers::get_wallet_history,
+        handlers::export_wallet_history,
+        handlers::export_user_activity,
+        handlers::get_user_activity,
+        handlers::get_wallet_summary,
+        handlers::get_user_summary,
+        handlers::get_balance_at,
+        handlers::get_wallet_sequence,
+        handlers::get_sequence_gaps,
+        handlers::get_event_archives,
+        handlers::replay_events,
+        handlers::get_cache_stats,
+        handlers::get_consumer_status,
+        handlers::pause_consumer,
+        handlers::resume_consumer,
+        handlers::seek_consumer,
+        handlers::get_metrics,
+        handlers::health_check
+    ),
+    tags(
+        (name = "history", description = "Paginated history, summaries and point-in-time balances"),
+        (name = "export", description = "Full-history downloads (CSV, NDJSON, Parquet)"),
+        (name = "consistency", description = "Per-wallet event numbering and gaps"),
+        (name = "admin", description = "Consumer control, replays and cold storage"),
+        (name = "service", description = "Health, metrics and cache stats")
+    )
+)]
+pub struct ApiDoc;
+
+/// GET /openapi.json
+pub async fn openapi_json() -> Json<utoipa::openapi::OpenApi> {
+    Json(ApiDoc::openapi())
+}
+
+/// GET /docs - Swagger UI for /openapi.json (scripts come from a CDN)
+pub async fn swagger_ui() -> Html<&'static str> {
+    Html(SWAGGER_UI)
+}
+
+const SWAGGER_UI: &str = r##"<!doctype html>
+<html>
+<head>
+  <meta charset="utf-8">
+  <title>History Service API</title>
+  <link rel="stylesheet" href="https://unpkg.com/swagger-ui-dist@5/swagger-ui.css">
+</head>
+<body>
+  <div id="swagger-ui"></div>
+  <script src="https://unpkg.com/swagger-ui-dist@5/swagger-ui-bundle.js"></script>
+  <script>
+    window.ui = SwaggerUIBundle({ url: "/openapi.json", dom_id: "#swagger-ui" });
+  </script>
+</body>
+</html>
+"##;
@@ -0,0 +1,126 @@
+use crate::errors::{HistoryError, HistoryResult};
+use crate::models::TransactionEvent;
+use chrono::{DateTime, Utc};
+use serde::Deserialize;
+use utoipa::IntoParams;
+
+/// Page size when `limit` isn't given
+pub const DEFAULT_PAGE_SIZE: i64 = 50;
+
+/// Largest `limit` accepted
+pub const MAX_PAGE_SIZE: i64 = 500;
+
+/// `?limit=&cursor=` on the history endpoints
+#[derive(Debug, Clone, Default, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct PageParams {
+    pub limit: Option<i64>,
+    /// `next_cursor` from the previous page (absent = first page)
+    pub cursor: Option<String>,
+}
+
+impl PageParams {
+    pub fn limit(&self) -> HistoryResult<i64> {
+        match self.limit {
+            None => Ok(DEFAULT_PAGE_SIZE),
+            Some(limit) if (1..=MAX_PAGE_SIZE).contains(&limit) => Ok(limit),
+            Some(_) => Err(HistoryError::InvalidRequest(format!(
+                "limit must be between 1 and {}",
+                MAX_PAGE_SIZE
+            ))),
+        }
+    }
+
+    pub fn cursor(&self) -> HistoryResult<Option<Cursor>> {
+        self.cursor.as_deref().map(Cursor::decode).transpose()
+    }
+
+    /// Cache key for `endpoint` with these parameters
+    pub fn cache_key(&self, endpoint: &str) -> String {
+        format!(
+            "{}?limit={}&cursor={}",
+            endpoint,
+            self.limit.unwrap_or(DEFAULT_PAGE_SIZE),
+            self.cursor.as_deref().unwrap_or("")
+        )
+    }
+}
+
+/// Position after the last event of a page (keyset pagination)
+///
+/// Why keyset rather than OFFSET?
+/// - New events arrive at the top while a client pages down; with OFFSET
+///   every insert shifts the pages and rows repeat or go missing
+/// - `(created_at, id) < cursor` is stable and uses the index however deep
+///   the page is
+///
+/// The ID breaks ties between events stored in the same microsecond (both
+/// legs of a transfer, typically). Clients treat the encoded form as opaque.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Cursor {
+    pub created_at: DateTime<Utc>,
+    pub id: String,
+}
+
+impl Cursor {
+    pub fn after(event: &TransactionEvent) -> Self {
+        Self {
+            created_at: event.created_at,
+            id: event.id.clone(),
+        }
+    }
+
+    /// Hex of "<created_at in microseconds>:<id>"
+    pub fn encode(&self) -> String {
+        hex::encode(format!("{}:{}", self.created_at.timestamp_micros(), self.id))
+    }
+
+    pub fn decode(encoded: &str) -> HistoryResult<Self> {
+        let invalid = || HistoryError::InvalidRequest("invalid cursor".to_string());
+
+        let bytes = hex::decode(encoded).map_err(|_| invalid())?;
+        let text = String::from_utf8(bytes).map_err(|_| invalid())?;
+
+        let (micros, id) = text.split_once(':').ok_or_else(invalid)?;
+        let created_at = micros
+            .parse::<i64>()
+            .ok()
+            .and_then(DateTime::from_timestamp_micros)
+            .ok_or_else(invalid)?;
+        if id.is_empty() {
+            return Err(invalid());
+        }
+
+        Ok(Self {
+            created_at,
+            id: id.to_string(),
+        })
+    }
+}
+
+/// One page of results, newest first
+#[derive(Debug, Clone)]
+pub struct Page<T> {
+    pub items: Vec<T>,
+    /// None on the last page
+    pub next_cursor: Option<String>,
+}
+
+impl Page<TransactionEvent> {
+    /// Build a page from up to `limit + 1` rows (the extra one only says
+    /// there's more)
+    pub fn from_rows(mut rows: Vec<TransactionEvent>, limit: i64) -> Self {
+        let more = rows.len() as i64 > limit;
+        rows.truncate(limit as usize);
+        let next_cursor = if more {
+            rows.last().map(|last| Cursor::after(last).encode())
+        } else {
+            None
+        };
+
+        Self {
+            items: rows,
+            next_cursor,
+        }
+    }
+}
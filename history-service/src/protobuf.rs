@@ -0,0 +1,254 @@
+use crate::errors::{HistoryError, HistoryResult};
+use chrono::{SecondsFormat, TimeZone, Utc};
+use serde_json::{Map, Value};
+use std::collections::HashMap;
+
+/// The wallet event .proto (shared with wallet-service)
+pub const WALLET_EVENT_PROTO: &str = include_str!("../../proto/wallet_event.proto");
+
+const WIRE_VARINT: u32 = 0;
+const WIRE_FIXED64: u32 = 1;
+const WIRE_LEN: u32 = 2;
+const WIRE_FIXED32: u32 = 5;
+
+/// One event message: its JSON tag and fields by number
+#[derive(Debug, Clone)]
+pub struct EventMessage {
+    pub event_type: String,
+    pub fields: HashMap<u32, ProtoField>,
+}
+
+#[derive(Debug, Clone)]
+pub struct ProtoField {
+    pub name: String,
+    pub kind: FieldKind,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FieldKind {
+    String,
+    Int64,
+    Timestamp, // google.protobuf.Timestamp
+}
+
+/// Protobuf decoding for wallet events, driven by proto/wallet_event.proto
+///
+/// Mirrors wallet-service's encoder. Evolution follows the usual proto3 rules:
+/// unknown field numbers are skipped, fields an older writer didn't send
+/// decode as their defaults ("" / 0 / the epoch).
+#[derive(Debug, Clone)]
+pub struct ProtoSchema {
+    by_oneof_number: HashMap<u32, EventMessage>,
+}
+
+impl ProtoSchema {
+    /// Read the messages out of a .proto file (the subset ours uses)
+    pub fn parse(text: &str) -> HistoryResult<Self> {
+        let mut messages: HashMap<String, Vec<(String, String, u32)>> = HashMap::new();
+        let mut current: Option<String> = None;
+
+        for line in text.lines() {
+            let line = line.split("//").next().unwrap_or_default().trim();
+
+            if let Some(rest) = line.strip_prefix("message ") {
+                let name = rest.trim_end_matches('{').trim().to_string();
+                messages.entry(name.clone()).or_default();
+                current = Some(name);
+            } else if line == "}" || line.starts_with("oneof ") {
+                // Oneof members are listed as ordinary fields of the message
+            } else if let (Some(message), Some(field)) = (&current, parse_field(line)) {
+                messages.entry(message.clone()).or_default().push(field?);
+            }
+        }
+
+        let envelope = messages
+            .get("WalletEvent")
+            .ok_or_else(|| invalid("no WalletEvent message"))?;
+
+        let mut by_oneof_number = HashMap::new();
+        for (type_name, _, oneof_number) in envelope {
+            let fields = messages
+                .get(type_name)
+                .ok_or_else(|| invalid(&format!("message {} not defined", type_name)))?
+                .iter()
+                .map(|(field_type, name, number)| {
+                    let kind = match field_type.as_str() {
+                        "string" => FieldKind::String,
+                        "int64" => FieldKind::Int64,
+                        "google.protobuf.Timestamp" => FieldKind::Timestamp,
+                        other => return Err(invalid(&format!("unsupported field type {}", other))),
+                    };
+                    Ok((
+                        *number,
+                        ProtoField {
+                            name: name.clone(),
+                            kind,
+                        },
+                    ))
+                })
+                .collect::<HistoryResult<_>>()?;
+
+            by_oneof_number.insert(
+                *oneof_number,
+                EventMessage {
+                    event_type: screaming_snake(type_name),
+                    fields,
+                },
+            );
+        }
+
+        Ok(Self { by_oneof_number })
+    }
+
+    /// Decode a WalletEvent message into the JSON shape serde expects
+    pub fn decode(&self, mut input: &[u8]) -> HistoryResult<Value> {
+        let mut event = None;
+
+        while !input.is_empty() {
+            let (number, wire_type) = read_tag(&mut input)?;
+            match (self.by_oneof_number.get(&number), wire_type) {
+                // Last one wins, as for any oneof
+                (Some(message), WIRE_LEN) => event = Some((message, read_len(&mut input)?)),
+                _ => skip(wire_type, &mut input)?,
+            }
+        }
+
+        let (message, mut body) = event.ok_or_else(|| malformed("no known event in message"))?;
+
+        let mut obj = Map::new();
+        obj.insert("eventType".to_string(), Value::String(message.event_type.clone()));
+        for field in message.fields.values() {
+            let default = match field.kind {
+                FieldKind::String => Value::String(String::new()),
+                FieldKind::Int64 => Value::from(0),
+                FieldKind::Timestamp => timestamp_value(0, 0)?,
+            };
+            obj.insert(field.name.clone(), default);
+        }
+
+        while !body.is_empty() {
+            let (number, wire_type) = read_tag(&mut body)?;
+            match (message.fields.get(&number), wire_type) {
+                (Some(field), WIRE_VARINT) if field.kind == FieldKind::Int64 => {
+                    let n = read_varint(&mut body)? as i64;
+                    obj.insert(field.name.clone(), Value::from(n));
+                }
+                (Some(field), WIRE_LEN) if field.kind != FieldKind::Int64 => {
+                    let bytes = read_len(&mut body)?;
+                    let value = if field.kind == FieldKind::Timestamp {
+                        decode_timestamp(bytes)?
+                    } else {
+                        Value::String(
+                            String::from_utf8(bytes.to_vec())
+                                .map_err(|_| malformed("string is not UTF-8"))?,
+                        )
+                    };
+                    obj.insert(field.name.clone(), value);
+                }
+                _ => skip(wire_type, &mut body)?,
+            }
+        }
+
+        Ok(Value::Object(obj))
+    }
+}
+
+/// google.protobuf.Timestamp { seconds = 1; nanos = 2; }
+fn decode_timestamp(mut input: &[u8]) -> HistoryResult<Value> {
+    let (mut seconds, mut nanos) = (0i64, 0u32);
+    while !input.is_empty() {
+        match read_tag(&mut input)? {
+            (1, WIRE_VARINT) => seconds = read_varint(&mut input)? as i64,
+            (2, WIRE_VARINT) => nanos = read_varint(&mut input)? as u32,
+            (_, wire_type) => skip(wire_type, &mut input)?,
+        }
+    }
+    timestamp_value(seconds, nanos)
+}
+
+fn timestamp_value(seconds: i64, nanos: u32) -> HistoryResult<Value> {
+    let ts = Utc
+        .timestamp_opt(seconds, nanos)
+        .single()
+        .ok_or_else(|| malformed(&format!("timestamp {}s out of range", seconds)))?;
+    Ok(Value::String(ts.to_rfc3339_opts(SecondsFormat::AutoSi, true)))
+}
+
+/// `Type name = 3;` -> (Type, name, 3)
+fn parse_field(line: &str) -> Option<HistoryResult<(String, String, u32)>> {
+    let (decl, number) = line.strip_suffix(';')?.split_once('=')?;
+    let mut parts = decl.split_whitespace();
+    let (field_type, name) = (parts.next()?, parts.next()?);
+    Some(
+        number
+            .trim()
+            .parse()
+            .map(|n| (field_type.to_string(), name.to_string(), n))
+            .map_err(|_| invalid(&format!("bad field number in '{}'", line))),
+    )
+}
+
+/// WalletCreated -> WALLET_CREATED
+fn screaming_snake(name: &str) -> String {
+    let mut out = String::new();
+    for (i, c) in name.chars().enumerate() {
+        if c.is_uppercase() && i > 0 {
+            out.push('_');
+        }
+        out.push(c.to_ascii_uppercase());
+    }
+    out
+}
+
+fn read_varint(input: &mut &[u8]) -> HistoryResult<u64> {
+    let mut n: u64 = 0;
+    for shift in (0..64).step_by(7) {
+        let (&byte, rest) = input
+            .split_first()
+            .ok_or_else(|| malformed("unexpected end of message"))?;
+        *input = rest;
+        n |= u64::from(byte & 0x7f) << shift;
+        if byte & 0x80 == 0 {
+            return Ok(n);
+        }
+    }
+    Err(malformed("varint longer than 10 bytes"))
+}
+
+fn read_tag(input: &mut &[u8]) -> HistoryResult<(u32, u32)> {
+    let tag = read_varint(input)?;
+    Ok(((tag >> 3) as u32, (tag & 0x7) as u32))
+}
+
+fn read_len<'a>(input: &mut &'a [u8]) -> HistoryResult<&'a [u8]> {
+    let len = read_varint(input)? as usize;
+    take(input, len)
+}
+
+fn take<'a>(input: &mut &'a [u8], n: usize) -> HistoryResult<&'a [u8]> {
+    if input.len() < n {
+        return Err(malformed("unexpected end of message"));
+    }
+    let (head, rest) = input.split_at(n);
+    *input = rest;
+    Ok(head)
+}
+
+/// Skip a field this schema doesn't know (written by a newer producer)
+fn skip(wire_type: u32, input: &mut &[u8]) -> HistoryResult<()> {
+    match wire_type {
+        WIRE_VARINT => read_varint(input).map(|_| ()),
+        WIRE_FIXED64 => take(input, 8).map(|_| ()),
+        WIRE_LEN => read_len(input).map(|_| ()),
+        WIRE_FIXED32 => take(input, 4).map(|_| ()),
+        other => Err(malformed(&format!("unsupported wire type {}", other))),
+    }
+}
+
+fn invalid(what: &str) -> HistoryError {
+    HistoryError::SerializationError(format!("Protobuf schema: {}", what))
+}
+
+fn malformed(what: &str) -> HistoryError {
+    HistoryError::SerializationError(format!("Malformed Protobuf message: {}", what))
+}
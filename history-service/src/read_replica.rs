@@ -0,0 +1,145 @@
+use crate::db_pool::PoolConfig;
+use sqlx::PgPool;
+use std::sync::atomic::{AtomicBool, AtomicI64, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+/// DATABASE_READ_URL and how far behind it may be
+#[derive(Debug, Clone)]
+pub struct ReplicaConfig {
+    /// DATABASE_READ_URL - a streaming replica of DATABASE_URL
+    pub url: String,
+    /// REPLICA_MAX_LAG_MS - further behind than this, queries go to the
+    /// primary until it catches up (default 1000)
+    pub max_lag: Duration,
+    /// REPLICA_PROBE_INTERVAL_SECS - how often the lag is measured (default 5)
+    pub probe_interval: Duration,
+}
+
+impl ReplicaConfig {
+    /// None when DATABASE_READ_URL is unset: queries use the primary
+    pub fn from_env() -> Option<Self> {
+        fn env_or<T: std::str::FromStr>(name: &str, default: T) -> T {
+            std::env::var(name)
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(default)
+        }
+
+        let url = std::env::var("DATABASE_READ_URL").ok().filter(|v| !v.is_empty())?;
+        Some(Self {
+            url,
+            max_lag: Duration::from_millis(env_or("REPLICA_MAX_LAG_MS", 1000)),
+            probe_interval: Duration::from_secs(env_or("REPLICA_PROBE_INTERVAL_SECS", 5).max(1)),
+        })
+    }
+}
+
+/// A read-only replica for the history queries
+///
+/// The consumer writes to the primary; the HTTP, GraphQL and export
+/// queries read here while the replica keeps up.
+///
+/// Staleness:
+/// - History already trails the wallet service by the Kafka hop; the
+///   replica adds at most `max_lag` to that
+/// - The probe measures replay lag every `probe_interval`. Over `max_lag`,
+///   or unreachable, queries fall back to the primary until it's caught up
+/// - An idle primary isn't lag: a replica that has replayed everything it
+///   received counts as 0ms behind
+#[derive(Clone)]
+pub struct ReadReplica {
+    pool: PgPool,
+    max_lag: Duration,
+    probe_interval: Duration,
+    /// Last measured lag in ms; -1 before the first probe or after a failed one
+    lag_ms: Arc<AtomicI64>,
+    usable: Arc<AtomicBool>,
+}
+
+impl ReadReplica {
+    /// Connect with the same pool settings as the primary
+    pub async fn connect(config: &ReplicaConfig, pool: &PoolConfig) -> Result<Self, sqlx::Error> {
+        let pool = pool.connect(&config.url).await?;
+
+        Ok(Self::new(pool, config.max_lag, config.probe_interval))
+    }
+
+    /// Unused until the first `probe` finds it within `max_lag`
+    pub fn new(pool: PgPool, max_lag: Duration, probe_interval: Duration) -> Self {
+        Self {
+            pool,
+            max_lag,
+            probe_interval,
+            lag_ms: Arc::new(AtomicI64::new(-1)),
+            usable: Arc::new(AtomicBool::new(false)),
+        }
+    }
+
+    pub fn pool(&self) -> &PgPool {
+        &self.pool
+    }
+
+    /// Whether queries may go here right now
+    pub fn is_usable(&self) -> bool {
+        self.usable.load(Ordering::Relaxed)
+    }
+
+    /// Lag at the last probe (None: not probed yet, or unreachable)
+    pub fn lag(&self) -> Option<Duration> {
+        let lag_ms = self.lag_ms.load(Ordering::Relaxed);
+        (lag_ms >= 0).then(|| Duration::from_millis(lag_ms as u64))
+    }
+
+    /// Measure the replay lag and decide whether queries may use the replica
+    pub async fn probe(&self) {
+        let lag = tokio::time::timeout(self.probe_interval, self.measure_lag()).await;
+        let lag_ms = match lag {
+            Ok(Ok(lag_ms)) => lag_ms,
+            Ok(Err(e)) => {
+                tracing::warn!(error = %e, "Read replica probe failed - querying the primary");
+                -1
+            }
+            Err(_) => {
+                tracing::warn!("Read replica probe timed out - querying the primary");
+                -1
+            }
+        };
+
+        let usable = lag_ms >= 0 && lag_ms as u128 <= self.max_lag.as_millis();
+        let was_usable = self.usable.swap(usable, Ordering::Relaxed);
+        self.lag_ms.store(lag_ms, Ordering::Relaxed);
+        if was_usable && !usable && lag_ms >= 0 {
+            tracing::warn!(lag_ms, "Read replica lagging - querying the primary");
+        } else if !was_usable && usable {
+            tracing::info!(lag_ms, "Read replica in use");
+        }
+    }
+
+    /// Run the probe forever in a background task
+    pub fn spawn_probe(self) {
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(self.probe_interval);
+            loop {
+                interval.tick().await;
+                self.probe().await;
+            }
+        });
+    }
+
+    async fn measure_lag(&self) -> Result<i64, sqlx::Error> {
+        // NULL on a server that isn't replaying WAL (a primary) - no lag
+        let lag_ms = sqlx::query_scalar::<_, Option<f64>>(
+            r#"
+            SELECT CASE
+                WHEN pg_last_wal_receive_lsn() = pg_last_wal_replay_lsn() THEN 0
+                ELSE EXTRACT(EPOCH FROM now() - pg_last_xact_replay_timestamp()) * 1000
+            END::FLOAT8
+            "#,
+        )
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(lag_ms.unwrap_or(0.0).max(0.0) as i64)
+    }
+}
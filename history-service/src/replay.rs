@@ -0,0 +1,237 @@
+use crate::cache::{CacheScope, ResponseCache};
+use crate::errors::{HistoryError, HistoryResult};
+use crate::kafka_security::client_config;
+use crate::models::EventEnvelope;
+use crate::repository::EventRepository;
+use chrono::{DateTime, Utc};
+use rdkafka::producer::{FutureProducer, FutureRecord, Producer};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use std::time::Duration;
+use utoipa::ToSchema;
+
+/// Most events one replay request handles (page through with `next_from`)
+pub const MAX_REPLAY_EVENTS: i64 = 10_000;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ReplayMode {
+    /// Send the stored events to the replay topic for downstream consumers
+    #[default]
+    Republish,
+    /// Run them through our own write path again (missing legs, sequence
+    /// gaps, cache) - already-stored rows are skipped as duplicates
+    Reprocess,
+}
+
+/// Body of POST /admin/replay
+///
+/// ```json
+/// {"from": "2025-01-29T00:00:00Z", "to": "2025-01-30T00:00:00Z",
+///  "wallet_id": "abc-123", "mode": "republish", "limit": 1000}
+/// ```
+///
+/// The window is by when events were stored, `from` inclusive, `to` exclusive.
+#[derive(Debug, Clone, Deserialize, ToSchema)]
+pub struct ReplayRequest {
+    pub from: DateTime<Utc>,
+    pub to: DateTime<Utc>,
+    pub wallet_id: Option<String>,
+    #[serde(default)]
+    pub mode: ReplayMode,
+    /// Default and maximum MAX_REPLAY_EVENTS
+    pub limit: Option<i64>,
+}
+
+impl ReplayRequest {
+    /// Check the window; returns how many events to take
+    pub fn validate(&self) -> HistoryResult<i64> {
+        if self.from >= self.to {
+            return Err(HistoryError::InvalidRequest(
+                "`from` must be before `to`".to_string(),
+            ));
+        }
+        match self.limit {
+            None => Ok(MAX_REPLAY_EVENTS),
+            Some(limit) if (1..=MAX_REPLAY_EVENTS).contains(&limit) => Ok(limit),
+            Some(_) => Err(HistoryError::InvalidRequest(format!(
+                "`limit` must be between 1 and {}",
+                MAX_REPLAY_EVENTS
+            ))),
+        }
+    }
+}
+
+/// What a replay did
+#[derive(Debug, Clone, Default, Serialize, ToSchema)]
+pub struct ReplayReport {
+    pub mode: ReplayMode,
+    /// Events replayed (a transfer counts once)
+    pub events: usize,
+    /// Reprocess only: rows that were missing and got stored
+    pub rows_stored: usize,
+    /// Why the replay stopped early, if an event failed
+    pub failed: Option<String>,
+    /// More to do: send the same request again with `from` set to this
+    pub next_from: Option<DateTime<Utc>>,
+}
+
+/// Producer for the replay topic (wallet-events-replay)
+///
+/// Why not the main topic?
+/// - Every consumer of wallet-events would get the replay, not just the one
+///   being backfilled
+/// - A separate topic lets the team that needs it read it on their terms
+pub struct ReplayProducer {
+    producer: FutureProducer,
+    topic: String,
+}
+
+impl ReplayProducer {
+    pub fn new(brokers: &str, topic: String) -> HistoryResult<Self> {
+        let producer: FutureProducer = client_config(brokers)
+            .set("message.timeout.ms", "5000")
+            .set("acks", "all")
+            .set("enable.idempotence", "true")
+            .create()
+            .map_err(|e| HistoryError::KafkaError(format!("Failed to create replay producer: {}", e)))?;
+
+        Ok(Self { producer, topic })
+    }
+
+    pub fn topic(&self) -> &str {
+        &self.topic
+    }
+
+    /// How many brokers answer a metadata request for the replay topic
+    ///
+    /// Blocks the thread - call it from `spawn_blocking`.
+    pub fn fetch_broker_count(&self, timeout: Duration) -> HistoryResult<usize> {
+        self.producer
+            .client()
+            .fetch_metadata(Some(&self.topic), timeout)
+            .map(|metadata| metadata.brokers().len())
+            .map_err(|e| HistoryError::KafkaError(format!("Failed to fetch metadata: {}", e)))
+    }
+
+    /// Publish one event as JSON, keyed by wallet like the original
+    pub async fn publish(&self, key: &str, payload: &str) -> HistoryResult<()> {
+        let record = FutureRecord::to(&self.topic).key(key).payload(payload);
+
+        self.producer
+            .send(record, Duration::from_secs(5))
+            .await
+            .map(|_| ())
+            .map_err(|(e, _)| HistoryError::KafkaError(format!("Failed to publish replayed event: {}", e)))
+    }
+}
+
+/// Replays stored events for targeted backfills (POST /admin/replay)
+///
+/// Why from the database rather than the topic?
+/// - The topic may have aged out; `transaction_events` keeps the full event
+///   (event_data) forever
+/// - A window and a wallet can be picked by query, without reading every
+///   partition from some offset
+///
+/// Republished events are the stored envelope as JSON (same IDs and
+/// sequence as the original), in the order they were stored. Consumers
+/// should dedupe by `event_id`.
+pub struct EventReplayer {
+    repository: EventRepository,
+    cache: Arc<ResponseCache>,
+    producer: ReplayProducer,
+}
+
+impl EventReplayer {
+    pub fn new(repository: EventRepository, cache: Arc<ResponseCache>, producer: ReplayProducer) -> Self {
+        Self {
+            repository,
+            cache,
+            producer,
+        }
+    }
+
+    pub fn topic(&self) -> &str {
+        self.producer.topic()
+    }
+
+    /// Kafka reachability, through the replay producer (health check)
+    pub fn fetch_broker_count(&self, timeout: Duration) -> HistoryResult<usize> {
+        self.producer.fetch_broker_count(timeout)
+    }
+
+    /// Replay one page of the window; stops at the first failure
+    pub async fn replay(&self, request: &ReplayRequest) -> HistoryResult<ReplayReport> {
+        let limit = request.validate()?;
+        let mut events = self
+            .repository
+            .get_stored_envelopes(request.from, request.to, request.wallet_id.as_deref(), limit + 1)
+            .await?;
+
+        let mut report = ReplayReport {
+            mode: request.mode,
+            ..ReplayReport::default()
+        };
+        if events.len() as i64 > limit {
+            report.next_from = events.pop().map(|(_, stored_at)| stored_at);
+        }
+
+        tracing::warn!(
+            mode = ?request.mode,
+            from = %request.from,
+            to = %request.to,
+            wallet_id = ?request.wallet_id,
+            events = events.len(),
+            "Replaying stored events"
+        );
+
+        for (event_data, stored_at) in events {
+            let envelope: EventEnvelope = serde_json::from_value(event_data)
+                .map_err(|e| HistoryError::SerializationError(e.to_string()))?;
+
+            let result = match request.mode {
+                ReplayMode::Republish => self.republish(&envelope).await,
+                ReplayMode::Reprocess => self.reprocess(&envelope).await,
+            };
+
+            match result {
+                Ok(rows_stored) => {
+                    report.events += 1;
+                    report.rows_stored += rows_stored;
+                }
+                Err(e) => {
+                    tracing::error!(error = %e, event_id = %envelope.ids.event_id, "Replay stopped");
+                    report.failed = Some(e.to_string());
+                    // Resume from the failed event (the ones before it may
+                    // be replayed again - harmless)
+                    report.next_from = Some(stored_at);
+                    break;
+                }
+            }
+        }
+
+        Ok(report)
+    }
+
+    async fn republish(&self, envelope: &EventEnvelope) -> HistoryResult<usize> {
+        let payload = serde_json::to_string(envelope)
+            .map_err(|e| HistoryError::SerializationError(e.to_string()))?;
+        self.producer.publish(envelope.event.wallet_id(), &payload).await?;
+        Ok(0)
+    }
+
+    async fn reprocess(&self, envelope: &EventEnvelope) -> HistoryResult<usize> {
+        let stored = self.repository.store_envelope(envelope).await?;
+        for row in &stored {
+            self.cache.invalidate(&CacheScope::Wallet(row.wallet_id.clone()));
+            self.cache.invalidate(&CacheScope::User(row.user_id.clone()));
+        }
+        if let Some(sequence) = envelope.sequence() {
+            self.repository
+                .track_sequence(envelope.event.wallet_id(), sequence)
+                .await?;
+        }
+        Ok(stored.len())
+    }
+}
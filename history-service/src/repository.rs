@@ -1,224 +1,1147 @@
+#[cfg(feature = "clickhouse")]
+use crate::analytics::ClickHouse;
+use crate::balance::{CREDIT_TYPES, DEBIT_TYPES};
+use crate::cold_storage::EventArchive;
+use crate::db_pool::PoolStats;
 use crate::errors::{HistoryError, HistoryResult};
-use crate::models::{TransactionEvent, WalletEvent};
+use crate::filter::{HistoryFilter, WalletSelection};
+use crate::models::{EventEnvelope, TransactionEvent, WalletEvent};
+use crate::pagination::{Cursor, Page};
+use crate::read_replica::ReadReplica;
+use crate::sequence::{Position, SequenceGap, SequenceOutcome, WalletSequenceStatus};
+use crate::summary::{whole_days, SummaryPeriod, SummaryRow};
+use chrono::{DateTime, Utc};
+use futures_util::stream::{BoxStream, StreamExt, TryStreamExt};
 use rust_decimal::Decimal;
 use sqlx::PgPool;
 use uuid::Uuid;
 
+/// One page of a wallet's events (see `get_wallet_history`)
+///
+/// Every bound is an index condition on idx_transaction_events_wallet_time,
+/// even in a generic plan: a missing cursor or window end is an infinite
+/// bound, not an `IS NULL OR` the planner can only filter with.
+pub const WALLET_HISTORY_PAGE: &str = include_str!("../queries/wallet_history_page.sql");
+
+/// One page of a user's events (see `get_user_activity`), on
+/// idx_transaction_events_user_time the same way
+pub const USER_ACTIVITY_PAGE: &str = include_str!("../queries/user_activity_page.sql");
+
 /// Repository for transaction event operations
 #[derive(Clone)]
 pub struct EventRepository {
     pool: PgPool,
+    queries: Option<PgPool>,
+    replica: Option<ReadReplica>,
+    #[cfg(feature = "clickhouse")]
+    analytics: Option<ClickHouse>,
 }
 
 impl EventRepository {
     pub fn new(pool: PgPool) -> Self {
-        Self { pool }
+        Self {
+            pool,
+            queries: None,
+            replica: None,
+            #[cfg(feature = "clickhouse")]
+            analytics: None,
+        }
+    }
+
+    /// Run the history queries on their own pool (see db_pool.rs; they
+    /// share the consumer's by default)
+    pub fn with_query_pool(mut self, queries: PgPool) -> Self {
+        self.queries = Some(queries);
+        self
+    }
+
+    /// Run the history queries on a replica while it keeps up (the
+    /// consumer always writes to the primary)
+    pub fn with_read_replica(mut self, replica: ReadReplica) -> Self {
+        self.replica = Some(replica);
+        self
+    }
+
+    /// Copy stored events to ClickHouse and run the summaries there
+    /// (falling back to Postgres when it fails)
+    #[cfg(feature = "clickhouse")]
+    pub fn with_analytics(mut self, analytics: ClickHouse) -> Self {
+        self.analytics = Some(analytics);
+        self
+    }
+
+    /// Connections in use per pool, for /metrics
+    pub fn pool_stats(&self) -> Vec<PoolStats> {
+        let mut stats = vec![PoolStats::of("primary", &self.pool)];
+        if let Some(queries) = &self.queries {
+            stats.push(PoolStats::of("queries", queries));
+        }
+        if let Some(replica) = &self.replica {
+            stats.push(PoolStats::of("replica", replica.pool()));
+        }
+        stats
+    }
+
+    /// Where a query goes: the replica while it's within its lag limit,
+    /// else the query pool
+    fn reader(&self) -> &PgPool {
+        match &self.replica {
+            Some(replica) if replica.is_usable() => replica.pool(),
+            _ => self.queries.as_ref().unwrap_or(&self.pool),
+        }
     }
 
-    /// Store an event from Kafka
-    /// 
+    /// Cheap round-trip to the database (health check)
+    pub async fn ping(&self) -> HistoryResult<()> {
+        sqlx::query("SELECT 1").execute(&self.pool).await?;
+        Ok(())
+    }
+
+    /// Every migration the database has run: `(version, success)`
+    pub async fn applied_migrations(&self) -> HistoryResult<Vec<(i64, bool)>> {
+        let applied = sqlx::query!("SELECT version, success FROM _sqlx_migrations ORDER BY version")
+            .fetch_all(&self.pool)
+            .await?;
+
+        Ok(applied.into_iter().map(|row| (row.version, row.success)).collect())
+    }
+
+    /// Store an event the way its type calls for (one row, two legs, or
+    /// nothing for declines); returns the rows actually inserted (for
+    /// USER_ANONYMIZED, the rows rewritten)
+    ///
+    /// Shared by the consumer and `POST /admin/replay`'s reprocess mode.
+    pub async fn store_envelope(&self, envelope: &EventEnvelope) -> HistoryResult<Vec<TransactionEvent>> {
+        if let WalletEvent::UserAnonymized { user_id, anonymized_user_id, .. } = &envelope.event {
+            // Not stored itself (it names the user); rewrites their rows
+            let events = self.anonymize_user(user_id, anonymized_user_id).await?;
+            tracing::info!(
+                anonymized_user_id = %anonymized_user_id,
+                event_count = events.len(),
+                "User anonymized"
+            );
+            return Ok(events);
+        }
+
+        self.store_envelopes(&[envelope]).await
+    }
+
+    /// Store many events with one multi-row INSERT; returns the rows
+    /// actually inserted, in no particular order
+    ///
     /// CRITICAL: This must be idempotent!
-    /// - Uses transaction_id to prevent duplicates
-    /// - If event with same transaction_id exists, skip it
-    /// 
+    /// - A unique index on (transaction_id, event_type) rejects duplicates
+    /// - INSERT ... ON CONFLICT DO NOTHING skips them atomically, so two
+    ///   workers racing on the same event can't both store it (a separate
+    ///   SELECT-then-INSERT check could let both through) - and a batch
+    ///   holding the same event twice stores it once
+    ///
     /// Why? Kafka delivers at-least-once, so we might see the same event multiple times
-    pub async fn store_event(&self, event: &WalletEvent) -> HistoryResult<Option<TransactionEvent>> {
-        let event_id = Uuid::new_v4().to_string();
-        let wallet_id = event.wallet_id().to_string();
-        let user_id = event.user_id().to_string();
-        let amount = event.amount();
-        let event_type = event.event_type().to_string();
-        let transaction_id = event.transaction_id();
-        
-        // Serialize full event as JSON for debugging
-        let event_data = serde_json::to_value(event)
-            .map_err(|e| HistoryError::SerializationError(e.to_string()))?;
-
-        // Check if we've already processed this event (idempotency check)
-        if let Some(ref txn_id) = transaction_id {
-            let exists = sqlx::query_scalar::<_, bool>(
-                r#"
-                SELECT EXISTS(
-                    SELECT 1 FROM transaction_events 
-                    WHERE transaction_id = $1
-                )
-                "#
-            )
-            .bind(txn_id)
-            .fetch_one(&self.pool)
-            .await?;
+    ///
+    /// One statement, so the batch is stored entirely or not at all. USER_ANONYMIZED
+    /// rewrites rows rather than adding them - use `store_envelope` for it.
+    pub async fn store_envelopes(&self, envelopes: &[&EventEnvelope]) -> HistoryResult<Vec<TransactionEvent>> {
+        let mut rows = Vec::new();
+        for envelope in envelopes {
+            rows.extend(new_rows(envelope)?);
+        }
+        if rows.is_empty() {
+            return Ok(Vec::new());
+        }
 
-            if exists {
-                tracing::info!(
-                    transaction_id = %txn_id,
-                    event_type = %event_type,
-                    "Event already processed, skipping (idempotent)"
-                );
-                return Ok(None); // Already processed
-            }
+        let mut columns = NewEventColumns::default();
+        for row in &rows {
+            columns.push(row);
         }
 
-        // Store the event
-        let stored_event = sqlx::query_as::<_, TransactionEvent>(
+        // created_at: the event's own time for legs, NOW() otherwise. What
+        // was inserted is added to the daily totals in the same statement
+        let stored = sqlx::query_as!(
+            TransactionEvent,
             r#"
-            INSERT INTO transaction_events 
-                (id, wallet_id, user_id, amount, event_type, transaction_id, event_data, created_at)
-            VALUES ($1, $2, $3, $4, $5, $6, $7, NOW())
-            RETURNING id, wallet_id, user_id, amount, event_type, transaction_id, created_at, event_data
-            "#
+            WITH inserted AS (
+                INSERT INTO transaction_events
+                    (id, wallet_id, user_id, amount, event_type, transaction_id, event_data, created_at,
+                     event_id, correlation_id, causation_id)
+                SELECT id, wallet_id, user_id, amount, event_type, transaction_id, event_data,
+                    COALESCE(created_at, NOW()), event_id, correlation_id, causation_id
+                FROM UNNEST(
+                    $1::TEXT[], $2::TEXT[], $3::TEXT[], $4::DECIMAL[], $5::TEXT[], $6::TEXT[],
+                    $7::JSONB[], $8::TIMESTAMPTZ[], $9::TEXT[], $10::TEXT[], $11::TEXT[]
+                ) AS e (id, wallet_id, user_id, amount, event_type, transaction_id, event_data,
+                    created_at, event_id, correlation_id, causation_id)
+                ON CONFLICT DO NOTHING
+                RETURNING id, wallet_id, user_id, amount, event_type, transaction_id, created_at, event_data,
+                    event_id, correlation_id, causation_id
+            ),
+            summed AS (
+                INSERT INTO user_daily_summary (user_id, wallet_id, day, event_type, event_count, amount)
+                SELECT user_id, wallet_id, (created_at AT TIME ZONE 'UTC')::DATE, event_type, COUNT(*), SUM(amount)
+                FROM inserted
+                GROUP BY 1, 2, 3, 4
+                ON CONFLICT (user_id, day, wallet_id, event_type) DO UPDATE SET
+                    event_count = user_daily_summary.event_count + EXCLUDED.event_count,
+                    amount = user_daily_summary.amount + EXCLUDED.amount
+            )
+            SELECT * FROM inserted
+            "#,
+            &columns.id,
+            &columns.wallet_id,
+            &columns.user_id,
+            &columns.amount,
+            &columns.event_type,
+            &columns.transaction_id as &[Option<String>],
+            &columns.event_data,
+            &columns.created_at as &[Option<DateTime<Utc>>],
+            &columns.event_id as &[Option<String>],
+            &columns.correlation_id as &[Option<String>],
+            &columns.causation_id as &[Option<String>]
         )
-        .bind(&event_id)
-        .bind(&wallet_id)
-        .bind(&user_id)
-        .bind(amount)
-        .bind(&event_type)
-        .bind(transaction_id.as_ref())
-        .bind(&event_data)
-        .fetch_one(&self.pool)
+        .fetch_all(&self.pool)
         .await?;
 
+        if stored.len() < rows.len() {
+            tracing::info!(
+                skipped = rows.len() - stored.len(),
+                "Events already processed, skipping (idempotent)"
+            );
+        }
+
+        // Postgres has them whatever happens here: a failed copy is left
+        // for --analytics-backfill rather than failing the batch
+        #[cfg(feature = "clickhouse")]
+        if let Some(analytics) = &self.analytics {
+            if let Err(e) = analytics.insert_events(&stored).await {
+                tracing::error!(error = %e, events = stored.len(), "Failed to copy events to ClickHouse");
+            }
+        }
         tracing::info!(
-            event_id = %event_id,
-            wallet_id = %wallet_id,
-            event_type = %event_type,
-            "Event stored successfully"
+            envelopes = envelopes.len(),
+            stored = stored.len(),
+            "Events stored"
         );
 
-        Ok(Some(stored_event))
+        Ok(stored)
     }
 
-    /// Handle TRANSFER_COMPLETED event specially
-    /// 
-    /// Transfers affect TWO wallets, so we create TWO events:
-    /// 1. Outgoing event for sender
-    /// 2. Incoming event for receiver
-    pub async fn store_transfer_events(&self, event: &WalletEvent) -> HistoryResult<Vec<TransactionEvent>> {
-        if let WalletEvent::TransferCompleted {
-            from_wallet_id,
-            from_user_id,
-            to_wallet_id,
-            to_user_id,
-            amount,
-            reference_id,
-            timestamp,
-        } = event
-        {
-            let event_data = serde_json::to_value(event)
-                .map_err(|e| HistoryError::SerializationError(e.to_string()))?;
+    /// Replace a user's ID with its pseudonym (USER_ANONYMIZED); returns the
+    /// rows rewritten
+    ///
+    /// Covers the user's own rows and every row whose stored event names
+    /// them - the other leg of a transfer carries both user IDs. Amounts and
+    /// types stay as they were. Running it twice finds nothing the second
+    /// time, so redelivery is harmless.
+    pub async fn anonymize_user(
+        &self,
+        user_id: &str,
+        anonymized_user_id: &str,
+    ) -> HistoryResult<Vec<TransactionEvent>> {
+        // event_data as text: swap the ID only where it's a whole string
+        let quoted = |id: &str| serde_json::to_string(id).unwrap_or_default();
 
-            // Check if we've already processed this transfer
-            let exists = sqlx::query_scalar::<_, bool>(
-                r#"
-                SELECT EXISTS(
-                    SELECT 1 FROM transaction_events 
-                    WHERE transaction_id = $1
-                )
-                "#
+        // The user's daily totals move to the pseudonym in the same statement
+        let events = sqlx::query_as!(
+            TransactionEvent,
+            r#"
+            WITH moved AS (
+                UPDATE user_daily_summary SET user_id = $2 WHERE user_id = $1
             )
-            .bind(reference_id)
-            .fetch_one(&self.pool)
-            .await?;
+            UPDATE transaction_events
+            SET user_id = CASE WHEN user_id = $1 THEN $2 ELSE user_id END,
+                event_data = replace(event_data::text, $3, $4)::jsonb
+            WHERE user_id = $1 OR strpos(event_data::text, $3) > 0
+            RETURNING id, wallet_id, user_id, amount, event_type, transaction_id, created_at, event_data,
+                event_id, correlation_id, causation_id
+            "#,
+            user_id,
+            anonymized_user_id,
+            quoted(user_id),
+            quoted(anonymized_user_id)
+        )
+        .fetch_all(&self.pool)
+        .await?;
 
-            if exists {
-                tracing::info!(
-                    reference_id = %reference_id,
-                    "Transfer already processed, skipping"
-                );
-                return Ok(vec![]);
+        // Unlike a missed insert, a missed rewrite must be retried: the
+        // message fails and comes back, finding nothing left in Postgres
+        #[cfg(feature = "clickhouse")]
+        if let Some(analytics) = &self.analytics {
+            analytics.anonymize_user(user_id, anonymized_user_id).await?;
+        }
+
+        Ok(events)
+    }
+
+    /// Empty the read model before a rebuild (`--rebuild`)
+    ///
+    /// Everything in it is derived from the topic, so nothing is lost that
+    /// the replay won't restore.
+    pub async fn truncate_history(&self) -> HistoryResult<()> {
+        sqlx::query!(
+            "TRUNCATE TABLE transaction_events, user_daily_summary, wallet_sequences, sequence_gaps"
+        )
+        .execute(&self.pool)
+        .await?;
+        #[cfg(feature = "clickhouse")]
+        if let Some(analytics) = &self.analytics {
+            analytics.truncate().await?;
+        }
+
+        tracing::warn!("transaction_events truncated");
+        Ok(())
+    }
+
+    /// Record an event's `sequence` for its wallet, noting gaps
+    ///
+    /// The wallet's row is locked for the check, so the main-topic worker
+    /// and a retry tier handling the same wallet can't interleave.
+    pub async fn track_sequence(&self, wallet_id: &str, sequence: i64) -> HistoryResult<SequenceOutcome> {
+        let mut tx = self.pool.begin().await?;
+
+        // Creates the row on the wallet's first numbered event; locks it either way
+        let last_seen = sqlx::query_scalar!(
+            r#"
+            INSERT INTO wallet_sequences (wallet_id, last_sequence)
+            VALUES ($1, 0)
+            ON CONFLICT (wallet_id) DO UPDATE SET wallet_id = EXCLUDED.wallet_id
+            RETURNING last_sequence
+            "#,
+            wallet_id
+        )
+        .fetch_one(&mut *tx)
+        .await?;
+
+        let outcome = match Position::of(last_seen, sequence) {
+            Position::Behind => {
+                let filled = sqlx::query!(
+                    r#"
+                    UPDATE sequence_gaps SET resolved_at = NOW()
+                    WHERE wallet_id = $1 AND sequence = $2 AND resolved_at IS NULL
+                    "#,
+                    wallet_id,
+                    sequence
+                )
+                .execute(&mut *tx)
+                .await?;
+
+                if filled.rows_affected() > 0 {
+                    SequenceOutcome::GapFilled
+                } else {
+                    SequenceOutcome::Duplicate
+                }
+            }
+            position => {
+                let outcome = match position {
+                    Position::Ahead { first_missing, last_missing } => {
+                        sqlx::query!(
+                            r#"
+                            INSERT INTO sequence_gaps (wallet_id, sequence)
+                            SELECT $1, missing FROM generate_series($2::BIGINT, $3::BIGINT) AS missing
+                            ON CONFLICT DO NOTHING
+                            "#,
+                            wallet_id,
+                            first_missing,
+                            last_missing
+                        )
+                        .execute(&mut *tx)
+                        .await?;
+
+                        SequenceOutcome::GapOpened { first_missing, last_missing }
+                    }
+                    _ => SequenceOutcome::InOrder,
+                };
+
+                sqlx::query!(
+                    "UPDATE wallet_sequences SET last_sequence = $2, updated_at = NOW() WHERE wallet_id = $1",
+                    wallet_id,
+                    sequence
+                )
+                .execute(&mut *tx)
+                .await?;
+
+                outcome
             }
+        };
+
+        tx.commit().await?;
+        Ok(outcome)
+    }
+
+    /// Highest sequence stored for a wallet and the numbers still missing
+    pub async fn get_wallet_sequence(&self, wallet_id: &str) -> HistoryResult<WalletSequenceStatus> {
+        let last_sequence = sqlx::query_scalar!(
+            "SELECT last_sequence FROM wallet_sequences WHERE wallet_id = $1",
+            wallet_id
+        )
+        .fetch_optional(self.reader())
+        .await?
+        .unwrap_or(0);
+
+        let open_gaps = sqlx::query_scalar!(
+            r#"
+            SELECT sequence FROM sequence_gaps
+            WHERE wallet_id = $1 AND resolved_at IS NULL
+            ORDER BY sequence
+            "#,
+            wallet_id
+        )
+        .fetch_all(self.reader())
+        .await?;
+
+        Ok(WalletSequenceStatus {
+            wallet_id: wallet_id.to_string(),
+            last_sequence,
+            open_gaps,
+        })
+    }
+
+    /// Missing events across all wallets, oldest first
+    pub async fn get_open_sequence_gaps(&self, limit: i64) -> HistoryResult<Vec<SequenceGap>> {
+        let gaps = sqlx::query_as!(
+            SequenceGap,
+            r#"
+            SELECT wallet_id, sequence, detected_at
+            FROM sequence_gaps
+            WHERE resolved_at IS NULL
+            ORDER BY detected_at, wallet_id, sequence
+            LIMIT $1
+            "#,
+            limit
+        )
+        .fetch_all(self.reader())
+        .await?;
+
+        Ok(gaps)
+    }
+
+    /// One page of a wallet's events, newest first
+    ///
+    /// `after`: the previous page's cursor (None = from the newest event).
+    /// Keyset on (created_at, id), so pages are stable as new events arrive.
+    /// `filter` narrows it to a time window and/or event types.
+    pub async fn get_wallet_history(
+        &self,
+        wallet_id: &str,
+        filter: &HistoryFilter,
+        after: Option<&Cursor>,
+        limit: i64,
+    ) -> HistoryResult<Page<TransactionEvent>> {
+        let event_types = filter.event_types();
+        let events = sqlx::query_file_as!(
+            TransactionEvent,
+            "queries/wallet_history_page.sql",
+            wallet_id,
+            after.map(|cursor| cursor.created_at),
+            after.map(|cursor| cursor.id.as_str()),
+            filter.from,
+            filter.to,
+            event_types.as_deref(),
+            limit + 1
+        )
+        .fetch_all(self.reader())
+        .await?;
+
+        Ok(Page::from_rows(events, limit))
+    }
+
+    /// All of a wallet's events matching `filter`, oldest first, as a stream
+    ///
+    /// Rows are decoded as Postgres sends them, so memory stays flat however
+    /// long the history is. Holds a pool connection until the stream ends.
+    pub fn stream_wallet_history<'a>(
+        &'a self,
+        wallet_id: &'a str,
+        filter: &'a HistoryFilter,
+    ) -> BoxStream<'a, HistoryResult<TransactionEvent>> {
+        let event_types = filter.event_types();
+        sqlx::query_as!(
+            TransactionEvent,
+            r#"
+            SELECT id, wallet_id, user_id, amount, event_type, transaction_id, created_at, event_data,
+                event_id, correlation_id, causation_id
+            FROM transaction_events
+            WHERE wallet_id = $1
+              AND created_at >= COALESCE($2::TIMESTAMPTZ, '-infinity')
+              AND created_at < COALESCE($3::TIMESTAMPTZ, 'infinity')
+              AND ($4::TEXT[] IS NULL OR event_type = ANY($4))
+            ORDER BY created_at ASC, id ASC
+            "#,
+            wallet_id,
+            filter.from,
+            filter.to,
+            event_types.as_deref()
+        )
+        .fetch(self.reader())
+        .map_err(HistoryError::from)
+        .boxed()
+    }
+
+    /// All of a user's events (every wallet) matching `filter`, oldest first
+    pub fn stream_user_activity<'a>(
+        &'a self,
+        user_id: &'a str,
+        filter: &'a HistoryFilter,
+    ) -> BoxStream<'a, HistoryResult<TransactionEvent>> {
+        let event_types = filter.event_types();
+        sqlx::query_as!(
+            TransactionEvent,
+            r#"
+            SELECT id, wallet_id, user_id, amount, event_type, transaction_id, created_at, event_data,
+                event_id, correlation_id, causation_id
+            FROM transaction_events
+            WHERE user_id = $1
+              AND created_at >= COALESCE($2::TIMESTAMPTZ, '-infinity')
+              AND created_at < COALESCE($3::TIMESTAMPTZ, 'infinity')
+              AND ($4::TEXT[] IS NULL OR event_type = ANY($4))
+            ORDER BY created_at ASC, id ASC
+            "#,
+            user_id,
+            filter.from,
+            filter.to,
+            event_types.as_deref()
+        )
+        .fetch(self.reader())
+        .map_err(HistoryError::from)
+        .boxed()
+    }
+
+    /// Whose wallet this is, going by its events (None = no events yet)
+    pub async fn find_wallet_owner(&self, wallet_id: &str) -> HistoryResult<Option<String>> {
+        let owner = sqlx::query_scalar!(
+            "SELECT user_id FROM transaction_events WHERE wallet_id = $1 LIMIT 1",
+            wallet_id
+        )
+        .fetch_optional(self.reader())
+        .await?;
+
+        Ok(owner)
+    }
 
-            let mut events = Vec::new();
+    /// The wallets a user has events for, oldest wallet first
+    ///
+    /// Pots included - history only knows a wallet through its events, so a
+    /// wallet with none yet (its WALLET_CREATED not consumed) isn't listed.
+    pub async fn get_user_wallet_ids(&self, user_id: &str) -> HistoryResult<Vec<String>> {
+        let wallet_ids = sqlx::query_scalar!(
+            r#"
+            SELECT wallet_id
+            FROM transaction_events
+            WHERE user_id = $1
+            GROUP BY wallet_id
+            ORDER BY MIN(created_at), wallet_id
+            "#,
+            user_id
+        )
+        .fetch_all(self.reader())
+        .await?;
+
+        Ok(wallet_ids)
+    }
+
+    /// One page of a user's events (across all their wallets, or just
+    /// `wallets`), newest first
+    pub async fn get_user_activity(
+        &self,
+        user_id: &str,
+        filter: &HistoryFilter,
+        wallets: &WalletSelection,
+        after: Option<&Cursor>,
+        limit: i64,
+    ) -> HistoryResult<Page<TransactionEvent>> {
+        let event_types = filter.event_types();
+        let wallet_ids = wallets.wallet_ids();
+        let events = sqlx::query_file_as!(
+            TransactionEvent,
+            "queries/user_activity_page.sql",
+            user_id,
+            after.map(|cursor| cursor.created_at),
+            after.map(|cursor| cursor.id.as_str()),
+            filter.from,
+            filter.to,
+            event_types.as_deref(),
+            wallet_ids.as_deref(),
+            limit + 1
+        )
+        .fetch_all(self.reader())
+        .await?;
+
+        Ok(Page::from_rows(events, limit))
+    }
 
-            // Event 1: Outgoing from sender
-            let out_event_id = Uuid::new_v4().to_string();
-            let out_event = sqlx::query_as::<_, TransactionEvent>(
+    /// Event counts and summed amounts per (period, event type), newest
+    /// period first - the rows summary.rs folds into a summary
+    ///
+    /// From ClickHouse when it's configured (the `clickhouse` feature). Else
+    /// from the daily totals when the window is whole UTC days (or open);
+    /// otherwise the window's events are grouped directly.
+    pub async fn get_wallet_summary(
+        &self,
+        wallet_id: &str,
+        period: SummaryPeriod,
+        filter: &HistoryFilter,
+    ) -> HistoryResult<Vec<SummaryRow>> {
+        #[cfg(feature = "clickhouse")]
+        if let Some(analytics) = &self.analytics {
+            match analytics.wallet_summary(wallet_id, period, filter).await {
+                Ok(rows) => return Ok(rows),
+                Err(e) => tracing::warn!(error = %e, "ClickHouse summary failed, using Postgres"),
+            }
+        }
+
+        let event_types = filter.event_types();
+        if let Some((from, to)) = whole_days(filter) {
+            let rows = sqlx::query!(
                 r#"
-                INSERT INTO transaction_events 
-                    (id, wallet_id, user_id, amount, event_type, transaction_id, event_data, created_at)
-                VALUES ($1, $2, $3, $4, 'TRANSFER_OUT', $5, $6, $7)
-                RETURNING id, wallet_id, user_id, amount, event_type, transaction_id, created_at, event_data
-                "#
+                SELECT
+                    date_trunc($2, day::TIMESTAMP) AT TIME ZONE 'UTC' AS "period_start!",
+                    event_type,
+                    SUM(event_count)::BIGINT AS "event_count!",
+                    SUM(amount) AS "amount!"
+                FROM user_daily_summary
+                WHERE wallet_id = $1
+                  AND ($3::DATE IS NULL OR day >= $3)
+                  AND ($4::DATE IS NULL OR day < $4)
+                  AND ($5::TEXT[] IS NULL OR event_type = ANY($5))
+                GROUP BY 1, 2
+                ORDER BY 1 DESC, 2
+                "#,
+                wallet_id,
+                period.as_sql(),
+                from,
+                to,
+                event_types.as_deref()
             )
-            .bind(&out_event_id)
-            .bind(from_wallet_id)
-            .bind(from_user_id)
-            .bind(amount)
-            .bind(reference_id)
-            .bind(&event_data)
-            .bind(timestamp)
-            .fetch_one(&self.pool)
+            .fetch_all(self.reader())
             .await?;
 
-            events.push(out_event);
+            return Ok(rows
+                .into_iter()
+                .map(|row| (row.period_start, row.event_type, row.event_count, row.amount))
+                .collect());
+        }
+
+        let rows = sqlx::query!(
+            r#"
+            SELECT
+                date_trunc($2, created_at AT TIME ZONE 'UTC') AT TIME ZONE 'UTC' AS "period_start!",
+                event_type,
+                COUNT(*) AS "event_count!",
+                SUM(amount) AS "amount!"
+            FROM transaction_events
+            WHERE wallet_id = $1
+              AND created_at >= COALESCE($3::TIMESTAMPTZ, '-infinity')
+              AND created_at < COALESCE($4::TIMESTAMPTZ, 'infinity')
+              AND ($5::TEXT[] IS NULL OR event_type = ANY($5))
+            GROUP BY 1, 2
+            ORDER BY 1 DESC, 2
+            "#,
+            wallet_id,
+            period.as_sql(),
+            filter.from,
+            filter.to,
+            event_types.as_deref()
+        )
+        .fetch_all(self.reader())
+        .await?;
 
-            // Event 2: Incoming to receiver
-            let in_event_id = Uuid::new_v4().to_string();
-            let in_event = sqlx::query_as::<_, TransactionEvent>(
+        Ok(rows
+            .into_iter()
+            .map(|row| (row.period_start, row.event_type, row.event_count, row.amount))
+            .collect())
+    }
+
+    /// Like `get_wallet_summary`, across a user's wallets (or the selected
+    /// ones)
+    pub async fn get_user_summary(
+        &self,
+        user_id: &str,
+        period: SummaryPeriod,
+        filter: &HistoryFilter,
+        wallets: &WalletSelection,
+    ) -> HistoryResult<Vec<SummaryRow>> {
+        #[cfg(feature = "clickhouse")]
+        if let Some(analytics) = &self.analytics {
+            match analytics.user_summary(user_id, period, filter, wallets).await {
+                Ok(rows) => return Ok(rows),
+                Err(e) => tracing::warn!(error = %e, "ClickHouse summary failed, using Postgres"),
+            }
+        }
+
+        let event_types = filter.event_types();
+        let wallet_ids = wallets.wallet_ids();
+        if let Some((from, to)) = whole_days(filter) {
+            let rows = sqlx::query!(
                 r#"
-                INSERT INTO transaction_events 
-                    (id, wallet_id, user_id, amount, event_type, transaction_id, event_data, created_at)
-                VALUES ($1, $2, $3, $4, 'TRANSFER_IN', $5, $6, $7)
-                RETURNING id, wallet_id, user_id, amount, event_type, transaction_id, created_at, event_data
-                "#
+                SELECT
+                    date_trunc($2, day::TIMESTAMP) AT TIME ZONE 'UTC' AS "period_start!",
+                    event_type,
+                    SUM(event_count)::BIGINT AS "event_count!",
+                    SUM(amount) AS "amount!"
+                FROM user_daily_summary
+                WHERE user_id = $1
+                  AND ($3::DATE IS NULL OR day >= $3)
+                  AND ($4::DATE IS NULL OR day < $4)
+                  AND ($5::TEXT[] IS NULL OR event_type = ANY($5))
+                  AND ($6::TEXT[] IS NULL OR wallet_id = ANY($6))
+                GROUP BY 1, 2
+                ORDER BY 1 DESC, 2
+                "#,
+                user_id,
+                period.as_sql(),
+                from,
+                to,
+                event_types.as_deref(),
+                wallet_ids.as_deref()
             )
-            .bind(&in_event_id)
-            .bind(to_wallet_id)
-            .bind(to_user_id)
-            .bind(amount)
-            .bind(reference_id)
-            .bind(&event_data)
-            .bind(timestamp)
-            .fetch_one(&self.pool)
+            .fetch_all(self.reader())
             .await?;
 
-            events.push(in_event);
+            return Ok(rows
+                .into_iter()
+                .map(|row| (row.period_start, row.event_type, row.event_count, row.amount))
+                .collect());
+        }
 
-            tracing::info!(
-                reference_id = %reference_id,
-                from_wallet = %from_wallet_id,
-                to_wallet = %to_wallet_id,
-                "Transfer events stored"
-            );
+        let rows = sqlx::query!(
+            r#"
+            SELECT
+                date_trunc($2, created_at AT TIME ZONE 'UTC') AT TIME ZONE 'UTC' AS "period_start!",
+                event_type,
+                COUNT(*) AS "event_count!",
+                SUM(amount) AS "amount!"
+            FROM transaction_events
+            WHERE user_id = $1
+              AND created_at >= COALESCE($3::TIMESTAMPTZ, '-infinity')
+              AND created_at < COALESCE($4::TIMESTAMPTZ, 'infinity')
+              AND ($5::TEXT[] IS NULL OR event_type = ANY($5))
+              AND ($6::TEXT[] IS NULL OR wallet_id = ANY($6))
+            GROUP BY 1, 2
+            ORDER BY 1 DESC, 2
+            "#,
+            user_id,
+            period.as_sql(),
+            filter.from,
+            filter.to,
+            event_types.as_deref(),
+            wallet_ids.as_deref()
+        )
+        .fetch_all(self.reader())
+        .await?;
 
-            Ok(events)
-        } else {
-            Err(HistoryError::InternalError(
-                "Expected TransferCompleted event".to_string(),
-            ))
-        }
+        Ok(rows
+            .into_iter()
+            .map(|row| (row.period_start, row.event_type, row.event_count, row.amount))
+            .collect())
+    }
+
+    /// Sum a wallet's credits and debits up to `at` (see balance.rs)
+    ///
+    /// Returns (balance, events counted, newest event's time). Older rows
+    /// whose event_data has no timestamp fall back to when they were stored.
+    pub async fn get_balance_at(
+        &self,
+        wallet_id: &str,
+        at: DateTime<Utc>,
+    ) -> HistoryResult<(Decimal, i64, Option<DateTime<Utc>>)> {
+        let totals = sqlx::query!(
+            r#"
+            WITH placed AS (
+                SELECT event_type, amount,
+                    COALESCE((event_data->>'timestamp')::TIMESTAMPTZ, created_at) AS happened_at
+                FROM transaction_events
+                WHERE wallet_id = $1
+            )
+            SELECT
+                SUM(CASE
+                    WHEN event_type = ANY($3) THEN amount
+                    WHEN event_type = ANY($4) THEN -amount
+                    ELSE 0
+                END) AS balance,
+                COUNT(*) AS "events!",
+                MAX(happened_at) AS last_event_at
+            FROM placed
+            WHERE happened_at <= $2
+            "#,
+            wallet_id,
+            at,
+            CREDIT_TYPES as &[&str],
+            DEBIT_TYPES as &[&str]
+        )
+        .fetch_one(self.reader())
+        .await?;
+
+        Ok((totals.balance.unwrap_or(Decimal::ZERO), totals.events, totals.last_event_at))
+    }
+
+    /// What a wallet's pruned events summed to: (balance, events, newest
+    /// event) - None if none of its events were archived
+    pub async fn get_archived_balance(
+        &self,
+        wallet_id: &str,
+    ) -> HistoryResult<Option<(Decimal, i64, Option<DateTime<Utc>>)>> {
+        let carried = sqlx::query!(
+            "SELECT balance, events, last_event_at FROM archived_balances WHERE wallet_id = $1",
+            wallet_id
+        )
+        .fetch_optional(self.reader())
+        .await?;
+
+        Ok(carried.map(|row| (row.balance, row.events, row.last_event_at)))
     }
 
-    /// Get all events for a specific wallet
-    pub async fn get_wallet_history(&self, wallet_id: &str) -> HistoryResult<Vec<TransactionEvent>> {
-        let events = sqlx::query_as::<_, TransactionEvent>(
+    /// Distinct events stored in [from, to), oldest first (for replays)
+    ///
+    /// Both legs of a transfer carry the same event_data, so grouping by it
+    /// yields each Kafka event once. With `wallet_id`, events with a leg on
+    /// that wallet (either side of a transfer).
+    pub async fn get_stored_envelopes(
+        &self,
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
+        wallet_id: Option<&str>,
+        limit: i64,
+    ) -> HistoryResult<Vec<(serde_json::Value, DateTime<Utc>)>> {
+        let events = sqlx::query!(
             r#"
-            SELECT id, wallet_id, user_id, amount, event_type, transaction_id, created_at, event_data
+            SELECT event_data, MIN(created_at) AS "stored_at!"
             FROM transaction_events
-            WHERE wallet_id = $1
-            ORDER BY created_at DESC
-            "#
+            WHERE created_at >= $1 AND created_at < $2
+              AND ($3::TEXT IS NULL OR wallet_id = $3)
+            GROUP BY event_data
+            ORDER BY MIN(created_at)
+            LIMIT $4
+            "#,
+            from,
+            to,
+            wallet_id,
+            limit
         )
-        .bind(wallet_id)
         .fetch_all(&self.pool)
         .await?;
 
-        Ok(events)
+        Ok(events.into_iter().map(|row| (row.event_data, row.stored_at)).collect())
     }
 
-    /// Get all events for a specific user (across all their wallets)
-    pub async fn get_user_activity(&self, user_id: &str) -> HistoryResult<Vec<TransactionEvent>> {
-        let events = sqlx::query_as::<_, TransactionEvent>(
+    /// When the oldest stored event was stored (None: no events)
+    pub async fn oldest_event_at(&self) -> HistoryResult<Option<DateTime<Utc>>> {
+        let oldest = sqlx::query_scalar!("SELECT MIN(created_at) FROM transaction_events")
+            .fetch_one(&self.pool)
+            .await?;
+
+        Ok(oldest)
+    }
+
+    /// How many events were stored in [from, to)
+    pub async fn count_events_between(
+        &self,
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
+    ) -> HistoryResult<i64> {
+        let count = sqlx::query_scalar!(
+            r#"SELECT COUNT(*) AS "count!" FROM transaction_events WHERE created_at >= $1 AND created_at < $2"#,
+            from,
+            to
+        )
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(count)
+    }
+
+    /// Every event stored in [from, to), oldest first, as a stream (for
+    /// cold storage - always from the primary)
+    pub fn stream_events_between(
+        &self,
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
+    ) -> BoxStream<'_, HistoryResult<TransactionEvent>> {
+        sqlx::query_as!(
+            TransactionEvent,
             r#"
-            SELECT id, wallet_id, user_id, amount, event_type, transaction_id, created_at, event_data
+            SELECT id, wallet_id, user_id, amount, event_type, transaction_id, created_at, event_data,
+                event_id, correlation_id, causation_id
             FROM transaction_events
-            WHERE user_id = $1
-            ORDER BY created_at DESC
+            WHERE created_at >= $1 AND created_at < $2
+            ORDER BY created_at ASC, id ASC
+            "#,
+            from,
+            to
+        )
+        .fetch(&self.pool)
+        .map_err(HistoryError::from)
+        .boxed()
+    }
+
+    /// Delete the events `archive` holds, carrying their sums into
+    /// archived_balances, and record the archive - one transaction
+    ///
+    /// Refuses (and changes nothing) unless exactly `archive.event_count`
+    /// rows are deleted: a file that doesn't hold every pruned event would
+    /// lose history.
+    pub async fn prune_archived_events(&self, archive: &EventArchive) -> HistoryResult<()> {
+        let mut tx = self.pool.begin().await?;
+
+        let pruned = sqlx::query_scalar!(
+            r#"
+            WITH pruned AS (
+                DELETE FROM transaction_events
+                WHERE created_at >= $1 AND created_at < $2
+                RETURNING wallet_id, event_type, amount,
+                    COALESCE((event_data->>'timestamp')::TIMESTAMPTZ, created_at) AS happened_at
+            ),
+            carried AS (
+                INSERT INTO archived_balances (wallet_id, balance, events, last_event_at, archived_through)
+                SELECT
+                    wallet_id,
+                    SUM(CASE
+                        WHEN event_type = ANY($3) THEN amount
+                        WHEN event_type = ANY($4) THEN -amount
+                        ELSE 0
+                    END),
+                    COUNT(*),
+                    MAX(happened_at),
+                    $2
+                FROM pruned
+                GROUP BY wallet_id
+                ON CONFLICT (wallet_id) DO UPDATE SET
+                    balance = archived_balances.balance + EXCLUDED.balance,
+                    events = archived_balances.events + EXCLUDED.events,
+                    last_event_at = GREATEST(archived_balances.last_event_at, EXCLUDED.last_event_at),
+                    archived_through = EXCLUDED.archived_through
+            )
+            SELECT COUNT(*) AS "count!" FROM pruned
+            "#,
+            archive.range_start,
+            archive.range_end,
+            CREDIT_TYPES as &[&str],
+            DEBIT_TYPES as &[&str]
+        )
+        .fetch_one(&mut *tx)
+        .await?;
+
+        if pruned != archive.event_count {
+            // Dropping tx rolls everything back
+            return Err(HistoryError::InternalError(format!(
+                "Archive {} holds {} events but {} were stored - not pruned",
+                archive.period, archive.event_count, pruned
+            )));
+        }
+
+        sqlx::query!(
+            r#"
+            INSERT INTO event_archives
+                (period, range_start, range_end, object_key, format, event_count, byte_size, sha256, archived_at)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)
+            "#,
+            &archive.period,
+            archive.range_start,
+            archive.range_end,
+            &archive.object_key,
+            &archive.format,
+            archive.event_count,
+            archive.byte_size,
+            &archive.sha256,
+            archive.archived_at
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+        Ok(())
+    }
+
+    /// Every month in cold storage, oldest first
+    pub async fn get_event_archives(&self) -> HistoryResult<Vec<EventArchive>> {
+        let archives = sqlx::query_as!(
+            EventArchive,
+            r#"
+            SELECT period, range_start, range_end, object_key, format, event_count, byte_size, sha256,
+                archived_at
+            FROM event_archives
+            ORDER BY range_start
             "#
         )
-        .bind(user_id)
-        .fetch_all(&self.pool)
+        .fetch_all(self.reader())
         .await?;
 
-        Ok(events)
+        Ok(archives)
+    }
+
+    /// Events stored before this are in cold storage, not Postgres (None:
+    /// nothing archived)
+    pub async fn get_archive_horizon(&self) -> HistoryResult<Option<DateTime<Utc>>> {
+        let horizon = sqlx::query_scalar!("SELECT MAX(range_end) FROM event_archives")
+            .fetch_one(self.reader())
+            .await?;
+
+        Ok(horizon)
+    }
+}
+
+/// A transaction_events row about to be inserted
+struct NewEvent<'a> {
+    envelope: &'a EventEnvelope,
+    wallet_id: &'a str,
+    user_id: &'a str,
+    event_type: &'a str,
+    transaction_id: Option<String>,
+    /// None: stored at NOW()
+    created_at: Option<DateTime<Utc>>,
+    event_data: serde_json::Value,
+}
+
+/// The rows an event stores (one, two legs, or none for declines)
+///
+/// Transfers, round-ups and pot moves touch TWO wallets, so they store a
+/// leg on each (TRANSFER_OUT for the sender, TRANSFER_IN for the receiver,
+/// ...). Both transfer legs share the reference_id; the event type tells
+/// them apart in the unique index, so each leg is deduplicated on its own.
+/// Legs are placed at the event's own timestamp.
+///
+/// The envelope's tracing IDs are kept on every row (NULL for older events).
+fn new_rows(envelope: &EventEnvelope) -> HistoryResult<Vec<NewEvent<'_>>> {
+    let (legs, timestamp) = match &envelope.event {
+        WalletEvent::TransferCompleted {
+            from_wallet_id,
+            from_user_id,
+            to_wallet_id,
+            to_user_id,
+            reference_id,
+            timestamp,
+            ..
+        } => (
+            [
+                (from_wallet_id, from_user_id, "TRANSFER_OUT", reference_id),
+                (to_wallet_id, to_user_id, "TRANSFER_IN", reference_id),
+            ],
+            timestamp,
+        ),
+        WalletEvent::RoundUpApplied {
+            wallet_id,
+            user_id,
+            savings_wallet_id,
+            savings_user_id,
+            out_transaction_id,
+            in_transaction_id,
+            timestamp,
+            ..
+        } => (
+            // Each leg has its own transaction ID
+            [
+                (wallet_id, user_id, "ROUND_UP_OUT", out_transaction_id),
+                (savings_wallet_id, savings_user_id, "ROUND_UP_IN", in_transaction_id),
+            ],
+            timestamp,
+        ),
+        WalletEvent::PotTransferCompleted {
+            from_wallet_id,
+            to_wallet_id,
+            user_id,
+            out_transaction_id,
+            in_transaction_id,
+            timestamp,
+            ..
+        } => (
+            [
+                (from_wallet_id, user_id, "POT_TRANSFER_OUT", out_transaction_id),
+                (to_wallet_id, user_id, "POT_TRANSFER_IN", in_transaction_id),
+            ],
+            timestamp,
+        ),
+        WalletEvent::FundingFailed { reason, .. } | WalletEvent::TransferFailed { reason, .. } => {
+            // Declines moved no money - nothing belongs in the history
+            tracing::info!(reason = %reason, "Declined operation, nothing to store");
+            return Ok(Vec::new());
+        }
+        WalletEvent::FraudAlert { action, rules, .. } => {
+            // The alert moved no money; the funding or transfer it was
+            // about is stored (or declined) by its own event
+            tracing::info!(action = %action, rules = %rules, "Fraud alert, nothing to store");
+            return Ok(Vec::new());
+        }
+        WalletEvent::VelocityLimitExceeded { measure, .. } => {
+            // Refused, so nothing moved (TRANSFER_FAILED says the same)
+            tracing::info!(measure = %measure, "Velocity limit exceeded, nothing to store");
+            return Ok(Vec::new());
+        }
+        WalletEvent::BlocklistEntryAdded { target_type, target_id, .. }
+        | WalletEvent::BlocklistEntryRemoved { target_type, target_id, .. } => {
+            // Blocklist changes aren't money movement
+            tracing::info!(target_type = %target_type, target_id = %target_id, "Blocklist change, nothing to store");
+            return Ok(Vec::new());
+        }
+        WalletEvent::VerificationLockout { factor, lockouts, .. } => {
+            // A security alert, not money movement
+            tracing::info!(factor = %factor, lockouts = %lockouts, "Verification lockout, nothing to store");
+            return Ok(Vec::new());
+        }
+        WalletEvent::UserAnonymized { .. } => {
+            return Err(HistoryError::InternalError(
+                "USER_ANONYMIZED rewrites rows - store it with store_envelope".to_string(),
+            ));
+        }
+        event => {
+            // Other events create ONE row
+            return Ok(vec![NewEvent {
+                envelope,
+                wallet_id: event.wallet_id(),
+                user_id: event.user_id(),
+                event_type: event.stored_event_type(),
+                transaction_id: event.transaction_id(),
+                created_at: None,
+                event_data: event_data(envelope)?,
+            }]);
+        }
+    };
+
+    let event_data = event_data(envelope)?;
+    Ok(legs
+        .into_iter()
+        .map(|(wallet_id, user_id, event_type, transaction_id)| NewEvent {
+            envelope,
+            wallet_id,
+            user_id,
+            event_type,
+            transaction_id: Some(transaction_id.clone()),
+            created_at: Some(*timestamp),
+            event_data: event_data.clone(),
+        })
+        .collect())
+}
+
+/// The full envelope as JSON, kept on each row for debugging
+fn event_data(envelope: &EventEnvelope) -> HistoryResult<serde_json::Value> {
+    serde_json::to_value(envelope).map_err(|e| HistoryError::SerializationError(e.to_string()))
+}
+
+/// Rows turned into one array per column, for INSERT ... SELECT FROM UNNEST
+#[derive(Default)]
+struct NewEventColumns {
+    id: Vec<String>,
+    wallet_id: Vec<String>,
+    user_id: Vec<String>,
+    amount: Vec<Decimal>,
+    event_type: Vec<String>,
+    transaction_id: Vec<Option<String>>,
+    event_data: Vec<serde_json::Value>,
+    created_at: Vec<Option<DateTime<Utc>>>,
+    event_id: Vec<Option<String>>,
+    correlation_id: Vec<Option<String>>,
+    causation_id: Vec<Option<String>>,
+}
+
+impl NewEventColumns {
+    fn push(&mut self, row: &NewEvent<'_>) {
+        let ids = &row.envelope.ids;
+        self.id.push(Uuid::new_v4().to_string());
+        self.wallet_id.push(row.wallet_id.to_string());
+        self.user_id.push(row.user_id.to_string());
+        self.amount.push(row.envelope.event.amount());
+        self.event_type.push(row.event_type.to_string());
+        self.transaction_id.push(row.transaction_id.clone());
+        self.event_data.push(row.event_data.clone());
+        self.created_at.push(row.created_at);
+        self.event_id.push(ids.event_id().map(str::to_string));
+        self.correlation_id.push(ids.correlation_id().map(str::to_string));
+        self.causation_id.push(ids.causation_id().map(str::to_string));
     }
 }
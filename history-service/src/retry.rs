@@ -0,0 +1,176 @@
+use crate::errors::{HistoryError, HistoryResult};
+use crate::kafka_security::client_config;
+use crate::signing::SIGNATURE_HEADER;
+use chrono::{DateTime, TimeZone, Utc};
+use rdkafka::message::{Header, Headers, OwnedHeaders};
+use rdkafka::producer::{FutureProducer, FutureRecord};
+use std::time::Duration;
+
+const HEADER_ATTEMPT: &str = "x-retry-attempt";
+const HEADER_NOT_BEFORE: &str = "x-retry-not-before";
+const HEADER_ORIGINAL_TOPIC: &str = "x-original-topic";
+const HEADER_LAST_ERROR: &str = "x-last-error";
+
+/// One retry topic and how long messages wait on it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RetryTier {
+    pub topic: String,
+    pub delay: Duration,
+}
+
+/// The tiers for a source topic: retry-5s, retry-1m, retry-10m
+///
+/// A message that keeps failing walks down the list, then lands in the DLQ.
+pub fn retry_tiers(base_topic: &str) -> Vec<RetryTier> {
+    [("5s", 5), ("1m", 60), ("10m", 600)]
+        .into_iter()
+        .map(|(suffix, secs)| RetryTier {
+            topic: format!("{}-retry-{}", base_topic, suffix),
+            delay: Duration::from_secs(secs),
+        })
+        .collect()
+}
+
+/// Retry bookkeeping carried in Kafka headers
+///
+/// The payload stays the original event JSON, so a retried message is
+/// processed exactly like a fresh one.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RetryInfo {
+    /// Failed attempts so far (1 = failed once on the main topic)
+    pub attempt: u32,
+    /// Don't process before this time
+    pub not_before: DateTime<Utc>,
+    pub original_topic: String,
+    pub last_error: String,
+}
+
+impl RetryInfo {
+    pub fn to_headers(&self) -> OwnedHeaders {
+        let attempt = self.attempt.to_string();
+        let not_before = self.not_before.timestamp_millis().to_string();
+
+        OwnedHeaders::new()
+            .insert(Header {
+                key: HEADER_ATTEMPT,
+                value: Some(&attempt),
+            })
+            .insert(Header {
+                key: HEADER_NOT_BEFORE,
+                value: Some(&not_before),
+            })
+            .insert(Header {
+                key: HEADER_ORIGINAL_TOPIC,
+                value: Some(&self.original_topic),
+            })
+            .insert(Header {
+                key: HEADER_LAST_ERROR,
+                value: Some(&self.last_error),
+            })
+    }
+
+    /// Read retry headers (None for messages that were never retried)
+    pub fn from_headers<H: Headers>(headers: &H) -> Option<Self> {
+        let mut attempt = None;
+        let mut not_before = None;
+        let mut original_topic = None;
+        let mut last_error = String::new();
+
+        for header in headers.iter() {
+            let value = header
+                .value
+                .map(|v| String::from_utf8_lossy(v).into_owned())
+                .unwrap_or_default();
+
+            match header.key {
+                HEADER_ATTEMPT => attempt = value.parse::<u32>().ok(),
+                HEADER_NOT_BEFORE => {
+                    not_before = value
+                        .parse::<i64>()
+                        .ok()
+                        .and_then(|ms| Utc.timestamp_millis_opt(ms).single())
+                }
+                HEADER_ORIGINAL_TOPIC => original_topic = Some(value),
+                HEADER_LAST_ERROR => last_error = value,
+                _ => {}
+            }
+        }
+
+        Some(Self {
+            attempt: attempt?,
+            not_before: not_before?,
+            original_topic: original_topic?,
+            last_error,
+        })
+    }
+}
+
+/// Producer for the retry topics
+///
+/// Why retry topics instead of retrying in place?
+/// - A DB outage lasts seconds to minutes; retrying in the consumer loop
+///   either spins or blocks the whole partition for that long
+/// - Moving the message aside lets the main topic keep flowing
+/// - Each tier waits longer, so a persistent failure costs 3 attempts over
+///   ~11 minutes rather than thousands in a tight loop
+///
+/// Trade-off: a retried event is stored after later events for the same
+/// wallet. History is ordered by event timestamp, so reads are unaffected.
+pub struct RetryProducer {
+    producer: FutureProducer,
+    tiers: Vec<RetryTier>,
+}
+
+impl RetryProducer {
+    pub fn new(brokers: &str, tiers: Vec<RetryTier>) -> HistoryResult<Self> {
+        let producer: FutureProducer = client_config(brokers)
+            .set("message.timeout.ms", "5000")
+            .set("acks", "all")
+            .create()
+            .map_err(|e| HistoryError::KafkaError(format!("Failed to create retry producer: {}", e)))?;
+
+        Ok(Self { producer, tiers })
+    }
+
+    pub fn tiers(&self) -> &[RetryTier] {
+        &self.tiers
+    }
+
+    /// Send a message to tier `tier` (0-based) to be retried after its delay
+    ///
+    /// `signature` is the original SIGNATURE_HEADER, carried over so the tier
+    /// consumer can verify the message too.
+    pub async fn schedule(
+        &self,
+        tier: usize,
+        key: Option<&[u8]>,
+        payload: &[u8],
+        signature: Option<&[u8]>,
+        info: &RetryInfo,
+    ) -> HistoryResult<()> {
+        let target = self.tiers.get(tier).ok_or_else(|| {
+            HistoryError::InternalError(format!("No retry tier {}", tier))
+        })?;
+
+        let mut headers = info.to_headers();
+        if let Some(signature) = signature {
+            headers = headers.insert(Header {
+                key: SIGNATURE_HEADER,
+                value: Some(signature),
+            });
+        }
+
+        let mut record = FutureRecord::to(&target.topic)
+            .payload(payload)
+            .headers(headers);
+        if let Some(key) = key {
+            record = record.key(key);
+        }
+
+        self.producer
+            .send(record, Duration::from_secs(5))
+            .await
+            .map(|_| ())
+            .map_err(|(e, _)| HistoryError::KafkaError(format!("Failed to schedule retry: {}", e)))
+    }
+}
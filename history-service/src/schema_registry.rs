@@ -0,0 +1,95 @@
+use crate::errors::{HistoryError, HistoryResult};
+use serde_json::Value;
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpStream;
+
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Minimal Confluent Schema Registry client - looks up schemas by ID
+///
+/// Plain `http://` only - the registry is expected on the internal network.
+pub struct SchemaRegistryClient {
+    host: String,
+    port: u16,
+}
+
+impl SchemaRegistryClient {
+    /// `url` like `http://localhost:8081`
+    pub fn new(url: &str) -> HistoryResult<Self> {
+        let authority = url
+            .strip_prefix("http://")
+            .ok_or_else(|| config_error("only http:// registry URLs are supported"))?
+            .trim_end_matches('/');
+
+        let (host, port) = match authority.rsplit_once(':') {
+            Some((host, port)) => (
+                host.to_string(),
+                port.parse()
+                    .map_err(|_| config_error(&format!("invalid port in {}", url)))?,
+            ),
+            None => (authority.to_string(), 80),
+        };
+
+        Ok(Self { host, port })
+    }
+
+    /// The schema text registered under `id`
+    pub async fn schema_by_id(&self, id: u32) -> HistoryResult<String> {
+        let path = format!("/schemas/ids/{}", id);
+        let request = format!(
+            "GET {} HTTP/1.0\r\nHost: {}:{}\r\nAccept: application/vnd.schemaregistry.v1+json\r\n\r\n",
+            path, self.host, self.port
+        );
+
+        // HTTP/1.0 so the reply is never chunked
+        let exchange = async {
+            let mut stream = TcpStream::connect((self.host.as_str(), self.port)).await?;
+            stream.write_all(request.as_bytes()).await?;
+            let mut response = Vec::new();
+            stream.read_to_end(&mut response).await?;
+            Ok::<_, std::io::Error>(response)
+        };
+
+        let response = tokio::time::timeout(REQUEST_TIMEOUT, exchange)
+            .await
+            .map_err(|_| registry_error("request timed out"))?
+            .map_err(|e| registry_error(&e.to_string()))?;
+
+        let response = String::from_utf8_lossy(&response);
+        let (head, body) = response
+            .split_once("\r\n\r\n")
+            .ok_or_else(|| registry_error("malformed HTTP response"))?;
+
+        let status: u16 = head
+            .split_whitespace()
+            .nth(1)
+            .and_then(|code| code.parse().ok())
+            .ok_or_else(|| registry_error("malformed HTTP status line"))?;
+        if status == 404 {
+            // Retrying won't make an unknown schema appear
+            return Err(HistoryError::SerializationError(format!(
+                "Unknown schema id {}",
+                id
+            )));
+        }
+        if !(200..300).contains(&status) {
+            return Err(registry_error(&format!("GET {} returned {}: {}", path, status, body)));
+        }
+
+        serde_json::from_str::<Value>(body)
+            .ok()
+            .and_then(|reply| reply.get("schema").and_then(Value::as_str).map(str::to_string))
+            .ok_or_else(|| registry_error(&format!("no schema in reply: {}", body)))
+    }
+}
+
+/// Registry outages are transient - surfaced as Kafka-side errors so the
+/// message goes through the retry topics instead of straight to the DLQ
+fn registry_error(message: &str) -> HistoryError {
+    HistoryError::KafkaError(format!("Schema registry: {}", message))
+}
+
+fn config_error(message: &str) -> HistoryError {
+    HistoryError::InternalError(format!("Schema registry: {}", message))
+}
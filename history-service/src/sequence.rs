@@ -0,0 +1,70 @@
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use sqlx::FromRow;
+use utoipa::ToSchema;
+
+/// Most missing numbers recorded for one jump (the nearest ones are kept)
+pub const MAX_GAP_ROWS: i64 = 1000;
+
+/// Where an event's `sequence` falls against the highest one seen for its wallet
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Position {
+    /// Exactly the next one
+    Next,
+    /// Numbers were skipped - `first_missing..=last_missing` haven't arrived
+    Ahead { first_missing: i64, last_missing: i64 },
+    /// At or below the highest seen: a late arrival or a redelivery
+    Behind,
+}
+
+impl Position {
+    pub fn of(last_seen: i64, sequence: i64) -> Self {
+        if sequence == last_seen + 1 {
+            Position::Next
+        } else if sequence > last_seen {
+            Position::Ahead {
+                first_missing: (last_seen + 1).max(sequence - MAX_GAP_ROWS),
+                last_missing: sequence - 1,
+            }
+        } else {
+            Position::Behind
+        }
+    }
+}
+
+/// What tracking one event found
+///
+/// Why track it?
+/// - wallet-service numbers each wallet's events 1, 2, 3 ..., so a missing
+///   number means an event never reached us (lost before Kafka, parked in
+///   the DLQ, or still waiting in a retry tier)
+/// - An event arriving after a higher number means reordering (retry tiers,
+///   or the outbox relay racing a direct send) - the history is complete
+///   again once it's stored
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SequenceOutcome {
+    InOrder,
+    GapOpened { first_missing: i64, last_missing: i64 },
+    /// A late event filled a gap
+    GapFilled,
+    /// Already seen (a redelivery)
+    Duplicate,
+}
+
+/// A wallet's numbering as the read model sees it
+/// (GET /wallets/:wallet_id/sequence)
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct WalletSequenceStatus {
+    pub wallet_id: String,
+    /// 0 until a numbered event is stored
+    pub last_sequence: i64,
+    pub open_gaps: Vec<i64>,
+}
+
+/// One missing event (GET /admin/sequence-gaps)
+#[derive(Debug, Clone, FromRow, Serialize, ToSchema)]
+pub struct SequenceGap {
+    pub wallet_id: String,
+    pub sequence: i64,
+    pub detected_at: DateTime<Utc>,
+}
@@ -0,0 +1,87 @@
+use std::sync::Arc;
+use tokio::sync::watch;
+
+/// Process-wide "stop taking new work" flag, set on SIGTERM / Ctrl-C
+///
+/// Why?
+/// - Kubernetes sends SIGTERM on every rollout; exiting right away cuts
+///   messages off half-processed and loses offsets stored since the last
+///   auto-commit, so the next owner re-reads (and re-skips) them
+/// - The consumers stop polling, let their workers finish the message in
+///   hand and commit; the HTTP server stops accepting and finishes open
+///   requests
+///
+/// Cheap to clone - every clone sees the same flag.
+#[derive(Clone)]
+pub struct Shutdown {
+    triggered: Arc<watch::Sender<bool>>,
+}
+
+impl Shutdown {
+    pub fn new() -> Self {
+        let (triggered, _) = watch::channel(false);
+        Self {
+            triggered: Arc::new(triggered),
+        }
+    }
+
+    /// Start shutting down (later calls are no-ops)
+    pub fn trigger(&self) {
+        self.triggered.send_if_modified(|triggered| !std::mem::replace(triggered, true));
+    }
+
+    pub fn is_triggered(&self) -> bool {
+        *self.triggered.borrow()
+    }
+
+    /// Resolves once `trigger` has been called
+    pub async fn wait(&self) {
+        let mut triggered = self.triggered.subscribe();
+        let _ = triggered.wait_for(|triggered| *triggered).await;
+    }
+
+    /// Trigger on SIGTERM or Ctrl-C, in the background
+    pub fn trigger_on_signal(&self) {
+        let shutdown = self.clone();
+        tokio::spawn(async move {
+            wait_for_signal().await;
+            tracing::info!("Shutdown signal received, draining");
+            shutdown.trigger();
+        });
+    }
+}
+
+impl Default for Shutdown {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+async fn wait_for_signal() {
+    let ctrl_c = async {
+        if let Err(e) = tokio::signal::ctrl_c().await {
+            tracing::error!(error = %e, "Failed to listen for Ctrl-C");
+            std::future::pending::<()>().await;
+        }
+    };
+
+    #[cfg(unix)]
+    let terminate = async {
+        match tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate()) {
+            Ok(mut sigterm) => {
+                sigterm.recv().await;
+            }
+            Err(e) => {
+                tracing::error!(error = %e, "Failed to listen for SIGTERM");
+                std::future::pending::<()>().await;
+            }
+        }
+    };
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        () = ctrl_c => {}
+        () = terminate => {}
+    }
+}
@@ -0,0 +1,114 @@
+use crate::errors::{HistoryError, HistoryResult};
+use hmac::{Hmac, Mac};
+use rdkafka::message::Headers;
+use sha2::Sha256;
+use std::collections::HashMap;
+
+/// Kafka header wallet-service signs events with: `<key id>:<hex HMAC-SHA256>`
+pub const SIGNATURE_HEADER: &str = "x-event-signature";
+
+/// Checks the signature wallet-service puts on every event
+///
+/// Why verify at all?
+/// - Anyone who can write to the topic could otherwise put money movements
+///   into the history; a signature only wallet-service's key can make
+///   rules that out, and catches payloads altered on the way
+///
+/// Messages that fail go straight to the DLQ (never retried - they'd fail
+/// the same way again) and are never stored.
+///
+/// Keys (EVENT_VERIFY_KEYS): `<key id>:<secret>,...` - every key listed is
+/// accepted, so a new signing key can be added here before wallet-service
+/// switches to it. Secrets can't contain commas.
+pub struct EventVerifier {
+    keys: HashMap<String, Vec<u8>>,
+    /// EVENT_ACCEPT_UNSIGNED=true: let unsigned messages through (a rollout
+    /// window, or replaying a topic from before signing); wrong signatures
+    /// are still rejected
+    accept_unsigned: bool,
+}
+
+impl EventVerifier {
+    /// None when EVENT_VERIFY_KEYS is unset (nothing is verified)
+    pub fn from_env() -> Result<Option<Self>, String> {
+        let Some(value) = std::env::var("EVENT_VERIFY_KEYS").ok().filter(|v| !v.is_empty()) else {
+            return Ok(None);
+        };
+        let keys = value
+            .split(',')
+            .filter(|entry| !entry.trim().is_empty())
+            .map(|entry| {
+                let (key_id, secret) = entry.trim().split_once(':').ok_or_else(|| {
+                    "EVENT_VERIFY_KEYS entries must be <key id>:<secret>".to_string()
+                })?;
+                Ok((key_id.to_string(), secret.as_bytes().to_vec()))
+            })
+            .collect::<Result<HashMap<_, _>, String>>()?;
+        if keys.is_empty() {
+            return Err("EVENT_VERIFY_KEYS has no keys".to_string());
+        }
+
+        let accept_unsigned = std::env::var("EVENT_ACCEPT_UNSIGNED").as_deref() == Ok("true");
+        Ok(Some(Self::new(keys, accept_unsigned)))
+    }
+
+    pub fn new(keys: HashMap<String, Vec<u8>>, accept_unsigned: bool) -> Self {
+        Self {
+            keys,
+            accept_unsigned,
+        }
+    }
+
+    /// Key IDs accepted, sorted (for the startup log)
+    pub fn key_ids(&self) -> Vec<&str> {
+        let mut ids: Vec<&str> = self.keys.keys().map(String::as_str).collect();
+        ids.sort_unstable();
+        ids
+    }
+
+    pub fn accepts_unsigned(&self) -> bool {
+        self.accept_unsigned
+    }
+
+    /// Is `signature` (the SIGNATURE_HEADER value) right for this message?
+    pub fn verify(
+        &self,
+        key: Option<&[u8]>,
+        payload: &[u8],
+        signature: Option<&[u8]>,
+    ) -> HistoryResult<()> {
+        let invalid = |why: &str| HistoryError::InvalidSignature(why.to_string());
+
+        let Some(signature) = signature else {
+            return match self.accept_unsigned {
+                true => Ok(()),
+                false => Err(invalid("message is not signed")),
+            };
+        };
+        let signature = std::str::from_utf8(signature).map_err(|_| invalid("malformed signature"))?;
+        let (key_id, hex) = signature
+            .split_once(':')
+            .ok_or_else(|| invalid("malformed signature"))?;
+        let secret = self
+            .keys
+            .get(key_id)
+            .ok_or_else(|| invalid(&format!("unknown key '{}'", key_id)))?;
+        let expected = hex::decode(hex).map_err(|_| invalid("malformed signature"))?;
+
+        let mut mac = Hmac::<Sha256>::new_from_slice(secret).expect("HMAC takes any key length");
+        mac.update(key.unwrap_or_default());
+        mac.update(&[0]);
+        mac.update(payload);
+        // Constant-time comparison
+        mac.verify_slice(&expected)
+            .map_err(|_| invalid("signature does not match"))
+    }
+}
+
+/// The SIGNATURE_HEADER value of a message, if it has one
+pub fn signature<H: Headers>(headers: &H) -> Option<&[u8]> {
+    headers
+        .iter()
+        .find(|header| header.key == SIGNATURE_HEADER)
+        .and_then(|header| header.value)
+}
@@ -0,0 +1,121 @@
+use crate::balance::{CREDIT_TYPES, DEBIT_TYPES};
+use crate::filter::HistoryFilter;
+use async_graphql::{Enum, SimpleObject};
+use chrono::{DateTime, NaiveDate, NaiveTime, Utc};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use utoipa::{IntoParams, ToSchema};
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, ToSchema, Enum)]
+#[serde(rename_all = "lowercase")]
+pub enum SummaryPeriod {
+    Day,
+    /// ISO weeks, starting Monday
+    Week,
+    #[default]
+    Month,
+    Year,
+}
+
+impl SummaryPeriod {
+    /// The `date_trunc` field for this period
+    pub fn as_sql(self) -> &'static str {
+        match self {
+            SummaryPeriod::Day => "day",
+            SummaryPeriod::Week => "week",
+            SummaryPeriod::Month => "month",
+            SummaryPeriod::Year => "year",
+        }
+    }
+}
+
+/// `?period=day|week|month|year` on the summary endpoints (default month)
+#[derive(Debug, Clone, Default, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct SummaryQuery {
+    #[serde(default)]
+    pub period: SummaryPeriod,
+}
+
+/// One GROUP BY row: (period start, event type, events, summed amount)
+pub type SummaryRow = (DateTime<Utc>, String, i64, Decimal);
+
+/// Totals for one period (UTC)
+#[derive(Debug, Clone, PartialEq, Serialize, ToSchema, SimpleObject)]
+pub struct PeriodSummary {
+    pub period_start: DateTime<Utc>,
+    /// Money in: funding, vouchers, incoming transfers, round-ups and pot moves
+    pub total_in: Decimal,
+    pub total_out: Decimal,
+    /// total_in - total_out
+    pub net_change: Decimal,
+    /// Events per type, WALLET_CREATED included
+    pub counts: BTreeMap<String, i64>,
+}
+
+/// Spend/income per period, newest period first
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct WalletSummary {
+    pub wallet_id: String,
+    pub period: SummaryPeriod,
+    pub periods: Vec<PeriodSummary>,
+}
+
+/// Spend/income per period across a user's wallets, newest period first
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct UserSummary {
+    pub user_id: String,
+    pub period: SummaryPeriod,
+    pub periods: Vec<PeriodSummary>,
+}
+
+/// The filter's window as UTC dates, when it covers whole days (an open
+/// end counts) - then the summary can come from `user_daily_summary`
+/// instead of the events themselves
+pub fn whole_days(filter: &HistoryFilter) -> Option<(Option<NaiveDate>, Option<NaiveDate>)> {
+    fn day(at: Option<DateTime<Utc>>) -> Option<Option<NaiveDate>> {
+        match at {
+            None => Some(None),
+            Some(at) if at.time() == NaiveTime::MIN => Some(Some(at.date_naive())),
+            Some(_) => None,
+        }
+    }
+
+    Some((day(filter.from)?, day(filter.to)?))
+}
+
+/// Fold the per-type rows into one summary per period
+///
+/// The database does the heavy part (one row per period and type, however
+/// many events there are); this only splits the sums into in and out.
+/// Rows must come ordered by period; the output keeps that order.
+pub fn summarize(rows: Vec<SummaryRow>) -> Vec<PeriodSummary> {
+    let mut periods: Vec<PeriodSummary> = Vec::new();
+
+    for (period_start, event_type, count, amount) in rows {
+        let summary = match periods.last_mut() {
+            Some(last) if last.period_start == period_start => last,
+            _ => {
+                periods.push(PeriodSummary {
+                    period_start,
+                    total_in: Decimal::ZERO,
+                    total_out: Decimal::ZERO,
+                    net_change: Decimal::ZERO,
+                    counts: BTreeMap::new(),
+                });
+                periods.last_mut().expect("just pushed")
+            }
+        };
+
+        if CREDIT_TYPES.contains(&event_type.as_str()) {
+            summary.total_in += amount;
+        } else if DEBIT_TYPES.contains(&event_type.as_str()) {
+            summary.total_out += amount;
+        }
+        summary.net_change = summary.total_in - summary.total_out;
+        *summary.counts.entry(event_type).or_insert(0) += count;
+    }
+
+    periods
+}
@@ -0,0 +1,129 @@
+use crate::errors::ErrorResponse;
+use axum::{
+    extract::{MatchedPath, Request, State},
+    http::StatusCode,
+    middleware::Next,
+    response::{IntoResponse, Response},
+    Json,
+};
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+use tracing::Instrument;
+
+/// How long a request may take before the client gets a 504
+#[derive(Debug, Clone)]
+pub struct TimeoutConfig {
+    /// REQUEST_TIMEOUT_MS - every route without its own timeout
+    pub default: Duration,
+    /// REQUEST_TIMEOUT_ROUTES - per-route overrides, keyed by route pattern
+    pub routes: HashMap<String, Duration>,
+}
+
+impl Default for TimeoutConfig {
+    fn default() -> Self {
+        Self {
+            default: Duration::from_secs(10),
+            // Reads and republishes up to MAX_REPLAY_EVENTS events
+            routes: HashMap::from([("/admin/replay".to_string(), Duration::from_secs(60))]),
+        }
+    }
+}
+
+impl TimeoutConfig {
+    /// REQUEST_TIMEOUT_ROUTES is `pattern=ms` pairs, comma separated, e.g.
+    /// `/graphql=5000,/admin/replay=120000`
+    pub fn from_env() -> Self {
+        let mut config = Self::default();
+        if let Some(ms) = std::env::var("REQUEST_TIMEOUT_MS")
+            .ok()
+            .and_then(|v| v.parse().ok())
+        {
+            config.default = Duration::from_millis(ms);
+        }
+        if let Ok(routes) = std::env::var("REQUEST_TIMEOUT_ROUTES") {
+            config.routes.extend(parse_routes(&routes));
+        }
+        config
+    }
+
+    /// The timeout for a route pattern (as matched, e.g. `/wallets/:wallet_id/history`)
+    pub fn for_route(&self, route: &str) -> Duration {
+        self.routes.get(route).copied().unwrap_or(self.default)
+    }
+}
+
+/// `pattern=ms` pairs; malformed entries are logged and skipped
+pub fn parse_routes(value: &str) -> HashMap<String, Duration> {
+    value
+        .split(',')
+        .map(str::trim)
+        .filter(|entry| !entry.is_empty())
+        .filter_map(|entry| {
+            let parsed = entry
+                .rsplit_once('=')
+                .and_then(|(route, ms)| Some((route.trim(), ms.trim().parse().ok()?)));
+            match parsed {
+                Some((route, ms)) if route.starts_with('/') => {
+                    Some((route.to_string(), Duration::from_millis(ms)))
+                }
+                _ => {
+                    tracing::warn!(entry, "Ignoring malformed REQUEST_TIMEOUT_ROUTES entry");
+                    None
+                }
+            }
+        })
+        .collect()
+}
+
+/// Middleware: answer 504 when a route takes longer than its timeout
+///
+/// - The handler runs in its own task and is left to finish; only the
+///   client stops waiting. A replay dropped halfway would have republished
+///   part of its window, so a 504 from `/admin/replay` means "check the
+///   topic before replaying again"
+/// - Exports stream their body: the timeout covers the time to the first
+///   byte, not the whole download
+///
+/// Added with `route_layer` so the matched route pattern is known.
+pub async fn enforce_timeout(
+    State(config): State<Arc<TimeoutConfig>>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let route = request
+        .extensions()
+        .get::<MatchedPath>()
+        .map(|path| path.as_str().to_string())
+        .unwrap_or_else(|| request.uri().path().to_string());
+    let timeout = config.for_route(&route);
+
+    let handler = tokio::spawn(next.run(request).in_current_span());
+
+    match tokio::time::timeout(timeout, handler).await {
+        Ok(Ok(response)) => response,
+        Ok(Err(e)) => {
+            tracing::error!(route = %route, error = %e, "Request handler panicked");
+            error_response(StatusCode::INTERNAL_SERVER_ERROR, "Internal server error".to_string())
+        }
+        Err(_) => {
+            tracing::warn!(
+                route = %route,
+                timeout_ms = timeout.as_millis() as u64,
+                "Request timed out"
+            );
+            error_response(
+                StatusCode::GATEWAY_TIMEOUT,
+                format!("Request timed out after {}ms", timeout.as_millis()),
+            )
+        }
+    }
+}
+
+fn error_response(status: StatusCode, error: String) -> Response {
+    let body = Json(ErrorResponse {
+        success: false,
+        error,
+    });
+    (status, body).into_response()
+}
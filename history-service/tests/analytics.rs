@@ -0,0 +1,78 @@
+//! Tests for the ClickHouse copy's encoding (no server needed)
+//!
+//! Run with: cargo test --features clickhouse --test analytics
+#![cfg(feature = "clickhouse")]
+
+use chrono::{TimeZone, Utc};
+use history_service::analytics::{array_param, event_rows, parse_summary_rows, period_start_function};
+use history_service::errors::HistoryError;
+use history_service::models::TransactionEvent;
+use history_service::summary::{summarize, SummaryPeriod};
+use rust_decimal::Decimal;
+
+#[test]
+fn test_events_are_copied_without_their_payload() {
+    let event = TransactionEvent {
+        id: "row-1".to_string(),
+        wallet_id: "wallet-1".to_string(),
+        user_id: "alice".to_string(),
+        amount: Decimal::new(105000, 4),
+        event_type: "WALLET_FUNDED".to_string(),
+        transaction_id: Some("tx-1".to_string()),
+        created_at: Utc.timestamp_micros(1_738_404_000_123_456).unwrap(),
+        event_data: serde_json::json!({"memo": "not copied"}),
+        event_id: None,
+        correlation_id: None,
+        causation_id: None,
+    };
+
+    let rows = event_rows(&[event.clone(), event]);
+    let lines: Vec<&str> = rows.lines().collect();
+    assert_eq!(lines.len(), 2);
+
+    let row: serde_json::Value = serde_json::from_str(lines[0]).unwrap();
+    assert_eq!(row["id"], "row-1");
+    assert_eq!(row["amount"], "10.5000");
+    assert_eq!(row["created_at"], "2025-02-01 10:00:00.123456");
+    assert!(row.get("event_data").is_none());
+}
+
+#[test]
+fn test_summary_rows_fold_like_postgres_rows() {
+    let body = concat!(
+        r#"{"period_start":"2025-03-01 00:00:00","event_type":"TRANSFER_OUT","events":"2","amount":"70.0000"}"#,
+        "\n",
+        r#"{"period_start":"2025-03-01 00:00:00","event_type":"WALLET_FUNDED","events":"1","amount":"100"}"#,
+        "\n",
+        r#"{"period_start":"2025-02-01 00:00:00","event_type":"TRANSFER_IN","events":"1","amount":"30"}"#,
+        "\n",
+    );
+
+    let rows = parse_summary_rows(body).unwrap();
+    assert_eq!(rows[0].0, Utc.with_ymd_and_hms(2025, 3, 1, 0, 0, 0).unwrap());
+    assert_eq!((rows[0].2, rows[0].3), (2, Decimal::new(70, 0)));
+
+    let periods = summarize(rows);
+    assert_eq!(periods.len(), 2);
+    assert_eq!(periods[0].net_change, Decimal::new(30, 0));
+    assert_eq!(periods[1].total_in, Decimal::new(30, 0));
+
+    assert!(parse_summary_rows("").unwrap().is_empty());
+    assert!(matches!(
+        parse_summary_rows(r#"{"period_start":"March","event_type":"X","events":"1","amount":"1"}"#),
+        Err(HistoryError::AnalyticsError(_))
+    ));
+}
+
+#[test]
+fn test_parameters_are_quoted() {
+    assert_eq!(
+        array_param(&["TRANSFER_IN".to_string(), "it's".to_string()]),
+        r"['TRANSFER_IN','it\'s']"
+    );
+    assert_eq!(array_param(&[]), "[]");
+
+    // Weeks start on Monday, as with date_trunc
+    assert_eq!(period_start_function(SummaryPeriod::Week), "toMonday");
+    assert_eq!(period_start_function(SummaryPeriod::Month), "toStartOfMonth");
+}
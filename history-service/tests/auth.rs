@@ -0,0 +1,73 @@
+//! Tests for roles and history access (no database needed)
+
+use axum::http::Method;
+use history_service::auth::{check_user, required_role, ClaimMapping, Principal, Role};
+use history_service::errors::HistoryError;
+use serde_json::json;
+
+fn claims(value: serde_json::Value) -> serde_json::Map<String, serde_json::Value> {
+    value.as_object().unwrap().clone()
+}
+
+fn roles_at(path: &str) -> ClaimMapping {
+    ClaimMapping {
+        roles_claim: path.to_string(),
+        ..ClaimMapping::default()
+    }
+}
+
+#[test]
+fn test_highest_claimed_role_wins() {
+    let listed = claims(json!({"roles": ["support", "admin", "billing"]}));
+    assert_eq!(ClaimMapping::default().role(&listed), Role::Admin);
+
+    // Keycloak
+    let nested = claims(json!({"realm_access": {"roles": ["support"]}}));
+    assert_eq!(roles_at("realm_access.roles").role(&nested), Role::Support);
+    assert_eq!(ClaimMapping::default().role(&nested), Role::User);
+
+    let spaced = claims(json!({"groups": "staff support"}));
+    assert_eq!(roles_at("groups").role(&spaced), Role::Support);
+}
+
+#[test]
+fn test_namespaced_claims_and_provider_role_names() {
+    // Auth0: custom claims are namespaced by URL (dots and all)
+    let token = claims(json!({
+        "sub": "auth0|5f7c8ec7c33c6c004bbafe82",
+        "https://wallet.example.com/user_id": "alice",
+        "https://wallet.example.com/roles": ["wallet-admin"],
+    }));
+    let mapping = ClaimMapping {
+        user_id_claim: "https://wallet.example.com/user_id".to_string(),
+        roles_claim: "https://wallet.example.com/roles".to_string(),
+        admin_role: "wallet-admin".to_string(),
+        support_role: "wallet-support".to_string(),
+    };
+    assert_eq!(mapping.user_id(&token).as_deref(), Some("alice"));
+    assert_eq!(mapping.role(&token), Role::Admin);
+
+    // The default names mean nothing to this provider
+    assert_eq!(roles_at("https://wallet.example.com/roles").role(&token), Role::User);
+    assert_eq!(
+        ClaimMapping::default().user_id(&token).as_deref(),
+        Some("auth0|5f7c8ec7c33c6c004bbafe82")
+    );
+    assert_eq!(ClaimMapping::default().user_id(&claims(json!({"sub": ""}))), None);
+}
+
+#[test]
+fn test_support_reads_anyone_and_only_admins_steer_the_consumer() {
+    let user = Principal::user("alice");
+    let support = Principal::user("carol").with_role(Role::Support);
+
+    assert!(check_user(&user, "alice").is_ok());
+    assert!(matches!(check_user(&user, "bob"), Err(HistoryError::Forbidden(_))));
+    assert!(check_user(&support, "bob").is_ok());
+
+    assert_eq!(required_role(&Method::GET, "/wallets/w-1/history"), Role::User);
+    assert_eq!(required_role(&Method::POST, "/graphql"), Role::User);
+    assert_eq!(required_role(&Method::GET, "/admin/consumer"), Role::Support);
+    assert_eq!(required_role(&Method::POST, "/admin/consumer/pause"), Role::Admin);
+    assert_eq!(required_role(&Method::POST, "/admin/replay"), Role::Admin);
+}
@@ -0,0 +1,67 @@
+//! Tests for balance-as-of classification (no database needed)
+
+use history_service::balance::{BalanceQuery, CREDIT_TYPES, DEBIT_TYPES};
+use history_service::filter::STORED_EVENT_TYPES;
+use history_service::models::WalletEvent;
+use serde_json::json;
+
+#[test]
+fn test_every_money_moving_type_is_credit_or_debit() {
+    for event_type in STORED_EVENT_TYPES {
+        let credit = CREDIT_TYPES.contains(event_type);
+        let debit = DEBIT_TYPES.contains(event_type);
+        assert!(!(credit && debit), "{} is both credit and debit", event_type);
+        // A new stored type that moves money must be added to one list
+        assert!(
+            credit || debit || *event_type == "WALLET_CREATED",
+            "{} is neither credit nor debit",
+            event_type
+        );
+    }
+}
+
+#[test]
+fn test_every_transfer_out_has_an_in() {
+    for debit in DEBIT_TYPES {
+        let credit = debit.replace("_OUT", "_IN");
+        assert!(CREDIT_TYPES.contains(&credit.as_str()), "No credit leg for {}", debit);
+    }
+}
+
+#[test]
+fn test_adjustments_are_stored_by_direction() {
+    let adjusted = |direction: &str| -> WalletEvent {
+        serde_json::from_value(json!({
+            "eventType": "WALLET_ADJUSTED",
+            "wallet_id": "w1",
+            "user_id": "u1",
+            "direction": direction,
+            "amount": "12.50",
+            "new_balance": "87.50",
+            "reason_code": "CHARGEBACK",
+            "actor": "ops@example.com",
+            "adjustment_id": "a1",
+            "transaction_id": "t1",
+            "timestamp": "2025-03-03T12:00:00Z"
+        }))
+        .unwrap()
+    };
+
+    let debit = adjusted("DEBIT");
+    assert_eq!(debit.event_type(), "WALLET_ADJUSTED");
+    assert_eq!(debit.stored_event_type(), "ADJUSTMENT_OUT");
+    assert!(DEBIT_TYPES.contains(&debit.stored_event_type()));
+    assert_eq!(debit.transaction_id().as_deref(), Some("t1"));
+
+    let credit = adjusted("CREDIT");
+    assert!(CREDIT_TYPES.contains(&credit.stored_event_type()));
+}
+
+#[test]
+fn test_at_is_optional() {
+    let query: BalanceQuery = serde_json::from_str("{}").unwrap();
+    assert!(query.at.is_none());
+
+    let query: BalanceQuery = serde_json::from_str(r#"{"at": "2025-03-03T12:00:00Z"}"#).unwrap();
+    assert_eq!(query.at.unwrap().to_rfc3339(), "2025-03-03T12:00:00+00:00");
+}
@@ -0,0 +1,44 @@
+//! Tests for the response cache (no database needed)
+
+use history_service::cache::{CacheScope, ResponseCache};
+use std::time::Duration;
+
+#[test]
+fn test_cache_hit_miss_and_invalidation() {
+    let cache = ResponseCache::new(Duration::from_secs(60));
+    let wallet = CacheScope::Wallet("wallet-1".to_string());
+    let user = CacheScope::User("alice".to_string());
+
+    assert_eq!(cache.get::<Vec<u32>>(&wallet, "history"), None);
+
+    cache.insert(wallet.clone(), "history", vec![1u32, 2, 3]);
+    cache.insert(user.clone(), "activity", vec![4u32]);
+    assert_eq!(cache.get::<Vec<u32>>(&wallet, "history"), Some(vec![1, 2, 3]));
+
+    // Different parameters are a different entry
+    assert_eq!(cache.get::<Vec<u32>>(&wallet, "history?limit=1"), None);
+
+    // Invalidating the wallet leaves the user's entries alone
+    cache.invalidate(&wallet);
+    assert_eq!(cache.get::<Vec<u32>>(&wallet, "history"), None);
+    assert_eq!(cache.get::<Vec<u32>>(&user, "activity"), Some(vec![4]));
+
+    let stats = cache.stats();
+    assert_eq!(stats.hits, 2);
+    assert_eq!(stats.misses, 3);
+    assert_eq!(stats.entries, 1);
+}
+
+#[test]
+fn test_expired_and_disabled_cache() {
+    let cache = ResponseCache::new(Duration::ZERO);
+    let wallet = CacheScope::Wallet("wallet-1".to_string());
+
+    cache.insert(wallet.clone(), "history", vec![1u32]);
+    assert_eq!(cache.get::<Vec<u32>>(&wallet, "history"), None);
+
+    let cache = ResponseCache::disabled();
+    cache.insert(wallet.clone(), "history", vec![1u32]);
+    assert_eq!(cache.get::<Vec<u32>>(&wallet, "history"), None);
+    assert!(!cache.stats().enabled);
+}
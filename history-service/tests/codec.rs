@@ -0,0 +1,202 @@
+//! Tests for payload decoding (no Kafka or Schema Registry needed)
+
+use chrono::{TimeZone, Utc};
+use history_service::avro::{Schema, WALLET_EVENT_SCHEMA};
+use history_service::codec::EventDecoder;
+use history_service::models::WalletEvent;
+use serde_json::json;
+
+/// WalletCreated { wallet_id: "w1", user_id: "u1", timestamp: 1s } as
+/// wallet-service's Avro codec writes it, under schema ID 7
+const AVRO_WALLET_CREATED: &[u8] = &[
+    0, 0, 0, 0, 7, // magic byte + schema ID
+    0, // union branch 0 = WalletCreated
+    4, b'w', b'1', 4, b'u', b'1', // wallet_id, user_id
+    0x80, 0x89, 0x7a, // timestamp: 1_000_000 micros
+    0, 0, 0, // event_id, correlation_id, causation_id: ""
+    0, // sequence: 0 (unnumbered)
+    0, // request_id: ""
+];
+
+fn assert_wallet_created(event: WalletEvent) {
+    match event {
+        WalletEvent::WalletCreated {
+            wallet_id,
+            user_id,
+            timestamp,
+        } => {
+            assert_eq!((wallet_id.as_str(), user_id.as_str()), ("w1", "u1"));
+            assert_eq!(timestamp, Utc.timestamp_opt(1, 0).unwrap());
+        }
+        other => panic!("unexpected event {:?}", other),
+    }
+}
+
+#[tokio::test]
+async fn test_detects_json_and_avro() {
+    let decoder = EventDecoder::without_registry().unwrap();
+    decoder.register_writer_schema(7, Schema::parse(WALLET_EVENT_SCHEMA).unwrap());
+
+    let json = br#"{"eventType":"WALLET_CREATED","wallet_id":"w1","user_id":"u1","timestamp":"1970-01-01T00:00:01Z"}"#;
+    assert_wallet_created(decoder.decode(json).await.unwrap().event);
+    assert_wallet_created(decoder.decode(AVRO_WALLET_CREATED).await.unwrap().event);
+}
+
+#[tokio::test]
+async fn test_detects_protobuf() {
+    let decoder = EventDecoder::without_registry().unwrap();
+
+    // As wallet-service's Protobuf codec writes it
+    let payload = [
+        0x0a, 14, // wallet_created = 1
+        0x0a, 2, b'w', b'1', 0x12, 2, b'u', b'1', // wallet_id, user_id
+        0x1a, 4, 0x08, 1, 0x10, 0, // timestamp { seconds: 1 }
+    ];
+    assert_wallet_created(decoder.decode(&payload).await.unwrap().event);
+
+    // A newer producer's extra field 9 is skipped
+    let mut newer = payload.to_vec();
+    newer[1] += 5;
+    newer.extend_from_slice(&[0x4a, 3, b'a', b'p', b'p']);
+    assert_wallet_created(decoder.decode(&newer).await.unwrap().event);
+}
+
+#[tokio::test]
+async fn test_newer_writer_schema_is_readable() {
+    // A later producer added an optional "channel" field (BACKWARD-compatible)
+    let mut newer: serde_json::Value = serde_json::from_str(WALLET_EVENT_SCHEMA).unwrap();
+    newer[0]["fields"]
+        .as_array_mut()
+        .unwrap()
+        .push(json!({"name": "channel", "type": ["null", "string"], "default": null}));
+
+    let decoder = EventDecoder::without_registry().unwrap();
+    decoder.register_writer_schema(8, Schema::parse(&newer.to_string()).unwrap());
+
+    let mut payload = AVRO_WALLET_CREATED.to_vec();
+    payload[4] = 8;
+    payload.extend_from_slice(&[2, 6, b'a', b'p', b'p']); // channel = "app"
+
+    assert_wallet_created(decoder.decode(&payload).await.unwrap().event);
+}
+
+#[test]
+fn test_reader_defaults_fill_fields_old_writers_lack() {
+    let reader = Schema::parse(
+        &json!([{
+            "type": "record",
+            "name": "WalletCreated",
+            "eventType": "WALLET_CREATED",
+            "fields": [
+                {"name": "wallet_id", "type": "string"},
+                {"name": "channel", "type": "string", "default": "api"}
+            ]
+        }])
+        .to_string(),
+    )
+    .unwrap();
+
+    let mut value = json!({"eventType": "WALLET_CREATED", "wallet_id": "w1"});
+    reader.fill_defaults(&mut value);
+    assert_eq!(value["channel"], "api");
+}
+
+#[tokio::test]
+async fn test_unknown_schema_without_registry_is_not_retryable() {
+    let decoder = EventDecoder::without_registry().unwrap();
+
+    let error = decoder.decode(AVRO_WALLET_CREATED).await.unwrap_err();
+    assert!(!error.is_retryable());
+}
+
+#[tokio::test]
+async fn test_decodes_decline_events() {
+    let decoder = EventDecoder::without_registry().unwrap();
+
+    // FundingFailed (oneof 7): wallet_id, user_id, amount, reason
+    let mut body = Vec::new();
+    for (tag, text) in [(0x0a, "w1"), (0x12, "u1"), (0x1a, "-5"), (0x22, "invalid_amount")] {
+        body.extend_from_slice(&[tag, text.len() as u8]);
+        body.extend_from_slice(text.as_bytes());
+    }
+    let mut payload = vec![0x3a, body.len() as u8];
+    payload.extend_from_slice(&body);
+
+    let event = decoder.decode(&payload).await.unwrap().event;
+    assert_eq!(event.event_type(), "FUNDING_FAILED");
+    assert_eq!(event.transaction_id(), None);
+    match event {
+        WalletEvent::FundingFailed { reason, .. } => assert_eq!(reason, "invalid_amount"),
+        other => panic!("unexpected event {:?}", other),
+    }
+}
+
+#[tokio::test]
+async fn test_decodes_user_anonymized() {
+    let decoder = EventDecoder::without_registry().unwrap();
+
+    // UserAnonymized (oneof 14): user_id, anonymized_user_id
+    let mut body = Vec::new();
+    for (tag, text) in [(0x0a, "u1"), (0x12, "anon-1")] {
+        body.extend_from_slice(&[tag, text.len() as u8]);
+        body.extend_from_slice(text.as_bytes());
+    }
+    let mut payload = vec![0x72, body.len() as u8];
+    payload.extend_from_slice(&body);
+
+    let event = decoder.decode(&payload).await.unwrap().event;
+    assert_eq!(event.event_type(), "USER_ANONYMIZED");
+    assert_eq!((event.wallet_id(), event.user_id()), ("u1", "u1"));
+    assert_eq!(event.transaction_id(), None);
+    match event {
+        WalletEvent::UserAnonymized { anonymized_user_id, .. } => {
+            assert_eq!(anonymized_user_id, "anon-1")
+        }
+        other => panic!("unexpected event {:?}", other),
+    }
+}
+
+#[tokio::test]
+async fn test_decodes_tracing_ids() {
+    let decoder = EventDecoder::without_registry().unwrap();
+
+    let json = br#"{"eventType":"WALLET_CREATED","wallet_id":"w1","user_id":"u1","timestamp":"1970-01-01T00:00:01Z","event_id":"e1","correlation_id":"c1","causation_id":"c1","request_id":"r1"}"#;
+    let envelope = decoder.decode(json).await.unwrap();
+    assert_eq!(envelope.ids.event_id(), Some("e1"));
+    assert_eq!(envelope.ids.correlation_id(), Some("c1"));
+    assert_eq!(envelope.ids.causation_id(), Some("c1"));
+    assert_eq!(envelope.ids.request_id(), Some("r1"));
+    assert_wallet_created(envelope.event);
+
+    // Events from before the IDs existed decode with none
+    let legacy = decoder
+        .decode(br#"{"eventType":"WALLET_CREATED","wallet_id":"w1","user_id":"u1","timestamp":"1970-01-01T00:00:01Z"}"#)
+        .await
+        .unwrap();
+    assert_eq!(legacy.ids.event_id(), None);
+    assert_eq!(legacy.ids.correlation_id(), None);
+    assert_eq!(legacy.ids.request_id(), None);
+}
+
+#[tokio::test]
+async fn test_decodes_sequence() {
+    let decoder = EventDecoder::without_registry().unwrap();
+
+    let json = br#"{"eventType":"WALLET_CREATED","wallet_id":"w1","user_id":"u1","timestamp":"1970-01-01T00:00:01Z","sequence":3}"#;
+    assert_eq!(decoder.decode(json).await.unwrap().sequence(), Some(3));
+
+    // Protobuf: sequence (field 7) = 3, a varint
+    let payload = [
+        0x0a, 16, // wallet_created = 1
+        0x0a, 2, b'w', b'1', 0x12, 2, b'u', b'1', // wallet_id, user_id
+        0x1a, 4, 0x08, 1, 0x10, 0, // timestamp { seconds: 1 }
+        0x38, 3, // sequence
+    ];
+    let envelope = decoder.decode(&payload).await.unwrap();
+    assert_eq!(envelope.sequence(), Some(3));
+    assert_wallet_created(envelope.event);
+
+    // Unnumbered (older) events
+    decoder.register_writer_schema(7, Schema::parse(WALLET_EVENT_SCHEMA).unwrap());
+    assert_eq!(decoder.decode(AVRO_WALLET_CREATED).await.unwrap().sequence(), None);
+}
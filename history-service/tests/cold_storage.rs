@@ -0,0 +1,57 @@
+//! Tests for cold storage settings and the truncation flag (no database needed)
+
+use axum::response::IntoResponse;
+use chrono::{TimeZone, Utc};
+use history_service::cold_storage::{month_start, open_store, ColdStorageConfig};
+use history_service::export::{flag_truncated, ExportFormat, HISTORY_TRUNCATED_HEADER};
+use history_service::models::ApiResponse;
+use std::time::Duration;
+
+fn config(after_months: u32) -> ColdStorageConfig {
+    ColdStorageConfig {
+        url: "file:///tmp/unused".to_string(),
+        after_months,
+        format: ExportFormat::Parquet,
+        interval: Duration::from_secs(60),
+    }
+}
+
+#[test]
+fn test_only_whole_months_past_the_cutoff_are_archived() {
+    let now = Utc.with_ymd_and_hms(2025, 3, 17, 15, 30, 0).unwrap();
+    assert_eq!(month_start(now), Utc.with_ymd_and_hms(2025, 3, 1, 0, 0, 0).unwrap());
+
+    // 12 months back from the start of March: March 2024 is kept whole
+    assert_eq!(config(12).cutoff(now), Utc.with_ymd_and_hms(2024, 3, 1, 0, 0, 0).unwrap());
+    assert_eq!(config(3).cutoff(now), Utc.with_ymd_and_hms(2024, 12, 1, 0, 0, 0).unwrap());
+}
+
+#[test]
+fn test_store_urls() {
+    let dir = std::env::temp_dir().join(format!("cold-storage-{}", std::process::id()));
+    let url = format!("file://{}", dir.display());
+    assert!(open_store(&url).is_ok());
+    assert!(dir.is_dir(), "the directory is created");
+    let _ = std::fs::remove_dir_all(&dir);
+
+    assert!(open_store("ftp://archive/events").is_err());
+}
+
+#[test]
+fn test_truncation_is_flagged_only_when_set() {
+    let horizon = Utc.with_ymd_and_hms(2024, 3, 1, 0, 0, 0).unwrap();
+
+    let flagged = serde_json::to_value(ApiResponse::success(1).truncated_before(Some(horizon))).unwrap();
+    assert_eq!(flagged["history_truncated_before"], "2024-03-01T00:00:00Z");
+    let whole = serde_json::to_value(ApiResponse::success(1).truncated_before(None)).unwrap();
+    assert!(whole.get("history_truncated_before").is_none());
+
+    let response = flag_truncated("rows".into_response(), Some(horizon));
+    assert_eq!(
+        response.headers()[HISTORY_TRUNCATED_HEADER],
+        "2024-03-01T00:00:00+00:00"
+    );
+    assert!(!flag_truncated("rows".into_response(), None)
+        .headers()
+        .contains_key(HISTORY_TRUNCATED_HEADER));
+}
@@ -0,0 +1,69 @@
+//! Tests for the consumer pause/resume switch (no Kafka needed)
+
+use chrono::{TimeZone, Utc};
+use history_service::control::{ConsumerControl, SeekRequest, SeekScope, SeekTarget};
+use serde_json::json;
+
+fn seek_request(body: serde_json::Value) -> SeekRequest {
+    serde_json::from_value(body).unwrap()
+}
+
+#[tokio::test]
+async fn test_pause_and_resume_notify_consumers() {
+    let control = ConsumerControl::new();
+    let mut watcher = control.subscribe();
+    assert!(!control.is_paused());
+    assert_eq!(control.status().changed_at, None);
+
+    let status = control.pause();
+    assert!(status.paused);
+    assert!(status.changed_at.is_some());
+    watcher.changed().await.unwrap();
+    assert!(*watcher.borrow_and_update());
+
+    control.resume();
+    watcher.changed().await.unwrap();
+    assert!(!*watcher.borrow_and_update());
+}
+
+#[test]
+fn test_repeated_pause_is_a_no_op() {
+    let control = ConsumerControl::new();
+    let mut watcher = control.subscribe();
+
+    let first = control.pause();
+    watcher.borrow_and_update();
+    let second = control.pause();
+
+    assert_eq!(first.changed_at, second.changed_at);
+    assert!(!watcher.has_changed().unwrap());
+}
+
+#[test]
+fn test_seek_request_validation() {
+    let at = Utc.with_ymd_and_hms(2025, 1, 29, 10, 0, 0).unwrap();
+
+    let scope = seek_request(json!({"timestamp": "2025-01-29T10:00:00Z"})).scope().unwrap();
+    assert_eq!(scope, SeekScope::AllAssigned(at));
+
+    let scope = seek_request(json!({"partitions": [
+        {"partition": 0, "offset": 1200},
+        {"partition": 1, "timestamp": "2025-01-29T10:00:00Z"}
+    ]}))
+    .scope()
+    .unwrap();
+    assert_eq!(
+        scope,
+        SeekScope::Partitions(vec![(0, SeekTarget::Offset(1200)), (1, SeekTarget::Timestamp(at))])
+    );
+
+    for invalid in [
+        json!({}),
+        json!({"timestamp": "2025-01-29T10:00:00Z", "partitions": [{"partition": 0, "offset": 1}]}),
+        json!({"partitions": [{"partition": 0}]}),
+        json!({"partitions": [{"partition": 0, "offset": -1}]}),
+        json!({"partitions": [{"partition": 0, "offset": 1, "timestamp": "2025-01-29T10:00:00Z"}]}),
+    ] {
+        assert!(seek_request(invalid).scope().is_err());
+    }
+}
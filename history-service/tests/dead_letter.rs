@@ -0,0 +1,45 @@
+//! Tests for dead-letter envelopes (no Kafka needed)
+
+use history_service::dlq::DeadLetter;
+use history_service::errors::HistoryError;
+
+#[test]
+fn test_poison_message_envelope() {
+    let error = HistoryError::SerializationError("missing field `wallet_id`".to_string());
+    let letter = DeadLetter::new(
+        "wallet-events",
+        2,
+        1041,
+        Some(b"wallet-1"),
+        b"{\"eventType\":\"WALLET_CREATED\"}",
+        &error,
+        1,
+    );
+
+    assert_eq!(letter.key.as_deref(), Some("wallet-1"));
+    assert_eq!(letter.payload, "{\"eventType\":\"WALLET_CREATED\"}");
+    assert!(!letter.retryable);
+
+    let json = serde_json::to_value(&letter).unwrap();
+    assert_eq!(json["original_topic"], "wallet-events");
+    assert_eq!(json["partition"], 2);
+    assert_eq!(json["offset"], 1041);
+    assert!(json["error"].as_str().unwrap().contains("missing field"));
+}
+
+#[test]
+fn test_transient_errors_are_retryable() {
+    assert!(HistoryError::KafkaError("timeout".to_string()).is_retryable());
+    assert!(!HistoryError::InternalError("unexpected".to_string()).is_retryable());
+}
+
+#[test]
+fn test_unreachable_database_is_a_connection_error() {
+    assert!(HistoryError::DatabaseError(sqlx::Error::PoolTimedOut).is_connection_error());
+    let refused = std::io::Error::new(std::io::ErrorKind::ConnectionRefused, "refused");
+    assert!(HistoryError::DatabaseError(sqlx::Error::Io(refused)).is_connection_error());
+
+    // A query the database rejected is still retried message by message
+    assert!(!HistoryError::DatabaseError(sqlx::Error::RowNotFound).is_connection_error());
+    assert!(!HistoryError::KafkaError("timeout".to_string()).is_connection_error());
+}
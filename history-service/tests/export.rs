@@ -0,0 +1,174 @@
+//! Tests for history export formatting (no database needed)
+
+use chrono::{TimeZone, Utc};
+use arrow_array::{Array, Decimal128Array, StringArray, TimestampMicrosecondArray};
+use axum::body::Bytes;
+use history_service::export::{csv_field, export_filename, ExportEncoder, ExportFormat, ExportQuery, ExportScope};
+use parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder;
+use history_service::models::TransactionEvent;
+use rust_decimal::Decimal;
+
+fn transfer_out() -> TransactionEvent {
+    TransactionEvent {
+        id: "tx-1:out".to_string(),
+        wallet_id: "wallet-1".to_string(),
+        user_id: "user-1".to_string(),
+        amount: Decimal::new(3050, 2),
+        event_type: "TRANSFER_OUT".to_string(),
+        transaction_id: Some("tx-1".to_string()),
+        created_at: Utc.with_ymd_and_hms(2025, 3, 3, 12, 0, 0).unwrap(),
+        event_data: serde_json::json!({}),
+        event_id: Some("evt-1".to_string()),
+        correlation_id: None,
+        causation_id: None,
+    }
+}
+
+#[test]
+fn test_csv_row_matches_header() {
+    let mut encoder = ExportEncoder::new(ExportFormat::Csv).unwrap();
+    encoder.push(transfer_out()).unwrap();
+    let out = String::from_utf8(encoder.finish().unwrap()).unwrap();
+
+    let lines: Vec<&str> = out.lines().collect();
+    assert_eq!(lines.len(), 2);
+    assert_eq!(
+        lines[1],
+        "tx-1:out,wallet-1,user-1,TRANSFER_OUT,30.50,tx-1,evt-1,,2025-03-03T12:00:00+00:00"
+    );
+    assert_eq!(lines[0].split(',').count(), lines[1].split(',').count());
+}
+
+#[test]
+fn test_csv_fields_are_quoted_only_when_needed() {
+    assert_eq!(csv_field("plain"), "plain");
+    assert_eq!(csv_field("a,b"), "\"a,b\"");
+    assert_eq!(csv_field("say \"hi\""), "\"say \"\"hi\"\"\"");
+    assert_eq!(csv_field("two\nlines"), "\"two\nlines\"");
+}
+
+#[test]
+fn test_filename_drops_unsafe_characters() {
+    let wallet = ExportScope::Wallet("wallet-1".to_string());
+    assert_eq!(export_filename(&wallet, ExportFormat::Csv), "wallet-1-history.csv");
+    let user = ExportScope::User("a\"b/c".to_string());
+    assert_eq!(export_filename(&user, ExportFormat::Parquet), "abc-activity.parquet");
+}
+
+#[test]
+fn test_format_defaults_to_csv() {
+    let query: ExportQuery = serde_json::from_str("{}").unwrap();
+    assert_eq!(query.format, ExportFormat::Csv);
+
+    let query: ExportQuery = serde_json::from_str(r#"{"format": "csv"}"#).unwrap();
+    assert_eq!(query.format, ExportFormat::Csv);
+    let query: ExportQuery = serde_json::from_str(r#"{"format": "ndjson"}"#).unwrap();
+    assert_eq!(query.format, ExportFormat::Ndjson);
+    let query: ExportQuery = serde_json::from_str(r#"{"format": "parquet"}"#).unwrap();
+    assert_eq!(query.format, ExportFormat::Parquet);
+    let query: ExportQuery = serde_json::from_str(r#"{"format": "json"}"#).unwrap();
+    assert_eq!(query.format, ExportFormat::Json);
+    assert!(serde_json::from_str::<ExportQuery>(r#"{"format": "xlsx"}"#).is_err());
+}
+
+#[test]
+fn test_ndjson_is_one_full_event_per_line() {
+    let mut event = transfer_out();
+    // Newlines inside event_data must not split the line
+    event.event_data = serde_json::json!({"eventType": "TRANSFER_COMPLETED", "note": "two\nlines"});
+
+    let mut encoder = ExportEncoder::new(ExportFormat::Ndjson).unwrap();
+    encoder.push(event.clone()).unwrap();
+    encoder.push(event).unwrap();
+    let out = String::from_utf8(encoder.finish().unwrap()).unwrap();
+
+    let lines: Vec<&str> = out.lines().collect();
+    assert_eq!(lines.len(), 2);
+    let parsed: TransactionEvent = serde_json::from_str(lines[0]).unwrap();
+    assert_eq!(parsed.id, "tx-1:out");
+    assert_eq!(parsed.amount, Decimal::new(3050, 2));
+    assert_eq!(parsed.event_data["note"], "two\nlines");
+}
+
+#[test]
+fn test_json_is_one_document_split_across_chunks() {
+    let mut encoder = ExportEncoder::new(ExportFormat::Json).unwrap();
+    let mut out = Vec::new();
+    for i in 0..3 {
+        let mut event = transfer_out();
+        event.id = format!("tx-{}:out", i);
+        encoder.push(event).unwrap();
+        if let Some(chunk) = encoder.take_chunk(1) {
+            out.extend(chunk);
+        }
+    }
+    out.extend(encoder.finish().unwrap());
+
+    let document: serde_json::Value = serde_json::from_slice(&out).unwrap();
+    assert_eq!(document["success"], true);
+    let events = document["data"].as_array().unwrap();
+    assert_eq!(events.len(), 3);
+    assert_eq!(events[2]["id"], "tx-2:out");
+    // The history pages' objects: no event_data
+    assert!(events[0].get("event_data").is_none());
+
+    let empty = ExportEncoder::new(ExportFormat::Json).unwrap().finish().unwrap();
+    assert_eq!(String::from_utf8(empty).unwrap(), r#"{"success":true,"data":[]}"#);
+}
+
+#[test]
+fn test_text_chunks_wait_for_min_bytes() {
+    let mut encoder = ExportEncoder::new(ExportFormat::Csv).unwrap();
+    encoder.push(transfer_out()).unwrap();
+    assert!(encoder.take_chunk(64 * 1024).is_none());
+
+    let chunk = encoder.take_chunk(1).unwrap();
+    assert!(String::from_utf8(chunk).unwrap().starts_with("id,wallet_id"));
+    // Taken bytes aren't sent again
+    assert!(encoder.finish().unwrap().is_empty());
+}
+
+#[test]
+fn test_parquet_reads_back_with_typed_columns() {
+    let mut encoder = ExportEncoder::new(ExportFormat::Parquet).unwrap();
+    let mut file = Vec::new();
+    for i in 0..3 {
+        let mut event = transfer_out();
+        event.id = format!("tx-{}:out", i);
+        encoder.push(event).unwrap();
+        if let Some(chunk) = encoder.take_chunk(1) {
+            file.extend(chunk);
+        }
+    }
+    file.extend(encoder.finish().unwrap());
+
+    let reader = ParquetRecordBatchReaderBuilder::try_new(Bytes::from(file))
+        .unwrap()
+        .build()
+        .unwrap();
+    let batches: Vec<_> = reader.map(|batch| batch.unwrap()).collect();
+    let batch = &batches[0];
+    assert_eq!(batches.iter().map(|b| b.num_rows()).sum::<usize>(), 3);
+
+    let ids = batch.column_by_name("id").unwrap().as_any().downcast_ref::<StringArray>().unwrap();
+    assert_eq!(ids.value(2), "tx-2:out");
+
+    let amounts = batch
+        .column_by_name("amount")
+        .unwrap()
+        .as_any()
+        .downcast_ref::<Decimal128Array>()
+        .unwrap();
+    assert_eq!(amounts.value_as_string(0), "30.5000");
+
+    let created_at = batch
+        .column_by_name("created_at")
+        .unwrap()
+        .as_any()
+        .downcast_ref::<TimestampMicrosecondArray>()
+        .unwrap();
+    assert_eq!(created_at.value(0), transfer_out().created_at.timestamp_micros());
+
+    let correlation = batch.column_by_name("correlation_id").unwrap();
+    assert!(correlation.is_null(0));
+}
@@ -0,0 +1,84 @@
+//! Tests for history filters (no database needed)
+
+use history_service::filter::{HistoryFilter, WalletSelection, MAX_WALLET_IDS};
+
+/// Parse "a=1&b=2" the way the query extractor sees it (all strings)
+fn filter(query: &str) -> HistoryFilter {
+    let params: serde_json::Map<String, serde_json::Value> = query
+        .split('&')
+        .filter_map(|pair| pair.split_once('='))
+        .map(|(k, v)| (k.to_string(), serde_json::Value::String(v.to_string())))
+        .collect();
+    serde_json::from_value(serde_json::Value::Object(params)).unwrap()
+}
+
+#[test]
+fn test_transfers_in_march() {
+    let filter = filter("from=2025-03-01T00:00:00Z&to=2025-04-01T00:00:00Z&event_type=transfer_in,TRANSFER_OUT");
+
+    assert!(filter.validate().is_ok());
+    assert_eq!(
+        filter.event_types(),
+        Some(vec!["TRANSFER_IN".to_string(), "TRANSFER_OUT".to_string()])
+    );
+}
+
+#[test]
+fn test_no_filter_means_everything() {
+    let filter = filter("limit=20");
+
+    assert!(filter.validate().is_ok());
+    assert_eq!(filter.event_types(), None);
+    assert_eq!(filter.from, None);
+}
+
+#[test]
+fn test_rejects_unknown_types_and_backwards_windows() {
+    assert!(filter("event_type=TRANSFER_COMPLETED").validate().is_err());
+    assert!(filter("from=2025-04-01T00:00:00Z&to=2025-03-01T00:00:00Z")
+        .validate()
+        .is_err());
+}
+
+#[test]
+fn test_cache_key_differs_per_filter() {
+    let march = filter("from=2025-03-01T00:00:00Z");
+    let april = filter("from=2025-04-01T00:00:00Z");
+
+    assert_ne!(march.cache_key(), april.cache_key());
+    assert_eq!(march.cache_key(), filter("from=2025-03-01T00:00:00Z").cache_key());
+}
+
+#[test]
+fn test_wallet_selection_is_sorted_and_deduplicated() {
+    let wallets = WalletSelection {
+        wallet_ids: Some("w-2, w-1,,w-2".to_string()),
+    };
+
+    assert!(wallets.validate().is_ok());
+    assert_eq!(wallets.wallet_ids(), Some(vec!["w-1".to_string(), "w-2".to_string()]));
+    // Same wallets in another order share a cache entry
+    let reordered = WalletSelection {
+        wallet_ids: Some("w-1,w-2".to_string()),
+    };
+    assert_eq!(wallets.cache_key(), reordered.cache_key());
+}
+
+#[test]
+fn test_no_wallet_selection_means_every_wallet() {
+    assert_eq!(WalletSelection::default().wallet_ids(), None);
+    let empty = WalletSelection {
+        wallet_ids: Some(" , ".to_string()),
+    };
+    assert_eq!(empty.wallet_ids(), None);
+}
+
+#[test]
+fn test_rejects_too_many_wallets() {
+    let ids: Vec<String> = (0..=MAX_WALLET_IDS).map(|i| format!("w-{}", i)).collect();
+    let wallets = WalletSelection {
+        wallet_ids: Some(ids.join(",")),
+    };
+
+    assert!(wallets.validate().is_err());
+}
@@ -0,0 +1,62 @@
+//! Tests for the GraphQL schema (no database needed)
+
+use history_service::graphql::{schema_builder, MAX_DEPTH};
+
+#[test]
+fn test_schema_exposes_users_wallets_and_aggregates() {
+    let sdl = schema_builder().finish().sdl();
+
+    for expected in [
+        "type User",
+        "type Wallet",
+        "type Event",
+        "type PeriodSummary",
+        "type BalanceAsOf",
+        "input EventFilter",
+        "enum SummaryPeriod",
+    ] {
+        assert!(sdl.contains(expected), "{} missing from the schema", expected);
+    }
+}
+
+#[tokio::test]
+async fn test_ids_resolve_without_touching_the_database() {
+    let schema = schema_builder().finish();
+    let response = schema
+        .execute(r#"{ user(id: "user-1") { id } wallet(id: "w-1") { id } }"#)
+        .await;
+
+    assert!(response.errors.is_empty(), "{:?}", response.errors);
+    let data = response.data.into_json().unwrap();
+    assert_eq!(data["user"]["id"], "user-1");
+    assert_eq!(data["wallet"]["id"], "w-1");
+}
+
+#[tokio::test]
+async fn test_bad_page_arguments_are_bad_requests() {
+    let schema = schema_builder().finish();
+
+    for query in [
+        r#"{ wallet(id: "w-1") { events(first: 0) { nextCursor } } }"#,
+        r#"{ wallet(id: "w-1") { events(after: "not-a-cursor") { nextCursor } } }"#,
+        r#"{ user(id: "u-1") { activity(filter: { eventTypes: ["NOPE"] }) { nextCursor } } }"#,
+    ] {
+        let response = schema.execute(query).await;
+        assert_eq!(response.errors.len(), 1, "{}", query);
+        let error = serde_json::to_value(&response.errors[0]).unwrap();
+        assert_eq!(error["extensions"]["code"], "BAD_REQUEST", "{}", query);
+    }
+}
+
+#[tokio::test]
+async fn test_overly_deep_queries_are_rejected() {
+    let schema = schema_builder().finish();
+    let mut selection = String::from("name");
+    for _ in 0..MAX_DEPTH {
+        selection = format!("ofType {{ {} }}", selection);
+    }
+
+    let query = format!(r#"{{ __type(name: "Wallet") {{ fields {{ type {{ {} }} }} }} }}"#, selection);
+    let response = schema.execute(query).await;
+    assert!(!response.errors.is_empty());
+}
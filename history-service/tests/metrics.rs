@@ -0,0 +1,38 @@
+//! Tests for the consumer lag gauges (no Kafka needed)
+
+use history_service::metrics::{ConsumerLag, PartitionLag};
+
+#[test]
+fn test_lag_from_committed_offset() {
+    assert_eq!(PartitionLag::new("t", 0, Some(90), 0, 100).lag, 10);
+    // Nothing committed yet: everything still on the partition is behind
+    assert_eq!(PartitionLag::new("t", 0, None, 40, 100).lag, 60);
+    // Committed offset older than retention: count from what's left
+    assert_eq!(PartitionLag::new("t", 0, Some(10), 40, 100).lag, 60);
+}
+
+#[test]
+fn test_render_openmetrics_and_replace_samples() {
+    let lag = ConsumerLag::new();
+    lag.record(
+        "wallet-events",
+        vec![
+            PartitionLag::new("wallet-events", 0, Some(95), 0, 100),
+            PartitionLag::new("wallet-events", 1, None, 0, 7),
+        ],
+    );
+    lag.record("wallet-events-retry-5s", vec![PartitionLag::new("wallet-events-retry-5s", 0, Some(3), 0, 3)]);
+
+    let output = lag.render();
+    assert!(output.contains("# TYPE history_consumer_lag gauge\n"));
+    assert!(output.contains("history_consumer_lag{topic=\"wallet-events\",partition=\"0\"} 5\n"));
+    assert!(output.contains("history_consumer_lag{topic=\"wallet-events\",partition=\"1\"} 7\n"));
+    assert!(output.contains("history_consumer_committed_offset{topic=\"wallet-events\",partition=\"0\"} 95\n"));
+    assert!(!output.contains("history_consumer_committed_offset{topic=\"wallet-events\",partition=\"1\"}"));
+    assert!(output.ends_with("# EOF\n"));
+
+    // Partition 1 was revoked: the next sample drops it
+    lag.record("wallet-events", vec![PartitionLag::new("wallet-events", 0, Some(100), 0, 100)]);
+    assert_eq!(lag.snapshot().len(), 2);
+    assert!(!lag.render().contains("partition=\"1\""));
+}
@@ -0,0 +1,68 @@
+//! Tests for history pagination cursors (no database needed)
+
+use chrono::{TimeZone, Utc};
+use history_service::models::TransactionEvent;
+use history_service::pagination::{Cursor, Page, PageParams, DEFAULT_PAGE_SIZE, MAX_PAGE_SIZE};
+use rust_decimal::Decimal;
+
+fn event(id: &str, second: u32) -> TransactionEvent {
+    TransactionEvent {
+        id: id.to_string(),
+        wallet_id: "wallet-1".to_string(),
+        user_id: "user-1".to_string(),
+        amount: Decimal::ONE,
+        event_type: "WALLET_FUNDED".to_string(),
+        transaction_id: None,
+        created_at: Utc.with_ymd_and_hms(2025, 1, 29, 10, 0, second).unwrap(),
+        event_data: serde_json::json!({}),
+        event_id: None,
+        correlation_id: None,
+        causation_id: None,
+    }
+}
+
+#[test]
+fn test_cursor_round_trips() {
+    let cursor = Cursor {
+        created_at: Utc.timestamp_micros(1_738_144_800_123_456).unwrap(),
+        id: "2f0d6a4e-leg:out".to_string(),
+    };
+
+    let encoded = cursor.encode();
+    assert!(encoded.chars().all(|c| c.is_ascii_hexdigit()));
+    assert_eq!(Cursor::decode(&encoded).unwrap(), cursor);
+}
+
+#[test]
+fn test_garbage_cursor_is_rejected() {
+    for cursor in ["", "abc", "zz", "7878", "3a6964"] {
+        assert!(Cursor::decode(cursor).is_err(), "accepted {:?}", cursor);
+    }
+}
+
+#[test]
+fn test_limit_defaults_and_bounds() {
+    assert_eq!(PageParams::default().limit().unwrap(), DEFAULT_PAGE_SIZE);
+
+    for limit in [0, MAX_PAGE_SIZE + 1] {
+        let params = PageParams {
+            limit: Some(limit),
+            cursor: None,
+        };
+        assert!(params.limit().is_err());
+    }
+}
+
+#[test]
+fn test_extra_row_means_another_page() {
+    let rows = vec![event("c", 3), event("b", 2), event("a", 1)];
+
+    let page = Page::from_rows(rows.clone(), 2);
+    assert_eq!(page.items.len(), 2);
+    let next = Cursor::decode(page.next_cursor.as_deref().unwrap()).unwrap();
+    assert_eq!(next, Cursor::after(&rows[1]));
+
+    let last = Page::from_rows(rows, 3);
+    assert_eq!(last.items.len(), 3);
+    assert_eq!(last.next_cursor, None);
+}
@@ -0,0 +1,42 @@
+//! Tests for replay requests (no Kafka or database needed)
+
+use history_service::replay::{ReplayMode, ReplayRequest, MAX_REPLAY_EVENTS};
+
+fn request(json: &str) -> ReplayRequest {
+    serde_json::from_str(json).unwrap()
+}
+
+#[test]
+fn test_defaults_to_republishing_a_full_page() {
+    let request = request(r#"{"from": "2025-01-29T00:00:00Z", "to": "2025-01-30T00:00:00Z"}"#);
+
+    assert_eq!(request.mode, ReplayMode::Republish);
+    assert_eq!(request.wallet_id, None);
+    assert_eq!(request.validate().unwrap(), MAX_REPLAY_EVENTS);
+}
+
+#[test]
+fn test_reprocess_for_one_wallet() {
+    let request = request(
+        r#"{"from": "2025-01-29T00:00:00Z", "to": "2025-01-30T00:00:00Z",
+            "wallet_id": "wallet-1", "mode": "reprocess", "limit": 50}"#,
+    );
+
+    assert_eq!(request.mode, ReplayMode::Reprocess);
+    assert_eq!(request.wallet_id.as_deref(), Some("wallet-1"));
+    assert_eq!(request.validate().unwrap(), 50);
+}
+
+#[test]
+fn test_rejects_empty_window_and_bad_limits() {
+    let backwards = request(r#"{"from": "2025-01-30T00:00:00Z", "to": "2025-01-29T00:00:00Z"}"#);
+    assert!(backwards.validate().is_err());
+
+    for limit in [0, MAX_REPLAY_EVENTS + 1] {
+        let request = request(&format!(
+            r#"{{"from": "2025-01-29T00:00:00Z", "to": "2025-01-30T00:00:00Z", "limit": {}}}"#,
+            limit
+        ));
+        assert!(request.validate().is_err());
+    }
+}
@@ -0,0 +1,37 @@
+//! Tests for retry tiers and retry headers (no Kafka needed)
+
+use chrono::{TimeZone, Utc};
+use history_service::retry::{retry_tiers, RetryInfo};
+use std::time::Duration;
+
+#[test]
+fn test_retry_tiers_escalate() {
+    let tiers = retry_tiers("wallet-events");
+
+    let topics: Vec<&str> = tiers.iter().map(|t| t.topic.as_str()).collect();
+    assert_eq!(
+        topics,
+        vec![
+            "wallet-events-retry-5s",
+            "wallet-events-retry-1m",
+            "wallet-events-retry-10m"
+        ]
+    );
+    assert_eq!(tiers[2].delay, Duration::from_secs(600));
+}
+
+#[test]
+fn test_retry_headers_round_trip() {
+    let info = RetryInfo {
+        attempt: 2,
+        not_before: Utc.timestamp_millis_opt(1_738_000_000_123).unwrap(),
+        original_topic: "wallet-events".to_string(),
+        last_error: "Database error: pool timed out".to_string(),
+    };
+
+    let headers = info.to_headers();
+    assert_eq!(RetryInfo::from_headers(&headers), Some(info));
+
+    // A fresh message has no retry headers
+    assert_eq!(RetryInfo::from_headers(&rdkafka::message::OwnedHeaders::new()), None);
+}
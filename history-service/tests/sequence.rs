@@ -0,0 +1,37 @@
+//! Tests for per-wallet sequence checks (no database needed)
+
+use history_service::sequence::{Position, MAX_GAP_ROWS};
+
+#[test]
+fn test_next_sequence_is_in_order() {
+    assert_eq!(Position::of(0, 1), Position::Next);
+    assert_eq!(Position::of(41, 42), Position::Next);
+}
+
+#[test]
+fn test_skipped_numbers_are_a_gap() {
+    assert_eq!(
+        Position::of(3, 7),
+        Position::Ahead {
+            first_missing: 4,
+            last_missing: 6
+        }
+    );
+}
+
+#[test]
+fn test_huge_gap_keeps_the_nearest_numbers() {
+    assert_eq!(
+        Position::of(0, 5000),
+        Position::Ahead {
+            first_missing: 5000 - MAX_GAP_ROWS,
+            last_missing: 4999
+        }
+    );
+}
+
+#[test]
+fn test_late_or_repeated_numbers_are_behind() {
+    assert_eq!(Position::of(7, 5), Position::Behind);
+    assert_eq!(Position::of(7, 7), Position::Behind);
+}
@@ -0,0 +1,76 @@
+//! Tests for event signature verification (no Kafka needed)
+
+use history_service::errors::HistoryError;
+use history_service::signing::{signature, EventVerifier, SIGNATURE_HEADER};
+use rdkafka::message::{Header, OwnedHeaders};
+use std::collections::HashMap;
+
+const PAYLOAD: &[u8] = br#"{"amount":"10.00"}"#;
+// What wallet-service's EventSigner produces for key "wallet-1" and PAYLOAD
+// under k1 (see its tests)
+const SIGNED: &[u8] = b"k1:95c5976490b6d5eb123b2bbae2811a2e266de64fca8591c824e7602c297329a2";
+
+fn verifier(accept_unsigned: bool) -> EventVerifier {
+    let keys = HashMap::from([
+        ("k2".to_string(), b"fedcba9876543210fedcba9876543210".to_vec()),
+        ("k1".to_string(), b"0123456789abcdef0123456789abcdef".to_vec()),
+    ]);
+    EventVerifier::new(keys, accept_unsigned)
+}
+
+fn rejected(result: Result<(), HistoryError>) -> bool {
+    matches!(result, Err(HistoryError::InvalidSignature(_)))
+}
+
+#[test]
+fn test_accepts_signatures_from_any_configured_key() {
+    let verifier = verifier(false);
+    assert_eq!(verifier.key_ids(), vec!["k1", "k2"]);
+    assert!(verifier.verify(Some(&b"wallet-1"[..]), PAYLOAD, Some(SIGNED)).is_ok());
+
+    // Not retryable - straight to the DLQ
+    let error = verifier.verify(Some(&b"wallet-1"[..]), PAYLOAD, None).unwrap_err();
+    assert!(!error.is_retryable());
+}
+
+#[test]
+fn test_rejects_tampered_foreign_and_unsigned_messages() {
+    let verifier = verifier(false);
+    let key = Some(&b"wallet-1"[..]);
+
+    assert!(rejected(verifier.verify(key, br#"{"amount":"99.00"}"#, Some(SIGNED))));
+    assert!(rejected(verifier.verify(Some(&b"wallet-2"[..]), PAYLOAD, Some(SIGNED))));
+    // Signed with k1's bytes but claiming k2
+    let relabelled = [&b"k2"[..], &SIGNED[2..]].concat();
+    assert!(rejected(verifier.verify(key, PAYLOAD, Some(&relabelled))));
+    let unknown = [&b"k9"[..], &SIGNED[2..]].concat();
+    assert!(rejected(verifier.verify(key, PAYLOAD, Some(&unknown))));
+    assert!(rejected(verifier.verify(key, PAYLOAD, Some(&b"k1:not-hex"[..]))));
+    assert!(rejected(verifier.verify(key, PAYLOAD, Some(&b"garbage"[..]))));
+    assert!(rejected(verifier.verify(key, PAYLOAD, None)));
+}
+
+#[test]
+fn test_accept_unsigned_still_rejects_bad_signatures() {
+    let verifier = verifier(true);
+    let key = Some(&b"wallet-1"[..]);
+
+    assert!(verifier.verify(key, PAYLOAD, None).is_ok());
+    assert!(verifier.verify(key, PAYLOAD, Some(SIGNED)).is_ok());
+    assert!(rejected(verifier.verify(key, br#"{"amount":"99.00"}"#, Some(SIGNED))));
+}
+
+#[test]
+fn test_signature_is_read_from_headers() {
+    let headers = OwnedHeaders::new()
+        .insert(Header {
+            key: "x-retry-attempt",
+            value: Some("1"),
+        })
+        .insert(Header {
+            key: SIGNATURE_HEADER,
+            value: Some(SIGNED),
+        });
+    assert_eq!(signature(&headers), Some(SIGNED));
+    assert_eq!(signature(&OwnedHeaders::new()), None);
+}
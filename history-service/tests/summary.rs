@@ -0,0 +1,72 @@
+//! Tests for spend/income summaries (no database needed)
+
+use chrono::{NaiveDate, TimeZone, Utc};
+use history_service::filter::HistoryFilter;
+use history_service::summary::{summarize, whole_days, SummaryPeriod, SummaryQuery, SummaryRow};
+use rust_decimal::Decimal;
+
+fn row(month: u32, event_type: &str, count: i64, amount: i64) -> SummaryRow {
+    (
+        Utc.with_ymd_and_hms(2025, month, 1, 0, 0, 0).unwrap(),
+        event_type.to_string(),
+        count,
+        Decimal::new(amount, 0),
+    )
+}
+
+#[test]
+fn test_rows_fold_into_one_summary_per_period() {
+    let periods = summarize(vec![
+        row(3, "TRANSFER_OUT", 2, 70),
+        row(3, "WALLET_FUNDED", 1, 100),
+        row(2, "TRANSFER_IN", 1, 30),
+        row(2, "WALLET_CREATED", 1, 0),
+    ]);
+
+    assert_eq!(periods.len(), 2);
+    let march = &periods[0];
+    assert_eq!(march.period_start.to_rfc3339(), "2025-03-01T00:00:00+00:00");
+    assert_eq!(march.total_in, Decimal::new(100, 0));
+    assert_eq!(march.total_out, Decimal::new(70, 0));
+    assert_eq!(march.net_change, Decimal::new(30, 0));
+    assert_eq!(march.counts["TRANSFER_OUT"], 2);
+
+    let february = &periods[1];
+    assert_eq!(february.net_change, Decimal::new(30, 0));
+    // Counted, but no money moved
+    assert_eq!(february.counts["WALLET_CREATED"], 1);
+}
+
+#[test]
+fn test_no_rows_no_periods() {
+    assert!(summarize(Vec::new()).is_empty());
+}
+
+#[test]
+fn test_period_defaults_to_month() {
+    let query: SummaryQuery = serde_json::from_str("{}").unwrap();
+    assert_eq!(query.period, SummaryPeriod::Month);
+
+    let query: SummaryQuery = serde_json::from_str(r#"{"period": "week"}"#).unwrap();
+    assert_eq!(query.period.as_sql(), "week");
+    assert!(serde_json::from_str::<SummaryQuery>(r#"{"period": "fortnight"}"#).is_err());
+}
+
+#[test]
+fn test_only_whole_day_windows_use_the_daily_totals() {
+    let midnight = Utc.with_ymd_and_hms(2025, 3, 1, 0, 0, 0).unwrap();
+    let afternoon = Utc.with_ymd_and_hms(2025, 3, 1, 15, 30, 0).unwrap();
+    let filter = |from, to| HistoryFilter {
+        from,
+        to,
+        event_type: None,
+    };
+
+    assert_eq!(whole_days(&filter(None, None)), Some((None, None)));
+    assert_eq!(
+        whole_days(&filter(Some(midnight), None)),
+        Some((NaiveDate::from_ymd_opt(2025, 3, 1), None))
+    );
+    assert_eq!(whole_days(&filter(None, Some(afternoon))), None);
+    assert_eq!(whole_days(&filter(Some(midnight), Some(afternoon))), None);
+}
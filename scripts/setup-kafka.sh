@@ -46,6 +46,25 @@ bin/kafka-topics.sh --create \
     --partitions 3 \
     --topic wallet-events
 
+# Retry tiers, dead-letter and replay topics for history-service
+for topic in wallet-events-retry-5s wallet-events-retry-1m wallet-events-retry-10m wallet-events-dlq wallet-events-replay; do
+    echo "Creating $topic topic..."
+    bin/kafka-topics.sh --create \
+        --bootstrap-server localhost:9092 \
+        --replication-factor 1 \
+        --partitions 3 \
+        --topic $topic
+done
+
+# Latest snapshot per wallet - compaction keeps one record per key
+echo "Creating wallet-state topic..."
+bin/kafka-topics.sh --create \
+    --bootstrap-server localhost:9092 \
+    --replication-factor 1 \
+    --partitions 3 \
+    --config cleanup.policy=compact \
+    --topic wallet-state
+
 echo ""
 echo "✅ Kafka setup complete!"
 echo "Zookeeper PID: $ZOOKEEPER_PID (logs: /tmp/zookeeper.log)"
@@ -0,0 +1,22 @@
+-- Create beneficiaries table
+-- Saved recipients a user can send money to by name
+-- Key features:
+-- 1. A beneficiary points at EITHER a specific wallet OR another user
+-- 2. Owned by user_id - only the owner can use it in a transfer
+-- 3. Names are unique per owner so "Mum" always means the same person
+
+CREATE TABLE IF NOT EXISTS beneficiaries (
+    id VARCHAR(36) PRIMARY KEY,
+    user_id VARCHAR(100) NOT NULL,
+    name VARCHAR(100) NOT NULL,
+    wallet_id VARCHAR(36),
+    beneficiary_user_id VARCHAR(100),
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    FOREIGN KEY (wallet_id) REFERENCES wallets(id) ON DELETE CASCADE,
+    -- Exactly one kind of reference must be set
+    CHECK ((wallet_id IS NULL) <> (beneficiary_user_id IS NULL)),
+    UNIQUE (user_id, name)
+);
+
+-- Index for listing a user's saved contacts
+CREATE INDEX idx_beneficiaries_user_id ON beneficiaries(user_id);
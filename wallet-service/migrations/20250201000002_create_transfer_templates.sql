@@ -0,0 +1,28 @@
+-- Free-text memo on transactions ("rent", "dinner")
+ALTER TABLE wallet_transactions ADD COLUMN IF NOT EXISTS memo VARCHAR(255);
+
+-- Create transfer_templates table
+-- A saved transfer ("pay rent") that can be executed with one call
+-- Key features:
+-- 1. Belongs to the SOURCE wallet - executing it debits that wallet
+-- 2. Recipient is a wallet OR one of the owner's beneficiaries
+-- 3. Default amount can be overridden at execution time
+
+CREATE TABLE IF NOT EXISTS transfer_templates (
+    id VARCHAR(36) PRIMARY KEY,
+    wallet_id VARCHAR(36) NOT NULL,
+    name VARCHAR(100) NOT NULL,
+    to_wallet_id VARCHAR(36),
+    beneficiary_id VARCHAR(36),
+    amount DECIMAL(19,4) NOT NULL CHECK (amount > 0),
+    memo VARCHAR(255),
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    FOREIGN KEY (wallet_id) REFERENCES wallets(id) ON DELETE CASCADE,
+    FOREIGN KEY (to_wallet_id) REFERENCES wallets(id) ON DELETE CASCADE,
+    FOREIGN KEY (beneficiary_id) REFERENCES beneficiaries(id) ON DELETE CASCADE,
+    -- Exactly one kind of recipient must be set
+    CHECK ((to_wallet_id IS NULL) <> (beneficiary_id IS NULL))
+);
+
+-- Index for listing a wallet's templates
+CREATE INDEX idx_transfer_templates_wallet_id ON transfer_templates(wallet_id);
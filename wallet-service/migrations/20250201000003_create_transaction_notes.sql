@@ -0,0 +1,22 @@
+-- Create transaction_notes table
+-- Internal support annotations on transactions
+-- Key features:
+-- 1. Kept OUT of wallet_transactions - the ledger stays immutable
+-- 2. case_id links a transaction to a ticket in the support system
+-- 3. Append-only: notes are never edited, only added
+
+CREATE TABLE IF NOT EXISTS transaction_notes (
+    id VARCHAR(36) PRIMARY KEY,
+    transaction_id VARCHAR(36) NOT NULL,
+    case_id VARCHAR(100),
+    note TEXT NOT NULL,
+    author VARCHAR(100) NOT NULL,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    FOREIGN KEY (transaction_id) REFERENCES wallet_transactions(id) ON DELETE CASCADE
+);
+
+-- Index for showing notes alongside a transaction
+CREATE INDEX idx_transaction_notes_transaction_id ON transaction_notes(transaction_id);
+
+-- Index for "everything linked to case X"
+CREATE INDEX idx_transaction_notes_case_id ON transaction_notes(case_id) WHERE case_id IS NOT NULL;
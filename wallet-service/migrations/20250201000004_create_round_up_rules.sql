@@ -0,0 +1,21 @@
+-- Create round_up_rules table
+-- "Spare change" savings: each debit is rounded up to the next increment
+-- and the difference moves to a savings wallet in the same DB transaction
+-- Key features:
+-- 1. At most one rule per wallet (wallet_id is the primary key)
+-- 2. increment is the rounding step, e.g. 1.00 turns 3.40 into 4.00 (+0.60)
+
+CREATE TABLE IF NOT EXISTS round_up_rules (
+    wallet_id VARCHAR(36) PRIMARY KEY,
+    savings_wallet_id VARCHAR(36) NOT NULL,
+    increment DECIMAL(19,4) NOT NULL CHECK (increment > 0),
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    FOREIGN KEY (wallet_id) REFERENCES wallets(id) ON DELETE CASCADE,
+    FOREIGN KEY (savings_wallet_id) REFERENCES wallets(id) ON DELETE CASCADE,
+    CHECK (wallet_id <> savings_wallet_id)
+);
+
+-- Round-up legs get their own transaction types
+ALTER TABLE wallet_transactions DROP CONSTRAINT IF EXISTS wallet_transactions_type_check;
+ALTER TABLE wallet_transactions ADD CONSTRAINT wallet_transactions_type_check
+    CHECK (type IN ('FUND', 'TRANSFER_OUT', 'TRANSFER_IN', 'ROUND_UP_OUT', 'ROUND_UP_IN'));
@@ -0,0 +1,20 @@
+-- Sub-wallets ("pots")
+-- A pot is an ordinary wallet row with parent_wallet_id set
+-- Key features:
+-- 1. Parent's balance is the SPENDABLE amount - money in pots is excluded
+-- 2. Total balance = parent balance + sum of its pots (computed on read)
+-- 3. Only one level: pots can't have pots (enforced in the repository)
+-- 4. nickname gives pots (and wallets) a human name ("Holiday")
+
+ALTER TABLE wallets ADD COLUMN IF NOT EXISTS parent_wallet_id VARCHAR(36)
+    REFERENCES wallets(id) ON DELETE CASCADE;
+ALTER TABLE wallets ADD COLUMN IF NOT EXISTS nickname VARCHAR(100);
+
+-- Index for listing a wallet's pots
+CREATE INDEX idx_wallets_parent_wallet_id ON wallets(parent_wallet_id) WHERE parent_wallet_id IS NOT NULL;
+
+-- Moves between a wallet and its pots get their own transaction types
+ALTER TABLE wallet_transactions DROP CONSTRAINT IF EXISTS wallet_transactions_type_check;
+ALTER TABLE wallet_transactions ADD CONSTRAINT wallet_transactions_type_check
+    CHECK (type IN ('FUND', 'TRANSFER_OUT', 'TRANSFER_IN', 'ROUND_UP_OUT', 'ROUND_UP_IN',
+                    'POT_TRANSFER_OUT', 'POT_TRANSFER_IN'));
@@ -0,0 +1,26 @@
+-- Create vouchers table
+-- Single-use gift codes minted by admins
+-- Key features:
+-- 1. code is what the customer types - unique, never reused
+-- 2. redeemed_at / wallet_id / transaction_id are set together, exactly once
+-- 3. Redemption and funding happen in ONE DB transaction, so a code can't be
+--    marked used without the money landing (or vice versa)
+
+CREATE TABLE IF NOT EXISTS vouchers (
+    id VARCHAR(36) PRIMARY KEY,
+    code VARCHAR(32) NOT NULL UNIQUE,
+    amount DECIMAL(19,4) NOT NULL CHECK (amount > 0),
+    created_by VARCHAR(100) NOT NULL,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    redeemed_at TIMESTAMP WITH TIME ZONE,
+    wallet_id VARCHAR(36),
+    transaction_id VARCHAR(36),
+    FOREIGN KEY (wallet_id) REFERENCES wallets(id) ON DELETE SET NULL,
+    FOREIGN KEY (transaction_id) REFERENCES wallet_transactions(id) ON DELETE SET NULL
+);
+
+-- Redeemed voucher money gets its own transaction type
+ALTER TABLE wallet_transactions DROP CONSTRAINT IF EXISTS wallet_transactions_type_check;
+ALTER TABLE wallet_transactions ADD CONSTRAINT wallet_transactions_type_check
+    CHECK (type IN ('FUND', 'TRANSFER_OUT', 'TRANSFER_IN', 'ROUND_UP_OUT', 'ROUND_UP_IN',
+                    'POT_TRANSFER_OUT', 'POT_TRANSFER_IN', 'VOUCHER_REDEEM'));
@@ -0,0 +1,21 @@
+-- Create event_outbox table
+-- Events waiting to be relayed to Kafka by the outbox-relay worker
+-- Key features:
+-- 1. id (BIGSERIAL) gives a total order - the relay publishes oldest first
+-- 2. partition_key is the wallet ID, so per-wallet ordering survives retries
+-- 3. published_at NULL = still pending; attempts / next_attempt_at drive backoff
+
+CREATE TABLE IF NOT EXISTS event_outbox (
+    id BIGSERIAL PRIMARY KEY,
+    partition_key VARCHAR(36) NOT NULL,
+    event_type VARCHAR(50) NOT NULL,
+    payload TEXT NOT NULL,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    attempts INT NOT NULL DEFAULT 0,
+    next_attempt_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    last_error TEXT,
+    published_at TIMESTAMP WITH TIME ZONE
+);
+
+-- The relay only ever scans pending rows
+CREATE INDEX idx_event_outbox_pending ON event_outbox(id) WHERE published_at IS NULL;
@@ -0,0 +1,13 @@
+-- Create wallet_event_sequences table
+-- The last event number handed out per wallet (the event's partition key)
+-- Key features:
+-- 1. One row per wallet, created with its first numbered event
+-- 2. UPDATE ... RETURNING under the row lock keeps numbers gap-free and
+--    unique across wallet-service instances
+-- 3. No foreign key: declined events may name wallets that don't exist
+
+CREATE TABLE IF NOT EXISTS wallet_event_sequences (
+    wallet_id VARCHAR(36) PRIMARY KEY,
+    last_sequence BIGINT NOT NULL,
+    updated_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
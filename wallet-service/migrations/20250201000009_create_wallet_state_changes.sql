@@ -0,0 +1,34 @@
+-- Create wallet_state_changes table
+-- Wallets whose snapshot still has to go to the compacted wallet-state topic
+-- Key features:
+-- 1. Filled by a trigger on wallets, so no code path can forget it
+-- 2. Only the wallet ID is queued - the publisher reads the CURRENT row
+--    when it sends, so a late commit can never publish an older balance
+--    over a newer one
+-- 3. Rows are deleted once their snapshot is acknowledged
+
+CREATE TABLE IF NOT EXISTS wallet_state_changes (
+    id BIGSERIAL PRIMARY KEY,
+    wallet_id VARCHAR(36) NOT NULL,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE OR REPLACE FUNCTION queue_wallet_state_change()
+RETURNS TRIGGER AS $$
+BEGIN
+    IF TG_OP = 'DELETE' THEN
+        INSERT INTO wallet_state_changes (wallet_id) VALUES (OLD.id);
+    ELSE
+        INSERT INTO wallet_state_changes (wallet_id) VALUES (NEW.id);
+    END IF;
+    RETURN NULL;
+END;
+$$ language 'plpgsql';
+
+CREATE TRIGGER queue_wallets_state_change
+    AFTER INSERT OR UPDATE OR DELETE ON wallets
+    FOR EACH ROW
+    EXECUTE FUNCTION queue_wallet_state_change();
+
+-- Existing wallets get one snapshot each
+INSERT INTO wallet_state_changes (wallet_id) SELECT id FROM wallets;
@@ -0,0 +1,15 @@
+-- Shape event_outbox for Debezium's outbox event router
+-- Key features:
+-- 1. aggregate_id (the wallet ID, was partition_key) becomes the Kafka key
+-- 2. aggregate_type routes the row: 'wallet' -> the wallet-events topic
+-- 3. event_id is the event's own ID, passed on as the `id` header
+-- The relay columns (attempts, published_at...) are simply ignored by CDC
+
+ALTER TABLE event_outbox RENAME COLUMN partition_key TO aggregate_id;
+
+ALTER TABLE event_outbox
+    ADD COLUMN aggregate_type VARCHAR(50) NOT NULL DEFAULT 'wallet';
+
+-- Rows queued before this migration get a fresh ID
+ALTER TABLE event_outbox
+    ADD COLUMN event_id UUID NOT NULL DEFAULT gen_random_uuid();
@@ -0,0 +1,31 @@
+-- Create wallet_adjustments table
+-- Manual balance corrections made by operations staff
+-- Key features:
+-- 1. Each adjustment is also an ADJUSTMENT row in wallet_transactions, so the
+--    ledger still explains every balance change
+-- 2. That row's amount is signed (negative for a debit); the amount here is
+--    always positive and `direction` says which way it went
+-- 3. reason_code and actor are mandatory - every correction has to say why
+--    and who made it
+
+CREATE TABLE IF NOT EXISTS wallet_adjustments (
+    id VARCHAR(36) PRIMARY KEY,
+    wallet_id VARCHAR(36) NOT NULL,
+    transaction_id VARCHAR(36) NOT NULL UNIQUE,
+    direction VARCHAR(6) NOT NULL CHECK (direction IN ('CREDIT', 'DEBIT')),
+    amount DECIMAL(19,4) NOT NULL CHECK (amount > 0),
+    reason_code VARCHAR(32) NOT NULL,
+    actor VARCHAR(100) NOT NULL,
+    note TEXT,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    FOREIGN KEY (wallet_id) REFERENCES wallets(id) ON DELETE CASCADE,
+    FOREIGN KEY (transaction_id) REFERENCES wallet_transactions(id) ON DELETE CASCADE
+);
+
+-- Index for "every correction made to this wallet"
+CREATE INDEX idx_wallet_adjustments_wallet_id ON wallet_adjustments(wallet_id);
+
+ALTER TABLE wallet_transactions DROP CONSTRAINT IF EXISTS wallet_transactions_type_check;
+ALTER TABLE wallet_transactions ADD CONSTRAINT wallet_transactions_type_check
+    CHECK (type IN ('FUND', 'TRANSFER_OUT', 'TRANSFER_IN', 'ROUND_UP_OUT', 'ROUND_UP_IN',
+                    'POT_TRANSFER_OUT', 'POT_TRANSFER_IN', 'VOUCHER_REDEEM', 'ADJUSTMENT'));
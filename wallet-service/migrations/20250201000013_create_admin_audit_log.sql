@@ -0,0 +1,36 @@
+-- Create admin_audit_log table
+-- Every privileged operation, for compliance
+-- Key features:
+-- 1. Written in the same DB transaction as the change it describes, so
+--    there's no change without its entry (and no entry without its change)
+-- 2. before_value / after_value are JSON snapshots of what changed
+-- 3. Append-only: a trigger refuses UPDATE and DELETE
+
+CREATE TABLE IF NOT EXISTS admin_audit_log (
+    id BIGSERIAL PRIMARY KEY,
+    actor VARCHAR(100) NOT NULL,
+    action VARCHAR(50) NOT NULL,
+    target_type VARCHAR(30) NOT NULL,
+    target_id VARCHAR(36) NOT NULL,
+    before_value JSONB,
+    after_value JSONB,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+-- Index for "everything done to this wallet / voucher / transaction"
+CREATE INDEX idx_admin_audit_log_target ON admin_audit_log(target_type, target_id);
+
+-- Index for "everything this person did"
+CREATE INDEX idx_admin_audit_log_actor ON admin_audit_log(actor);
+
+CREATE OR REPLACE FUNCTION reject_admin_audit_log_change()
+RETURNS TRIGGER AS $$
+BEGIN
+    RAISE EXCEPTION 'admin_audit_log is append-only';
+END;
+$$ language 'plpgsql';
+
+CREATE TRIGGER admin_audit_log_append_only
+    BEFORE UPDATE OR DELETE ON admin_audit_log
+    FOR EACH ROW
+    EXECUTE FUNCTION reject_admin_audit_log_change();
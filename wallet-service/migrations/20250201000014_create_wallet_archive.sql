@@ -0,0 +1,65 @@
+-- Create archive tables for inactive wallets
+-- Wallets untouched for the retention period (and empty) move here with
+-- their history, keeping the hot tables small
+-- Key features:
+-- 1. Same columns as the hot tables plus archived_at - rows are moved, not
+--    rewritten, so the ledger of an archived wallet still adds up
+-- 2. No foreign keys to the hot tables: the originals are deleted
+-- 3. Transaction notes and adjustments go with their transactions; config
+--    (beneficiaries, templates, round-up rules) is dropped with the wallet
+
+CREATE TABLE IF NOT EXISTS archived_wallets (
+    id VARCHAR(36) PRIMARY KEY,
+    user_id VARCHAR(100) NOT NULL,
+    balance DECIMAL(19,4) NOT NULL,
+    version BIGINT NOT NULL,
+    parent_wallet_id VARCHAR(36),
+    nickname VARCHAR(100),
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL,
+    updated_at TIMESTAMP WITH TIME ZONE NOT NULL,
+    archived_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX idx_archived_wallets_user_id ON archived_wallets(user_id);
+
+CREATE TABLE IF NOT EXISTS archived_wallet_transactions (
+    id VARCHAR(36) PRIMARY KEY,
+    wallet_id VARCHAR(36) NOT NULL,
+    amount DECIMAL(19,4) NOT NULL,
+    type VARCHAR(20) NOT NULL,
+    status VARCHAR(20) NOT NULL,
+    reference_id VARCHAR(36),
+    memo VARCHAR(255),
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL,
+    archived_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX idx_archived_wallet_transactions_wallet_id ON archived_wallet_transactions(wallet_id);
+
+CREATE TABLE IF NOT EXISTS archived_transaction_notes (
+    id VARCHAR(36) PRIMARY KEY,
+    transaction_id VARCHAR(36) NOT NULL,
+    case_id VARCHAR(100),
+    note TEXT NOT NULL,
+    author VARCHAR(100) NOT NULL,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL,
+    archived_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE TABLE IF NOT EXISTS archived_wallet_adjustments (
+    id VARCHAR(36) PRIMARY KEY,
+    wallet_id VARCHAR(36) NOT NULL,
+    transaction_id VARCHAR(36) NOT NULL,
+    direction VARCHAR(6) NOT NULL,
+    amount DECIMAL(19,4) NOT NULL,
+    reason_code VARCHAR(32) NOT NULL,
+    actor VARCHAR(100) NOT NULL,
+    note TEXT,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL,
+    archived_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+-- A redeemed voucher keeps pointing at its wallet and transaction once
+-- they're archived (ON DELETE SET NULL would forget where the money went)
+ALTER TABLE vouchers DROP CONSTRAINT IF EXISTS vouchers_wallet_id_fkey;
+ALTER TABLE vouchers DROP CONSTRAINT IF EXISTS vouchers_transaction_id_fkey;
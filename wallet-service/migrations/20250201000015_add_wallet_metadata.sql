@@ -0,0 +1,16 @@
+-- Wallet metadata editable through PATCH /wallets/:id
+-- Key features:
+-- 1. labels: the user's own tags ("bills", "travel"), replaced as a whole
+-- 2. is_default: the user's primary wallet (clients show it first) - at
+--    most one per user, enforced by a partial unique index; pots can't be
+-- 3. Neither touches balance; a change still bumps version (and the ETag)
+
+ALTER TABLE wallets ADD COLUMN IF NOT EXISTS labels TEXT[] NOT NULL DEFAULT '{}';
+ALTER TABLE wallets ADD COLUMN IF NOT EXISTS is_default BOOLEAN NOT NULL DEFAULT FALSE;
+
+CREATE UNIQUE INDEX IF NOT EXISTS idx_wallets_one_default_per_user
+    ON wallets(user_id) WHERE is_default;
+
+-- Archived wallets keep their metadata too
+ALTER TABLE archived_wallets ADD COLUMN IF NOT EXISTS labels TEXT[] NOT NULL DEFAULT '{}';
+ALTER TABLE archived_wallets ADD COLUMN IF NOT EXISTS is_default BOOLEAN NOT NULL DEFAULT FALSE;
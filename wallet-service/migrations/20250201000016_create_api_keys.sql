@@ -0,0 +1,21 @@
+-- Create api_keys table
+-- Credentials for machine clients (other services), sent as X-API-Key
+-- Key features:
+-- 1. Only a SHA-256 of the key is stored; the key is shown once, at creation
+-- 2. scope: 'read' keys may only GET, 'transact' keys may move money too
+-- 3. Revoked keys are kept (revoked_at set) so old audit entries still
+--    name a key that exists
+
+CREATE TABLE IF NOT EXISTS api_keys (
+    id VARCHAR(36) PRIMARY KEY,
+    name VARCHAR(100) NOT NULL,
+    -- Start of the key ("wk_1a2b3c4d"), to recognise it in lists and logs
+    key_prefix VARCHAR(20) NOT NULL,
+    key_hash CHAR(64) NOT NULL UNIQUE,
+    scope VARCHAR(20) NOT NULL CHECK (scope IN ('read', 'transact')),
+    created_by VARCHAR(100) NOT NULL,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    -- Updated at most once a minute, not on every request
+    last_used_at TIMESTAMP WITH TIME ZONE,
+    revoked_at TIMESTAMP WITH TIME ZONE
+);
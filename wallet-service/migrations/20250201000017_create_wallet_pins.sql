@@ -0,0 +1,19 @@
+-- Create wallet_pins table
+-- An optional PIN that transfers out of a wallet must quote
+-- Key features:
+-- 1. At most one PIN per wallet (wallet_id is the primary key)
+-- 2. Only an Argon2 hash (PHC string, salt included) is stored
+-- 3. threshold: transfers up to this amount don't need the PIN; 0 means
+--    every transfer does
+-- 4. failed_attempts counts wrong PINs since the last right one; reaching
+--    the limit sets locked_until and starts the count again
+
+CREATE TABLE IF NOT EXISTS wallet_pins (
+    wallet_id VARCHAR(36) PRIMARY KEY,
+    pin_hash TEXT NOT NULL,
+    threshold DECIMAL(19,4) NOT NULL DEFAULT 0 CHECK (threshold >= 0),
+    failed_attempts INTEGER NOT NULL DEFAULT 0,
+    locked_until TIMESTAMP WITH TIME ZONE,
+    updated_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    FOREIGN KEY (wallet_id) REFERENCES wallets(id) ON DELETE CASCADE
+);
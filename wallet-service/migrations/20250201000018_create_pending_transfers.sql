@@ -0,0 +1,33 @@
+-- Create pending_transfers table
+-- Step-up verification: a transfer over STEP_UP_THRESHOLD waits here until
+-- it's confirmed with the one-time code sent to the user
+-- Key features:
+-- 1. No money moves until confirmation - the transfer runs (with the usual
+--    balance checks) when the code is confirmed
+-- 2. Only a SHA-256 of the code is stored
+-- 3. status: PENDING_VERIFICATION -> CONFIRMED -> COMPLETED | FAILED,
+--    or CANCELLED / EXPIRED if never confirmed
+-- 4. reference_id links a completed transfer to its wallet_transactions
+
+CREATE TABLE IF NOT EXISTS pending_transfers (
+    id VARCHAR(36) PRIMARY KEY,
+    from_wallet_id VARCHAR(36) NOT NULL,
+    to_wallet_id VARCHAR(36) NOT NULL,
+    amount DECIMAL(19,4) NOT NULL CHECK (amount > 0),
+    memo VARCHAR(255),
+    status VARCHAR(30) NOT NULL DEFAULT 'PENDING_VERIFICATION' CHECK (status IN (
+        'PENDING_VERIFICATION', 'CONFIRMED', 'COMPLETED', 'FAILED', 'CANCELLED', 'EXPIRED'
+    )),
+    code_hash CHAR(64) NOT NULL,
+    failed_attempts INTEGER NOT NULL DEFAULT 0,
+    expires_at TIMESTAMP WITH TIME ZONE NOT NULL,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    resolved_at TIMESTAMP WITH TIME ZONE,
+    reference_id VARCHAR(36),
+    FOREIGN KEY (from_wallet_id) REFERENCES wallets(id) ON DELETE CASCADE,
+    FOREIGN KEY (to_wallet_id) REFERENCES wallets(id) ON DELETE CASCADE
+);
+
+-- The expiry sweep only looks at open ones
+CREATE INDEX IF NOT EXISTS idx_pending_transfers_open
+    ON pending_transfers(expires_at) WHERE status = 'PENDING_VERIFICATION';
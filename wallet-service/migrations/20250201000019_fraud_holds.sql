@@ -0,0 +1,20 @@
+-- Transfers held by the fraud rules wait in pending_transfers too
+-- Key features:
+-- 1. status HELD: no code is sent; an admin releases (the transfer runs,
+--    CONFIRMED -> COMPLETED | FAILED) or rejects it (CANCELLED)
+-- 2. Held transfers have no code and don't expire, so code_hash and
+--    expires_at become optional
+-- 3. hold_reason: the rules that held it, for the reviewer
+
+ALTER TABLE pending_transfers DROP CONSTRAINT IF EXISTS pending_transfers_status_check;
+ALTER TABLE pending_transfers ADD CONSTRAINT pending_transfers_status_check CHECK (status IN (
+    'PENDING_VERIFICATION', 'HELD', 'CONFIRMED', 'COMPLETED', 'FAILED', 'CANCELLED', 'EXPIRED'
+));
+
+ALTER TABLE pending_transfers ALTER COLUMN code_hash DROP NOT NULL;
+ALTER TABLE pending_transfers ALTER COLUMN expires_at DROP NOT NULL;
+ALTER TABLE pending_transfers ADD COLUMN IF NOT EXISTS hold_reason TEXT;
+
+-- The review queue
+CREATE INDEX IF NOT EXISTS idx_pending_transfers_held
+    ON pending_transfers(created_at) WHERE status = 'HELD';
@@ -0,0 +1,4 @@
+-- Velocity limits count a wallet's outgoing transfers in a recent window,
+-- inside every transfer's DB transaction - keep that a short index range scan
+CREATE INDEX IF NOT EXISTS idx_wallet_transactions_transfer_out_recent
+    ON wallet_transactions(wallet_id, created_at) WHERE type = 'TRANSFER_OUT';
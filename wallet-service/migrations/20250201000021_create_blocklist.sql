@@ -0,0 +1,23 @@
+-- Create blocklist_entries table
+-- Wallets and users that may not send or receive transfers
+-- Key features:
+-- 1. target_type 'wallet' blocks one wallet, 'user' every wallet of a user
+-- 2. Checked inside the transfer's DB transaction (repository level), so
+--    no route - templates, step-ups, released holds - gets around it
+-- 3. Removed entries are kept (removed_at set) so old audit entries and
+--    events still name an entry that exists; one live entry per target
+
+CREATE TABLE IF NOT EXISTS blocklist_entries (
+    id VARCHAR(36) PRIMARY KEY,
+    target_type VARCHAR(10) NOT NULL CHECK (target_type IN ('wallet', 'user')),
+    target_id VARCHAR(100) NOT NULL,
+    reason VARCHAR(500) NOT NULL,
+    created_by VARCHAR(100) NOT NULL,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    removed_by VARCHAR(100),
+    removed_at TIMESTAMP WITH TIME ZONE
+);
+
+-- One live entry per target; also the index the transfer check uses
+CREATE UNIQUE INDEX IF NOT EXISTS idx_blocklist_entries_live
+    ON blocklist_entries(target_type, target_id) WHERE removed_at IS NULL;
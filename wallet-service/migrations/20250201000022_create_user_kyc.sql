@@ -0,0 +1,17 @@
+-- Create user_kyc table
+-- Each user's identity verification level
+-- Key features:
+-- 1. No row = unverified; an admin (or the KYC provider's integration)
+--    sets the level
+-- 2. Unverified users are held to KYC_UNVERIFIED_* caps, checked in the
+--    same DB transaction as the money movement
+
+CREATE TABLE IF NOT EXISTS user_kyc (
+    user_id VARCHAR(100) PRIMARY KEY,
+    level VARCHAR(20) NOT NULL CHECK (level IN ('unverified', 'verified')),
+    updated_by VARCHAR(100) NOT NULL,
+    updated_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+-- KYC changes are audited against the user, and user IDs run to 100 characters
+ALTER TABLE admin_audit_log ALTER COLUMN target_id TYPE VARCHAR(100);
@@ -0,0 +1,38 @@
+-- Free-text columns are encrypted by the service (FIELD_ENCRYPTION_KEYS)
+-- Key features:
+-- 1. Ciphertext is stored as "enc:<key id>:<hex>", far longer than what
+--    users may type, so the columns become TEXT (request validation still
+--    caps the plaintext)
+-- 2. Rows written before encryption stay readable as they are;
+--    `rekey-fields` encrypts them, and re-encrypts values under old keys
+-- 3. The database can't compare encrypted beneficiary names, so their
+--    per-user uniqueness moves to name_hash, a keyed hash of the name
+
+ALTER TABLE wallet_transactions ALTER COLUMN memo TYPE TEXT;
+ALTER TABLE archived_wallet_transactions ALTER COLUMN memo TYPE TEXT;
+ALTER TABLE transfer_templates ALTER COLUMN memo TYPE TEXT;
+ALTER TABLE pending_transfers ALTER COLUMN memo TYPE TEXT;
+ALTER TABLE wallets ALTER COLUMN nickname TYPE TEXT;
+ALTER TABLE archived_wallets ALTER COLUMN nickname TYPE TEXT;
+ALTER TABLE beneficiaries ALTER COLUMN name TYPE TEXT;
+
+-- Plain SHA-256 for existing (plaintext) names - what the service uses
+-- while encryption is off; `rekey-fields` replaces it with the keyed hash
+ALTER TABLE beneficiaries ADD COLUMN IF NOT EXISTS name_hash CHAR(64);
+UPDATE beneficiaries
+SET name_hash = encode(sha256(convert_to(name, 'UTF8')), 'hex')
+WHERE name_hash IS NULL;
+ALTER TABLE beneficiaries ALTER COLUMN name_hash SET NOT NULL;
+
+ALTER TABLE beneficiaries DROP CONSTRAINT IF EXISTS beneficiaries_user_id_name_key;
+CREATE UNIQUE INDEX IF NOT EXISTS idx_beneficiaries_user_id_name_hash
+    ON beneficiaries(user_id, name_hash);
+
+-- Re-encrypting a nickname or labels isn't activity: only the columns real
+-- changes touch (every one of them bumps version) move updated_at, so
+-- `rekey-fields` doesn't restart the dormancy clock archival goes by
+DROP TRIGGER IF EXISTS update_wallets_updated_at ON wallets;
+CREATE TRIGGER update_wallets_updated_at
+    BEFORE UPDATE OF user_id, balance, version, parent_wallet_id, is_default ON wallets
+    FOR EACH ROW
+    EXECUTE FUNCTION update_updated_at_column();
@@ -0,0 +1,84 @@
+-- Create transaction_ledger table
+-- A tamper-evident copy of wallet_transactions, for compliance
+-- Key features:
+-- 1. One hash chain per wallet: each entry's hash covers the previous
+--    entry's hash plus the transaction (see src/ledger.rs for the format)
+-- 2. Written in the same DB transaction as the wallet transaction
+-- 3. Append-only: a trigger refuses UPDATE and DELETE
+-- 4. No foreign keys - entries outlive archived wallets
+-- GET /admin/ledger/verify (or the verify-ledger binary) checks the chains.
+
+CREATE TABLE IF NOT EXISTS transaction_ledger (
+    seq BIGSERIAL PRIMARY KEY,
+    transaction_id VARCHAR(36) NOT NULL UNIQUE,
+    wallet_id VARCHAR(36) NOT NULL,
+    amount DECIMAL(19,4) NOT NULL,
+    type VARCHAR(20) NOT NULL,
+    status VARCHAR(20) NOT NULL,
+    reference_id VARCHAR(36),
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL,
+    prev_hash VARCHAR(64) NOT NULL,
+    hash VARCHAR(64) NOT NULL
+);
+
+-- Index for walking (and appending to) one wallet's chain
+CREATE INDEX idx_transaction_ledger_wallet ON transaction_ledger(wallet_id, seq);
+
+-- A chain can't fork: each entry follows exactly one predecessor
+CREATE UNIQUE INDEX idx_transaction_ledger_chain ON transaction_ledger(wallet_id, prev_hash);
+
+-- Backfill existing transactions (live and archived), oldest first per
+-- wallet. The hash must match ledger::entry_hash byte for byte:
+-- prev|id|wallet|normalized amount|type|status|reference|RFC 3339 micros UTC
+DO $$
+DECLARE
+    t RECORD;
+    prev VARCHAR(64);
+    last_wallet VARCHAR(36);
+    entry_hash VARCHAR(64);
+BEGIN
+    FOR t IN
+        SELECT id, wallet_id, amount, type, status, reference_id, created_at
+        FROM wallet_transactions
+        UNION ALL
+        SELECT id, wallet_id, amount, type, status, reference_id, created_at
+        FROM archived_wallet_transactions
+        ORDER BY wallet_id, created_at, id
+    LOOP
+        IF last_wallet IS DISTINCT FROM t.wallet_id THEN
+            prev := repeat('0', 64);
+            last_wallet := t.wallet_id;
+        END IF;
+
+        entry_hash := encode(sha256(convert_to(concat_ws('|',
+            prev,
+            t.id,
+            t.wallet_id,
+            trim_scale(t.amount)::text,
+            t.type,
+            t.status,
+            COALESCE(t.reference_id, ''),
+            to_char(t.created_at AT TIME ZONE 'UTC', 'YYYY-MM-DD"T"HH24:MI:SS.US"Z"')
+        ), 'UTF8')), 'hex');
+
+        INSERT INTO transaction_ledger
+            (transaction_id, wallet_id, amount, type, status, reference_id, created_at, prev_hash, hash)
+        VALUES
+            (t.id, t.wallet_id, t.amount, t.type, t.status, t.reference_id, t.created_at, prev, entry_hash);
+
+        prev := entry_hash;
+    END LOOP;
+END;
+$$;
+
+CREATE OR REPLACE FUNCTION reject_transaction_ledger_change()
+RETURNS TRIGGER AS $$
+BEGIN
+    RAISE EXCEPTION 'transaction_ledger is append-only';
+END;
+$$ language 'plpgsql';
+
+CREATE TRIGGER transaction_ledger_append_only
+    BEFORE UPDATE OR DELETE ON transaction_ledger
+    FOR EACH ROW
+    EXECUTE FUNCTION reject_transaction_ledger_change();
@@ -0,0 +1,19 @@
+-- Escalating lockouts for PINs and step-up codes
+-- Key features:
+-- 1. wallet_pins.lockouts counts lockouts since the last right PIN; each
+--    one lasts twice as long as the one before (PIN_MAX_LOCKOUT_SECS caps it)
+-- 2. step_up_lockouts counts wrong codes per wallet, across all of its
+--    pending transfers - cancelling one transfer no longer resets the count
+-- 3. Reaching STEP_UP_WALLET_MAX_FAILURES sets locked_until the same way
+--    and starts the count again
+
+ALTER TABLE wallet_pins ADD COLUMN IF NOT EXISTS lockouts INTEGER NOT NULL DEFAULT 0;
+
+CREATE TABLE IF NOT EXISTS step_up_lockouts (
+    wallet_id VARCHAR(36) PRIMARY KEY,
+    failed_attempts INTEGER NOT NULL DEFAULT 0,
+    lockouts INTEGER NOT NULL DEFAULT 0,
+    locked_until TIMESTAMP WITH TIME ZONE,
+    updated_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    FOREIGN KEY (wallet_id) REFERENCES wallets(id) ON DELETE CASCADE
+);
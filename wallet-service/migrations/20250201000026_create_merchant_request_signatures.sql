@@ -0,0 +1,15 @@
+-- Create merchant_request_signatures table
+-- Signatures of accepted merchant requests, so each is accepted only once
+-- Key features:
+-- 1. signature (the hex HMAC) is the primary key - a replay fails the insert
+-- 2. Kept only for the replay window: older signatures are refused by their
+--    timestamp anyway, so expired rows are deleted as new ones come in
+
+CREATE TABLE IF NOT EXISTS merchant_request_signatures (
+    signature CHAR(64) PRIMARY KEY,
+    merchant_id VARCHAR(64) NOT NULL,
+    expires_at TIMESTAMP WITH TIME ZONE NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_merchant_request_signatures_expires_at
+    ON merchant_request_signatures(expires_at);
@@ -0,0 +1,65 @@
+-- Create wallet_balance_shards table
+-- Part of a hot wallet's balance, split over several rows
+-- Key features:
+-- 1. wallets.balance_shards > 0 turns it on for a wallet (merchant
+--    accounts); 0 = off
+-- 2. A transfer INTO a sharded wallet adds to one shard, picked
+--    round-robin, instead of updating (and locking) the wallet row
+-- 3. The balance is wallets.balance plus the shards: read it with
+--    total_balance(wallets)
+-- 4. Whatever locks the wallet row (debits, adjustments...) folds the
+--    shards back into it first, so it sees the whole balance
+-- 5. Each shard has its own ledger chain, so credits don't queue on the
+--    wallet's chain either
+
+ALTER TABLE wallets
+    ADD COLUMN IF NOT EXISTS balance_shards INTEGER NOT NULL DEFAULT 0
+        CHECK (balance_shards >= 0);
+
+CREATE TABLE IF NOT EXISTS wallet_balance_shards (
+    wallet_id VARCHAR(36) NOT NULL REFERENCES wallets(id) ON DELETE CASCADE,
+    shard INTEGER NOT NULL,
+    balance DECIMAL(19,4) NOT NULL DEFAULT 0 CHECK (balance >= 0),
+    PRIMARY KEY (wallet_id, shard)
+);
+
+-- Round-robin over the shards. nextval never waits on another transaction
+CREATE SEQUENCE IF NOT EXISTS wallet_balance_shard_picks;
+
+-- The whole balance of a wallet row
+CREATE OR REPLACE FUNCTION total_balance(w wallets)
+RETURNS DECIMAL AS $$
+    SELECT w.balance + CASE
+        WHEN w.balance_shards > 0 THEN
+            (SELECT COALESCE(SUM(s.balance), 0) FROM wallet_balance_shards s WHERE s.wallet_id = w.id)
+        ELSE 0
+    END
+$$ LANGUAGE SQL STABLE;
+
+-- A credit to a shard changes the wallet's balance: queue its snapshot
+-- for the wallet-state topic like a change to the wallet row
+CREATE OR REPLACE FUNCTION queue_wallet_shard_state_change()
+RETURNS TRIGGER AS $$
+BEGIN
+    INSERT INTO wallet_state_changes (wallet_id) VALUES (NEW.wallet_id);
+    RETURN NULL;
+END;
+$$ language 'plpgsql';
+
+CREATE TRIGGER queue_wallet_balance_shards_state_change
+    AFTER UPDATE ON wallet_balance_shards
+    FOR EACH ROW
+    WHEN (OLD.balance IS DISTINCT FROM NEW.balance)
+    EXECUTE FUNCTION queue_wallet_shard_state_change();
+
+-- Ledger entries of shard credits chain per shard (NULL: the wallet's own
+-- chain). The hash format is unchanged
+ALTER TABLE transaction_ledger ADD COLUMN IF NOT EXISTS shard INTEGER;
+
+DROP INDEX IF EXISTS idx_transaction_ledger_chain;
+CREATE UNIQUE INDEX idx_transaction_ledger_chain
+    ON transaction_ledger(wallet_id, COALESCE(shard, -1), prev_hash);
+
+-- Appending finds its chain's last entry without walking the others
+CREATE INDEX IF NOT EXISTS idx_transaction_ledger_chain_head
+    ON transaction_ledger(wallet_id, COALESCE(shard, -1), seq);
@@ -0,0 +1,117 @@
+use crate::errors::WalletResult;
+use crate::repository::WalletRepository;
+use crate::shutdown::Shutdown;
+use chrono::{DateTime, Duration as ChronoDuration, Utc};
+use std::time::Duration;
+
+/// Actor recorded in the audit log for the background job's archiving
+pub const ARCHIVER_ACTOR: &str = "system:archiver";
+
+/// When a wallet counts as inactive and how much the job moves at a time
+///
+/// Why archive at all?
+/// - Abandoned, empty wallets pile up forever in the tables every money
+///   movement touches; moving them out keeps those tables (and their
+///   indexes) sized by active customers
+/// - Nothing is thrown away: the archive tables keep the wallet and its
+///   full history for audits (GET /admin/archived-wallets/:id)
+#[derive(Debug, Clone)]
+pub struct ArchiveConfig {
+    /// WALLET_ARCHIVE_AFTER_DAYS - untouched this long (default 2 years)
+    pub inactive_days: i64,
+    /// WALLET_ARCHIVE_BATCH - wallets per DB transaction
+    pub batch_size: i64,
+    /// WALLET_ARCHIVE_INTERVAL_SECS - time between background passes
+    pub interval: Duration,
+}
+
+impl Default for ArchiveConfig {
+    fn default() -> Self {
+        Self {
+            inactive_days: 730,
+            batch_size: 100,
+            interval: Duration::from_secs(60 * 60),
+        }
+    }
+}
+
+impl ArchiveConfig {
+    pub fn from_env() -> Self {
+        fn env_or<T: std::str::FromStr>(name: &str, default: T) -> T {
+            std::env::var(name)
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(default)
+        }
+
+        let defaults = Self::default();
+        Self {
+            inactive_days: env_or("WALLET_ARCHIVE_AFTER_DAYS", defaults.inactive_days).max(1),
+            batch_size: env_or("WALLET_ARCHIVE_BATCH", defaults.batch_size).max(1),
+            interval: Duration::from_secs(env_or(
+                "WALLET_ARCHIVE_INTERVAL_SECS",
+                defaults.interval.as_secs(),
+            )),
+        }
+    }
+
+    /// Wallets last changed before this are inactive
+    pub fn cutoff(&self, now: DateTime<Utc>) -> DateTime<Utc> {
+        cutoff(now, self.inactive_days)
+    }
+}
+
+/// `now` minus `inactive_days`
+pub fn cutoff(now: DateTime<Utc>, inactive_days: i64) -> DateTime<Utc> {
+    now - ChronoDuration::days(inactive_days)
+}
+
+/// Archives inactive wallets in the background, a batch per pass
+pub struct WalletArchiver {
+    repository: WalletRepository,
+    config: ArchiveConfig,
+}
+
+impl WalletArchiver {
+    pub fn new(repository: WalletRepository, config: ArchiveConfig) -> Self {
+        Self { repository, config }
+    }
+
+    /// Archive batches until none is full (everything eligible is moved);
+    /// returns how many wallets were archived
+    pub async fn run_once(&self) -> WalletResult<usize> {
+        let cutoff = self.config.cutoff(Utc::now());
+        let mut total = 0;
+
+        loop {
+            let archived = self
+                .repository
+                .archive_inactive_wallets(cutoff, self.config.batch_size, ARCHIVER_ACTOR)
+                .await?;
+            total += archived.len();
+            if (archived.len() as i64) < self.config.batch_size {
+                return Ok(total);
+            }
+        }
+    }
+
+    /// Run a pass every `interval` in the background, until shutdown
+    pub fn spawn(self, shutdown: Shutdown) {
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(self.config.interval);
+
+            loop {
+                tokio::select! {
+                    _ = ticker.tick() => {}
+                    () = shutdown.wait() => return,
+                }
+
+                match self.run_once().await {
+                    Ok(0) => {}
+                    Ok(archived) => tracing::info!(archived, "Archived inactive wallets"),
+                    Err(e) => tracing::error!(error = %e, "Failed to archive inactive wallets"),
+                }
+            }
+        });
+    }
+}
@@ -0,0 +1,851 @@
+use crate::errors::{WalletError, WalletResult};
+use crate::handlers::AppState;
+use crate::merchant_signing::{self, MerchantSigning, MERCHANT_ID_HEADER, MERCHANT_SIGNATURE_HEADER};
+use crate::models::ApiKeyScope;
+use crate::rate_limit::Caller;
+use crate::repository::WalletRepository;
+use axum::{
+    body::Body,
+    extract::{FromRequestParts, RawPathParams, Request, State},
+    http::{header, HeaderValue, Method},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use jsonwebtoken::errors::ErrorKind;
+use jsonwebtoken::jwk::{AlgorithmParameters, EllipticCurve, JwkSet};
+use jsonwebtoken::{Algorithm, DecodingKey, Validation};
+use rand::Rng;
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::str::FromStr;
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, Instant};
+use tokio::sync::OnceCell;
+
+/// Routes served without a token (probes, scrapers, the API description)
+pub const PUBLIC_PATHS: &[&str] = &[
+    "/health",
+    "/health/degradation",
+    "/metrics",
+    "/openapi.json",
+    "/docs",
+];
+
+/// Header machine clients send their API key in
+pub const API_KEY_HEADER: &str = "x-api-key";
+
+/// POST routes that only read (open to `read` API keys and support staff)
+pub const READ_ONLY_POSTS: &[&str] = &["/wallets/batch-get"];
+
+/// Where tokens come from and what they must say
+///
+/// The issuer is an OpenID Connect provider (Keycloak, Auth0...). Its
+/// signing keys are found through its discovery document unless
+/// AUTH_JWKS_URL names them directly.
+#[derive(Debug, Clone)]
+pub struct AuthConfig {
+    /// AUTH_ISSUER - the `iss` every token must carry
+    pub issuer: String,
+    /// AUTH_AUDIENCE - the `aud` tokens must include (unset = not checked)
+    pub audience: Option<String>,
+    /// AUTH_JWKS_URL - the issuer's signing keys (unset = from
+    /// `<issuer>/.well-known/openid-configuration`)
+    pub jwks_url: Option<String>,
+    /// AUTH_LEEWAY_SECS - clock skew allowed on `exp` / `nbf`
+    pub leeway_secs: u64,
+    pub claims: ClaimMapping,
+}
+
+impl AuthConfig {
+    /// None when AUTH=off (local development only); otherwise AUTH_ISSUER
+    /// is required - a typo must not switch auth off
+    pub fn from_env() -> WalletResult<Option<Self>> {
+        if std::env::var("AUTH").as_deref() == Ok("off") {
+            return Ok(None);
+        }
+        let required = |name: &str| {
+            std::env::var(name).map_err(|_| {
+                WalletError::InternalError(format!("{} must be set (or AUTH=off)", name))
+            })
+        };
+
+        Ok(Some(Self {
+            issuer: required("AUTH_ISSUER")?,
+            audience: std::env::var("AUTH_AUDIENCE").ok(),
+            jwks_url: std::env::var("AUTH_JWKS_URL").ok(),
+            leeway_secs: std::env::var("AUTH_LEEWAY_SECS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(30),
+            claims: ClaimMapping::from_env(),
+        }))
+    }
+}
+
+/// How a token's claims become a `Principal`
+///
+/// Providers put things in different places, e.g.
+/// - Keycloak: roles in `realm_access.roles`
+/// - Auth0: roles in a namespaced claim (`https://wallet.example.com/roles`)
+///   added by an Action
+///
+/// A claim name is first looked up as it is, then as a dotted path into
+/// nested objects.
+#[derive(Debug, Clone)]
+pub struct ClaimMapping {
+    /// AUTH_USER_ID_CLAIM - the claim holding our user_id (default `sub`)
+    pub user_id_claim: String,
+    /// AUTH_ROLES_CLAIM - where the token lists its roles (default `roles`)
+    pub roles_claim: String,
+    /// AUTH_ADMIN_ROLE - the provider's name for admins (default `admin`)
+    pub admin_role: String,
+    /// AUTH_SUPPORT_ROLE - ...and for support staff (default `support`)
+    pub support_role: String,
+}
+
+impl Default for ClaimMapping {
+    fn default() -> Self {
+        Self {
+            user_id_claim: "sub".to_string(),
+            roles_claim: "roles".to_string(),
+            admin_role: "admin".to_string(),
+            support_role: "support".to_string(),
+        }
+    }
+}
+
+impl ClaimMapping {
+    pub fn from_env() -> Self {
+        let defaults = Self::default();
+        let var = |name: &str, default: String| std::env::var(name).unwrap_or(default);
+
+        Self {
+            user_id_claim: var("AUTH_USER_ID_CLAIM", defaults.user_id_claim),
+            roles_claim: var("AUTH_ROLES_CLAIM", defaults.roles_claim),
+            admin_role: var("AUTH_ADMIN_ROLE", defaults.admin_role),
+            support_role: var("AUTH_SUPPORT_ROLE", defaults.support_role),
+        }
+    }
+
+    /// The user_id the claims name (None if the claim is missing or empty)
+    pub fn user_id(&self, claims: &Claims) -> Option<String> {
+        claim(claims, &self.user_id_claim)
+            .and_then(|value| value.as_str())
+            .filter(|user_id| !user_id.is_empty())
+            .map(str::to_string)
+    }
+
+    /// The highest role the claims list - an array of names, or one
+    /// space-separated string (like `scope`). Other names are ignored.
+    pub fn role(&self, claims: &Claims) -> Role {
+        let names: Vec<&str> = match claim(claims, &self.roles_claim) {
+            Some(serde_json::Value::Array(names)) => {
+                names.iter().filter_map(|name| name.as_str()).collect()
+            }
+            Some(serde_json::Value::String(names)) => names.split_whitespace().collect(),
+            _ => Vec::new(),
+        };
+
+        names
+            .into_iter()
+            .filter_map(|name| {
+                if name == self.admin_role {
+                    Some(Role::Admin)
+                } else if name == self.support_role {
+                    Some(Role::Support)
+                } else {
+                    None
+                }
+            })
+            .max()
+            .unwrap_or(Role::User)
+    }
+}
+
+/// A token's claims
+pub type Claims = serde_json::Map<String, serde_json::Value>;
+
+/// The claim called `name`, or else at the dotted path `name`
+fn claim<'a>(claims: &'a Claims, name: &str) -> Option<&'a serde_json::Value> {
+    if let Some(value) = claims.get(name) {
+        return Some(value);
+    }
+    let mut segments = name.split('.');
+    let first = segments.next().and_then(|segment| claims.get(segment));
+    segments.fold(first, |value, segment| value.and_then(|v| v.get(segment)))
+}
+
+/// What a person may do, highest last
+///
+/// - User: their own wallets
+/// - Support: read anything (`/admin/*` included), add support notes
+/// - Admin: everything, adjustments included
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Role {
+    User,
+    Support,
+    Admin,
+}
+
+impl std::fmt::Display for Role {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Role::User => write!(f, "user"),
+            Role::Support => write!(f, "support"),
+            Role::Admin => write!(f, "admin"),
+        }
+    }
+}
+
+/// Who made the request
+///
+/// - A person: `subject` is the token's `sub`, which is our user_id, and
+///   `role` comes from the token's roles claim
+/// - A machine client: `subject` is `api-key:<id>`, and the key's scope is
+///   set. Services act for any user, so they own every wallet
+/// - A merchant's signed request: `subject` is `merchant:<id>` and
+///   `merchant_id` is set. A merchant is not a service: it only owns its
+///   own wallets (those whose user_id is the merchant ID), and only fund
+///   and transfer take signed requests
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Principal {
+    pub subject: String,
+    pub role: Role,
+    pub api_key_scope: Option<ApiKeyScope>,
+    pub merchant_id: Option<String>,
+}
+
+impl Principal {
+    pub fn user(subject: impl Into<String>) -> Self {
+        Self {
+            subject: subject.into(),
+            role: Role::User,
+            api_key_scope: None,
+            merchant_id: None,
+        }
+    }
+
+    pub fn merchant(merchant_id: impl Into<String>) -> Self {
+        let merchant_id = merchant_id.into();
+        Self {
+            subject: format!("merchant:{}", merchant_id),
+            role: Role::User,
+            api_key_scope: None,
+            merchant_id: Some(merchant_id),
+        }
+    }
+
+    pub fn with_role(mut self, role: Role) -> Self {
+        self.role = role;
+        self
+    }
+
+    /// May this caller act on things belonging to `user_id`?
+    pub fn owns(&self, user_id: &str) -> bool {
+        if let Some(merchant_id) = &self.merchant_id {
+            return merchant_id == user_id;
+        }
+        self.api_key_scope.is_some() || self.role == Role::Admin || self.subject == user_id
+    }
+
+    /// May this caller see things belonging to `user_id`?
+    pub fn can_read(&self, user_id: &str) -> bool {
+        self.role >= Role::Support || self.owns(user_id)
+    }
+}
+
+/// `owns`, for handlers that run with auth off too (no principal = allowed)
+pub fn may_access(principal: Option<&Principal>, user_id: &str) -> bool {
+    principal.is_none_or(|p| p.owns(user_id))
+}
+
+/// `can_read`, for handlers that run with auth off too
+pub fn may_read(principal: Option<&Principal>, user_id: &str) -> bool {
+    principal.is_none_or(|p| p.can_read(user_id))
+}
+
+/// Who the audit log records for an admin action: the caller's subject
+/// ("unauthenticated" with auth off)
+pub fn actor(principal: Option<&Principal>) -> String {
+    principal.map_or_else(|| "unauthenticated".to_string(), |p| p.subject.clone())
+}
+
+/// What an OpenID provider's discovery document tells us
+#[derive(Debug, Deserialize)]
+struct ProviderMetadata {
+    issuer: String,
+    jwks_uri: String,
+}
+
+/// One signing key, and the only algorithm it may be used with
+///
+/// Pinning the algorithm per key is what stops a token "signed" with HS256
+/// using an RSA public key as the secret.
+#[derive(Clone)]
+pub struct VerifyingKey {
+    pub key: DecodingKey,
+    pub algorithm: Algorithm,
+}
+
+struct KeyCache {
+    keys: HashMap<String, VerifyingKey>,
+    fetched_at: Option<Instant>,
+}
+
+/// Verifies bearer tokens against the issuer's JWKS
+///
+/// How it works:
+/// 1. The token's `kid` picks the key; the key's algorithm must match the
+///    token's `alg`
+/// 2. Signature, `exp`, `nbf`, `iss` (and `aud` if configured) are checked
+/// 3. An unknown `kid` refetches the JWKS - that's how key rotation shows
+///    up - but at most once per JWKS_REFETCH, so junk tokens can't make us
+///    hammer the issuer
+///
+/// API keys (X-API-Key) are looked up by their SHA-256 in `api_keys`, once
+/// `with_api_keys` has given us the repository. Merchants' signed requests
+/// (`with_merchants`) need it too, to refuse replays.
+pub struct Authenticator {
+    config: AuthConfig,
+    client: Option<reqwest::Client>,
+    cache: RwLock<KeyCache>,
+    discovered_jwks_url: OnceCell<String>,
+    repository: Option<WalletRepository>,
+    merchants: Option<MerchantSigning>,
+}
+
+/// Shortest time between two JWKS fetches
+const JWKS_REFETCH: Duration = Duration::from_secs(60);
+
+impl Authenticator {
+    pub fn new(config: AuthConfig) -> WalletResult<Self> {
+        let client = crate::tls::client_builder()
+            .map_err(|e| WalletError::InternalError(format!("JWKS client TLS: {}", e)))?
+            .timeout(Duration::from_secs(5))
+            .build()
+            .map_err(|e| WalletError::InternalError(format!("JWKS client: {}", e)))?;
+
+        Ok(Self {
+            config,
+            client: Some(client),
+            cache: RwLock::new(KeyCache {
+                keys: HashMap::new(),
+                fetched_at: None,
+            }),
+            discovered_jwks_url: OnceCell::new(),
+            repository: None,
+            merchants: None,
+        })
+    }
+
+    /// A fixed key set that is never fetched (tests, or keys from a file)
+    pub fn with_keys(config: AuthConfig, keys: HashMap<String, VerifyingKey>) -> Self {
+        Self {
+            config,
+            client: None,
+            cache: RwLock::new(KeyCache {
+                keys,
+                fetched_at: Some(Instant::now()),
+            }),
+            discovered_jwks_url: OnceCell::new(),
+            repository: None,
+            merchants: None,
+        }
+    }
+
+    /// Accept the API keys stored in `repository` too
+    pub fn with_api_keys(mut self, repository: WalletRepository) -> Self {
+        self.repository = Some(repository);
+        self
+    }
+
+    /// Accept requests signed with these merchants' secrets too
+    pub fn with_merchants(mut self, merchants: MerchantSigning) -> Self {
+        self.merchants = Some(merchants);
+        self
+    }
+
+    /// The machine client behind an API key, or Unauthorized
+    ///
+    /// Unknown and revoked keys get the same answer.
+    pub async fn verify_api_key(&self, key: &str) -> WalletResult<Principal> {
+        let Some(repository) = &self.repository else {
+            return Err(WalletError::Unauthorized("API keys not accepted".to_string()));
+        };
+
+        match repository.find_active_api_key(&hash_api_key(key)).await? {
+            Some(api_key) => Ok(Principal {
+                subject: format!("api-key:{}", api_key.id),
+                role: Role::User,
+                api_key_scope: Some(api_key.scope),
+                merchant_id: None,
+            }),
+            None => Err(WalletError::Unauthorized("invalid API key".to_string())),
+        }
+    }
+
+    /// The merchant behind a signed request, or Unauthorized
+    ///
+    /// `path` is the path with its query string. A signature is accepted
+    /// once; the same request sent again is a replay.
+    pub async fn verify_merchant(
+        &self,
+        merchant_id: &str,
+        signature: &str,
+        method: &Method,
+        path: &str,
+        body: &[u8],
+    ) -> WalletResult<Principal> {
+        let (Some(merchants), Some(repository)) = (&self.merchants, &self.repository) else {
+            return Err(WalletError::Unauthorized("signed requests not accepted".to_string()));
+        };
+
+        let now = chrono::Utc::now().timestamp();
+        let digest = merchants.verify(merchant_id, signature, method, path, body, now)?;
+        if !repository
+            .record_merchant_signature(merchant_id, &digest, merchants.window() * 2)
+            .await?
+        {
+            tracing::warn!(merchant_id = %merchant_id, path = %path, "Replayed merchant request refused");
+            return Err(WalletError::Unauthorized("signature already used".to_string()));
+        }
+
+        Ok(Principal::merchant(merchant_id))
+    }
+
+    /// The caller behind `token`, or Unauthorized saying why not
+    pub async fn verify(&self, token: &str) -> WalletResult<Principal> {
+        let unauthorized = |reason: &str| WalletError::Unauthorized(reason.to_string());
+
+        let header =
+            jsonwebtoken::decode_header(token).map_err(|_| unauthorized("malformed token"))?;
+        let kid = header.kid.ok_or_else(|| unauthorized("token has no kid"))?;
+
+        let key = match self.key(&kid) {
+            Some(key) => key,
+            None => {
+                self.refresh().await;
+                self.key(&kid).ok_or_else(|| unauthorized("unknown signing key"))?
+            }
+        };
+        if header.alg != key.algorithm {
+            return Err(unauthorized("wrong algorithm for this key"));
+        }
+
+        let mut validation = Validation::new(key.algorithm);
+        validation.leeway = self.config.leeway_secs;
+        validation.validate_nbf = true;
+        validation.set_issuer(&[&self.config.issuer]);
+        validation.set_required_spec_claims(&["exp", "iss", "sub"]);
+        match &self.config.audience {
+            Some(audience) => validation.set_audience(&[audience]),
+            None => validation.validate_aud = false,
+        }
+
+        let claims = jsonwebtoken::decode::<Claims>(token, &key.key, &validation)
+            .map_err(|e| {
+                tracing::debug!(error = %e, "Token rejected");
+                match e.kind() {
+                    ErrorKind::ExpiredSignature => unauthorized("token expired"),
+                    _ => unauthorized("invalid token"),
+                }
+            })?
+            .claims;
+
+        let mapping = &self.config.claims;
+        let user_id = mapping
+            .user_id(&claims)
+            .ok_or_else(|| unauthorized("token names no user"))?;
+        Ok(Principal::user(user_id).with_role(mapping.role(&claims)))
+    }
+
+    fn key(&self, kid: &str) -> Option<VerifyingKey> {
+        let cache = self.cache.read().unwrap_or_else(|e| e.into_inner());
+        cache.keys.get(kid).cloned()
+    }
+
+    /// Refetch the JWKS, unless we did so recently; failures keep the old keys
+    async fn refresh(&self) {
+        let Some(client) = &self.client else {
+            return;
+        };
+        {
+            let mut cache = self.cache.write().unwrap_or_else(|e| e.into_inner());
+            if cache.fetched_at.is_some_and(|at| at.elapsed() < JWKS_REFETCH) {
+                return;
+            }
+            // Claim this refresh so concurrent misses don't all fetch
+            cache.fetched_at = Some(Instant::now());
+        }
+
+        let fetched = match self.jwks_url(client).await {
+            Ok(url) => fetch_jwks(client, url).await,
+            Err(e) => Err(e),
+        };
+        match fetched {
+            Ok(keys) => {
+                tracing::info!(keys = keys.len(), "Loaded JWKS");
+                self.cache.write().unwrap_or_else(|e| e.into_inner()).keys = keys;
+            }
+            Err(e) => tracing::warn!(error = %e, issuer = %self.config.issuer, "JWKS fetch failed"),
+        }
+    }
+
+    /// AUTH_JWKS_URL, or the provider's `jwks_uri` (discovered once)
+    async fn jwks_url(&self, client: &reqwest::Client) -> Result<&str, String> {
+        if let Some(url) = &self.config.jwks_url {
+            return Ok(url);
+        }
+        self.discovered_jwks_url
+            .get_or_try_init(|| discover_jwks_url(client, &self.config.issuer))
+            .await
+            .map(String::as_str)
+    }
+}
+
+/// The provider's JWKS URL, from its OpenID Connect discovery document
+///
+/// The document must be for the issuer we asked about (OIDC Discovery
+/// 4.3) - otherwise we'd trust another issuer's keys.
+pub async fn discover_jwks_url(client: &reqwest::Client, issuer: &str) -> Result<String, String> {
+    let url = format!("{}/.well-known/openid-configuration", issuer.trim_end_matches('/'));
+    let metadata: ProviderMetadata = client
+        .get(&url)
+        .send()
+        .await
+        .and_then(|r| r.error_for_status())
+        .map_err(|e| e.to_string())?
+        .json()
+        .await
+        .map_err(|e| e.to_string())?;
+
+    if metadata.issuer != issuer {
+        return Err(format!("{} describes issuer {}, not {}", url, metadata.issuer, issuer));
+    }
+    tracing::info!(jwks_uri = %metadata.jwks_uri, "Discovered OpenID provider keys");
+    Ok(metadata.jwks_uri)
+}
+
+async fn fetch_jwks(
+    client: &reqwest::Client,
+    url: &str,
+) -> Result<HashMap<String, VerifyingKey>, String> {
+    let jwks: JwkSet = client
+        .get(url)
+        .send()
+        .await
+        .and_then(|r| r.error_for_status())
+        .map_err(|e| e.to_string())?
+        .json()
+        .await
+        .map_err(|e| e.to_string())?;
+
+    Ok(verifying_keys(&jwks))
+}
+
+/// The usable keys of a JWKS, by `kid`
+///
+/// Keys without a `kid`, symmetric keys and keys we can't parse are
+/// skipped. A key without `alg` gets its type's usual one (RS256, ES256
+/// or ES384 by curve, EdDSA).
+pub fn verifying_keys(jwks: &JwkSet) -> HashMap<String, VerifyingKey> {
+    jwks.keys
+        .iter()
+        .filter_map(|jwk| {
+            let kid = jwk.common.key_id.clone()?;
+            let algorithm = match (&jwk.common.key_algorithm, &jwk.algorithm) {
+                (Some(alg), _) => Algorithm::from_str(&alg.to_string()).ok()?,
+                (None, AlgorithmParameters::RSA(_)) => Algorithm::RS256,
+                (None, AlgorithmParameters::EllipticCurve(ec)) => match ec.curve {
+                    EllipticCurve::P256 => Algorithm::ES256,
+                    EllipticCurve::P384 => Algorithm::ES384,
+                    _ => return None,
+                },
+                (None, AlgorithmParameters::OctetKeyPair(_)) => Algorithm::EdDSA,
+                (None, AlgorithmParameters::OctetKey(_)) => return None,
+            };
+            if matches!(algorithm, Algorithm::HS256 | Algorithm::HS384 | Algorithm::HS512) {
+                return None;
+            }
+            let key = DecodingKey::from_jwk(jwk).ok()?;
+            Some((kid, VerifyingKey { key, algorithm }))
+        })
+        .collect()
+}
+
+/// The token from `Authorization: Bearer <token>`
+pub fn bearer_token(headers: &axum::http::HeaderMap) -> Option<&str> {
+    let value = headers.get(header::AUTHORIZATION)?.to_str().ok()?;
+    let (scheme, token) = value.split_once(' ')?;
+    scheme.eq_ignore_ascii_case("bearer").then_some(token.trim())
+}
+
+/// A new API key: `wk_` + 32 random bytes as hex
+pub fn generate_api_key() -> String {
+    let bytes: [u8; 32] = rand::thread_rng().gen();
+    format!("wk_{}", hex::encode(bytes))
+}
+
+/// What we store instead of the key (hex SHA-256)
+///
+/// A plain hash is enough: keys are 256 random bits, nothing to brute-force.
+pub fn hash_api_key(key: &str) -> String {
+    hex::encode(Sha256::digest(key.as_bytes()))
+}
+
+/// The start of a key that is safe to show ("wk_1a2b3c4d")
+pub fn api_key_prefix(key: &str) -> String {
+    key.chars().take(11).collect()
+}
+
+/// GET, HEAD and READ_ONLY_POSTS
+pub fn is_read(method: &Method, path: &str) -> bool {
+    method == Method::GET
+        || method == Method::HEAD
+        || (method == Method::POST && READ_ONLY_POSTS.contains(&path))
+}
+
+/// The role a person needs for this request
+///
+/// - `/admin/*` and `/webhooks*`: reads need support, changes need admin,
+///   except support notes (`POST /admin/transactions/:id/notes`)
+/// - Everything else: any user - `enforce_ownership` keeps them to their
+///   own wallets
+pub fn required_role(method: &Method, path: &str) -> Role {
+    let privileged = path.starts_with("/admin/") || path.starts_with("/webhooks");
+    let support_note = method == Method::POST
+        && path.starts_with("/admin/transactions/")
+        && path.ends_with("/notes");
+
+    if !privileged {
+        Role::User
+    } else if is_read(method, path) || support_note {
+        Role::Support
+    } else {
+        Role::Admin
+    }
+}
+
+/// May this caller make this request at all? (Forbidden if not)
+///
+/// API keys are held to their scope, people to their role.
+pub fn authorize(principal: &Principal, method: &Method, path: &str) -> WalletResult<()> {
+    if let Some(scope) = principal.api_key_scope {
+        return if api_key_allows(scope, method, path) {
+            Ok(())
+        } else {
+            Err(WalletError::Forbidden(format!("not allowed for a {} API key", scope)))
+        };
+    }
+
+    let required = required_role(method, path);
+    if principal.role >= required {
+        Ok(())
+    } else {
+        Err(WalletError::Forbidden(format!("needs the {} role", required)))
+    }
+}
+
+/// May an API key with `scope` make this request?
+///
+/// `/admin/*` is for people, never keys - otherwise a leaked key could
+/// mint more keys. Nor can a key erase a user's data (keys act for any
+/// user). `read` keys may only GET (and READ_ONLY_POSTS).
+pub fn api_key_allows(scope: ApiKeyScope, method: &Method, path: &str) -> bool {
+    if path.starts_with("/admin/") || is_erasure(method, path) {
+        return false;
+    }
+    match scope {
+        ApiKeyScope::Read => is_read(method, path),
+        ApiKeyScope::Transact => true,
+    }
+}
+
+/// DELETE /users/:user_id/data
+fn is_erasure(method: &Method, path: &str) -> bool {
+    *method == Method::DELETE && path.starts_with("/users/") && path.ends_with("/data")
+}
+
+/// Middleware: every route outside PUBLIC_PATHS needs a valid bearer token
+/// or API key - or, on fund and transfer, a merchant's signature
+///
+/// The caller is put in the request extensions as a `Principal`. A role
+/// too low for the route, or an API key outside its scope, is a 403.
+pub async fn require_auth(
+    State(authenticator): State<Arc<Authenticator>>,
+    request: Request,
+    next: Next,
+) -> Response {
+    if PUBLIC_PATHS.contains(&request.uri().path()) {
+        return next.run(request).await;
+    }
+
+    let (mut request, verified) = if request.headers().contains_key(MERCHANT_ID_HEADER) {
+        verify_signed(&authenticator, request).await
+    } else {
+        let api_key = request
+            .headers()
+            .get(API_KEY_HEADER)
+            .and_then(|value| value.to_str().ok());
+        let verified = match (api_key, bearer_token(request.headers())) {
+            (Some(key), _) => authenticator.verify_api_key(key.trim()).await,
+            (None, Some(token)) => authenticator.verify(token).await,
+            (None, None) => Err(WalletError::Unauthorized("missing bearer token".to_string())),
+        };
+        (request, verified)
+    };
+
+    match verified {
+        Ok(principal) => {
+            if let Err(e) = authorize(&principal, request.method(), request.uri().path()) {
+                return e.into_response();
+            }
+            // Keys the per-user rate limit
+            request.extensions_mut().insert(Caller(principal.subject.clone()));
+            request.extensions_mut().insert(principal);
+            next.run(request).await
+        }
+        Err(e @ WalletError::Unauthorized(_)) => {
+            let mut response = e.into_response();
+            response.headers_mut().insert(
+                header::WWW_AUTHENTICATE,
+                HeaderValue::from_static("Bearer error=\"invalid_token\""),
+            );
+            response
+        }
+        // The API key lookup itself failed
+        Err(e) => e.into_response(),
+    }
+}
+
+/// A merchant's signed request: the body is read to check it, then put
+/// back for the handler
+///
+/// The body is already capped by `require_json_body`.
+async fn verify_signed(
+    authenticator: &Authenticator,
+    request: Request,
+) -> (Request, WalletResult<Principal>) {
+    let (parts, body) = request.into_parts();
+    if !merchant_signing::is_signed_route(&parts.method, parts.uri.path()) {
+        let refused = Err(WalletError::Forbidden(
+            "signed requests are only accepted on fund and transfer".to_string(),
+        ));
+        return (Request::from_parts(parts, body), refused);
+    }
+    let body = match axum::body::to_bytes(body, usize::MAX).await {
+        Ok(body) => body,
+        Err(e) => {
+            let unreadable = Err(WalletError::InvalidRequest(format!("unreadable body: {}", e)));
+            return (Request::from_parts(parts, Body::empty()), unreadable);
+        }
+    };
+
+    let header = |name: &str| {
+        parts
+            .headers
+            .get(name)
+            .and_then(|value| value.to_str().ok())
+            .unwrap_or_default()
+    };
+    let path = parts
+        .uri
+        .path_and_query()
+        .map_or_else(|| parts.uri.path(), |pq| pq.as_str());
+    let verified = authenticator
+        .verify_merchant(
+            header(MERCHANT_ID_HEADER).trim(),
+            header(MERCHANT_SIGNATURE_HEADER),
+            &parts.method,
+            path,
+            &body,
+        )
+        .await;
+
+    (Request::from_parts(parts, Body::from(body)), verified)
+}
+
+/// Middleware: a caller only reaches their own wallets
+///
+/// Looks at the route's path parameters:
+/// - `wallet_id` (pots too): the wallet's user_id must be the caller
+/// - `user_id`: must be the caller
+/// - `template_id` / `transaction_id`: the wallet it belongs to must be theirs
+///
+/// Someone else's wallet, template or transaction is a 404, exactly like
+/// one that doesn't exist - guessing IDs tells you nothing. A wrong
+/// user_id is a 403 (user IDs aren't secret).
+///
+/// Not checked: `/admin/*` (roles decide, see `required_role`), API keys
+/// and admins (every wallet), and support staff reading. Without a
+/// `Principal` (AUTH=off) this does nothing.
+pub async fn enforce_ownership(
+    State(state): State<AppState>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let (mut parts, body) = request.into_parts();
+    let Some(principal) = parts.extensions.get::<Principal>().cloned() else {
+        return next.run(Request::from_parts(parts, body)).await;
+    };
+    let unrestricted = principal.api_key_scope.is_some()
+        || principal.role == Role::Admin
+        || (principal.role == Role::Support && is_read(&parts.method, parts.uri.path()));
+    if parts.uri.path().starts_with("/admin/") || unrestricted {
+        return next.run(Request::from_parts(parts, body)).await;
+    }
+
+    if let Ok(params) = RawPathParams::from_request_parts(&mut parts, &()).await {
+        for (name, value) in params.iter() {
+            if let Err(e) = check_owner(&state, &principal, name, value).await {
+                return e.into_response();
+            }
+        }
+    }
+
+    next.run(Request::from_parts(parts, body)).await
+}
+
+async fn check_owner(
+    state: &AppState,
+    principal: &Principal,
+    name: &str,
+    value: &str,
+) -> WalletResult<()> {
+    let repository = &state.repository;
+    match name {
+        "user_id" if !principal.owns(value) => Err(WalletError::Forbidden(
+            "you can only access your own user".to_string(),
+        )),
+        "wallet_id" => {
+            let wallet = repository.find_by_id(value).await?;
+            owned(principal, &wallet.user_id, || WalletError::WalletNotFound(value.to_string()))
+        }
+        "template_id" => {
+            let template = repository.find_template(value).await?;
+            let wallet = repository.find_by_id(&template.wallet_id).await?;
+            owned(principal, &wallet.user_id, || WalletError::TemplateNotFound(value.to_string()))
+        }
+        "transaction_id" => {
+            let transaction = repository.find_transaction(value).await?;
+            let wallet = repository.find_by_id(&transaction.wallet_id).await?;
+            owned(principal, &wallet.user_id, || {
+                WalletError::TransactionNotFound(value.to_string())
+            })
+        }
+        _ => Ok(()),
+    }
+}
+
+fn owned(
+    principal: &Principal,
+    owner: &str,
+    not_found: impl FnOnce() -> WalletError,
+) -> WalletResult<()> {
+    if principal.owns(owner) {
+        Ok(())
+    } else {
+        Err(not_found())
+    }
+}
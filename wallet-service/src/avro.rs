@@ -0,0 +1,228 @@
+use crate::errors::{WalletError, WalletResult};
+use chrono::{DateTime, Utc};
+use serde_json::Value;
+
+/// The wallet event schema, shared with history-service
+pub const WALLET_EVENT_SCHEMA: &str = include_str!("../../schemas/wallet-event.avsc");
+
+/// Magic byte that starts every Confluent-framed message
+pub const CONFLUENT_MAGIC: u8 = 0;
+
+/// The subset of Avro the wallet event schema uses
+///
+/// Why hand-rolled?
+/// - The events are flat records of strings and timestamps - a handful of
+///   primitive encodings covers them
+/// - Named type references, maps, arrays, enums and fixed aren't supported;
+///   `parse` rejects a schema that needs them rather than mis-encoding
+#[derive(Debug, Clone, PartialEq)]
+pub enum Schema {
+    Null,
+    Boolean,
+    Int,
+    Long { timestamp_micros: bool },
+    Double,
+    String,
+    Record(RecordSchema),
+    Union(Vec<Schema>),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct RecordSchema {
+    pub name: String,
+    /// Our `eventType` attribute: the JSON tag this record stands for
+    pub event_type: Option<String>,
+    pub fields: Vec<Field>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Field {
+    pub name: String,
+    pub schema: Schema,
+    pub default: Option<Value>,
+}
+
+impl Schema {
+    /// Parse a schema from its JSON text
+    pub fn parse(text: &str) -> WalletResult<Self> {
+        let json: Value = serde_json::from_str(text)
+            .map_err(|e| WalletError::InternalError(format!("Invalid Avro schema: {}", e)))?;
+        Self::from_json(&json)
+    }
+
+    fn from_json(json: &Value) -> WalletResult<Self> {
+        match json {
+            Value::String(name) => Self::primitive(name, None),
+            Value::Array(branches) => Ok(Schema::Union(
+                branches.iter().map(Self::from_json).collect::<WalletResult<_>>()?,
+            )),
+            Value::Object(obj) => match obj.get("type").and_then(Value::as_str) {
+                Some("record") => {
+                    let fields = obj
+                        .get("fields")
+                        .and_then(Value::as_array)
+                        .ok_or_else(|| unsupported("record without fields"))?
+                        .iter()
+                        .map(|field| {
+                            Ok(Field {
+                                name: field
+                                    .get("name")
+                                    .and_then(Value::as_str)
+                                    .ok_or_else(|| unsupported("field without a name"))?
+                                    .to_string(),
+                                schema: Self::from_json(
+                                    field.get("type").ok_or_else(|| unsupported("field without a type"))?,
+                                )?,
+                                default: field.get("default").cloned(),
+                            })
+                        })
+                        .collect::<WalletResult<_>>()?;
+
+                    Ok(Schema::Record(RecordSchema {
+                        name: obj
+                            .get("name")
+                            .and_then(Value::as_str)
+                            .unwrap_or_default()
+                            .to_string(),
+                        event_type: obj
+                            .get("eventType")
+                            .and_then(Value::as_str)
+                            .map(str::to_string),
+                        fields,
+                    }))
+                }
+                Some(name) => Self::primitive(name, obj.get("logicalType").and_then(Value::as_str)),
+                None => Err(unsupported("schema without a type")),
+            },
+            _ => Err(unsupported("schema must be a string, array or object")),
+        }
+    }
+
+    fn primitive(name: &str, logical_type: Option<&str>) -> WalletResult<Self> {
+        match name {
+            "null" => Ok(Schema::Null),
+            "boolean" => Ok(Schema::Boolean),
+            "int" => Ok(Schema::Int),
+            "long" => Ok(Schema::Long {
+                timestamp_micros: logical_type == Some("timestamp-micros"),
+            }),
+            "double" => Ok(Schema::Double),
+            "string" => Ok(Schema::String),
+            other => Err(unsupported(&format!("type '{}'", other))),
+        }
+    }
+
+    /// Encode a JSON value (as serde produces it for a WalletEvent)
+    pub fn encode(&self, value: &Value, out: &mut Vec<u8>) -> WalletResult<()> {
+        match (self, value) {
+            (Schema::Null, Value::Null) => Ok(()),
+            (Schema::Boolean, Value::Bool(b)) => {
+                out.push(*b as u8);
+                Ok(())
+            }
+            (Schema::Int, Value::Number(n)) | (Schema::Long { .. }, Value::Number(n)) => {
+                let n = n.as_i64().ok_or_else(|| mismatch(self, value))?;
+                write_long(n, out);
+                Ok(())
+            }
+            // Timestamps travel as RFC 3339 strings in JSON
+            (Schema::Long { timestamp_micros: true }, Value::String(s)) => {
+                let ts = DateTime::parse_from_rfc3339(s)
+                    .map_err(|_| mismatch(self, value))?
+                    .with_timezone(&Utc);
+                write_long(ts.timestamp_micros(), out);
+                Ok(())
+            }
+            (Schema::Double, Value::Number(n)) => {
+                out.extend_from_slice(&n.as_f64().unwrap_or_default().to_le_bytes());
+                Ok(())
+            }
+            (Schema::String, Value::String(s)) => {
+                write_long(s.len() as i64, out);
+                out.extend_from_slice(s.as_bytes());
+                Ok(())
+            }
+            (Schema::Record(record), Value::Object(obj)) => {
+                for field in &record.fields {
+                    let field_value = obj
+                        .get(&field.name)
+                        .or(field.default.as_ref())
+                        .unwrap_or(&Value::Null);
+                    field.schema.encode(field_value, out)?;
+                }
+                Ok(())
+            }
+            (Schema::Union(branches), _) => {
+                let index = branches
+                    .iter()
+                    .position(|branch| branch.accepts(value))
+                    .ok_or_else(|| mismatch(self, value))?;
+                write_long(index as i64, out);
+                branches[index].encode(value, out)
+            }
+            _ => Err(mismatch(self, value)),
+        }
+    }
+
+    fn kind(&self) -> &'static str {
+        match self {
+            Schema::Null => "null",
+            Schema::Boolean => "boolean",
+            Schema::Int => "int",
+            Schema::Long { .. } => "long",
+            Schema::Double => "double",
+            Schema::String => "string",
+            Schema::Record(_) => "record",
+            Schema::Union(_) => "union",
+        }
+    }
+
+    /// Which union branch a value belongs to
+    ///
+    /// Records are matched on `eventType`, so each event picks its own record.
+    fn accepts(&self, value: &Value) -> bool {
+        match (self, value) {
+            (Schema::Null, Value::Null) => true,
+            (Schema::Boolean, Value::Bool(_)) => true,
+            (Schema::Int | Schema::Long { .. } | Schema::Double, Value::Number(_)) => true,
+            (Schema::Long { timestamp_micros: true }, Value::String(_)) => true,
+            (Schema::String, Value::String(_)) => true,
+            (Schema::Record(record), Value::Object(obj)) => match &record.event_type {
+                Some(event_type) => obj.get("eventType").and_then(Value::as_str) == Some(event_type),
+                None => true,
+            },
+            _ => false,
+        }
+    }
+}
+
+/// Avro long: zig-zag, then base-128 varint
+fn write_long(n: i64, out: &mut Vec<u8>) {
+    let mut z = ((n << 1) ^ (n >> 63)) as u64;
+    while z >= 0x80 {
+        out.push((z as u8) | 0x80);
+        z >>= 7;
+    }
+    out.push(z as u8);
+}
+
+/// Confluent wire format: magic byte, 4-byte schema ID, Avro body
+pub fn confluent_frame(schema_id: u32, body: &[u8]) -> Vec<u8> {
+    let mut framed = Vec::with_capacity(body.len() + 5);
+    framed.push(CONFLUENT_MAGIC);
+    framed.extend_from_slice(&schema_id.to_be_bytes());
+    framed.extend_from_slice(body);
+    framed
+}
+
+fn unsupported(what: &str) -> WalletError {
+    WalletError::InternalError(format!("Unsupported Avro schema: {}", what))
+}
+
+fn mismatch(schema: &Schema, value: &Value) -> WalletError {
+    WalletError::InternalError(format!(
+        "Value {} does not match Avro {}",
+        value,
+        schema.kind()
+    ))
+}
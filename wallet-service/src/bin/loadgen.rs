@@ -0,0 +1,255 @@
+//! Drive fund/transfer traffic at a running wallet-service and report
+//! latency and errors per operation
+//!
+//! Usage:
+//!   cargo run --release --bin loadgen -- \
+//!       [--url http://localhost:3000] [--wallets 20] [--users 5] \
+//!       [--concurrency 16] [--duration 30] [--mix fund=20,transfer=80] \
+//!       [--hot-wallets 0] [--initial-balance 10000.00] [--seed 42] \
+//!       [--max-failure-rate 0.01]
+//!
+//! Creates `--wallets` wallets spread over `--users` users, funds each with
+//! `--initial-balance`, then runs `--concurrency` workers for `--duration`
+//! seconds. `--hot-wallets N` sends every transfer between the first N
+//! wallets, to load the same rows from every worker at once.
+//!
+//! Every request goes through the HTTP API, rate limits and auth included.
+//! With auth on, set LOADGEN_TOKEN (sent as a Bearer token) or
+//! LOADGEN_API_KEY (X-API-Key) for a caller allowed to use every wallet.
+//! Exits non-zero when more than `--max-failure-rate` of the requests fail.
+
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use reqwest::header::{HeaderMap, HeaderValue, AUTHORIZATION};
+use serde_json::{json, Value};
+use std::collections::BTreeMap;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use wallet_service::auth::API_KEY_HEADER;
+use wallet_service::loadgen::{render_report, Mix, Operation, OperationStats};
+
+struct Args {
+    url: String,
+    wallets: usize,
+    users: usize,
+    concurrency: usize,
+    duration: Duration,
+    mix: Mix,
+    hot_wallets: usize,
+    initial_balance: String,
+    seed: u64,
+    max_failure_rate: Option<f64>,
+}
+
+fn parse_args() -> anyhow::Result<Args> {
+    let mut parsed = Args {
+        url: "http://localhost:3000".to_string(),
+        wallets: 20,
+        users: 5,
+        concurrency: 16,
+        duration: Duration::from_secs(30),
+        mix: Mix::default(),
+        hot_wallets: 0,
+        initial_balance: "10000.00".to_string(),
+        seed: rand::random(),
+        max_failure_rate: None,
+    };
+
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        let mut value = || {
+            args.next()
+                .ok_or_else(|| anyhow::anyhow!("Missing value for {}", arg))
+        };
+        match arg.as_str() {
+            "--url" => parsed.url = value()?.trim_end_matches('/').to_string(),
+            "--wallets" => parsed.wallets = value()?.parse()?,
+            "--users" => parsed.users = value()?.parse()?,
+            "--concurrency" => parsed.concurrency = value()?.parse()?,
+            "--duration" => parsed.duration = Duration::from_secs(value()?.parse()?),
+            "--mix" => parsed.mix = Mix::parse(&value()?).map_err(anyhow::Error::msg)?,
+            "--hot-wallets" => parsed.hot_wallets = value()?.parse()?,
+            "--initial-balance" => parsed.initial_balance = value()?,
+            "--seed" => parsed.seed = value()?.parse()?,
+            "--max-failure-rate" => parsed.max_failure_rate = Some(value()?.parse()?),
+            other => anyhow::bail!("Unknown argument: {}", other),
+        }
+    }
+
+    if parsed.mix.transfer > 0 && parsed.wallets < 2 {
+        anyhow::bail!("--wallets must be at least 2 for transfers");
+    }
+    if parsed.hot_wallets == 1 || parsed.hot_wallets > parsed.wallets {
+        anyhow::bail!("--hot-wallets must be 0 (off) or between 2 and --wallets");
+    }
+    parsed.users = parsed.users.clamp(1, parsed.wallets.max(1));
+    parsed.concurrency = parsed.concurrency.max(1);
+
+    Ok(parsed)
+}
+
+/// The client every worker shares, with the caller's credentials
+fn client() -> anyhow::Result<reqwest::Client> {
+    let mut headers = HeaderMap::new();
+    if let Ok(token) = std::env::var("LOADGEN_TOKEN") {
+        headers.insert(AUTHORIZATION, HeaderValue::from_str(&format!("Bearer {}", token))?);
+    }
+    if let Ok(key) = std::env::var("LOADGEN_API_KEY") {
+        headers.insert(API_KEY_HEADER, HeaderValue::from_str(&key)?);
+    }
+
+    Ok(reqwest::Client::builder()
+        .default_headers(headers)
+        .timeout(Duration::from_secs(30))
+        .build()?)
+}
+
+/// POST `body` to `path`; the response body when it's a 2xx
+async fn post(client: &reqwest::Client, url: String, body: Value) -> anyhow::Result<Value> {
+    let response = client.post(&url).json(&body).send().await?;
+    let status = response.status();
+    let body: Value = response.json().await.unwrap_or(Value::Null);
+    if !status.is_success() {
+        anyhow::bail!("POST {} returned {}: {}", url, status, body);
+    }
+    Ok(body)
+}
+
+/// Create and fund the wallets; their IDs in creation order
+async fn setup_wallets(client: &reqwest::Client, args: &Args) -> anyhow::Result<Vec<String>> {
+    let run = uuid::Uuid::new_v4().simple().to_string();
+    let mut wallet_ids = Vec::with_capacity(args.wallets);
+
+    for i in 0..args.wallets {
+        let user_id = format!("loadgen_{}_{}", &run[..8], i % args.users);
+        let wallet = post(client, format!("{}/wallets", args.url), json!({ "user_id": user_id })).await?;
+        let wallet_id = wallet["data"]["id"]
+            .as_str()
+            .ok_or_else(|| anyhow::anyhow!("No wallet ID in {}", wallet))?
+            .to_string();
+
+        post(
+            client,
+            format!("{}/wallets/{}/fund", args.url, wallet_id),
+            json!({ "amount": args.initial_balance }),
+        )
+        .await?;
+        wallet_ids.push(wallet_id);
+    }
+
+    Ok(wallet_ids)
+}
+
+/// Send requests until `deadline`, recording each one
+async fn worker(
+    client: reqwest::Client,
+    args: Arc<Args>,
+    wallet_ids: Arc<Vec<String>>,
+    seed: u64,
+    deadline: Instant,
+) -> BTreeMap<Operation, OperationStats> {
+    let mut rng = StdRng::seed_from_u64(seed);
+    let mut stats: BTreeMap<Operation, OperationStats> = BTreeMap::new();
+    let pool = if args.hot_wallets > 0 { args.hot_wallets } else { wallet_ids.len() };
+
+    while Instant::now() < deadline {
+        let operation = args.mix.pick(rng.gen_range(0..args.mix.total()));
+        let (url, body) = match operation {
+            Operation::Fund => {
+                let wallet_id = &wallet_ids[rng.gen_range(0..wallet_ids.len())];
+                let amount = format!("{}.00", rng.gen_range(1..=100));
+                (
+                    format!("{}/wallets/{}/fund", args.url, wallet_id),
+                    json!({ "amount": amount }),
+                )
+            }
+            Operation::Transfer => {
+                let from = rng.gen_range(0..pool);
+                let to = (from + rng.gen_range(1..pool)) % pool;
+                let amount = format!("0.{:02}", rng.gen_range(1..100));
+                (
+                    format!("{}/wallets/{}/transfer", args.url, wallet_ids[from]),
+                    json!({ "to_wallet_id": wallet_ids[to], "amount": amount }),
+                )
+            }
+        };
+
+        let started = Instant::now();
+        let outcome = match client.post(&url).json(&body).send().await {
+            Ok(response) => {
+                let status = response.status().as_u16().to_string();
+                // Read the body so the timing covers the whole response
+                let _ = response.bytes().await;
+                status
+            }
+            Err(e) => {
+                tracing::debug!(error = %e, url = %url, "Request failed");
+                "error".to_string()
+            }
+        };
+        stats.entry(operation).or_default().record(started.elapsed(), &outcome);
+    }
+
+    stats
+}
+
+#[tokio::main]
+async fn main() -> anyhow::Result<()> {
+    dotenvy::dotenv().ok();
+    tracing_subscriber::fmt()
+        .with_env_filter(
+            tracing_subscriber::EnvFilter::try_from_default_env()
+                .unwrap_or_else(|_| "loadgen=info".into()),
+        )
+        .init();
+
+    let args = Arc::new(parse_args()?);
+    let client = client()?;
+
+    println!("Creating {} wallets for {} users at {}", args.wallets, args.users, args.url);
+    let wallet_ids = Arc::new(setup_wallets(&client, &args).await?);
+
+    println!(
+        "Running {} workers for {}s (mix fund={} transfer={}, hot wallets {}, seed {})",
+        args.concurrency,
+        args.duration.as_secs(),
+        args.mix.fund,
+        args.mix.transfer,
+        args.hot_wallets,
+        args.seed
+    );
+    let started = Instant::now();
+    let deadline = started + args.duration;
+    let workers: Vec<_> = (0..args.concurrency)
+        .map(|i| {
+            tokio::spawn(worker(
+                client.clone(),
+                args.clone(),
+                wallet_ids.clone(),
+                args.seed.wrapping_add(i as u64),
+                deadline,
+            ))
+        })
+        .collect();
+
+    let mut totals: BTreeMap<Operation, OperationStats> = BTreeMap::new();
+    for worker in workers {
+        for (operation, stats) in worker.await? {
+            totals.entry(operation).or_default().merge(stats);
+        }
+    }
+    let elapsed = started.elapsed();
+
+    print!("{}", render_report(&totals, elapsed));
+
+    let requests: u64 = totals.values().map(OperationStats::requests).sum();
+    let failures: u64 = totals.values().map(OperationStats::failures).sum();
+    if let Some(max) = args.max_failure_rate {
+        let rate = failures as f64 / requests.max(1) as f64;
+        if rate > max {
+            anyhow::bail!("{} of {} requests failed ({:.2}% > {:.2}%)", failures, requests, rate * 100.0, max * 100.0);
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,235 @@
+use serde::Serialize;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// When the Kafka circuit opens and for how long
+#[derive(Debug, Clone)]
+pub struct BreakerConfig {
+    /// Consecutive failed sends that open the circuit
+    pub failure_threshold: u32,
+    /// How long the circuit stays open the first time (doubles on every
+    /// failed probe)
+    pub open_for: Duration,
+    /// Cap on the doubling
+    pub max_open_for: Duration,
+}
+
+impl BreakerConfig {
+    /// Read KAFKA_BREAKER_FAILURES, KAFKA_BREAKER_OPEN_MS and
+    /// KAFKA_BREAKER_MAX_OPEN_MS, falling back to defaults
+    pub fn from_env() -> Self {
+        fn env_or<T: std::str::FromStr>(name: &str, default: T) -> T {
+            std::env::var(name)
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(default)
+        }
+
+        let default = Self::default();
+        Self {
+            failure_threshold: env_or("KAFKA_BREAKER_FAILURES", default.failure_threshold).max(1),
+            open_for: Duration::from_millis(env_or("KAFKA_BREAKER_OPEN_MS", 1000)),
+            max_open_for: Duration::from_millis(env_or("KAFKA_BREAKER_MAX_OPEN_MS", 30_000)),
+        }
+    }
+}
+
+impl Default for BreakerConfig {
+    /// A failed send costs the full 5s delivery timeout, so one is enough
+    fn default() -> Self {
+        Self {
+            failure_threshold: 1,
+            open_for: Duration::from_secs(1),
+            max_open_for: Duration::from_secs(30),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CircuitState {
+    /// Sends go through
+    Closed,
+    /// Sends fail fast until the cooldown ends
+    Open,
+    /// One probe send is in flight; everything else still fails fast
+    HalfOpen,
+}
+
+/// Snapshot of the breaker (shown on /health/degradation and
+/// /admin/kafka/producer)
+#[derive(Debug, Clone, Serialize)]
+pub struct BreakerStatus {
+    pub state: CircuitState,
+    /// False while a send would be refused
+    pub available: bool,
+    pub consecutive_failures: u32,
+    pub retry_in_secs: u64,
+}
+
+/// Circuit breaker around Kafka sends
+///
+/// Why?
+/// - A send to an unhealthy broker waits the full 5s delivery timeout;
+///   without a breaker every API call pays that during an outage
+/// - Once open, calls fail at once (EventBusUnavailable -> 503, or the event
+///   is spilled to the outbox) instead of piling up in the producer
+///
+/// How it works:
+/// 1. Closed: `failure_threshold` consecutive failures open it
+/// 2. Open: every call is refused for `open_for` (1s, 2s, 4s ... capped at
+///    `max_open_for` as probes keep failing)
+/// 3. Half-open: the first call after the cooldown is the probe - only one,
+///    so a recovering broker isn't hit by every waiting request at once
+/// 4. The probe succeeding closes the circuit; failing reopens it for longer
+///
+/// A probe whose caller gives up (the request is dropped) just frees the
+/// slot for the next call.
+pub struct CircuitBreaker {
+    config: BreakerConfig,
+    inner: Mutex<BreakerInner>,
+}
+
+#[derive(Debug)]
+struct BreakerInner {
+    state: CircuitState,
+    failures: u32,
+    /// Times opened in a row (drives the cooldown doubling)
+    opened: u32,
+    retry_at: Option<Instant>,
+}
+
+/// Permission to make one call; report how it went with `succeeded` or
+/// `failed`
+pub struct CallPermit<'a> {
+    breaker: &'a CircuitBreaker,
+    probe: bool,
+    reported: bool,
+}
+
+impl CircuitBreaker {
+    pub fn new(config: BreakerConfig) -> Self {
+        Self {
+            config,
+            inner: Mutex::new(BreakerInner {
+                state: CircuitState::Closed,
+                failures: 0,
+                opened: 0,
+                retry_at: None,
+            }),
+        }
+    }
+
+    /// May a call go through? Err carries how long until it might
+    pub fn try_acquire(&self) -> Result<CallPermit<'_>, Duration> {
+        let mut inner = self.inner.lock().unwrap_or_else(|e| e.into_inner());
+        let now = Instant::now();
+
+        let probe = match inner.state {
+            CircuitState::Closed => false,
+            CircuitState::Open => {
+                let retry_at = inner.retry_at.unwrap_or(now);
+                if now < retry_at {
+                    return Err(retry_at - now);
+                }
+                inner.state = CircuitState::HalfOpen;
+                true
+            }
+            // The probe hasn't come back yet
+            CircuitState::HalfOpen => return Err(self.config.open_for),
+        };
+
+        Ok(CallPermit {
+            breaker: self,
+            probe,
+            reported: false,
+        })
+    }
+
+    pub fn status(&self) -> BreakerStatus {
+        let inner = self.inner.lock().unwrap_or_else(|e| e.into_inner());
+        let retry_in = match inner.state {
+            CircuitState::Closed => Duration::ZERO,
+            CircuitState::Open => inner
+                .retry_at
+                .map(|at| at.saturating_duration_since(Instant::now()))
+                .unwrap_or_default(),
+            CircuitState::HalfOpen => self.config.open_for,
+        };
+
+        BreakerStatus {
+            state: inner.state,
+            available: retry_in.is_zero(),
+            consecutive_failures: inner.failures,
+            retry_in_secs: retry_in.as_secs_f64().ceil() as u64,
+        }
+    }
+
+    fn record(&self, succeeded: bool, probe: bool) {
+        let mut inner = self.inner.lock().unwrap_or_else(|e| e.into_inner());
+
+        if succeeded {
+            if inner.failures > 0 {
+                tracing::info!(failures = inner.failures, "Kafka reachable again, circuit closed");
+            }
+            inner.state = CircuitState::Closed;
+            inner.failures = 0;
+            inner.opened = 0;
+            inner.retry_at = None;
+            return;
+        }
+
+        inner.failures += 1;
+        // Calls started before the circuit opened may still fail afterwards;
+        // only the probe (or crossing the threshold) reopens it
+        let open = match inner.state {
+            CircuitState::Closed => inner.failures >= self.config.failure_threshold,
+            CircuitState::HalfOpen => probe,
+            CircuitState::Open => false,
+        };
+        if open {
+            inner.opened += 1;
+            let cooldown = self
+                .config
+                .open_for
+                .saturating_mul(1 << (inner.opened - 1).min(16))
+                .min(self.config.max_open_for);
+            inner.state = CircuitState::Open;
+            inner.retry_at = Some(Instant::now() + cooldown);
+            tracing::warn!(
+                failures = inner.failures,
+                open_for_ms = cooldown.as_millis() as u64,
+                "Kafka circuit opened"
+            );
+        }
+    }
+
+    /// A probe that never reported: let the next call probe instead
+    fn release_probe(&self) {
+        let mut inner = self.inner.lock().unwrap_or_else(|e| e.into_inner());
+        if inner.state == CircuitState::HalfOpen {
+            inner.state = CircuitState::Open;
+            inner.retry_at = Some(Instant::now());
+        }
+    }
+}
+
+impl CallPermit<'_> {
+    pub fn succeeded(mut self) {
+        self.reported = true;
+        self.breaker.record(true, self.probe);
+    }
+
+    pub fn failed(mut self) {
+        self.reported = true;
+        self.breaker.record(false, self.probe);
+    }
+}
+
+impl Drop for CallPermit<'_> {
+    fn drop(&mut self) {
+        if self.probe && !self.reported {
+            self.breaker.release_probe();
+        }
+    }
+}
@@ -0,0 +1,98 @@
+use crate::avro::{confluent_frame, Schema, WALLET_EVENT_SCHEMA};
+use crate::errors::{WalletError, WalletResult};
+use crate::protobuf::{ProtoSchema, WALLET_EVENT_PROTO};
+use crate::schema_registry::SchemaRegistryClient;
+use serde_json::Value;
+
+/// How events are encoded on the wallet-events topic
+///
+/// - Json: the default, readable with kafka-console-consumer (local dev)
+/// - Avro: Confluent wire format with the schema in Schema Registry
+/// - Protobuf: proto/wallet_event.proto, no registry needed (about half
+///   the size of JSON, and any language can generate types from the .proto)
+///
+/// Events are always built (and stored in the outbox) as JSON; the codec
+/// converts at the moment they're sent. history-service detects the format
+/// per message, so switching codecs needs no consumer redeploy.
+pub enum EventCodec {
+    Json,
+    Avro { schema: Schema, schema_id: u32 },
+    Protobuf(ProtoSchema),
+}
+
+impl EventCodec {
+    /// Configure from EVENT_CODEC (json | avro | protobuf)
+    ///
+    /// Avro needs SCHEMA_REGISTRY_URL. At startup the schema is registered
+    /// under `{topic}-value` with SCHEMA_COMPATIBILITY (default BACKWARD),
+    /// so an incompatible schema change fails here instead of in consumers.
+    pub async fn from_env(topic: &str) -> WalletResult<Self> {
+        match std::env::var("EVENT_CODEC").as_deref() {
+            Ok("avro") => {
+                let url = std::env::var("SCHEMA_REGISTRY_URL").map_err(|_| {
+                    WalletError::InternalError("EVENT_CODEC=avro needs SCHEMA_REGISTRY_URL".to_string())
+                })?;
+                let compatibility = std::env::var("SCHEMA_COMPATIBILITY")
+                    .unwrap_or_else(|_| "BACKWARD".to_string());
+
+                let registry = SchemaRegistryClient::new(&url)?;
+                let subject = format!("{}-value", topic);
+                registry.set_compatibility(&subject, &compatibility).await?;
+                let schema_id = registry.register(&subject, WALLET_EVENT_SCHEMA).await?;
+
+                tracing::info!(
+                    subject = %subject,
+                    schema_id = schema_id,
+                    compatibility = %compatibility,
+                    "Registered Avro schema"
+                );
+                Self::avro(schema_id)
+            }
+            Ok("protobuf") => Self::protobuf(),
+            Ok("json") | Err(_) => Ok(EventCodec::Json),
+            Ok(other) => Err(WalletError::InternalError(format!(
+                "Unknown EVENT_CODEC '{}' (expected json, avro or protobuf)",
+                other
+            ))),
+        }
+    }
+
+    /// Avro with the bundled schema, registered under `schema_id`
+    pub fn avro(schema_id: u32) -> WalletResult<Self> {
+        Ok(EventCodec::Avro {
+            schema: Schema::parse(WALLET_EVENT_SCHEMA)?,
+            schema_id,
+        })
+    }
+
+    /// Protobuf with the bundled .proto
+    pub fn protobuf() -> WalletResult<Self> {
+        Ok(EventCodec::Protobuf(ProtoSchema::parse(WALLET_EVENT_PROTO)?))
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            EventCodec::Json => "json",
+            EventCodec::Avro { .. } => "avro",
+            EventCodec::Protobuf(_) => "protobuf",
+        }
+    }
+
+    /// Encode a serialized (JSON) event for the wire
+    pub fn encode(&self, json_payload: &str) -> WalletResult<Vec<u8>> {
+        match self {
+            EventCodec::Json => Ok(json_payload.as_bytes().to_vec()),
+            EventCodec::Avro { schema, schema_id } => {
+                let mut body = Vec::new();
+                schema.encode(&parse_event(json_payload)?, &mut body)?;
+                Ok(confluent_frame(*schema_id, &body))
+            }
+            EventCodec::Protobuf(schema) => schema.encode(&parse_event(json_payload)?),
+        }
+    }
+}
+
+fn parse_event(json_payload: &str) -> WalletResult<Value> {
+    serde_json::from_str(json_payload)
+        .map_err(|e| WalletError::InternalError(format!("Failed to parse event: {}", e)))
+}
@@ -0,0 +1,152 @@
+use crate::kafka::EventIds;
+use axum::{
+    extract::Request,
+    http::HeaderValue,
+    middleware::Next,
+    response::Response,
+};
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+use tracing::Instrument;
+use uuid::Uuid;
+
+/// Request header carrying the correlation ID (echoed on the response)
+pub const CORRELATION_HEADER: &str = "x-correlation-id";
+
+/// Request header carrying the request ID (echoed on the response)
+pub const REQUEST_ID_HEADER: &str = "x-request-id";
+
+/// Longest client-supplied ID we accept; longer ones are replaced
+const MAX_CORRELATION_ID_LEN: usize = 128;
+
+/// The user action the events being published belong to
+///
+/// - correlation_id: the same on every event of one action (one request)
+/// - causation_id: what caused this event - the previous event of the
+///   action, or the request itself (its correlation ID) for the first
+/// - request_id: the HTTP request, as the gateway and our logs know it
+///
+/// So a transfer with a round-up reads: request -> TRANSFER_COMPLETED ->
+/// ROUND_UP_APPLIED, all under one correlation ID.
+#[derive(Debug)]
+struct Action {
+    correlation_id: String,
+    request_id: Option<String>,
+    last_event_id: Mutex<Option<String>>,
+}
+
+tokio::task_local! {
+    static CURRENT: Arc<Action>;
+}
+
+/// Run `f` as one action: events it publishes share `correlation_id`
+pub async fn scope<F: Future>(
+    correlation_id: String,
+    request_id: Option<String>,
+    f: F,
+) -> F::Output {
+    let action = Arc::new(Action {
+        correlation_id,
+        request_id,
+        last_event_id: Mutex::new(None),
+    });
+    CURRENT.scope(action, f).await
+}
+
+/// The current request's ID (None outside a request)
+pub fn current_request_id() -> Option<String> {
+    CURRENT
+        .try_with(|action| action.request_id.clone())
+        .ok()
+        .flatten()
+}
+
+/// `f` in the current action and tracing span, for handing to another task
+///
+/// Spawned tasks don't inherit either, so without this their events would
+/// start new actions and their logs would lose the request ID.
+pub fn carry_over<F: Future>(f: F) -> impl Future<Output = F::Output> {
+    let action = CURRENT.try_with(Arc::clone).ok();
+    let span = tracing::Span::current();
+
+    async move {
+        match action {
+            Some(action) => CURRENT.scope(action, f).instrument(span).await,
+            None => f.instrument(span).await,
+        }
+    }
+}
+
+/// IDs for the next event published in the current action
+///
+/// Outside any action (background jobs) the event starts its own.
+pub fn next_event_ids() -> EventIds {
+    let event_id = Uuid::new_v4().to_string();
+
+    CURRENT
+        .try_with(|action| {
+            let mut last = action
+                .last_event_id
+                .lock()
+                .unwrap_or_else(|e| e.into_inner());
+            let causation_id = last
+                .replace(event_id.clone())
+                .unwrap_or_else(|| action.correlation_id.clone());
+
+            EventIds {
+                event_id: event_id.clone(),
+                correlation_id: action.correlation_id.clone(),
+                causation_id,
+                request_id: action.request_id.clone(),
+            }
+        })
+        .unwrap_or_else(|_| EventIds {
+            event_id: event_id.clone(),
+            correlation_id: event_id.clone(),
+            causation_id: event_id.clone(),
+            request_id: None,
+        })
+}
+
+/// Middleware: every request is an action, correlated by X-Correlation-ID
+/// and identified by X-Request-ID
+///
+/// Clients (or the gateway) may send their own IDs to tie our events to
+/// their logs; otherwise they're generated. Either way they're returned.
+/// The request ID is on every log line of the request (its span), in error
+/// bodies and on the events it publishes.
+pub async fn with_correlation(request: Request, next: Next) -> Response {
+    let correlation_id = client_id(&request, CORRELATION_HEADER);
+    let request_id = client_id(&request, REQUEST_ID_HEADER);
+
+    let span = tracing::info_span!(
+        "request",
+        request_id = %request_id,
+        correlation_id = %correlation_id,
+    );
+    let mut response = scope(
+        correlation_id.clone(),
+        Some(request_id.clone()),
+        next.run(request),
+    )
+    .instrument(span)
+    .await;
+
+    for (header, id) in [(CORRELATION_HEADER, correlation_id), (REQUEST_ID_HEADER, request_id)] {
+        if let Ok(value) = HeaderValue::from_str(&id) {
+            response.headers_mut().insert(header, value);
+        }
+    }
+    response
+}
+
+/// The client's ID from `header` if it's usable, else a new one
+fn client_id(request: &Request, header: &str) -> String {
+    request
+        .headers()
+        .get(header)
+        .and_then(|value| value.to_str().ok())
+        .filter(|id| !id.is_empty() && id.len() <= MAX_CORRELATION_ID_LEN)
+        .map(str::to_string)
+        .unwrap_or_else(|| Uuid::new_v4().to_string())
+}
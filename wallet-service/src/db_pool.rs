@@ -0,0 +1,159 @@
+use sqlx::postgres::{PgConnectOptions, PgPoolOptions};
+use sqlx::PgPool;
+use std::fmt::Write;
+use std::str::FromStr;
+use std::time::Duration;
+
+/// Connection pool sizing and timeouts
+///
+/// Two pools on the primary: mutations (and the reads they make) use the
+/// mutation pool (DATABASE_*), read-only queries the query pool
+/// (DATABASE_QUERY_*, each falling back to its DATABASE_* value). Slow
+/// listings and histories then wait on their own connections, never on
+/// the ones transfers need. The read replica gets the query pool's
+/// settings.
+///
+/// Why configurable?
+/// - The right size depends on the instance count and Postgres'
+///   max_connections: N instances x (both maxima) must fit under it
+/// - The `wallet_db_pool_*` gauges on /metrics show whether each pool
+///   waits for a connection (in use = size = max) or sits idle
+#[derive(Debug, Clone)]
+pub struct PoolConfig {
+    /// DATABASE_MAX_CONNECTIONS (default 10)
+    pub max_connections: u32,
+    /// DATABASE_MIN_CONNECTIONS - kept open even when idle (default 0)
+    pub min_connections: u32,
+    /// DATABASE_ACQUIRE_TIMEOUT_SECS - how long a query waits for a free
+    /// connection before failing (default 30)
+    pub acquire_timeout: Duration,
+    /// DATABASE_STATEMENT_TIMEOUT_MS - Postgres cancels statements running
+    /// longer (default: none)
+    pub statement_timeout: Option<Duration>,
+}
+
+impl Default for PoolConfig {
+    fn default() -> Self {
+        Self {
+            max_connections: 10,
+            min_connections: 0,
+            acquire_timeout: Duration::from_secs(30),
+            statement_timeout: None,
+        }
+    }
+}
+
+impl PoolConfig {
+    /// The mutation pool's settings (DATABASE_*)
+    pub fn from_env() -> Self {
+        Self::from_env_prefixed("DATABASE", &Self::default())
+    }
+
+    /// The query pool's settings: DATABASE_QUERY_* where set, else the same
+    /// as `self` (the mutation pool's)
+    pub fn queries_from_env(&self) -> Self {
+        Self::from_env_prefixed("DATABASE_QUERY", self)
+    }
+
+    fn from_env_prefixed(prefix: &str, defaults: &Self) -> Self {
+        fn env_or<T: std::str::FromStr>(name: &str, default: T) -> T {
+            std::env::var(name)
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(default)
+        }
+        let var = |name: &str| format!("{}_{}", prefix, name);
+
+        let max_connections = env_or(&var("MAX_CONNECTIONS"), defaults.max_connections).max(1);
+        // 0 turns the timeout off, even when the defaults have one
+        let statement_timeout_ms = env_or(
+            &var("STATEMENT_TIMEOUT_MS"),
+            defaults.statement_timeout.map_or(0, |t| t.as_millis() as u64),
+        );
+        Self {
+            max_connections,
+            min_connections: env_or(&var("MIN_CONNECTIONS"), defaults.min_connections)
+                .min(max_connections),
+            acquire_timeout: Duration::from_secs(
+                env_or(&var("ACQUIRE_TIMEOUT_SECS"), defaults.acquire_timeout.as_secs()).max(1),
+            ),
+            statement_timeout: Some(statement_timeout_ms)
+                .filter(|ms| *ms > 0)
+                .map(Duration::from_millis),
+        }
+    }
+
+    /// Connect a pool to `url` with these settings
+    pub async fn connect(&self, url: &str) -> Result<PgPool, sqlx::Error> {
+        let mut options = PgConnectOptions::from_str(url)?;
+        if let Some(timeout) = self.statement_timeout {
+            // Set on every connection at startup, so it also covers the
+            // connections opened later
+            options = options.options([("statement_timeout", timeout.as_millis().to_string())]);
+        }
+
+        PgPoolOptions::new()
+            .max_connections(self.max_connections)
+            .min_connections(self.min_connections)
+            .acquire_timeout(self.acquire_timeout)
+            .connect_with(options)
+            .await
+    }
+}
+
+/// One pool's connections at a moment
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PoolStats {
+    /// "primary" (mutations), "queries" or "replica"
+    pub pool: &'static str,
+    pub size: u32,
+    pub idle: u32,
+    pub max: u32,
+}
+
+impl PoolStats {
+    pub fn of(pool: &'static str, pg: &PgPool) -> Self {
+        let size = pg.size();
+        Self {
+            pool,
+            size,
+            idle: (pg.num_idle() as u32).min(size),
+            max: pg.options().get_max_connections(),
+        }
+    }
+
+    /// Connections checked out by queries right now
+    pub fn in_use(&self) -> u32 {
+        self.size - self.idle
+    }
+}
+
+/// Render the pool gauges in the OpenMetrics text format (no "# EOF" -
+/// they come before the business counters)
+pub fn render_metrics(pools: &[PoolStats]) -> String {
+    let mut out = String::new();
+
+    write_gauges(&mut out, "wallet_db_pool_connections", "Open connections", pools, |p| p.size);
+    write_gauges(&mut out, "wallet_db_pool_idle", "Open connections not in use", pools, |p| p.idle);
+    write_gauges(&mut out, "wallet_db_pool_in_use", "Connections running queries", pools, |p| {
+        p.in_use()
+    });
+    write_gauges(&mut out, "wallet_db_pool_max", "Most connections the pool may open", pools, |p| p.max);
+
+    out
+}
+
+/// One gauge family with a sample per pool
+fn write_gauges(
+    out: &mut String,
+    name: &str,
+    help: &str,
+    pools: &[PoolStats],
+    value: impl Fn(&PoolStats) -> u32,
+) {
+    let _ = writeln!(out, "# TYPE {} gauge", name);
+    let _ = writeln!(out, "# HELP {} {}", name, help);
+    for pool in pools {
+        let _ = writeln!(out, "{}{{pool=\"{}\"}} {}", name, pool.pool, value(pool));
+    }
+}
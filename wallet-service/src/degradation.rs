@@ -0,0 +1,231 @@
+use crate::errors::ErrorResponse;
+use crate::kafka::KafkaProducer;
+use crate::repository::WalletRepository;
+use axum::{
+    extract::{Request, State},
+    http::{header, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+    Json,
+};
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use std::fmt::Write;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, Instant};
+
+/// Thresholds for switching into degraded mode
+#[derive(Debug, Clone)]
+pub struct DegradationConfig {
+    /// Degrade when a `SELECT 1` takes longer than this (or fails)
+    pub db_latency_threshold: Duration,
+    /// Degrade when this many events are waiting for Kafka acknowledgment
+    pub kafka_in_flight_threshold: i32,
+    /// How often the probe runs (also the Retry-After we send)
+    pub probe_interval: Duration,
+}
+
+impl DegradationConfig {
+    /// Read DEGRADE_DB_LATENCY_MS, DEGRADE_KAFKA_IN_FLIGHT and
+    /// DEGRADE_PROBE_INTERVAL_SECS, falling back to defaults
+    pub fn from_env() -> Self {
+        fn env_or<T: std::str::FromStr>(name: &str, default: T) -> T {
+            std::env::var(name)
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(default)
+        }
+
+        Self {
+            db_latency_threshold: Duration::from_millis(env_or("DEGRADE_DB_LATENCY_MS", 250)),
+            kafka_in_flight_threshold: env_or("DEGRADE_KAFKA_IN_FLIGHT", 1000),
+            probe_interval: Duration::from_secs(env_or("DEGRADE_PROBE_INTERVAL_SECS", 5)),
+        }
+    }
+}
+
+/// Snapshot of the controller's state (shown on /health/degradation)
+#[derive(Debug, Clone, Serialize)]
+pub struct DegradationStatus {
+    pub degraded: bool,
+    pub reasons: Vec<String>,
+    pub db_latency_ms: Option<u64>, // None = probe failed or timed out
+    pub kafka_in_flight: i32,
+    pub since: Option<DateTime<Utc>>,
+    pub checked_at: Option<DateTime<Utc>>,
+}
+
+/// Priority-based graceful degradation
+///
+/// Why?
+/// - When Postgres is slow or Kafka is backing up, every extra query makes it worse
+/// - Money movement and balance reads matter most - keep those serving
+/// - Everything else (admin views, aggregates, configuration) can wait
+///
+/// How it works:
+/// 1. A background probe times `SELECT 1` and reads the producer's in-flight count
+/// 2. Crossing EITHER threshold switches degraded mode on
+/// 3. Non-essential routes answer 503 + Retry-After while degraded
+/// 4. The next probe with both signals healthy switches it off again
+///
+/// Kafka "lag" on the producer side = events queued but not yet acknowledged.
+pub struct DegradationController {
+    config: DegradationConfig,
+    degraded: AtomicBool,
+    status: RwLock<DegradationStatus>,
+    shed_requests: AtomicU64,
+}
+
+impl DegradationController {
+    pub fn new(config: DegradationConfig) -> Self {
+        Self {
+            config,
+            degraded: AtomicBool::new(false),
+            status: RwLock::new(DegradationStatus {
+                degraded: false,
+                reasons: Vec::new(),
+                db_latency_ms: None,
+                kafka_in_flight: 0,
+                since: None,
+                checked_at: None,
+            }),
+            shed_requests: AtomicU64::new(0),
+        }
+    }
+
+    /// Apply one probe result
+    ///
+    /// `db_latency` is None when the database probe failed or timed out.
+    pub fn evaluate(&self, db_latency: Option<Duration>, kafka_in_flight: i32) {
+        let mut reasons = Vec::new();
+
+        match db_latency {
+            None => reasons.push("database probe failed".to_string()),
+            Some(latency) if latency > self.config.db_latency_threshold => reasons.push(format!(
+                "database latency {}ms over {}ms",
+                latency.as_millis(),
+                self.config.db_latency_threshold.as_millis()
+            )),
+            Some(_) => {}
+        }
+
+        if kafka_in_flight > self.config.kafka_in_flight_threshold {
+            reasons.push(format!(
+                "{} events awaiting Kafka acknowledgment (limit {})",
+                kafka_in_flight, self.config.kafka_in_flight_threshold
+            ));
+        }
+
+        let degraded = !reasons.is_empty();
+        let was_degraded = self.degraded.swap(degraded, Ordering::Relaxed);
+
+        if degraded && !was_degraded {
+            tracing::warn!(reasons = ?reasons, "Entering degraded mode");
+        } else if !degraded && was_degraded {
+            tracing::info!("Leaving degraded mode");
+        }
+
+        let mut status = self.status.write().unwrap_or_else(|e| e.into_inner());
+        let now = Utc::now();
+        status.since = match (degraded, was_degraded) {
+            (true, false) => Some(now),
+            (true, true) => status.since,
+            (false, _) => None,
+        };
+        status.degraded = degraded;
+        status.reasons = reasons;
+        status.db_latency_ms = db_latency.map(|l| l.as_millis() as u64);
+        status.kafka_in_flight = kafka_in_flight;
+        status.checked_at = Some(now);
+    }
+
+    pub fn is_degraded(&self) -> bool {
+        self.degraded.load(Ordering::Relaxed)
+    }
+
+    pub fn status(&self) -> DegradationStatus {
+        self.status
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone()
+    }
+
+    /// Seconds a shed client should wait (one probe interval)
+    pub fn retry_after_secs(&self) -> u64 {
+        self.config.probe_interval.as_secs().max(1)
+    }
+
+    /// Run the probe forever in a background task
+    pub fn spawn_probe(self: Arc<Self>, repository: WalletRepository, kafka: Arc<KafkaProducer>) {
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(self.config.probe_interval);
+
+            loop {
+                interval.tick().await;
+
+                // A probe slower than the interval counts as failed
+                let started = Instant::now();
+                let db_latency =
+                    match tokio::time::timeout(self.config.probe_interval, repository.ping()).await {
+                        Ok(Ok(())) => Some(started.elapsed()),
+                        _ => None,
+                    };
+
+                self.evaluate(db_latency, kafka.in_flight_count());
+            }
+        });
+    }
+
+    /// Degradation gauges in the OpenMetrics text format (no # EOF)
+    pub fn render_metrics(&self) -> String {
+        let status = self.status();
+        let mut out = String::new();
+
+        let _ = writeln!(out, "# TYPE wallet_degraded gauge");
+        let _ = writeln!(out, "# HELP wallet_degraded 1 while non-essential endpoints are shed");
+        let _ = writeln!(out, "wallet_degraded {}", status.degraded as u8);
+        let _ = writeln!(out, "# TYPE wallet_db_probe_latency_ms gauge");
+        let _ = writeln!(out, "# HELP wallet_db_probe_latency_ms Last database probe latency (-1 = failed)");
+        let _ = writeln!(
+            out,
+            "wallet_db_probe_latency_ms {}",
+            status.db_latency_ms.map(|l| l as i64).unwrap_or(-1)
+        );
+        let _ = writeln!(out, "# TYPE wallet_kafka_in_flight gauge");
+        let _ = writeln!(out, "# HELP wallet_kafka_in_flight Events awaiting Kafka acknowledgment");
+        let _ = writeln!(out, "wallet_kafka_in_flight {}", status.kafka_in_flight);
+        let _ = writeln!(out, "# TYPE wallet_shed_requests counter");
+        let _ = writeln!(out, "# HELP wallet_shed_requests Requests rejected while degraded");
+        let _ = writeln!(
+            out,
+            "wallet_shed_requests_total {}",
+            self.shed_requests.load(Ordering::Relaxed)
+        );
+
+        out
+    }
+}
+
+/// Middleware for non-essential routes: 503 + Retry-After while degraded
+pub async fn shed_when_degraded(
+    State(controller): State<Arc<DegradationController>>,
+    request: Request,
+    next: Next,
+) -> Response {
+    if !controller.is_degraded() {
+        return next.run(request).await;
+    }
+
+    controller.shed_requests.fetch_add(1, Ordering::Relaxed);
+    tracing::debug!(path = %request.uri().path(), "Shedding non-essential request");
+
+    (
+        StatusCode::SERVICE_UNAVAILABLE,
+        [(header::RETRY_AFTER, controller.retry_after_secs().to_string())],
+        Json(ErrorResponse::new(
+            "Service is degraded; this endpoint is temporarily unavailable",
+        )),
+    )
+        .into_response()
+}
@@ -0,0 +1,256 @@
+use crate::errors::{WalletError, WalletResult};
+use crate::models::{
+    Beneficiary, PendingTransfer, TransactionNote, TransferTemplate, Wallet, WalletTransaction,
+};
+use crate::signing::hmac_sha256;
+use aes_gcm::aead::{Aead, KeyInit};
+use aes_gcm::{Aes256Gcm, Key, Nonce};
+use hmac::Mac;
+use rand::RngCore;
+use sha2::{Digest, Sha256};
+use std::sync::Arc;
+
+/// What every encrypted value starts with: `enc:<key id>:<hex nonce+ciphertext>`
+const PREFIX: &str = "enc:";
+const NONCE_LEN: usize = 12;
+
+/// Encrypts free-text columns (memos, notes, nicknames, labels, beneficiary
+/// names) before they reach the database
+///
+/// Why in the application rather than the database (pgcrypto, TDE)?
+/// - A dump, a replica or a backup only ever holds ciphertext; the keys
+///   live with the service, not next to the data
+/// - The columns are narratives users typed - we never filter or sort by
+///   them in SQL, so nothing is lost by the database not reading them
+///
+/// Key rotation:
+/// - FIELD_ENCRYPTION_KEYS is `id:hex,id:hex,...`; the first key encrypts,
+///   all of them decrypt, and each value records which key sealed it
+/// - After putting a new key first, `rekey-fields` rewrites the old values
+///   so the retired key can be dropped
+///
+/// Values written before encryption was turned on have no `enc:` prefix
+/// and are read as they are (the rekey pass seals them too). Without keys
+/// the cipher passes everything through.
+#[derive(Clone, Default)]
+pub struct FieldCipher {
+    inner: Option<Arc<Keyring>>,
+}
+
+struct Keyring {
+    /// Seals new values; always `keys[0]`
+    current: String,
+    keys: Vec<(String, Aes256Gcm)>,
+    /// Keys `blind_index` (FIELD_ENCRYPTION_INDEX_KEY)
+    index_key: Vec<u8>,
+}
+
+impl FieldCipher {
+    /// FIELD_ENCRYPTION_KEYS (unset: no encryption) and
+    /// FIELD_ENCRYPTION_INDEX_KEY (required with it), both 32 bytes as hex
+    pub fn from_env() -> Result<Self, String> {
+        fn var(name: &str) -> Option<String> {
+            std::env::var(name).ok().filter(|v| !v.is_empty())
+        }
+
+        let Some(keys) = var("FIELD_ENCRYPTION_KEYS") else {
+            return Ok(Self::default());
+        };
+        let keys = keys
+            .split(',')
+            .map(str::trim)
+            .filter(|entry| !entry.is_empty())
+            .map(|entry| {
+                let (id, hex) = entry.split_once(':').ok_or_else(|| {
+                    format!("FIELD_ENCRYPTION_KEYS entry '{}' is not id:hex", entry)
+                })?;
+                Ok((id.to_string(), decode_key(hex, "FIELD_ENCRYPTION_KEYS")?))
+            })
+            .collect::<Result<Vec<_>, String>>()?;
+        let index_key = var("FIELD_ENCRYPTION_INDEX_KEY")
+            .ok_or("FIELD_ENCRYPTION_INDEX_KEY must be set with FIELD_ENCRYPTION_KEYS")?;
+
+        Self::new(keys, decode_key(&index_key, "FIELD_ENCRYPTION_INDEX_KEY")?.to_vec())
+    }
+
+    /// `keys` newest first: the first one encrypts
+    pub fn new(keys: Vec<(String, [u8; 32])>, index_key: Vec<u8>) -> Result<Self, String> {
+        if keys.is_empty() {
+            return Err("At least one field encryption key is needed".to_string());
+        }
+        // Key IDs end up in stored values and in LIKE patterns
+        let valid_id = |id: &str| {
+            !id.is_empty() && id.chars().all(|c| c.is_ascii_alphanumeric() || c == '-')
+        };
+        if let Some((id, _)) = keys.iter().find(|(id, _)| !valid_id(id)) {
+            return Err(format!(
+                "Field encryption key id '{}' must be letters, digits and '-'",
+                id
+            ));
+        }
+
+        Ok(Self {
+            inner: Some(Arc::new(Keyring {
+                current: keys[0].0.clone(),
+                keys: keys
+                    .into_iter()
+                    .map(|(id, key)| (id, Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(&key))))
+                    .collect(),
+                index_key,
+            })),
+        })
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.inner.is_some()
+    }
+
+    /// ID of the key new values are sealed with
+    pub fn current_key_id(&self) -> Option<&str> {
+        self.inner.as_deref().map(|keyring| keyring.current.as_str())
+    }
+
+    /// Encrypt a value with the current key (a fresh nonce every time)
+    pub fn seal(&self, plaintext: &str) -> WalletResult<String> {
+        let Some(keyring) = self.inner.as_deref() else {
+            return Ok(plaintext.to_string());
+        };
+        let (id, cipher) = &keyring.keys[0];
+
+        let mut nonce = [0u8; NONCE_LEN];
+        rand::thread_rng().fill_bytes(&mut nonce);
+        let ciphertext = cipher
+            .encrypt(Nonce::from_slice(&nonce), plaintext.as_bytes())
+            .map_err(|_| WalletError::InternalError("Field encryption failed".to_string()))?;
+
+        let mut sealed = nonce.to_vec();
+        sealed.extend_from_slice(&ciphertext);
+        Ok(format!("{}{}:{}", PREFIX, id, hex::encode(&sealed)))
+    }
+
+    pub fn seal_opt(&self, plaintext: Option<&str>) -> WalletResult<Option<String>> {
+        plaintext.map(|value| self.seal(value)).transpose()
+    }
+
+    /// Decrypt a stored value; plaintext written before encryption passes through
+    pub fn open(&self, stored: &str) -> WalletResult<String> {
+        let Some(sealed) = stored.strip_prefix(PREFIX) else {
+            return Ok(stored.to_string());
+        };
+        let unreadable =
+            |why: &str| WalletError::InternalError(format!("Can't decrypt field: {}", why));
+
+        let keyring = self.inner.as_deref().ok_or_else(|| unreadable("no keys configured"))?;
+        let (id, hex) = sealed.split_once(':').ok_or_else(|| unreadable("malformed value"))?;
+        let (_, cipher) = keyring
+            .keys
+            .iter()
+            .find(|(key_id, _)| key_id == id)
+            .ok_or_else(|| unreadable(&format!("unknown key '{}'", id)))?;
+        let bytes = hex::decode(hex).map_err(|_| unreadable("malformed value"))?;
+        if bytes.len() < NONCE_LEN {
+            return Err(unreadable("malformed value"));
+        }
+
+        let (nonce, ciphertext) = bytes.split_at(NONCE_LEN);
+        let plaintext = cipher
+            .decrypt(Nonce::from_slice(nonce), ciphertext)
+            .map_err(|_| unreadable("authentication failed"))?;
+        String::from_utf8(plaintext).map_err(|_| unreadable("not UTF-8"))
+    }
+
+    pub fn open_opt(&self, stored: Option<String>) -> WalletResult<Option<String>> {
+        stored.map(|value| self.open(&value)).transpose()
+    }
+
+    /// Is a stored value already sealed with the current key? (plaintext is
+    /// "current" only while encryption is off)
+    pub fn is_current(&self, stored: &str) -> bool {
+        match self.current_key_id() {
+            Some(id) => stored
+                .strip_prefix(PREFIX)
+                .and_then(|sealed| sealed.split_once(':'))
+                .is_some_and(|(key_id, _)| key_id == id),
+            None => !stored.starts_with(PREFIX),
+        }
+    }
+
+    /// Deterministic stand-in for a value, so the database can still enforce
+    /// uniqueness on a column it can't read (hex HMAC-SHA256; plain SHA-256
+    /// while encryption is off, which is what migrations backfill)
+    pub fn blind_index(&self, value: &str) -> String {
+        match self.inner.as_deref() {
+            Some(keyring) => {
+                let mut mac = hmac_sha256(&keyring.index_key);
+                mac.update(value.as_bytes());
+                hex::encode(mac.finalize().into_bytes())
+            }
+            None => hex::encode(Sha256::digest(value.as_bytes())),
+        }
+    }
+}
+
+/// A row with encrypted columns, decrypted after it's read
+pub trait Sealed: Sized {
+    fn open_with(self, cipher: &FieldCipher) -> WalletResult<Self>;
+}
+
+impl Sealed for Wallet {
+    fn open_with(mut self, cipher: &FieldCipher) -> WalletResult<Self> {
+        self.nickname = cipher.open_opt(self.nickname)?;
+        self.labels = self
+            .labels
+            .iter()
+            .map(|label| cipher.open(label))
+            .collect::<WalletResult<_>>()?;
+        Ok(self)
+    }
+}
+
+impl Sealed for WalletTransaction {
+    fn open_with(mut self, cipher: &FieldCipher) -> WalletResult<Self> {
+        self.memo = cipher.open_opt(self.memo)?;
+        Ok(self)
+    }
+}
+
+impl Sealed for TransferTemplate {
+    fn open_with(mut self, cipher: &FieldCipher) -> WalletResult<Self> {
+        self.memo = cipher.open_opt(self.memo)?;
+        Ok(self)
+    }
+}
+
+impl Sealed for PendingTransfer {
+    fn open_with(mut self, cipher: &FieldCipher) -> WalletResult<Self> {
+        self.memo = cipher.open_opt(self.memo)?;
+        Ok(self)
+    }
+}
+
+impl Sealed for Beneficiary {
+    fn open_with(mut self, cipher: &FieldCipher) -> WalletResult<Self> {
+        self.name = cipher.open(&self.name)?;
+        Ok(self)
+    }
+}
+
+impl Sealed for TransactionNote {
+    fn open_with(mut self, cipher: &FieldCipher) -> WalletResult<Self> {
+        self.note = cipher.open(&self.note)?;
+        Ok(self)
+    }
+}
+
+impl<T: Sealed> Sealed for Vec<T> {
+    fn open_with(self, cipher: &FieldCipher) -> WalletResult<Self> {
+        self.into_iter().map(|row| row.open_with(cipher)).collect()
+    }
+}
+
+fn decode_key(hex: &str, name: &str) -> Result<[u8; 32], String> {
+    hex::decode(hex.trim())
+        .ok()
+        .and_then(|bytes| <[u8; 32]>::try_from(bytes).ok())
+        .ok_or_else(|| format!("{}: keys must be 32 bytes as 64 hex characters", name))
+}
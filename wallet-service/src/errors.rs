@@ -1,10 +1,12 @@
+use crate::correlation;
 use axum::{
     http::StatusCode,
     response::{IntoResponse, Response},
     Json,
 };
-use serde_json::json;
+use serde::Serialize;
 use thiserror::Error;
+use utoipa::ToSchema;
 
 /// Application-level errors
 /// 
@@ -30,25 +32,228 @@ pub enum WalletError {
     #[error("Invalid amount: {0}")]
     InvalidAmount(String),
 
+    #[error("Invalid request: {0}")]
+    InvalidRequest(String),
+
+    /// One entry per offending field (422)
+    #[error("Validation failed: {}", describe(.0))]
+    ValidationFailed(Vec<FieldError>),
+
+    #[error("Beneficiary not found: {0}")]
+    BeneficiaryNotFound(String),
+
+    #[error("Beneficiary already exists: {0}")]
+    DuplicateBeneficiary(String),
+
+    #[error("Transfer template not found: {0}")]
+    TemplateNotFound(String),
+
+    #[error("Transaction not found: {0}")]
+    TransactionNotFound(String),
+
+    #[error("No round-up rule for wallet: {0}")]
+    RoundUpRuleNotFound(String),
+
+    #[error("Pot not found: {0}")]
+    PotNotFound(String),
+
+    #[error("Voucher not found")]
+    VoucherNotFound,
+
+    #[error("Webhook not found: {0}")]
+    WebhookNotFound(String),
+
+    #[error("API key not found: {0}")]
+    ApiKeyNotFound(String),
+
+    #[error("Blocklist entry not found: {0}")]
+    BlocklistEntryNotFound(String),
+
+    /// The target already has a live entry (409)
+    #[error("Already blocklisted: {0}")]
+    DuplicateBlocklistEntry(String),
+
+    /// No wallet, archived wallet or KYC record belongs to the user
+    #[error("User not found: {0}")]
+    UserNotFound(String),
+
+    /// Erasure needs every wallet emptied first (409)
+    #[error("Wallet {0} still has a balance - withdraw or transfer it before erasure")]
+    BalanceNotZero(String),
+
+    #[error("Voucher already redeemed")]
+    VoucherAlreadyRedeemed,
+
+    #[error("No PIN set on wallet: {0}")]
+    PinNotSet(String),
+
+    /// The wallet has a PIN and the amount is over its threshold
+    #[error("This transfer needs the wallet's PIN")]
+    PinRequired,
+
+    #[error("Incorrect PIN ({attempts_left} attempts left)")]
+    IncorrectPin { attempts_left: u32 },
+
+    /// Too many wrong PINs (423)
+    #[error("PIN locked after too many wrong attempts, until {until}")]
+    PinLocked { until: chrono::DateTime<chrono::Utc> },
+
+    /// The wrong PIN or code that just started a lockout (423) - goes out
+    /// as VERIFICATION_LOCKOUT. `factor` is "pin" or "otp"; attempts
+    /// during the lockout get PinLocked / VerificationLocked.
+    #[error("Locked after too many wrong attempts, until {until}")]
+    LockedOut {
+        factor: &'static str,
+        failed_attempts: u32,
+        lockouts: u32,
+        until: chrono::DateTime<chrono::Utc>,
+    },
+
+    #[error("Pending transfer not found: {0}")]
+    PendingTransferNotFound(String),
+
+    /// The last allowed wrong code cancels the transfer (attempts_left 0)
+    #[error("Incorrect verification code ({attempts_left} attempts left)")]
+    IncorrectVerificationCode { attempts_left: u32 },
+
+    /// Too many wrong codes across the wallet's transfers (423)
+    #[error("Step-up verification locked after too many wrong codes, until {until}")]
+    VerificationLocked { until: chrono::DateTime<chrono::Utc> },
+
+    /// The code wasn't confirmed within STEP_UP_CODE_TTL_SECS (410)
+    #[error("Verification code expired - start the transfer again")]
+    VerificationExpired,
+
+    /// Already confirmed, cancelled or expired (409)
+    #[error("Pending transfer is already {0}")]
+    PendingTransferClosed(String),
+
+    /// A fraud rule rejected it (or held a funding)
+    #[error("Declined by fraud checks: {0}")]
+    FraudRejected(String),
+
+    /// The sender or recipient (wallet or user) is on the blocklist (403)
+    #[error("Transfer blocked: the {0} is blocklisted")]
+    Blocklisted(&'static str),
+
+    /// Over a KYC_UNVERIFIED_* cap - the user has to be verified first (403)
+    #[error("Identity verification required: {0}")]
+    KycLimitExceeded(String),
+
+    /// Over VELOCITY_MAX_TRANSFERS or VELOCITY_MAX_VOLUME (429)
+    #[error("Velocity limit exceeded: {measure} would reach {attempted}, limit {limit} per {window_secs}s")]
+    VelocityLimitExceeded {
+        measure: &'static str,
+        limit: rust_decimal::Decimal,
+        attempted: rust_decimal::Decimal,
+        window_secs: u64,
+    },
+
     #[error("Concurrent update detected. Please retry.")]
     OptimisticLockError,
 
+    /// If-Match named an older version than the wallet is at
+    #[error("Wallet has changed since it was read (now version {current_version})")]
+    PreconditionFailed { current_version: i64 },
+
+    /// No bearer token or API key, or one we can't verify (401)
+    #[error("Unauthorized: {0}")]
+    Unauthorized(String),
+
+    /// Authenticated, but not allowed here (403)
+    #[error("Forbidden: {0}")]
+    Forbidden(String),
+
     #[error("Database error: {0}")]
     DatabaseError(#[from] sqlx::Error),
 
     #[error("Kafka error: {0}")]
     KafkaError(String),
 
+    #[error("Kafka unavailable, retrying in {retry_after_secs}s")]
+    EventBusUnavailable { retry_after_secs: u64 },
+
     #[error("Internal server error: {0}")]
     InternalError(String),
 }
 
+impl WalletError {
+    /// Short, stable reason code (used as a metrics label)
+    pub fn reason(&self) -> &'static str {
+        match self {
+            WalletError::WalletNotFound(_) => "wallet_not_found",
+            WalletError::InsufficientBalance { .. } => "insufficient_balance",
+            WalletError::InvalidAmount(_) => "invalid_amount",
+            WalletError::InvalidRequest(_) => "invalid_request",
+            WalletError::ValidationFailed(_) => "validation_failed",
+            WalletError::BeneficiaryNotFound(_) => "beneficiary_not_found",
+            WalletError::DuplicateBeneficiary(_) => "duplicate_beneficiary",
+            WalletError::TemplateNotFound(_) => "template_not_found",
+            WalletError::TransactionNotFound(_) => "transaction_not_found",
+            WalletError::RoundUpRuleNotFound(_) => "round_up_rule_not_found",
+            WalletError::PotNotFound(_) => "pot_not_found",
+            WalletError::VoucherNotFound => "voucher_not_found",
+            WalletError::WebhookNotFound(_) => "webhook_not_found",
+            WalletError::ApiKeyNotFound(_) => "api_key_not_found",
+            WalletError::BlocklistEntryNotFound(_) => "blocklist_entry_not_found",
+            WalletError::DuplicateBlocklistEntry(_) => "duplicate_blocklist_entry",
+            WalletError::UserNotFound(_) => "user_not_found",
+            WalletError::BalanceNotZero(_) => "balance_not_zero",
+            WalletError::VoucherAlreadyRedeemed => "voucher_already_redeemed",
+            WalletError::PinNotSet(_) => "pin_not_set",
+            WalletError::PinRequired => "pin_required",
+            WalletError::IncorrectPin { .. } => "incorrect_pin",
+            WalletError::PinLocked { .. } => "pin_locked",
+            WalletError::LockedOut { factor: "pin", .. } => "pin_locked",
+            WalletError::LockedOut { .. } => "verification_locked",
+            WalletError::PendingTransferNotFound(_) => "pending_transfer_not_found",
+            WalletError::IncorrectVerificationCode { .. } => "incorrect_verification_code",
+            WalletError::VerificationLocked { .. } => "verification_locked",
+            WalletError::VerificationExpired => "verification_expired",
+            WalletError::PendingTransferClosed(_) => "pending_transfer_closed",
+            WalletError::FraudRejected(_) => "fraud_rejected",
+            WalletError::Blocklisted(_) => "blocklisted",
+            WalletError::KycLimitExceeded(_) => "kyc_limit_exceeded",
+            WalletError::VelocityLimitExceeded { .. } => "velocity_limit_exceeded",
+            WalletError::OptimisticLockError => "concurrent_update",
+            WalletError::PreconditionFailed { .. } => "precondition_failed",
+            WalletError::Unauthorized(_) => "unauthorized",
+            WalletError::Forbidden(_) => "forbidden",
+            WalletError::DatabaseError(_) => "database_error",
+            WalletError::KafkaError(_) => "kafka_error",
+            WalletError::EventBusUnavailable { .. } => "event_bus_unavailable",
+            WalletError::InternalError(_) => "internal_error",
+        }
+    }
+
+    /// Whether the operation was refused on its merits (balance, unknown
+    /// wallet, bad amount...) rather than failing on our side
+    ///
+    /// Declines are published as *_FAILED events; infrastructure failures,
+    /// auth failures and concurrent updates (stale If-Match included) are
+    /// not - the client retries those.
+    pub fn is_decline(&self) -> bool {
+        !matches!(
+            self,
+            WalletError::OptimisticLockError
+                | WalletError::PreconditionFailed { .. }
+                | WalletError::Unauthorized(_)
+                | WalletError::Forbidden(_)
+                | WalletError::DatabaseError(_)
+                | WalletError::KafkaError(_)
+                | WalletError::EventBusUnavailable { .. }
+                | WalletError::InternalError(_)
+        )
+    }
+}
+
 /// Convert WalletError to HTTP responses
 /// 
 /// This is where business errors become API responses
 /// Key insight: Not all errors are 500s!
 impl IntoResponse for WalletError {
     fn into_response(self) -> Response {
+        let mut field_errors = Vec::new();
         let (status, error_message) = match self {
             WalletError::WalletNotFound(_) => (StatusCode::NOT_FOUND, self.to_string()),
             
@@ -57,11 +262,86 @@ impl IntoResponse for WalletError {
             }
             
             WalletError::InvalidAmount(_) => (StatusCode::BAD_REQUEST, self.to_string()),
+
+            WalletError::InvalidRequest(_) => (StatusCode::BAD_REQUEST, self.to_string()),
+
+            WalletError::ValidationFailed(ref errors) => {
+                field_errors = errors.clone();
+                (StatusCode::UNPROCESSABLE_ENTITY, self.to_string())
+            }
+
+            WalletError::BeneficiaryNotFound(_) => (StatusCode::NOT_FOUND, self.to_string()),
+
+            WalletError::DuplicateBeneficiary(_) => (StatusCode::CONFLICT, self.to_string()),
+
+            WalletError::TemplateNotFound(_) => (StatusCode::NOT_FOUND, self.to_string()),
+
+            WalletError::TransactionNotFound(_) => (StatusCode::NOT_FOUND, self.to_string()),
+
+            WalletError::RoundUpRuleNotFound(_) => (StatusCode::NOT_FOUND, self.to_string()),
+
+            WalletError::PotNotFound(_) => (StatusCode::NOT_FOUND, self.to_string()),
+
+            WalletError::VoucherNotFound => (StatusCode::NOT_FOUND, self.to_string()),
+
+            WalletError::WebhookNotFound(_) => (StatusCode::NOT_FOUND, self.to_string()),
+
+            WalletError::ApiKeyNotFound(_) => (StatusCode::NOT_FOUND, self.to_string()),
+
+            WalletError::BlocklistEntryNotFound(_) => (StatusCode::NOT_FOUND, self.to_string()),
+
+            WalletError::DuplicateBlocklistEntry(_) => (StatusCode::CONFLICT, self.to_string()),
+
+            WalletError::UserNotFound(_) => (StatusCode::NOT_FOUND, self.to_string()),
+
+            WalletError::BalanceNotZero(_) => (StatusCode::CONFLICT, self.to_string()),
+
+            WalletError::VoucherAlreadyRedeemed => (StatusCode::CONFLICT, self.to_string()),
+
+            WalletError::PinNotSet(_) => (StatusCode::NOT_FOUND, self.to_string()),
+
+            WalletError::PinRequired => (StatusCode::FORBIDDEN, self.to_string()),
+
+            WalletError::IncorrectPin { .. } => (StatusCode::FORBIDDEN, self.to_string()),
+
+            WalletError::PinLocked { .. } => (StatusCode::LOCKED, self.to_string()),
+
+            WalletError::LockedOut { .. } => (StatusCode::LOCKED, self.to_string()),
+
+            WalletError::PendingTransferNotFound(_) => (StatusCode::NOT_FOUND, self.to_string()),
+
+            WalletError::IncorrectVerificationCode { .. } => {
+                (StatusCode::FORBIDDEN, self.to_string())
+            }
+
+            WalletError::VerificationLocked { .. } => (StatusCode::LOCKED, self.to_string()),
+
+            WalletError::VerificationExpired => (StatusCode::GONE, self.to_string()),
+
+            WalletError::PendingTransferClosed(_) => (StatusCode::CONFLICT, self.to_string()),
+
+            WalletError::FraudRejected(_) => (StatusCode::FORBIDDEN, self.to_string()),
+
+            WalletError::Blocklisted(_) => (StatusCode::FORBIDDEN, self.to_string()),
+
+            WalletError::KycLimitExceeded(_) => (StatusCode::FORBIDDEN, self.to_string()),
+
+            WalletError::VelocityLimitExceeded { .. } => {
+                (StatusCode::TOO_MANY_REQUESTS, self.to_string())
+            }
             
             WalletError::OptimisticLockError => {
                 (StatusCode::CONFLICT, self.to_string())
             }
+
+            WalletError::PreconditionFailed { .. } => {
+                (StatusCode::PRECONDITION_FAILED, self.to_string())
+            }
             
+            WalletError::Unauthorized(_) => (StatusCode::UNAUTHORIZED, self.to_string()),
+
+            WalletError::Forbidden(_) => (StatusCode::FORBIDDEN, self.to_string()),
+
             WalletError::DatabaseError(ref e) => {
                 tracing::error!("Database error: {:?}", e);
                 (
@@ -79,6 +359,16 @@ impl IntoResponse for WalletError {
                 )
             }
             
+            WalletError::EventBusUnavailable { .. } => {
+                // The change itself is committed - say so, so clients don't retry it
+                tracing::warn!("{}", self);
+                (
+                    StatusCode::SERVICE_UNAVAILABLE,
+                    "The change was saved, but its event could not be published (Kafka unavailable)"
+                        .to_string(),
+                )
+            }
+            
             WalletError::InternalError(ref e) => {
                 tracing::error!("Internal error: {}", e);
                 (
@@ -88,14 +378,64 @@ impl IntoResponse for WalletError {
             }
         };
 
-        let body = Json(json!({
-            "success": false,
-            "error": error_message,
-        }));
+        let body = Json(ErrorResponse {
+            errors: field_errors,
+            ..ErrorResponse::new(error_message)
+        });
 
         (status, body).into_response()
     }
 }
 
+/// Body of every error response: `{"success": false, "error": "..."}`
+///
+/// A 422 also lists the fields at fault in `errors`. `request_id` is the
+/// X-Request-ID - quote it to support.
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ErrorResponse {
+    pub success: bool,
+    pub error: String,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub errors: Vec<FieldError>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub request_id: Option<String>,
+}
+
+impl ErrorResponse {
+    /// An error body for the current request
+    pub fn new(error: impl Into<String>) -> Self {
+        Self {
+            success: false,
+            error: error.into(),
+            errors: Vec::new(),
+            request_id: correlation::current_request_id(),
+        }
+    }
+}
+
+/// One invalid field: `{"field": "amount", "message": "must be greater than 0"}`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, ToSchema)]
+pub struct FieldError {
+    pub field: String,
+    pub message: String,
+}
+
+impl FieldError {
+    pub fn new(field: impl Into<String>, message: impl Into<String>) -> Self {
+        Self {
+            field: field.into(),
+            message: message.into(),
+        }
+    }
+}
+
+fn describe(errors: &[FieldError]) -> String {
+    errors
+        .iter()
+        .map(|e| format!("{}: {}", e.field, e.message))
+        .collect::<Vec<_>>()
+        .join("; ")
+}
+
 /// Helper type for Results in this application
 pub type WalletResult<T> = Result<T, WalletError>;
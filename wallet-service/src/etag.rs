@@ -0,0 +1,59 @@
+use crate::errors::WalletError;
+use axum::{
+    extract::FromRequestParts,
+    http::{header, request::Parts, HeaderValue},
+};
+
+/// A wallet's ETag: its version, quoted (`"7"`)
+///
+/// The version goes up by one on every change - balance or metadata
+/// (nickname, labels, default) - so the tag changes exactly when the
+/// wallet does.
+pub fn wallet_etag(version: i64) -> HeaderValue {
+    HeaderValue::from_str(&format!("\"{}\"", version)).expect("a number is a valid header value")
+}
+
+/// The version an `If-Match` header asks for
+///
+/// - absent or `*` -> None (no precondition)
+/// - `"7"` -> Some(7)
+/// - anything else (weak tags, lists, garbage) -> 400; a precondition we
+///   can't evaluate must not be ignored
+pub fn parse_if_match(value: Option<&HeaderValue>) -> Result<Option<i64>, WalletError> {
+    let Some(value) = value else {
+        return Ok(None);
+    };
+    let invalid = || {
+        WalletError::InvalidRequest(
+            "If-Match must be a single ETag from GET /wallets/:id, e.g. \"7\"".to_string(),
+        )
+    };
+
+    let value = value.to_str().map_err(|_| invalid())?.trim();
+    if value == "*" {
+        return Ok(None);
+    }
+    value
+        .strip_prefix('"')
+        .and_then(|v| v.strip_suffix('"'))
+        .and_then(|v| v.parse().ok())
+        .map(Some)
+        .ok_or_else(invalid)
+}
+
+/// `If-Match` on a mutating call: the wallet version the client last saw
+///
+/// The repository checks it inside the same DB transaction as the change,
+/// so "fund only if nothing happened since I looked" is one atomic step.
+/// A stale version is a 412 and nothing is changed.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct IfMatch(pub Option<i64>);
+
+#[axum::async_trait]
+impl<S: Send + Sync> FromRequestParts<S> for IfMatch {
+    type Rejection = WalletError;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        parse_if_match(parts.headers.get(header::IF_MATCH)).map(IfMatch)
+    }
+}
@@ -0,0 +1,286 @@
+use rust_decimal::Decimal;
+use std::collections::HashSet;
+use std::str::FromStr;
+
+/// What a rule wants done with a transaction, least to most severe
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum FraudAction {
+    Allow,
+    /// Goes ahead; a FRAUD_ALERT tells the fraud team to look
+    Flag,
+    /// Transfers wait (HELD) for an admin to release or reject them;
+    /// fundings are declined - there's no money to park yet
+    Hold,
+    /// Declined outright
+    Reject,
+}
+
+impl FraudAction {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            FraudAction::Allow => "ALLOW",
+            FraudAction::Flag => "FLAG",
+            FraudAction::Hold => "HOLD",
+            FraudAction::Reject => "REJECT",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Operation {
+    Fund,
+    Transfer,
+}
+
+impl Operation {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Operation::Fund => "FUND",
+            Operation::Transfer => "TRANSFER",
+        }
+    }
+}
+
+/// The other side of a transfer
+#[derive(Debug, Clone)]
+pub struct Counterparty {
+    pub wallet_id: String,
+    pub user_id: String,
+    /// The sender has never transferred to this wallet before
+    pub is_new: bool,
+}
+
+/// Everything a rule gets to look at
+///
+/// Gathered once by the handler, so rules stay plain functions of it - no
+/// rule can slow a transfer down with its own queries.
+#[derive(Debug, Clone)]
+pub struct FraudContext {
+    pub operation: Operation,
+    pub wallet_id: String,
+    pub user_id: String,
+    pub amount: Decimal,
+    /// Transfers only
+    pub counterparty: Option<Counterparty>,
+}
+
+/// One rule that fired
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RuleHit {
+    pub rule: &'static str,
+    pub action: FraudAction,
+    pub reason: String,
+}
+
+/// A check run on every funding and transfer before it commits
+///
+/// Return None to let the transaction through as far as this rule goes.
+pub trait FraudRule: Send + Sync {
+    fn name(&self) -> &'static str;
+    fn evaluate(&self, context: &FraudContext) -> Option<(FraudAction, String)>;
+}
+
+/// The rules' combined answer: the most severe action any of them asked for
+#[derive(Debug, Clone)]
+pub struct FraudVerdict {
+    pub action: FraudAction,
+    pub hits: Vec<RuleHit>,
+}
+
+impl FraudVerdict {
+    /// Names of the rules that fired, e.g. "large_amount,new_counterparty"
+    pub fn rules(&self) -> String {
+        self.hits.iter().map(|hit| hit.rule).collect::<Vec<_>>().join(",")
+    }
+
+    /// What the most severe hits said, for the error and the reviewer
+    pub fn reason(&self) -> String {
+        self.hits
+            .iter()
+            .filter(|hit| hit.action == self.action)
+            .map(|hit| hit.reason.as_str())
+            .collect::<Vec<_>>()
+            .join("; ")
+    }
+}
+
+/// Runs every registered rule over a transaction
+///
+/// Why a rules engine rather than `if`s in the handlers?
+/// - Rules change far more often than money movement does; each one is a
+///   small `FraudRule` that can be added, tuned or dropped on its own
+/// - Every rule sees the same context and the strictest answer wins, so
+///   adding a rule can never loosen what another one decided
+#[derive(Default)]
+pub struct FraudEngine {
+    rules: Vec<Box<dyn FraudRule>>,
+}
+
+impl FraudEngine {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_rule(mut self, rule: impl FraudRule + 'static) -> Self {
+        self.rules.push(Box::new(rule));
+        self
+    }
+
+    /// The built-in rules configured by FRAUD_* (FRAUD=off for none)
+    pub fn from_env() -> Self {
+        if std::env::var("FRAUD").as_deref() == Ok("off") {
+            return Self::new();
+        }
+
+        let mut engine = Self::new();
+        let amounts = AmountRule {
+            flag_over: env_amount("FRAUD_FLAG_OVER"),
+            hold_over: env_amount("FRAUD_HOLD_OVER"),
+            reject_over: env_amount("FRAUD_REJECT_OVER"),
+        };
+        if amounts.flag_over.is_some() || amounts.hold_over.is_some() || amounts.reject_over.is_some() {
+            engine = engine.with_rule(amounts);
+        }
+        if let Some(hold_over) = env_amount("FRAUD_NEW_COUNTERPARTY_HOLD_OVER") {
+            engine = engine.with_rule(NewCounterpartyRule { hold_over });
+        }
+        let blocklist = BlocklistRule {
+            wallets: env_list("FRAUD_BLOCKED_WALLETS"),
+            users: env_list("FRAUD_BLOCKED_USERS"),
+        };
+        if !blocklist.wallets.is_empty() || !blocklist.users.is_empty() {
+            engine = engine.with_rule(blocklist);
+        }
+        engine
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rules.is_empty()
+    }
+
+    pub fn rule_names(&self) -> Vec<&'static str> {
+        self.rules.iter().map(|rule| rule.name()).collect()
+    }
+
+    pub fn evaluate(&self, context: &FraudContext) -> FraudVerdict {
+        let hits: Vec<RuleHit> = self
+            .rules
+            .iter()
+            .filter_map(|rule| {
+                rule.evaluate(context).map(|(action, reason)| RuleHit {
+                    rule: rule.name(),
+                    action,
+                    reason,
+                })
+            })
+            .filter(|hit| hit.action != FraudAction::Allow)
+            .collect();
+        let action = hits
+            .iter()
+            .map(|hit| hit.action)
+            .max()
+            .unwrap_or(FraudAction::Allow);
+
+        FraudVerdict { action, hits }
+    }
+}
+
+/// Large amounts: flag, hold or reject over each (optional) limit
+pub struct AmountRule {
+    /// FRAUD_FLAG_OVER
+    pub flag_over: Option<Decimal>,
+    /// FRAUD_HOLD_OVER
+    pub hold_over: Option<Decimal>,
+    /// FRAUD_REJECT_OVER
+    pub reject_over: Option<Decimal>,
+}
+
+impl FraudRule for AmountRule {
+    fn name(&self) -> &'static str {
+        "large_amount"
+    }
+
+    fn evaluate(&self, context: &FraudContext) -> Option<(FraudAction, String)> {
+        [
+            (self.reject_over, FraudAction::Reject),
+            (self.hold_over, FraudAction::Hold),
+            (self.flag_over, FraudAction::Flag),
+        ]
+        .into_iter()
+        .find_map(|(limit, action)| {
+            let limit = limit?;
+            (context.amount > limit).then(|| (action, format!("amount over {}", limit)))
+        })
+    }
+}
+
+/// First transfer to a wallet the sender has never paid: hold it over
+/// `hold_over` (FRAUD_NEW_COUNTERPARTY_HOLD_OVER)
+///
+/// Account takeovers usually drain to a fresh wallet in one go.
+pub struct NewCounterpartyRule {
+    pub hold_over: Decimal,
+}
+
+impl FraudRule for NewCounterpartyRule {
+    fn name(&self) -> &'static str {
+        "new_counterparty"
+    }
+
+    fn evaluate(&self, context: &FraudContext) -> Option<(FraudAction, String)> {
+        let counterparty = context.counterparty.as_ref()?;
+        (counterparty.is_new && context.amount > self.hold_over).then(|| {
+            (
+                FraudAction::Hold,
+                format!("first transfer to this wallet and over {}", self.hold_over),
+            )
+        })
+    }
+}
+
+/// Known-bad wallets and users, on either side (FRAUD_BLOCKED_WALLETS,
+/// FRAUD_BLOCKED_USERS - comma-separated IDs)
+pub struct BlocklistRule {
+    pub wallets: HashSet<String>,
+    pub users: HashSet<String>,
+}
+
+impl FraudRule for BlocklistRule {
+    fn name(&self) -> &'static str {
+        "blocklist"
+    }
+
+    fn evaluate(&self, context: &FraudContext) -> Option<(FraudAction, String)> {
+        let mut wallets = vec![&context.wallet_id];
+        let mut users = vec![&context.user_id];
+        if let Some(counterparty) = &context.counterparty {
+            wallets.push(&counterparty.wallet_id);
+            users.push(&counterparty.user_id);
+        }
+
+        if wallets.into_iter().any(|id| self.wallets.contains(id)) {
+            Some((FraudAction::Reject, "blocked wallet".to_string()))
+        } else if users.into_iter().any(|id| self.users.contains(id)) {
+            Some((FraudAction::Reject, "blocked user".to_string()))
+        } else {
+            None
+        }
+    }
+}
+
+fn env_amount(name: &str) -> Option<Decimal> {
+    std::env::var(name)
+        .ok()
+        .and_then(|v| Decimal::from_str(v.trim()).ok())
+        .filter(|amount| *amount >= Decimal::ZERO)
+}
+
+fn env_list(name: &str) -> HashSet<String> {
+    std::env::var(name)
+        .unwrap_or_default()
+        .split(',')
+        .map(str::trim)
+        .filter(|id| !id.is_empty())
+        .map(str::to_string)
+        .collect()
+}
@@ -1,12 +1,29 @@
-use crate::errors::WalletResult;
-use crate::kafka::KafkaProducer;
+use crate::archive::{self, ArchiveConfig};
+use crate::auth::{self, Principal};
+use crate::db_pool;
+use crate::degradation::{DegradationController, DegradationStatus};
+use crate::errors::{ErrorResponse, WalletError, WalletResult};
+use crate::etag::{wallet_etag, IfMatch};
+use crate::fraud::{Counterparty, FraudAction, FraudContext, FraudEngine, FraudVerdict, Operation};
+use crate::health::{self, HealthReport, HealthStatus};
+use crate::kafka::{KafkaProducer, ProducerDiagnostics};
+use crate::ledger;
+use crate::metrics::{BusinessMetrics, OPENMETRICS_CONTENT_TYPE};
 use crate::models::*;
+use crate::pin::PinPolicy;
 use crate::repository::WalletRepository;
+use crate::step_up::{self, CodeNotifier, StepUpConfig};
+use crate::validation::{self, AmountRules, ValidJson};
+use crate::wallet_state::STATUS_ACTIVE;
 use axum::{
-    extract::{Path, State},
-    http::StatusCode,
-    Json,
+    body::Bytes,
+    extract::{Path, Query, State},
+    http::{header, HeaderValue, StatusCode},
+    response::{IntoResponse, Response},
+    Extension, Json,
 };
+use rust_decimal::Decimal;
+use std::collections::{HashMap, HashSet};
 use std::sync::Arc;
 
 /// Application state shared across handlers
@@ -19,6 +36,21 @@ use std::sync::Arc;
 pub struct AppState {
     pub repository: WalletRepository,
     pub kafka_producer: Arc<KafkaProducer>,
+    pub metrics: Arc<BusinessMetrics>,
+    pub degradation: Arc<DegradationController>,
+    /// WALLET_CURRENCY - what balances are in
+    pub currency: String,
+    /// AMOUNT_MAX_DECIMALS / AMOUNT_MAX - checked on every request amount
+    pub amount_rules: AmountRules,
+    /// WALLET_ARCHIVE_* - defaults for POST /admin/wallets/archive
+    pub archive: ArchiveConfig,
+    /// PIN_MAX_ATTEMPTS / PIN_LOCKOUT_SECS - wrong transaction PINs
+    pub pin_policy: PinPolicy,
+    /// STEP_UP_* - transfers that wait for a one-time code
+    pub step_up: StepUpConfig,
+    pub code_notifier: CodeNotifier,
+    /// FRAUD_* - rules run on every funding and transfer
+    pub fraud: Arc<FraudEngine>,
 }
 
 /// Create a new wallet
@@ -32,14 +64,32 @@ pub struct AppState {
 /// - Wallet exists in DB but no event published
 /// - History service won't know about it
 /// - This is the distributed systems problem we discussed!
+#[utoipa::path(
+    post,
+    path = "/wallets",
+    tag = "wallets",
+    request_body = CreateWalletRequest,
+    responses(
+        (status = 200, description = "Wallet created", body = ApiResponse<WalletResponse>),
+        (status = 400, description = "Invalid amount or request", body = ErrorResponse)
+    )
+)]
 pub async fn create_wallet(
     State(state): State<AppState>,
-    Json(payload): Json<CreateWalletRequest>,
+    principal: Option<Extension<Principal>>,
+    ValidJson(payload): ValidJson<CreateWalletRequest>,
 ) -> WalletResult<Json<ApiResponse<WalletResponse>>> {
     tracing::info!(user_id = %payload.user_id, "Creating wallet");
 
+    if !auth::may_access(principal.as_deref(), &payload.user_id) {
+        return Err(WalletError::Forbidden(
+            "you can only create wallets for yourself".to_string(),
+        ));
+    }
+
     // Create wallet in database
     let wallet = state.repository.create_wallet(&payload.user_id).await?;
+    state.metrics.record_wallet_created();
 
     // Publish event (if this fails, we return error but wallet already exists!)
     state
@@ -57,18 +107,131 @@ pub async fn create_wallet(
 }
 
 /// Get wallet by ID
+///
+/// `?include=recent_transactions` embeds the latest transactions too - the
+/// wallet screen in one round trip instead of two.
+#[utoipa::path(
+    get,
+    path = "/wallets/{wallet_id}",
+    tag = "wallets",
+    params(("wallet_id" = String, Path, description = "Wallet ID"), WalletQuery),
+    responses(
+        (status = 200, description = "The wallet; its version is the ETag header", body = ApiResponse<WalletDetailResponse>,
+            headers(("ETag" = String, description = "Wallet version, e.g. \"7\" - send it back as If-Match"))),
+        (status = 400, description = "Unknown include or bad limit", body = ErrorResponse),
+        (status = 404, description = "Not found", body = ErrorResponse)
+    )
+)]
 pub async fn get_wallet(
     State(state): State<AppState>,
     Path(wallet_id): Path<String>,
-) -> WalletResult<Json<ApiResponse<WalletResponse>>> {
+    Query(query): Query<WalletQuery>,
+) -> WalletResult<([(header::HeaderName, HeaderValue); 1], Json<ApiResponse<WalletDetailResponse>>)> {
     tracing::debug!(wallet_id = %wallet_id, "Fetching wallet");
 
+    let with_transactions = includes_recent_transactions(query.include.as_deref())?;
+    let limit = query.transactions_limit.unwrap_or(RECENT_TRANSACTIONS_DEFAULT);
+    if !(1..=RECENT_TRANSACTIONS_MAX).contains(&limit) {
+        return Err(WalletError::InvalidRequest(format!(
+            "transactions_limit must be between 1 and {}",
+            RECENT_TRANSACTIONS_MAX
+        )));
+    }
+
     let wallet = state.repository.find_by_id(&wallet_id).await?;
+    let recent_transactions = if with_transactions {
+        let transactions = state.repository.find_recent_transactions(&wallet_id, limit).await?;
+        Some(transactions.into_iter().map(TransactionResponse::from).collect())
+    } else {
+        None
+    };
 
-    Ok(Json(ApiResponse::success(WalletResponse::from(wallet))))
+    Ok((
+        [(header::ETAG, wallet_etag(wallet.version))],
+        Json(ApiResponse::success(WalletDetailResponse {
+            wallet: WalletResponse::from(wallet),
+            recent_transactions,
+        })),
+    ))
+}
+
+/// Update a wallet's nickname, labels or default flag
+///
+/// Absent fields are left alone; the balance can't be changed here. Send
+/// the ETag from GET as If-Match so an edit made elsewhere isn't
+/// overwritten (412 if it was).
+#[utoipa::path(
+    patch,
+    path = "/wallets/{wallet_id}",
+    tag = "wallets",
+    params(
+        ("wallet_id" = String, Path, description = "Wallet ID"),
+        ("If-Match" = Option<String>, Header, description = "Only update if the wallet is still at this ETag")
+    ),
+    request_body = UpdateWalletRequest,
+    responses(
+        (status = 200, description = "The updated wallet, with its new ETag", body = ApiResponse<WalletResponse>),
+        (status = 400, description = "A pot can't be the default", body = ErrorResponse),
+        (status = 404, description = "Not found", body = ErrorResponse),
+        (status = 409, description = "Concurrent update - re-read and retry", body = ErrorResponse),
+        (status = 412, description = "If-Match is out of date - re-read the wallet", body = ErrorResponse),
+        (status = 422, description = "Invalid fields, listed in `errors`", body = ErrorResponse)
+    )
+)]
+pub async fn update_wallet(
+    State(state): State<AppState>,
+    Path(wallet_id): Path<String>,
+    IfMatch(expected_version): IfMatch,
+    ValidJson(payload): ValidJson<UpdateWalletRequest>,
+) -> WalletResult<([(header::HeaderName, HeaderValue); 1], Json<ApiResponse<WalletResponse>>)> {
+    tracing::info!(wallet_id = %wallet_id, "Updating wallet metadata");
+
+    let wallet = state
+        .repository
+        .update_wallet_metadata(&wallet_id, &payload, expected_version)
+        .await?;
+
+    Ok((
+        [(header::ETAG, wallet_etag(wallet.version))],
+        Json(ApiResponse::success(WalletResponse::from(wallet))),
+    ))
+}
+
+/// Transactions embedded by `?include=recent_transactions` unless
+/// `transactions_limit` says otherwise
+pub const RECENT_TRANSACTIONS_DEFAULT: i64 = 10;
+
+/// Largest `transactions_limit` - it's for a screen, not an export
+pub const RECENT_TRANSACTIONS_MAX: i64 = 50;
+
+/// Whether `?include=` asks for recent transactions; unknown names are a
+/// 400 so a typo doesn't look like "no transactions"
+pub fn includes_recent_transactions(include: Option<&str>) -> WalletResult<bool> {
+    let mut recent = false;
+    for name in include.unwrap_or("").split(',').map(str::trim).filter(|n| !n.is_empty()) {
+        match name {
+            "recent_transactions" => recent = true,
+            other => {
+                return Err(WalletError::InvalidRequest(format!(
+                    "Unknown include: {} (expected recent_transactions)",
+                    other
+                )))
+            }
+        }
+    }
+    Ok(recent)
 }
 
 /// Get all wallets for a user
+#[utoipa::path(
+    get,
+    path = "/users/{user_id}/wallets",
+    tag = "wallets",
+    params(("user_id" = String, Path, description = "User ID")),
+    responses(
+        (status = 200, description = "The user's wallets, pots included", body = ApiResponse<Vec<WalletResponse>>)
+    )
+)]
 pub async fn get_user_wallets(
     State(state): State<AppState>,
     Path(user_id): Path<String>,
@@ -83,34 +246,191 @@ pub async fn get_user_wallets(
     Ok(Json(ApiResponse::success(response)))
 }
 
+/// Fetch up to 100 wallets in one query
+///
+/// For aggregators that would otherwise GET /wallets/:id hundreds of
+/// times. Wallets come back in the order asked for (duplicates once);
+/// unknown IDs are listed in `not_found`.
+#[utoipa::path(
+    post,
+    path = "/wallets/batch-get",
+    tag = "wallets",
+    request_body = BatchGetWalletsRequest,
+    responses(
+        (status = 200, description = "The wallets found, and the IDs that weren't", body = ApiResponse<BatchGetWalletsResponse>),
+        (status = 422, description = "Invalid fields, listed in `errors`", body = ErrorResponse)
+    )
+)]
+pub async fn batch_get_wallets(
+    State(state): State<AppState>,
+    principal: Option<Extension<Principal>>,
+    ValidJson(payload): ValidJson<BatchGetWalletsRequest>,
+) -> WalletResult<Json<ApiResponse<BatchGetWalletsResponse>>> {
+    let mut wallet_ids = payload.wallet_ids;
+    let mut seen = HashSet::new();
+    wallet_ids.retain(|id| seen.insert(id.clone()));
+
+    tracing::debug!(count = wallet_ids.len(), "Fetching wallets in batch");
+
+    let mut found: HashMap<String, Wallet> = state
+        .repository
+        .find_by_ids(&wallet_ids)
+        .await?
+        .into_iter()
+        // Someone else's wallet is "not found", just like on GET
+        .filter(|wallet| auth::may_read(principal.as_deref(), &wallet.user_id))
+        .map(|wallet| (wallet.id.clone(), wallet))
+        .collect();
+
+    let mut response = BatchGetWalletsResponse {
+        wallets: Vec::with_capacity(found.len()),
+        not_found: Vec::new(),
+    };
+    for wallet_id in wallet_ids {
+        match found.remove(&wallet_id) {
+            Some(wallet) => response.wallets.push(WalletResponse::from(wallet)),
+            None => response.not_found.push(wallet_id),
+        }
+    }
+
+    Ok(Json(ApiResponse::success(response)))
+}
+
+/// Every wallet of a user with its balance, plus totals per currency
+///
+/// One query (the same as GET /users/:id/wallets); pots are included and
+/// counted as allocated, so `total_balance` is everything the user holds.
+#[utoipa::path(
+    get,
+    path = "/users/{user_id}/balance-summary",
+    tag = "wallets",
+    params(("user_id" = String, Path, description = "User ID")),
+    responses(
+        (status = 200, description = "Wallets and totals per currency", body = ApiResponse<UserBalanceSummary>)
+    )
+)]
+pub async fn get_user_balance_summary(
+    State(state): State<AppState>,
+    Path(user_id): Path<String>,
+) -> WalletResult<Json<ApiResponse<UserBalanceSummary>>> {
+    let wallets = state.repository.find_by_user_id(&user_id).await?;
+
+    Ok(Json(ApiResponse::success(UserBalanceSummary::new(
+        user_id,
+        &state.currency,
+        wallets,
+    ))))
+}
+
+/// Erase a user's personal data (GDPR right to erasure)
+///
+/// The user's ID is replaced by a pseudonym everywhere, memos, nicknames,
+/// labels and beneficiaries are dropped; amounts stay, for accounting.
+/// Published as USER_ANONYMIZED so every consumer does the same. Every
+/// wallet must be empty first. For the user themselves or an admin - never
+/// an API key.
+#[utoipa::path(
+    delete,
+    path = "/users/{user_id}/data",
+    tag = "wallets",
+    params(("user_id" = String, Path, description = "User ID")),
+    responses(
+        (status = 200, description = "Anonymized; the pseudonym is in the response", body = ApiResponse<UserAnonymization>),
+        (status = 404, description = "No wallets or KYC record for this user", body = ErrorResponse),
+        (status = 409, description = "A wallet still has a balance", body = ErrorResponse),
+        (status = 503, description = "Saved, but the event could not be published", body = ErrorResponse)
+    )
+)]
+pub async fn erase_user_data(
+    State(state): State<AppState>,
+    principal: Option<Extension<Principal>>,
+    Path(user_id): Path<String>,
+) -> WalletResult<Json<ApiResponse<UserAnonymization>>> {
+    // Not the user's own ID - the audit log mustn't be able to tell who it was
+    let actor = match principal.as_deref() {
+        Some(principal) if principal.subject != user_id => principal.subject.clone(),
+        Some(_) => "user".to_string(),
+        None => "unauthenticated".to_string(),
+    };
+    tracing::info!(actor = %actor, "Erasing user data");
+
+    let anonymization = state.repository.anonymize_user(&user_id, &actor).await?;
+    state
+        .kafka_producer
+        .publish_user_anonymized(&anonymization)
+        .await?;
+
+    tracing::info!(
+        anonymized_user_id = %anonymization.anonymized_user_id,
+        wallets = anonymization.wallet_ids.len(),
+        "User data erased"
+    );
+
+    Ok(Json(ApiResponse::success(anonymization)))
+}
+
 /// Fund a wallet (add money)
 /// 
 /// Flow:
-/// 1. Update wallet balance in database (with optimistic locking)
+/// 1. Add to the wallet balance in database (one atomic UPDATE)
 /// 2. Create transaction record
 /// 3. Publish event to Kafka
 /// 4. Return updated wallet
 /// 
-/// Retry handling:
-/// - If OptimisticLockError, client should retry
+/// Concurrency:
+/// - Parallel fundings all succeed; there is nothing to retry
 /// - Database guarantees consistency
 /// - Event published only after DB commit succeeds
+#[utoipa::path(
+    post,
+    path = "/wallets/{wallet_id}/fund",
+    tag = "operations",
+    params(
+        ("wallet_id" = String, Path, description = "Wallet ID"),
+        ("If-Match" = Option<String>, Header, description = "Only fund if the wallet is still at this ETag")
+    ),
+    request_body = FundWalletRequest,
+    responses(
+        (status = 200, description = "The funded wallet, with its new ETag", body = ApiResponse<WalletResponse>),
+        (status = 400, description = "Invalid amount or request", body = ErrorResponse),
+        (status = 403, description = "Over the unverified balance cap, or declined by the fraud rules", body = ErrorResponse),
+        (status = 404, description = "Not found", body = ErrorResponse),
+        (status = 409, description = "Concurrent update or duplicate - retry or give up", body = ErrorResponse),
+        (status = 412, description = "If-Match is out of date - re-read the wallet", body = ErrorResponse),
+        (status = 422, description = "Invalid fields, listed in `errors`", body = ErrorResponse),
+        (status = 503, description = "Saved, but the event could not be published", body = ErrorResponse)
+    )
+)]
 pub async fn fund_wallet(
     State(state): State<AppState>,
     Path(wallet_id): Path<String>,
-    Json(payload): Json<FundWalletRequest>,
-) -> WalletResult<Json<ApiResponse<WalletResponse>>> {
+    IfMatch(expected_version): IfMatch,
+    ValidJson(payload): ValidJson<FundWalletRequest>,
+) -> WalletResult<([(header::HeaderName, HeaderValue); 1], Json<ApiResponse<WalletResponse>>)> {
     tracing::info!(
         wallet_id = %wallet_id,
         amount = %payload.amount,
         "Funding wallet"
     );
 
+    if let Err(e) = screen_funding(&state, &wallet_id, payload.amount).await {
+        state.metrics.record_decline("fund", &e);
+        return Err(funding_declined(&state, &wallet_id, payload.amount, e).await);
+    }
+
     // Update database (atomic operation)
-    let (wallet, transaction) = state
+    let (wallet, transaction) = match state
         .repository
-        .fund_wallet(&wallet_id, payload.amount)
-        .await?;
+        .fund_wallet_expecting(&wallet_id, payload.amount, expected_version)
+        .await
+    {
+        Ok(funded) => funded,
+        Err(e) => {
+            state.metrics.record_decline("fund", &e);
+            return Err(funding_declined(&state, &wallet_id, payload.amount, e).await);
+        }
+    };
+    state.metrics.record_funding(payload.amount);
 
     // Publish event
     state
@@ -124,7 +444,10 @@ pub async fn fund_wallet(
         "Wallet funded successfully"
     );
 
-    Ok(Json(ApiResponse::success(WalletResponse::from(wallet))))
+    Ok((
+        [(header::ETAG, wallet_etag(wallet.version))],
+        Json(ApiResponse::success(WalletResponse::from(wallet))),
+    ))
 }
 
 /// Transfer money between wallets
@@ -141,27 +464,251 @@ pub async fn fund_wallet(
 /// - Everything happens in a single DB transaction
 /// - Wallets locked in consistent order (prevents deadlock)
 /// - Event published only after successful commit
+#[utoipa::path(
+    post,
+    path = "/wallets/{wallet_id}/transfer",
+    tag = "operations",
+    params(
+        ("wallet_id" = String, Path, description = "Wallet ID"),
+        ("If-Match" = Option<String>, Header, description = "Only transfer if the sender is still at this ETag")
+    ),
+    request_body = TransferRequest,
+    responses(
+        (status = 200, description = "Both legs, plus round-up legs if a rule applied", body = ApiResponse<Vec<TransactionResponse>>),
+        (status = 202, description = "Waiting for the step-up code (PENDING_VERIFICATION) or fraud review (HELD)", body = ApiResponse<PendingTransfer>),
+        (status = 400, description = "Invalid amount or request", body = ErrorResponse),
+        (status = 403, description = "PIN missing or wrong, blocklisted, over a KYC cap, or declined by the fraud rules", body = ErrorResponse),
+        (status = 404, description = "Not found", body = ErrorResponse),
+        (status = 409, description = "Concurrent update or duplicate - retry or give up", body = ErrorResponse),
+        (status = 412, description = "If-Match is out of date - re-read the wallet", body = ErrorResponse),
+        (status = 422, description = "Invalid fields, listed in `errors`", body = ErrorResponse),
+        (status = 423, description = "PIN or step-up locked after too many wrong attempts", body = ErrorResponse),
+        (status = 429, description = "Over the wallet's velocity limit (transfers or volume per window)", body = ErrorResponse),
+        (status = 503, description = "Saved, but the event could not be published", body = ErrorResponse)
+    )
+)]
 pub async fn transfer(
     State(state): State<AppState>,
     Path(from_wallet_id): Path<String>,
-    Json(payload): Json<TransferRequest>,
-) -> WalletResult<Json<ApiResponse<Vec<TransactionResponse>>>> {
+    IfMatch(expected_version): IfMatch,
+    ValidJson(payload): ValidJson<TransferRequest>,
+) -> WalletResult<TransferReply> {
+    execute_transfer(
+        &state,
+        &from_wallet_id,
+        payload.to_wallet_id,
+        payload.beneficiary_id,
+        payload.amount,
+        payload.memo,
+        payload.pin,
+        expected_version,
+    )
+    .await
+}
+
+/// A transfer's answer: its legs (200), or the pending transfer waiting
+/// for a step-up code or fraud review (202)
+pub enum TransferReply {
+    Completed(Vec<TransactionResponse>),
+    Pending(PendingTransfer),
+}
+
+impl IntoResponse for TransferReply {
+    fn into_response(self) -> Response {
+        match self {
+            TransferReply::Completed(legs) => Json(ApiResponse::success(legs)).into_response(),
+            TransferReply::Pending(pending) => {
+                (StatusCode::ACCEPTED, Json(ApiResponse::success(pending))).into_response()
+            }
+        }
+    }
+}
+
+/// Shared transfer flow for direct transfers and executed templates
+/// 
+/// Resolves the recipient (wallet ID or the sender's beneficiary), checks
+/// the sender's PIN and runs the fraud rules. Then it either runs the
+/// transfer or parks it: HELD for review if a rule said so, or - over
+/// STEP_UP_THRESHOLD - until the code sent to the user is confirmed.
+/// `expected_version` is the sender's If-Match, if any.
+#[allow(clippy::too_many_arguments)]
+async fn execute_transfer(
+    state: &AppState,
+    from_wallet_id: &str,
+    to_wallet_id: Option<String>,
+    beneficiary_id: Option<String>,
+    amount: Decimal,
+    memo: Option<String>,
+    pin: Option<String>,
+    expected_version: Option<i64>,
+) -> WalletResult<TransferReply> {
+    // Get the "from" wallet details for the event
+    let from_wallet = state.repository.find_by_id(from_wallet_id).await?;
+
+    // Resolve the recipient - either given directly or via a saved beneficiary
+    let to_wallet_id = match (to_wallet_id, beneficiary_id) {
+        (Some(to_wallet_id), None) => to_wallet_id,
+        (None, Some(beneficiary_id)) => {
+            state
+                .repository
+                .resolve_beneficiary(&from_wallet.user_id, &beneficiary_id)
+                .await?
+        }
+        _ => {
+            return Err(WalletError::InvalidRequest(
+                "Provide exactly one of to_wallet_id or beneficiary_id".to_string(),
+            ));
+        }
+    };
+
     tracing::info!(
         from_wallet_id = %from_wallet_id,
-        to_wallet_id = %payload.to_wallet_id,
-        amount = %payload.amount,
+        to_wallet_id = %to_wallet_id,
+        amount = %amount,
         "Processing transfer"
     );
 
-    // Get the "from" wallet details for the event
-    let from_wallet = state.repository.find_by_id(&from_wallet_id).await?;
-    let to_wallet = state.repository.find_by_id(&payload.to_wallet_id).await?;
+    let to_wallet = match state.repository.find_by_id(&to_wallet_id).await {
+        Ok(to_wallet) => to_wallet,
+        Err(e) => return Err(transfer_declined(state, &from_wallet, &to_wallet_id, amount, e).await),
+    };
+
+    // Over the wallet's PIN threshold the PIN must be right (and wrong
+    // ones count towards the lockout)
+    if let Err(e) = state
+        .repository
+        .check_pin(from_wallet_id, pin.as_deref(), amount, &state.pin_policy)
+        .await
+    {
+        state.metrics.record_decline("transfer", &e);
+        return Err(transfer_declined(state, &from_wallet, &to_wallet_id, amount, e).await);
+    }
+
+    // Fraud rules: a reject declines it, a hold parks it for review
+    let verdict = screen_transfer(state, &from_wallet, &to_wallet, amount).await?;
+    match verdict.action {
+        FraudAction::Reject => {
+            let e = WalletError::FraudRejected(verdict.reason());
+            state.metrics.record_decline("transfer", &e);
+            return Err(transfer_declined(state, &from_wallet, &to_wallet_id, amount, e).await);
+        }
+        FraudAction::Hold => {
+            let held = state
+                .repository
+                .create_held_transfer(
+                    from_wallet_id,
+                    &to_wallet_id,
+                    amount,
+                    memo.as_deref(),
+                    &verdict.reason(),
+                )
+                .await?;
+            tracing::warn!(
+                pending_transfer_id = %held.id,
+                from_wallet_id = %from_wallet_id,
+                amount = %amount,
+                "Transfer held for fraud review"
+            );
+            return Ok(TransferReply::Pending(held));
+        }
+        FraudAction::Flag | FraudAction::Allow => {}
+    }
+
+    // Over the step-up threshold nothing moves until the code comes back
+    if state.step_up.applies_to(amount) {
+        let code = step_up::generate_code();
+        let pending = state
+            .repository
+            .create_pending_transfer(
+                from_wallet_id,
+                &to_wallet_id,
+                amount,
+                memo.as_deref(),
+                &code,
+                state.step_up.code_ttl,
+            )
+            .await?;
+        if let Err(e) = state
+            .code_notifier
+            .send(&pending, &from_wallet.user_id, &code)
+            .await
+        {
+            // Nobody can confirm it now
+            state
+                .repository
+                .cancel_pending_transfer(from_wallet_id, &pending.id)
+                .await?;
+            return Err(e);
+        }
+
+        tracing::info!(
+            pending_transfer_id = %pending.id,
+            from_wallet_id = %from_wallet_id,
+            amount = %amount,
+            "Transfer waiting for step-up verification"
+        );
+        return Ok(TransferReply::Pending(pending));
+    }
+
+    let legs = complete_transfer(
+        state,
+        &from_wallet,
+        &to_wallet,
+        amount,
+        memo.as_deref(),
+        expected_version,
+        None,
+    )
+    .await?;
+
+    Ok(TransferReply::Completed(legs))
+}
+
+/// Run the atomic transfer, publish its events and build the response legs
+///
+/// `pending_id` is the confirmed pending transfer this runs, if any - it
+/// is marked COMPLETED or FAILED as soon as the transfer commits or is
+/// refused.
+async fn complete_transfer(
+    state: &AppState,
+    from_wallet: &Wallet,
+    to_wallet: &Wallet,
+    amount: Decimal,
+    memo: Option<&str>,
+    expected_version: Option<i64>,
+    pending_id: Option<&str>,
+) -> WalletResult<Vec<TransactionResponse>> {
+    let from_wallet_id = from_wallet.id.as_str();
+    let to_wallet_id = to_wallet.id.as_str();
 
     // Execute transfer (atomic operation)
-    let (out_txn, in_txn) = state
+    let result = state
         .repository
-        .transfer(&from_wallet_id, &payload.to_wallet_id, payload.amount)
-        .await?;
+        .transfer_expecting(from_wallet_id, to_wallet_id, amount, memo, expected_version)
+        .await;
+    if let Some(pending_id) = pending_id {
+        let reference_id = result
+            .as_ref()
+            .ok()
+            .and_then(|outcome| outcome.out_transaction.reference_id.as_deref());
+        // The transfer's own result stands either way
+        if let Err(e) = state
+            .repository
+            .resolve_pending_transfer(pending_id, reference_id)
+            .await
+        {
+            tracing::warn!(pending_transfer_id = %pending_id, error = %e, "Failed to resolve pending transfer");
+        }
+    }
+    let outcome = match result {
+        Ok(outcome) => outcome,
+        Err(e) => {
+            state.metrics.record_decline("transfer", &e);
+            return Err(transfer_declined(state, from_wallet, to_wallet_id, amount, e).await);
+        }
+    };
+    state.metrics.record_transfer(amount);
+    let reference_id = outcome.out_transaction.reference_id.clone().unwrap_or_default();
 
     // Publish event
     state
@@ -171,30 +718,2001 @@ pub async fn transfer(
             from_wallet.user_id.clone(),
             to_wallet.id.clone(),
             to_wallet.user_id.clone(),
-            payload.amount,
-            out_txn.reference_id.clone().unwrap_or_default(),
+            amount,
+            reference_id.clone(),
         )
         .await?;
 
+    if let Some(round_up) = &outcome.round_up {
+        state
+            .kafka_producer
+            .publish_round_up_applied(
+                from_wallet.id.clone(),
+                from_wallet.user_id.clone(),
+                round_up,
+                reference_id,
+            )
+            .await?;
+    }
+
     tracing::info!(
         from_wallet_id = %from_wallet_id,
-        to_wallet_id = %payload.to_wallet_id,
-        amount = %payload.amount,
+        to_wallet_id = %to_wallet_id,
+        amount = %amount,
         "Transfer completed successfully"
     );
 
-    let response = vec![
-        TransactionResponse::from(out_txn),
-        TransactionResponse::from(in_txn),
+    let mut response = vec![
+        TransactionResponse::from(outcome.out_transaction),
+        TransactionResponse::from(outcome.in_transaction),
     ];
+    if let Some(round_up) = outcome.round_up {
+        response.push(TransactionResponse::from(round_up.out_transaction));
+        response.push(TransactionResponse::from(round_up.in_transaction));
+    }
 
-    Ok(Json(ApiResponse::success(response)))
+    Ok(response)
 }
 
-/// Health check endpoint
+/// Run the fraud rules over a transaction about to commit
+///
+/// Whatever a rule fired on is published as FRAUD_ALERT; a failed publish
+/// is only logged - the verdict stands either way.
+async fn screen(state: &AppState, context: &FraudContext) -> FraudVerdict {
+    let verdict = state.fraud.evaluate(context);
+    if verdict.action == FraudAction::Allow {
+        return verdict;
+    }
+
+    tracing::warn!(
+        wallet_id = %context.wallet_id,
+        operation = context.operation.as_str(),
+        action = verdict.action.as_str(),
+        rules = %verdict.rules(),
+        "Fraud rules fired"
+    );
+    if let Err(e) = state.kafka_producer.publish_fraud_alert(context, &verdict).await {
+        tracing::warn!(wallet_id = %context.wallet_id, error = %e, "Failed to publish fraud alert");
+    }
+
+    verdict
+}
+
+/// Fraud rules for a funding: a hold declines it like a reject - there's
+/// no money to park yet
+async fn screen_funding(state: &AppState, wallet_id: &str, amount: Decimal) -> WalletResult<()> {
+    if state.fraud.is_empty() {
+        return Ok(());
+    }
+
+    let wallet = state.repository.find_by_id(wallet_id).await?;
+    let context = FraudContext {
+        operation: Operation::Fund,
+        wallet_id: wallet.id,
+        user_id: wallet.user_id,
+        amount,
+        counterparty: None,
+    };
+    let verdict = screen(state, &context).await;
+    if verdict.action >= FraudAction::Hold {
+        return Err(WalletError::FraudRejected(verdict.reason()));
+    }
+    Ok(())
+}
+
+/// Fraud rules for a transfer (nothing to look up when there are none)
+async fn screen_transfer(
+    state: &AppState,
+    from_wallet: &Wallet,
+    to_wallet: &Wallet,
+    amount: Decimal,
+) -> WalletResult<FraudVerdict> {
+    if state.fraud.is_empty() {
+        return Ok(FraudVerdict {
+            action: FraudAction::Allow,
+            hits: Vec::new(),
+        });
+    }
+
+    let seen = state
+        .repository
+        .has_transferred_to(&from_wallet.id, &to_wallet.id)
+        .await?;
+    let context = FraudContext {
+        operation: Operation::Transfer,
+        wallet_id: from_wallet.id.clone(),
+        user_id: from_wallet.user_id.clone(),
+        amount,
+        counterparty: Some(Counterparty {
+            wallet_id: to_wallet.id.clone(),
+            user_id: to_wallet.user_id.clone(),
+            is_new: !seen,
+        }),
+    };
+    Ok(screen(state, &context).await)
+}
+
+/// Publish FUNDING_FAILED for a declined funding, handing the error back
+///
+/// Unknown wallets are skipped - there's no owner to attribute the attempt
+/// to. A failed publish is only logged: the client gets the decline either way.
+async fn funding_declined(
+    state: &AppState,
+    wallet_id: &str,
+    amount: Decimal,
+    error: WalletError,
+) -> WalletError {
+    if !error.is_decline() {
+        return error;
+    }
+
+    if let Ok(wallet) = state.repository.find_by_id(wallet_id).await {
+        if let Err(e) = state
+            .kafka_producer
+            .publish_funding_failed(&wallet, amount, &error)
+            .await
+        {
+            tracing::warn!(wallet_id = %wallet_id, error = %e, "Failed to publish funding decline");
+        }
+    }
+
+    error
+}
+
+/// Publish TRANSFER_FAILED for a declined transfer, handing the error back
+async fn transfer_declined(
+    state: &AppState,
+    from_wallet: &Wallet,
+    to_wallet_id: &str,
+    amount: Decimal,
+    error: WalletError,
+) -> WalletError {
+    if !error.is_decline() {
+        return error;
+    }
+
+    if let Err(e) = state
+        .kafka_producer
+        .publish_transfer_failed(from_wallet, to_wallet_id.to_string(), amount, &error)
+        .await
+    {
+        tracing::warn!(
+            from_wallet_id = %from_wallet.id,
+            error = %e,
+            "Failed to publish transfer decline"
+        );
+    }
+
+    if matches!(error, WalletError::VelocityLimitExceeded { .. }) {
+        if let Err(e) = state
+            .kafka_producer
+            .publish_velocity_limit_exceeded(from_wallet, to_wallet_id.to_string(), amount, &error)
+            .await
+        {
+            tracing::warn!(
+                from_wallet_id = %from_wallet.id,
+                error = %e,
+                "Failed to publish velocity limit violation"
+            );
+        }
+    }
+
+    lockout_alert(state, &from_wallet.id, error).await
+}
+
+/// A wrong PIN or code that just locked the wallet is published as
+/// VERIFICATION_LOCKOUT - a failed publish is only logged. Hands the
+/// error back either way.
+async fn lockout_alert(state: &AppState, wallet_id: &str, error: WalletError) -> WalletError {
+    if !matches!(error, WalletError::LockedOut { .. }) {
+        return error;
+    }
+
+    let published = match state.repository.find_by_id(wallet_id).await {
+        Ok(wallet) => state.kafka_producer.publish_verification_lockout(&wallet, &error).await,
+        Err(e) => Err(e),
+    };
+    if let Err(e) = published {
+        tracing::warn!(wallet_id = %wallet_id, error = %e, "Failed to publish verification lockout");
+    }
+
+    error
+}
+
+/// Save a beneficiary for a user
+/// 
+/// The beneficiary can then be used as the recipient of a transfer
+/// (`beneficiary_id` in the transfer request) instead of a raw wallet ID
+#[utoipa::path(
+    post,
+    path = "/users/{user_id}/beneficiaries",
+    tag = "beneficiaries",
+    params(("user_id" = String, Path, description = "User ID")),
+    request_body = CreateBeneficiaryRequest,
+    responses(
+        (status = 201, description = "Beneficiary saved", body = ApiResponse<Beneficiary>),
+        (status = 400, description = "Invalid amount or request", body = ErrorResponse),
+        (status = 409, description = "Concurrent update or duplicate - retry or give up", body = ErrorResponse)
+    )
+)]
+pub async fn create_beneficiary(
+    State(state): State<AppState>,
+    Path(user_id): Path<String>,
+    ValidJson(payload): ValidJson<CreateBeneficiaryRequest>,
+) -> WalletResult<(StatusCode, Json<ApiResponse<Beneficiary>>)> {
+    tracing::info!(user_id = %user_id, name = %payload.name, "Creating beneficiary");
+
+    let beneficiary = state
+        .repository
+        .create_beneficiary(
+            &user_id,
+            &payload.name,
+            payload.wallet_id.as_deref(),
+            payload.beneficiary_user_id.as_deref(),
+        )
+        .await?;
+
+    Ok((StatusCode::CREATED, Json(ApiResponse::success(beneficiary))))
+}
+
+/// List a user's saved beneficiaries
+#[utoipa::path(
+    get,
+    path = "/users/{user_id}/beneficiaries",
+    tag = "beneficiaries",
+    params(("user_id" = String, Path, description = "User ID")),
+    responses(
+        (status = 200, description = "The user's beneficiaries", body = ApiResponse<Vec<Beneficiary>>)
+    )
+)]
+pub async fn get_beneficiaries(
+    State(state): State<AppState>,
+    Path(user_id): Path<String>,
+) -> WalletResult<Json<ApiResponse<Vec<Beneficiary>>>> {
+    tracing::debug!(user_id = %user_id, "Fetching beneficiaries");
+
+    let beneficiaries = state.repository.find_beneficiaries(&user_id).await?;
+
+    Ok(Json(ApiResponse::success(beneficiaries)))
+}
+
+/// Delete one of a user's saved beneficiaries
+#[utoipa::path(
+    delete,
+    path = "/users/{user_id}/beneficiaries/{beneficiary_id}",
+    tag = "beneficiaries",
+    params(("user_id" = String, Path, description = "User ID"), ("beneficiary_id" = String, Path, description = "Beneficiary ID")),
+    responses(
+        (status = 204, description = "Deleted"),
+        (status = 404, description = "Not found", body = ErrorResponse)
+    )
+)]
+pub async fn delete_beneficiary(
+    State(state): State<AppState>,
+    Path((user_id, beneficiary_id)): Path<(String, String)>,
+) -> WalletResult<StatusCode> {
+    tracing::info!(
+        user_id = %user_id,
+        beneficiary_id = %beneficiary_id,
+        "Deleting beneficiary"
+    );
+
+    state
+        .repository
+        .delete_beneficiary(&user_id, &beneficiary_id)
+        .await?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Save a transfer template on a wallet
+#[utoipa::path(
+    post,
+    path = "/wallets/{wallet_id}/templates",
+    tag = "templates",
+    params(("wallet_id" = String, Path, description = "Wallet ID")),
+    request_body = CreateTemplateRequest,
+    responses(
+        (status = 201, description = "Template saved", body = ApiResponse<TransferTemplate>),
+        (status = 400, description = "Invalid amount or request", body = ErrorResponse),
+        (status = 404, description = "Not found", body = ErrorResponse),
+        (status = 422, description = "Invalid fields, listed in `errors`", body = ErrorResponse)
+    )
+)]
+pub async fn create_template(
+    State(state): State<AppState>,
+    Path(wallet_id): Path<String>,
+    ValidJson(payload): ValidJson<CreateTemplateRequest>,
+) -> WalletResult<(StatusCode, Json<ApiResponse<TransferTemplate>>)> {
+    tracing::info!(wallet_id = %wallet_id, name = %payload.name, "Creating transfer template");
+
+    let template = state
+        .repository
+        .create_template(
+            &wallet_id,
+            &payload.name,
+            payload.to_wallet_id.as_deref(),
+            payload.beneficiary_id.as_deref(),
+            payload.amount,
+            payload.memo.as_deref(),
+        )
+        .await?;
+
+    Ok((StatusCode::CREATED, Json(ApiResponse::success(template))))
+}
+
+/// List the transfer templates saved on a wallet
+#[utoipa::path(
+    get,
+    path = "/wallets/{wallet_id}/templates",
+    tag = "templates",
+    params(("wallet_id" = String, Path, description = "Wallet ID")),
+    responses(
+        (status = 200, description = "The wallet's templates", body = ApiResponse<Vec<TransferTemplate>>)
+    )
+)]
+pub async fn get_templates(
+    State(state): State<AppState>,
+    Path(wallet_id): Path<String>,
+) -> WalletResult<Json<ApiResponse<Vec<TransferTemplate>>>> {
+    tracing::debug!(wallet_id = %wallet_id, "Fetching transfer templates");
+
+    let templates = state.repository.find_templates(&wallet_id).await?;
+
+    Ok(Json(ApiResponse::success(templates)))
+}
+
+/// Delete a transfer template
+#[utoipa::path(
+    delete,
+    path = "/templates/{template_id}",
+    tag = "templates",
+    params(("template_id" = String, Path, description = "Template ID")),
+    responses(
+        (status = 204, description = "Deleted"),
+        (status = 404, description = "Not found", body = ErrorResponse)
+    )
+)]
+pub async fn delete_template(
+    State(state): State<AppState>,
+    Path(template_id): Path<String>,
+) -> WalletResult<StatusCode> {
+    tracing::info!(template_id = %template_id, "Deleting transfer template");
+
+    state.repository.delete_template(&template_id).await?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Execute a saved transfer template
 /// 
-/// Returns 200 if service is healthy
-/// Can be extended to check database and Kafka connectivity
-pub async fn health_check() -> (StatusCode, &'static str) {
-    (StatusCode::OK, "OK")
+/// Runs exactly the same flow as POST /wallets/:id/transfer using the
+/// template's recipient and memo. The body may override the amount.
+#[utoipa::path(
+    post,
+    path = "/templates/{template_id}/execute",
+    tag = "templates",
+    params(("template_id" = String, Path, description = "Template ID")),
+    request_body(content = Option<ExecuteTemplateRequest>, description = "Optional - an empty body uses the saved amount"),
+    responses(
+        (status = 200, description = "The transfer legs", body = ApiResponse<Vec<TransactionResponse>>),
+        (status = 202, description = "Waiting for the step-up code (PENDING_VERIFICATION) or fraud review (HELD)", body = ApiResponse<PendingTransfer>),
+        (status = 400, description = "Invalid amount or request", body = ErrorResponse),
+        (status = 403, description = "PIN missing or wrong, blocklisted, over a KYC cap, or declined by the fraud rules", body = ErrorResponse),
+        (status = 404, description = "Not found", body = ErrorResponse),
+        (status = 409, description = "Concurrent update or duplicate - retry or give up", body = ErrorResponse),
+        (status = 422, description = "Invalid fields, listed in `errors`", body = ErrorResponse),
+        (status = 423, description = "PIN or step-up locked after too many wrong attempts", body = ErrorResponse),
+        (status = 429, description = "Over the wallet's velocity limit (transfers or volume per window)", body = ErrorResponse),
+        (status = 503, description = "Saved, but the event could not be published", body = ErrorResponse)
+    )
+)]
+pub async fn execute_template(
+    State(state): State<AppState>,
+    Path(template_id): Path<String>,
+    body: Bytes,
+) -> WalletResult<TransferReply> {
+    // An empty body means "use the defaults"; anything else must parse
+    let payload: ExecuteTemplateRequest = if body.is_empty() {
+        ExecuteTemplateRequest::default()
+    } else {
+        let value = serde_json::from_slice(&body)
+            .map_err(|e| WalletError::InvalidRequest(format!("Invalid request body: {}", e)))?;
+        validation::from_json(value)?
+    };
+    validation::validate(&payload, &state.amount_rules)?;
+
+    let template = state.repository.find_template(&template_id).await?;
+
+    tracing::info!(
+        template_id = %template_id,
+        wallet_id = %template.wallet_id,
+        "Executing transfer template"
+    );
+
+    execute_transfer(
+        &state,
+        &template.wallet_id,
+        template.to_wallet_id,
+        template.beneficiary_id,
+        payload.amount.unwrap_or(template.amount),
+        template.memo,
+        payload.pin,
+        None,
+    )
+    .await
+}
+
+/// Configure (create or replace) a wallet's round-up rule
+#[utoipa::path(
+    put,
+    path = "/wallets/{wallet_id}/round-up",
+    tag = "round-ups",
+    params(("wallet_id" = String, Path, description = "Wallet ID")),
+    request_body = SetRoundUpRuleRequest,
+    responses(
+        (status = 200, description = "Rule saved", body = ApiResponse<RoundUpRule>),
+        (status = 400, description = "Invalid amount or request", body = ErrorResponse),
+        (status = 404, description = "Not found", body = ErrorResponse),
+        (status = 422, description = "Invalid fields, listed in `errors`", body = ErrorResponse)
+    )
+)]
+pub async fn set_round_up_rule(
+    State(state): State<AppState>,
+    Path(wallet_id): Path<String>,
+    ValidJson(payload): ValidJson<SetRoundUpRuleRequest>,
+) -> WalletResult<Json<ApiResponse<RoundUpRule>>> {
+    tracing::info!(
+        wallet_id = %wallet_id,
+        savings_wallet_id = %payload.savings_wallet_id,
+        increment = %payload.increment,
+        "Setting round-up rule"
+    );
+
+    let rule = state
+        .repository
+        .set_round_up_rule(&wallet_id, &payload.savings_wallet_id, payload.increment)
+        .await?;
+
+    Ok(Json(ApiResponse::success(rule)))
+}
+
+/// Get a wallet's round-up rule
+#[utoipa::path(
+    get,
+    path = "/wallets/{wallet_id}/round-up",
+    tag = "round-ups",
+    params(("wallet_id" = String, Path, description = "Wallet ID")),
+    responses(
+        (status = 200, description = "The rule", body = ApiResponse<RoundUpRule>),
+        (status = 404, description = "Not found", body = ErrorResponse)
+    )
+)]
+pub async fn get_round_up_rule(
+    State(state): State<AppState>,
+    Path(wallet_id): Path<String>,
+) -> WalletResult<Json<ApiResponse<RoundUpRule>>> {
+    let rule = state.repository.find_round_up_rule(&wallet_id).await?;
+
+    Ok(Json(ApiResponse::success(rule)))
+}
+
+/// Remove a wallet's round-up rule
+#[utoipa::path(
+    delete,
+    path = "/wallets/{wallet_id}/round-up",
+    tag = "round-ups",
+    params(("wallet_id" = String, Path, description = "Wallet ID")),
+    responses(
+        (status = 204, description = "Deleted"),
+        (status = 404, description = "Not found", body = ErrorResponse)
+    )
+)]
+pub async fn delete_round_up_rule(
+    State(state): State<AppState>,
+    Path(wallet_id): Path<String>,
+) -> WalletResult<StatusCode> {
+    tracing::info!(wallet_id = %wallet_id, "Deleting round-up rule");
+
+    state.repository.delete_round_up_rule(&wallet_id).await?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+// === Transaction PINs ===
+
+/// Set or change a wallet's transaction PIN
+///
+/// Transfers over `threshold` must then carry `pin`. Changing the PIN
+/// needs `current_pin`; a wrong one counts towards the lockout.
+#[utoipa::path(
+    put,
+    path = "/wallets/{wallet_id}/pin",
+    tag = "pins",
+    params(("wallet_id" = String, Path, description = "Wallet ID")),
+    request_body = SetPinRequest,
+    responses(
+        (status = 200, description = "PIN saved", body = ApiResponse<WalletPin>),
+        (status = 403, description = "current_pin missing or wrong", body = ErrorResponse),
+        (status = 404, description = "Not found", body = ErrorResponse),
+        (status = 422, description = "Invalid fields, listed in `errors`", body = ErrorResponse),
+        (status = 423, description = "PIN locked after too many wrong attempts", body = ErrorResponse)
+    )
+)]
+pub async fn set_pin(
+    State(state): State<AppState>,
+    Path(wallet_id): Path<String>,
+    ValidJson(payload): ValidJson<SetPinRequest>,
+) -> WalletResult<Json<ApiResponse<WalletPin>>> {
+    tracing::info!(wallet_id = %wallet_id, "Setting transaction PIN");
+
+    let settings = match state
+        .repository
+        .set_pin(
+            &wallet_id,
+            &payload.pin,
+            payload.current_pin.as_deref(),
+            payload.threshold.unwrap_or(Decimal::ZERO),
+            &state.pin_policy,
+        )
+        .await
+    {
+        Ok(settings) => settings,
+        Err(e) => return Err(lockout_alert(&state, &wallet_id, e).await),
+    };
+
+    Ok(Json(ApiResponse::success(settings)))
+}
+
+/// A wallet's PIN settings: threshold, failed attempts, lockout
+#[utoipa::path(
+    get,
+    path = "/wallets/{wallet_id}/pin",
+    tag = "pins",
+    params(("wallet_id" = String, Path, description = "Wallet ID")),
+    responses(
+        (status = 200, description = "The settings", body = ApiResponse<WalletPin>),
+        (status = 404, description = "Not found, or no PIN set", body = ErrorResponse)
+    )
+)]
+pub async fn get_pin(
+    State(state): State<AppState>,
+    Path(wallet_id): Path<String>,
+) -> WalletResult<Json<ApiResponse<WalletPin>>> {
+    let settings = state.repository.find_pin(&wallet_id).await?;
+
+    Ok(Json(ApiResponse::success(settings)))
+}
+
+// === Step-up verification ===
+
+/// A transfer waiting for its step-up code, or how it ended
+#[utoipa::path(
+    get,
+    path = "/wallets/{wallet_id}/pending-transfers/{pending_id}",
+    tag = "operations",
+    params(
+        ("wallet_id" = String, Path, description = "Sending wallet ID"),
+        ("pending_id" = String, Path, description = "Pending transfer ID")
+    ),
+    responses(
+        (status = 200, description = "The pending transfer", body = ApiResponse<PendingTransfer>),
+        (status = 404, description = "Not found", body = ErrorResponse)
+    )
+)]
+pub async fn get_pending_transfer(
+    State(state): State<AppState>,
+    Path((wallet_id, pending_id)): Path<(String, String)>,
+) -> WalletResult<Json<ApiResponse<PendingTransfer>>> {
+    let pending = state
+        .repository
+        .find_pending_transfer(&wallet_id, &pending_id)
+        .await?;
+
+    Ok(Json(ApiResponse::success(pending)))
+}
+
+/// Confirm a pending transfer with the code sent to the user
+///
+/// The transfer then runs like any other (balance checks included). A
+/// wrong code counts down to the transfer being cancelled, and towards
+/// locking step-up for the whole wallet.
+#[utoipa::path(
+    post,
+    path = "/wallets/{wallet_id}/pending-transfers/{pending_id}/confirm",
+    tag = "operations",
+    params(
+        ("wallet_id" = String, Path, description = "Sending wallet ID"),
+        ("pending_id" = String, Path, description = "Pending transfer ID")
+    ),
+    request_body = ConfirmTransferRequest,
+    responses(
+        (status = 200, description = "Both legs, plus round-up legs if a rule applied", body = ApiResponse<Vec<TransactionResponse>>),
+        (status = 400, description = "Invalid amount or request", body = ErrorResponse),
+        (status = 403, description = "Wrong code, or a side is blocklisted", body = ErrorResponse),
+        (status = 404, description = "Not found", body = ErrorResponse),
+        (status = 409, description = "Already confirmed, cancelled or expired", body = ErrorResponse),
+        (status = 410, description = "The code expired", body = ErrorResponse),
+        (status = 422, description = "Invalid fields, listed in `errors`", body = ErrorResponse),
+        (status = 423, description = "Step-up locked after too many wrong codes on the wallet", body = ErrorResponse),
+        (status = 429, description = "Over the wallet's velocity limit (transfers or volume per window)", body = ErrorResponse),
+        (status = 503, description = "Saved, but the event could not be published", body = ErrorResponse)
+    )
+)]
+pub async fn confirm_pending_transfer(
+    State(state): State<AppState>,
+    Path((wallet_id, pending_id)): Path<(String, String)>,
+    ValidJson(payload): ValidJson<ConfirmTransferRequest>,
+) -> WalletResult<Json<ApiResponse<Vec<TransactionResponse>>>> {
+    let pending = match state
+        .repository
+        .confirm_pending_transfer(&wallet_id, &pending_id, &payload.code, &state.step_up)
+        .await
+    {
+        Ok(pending) => pending,
+        Err(e) => return Err(lockout_alert(&state, &wallet_id, e).await),
+    };
+
+    tracing::info!(
+        pending_transfer_id = %pending_id,
+        from_wallet_id = %wallet_id,
+        "Step-up verification passed"
+    );
+
+    let from_wallet = state.repository.find_by_id(&pending.from_wallet_id).await?;
+    let to_wallet = state.repository.find_by_id(&pending.to_wallet_id).await?;
+    let legs = complete_transfer(
+        &state,
+        &from_wallet,
+        &to_wallet,
+        pending.amount,
+        pending.memo.as_deref(),
+        None,
+        Some(&pending.id),
+    )
+    .await?;
+
+    Ok(Json(ApiResponse::success(legs)))
+}
+
+/// Cancel a transfer that is still waiting for its code
+#[utoipa::path(
+    delete,
+    path = "/wallets/{wallet_id}/pending-transfers/{pending_id}",
+    tag = "operations",
+    params(
+        ("wallet_id" = String, Path, description = "Sending wallet ID"),
+        ("pending_id" = String, Path, description = "Pending transfer ID")
+    ),
+    responses(
+        (status = 200, description = "Cancelled", body = ApiResponse<PendingTransfer>),
+        (status = 404, description = "Not found", body = ErrorResponse),
+        (status = 409, description = "Already confirmed, cancelled or expired", body = ErrorResponse)
+    )
+)]
+pub async fn cancel_pending_transfer(
+    State(state): State<AppState>,
+    Path((wallet_id, pending_id)): Path<(String, String)>,
+) -> WalletResult<Json<ApiResponse<PendingTransfer>>> {
+    tracing::info!(pending_transfer_id = %pending_id, "Cancelling pending transfer");
+
+    let pending = state
+        .repository
+        .cancel_pending_transfer(&wallet_id, &pending_id)
+        .await?;
+
+    Ok(Json(ApiResponse::success(pending)))
+}
+
+// === Pots (sub-wallets) ===
+
+/// Create a pot under a wallet
+#[utoipa::path(
+    post,
+    path = "/wallets/{wallet_id}/pots",
+    tag = "pots",
+    params(("wallet_id" = String, Path, description = "Wallet ID")),
+    request_body = CreatePotRequest,
+    responses(
+        (status = 201, description = "Pot created", body = ApiResponse<WalletResponse>),
+        (status = 400, description = "Invalid amount or request", body = ErrorResponse),
+        (status = 404, description = "Not found", body = ErrorResponse)
+    )
+)]
+pub async fn create_pot(
+    State(state): State<AppState>,
+    Path(wallet_id): Path<String>,
+    ValidJson(payload): ValidJson<CreatePotRequest>,
+) -> WalletResult<(StatusCode, Json<ApiResponse<WalletResponse>>)> {
+    tracing::info!(wallet_id = %wallet_id, name = %payload.name, "Creating pot");
+
+    let pot = state.repository.create_pot(&wallet_id, &payload.name).await?;
+
+    // A pot is a wallet as far as history is concerned
+    state.kafka_producer.publish_wallet_created(&pot).await?;
+
+    Ok((StatusCode::CREATED, Json(ApiResponse::success(WalletResponse::from(pot)))))
+}
+
+/// List a wallet's pots with spendable / allocated / total balances
+#[utoipa::path(
+    get,
+    path = "/wallets/{wallet_id}/pots",
+    tag = "pots",
+    params(("wallet_id" = String, Path, description = "Wallet ID")),
+    responses(
+        (status = 200, description = "Pots with spendable, allocated and total balances", body = ApiResponse<PotsResponse>),
+        (status = 404, description = "Not found", body = ErrorResponse)
+    )
+)]
+pub async fn get_pots(
+    State(state): State<AppState>,
+    Path(wallet_id): Path<String>,
+) -> WalletResult<Json<ApiResponse<PotsResponse>>> {
+    let wallet = state.repository.find_by_id(&wallet_id).await?;
+    let pots = state.repository.find_pots(&wallet_id).await?;
+
+    let allocated_balance: Decimal = pots.iter().map(|pot| pot.balance).sum();
+
+    Ok(Json(ApiResponse::success(PotsResponse {
+        wallet_id: wallet.id,
+        spendable_balance: wallet.balance,
+        allocated_balance,
+        total_balance: wallet.balance + allocated_balance,
+        pots: pots.into_iter().map(WalletResponse::from).collect(),
+    })))
+}
+
+/// Move money from a wallet into one of its pots
+#[utoipa::path(
+    post,
+    path = "/wallets/{wallet_id}/pots/{pot_id}/deposit",
+    tag = "pots",
+    params(
+        ("wallet_id" = String, Path, description = "Wallet ID"),
+        ("pot_id" = String, Path, description = "Pot (sub-wallet) ID"),
+        ("If-Match" = Option<String>, Header, description = "Only move if the wallet is still at this ETag")
+    ),
+    request_body = PotTransferRequest,
+    responses(
+        (status = 200, description = "Both legs", body = ApiResponse<Vec<TransactionResponse>>),
+        (status = 400, description = "Invalid amount or request", body = ErrorResponse),
+        (status = 404, description = "Not found", body = ErrorResponse),
+        (status = 409, description = "Concurrent update or duplicate - retry or give up", body = ErrorResponse),
+        (status = 412, description = "If-Match is out of date - re-read the wallet", body = ErrorResponse),
+        (status = 422, description = "Invalid fields, listed in `errors`", body = ErrorResponse),
+        (status = 503, description = "Saved, but the event could not be published", body = ErrorResponse)
+    )
+)]
+pub async fn deposit_to_pot(
+    State(state): State<AppState>,
+    Path((wallet_id, pot_id)): Path<(String, String)>,
+    IfMatch(expected_version): IfMatch,
+    ValidJson(payload): ValidJson<PotTransferRequest>,
+) -> WalletResult<Json<ApiResponse<Vec<TransactionResponse>>>> {
+    tracing::info!(
+        wallet_id = %wallet_id,
+        pot_id = %pot_id,
+        amount = %payload.amount,
+        "Moving money into pot"
+    );
+
+    let (out_txn, in_txn) = state
+        .repository
+        .move_pot_expecting(&wallet_id, &pot_id, payload.amount, true, expected_version)
+        .await?;
+
+    publish_pot_transfer(&state, &wallet_id, out_txn, in_txn).await
+}
+
+/// Move money from a pot back into its wallet
+#[utoipa::path(
+    post,
+    path = "/wallets/{wallet_id}/pots/{pot_id}/withdraw",
+    tag = "pots",
+    params(
+        ("wallet_id" = String, Path, description = "Wallet ID"),
+        ("pot_id" = String, Path, description = "Pot (sub-wallet) ID"),
+        ("If-Match" = Option<String>, Header, description = "Only move if the wallet is still at this ETag")
+    ),
+    request_body = PotTransferRequest,
+    responses(
+        (status = 200, description = "Both legs", body = ApiResponse<Vec<TransactionResponse>>),
+        (status = 400, description = "Invalid amount or request", body = ErrorResponse),
+        (status = 404, description = "Not found", body = ErrorResponse),
+        (status = 409, description = "Concurrent update or duplicate - retry or give up", body = ErrorResponse),
+        (status = 412, description = "If-Match is out of date - re-read the wallet", body = ErrorResponse),
+        (status = 422, description = "Invalid fields, listed in `errors`", body = ErrorResponse),
+        (status = 503, description = "Saved, but the event could not be published", body = ErrorResponse)
+    )
+)]
+pub async fn withdraw_from_pot(
+    State(state): State<AppState>,
+    Path((wallet_id, pot_id)): Path<(String, String)>,
+    IfMatch(expected_version): IfMatch,
+    ValidJson(payload): ValidJson<PotTransferRequest>,
+) -> WalletResult<Json<ApiResponse<Vec<TransactionResponse>>>> {
+    tracing::info!(
+        wallet_id = %wallet_id,
+        pot_id = %pot_id,
+        amount = %payload.amount,
+        "Moving money out of pot"
+    );
+
+    let (out_txn, in_txn) = state
+        .repository
+        .move_pot_expecting(&wallet_id, &pot_id, payload.amount, false, expected_version)
+        .await?;
+
+    publish_pot_transfer(&state, &wallet_id, out_txn, in_txn).await
+}
+
+/// Publish a completed pot move and build the response
+async fn publish_pot_transfer(
+    state: &AppState,
+    wallet_id: &str,
+    out_txn: WalletTransaction,
+    in_txn: WalletTransaction,
+) -> WalletResult<Json<ApiResponse<Vec<TransactionResponse>>>> {
+    let wallet = state.repository.find_by_id(wallet_id).await?;
+
+    state
+        .kafka_producer
+        .publish_pot_transfer_completed(wallet.user_id, &out_txn, &in_txn)
+        .await?;
+
+    Ok(Json(ApiResponse::success(vec![
+        TransactionResponse::from(out_txn),
+        TransactionResponse::from(in_txn),
+    ])))
+}
+
+// === Vouchers (gift codes) ===
+
+/// Admin: mint a single-use voucher
+#[utoipa::path(
+    post,
+    path = "/admin/vouchers",
+    tag = "admin",
+    request_body = CreateVoucherRequest,
+    responses(
+        (status = 201, description = "Voucher minted", body = ApiResponse<Voucher>),
+        (status = 400, description = "Invalid amount or request", body = ErrorResponse),
+        (status = 422, description = "Invalid fields, listed in `errors`", body = ErrorResponse)
+    )
+)]
+pub async fn create_voucher(
+    State(state): State<AppState>,
+    principal: Option<Extension<Principal>>,
+    ValidJson(payload): ValidJson<CreateVoucherRequest>,
+) -> WalletResult<(StatusCode, Json<ApiResponse<Voucher>>)> {
+    let created_by = auth::actor(principal.as_deref());
+    tracing::info!(
+        amount = %payload.amount,
+        created_by = %created_by,
+        "Minting voucher"
+    );
+
+    let voucher = state
+        .repository
+        .create_voucher(payload.amount, &created_by)
+        .await?;
+
+    Ok((StatusCode::CREATED, Json(ApiResponse::success(voucher))))
+}
+
+/// Redeem a voucher into a wallet
+/// 
+/// Flow mirrors funding:
+/// 1. Claim the code + credit the wallet (one DB transaction)
+/// 2. Publish VOUCHER_REDEEMED
+/// 3. Return the updated wallet
+#[utoipa::path(
+    post,
+    path = "/wallets/{wallet_id}/redeem",
+    tag = "operations",
+    params(("wallet_id" = String, Path, description = "Wallet ID")),
+    request_body = RedeemVoucherRequest,
+    responses(
+        (status = 200, description = "The credited wallet", body = ApiResponse<WalletResponse>),
+        (status = 403, description = "Over the unverified balance cap", body = ErrorResponse),
+        (status = 404, description = "Not found", body = ErrorResponse),
+        (status = 409, description = "Voucher already redeemed", body = ErrorResponse),
+        (status = 503, description = "Saved, but the event could not be published", body = ErrorResponse)
+    )
+)]
+pub async fn redeem_voucher(
+    State(state): State<AppState>,
+    Path(wallet_id): Path<String>,
+    ValidJson(payload): ValidJson<RedeemVoucherRequest>,
+) -> WalletResult<Json<ApiResponse<WalletResponse>>> {
+    // Never log the code itself - whoever holds it can spend it
+    tracing::info!(wallet_id = %wallet_id, "Redeeming voucher");
+
+    let (wallet, transaction, voucher) = state
+        .repository
+        .redeem_voucher(&wallet_id, &payload.code)
+        .await
+        .inspect_err(|e| state.metrics.record_decline("redeem_voucher", e))?;
+    state.metrics.record_voucher_redeemed(voucher.amount);
+
+    state
+        .kafka_producer
+        .publish_voucher_redeemed(&wallet, &voucher, transaction.id)
+        .await?;
+
+    tracing::info!(
+        wallet_id = %wallet_id,
+        voucher_id = %voucher.id,
+        new_balance = %wallet.balance,
+        "Voucher redeemed successfully"
+    );
+
+    Ok(Json(ApiResponse::success(WalletResponse::from(wallet))))
+}
+
+// === Admin: balance adjustments ===
+
+/// Admin: credit or debit a wallet by hand
+///
+/// For manual corrections (a chargeback, a movement we got wrong...):
+/// 1. Adjust the balance and record an ADJUSTMENT transaction plus who
+///    made it and why (one DB transaction)
+/// 2. Publish WALLET_ADJUSTED
+/// 3. Return the adjustment and the updated wallet
+#[utoipa::path(
+    post,
+    path = "/admin/wallets/{wallet_id}/adjustments",
+    tag = "admin",
+    params(("wallet_id" = String, Path, description = "Wallet ID")),
+    request_body = AdjustmentRequest,
+    responses(
+        (status = 201, description = "Balance adjusted", body = ApiResponse<AdjustmentResponse>),
+        (status = 400, description = "Insufficient balance for a debit, or a pot", body = ErrorResponse),
+        (status = 404, description = "Wallet not found", body = ErrorResponse),
+        (status = 422, description = "Invalid fields (e.g. unknown reason_code), listed in `errors`", body = ErrorResponse),
+        (status = 503, description = "Saved, but the event could not be published", body = ErrorResponse)
+    )
+)]
+pub async fn create_adjustment(
+    State(state): State<AppState>,
+    principal: Option<Extension<Principal>>,
+    Path(wallet_id): Path<String>,
+    ValidJson(payload): ValidJson<AdjustmentRequest>,
+) -> WalletResult<(StatusCode, Json<ApiResponse<AdjustmentResponse>>)> {
+    let actor = auth::actor(principal.as_deref());
+    tracing::info!(
+        wallet_id = %wallet_id,
+        direction = %payload.direction,
+        amount = %payload.amount,
+        reason_code = %payload.reason_code,
+        actor = %actor,
+        "Adjusting wallet balance"
+    );
+
+    let (wallet, adjustment) = state
+        .repository
+        .adjust_balance(&wallet_id, &payload, &actor)
+        .await
+        .inspect_err(|e| state.metrics.record_decline("adjust_balance", e))?;
+
+    state
+        .kafka_producer
+        .publish_wallet_adjusted(&wallet, &adjustment)
+        .await?;
+
+    tracing::info!(
+        wallet_id = %wallet_id,
+        adjustment_id = %adjustment.id,
+        new_balance = %wallet.balance,
+        "Wallet balance adjusted"
+    );
+
+    Ok((
+        StatusCode::CREATED,
+        Json(ApiResponse::success(AdjustmentResponse {
+            adjustment,
+            wallet: WalletResponse::from(wallet),
+        })),
+    ))
+}
+
+// === Admin: balance shards ===
+
+/// Admin: how a wallet's balance is split over shard rows
+#[utoipa::path(
+    get,
+    path = "/admin/wallets/{wallet_id}/balance-shards",
+    tag = "admin",
+    params(("wallet_id" = String, Path, description = "Wallet ID")),
+    responses(
+        (status = 200, description = "The shards (none if not sharded)", body = ApiResponse<BalanceShards>),
+        (status = 404, description = "Wallet not found", body = ErrorResponse)
+    )
+)]
+pub async fn get_balance_shards(
+    State(state): State<AppState>,
+    Path(wallet_id): Path<String>,
+) -> WalletResult<Json<ApiResponse<BalanceShards>>> {
+    let shards = state.repository.find_balance_shards(&wallet_id).await?;
+    Ok(Json(ApiResponse::success(shards)))
+}
+
+/// Admin: split a hot wallet's balance over `shards` rows (0 = off)
+///
+/// Transfers into it then credit one shard each, round-robin, instead of
+/// queueing on the wallet row. Changing the count folds the shards back
+/// into the wallet first; recorded in the audit log.
+#[utoipa::path(
+    put,
+    path = "/admin/wallets/{wallet_id}/balance-shards",
+    tag = "admin",
+    params(("wallet_id" = String, Path, description = "Wallet ID")),
+    request_body = SetBalanceShardsRequest,
+    responses(
+        (status = 200, description = "The new shards", body = ApiResponse<BalanceShards>),
+        (status = 400, description = "Pots can't be sharded", body = ErrorResponse),
+        (status = 404, description = "Wallet not found", body = ErrorResponse),
+        (status = 422, description = "Invalid fields, listed in `errors`", body = ErrorResponse)
+    )
+)]
+pub async fn set_balance_shards(
+    State(state): State<AppState>,
+    principal: Option<Extension<Principal>>,
+    Path(wallet_id): Path<String>,
+    ValidJson(payload): ValidJson<SetBalanceShardsRequest>,
+) -> WalletResult<Json<ApiResponse<BalanceShards>>> {
+    let actor = auth::actor(principal.as_deref());
+    tracing::info!(wallet_id = %wallet_id, shards = payload.shards, actor = %actor, "Setting balance shards");
+
+    let shards = state
+        .repository
+        .set_balance_shards(&wallet_id, payload.shards, &actor)
+        .await?;
+    Ok(Json(ApiResponse::success(shards)))
+}
+
+// === Admin: fraud review ===
+
+/// Admin: transfers held by the fraud rules, oldest first
+#[utoipa::path(
+    get,
+    path = "/admin/held-transfers",
+    tag = "admin",
+    responses(
+        (status = 200, description = "Up to 100 held transfers", body = ApiResponse<Vec<PendingTransfer>>)
+    )
+)]
+pub async fn get_held_transfers(
+    State(state): State<AppState>,
+) -> WalletResult<Json<ApiResponse<Vec<PendingTransfer>>>> {
+    let held = state.repository.find_held_transfers(100).await?;
+    Ok(Json(ApiResponse::success(held)))
+}
+
+/// Admin: let a held transfer go ahead
+///
+/// It runs like any other transfer from here (balance checks included);
+/// the release is recorded in the audit log.
+#[utoipa::path(
+    post,
+    path = "/admin/held-transfers/{pending_id}/release",
+    tag = "admin",
+    params(("pending_id" = String, Path, description = "Pending transfer ID")),
+    responses(
+        (status = 200, description = "The transfer legs", body = ApiResponse<Vec<TransactionResponse>>),
+        (status = 400, description = "Insufficient balance", body = ErrorResponse),
+        (status = 403, description = "A side is blocklisted", body = ErrorResponse),
+        (status = 404, description = "Not found", body = ErrorResponse),
+        (status = 409, description = "Not held (any more)", body = ErrorResponse),
+        (status = 429, description = "Over the wallet's velocity limit (transfers or volume per window)", body = ErrorResponse),
+        (status = 503, description = "Saved, but the event could not be published", body = ErrorResponse)
+    )
+)]
+pub async fn release_held_transfer(
+    State(state): State<AppState>,
+    principal: Option<Extension<Principal>>,
+    Path(pending_id): Path<String>,
+) -> WalletResult<Json<ApiResponse<Vec<TransactionResponse>>>> {
+    let actor = auth::actor(principal.as_deref());
+    tracing::info!(pending_transfer_id = %pending_id, actor = %actor, "Releasing held transfer");
+
+    let pending = state
+        .repository
+        .release_held_transfer(&pending_id, &actor)
+        .await?;
+    let from_wallet = state.repository.find_by_id(&pending.from_wallet_id).await?;
+    let to_wallet = state.repository.find_by_id(&pending.to_wallet_id).await?;
+    let legs = complete_transfer(
+        &state,
+        &from_wallet,
+        &to_wallet,
+        pending.amount,
+        pending.memo.as_deref(),
+        None,
+        Some(&pending.id),
+    )
+    .await?;
+
+    Ok(Json(ApiResponse::success(legs)))
+}
+
+/// Admin: refuse a held transfer
+///
+/// Published as a declined transfer (`fraud_rejected`); the rejection is
+/// recorded in the audit log.
+#[utoipa::path(
+    post,
+    path = "/admin/held-transfers/{pending_id}/reject",
+    tag = "admin",
+    params(("pending_id" = String, Path, description = "Pending transfer ID")),
+    responses(
+        (status = 200, description = "Rejected", body = ApiResponse<PendingTransfer>),
+        (status = 404, description = "Not found", body = ErrorResponse),
+        (status = 409, description = "Not held (any more)", body = ErrorResponse)
+    )
+)]
+pub async fn reject_held_transfer(
+    State(state): State<AppState>,
+    principal: Option<Extension<Principal>>,
+    Path(pending_id): Path<String>,
+) -> WalletResult<Json<ApiResponse<PendingTransfer>>> {
+    let actor = auth::actor(principal.as_deref());
+    tracing::info!(pending_transfer_id = %pending_id, actor = %actor, "Rejecting held transfer");
+
+    let pending = state
+        .repository
+        .reject_held_transfer(&pending_id, &actor)
+        .await?;
+    if let Ok(from_wallet) = state.repository.find_by_id(&pending.from_wallet_id).await {
+        let reason = pending.hold_reason.clone().unwrap_or_default();
+        transfer_declined(
+            &state,
+            &from_wallet,
+            &pending.to_wallet_id,
+            pending.amount,
+            WalletError::FraudRejected(reason),
+        )
+        .await;
+    }
+
+    Ok(Json(ApiResponse::success(pending)))
+}
+
+// === Admin: support case linkage ===
+
+/// Attach a support note / case ID to a transaction
+/// 
+/// Notes live in their own table - the transaction record is untouched
+#[utoipa::path(
+    post,
+    path = "/admin/transactions/{transaction_id}/notes",
+    tag = "admin",
+    params(("transaction_id" = String, Path, description = "Transaction ID")),
+    request_body = CreateTransactionNoteRequest,
+    responses(
+        (status = 201, description = "Note attached", body = ApiResponse<TransactionNote>),
+        (status = 400, description = "Invalid amount or request", body = ErrorResponse),
+        (status = 404, description = "Not found", body = ErrorResponse)
+    )
+)]
+pub async fn add_transaction_note(
+    State(state): State<AppState>,
+    principal: Option<Extension<Principal>>,
+    Path(transaction_id): Path<String>,
+    ValidJson(payload): ValidJson<CreateTransactionNoteRequest>,
+) -> WalletResult<(StatusCode, Json<ApiResponse<TransactionNote>>)> {
+    let author = auth::actor(principal.as_deref());
+    tracing::info!(
+        transaction_id = %transaction_id,
+        case_id = ?payload.case_id,
+        author = %author,
+        "Adding transaction note"
+    );
+
+    let note = state
+        .repository
+        .add_transaction_note(
+            &transaction_id,
+            payload.case_id.as_deref(),
+            &payload.note,
+            &author,
+        )
+        .await?;
+
+    Ok((StatusCode::CREATED, Json(ApiResponse::success(note))))
+}
+
+/// Admin view of a single transaction with its support notes
+#[utoipa::path(
+    get,
+    path = "/admin/transactions/{transaction_id}",
+    tag = "admin",
+    params(("transaction_id" = String, Path, description = "Transaction ID")),
+    responses(
+        (status = 200, description = "The transaction with its notes", body = ApiResponse<AdminTransactionResponse>),
+        (status = 404, description = "Not found", body = ErrorResponse)
+    )
+)]
+pub async fn get_admin_transaction(
+    State(state): State<AppState>,
+    Path(transaction_id): Path<String>,
+) -> WalletResult<Json<ApiResponse<AdminTransactionResponse>>> {
+    let transaction = state.repository.find_transaction(&transaction_id).await?;
+    let notes = state
+        .repository
+        .find_transaction_notes(&transaction_id)
+        .await?;
+
+    Ok(Json(ApiResponse::success(AdminTransactionResponse {
+        transaction: TransactionResponse::from(transaction),
+        notes,
+    })))
+}
+
+/// Admin: list wallets by user, balance and creation time, a page at a time
+///
+/// Pages are offsets (`?page=3`), newest first - support screens jump to a
+/// page and show "page 3 of 12", which a cursor can't.
+#[utoipa::path(
+    get,
+    path = "/admin/wallets",
+    tag = "admin",
+    params(AdminWalletQuery),
+    responses(
+        (status = 200, description = "One page of matching wallets", body = ApiResponse<AdminWalletPage>),
+        (status = 400, description = "Invalid filter or page", body = ErrorResponse)
+    )
+)]
+pub async fn get_admin_wallets(
+    State(state): State<AppState>,
+    Query(query): Query<AdminWalletQuery>,
+) -> WalletResult<Json<ApiResponse<AdminWalletPage>>> {
+    let (page, page_size) = admin_wallet_paging(&query)?;
+
+    let (wallets, total) = state
+        .repository
+        .find_wallets(&query, page_size, (page - 1) * page_size)
+        .await?;
+
+    Ok(Json(ApiResponse::success(AdminWalletPage {
+        wallets: wallets.into_iter().map(WalletResponse::from).collect(),
+        page,
+        page_size,
+        total,
+    })))
+}
+
+/// Wallets per admin listing page unless `page_size` says otherwise
+pub const ADMIN_WALLETS_PAGE_SIZE_DEFAULT: i64 = 50;
+
+/// Largest `page_size` on GET /admin/wallets
+pub const ADMIN_WALLETS_PAGE_SIZE_MAX: i64 = 200;
+
+/// `(page, page_size)` for an admin wallet listing, after checking the
+/// filters the database can't (an unknown `status` would otherwise just
+/// match nothing)
+pub fn admin_wallet_paging(query: &AdminWalletQuery) -> WalletResult<(i64, i64)> {
+    if let Some(status) = query.status.as_deref() {
+        if status != STATUS_ACTIVE {
+            return Err(WalletError::InvalidRequest(format!(
+                "Unknown status: {} (expected {})",
+                status, STATUS_ACTIVE
+            )));
+        }
+    }
+
+    let page = query.page.unwrap_or(1);
+    if page < 1 {
+        return Err(WalletError::InvalidRequest("page must be 1 or more".to_string()));
+    }
+    let page_size = query.page_size.unwrap_or(ADMIN_WALLETS_PAGE_SIZE_DEFAULT);
+    if !(1..=ADMIN_WALLETS_PAGE_SIZE_MAX).contains(&page_size) {
+        return Err(WalletError::InvalidRequest(format!(
+            "page_size must be between 1 and {}",
+            ADMIN_WALLETS_PAGE_SIZE_MAX
+        )));
+    }
+
+    Ok((page, page_size))
+}
+
+/// Admin listing of transactions linked to a support case
+#[utoipa::path(
+    get,
+    path = "/admin/transactions",
+    tag = "admin",
+    params(AdminTransactionQuery),
+    responses(
+        (status = 200, description = "Transactions linked to the case", body = ApiResponse<Vec<AdminTransactionResponse>>)
+    )
+)]
+pub async fn get_admin_transactions(
+    State(state): State<AppState>,
+    Query(query): Query<AdminTransactionQuery>,
+) -> WalletResult<Json<ApiResponse<Vec<AdminTransactionResponse>>>> {
+    tracing::debug!(case_id = %query.case_id, "Fetching transactions for case");
+
+    let transactions = state
+        .repository
+        .find_transactions_by_case(&query.case_id)
+        .await?;
+
+    let mut response = Vec::with_capacity(transactions.len());
+    for transaction in transactions {
+        let notes = state.repository.find_transaction_notes(&transaction.id).await?;
+        response.push(AdminTransactionResponse {
+            transaction: TransactionResponse::from(transaction),
+            notes,
+        });
+    }
+
+    Ok(Json(ApiResponse::success(response)))
+}
+
+/// Admin: the audit log of privileged operations, newest first
+///
+/// Adjustments, voucher minting and support notes each write an entry in
+/// the same DB transaction as the change; entries are never edited.
+#[utoipa::path(
+    get,
+    path = "/admin/audit-log",
+    tag = "admin",
+    params(AuditLogQuery),
+    responses(
+        (status = 200, description = "Matching entries, newest first", body = ApiResponse<Vec<AdminAuditEntry>>),
+        (status = 400, description = "Invalid limit", body = ErrorResponse)
+    )
+)]
+pub async fn get_audit_log(
+    State(state): State<AppState>,
+    Query(query): Query<AuditLogQuery>,
+) -> WalletResult<Json<ApiResponse<Vec<AdminAuditEntry>>>> {
+    let limit = query.limit.unwrap_or(AUDIT_LOG_LIMIT_DEFAULT);
+    if !(1..=AUDIT_LOG_LIMIT_MAX).contains(&limit) {
+        return Err(WalletError::InvalidRequest(format!(
+            "limit must be between 1 and {}",
+            AUDIT_LOG_LIMIT_MAX
+        )));
+    }
+
+    let entries = state.repository.find_audit_entries(&query, limit).await?;
+
+    Ok(Json(ApiResponse::success(entries)))
+}
+
+/// Audit entries returned unless `limit` says otherwise
+pub const AUDIT_LOG_LIMIT_DEFAULT: i64 = 100;
+
+/// Largest `limit` on GET /admin/audit-log (narrow with `since` instead)
+pub const AUDIT_LOG_LIMIT_MAX: i64 = 500;
+
+/// Admin: check the transaction ledger's hash chains
+///
+/// Every transaction is copied into an append-only ledger, chained per
+/// wallet by SHA-256. This recomputes the chains and compares each entry
+/// with its transaction. Checking every wallet reads the whole ledger -
+/// on large databases run the `verify-ledger` binary instead.
+#[utoipa::path(
+    get,
+    path = "/admin/ledger/verify",
+    tag = "admin",
+    params(LedgerVerifyQuery),
+    responses(
+        (status = 200, description = "What was checked and any breaks found", body = ApiResponse<LedgerVerification>)
+    )
+)]
+pub async fn verify_ledger(
+    State(state): State<AppState>,
+    Query(query): Query<LedgerVerifyQuery>,
+) -> WalletResult<Json<ApiResponse<LedgerVerification>>> {
+    let report = state
+        .repository
+        .verify_ledger(query.wallet_id.as_deref(), ledger::VERIFY_BATCH_SIZE)
+        .await?;
+    if !report.intact {
+        tracing::error!(
+            break_count = report.break_count,
+            wallet_id = ?query.wallet_id,
+            "Transaction ledger verification found breaks"
+        );
+    }
+
+    Ok(Json(ApiResponse::success(report)))
+}
+
+/// Archive inactive wallets now (instead of waiting for the background job)
+///
+/// Only empty wallets without pots, untouched for `inactive_days`, are
+/// moved - with their transactions, notes and adjustments - into the
+/// archive tables. Each one gets a WALLET_ARCHIVED audit entry.
+#[utoipa::path(
+    post,
+    path = "/admin/wallets/archive",
+    tag = "admin",
+    request_body = ArchiveWalletsRequest,
+    responses(
+        (status = 200, description = "Wallets archived (may be none)", body = ApiResponse<ArchiveRunResponse>),
+        (status = 422, description = "Invalid request body", body = ErrorResponse)
+    )
+)]
+pub async fn archive_wallets(
+    State(state): State<AppState>,
+    principal: Option<Extension<Principal>>,
+    ValidJson(payload): ValidJson<ArchiveWalletsRequest>,
+) -> WalletResult<Json<ApiResponse<ArchiveRunResponse>>> {
+    let actor = auth::actor(principal.as_deref());
+    let inactive_days = payload.inactive_days.unwrap_or(state.archive.inactive_days);
+    let cutoff = archive::cutoff(chrono::Utc::now(), inactive_days);
+    let limit = payload.limit.unwrap_or(state.archive.batch_size);
+
+    let archived_wallet_ids = state
+        .repository
+        .archive_inactive_wallets(cutoff, limit, &actor)
+        .await?;
+
+    tracing::info!(
+        actor = %actor,
+        archived = archived_wallet_ids.len(),
+        "Archived inactive wallets"
+    );
+
+    Ok(Json(ApiResponse::success(ArchiveRunResponse {
+        cutoff,
+        archived_wallet_ids,
+    })))
+}
+
+/// An archived wallet and its full history (audits, disputes)
+#[utoipa::path(
+    get,
+    path = "/admin/archived-wallets/{wallet_id}",
+    tag = "admin",
+    params(("wallet_id" = String, Path, description = "Wallet ID")),
+    responses(
+        (status = 200, description = "Archived wallet", body = ApiResponse<ArchivedWalletResponse>),
+        (status = 404, description = "No archived wallet with that ID", body = ErrorResponse)
+    )
+)]
+pub async fn get_archived_wallet(
+    State(state): State<AppState>,
+    Path(wallet_id): Path<String>,
+) -> WalletResult<Json<ApiResponse<ArchivedWalletResponse>>> {
+    let (wallet, archived_at, transactions) =
+        state.repository.find_archived_wallet(&wallet_id).await?;
+
+    Ok(Json(ApiResponse::success(ArchivedWalletResponse {
+        wallet: WalletResponse::from(wallet),
+        archived_at,
+        transactions: transactions.into_iter().map(TransactionResponse::from).collect(),
+    })))
+}
+
+/// Transactions sharing a reference_id (support tooling, reconciliation)
+///
+/// No match is an empty list, not a 404 - "we have nothing for that
+/// reference" is an answer reconciliation needs.
+#[utoipa::path(
+    get,
+    path = "/transactions",
+    tag = "transactions",
+    params(TransactionSearchQuery),
+    responses(
+        (status = 200, description = "Matching transactions, oldest first (may be empty)", body = ApiResponse<Vec<TransactionResponse>>),
+        (status = 400, description = "Invalid amount or request", body = ErrorResponse)
+    )
+)]
+pub async fn search_transactions(
+    State(state): State<AppState>,
+    principal: Option<Extension<Principal>>,
+    Query(query): Query<TransactionSearchQuery>,
+) -> WalletResult<Json<ApiResponse<Vec<TransactionResponse>>>> {
+    let reference_id = query.reference_id.trim();
+    if reference_id.is_empty() {
+        return Err(WalletError::InvalidRequest(
+            "reference_id must not be empty".to_string(),
+        ));
+    }
+
+    let mut transactions = state
+        .repository
+        .find_transactions_by_reference(reference_id)
+        .await?;
+
+    // Only legs on wallets the caller may see
+    if let Some(Extension(principal)) = &principal {
+        let wallet_ids: Vec<String> = transactions.iter().map(|t| t.wallet_id.clone()).collect();
+        let owned: HashSet<String> = state
+            .repository
+            .find_by_ids(&wallet_ids)
+            .await?
+            .into_iter()
+            .filter(|wallet| principal.can_read(&wallet.user_id))
+            .map(|wallet| wallet.id)
+            .collect();
+        transactions.retain(|t| owned.contains(&t.wallet_id));
+    }
+
+    Ok(Json(ApiResponse::success(
+        transactions.into_iter().map(TransactionResponse::from).collect(),
+    )))
+}
+
+/// A transaction's receipt - the "payment details" screen in one call
+#[utoipa::path(
+    get,
+    path = "/transactions/{transaction_id}/receipt",
+    tag = "transactions",
+    params(("transaction_id" = String, Path, description = "Transaction ID")),
+    responses(
+        (status = 200, description = "The transaction with its legs, counterparties and resulting balance", body = ApiResponse<TransactionReceipt>),
+        (status = 404, description = "Not found", body = ErrorResponse)
+    )
+)]
+pub async fn get_transaction_receipt(
+    State(state): State<AppState>,
+    Path(transaction_id): Path<String>,
+) -> WalletResult<Json<ApiResponse<TransactionReceipt>>> {
+    let receipt = state
+        .repository
+        .find_transaction_receipt(&transaction_id)
+        .await?;
+
+    Ok(Json(ApiResponse::success(receipt)))
+}
+
+/// Most deliveries GET /webhooks/:id/deliveries returns
+const WEBHOOK_DELIVERIES_LIMIT: i64 = 100;
+
+/// Register a webhook: matching events are POSTed to `url`, signed
+///
+/// The response is the only time the signing secret is shown.
+#[utoipa::path(
+    post,
+    path = "/webhooks",
+    tag = "webhooks",
+    request_body = CreateWebhookRequest,
+    responses(
+        (status = 201, description = "Registered; store the secret", body = ApiResponse<CreatedWebhook>),
+        (status = 400, description = "Invalid URL or event type", body = ErrorResponse)
+    )
+)]
+pub async fn create_webhook(
+    State(state): State<AppState>,
+    ValidJson(payload): ValidJson<CreateWebhookRequest>,
+) -> WalletResult<(StatusCode, Json<ApiResponse<CreatedWebhook>>)> {
+    tracing::info!(url = %payload.url, event_types = ?payload.event_types, "Registering webhook");
+
+    let subscription = state
+        .repository
+        .create_webhook(&payload.url, &payload.event_types, payload.description.as_deref())
+        .await?;
+    let secret = subscription.secret.clone();
+
+    Ok((
+        StatusCode::CREATED,
+        Json(ApiResponse::success(CreatedWebhook { subscription, secret })),
+    ))
+}
+
+/// List webhook subscriptions (secrets not included)
+#[utoipa::path(
+    get,
+    path = "/webhooks",
+    tag = "webhooks",
+    responses(
+        (status = 200, description = "Every subscription, oldest first", body = ApiResponse<Vec<WebhookSubscription>>)
+    )
+)]
+pub async fn get_webhooks(
+    State(state): State<AppState>,
+) -> WalletResult<Json<ApiResponse<Vec<WebhookSubscription>>>> {
+    let webhooks = state.repository.find_webhooks().await?;
+    Ok(Json(ApiResponse::success(webhooks)))
+}
+
+/// One webhook subscription
+#[utoipa::path(
+    get,
+    path = "/webhooks/{webhook_id}",
+    tag = "webhooks",
+    params(("webhook_id" = String, Path, description = "Webhook ID")),
+    responses(
+        (status = 200, description = "The subscription", body = ApiResponse<WebhookSubscription>),
+        (status = 404, description = "Not found", body = ErrorResponse)
+    )
+)]
+pub async fn get_webhook(
+    State(state): State<AppState>,
+    Path(webhook_id): Path<String>,
+) -> WalletResult<Json<ApiResponse<WebhookSubscription>>> {
+    let webhook = state.repository.find_webhook(&webhook_id).await?;
+    Ok(Json(ApiResponse::success(webhook)))
+}
+
+/// Change a webhook's URL, event types or description, or pause it
+/// (`"active": false`)
+#[utoipa::path(
+    put,
+    path = "/webhooks/{webhook_id}",
+    tag = "webhooks",
+    params(("webhook_id" = String, Path, description = "Webhook ID")),
+    request_body = UpdateWebhookRequest,
+    responses(
+        (status = 200, description = "Updated", body = ApiResponse<WebhookSubscription>),
+        (status = 400, description = "Invalid URL or event type", body = ErrorResponse),
+        (status = 404, description = "Not found", body = ErrorResponse)
+    )
+)]
+pub async fn update_webhook(
+    State(state): State<AppState>,
+    Path(webhook_id): Path<String>,
+    ValidJson(payload): ValidJson<UpdateWebhookRequest>,
+) -> WalletResult<Json<ApiResponse<WebhookSubscription>>> {
+    tracing::info!(webhook_id = %webhook_id, "Updating webhook");
+
+    let webhook = state.repository.update_webhook(&webhook_id, &payload).await?;
+    Ok(Json(ApiResponse::success(webhook)))
+}
+
+/// Delete a webhook; its queued deliveries are dropped
+#[utoipa::path(
+    delete,
+    path = "/webhooks/{webhook_id}",
+    tag = "webhooks",
+    params(("webhook_id" = String, Path, description = "Webhook ID")),
+    responses(
+        (status = 204, description = "Deleted"),
+        (status = 404, description = "Not found", body = ErrorResponse)
+    )
+)]
+pub async fn delete_webhook(
+    State(state): State<AppState>,
+    Path(webhook_id): Path<String>,
+) -> WalletResult<StatusCode> {
+    tracing::info!(webhook_id = %webhook_id, "Deleting webhook");
+
+    state.repository.delete_webhook(&webhook_id).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// A webhook's latest deliveries (newest 100) with their status and last error
+#[utoipa::path(
+    get,
+    path = "/webhooks/{webhook_id}/deliveries",
+    tag = "webhooks",
+    params(("webhook_id" = String, Path, description = "Webhook ID")),
+    responses(
+        (status = 200, description = "Deliveries, newest first", body = ApiResponse<Vec<WebhookDelivery>>),
+        (status = 404, description = "Not found", body = ErrorResponse)
+    )
+)]
+pub async fn get_webhook_deliveries(
+    State(state): State<AppState>,
+    Path(webhook_id): Path<String>,
+) -> WalletResult<Json<ApiResponse<Vec<WebhookDelivery>>>> {
+    let deliveries = state
+        .repository
+        .find_webhook_deliveries(&webhook_id, WEBHOOK_DELIVERIES_LIMIT)
+        .await?;
+    Ok(Json(ApiResponse::success(deliveries)))
+}
+
+/// Every HTTP attempt at one delivery (status code, error, duration)
+#[utoipa::path(
+    get,
+    path = "/webhooks/{webhook_id}/deliveries/{delivery_id}/attempts",
+    tag = "webhooks",
+    params(
+        ("webhook_id" = String, Path, description = "Webhook ID"),
+        ("delivery_id" = i64, Path, description = "Delivery ID")
+    ),
+    responses(
+        (status = 200, description = "Attempts, oldest first (empty if not yet tried)", body = ApiResponse<Vec<WebhookDeliveryAttempt>>)
+    )
+)]
+pub async fn get_webhook_delivery_attempts(
+    State(state): State<AppState>,
+    Path((webhook_id, delivery_id)): Path<(String, i64)>,
+) -> WalletResult<Json<ApiResponse<Vec<WebhookDeliveryAttempt>>>> {
+    let attempts = state
+        .repository
+        .find_webhook_delivery_attempts(&webhook_id, delivery_id)
+        .await?;
+    Ok(Json(ApiResponse::success(attempts)))
+}
+
+// === API keys (machine clients) ===
+
+/// Admin: issue an API key for another service
+///
+/// The response is the only time the key is shown; we keep its hash.
+#[utoipa::path(
+    post,
+    path = "/admin/api-keys",
+    tag = "admin",
+    request_body = CreateApiKeyRequest,
+    responses(
+        (status = 201, description = "Issued; store the key", body = ApiResponse<CreatedApiKey>),
+        (status = 422, description = "Invalid fields, listed in `errors`", body = ErrorResponse)
+    )
+)]
+pub async fn create_api_key(
+    State(state): State<AppState>,
+    principal: Option<Extension<Principal>>,
+    ValidJson(payload): ValidJson<CreateApiKeyRequest>,
+) -> WalletResult<(StatusCode, Json<ApiResponse<CreatedApiKey>>)> {
+    let created_by = auth::actor(principal.as_deref());
+    tracing::info!(
+        name = %payload.name,
+        scope = %payload.scope,
+        created_by = %created_by,
+        "Issuing API key"
+    );
+
+    let (api_key, key) = state.repository.create_api_key(&payload, &created_by).await?;
+
+    Ok((
+        StatusCode::CREATED,
+        Json(ApiResponse::success(CreatedApiKey { api_key, key })),
+    ))
+}
+
+/// Admin: list API keys (hashes and keys not included)
+#[utoipa::path(
+    get,
+    path = "/admin/api-keys",
+    tag = "admin",
+    responses(
+        (status = 200, description = "Every key, revoked ones too, oldest first", body = ApiResponse<Vec<ApiKey>>)
+    )
+)]
+pub async fn get_api_keys(
+    State(state): State<AppState>,
+) -> WalletResult<Json<ApiResponse<Vec<ApiKey>>>> {
+    let keys = state.repository.find_api_keys().await?;
+    Ok(Json(ApiResponse::success(keys)))
+}
+
+/// Admin: revoke an API key (takes effect on the key's next request)
+#[utoipa::path(
+    post,
+    path = "/admin/api-keys/{key_id}/revoke",
+    tag = "admin",
+    params(("key_id" = String, Path, description = "API key ID")),
+    responses(
+        (status = 200, description = "Revoked", body = ApiResponse<ApiKey>),
+        (status = 404, description = "Not found", body = ErrorResponse)
+    )
+)]
+pub async fn revoke_api_key(
+    State(state): State<AppState>,
+    principal: Option<Extension<Principal>>,
+    Path(key_id): Path<String>,
+) -> WalletResult<Json<ApiResponse<ApiKey>>> {
+    let actor = auth::actor(principal.as_deref());
+    tracing::info!(key_id = %key_id, actor = %actor, "Revoking API key");
+
+    let api_key = state.repository.revoke_api_key(&key_id, &actor).await?;
+    Ok(Json(ApiResponse::success(api_key)))
+}
+
+// === Admin: KYC ===
+
+/// Admin: a user's KYC level
+#[utoipa::path(
+    get,
+    path = "/admin/users/{user_id}/kyc",
+    tag = "admin",
+    params(("user_id" = String, Path, description = "User ID")),
+    responses(
+        (status = 200, description = "The level (unverified if never set)", body = ApiResponse<UserKyc>)
+    )
+)]
+pub async fn get_user_kyc(
+    State(state): State<AppState>,
+    Path(user_id): Path<String>,
+) -> WalletResult<Json<ApiResponse<UserKyc>>> {
+    let kyc = state.repository.find_user_kyc(&user_id).await?;
+    Ok(Json(ApiResponse::success(kyc)))
+}
+
+/// Admin: set a user's KYC level
+///
+/// Verified users are free of the KYC_UNVERIFIED_* caps from their next
+/// operation on; recorded in the audit log.
+#[utoipa::path(
+    put,
+    path = "/admin/users/{user_id}/kyc",
+    tag = "admin",
+    params(("user_id" = String, Path, description = "User ID")),
+    request_body = SetKycLevelRequest,
+    responses(
+        (status = 200, description = "The new level", body = ApiResponse<UserKyc>),
+        (status = 422, description = "Invalid fields, listed in `errors`", body = ErrorResponse)
+    )
+)]
+pub async fn set_user_kyc(
+    State(state): State<AppState>,
+    principal: Option<Extension<Principal>>,
+    Path(user_id): Path<String>,
+    ValidJson(payload): ValidJson<SetKycLevelRequest>,
+) -> WalletResult<Json<ApiResponse<UserKyc>>> {
+    let actor = auth::actor(principal.as_deref());
+    tracing::info!(user_id = %user_id, level = %payload.level, actor = %actor, "Setting KYC level");
+
+    let kyc = state
+        .repository
+        .set_user_kyc(&user_id, payload.level, &actor)
+        .await?;
+    Ok(Json(ApiResponse::success(kyc)))
+}
+
+// === Admin: blocklist ===
+
+/// Admin: blocklist a wallet or user
+///
+/// Transfers to or from it are refused (403, `blocklisted`) from the next
+/// one on; published as BLOCKLIST_ENTRY_ADDED.
+#[utoipa::path(
+    post,
+    path = "/admin/blocklist",
+    tag = "admin",
+    request_body = CreateBlocklistEntryRequest,
+    responses(
+        (status = 201, description = "Blocklisted", body = ApiResponse<BlocklistEntry>),
+        (status = 409, description = "Already blocklisted", body = ErrorResponse),
+        (status = 422, description = "Invalid fields, listed in `errors`", body = ErrorResponse),
+        (status = 503, description = "Saved, but the event could not be published", body = ErrorResponse)
+    )
+)]
+pub async fn create_blocklist_entry(
+    State(state): State<AppState>,
+    principal: Option<Extension<Principal>>,
+    ValidJson(payload): ValidJson<CreateBlocklistEntryRequest>,
+) -> WalletResult<(StatusCode, Json<ApiResponse<BlocklistEntry>>)> {
+    let created_by = auth::actor(principal.as_deref());
+    tracing::info!(
+        target_type = %payload.target_type,
+        target_id = %payload.target_id,
+        created_by = %created_by,
+        "Adding blocklist entry"
+    );
+
+    let entry = state
+        .repository
+        .create_blocklist_entry(&payload, &created_by)
+        .await?;
+    state
+        .kafka_producer
+        .publish_blocklist_entry_added(&entry)
+        .await?;
+
+    Ok((StatusCode::CREATED, Json(ApiResponse::success(entry))))
+}
+
+/// Admin: list blocklist entries
+#[utoipa::path(
+    get,
+    path = "/admin/blocklist",
+    tag = "admin",
+    responses(
+        (status = 200, description = "Every entry, removed ones too, newest first", body = ApiResponse<Vec<BlocklistEntry>>)
+    )
+)]
+pub async fn get_blocklist(
+    State(state): State<AppState>,
+) -> WalletResult<Json<ApiResponse<Vec<BlocklistEntry>>>> {
+    let entries = state.repository.find_blocklist_entries().await?;
+    Ok(Json(ApiResponse::success(entries)))
+}
+
+/// Admin: take a wallet or user off the blocklist
+#[utoipa::path(
+    post,
+    path = "/admin/blocklist/{entry_id}/remove",
+    tag = "admin",
+    params(("entry_id" = String, Path, description = "Blocklist entry ID")),
+    responses(
+        (status = 200, description = "Removed", body = ApiResponse<BlocklistEntry>),
+        (status = 404, description = "Not found", body = ErrorResponse),
+        (status = 503, description = "Saved, but the event could not be published", body = ErrorResponse)
+    )
+)]
+pub async fn remove_blocklist_entry(
+    State(state): State<AppState>,
+    principal: Option<Extension<Principal>>,
+    Path(entry_id): Path<String>,
+) -> WalletResult<Json<ApiResponse<BlocklistEntry>>> {
+    let actor = auth::actor(principal.as_deref());
+    tracing::info!(entry_id = %entry_id, actor = %actor, "Removing blocklist entry");
+
+    let entry = state
+        .repository
+        .remove_blocklist_entry(&entry_id, &actor)
+        .await?;
+    state
+        .kafka_producer
+        .publish_blocklist_entry_removed(&entry)
+        .await?;
+
+    Ok(Json(ApiResponse::success(entry)))
+}
+
+/// Business KPIs in the OpenMetrics text format (for Prometheus-style scrapers)
+#[utoipa::path(
+    get,
+    path = "/metrics",
+    tag = "service",
+    responses(
+        (status = 200, description = "Business KPIs, pool and degradation gauges", content_type = "application/openmetrics-text")
+    )
+)]
+pub async fn get_metrics(
+    State(state): State<AppState>,
+) -> ([(header::HeaderName, &'static str); 1], String) {
+    // Gauges first - the business render ends with "# EOF"
+    let body = db_pool::render_metrics(&state.repository.pool_stats())
+        + &state.degradation.render_metrics()
+        + &state.metrics.render();
+
+    ([(header::CONTENT_TYPE, OPENMETRICS_CONTENT_TYPE)], body)
+}
+
+/// Current degradation state (thresholds crossed, last probe readings)
+#[utoipa::path(
+    get,
+    path = "/health/degradation",
+    tag = "service",
+    responses(
+        (status = 200, description = "Degraded-mode state, probe readings and reasons")
+    )
+)]
+pub async fn get_degradation_status(
+    State(state): State<AppState>,
+) -> Json<ApiResponse<DegradationStatus>> {
+    Json(ApiResponse::success(state.degradation.status()))
+}
+
+/// Kafka producer internals: queue depth, delivery counts, broker state
+///
+/// Served while degraded - it's what you look at when publishes are slow.
+#[utoipa::path(
+    get,
+    path = "/admin/kafka/producer",
+    tag = "service",
+    responses(
+        (status = 200, description = "Producer queue depth, delivery counts, breaker and broker state")
+    )
+)]
+pub async fn get_producer_diagnostics(
+    State(state): State<AppState>,
+) -> Json<ApiResponse<ProducerDiagnostics>> {
+    Json(ApiResponse::success(state.kafka_producer.diagnostics()))
+}
+
+/// Health check endpoint
+///
+/// Checks the database, migrations and Kafka (see `health::check`):
+/// - 200 "healthy": everything is up
+/// - 200 "degraded": still serving money movement, but Kafka is unreachable
+///   (events wait in the outbox) or extras are shed (/health/degradation)
+/// - 503 "unhealthy": the database is down or the schema is behind
+#[utoipa::path(
+    get,
+    path = "/health",
+    tag = "service",
+    responses(
+        (status = 200, description = "Healthy or degraded, with per-dependency checks", body = HealthReport),
+        (status = 503, description = "Unhealthy - a critical dependency is down", body = HealthReport)
+    )
+)]
+pub async fn health_check(State(state): State<AppState>) -> (StatusCode, Json<HealthReport>) {
+    let report = health::check(&state.repository, &state.kafka_producer, &state.degradation).await;
+    let status = match report.status {
+        HealthStatus::Unhealthy => StatusCode::SERVICE_UNAVAILABLE,
+        HealthStatus::Healthy | HealthStatus::Degraded => StatusCode::OK,
+    };
+
+    (status, Json(report))
 }
@@ -0,0 +1,237 @@
+use crate::degradation::DegradationController;
+use crate::kafka::KafkaProducer;
+use crate::repository::WalletRepository;
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use sqlx::migrate::Migrator;
+use std::collections::BTreeMap;
+use std::future::Future;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use utoipa::ToSchema;
+
+/// The migrations this binary was built with
+static MIGRATOR: Migrator = sqlx::migrate!("./migrations");
+
+/// How one dependency is doing
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum CheckStatus {
+    Up,
+    /// Reachable, but not everything works (e.g. the Kafka circuit is open)
+    Degraded,
+    Down,
+}
+
+/// The service as a whole
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum HealthStatus {
+    Healthy,
+    /// Serving, with something missing - still a 200
+    Degraded,
+    /// A critical dependency is down - 503, take us out of rotation
+    Unhealthy,
+}
+
+/// Result of one dependency check
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct DependencyCheck {
+    pub status: CheckStatus,
+    /// Critical checks being down make the service unhealthy; the rest
+    /// only degrade it
+    pub critical: bool,
+    pub latency_ms: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub detail: Option<String>,
+}
+
+impl DependencyCheck {
+    pub fn up(critical: bool) -> Self {
+        Self {
+            status: CheckStatus::Up,
+            critical,
+            latency_ms: None,
+            detail: None,
+        }
+    }
+
+    pub fn with_status(mut self, status: CheckStatus, detail: impl Into<String>) -> Self {
+        self.status = status;
+        self.detail = Some(detail.into());
+        self
+    }
+
+    fn timed(mut self, started: Instant) -> Self {
+        self.latency_ms = Some(started.elapsed().as_millis() as u64);
+        self
+    }
+}
+
+/// What GET /health returns
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct HealthReport {
+    pub status: HealthStatus,
+    pub checks: BTreeMap<String, DependencyCheck>,
+    pub checked_at: DateTime<Utc>,
+}
+
+impl HealthReport {
+    /// Overall status: unhealthy if a critical check is down, degraded if
+    /// anything else isn't up, healthy otherwise
+    pub fn from_checks(checks: BTreeMap<String, DependencyCheck>) -> Self {
+        let status = if checks
+            .values()
+            .any(|c| c.critical && c.status == CheckStatus::Down)
+        {
+            HealthStatus::Unhealthy
+        } else if checks.values().any(|c| c.status != CheckStatus::Up) {
+            HealthStatus::Degraded
+        } else {
+            HealthStatus::Healthy
+        };
+
+        Self {
+            status,
+            checks,
+            checked_at: Utc::now(),
+        }
+    }
+}
+
+/// How long each check may take (HEALTH_CHECK_TIMEOUT_MS, default 2000)
+///
+/// Health checks are polled; one that hangs on a dead broker would pile up
+/// probes faster than they finish.
+pub fn check_timeout() -> Duration {
+    Duration::from_millis(
+        std::env::var("HEALTH_CHECK_TIMEOUT_MS")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(2000),
+    )
+}
+
+/// Run every check (concurrently) and combine them
+///
+/// - database: `SELECT 1` - critical
+/// - migrations: every migration in this binary applied, none half-done -
+///   critical, queries against an old schema fail
+/// - kafka: broker metadata fetch, plus the circuit breaker - not critical,
+///   events wait in event_outbox while it's down
+/// - degradation: whether non-essential routes are being shed
+pub async fn check(
+    repository: &WalletRepository,
+    kafka: &Arc<KafkaProducer>,
+    degradation: &DegradationController,
+) -> HealthReport {
+    let timeout = check_timeout();
+    let (database, migrations, kafka) = tokio::join!(
+        check_database(repository, timeout),
+        check_migrations(repository, timeout),
+        check_kafka(kafka.clone(), timeout),
+    );
+
+    let status = degradation.status();
+    let shedding = if status.degraded {
+        DependencyCheck::up(false).with_status(CheckStatus::Degraded, status.reasons.join("; "))
+    } else {
+        DependencyCheck::up(false)
+    };
+
+    HealthReport::from_checks(BTreeMap::from([
+        ("database".to_string(), database),
+        ("migrations".to_string(), migrations),
+        ("kafka".to_string(), kafka),
+        ("degradation".to_string(), shedding),
+    ]))
+}
+
+async fn check_database(repository: &WalletRepository, timeout: Duration) -> DependencyCheck {
+    let started = Instant::now();
+    match within(timeout, repository.ping()).await {
+        Ok(()) => DependencyCheck::up(true),
+        Err(e) => DependencyCheck::up(true).with_status(CheckStatus::Down, e),
+    }
+    .timed(started)
+}
+
+async fn check_migrations(repository: &WalletRepository, timeout: Duration) -> DependencyCheck {
+    let started = Instant::now();
+    let expected: Vec<i64> = MIGRATOR
+        .iter()
+        .filter(|m| !m.migration_type.is_down_migration())
+        .map(|m| m.version)
+        .collect();
+
+    match within(timeout, repository.applied_migrations()).await {
+        Ok(applied) => migration_check(&expected, &applied),
+        Err(e) => DependencyCheck::up(true).with_status(CheckStatus::Down, e),
+    }
+    .timed(started)
+}
+
+/// Compare the migrations we were built with against `_sqlx_migrations`
+/// (`(version, success)` rows)
+///
+/// Versions the database has and we don't are fine: a newer instance ran
+/// them during a rolling deploy.
+pub fn migration_check(expected: &[i64], applied: &[(i64, bool)]) -> DependencyCheck {
+    if let Some((version, _)) = applied.iter().find(|(_, success)| !success) {
+        return DependencyCheck::up(true)
+            .with_status(CheckStatus::Down, format!("migration {} failed partway", version));
+    }
+
+    let pending: Vec<String> = expected
+        .iter()
+        .filter(|v| !applied.iter().any(|(a, _)| a == *v))
+        .map(|v| v.to_string())
+        .collect();
+    if !pending.is_empty() {
+        return DependencyCheck::up(true).with_status(
+            CheckStatus::Down,
+            format!("{} pending: {}", pending.len(), pending.join(", ")),
+        );
+    }
+
+    match expected.iter().max() {
+        Some(latest) => DependencyCheck::up(true)
+            .with_status(CheckStatus::Up, format!("at {}", latest)),
+        None => DependencyCheck::up(true),
+    }
+}
+
+async fn check_kafka(kafka: Arc<KafkaProducer>, timeout: Duration) -> DependencyCheck {
+    let started = Instant::now();
+    let breaker = kafka.broker_status();
+
+    // librdkafka's metadata call blocks; keep it off the runtime threads
+    let fetched = tokio::task::spawn_blocking(move || kafka.fetch_broker_count(timeout)).await;
+    let check = match fetched {
+        Ok(Ok(0)) => DependencyCheck::up(false).with_status(CheckStatus::Down, "no brokers"),
+        Ok(Ok(_)) if !breaker.available => DependencyCheck::up(false).with_status(
+            CheckStatus::Degraded,
+            format!(
+                "brokers reachable, circuit {:?} after {} failures",
+                breaker.state, breaker.consecutive_failures
+            ),
+        ),
+        Ok(Ok(brokers)) => DependencyCheck::up(false)
+            .with_status(CheckStatus::Up, format!("{} brokers", brokers)),
+        Ok(Err(e)) => DependencyCheck::up(false).with_status(CheckStatus::Down, e.to_string()),
+        Err(e) => DependencyCheck::up(false).with_status(CheckStatus::Down, e.to_string()),
+    };
+    check.timed(started)
+}
+
+/// `future`, failing with a message after `timeout`
+async fn within<T, E: std::fmt::Display>(
+    timeout: Duration,
+    future: impl Future<Output = Result<T, E>>,
+) -> Result<T, String> {
+    match tokio::time::timeout(timeout, future).await {
+        Ok(Ok(value)) => Ok(value),
+        Ok(Err(e)) => Err(e.to_string()),
+        Err(_) => Err(format!("no answer within {}ms", timeout.as_millis())),
+    }
+}
@@ -1,12 +1,31 @@
+use crate::circuit_breaker::{BreakerConfig, BreakerStatus, CircuitBreaker};
+use crate::codec::EventCodec;
+use crate::correlation;
 use crate::errors::{WalletError, WalletResult};
-use crate::models::Wallet;
+use crate::fraud::{FraudContext, FraudVerdict};
+use crate::kafka_security::client_config;
+use crate::kafka_stats::{ProducerStats, StatsContext, STATS_INTERVAL_MS};
+use crate::models::{
+    BlocklistEntry, RoundUpOutcome, UserAnonymization, Voucher, Wallet, WalletAdjustment,
+    WalletTransaction,
+};
+use crate::outbox::{self, OutboxRelay};
+use crate::shutdown::Shutdown;
+use crate::signing::{EventSigner, SIGNATURE_HEADER};
+use crate::webhooks;
 use chrono::{DateTime, Utc};
-use rdkafka::config::ClientConfig;
-use rdkafka::producer::{FutureProducer, FutureRecord};
+use rdkafka::message::{Header, OwnedHeaders};
+use rdkafka::producer::{FutureProducer, FutureRecord, Producer};
 use rust_decimal::Decimal;
 use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::Arc;
 use std::time::Duration;
 
+/// How long transaction setup, commit and abort may block
+const TRANSACTION_TIMEOUT: Duration = Duration::from_secs(30);
+
 /// Wallet events that get published to Kafka
 /// 
 /// Design decisions:
@@ -44,8 +63,195 @@ pub enum WalletEvent {
         reference_id: String, // Links the two transaction records
         timestamp: DateTime<Utc>,
     },
+
+    #[serde(rename = "ROUND_UP_APPLIED")]
+    RoundUpApplied {
+        wallet_id: String,
+        user_id: String,
+        savings_wallet_id: String,
+        savings_user_id: String,
+        amount: Decimal,
+        out_transaction_id: String,
+        in_transaction_id: String,
+        reference_id: String, // The transfer that triggered the round-up
+        timestamp: DateTime<Utc>,
+    },
+
+    #[serde(rename = "POT_TRANSFER_COMPLETED")]
+    PotTransferCompleted {
+        from_wallet_id: String,
+        to_wallet_id: String,
+        user_id: String, // Pots always belong to their parent's owner
+        amount: Decimal,
+        out_transaction_id: String,
+        in_transaction_id: String,
+        reference_id: String,
+        timestamp: DateTime<Utc>,
+    },
+
+    #[serde(rename = "VOUCHER_REDEEMED")]
+    VoucherRedeemed {
+        wallet_id: String,
+        user_id: String,
+        voucher_id: String, // NOT the code - the code is a bearer secret
+        amount: Decimal,
+        new_balance: Decimal,
+        transaction_id: String,
+        timestamp: DateTime<Utc>,
+    },
+
+    /// An admin corrected a balance by hand (see POST /admin/wallets/:id/adjustments)
+    #[serde(rename = "WALLET_ADJUSTED")]
+    WalletAdjusted {
+        wallet_id: String,
+        user_id: String,
+        direction: String, // CREDIT or DEBIT; amount is always positive
+        amount: Decimal,
+        new_balance: Decimal,
+        reason_code: String,
+        actor: String,
+        adjustment_id: String,
+        transaction_id: String,
+        timestamp: DateTime<Utc>,
+    },
+
+    /// A funding was declined - nothing changed; for analytics and fraud
+    #[serde(rename = "FUNDING_FAILED")]
+    FundingFailed {
+        wallet_id: String,
+        user_id: String,
+        amount: Decimal,
+        reason: String, // WalletError::reason(), e.g. invalid_amount
+        timestamp: DateTime<Utc>,
+    },
+
+    /// A transfer was declined - nothing moved
+    #[serde(rename = "TRANSFER_FAILED")]
+    TransferFailed {
+        from_wallet_id: String,
+        from_user_id: String,
+        to_wallet_id: String,
+        amount: Decimal,
+        reason: String, // e.g. insufficient_balance, wallet_not_found
+        timestamp: DateTime<Utc>,
+    },
+
+    /// A fraud rule fired on a funding or transfer (see fraud.rs)
+    #[serde(rename = "FRAUD_ALERT")]
+    FraudAlert {
+        wallet_id: String,
+        user_id: String,
+        operation: String,              // FUND or TRANSFER
+        counterparty_wallet_id: String, // Empty for fundings
+        amount: Decimal,
+        action: String, // FLAG, HOLD or REJECT - the strictest rule's
+        rules: String,  // Comma-separated rule names
+        reason: String,
+        timestamp: DateTime<Utc>,
+    },
+
+    /// A transfer broke a velocity limit (see velocity.rs); it is also
+    /// published as TRANSFER_FAILED
+    #[serde(rename = "VELOCITY_LIMIT_EXCEEDED")]
+    VelocityLimitExceeded {
+        wallet_id: String,
+        user_id: String,
+        to_wallet_id: String,
+        amount: Decimal,
+        measure: String, // transfers or volume
+        limit: Decimal,
+        attempted: Decimal, // What the window would have reached with this transfer
+        window_secs: u64,
+        timestamp: DateTime<Utc>,
+    },
+
+    /// A wallet or user was blocklisted (keyed by the target's ID)
+    #[serde(rename = "BLOCKLIST_ENTRY_ADDED")]
+    BlocklistEntryAdded {
+        entry_id: String,
+        target_type: String, // wallet or user
+        target_id: String,
+        reason: String,
+        actor: String,
+        timestamp: DateTime<Utc>,
+    },
+
+    /// A wallet or user was taken off the blocklist
+    #[serde(rename = "BLOCKLIST_ENTRY_REMOVED")]
+    BlocklistEntryRemoved {
+        entry_id: String,
+        target_type: String,
+        target_id: String,
+        actor: String,
+        timestamp: DateTime<Utc>,
+    },
+
+    /// A user's data was erased: consumers replace `user_id` with
+    /// `anonymized_user_id` wherever they stored it (keyed by the old ID)
+    #[serde(rename = "USER_ANONYMIZED")]
+    UserAnonymized {
+        user_id: String,
+        anonymized_user_id: String,
+        timestamp: DateTime<Utc>,
+    },
+
+    /// Repeated wrong PINs or step-up codes just locked a wallet's checks
+    /// - someone may be guessing
+    #[serde(rename = "VERIFICATION_LOCKOUT")]
+    VerificationLockout {
+        wallet_id: String,
+        user_id: String,
+        factor: String,       // pin or otp
+        failed_attempts: u32, // Wrong attempts that triggered it
+        lockouts: u32,        // 1 for the first lockout since the last success, 2, 3 ...
+        locked_until: DateTime<Utc>,
+        timestamp: DateTime<Utc>,
+    },
+}
+
+/// Tracing IDs stamped on every event by the producer (see correlation.rs)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct EventIds {
+    pub event_id: String,
+    pub correlation_id: String,
+    pub causation_id: String,
+    /// The HTTP request that published it (None from background jobs)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub request_id: Option<String>,
 }
 
+/// What goes on the wire: the event's fields plus its tracing IDs, flat
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EventEnvelope {
+    #[serde(flatten)]
+    pub ids: EventIds,
+    /// 1, 2, 3 ... per wallet (the partition key), so consumers can spot gaps
+    /// and reordering (None when the producer has no pool to number from)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sequence: Option<i64>,
+    #[serde(flatten)]
+    pub event: WalletEvent,
+}
+
+/// Every `WalletEvent::event_type()` (what webhooks can subscribe to)
+pub const EVENT_TYPES: &[&str] = &[
+    "WALLET_CREATED",
+    "WALLET_FUNDED",
+    "TRANSFER_COMPLETED",
+    "ROUND_UP_APPLIED",
+    "POT_TRANSFER_COMPLETED",
+    "VOUCHER_REDEEMED",
+    "WALLET_ADJUSTED",
+    "FUNDING_FAILED",
+    "TRANSFER_FAILED",
+    "FRAUD_ALERT",
+    "VELOCITY_LIMIT_EXCEEDED",
+    "BLOCKLIST_ENTRY_ADDED",
+    "BLOCKLIST_ENTRY_REMOVED",
+    "USER_ANONYMIZED",
+    "VERIFICATION_LOCKOUT",
+];
+
 impl WalletEvent {
     /// Get the event type as a string (useful for logging)
     pub fn event_type(&self) -> &str {
@@ -53,6 +259,18 @@ impl WalletEvent {
             WalletEvent::WalletCreated { .. } => "WALLET_CREATED",
             WalletEvent::WalletFunded { .. } => "WALLET_FUNDED",
             WalletEvent::TransferCompleted { .. } => "TRANSFER_COMPLETED",
+            WalletEvent::RoundUpApplied { .. } => "ROUND_UP_APPLIED",
+            WalletEvent::PotTransferCompleted { .. } => "POT_TRANSFER_COMPLETED",
+            WalletEvent::VoucherRedeemed { .. } => "VOUCHER_REDEEMED",
+            WalletEvent::WalletAdjusted { .. } => "WALLET_ADJUSTED",
+            WalletEvent::FundingFailed { .. } => "FUNDING_FAILED",
+            WalletEvent::TransferFailed { .. } => "TRANSFER_FAILED",
+            WalletEvent::FraudAlert { .. } => "FRAUD_ALERT",
+            WalletEvent::VelocityLimitExceeded { .. } => "VELOCITY_LIMIT_EXCEEDED",
+            WalletEvent::BlocklistEntryAdded { .. } => "BLOCKLIST_ENTRY_ADDED",
+            WalletEvent::BlocklistEntryRemoved { .. } => "BLOCKLIST_ENTRY_REMOVED",
+            WalletEvent::UserAnonymized { .. } => "USER_ANONYMIZED",
+            WalletEvent::VerificationLockout { .. } => "VERIFICATION_LOCKOUT",
         }
     }
 
@@ -67,6 +285,19 @@ impl WalletEvent {
             WalletEvent::TransferCompleted {
                 from_wallet_id, ..
             } => from_wallet_id,
+            WalletEvent::RoundUpApplied { wallet_id, .. } => wallet_id,
+            WalletEvent::PotTransferCompleted { from_wallet_id, .. } => from_wallet_id,
+            WalletEvent::VoucherRedeemed { wallet_id, .. } => wallet_id,
+            WalletEvent::WalletAdjusted { wallet_id, .. } => wallet_id,
+            WalletEvent::FundingFailed { wallet_id, .. } => wallet_id,
+            WalletEvent::TransferFailed { from_wallet_id, .. } => from_wallet_id,
+            WalletEvent::FraudAlert { wallet_id, .. } => wallet_id,
+            WalletEvent::VelocityLimitExceeded { wallet_id, .. } => wallet_id,
+            // A user's entry is keyed by the user ID
+            WalletEvent::BlocklistEntryAdded { target_id, .. } => target_id,
+            WalletEvent::BlocklistEntryRemoved { target_id, .. } => target_id,
+            WalletEvent::UserAnonymized { user_id, .. } => user_id,
+            WalletEvent::VerificationLockout { wallet_id, .. } => wallet_id,
         }
     }
 }
@@ -78,9 +309,49 @@ impl WalletEvent {
 /// - Provide domain-specific publish methods
 /// - Centralize error handling
 /// - Make testing easier (can mock this trait)
+///
+/// Broker outages:
+/// - Creating the client never contacts Kafka: the service starts (and
+///   serves balances) with the brokers down, and librdkafka connects on
+///   the first send and reconnects on its own afterwards
+/// - Sends go through a circuit breaker (circuit_breaker.rs): after a
+///   failed send we stop trying for a while (1s, 2s, 4s ... capped at 30s)
+///   and fail fast with EventBusUnavailable, or spill to the outbox,
+///   instead of holding every request for the full 5s delivery timeout
+/// - The first send after the cooldown is the only probe: success closes it
 pub struct KafkaProducer {
-    producer: FutureProducer,
+    producer: FutureProducer<StatsContext>,
+    stats: StatsContext,
     topic: String,
+    outbox: Option<PgPool>,
+    transactional: bool,
+    codec: EventCodec,
+    breaker: CircuitBreaker,
+    spill: Option<PgPool>,
+    sequences: Option<PgPool>,
+    /// Queue each event for matching webhook subscriptions (webhooks.rs)
+    webhooks: Option<PgPool>,
+    /// Compacted topic for wallet snapshots (wallet_state.rs)
+    state_topic: Option<String>,
+    /// Signs each event for consumers to verify (signing.rs)
+    signer: Option<EventSigner>,
+    /// Spilled events may still be pending - new events queue behind them
+    spilling: AtomicBool,
+    delivered: AtomicU64,
+    delivery_failures: AtomicU64,
+}
+
+/// Producer internals for diagnosing slow or failing publishes
+/// (GET /admin/kafka/producer)
+#[derive(Debug, Clone, Serialize)]
+pub struct ProducerDiagnostics {
+    pub topic: String,
+    pub in_flight: i32,
+    pub delivered_total: u64,
+    pub delivery_failures_total: u64,
+    pub circuit: BreakerStatus,
+    /// librdkafka's latest statistics (None until its first report)
+    pub librdkafka: Option<ProducerStats>,
 }
 
 impl KafkaProducer {
@@ -92,8 +363,8 @@ impl KafkaProducer {
     /// - enable.idempotence=true: Exactly-once semantics within producer
     /// - max.in.flight.requests.per.connection=5: Pipelining for performance
     pub fn new(brokers: &str, topic: String) -> WalletResult<Self> {
-        let producer: FutureProducer = ClientConfig::new()
-            .set("bootstrap.servers", brokers)
+        let stats = StatsContext::default();
+        let producer: FutureProducer<StatsContext> = client_config(brokers)
             .set("message.timeout.ms", "5000")
             // Durability settings
             .set("acks", "all") // Wait for all in-sync replicas
@@ -102,16 +373,266 @@ impl KafkaProducer {
             .set("compression.type", "snappy")
             .set("batch.size", "16384")
             .set("linger.ms", "10")
-            .create()
+            // Reconnect quickly after a broker restart, back off to 10s
+            .set("reconnect.backoff.ms", "100")
+            .set("reconnect.backoff.max.ms", "10000")
+            .set("statistics.interval.ms", STATS_INTERVAL_MS)
+            .create_with_context(stats.clone())
+            .map_err(|e| WalletError::KafkaError(format!("Failed to create producer: {}", e)))?;
+
+        Ok(Self {
+            producer,
+            stats,
+            topic,
+            outbox: None,
+            transactional: false,
+            codec: EventCodec::Json,
+            breaker: CircuitBreaker::new(BreakerConfig::default()),
+            spill: None,
+            sequences: None,
+            webhooks: None,
+            state_topic: None,
+            signer: None,
+            spilling: AtomicBool::new(false),
+            delivered: AtomicU64::new(0),
+            delivery_failures: AtomicU64::new(0),
+        })
+    }
+
+    /// Create a transactional producer (used by the outbox relay)
+    ///
+    /// Sends only become visible to read_committed consumers once
+    /// `commit_transaction` succeeds, so a crash mid-batch leaves nothing
+    /// behind. `transactional_id` must be unique per relay instance: Kafka
+    /// fences the older producer when two register the same ID.
+    pub fn new_transactional(
+        brokers: &str,
+        topic: String,
+        transactional_id: &str,
+    ) -> WalletResult<Self> {
+        let stats = StatsContext::default();
+        let producer: FutureProducer<StatsContext> = client_config(brokers)
+            .set("message.timeout.ms", "5000")
+            .set("acks", "all")
+            .set("enable.idempotence", "true")
+            .set("transactional.id", transactional_id)
+            .set("transaction.timeout.ms", "60000")
+            .set("compression.type", "snappy")
+            .set("linger.ms", "10")
+            .set("statistics.interval.ms", STATS_INTERVAL_MS)
+            .create_with_context(stats.clone())
             .map_err(|e| WalletError::KafkaError(format!("Failed to create producer: {}", e)))?;
 
-        Ok(Self { producer, topic })
+        producer
+            .init_transactions(TRANSACTION_TIMEOUT)
+            .map_err(|e| WalletError::KafkaError(format!("Failed to init transactions: {}", e)))?;
+
+        Ok(Self {
+            producer,
+            stats,
+            topic,
+            outbox: None,
+            transactional: true,
+            codec: EventCodec::Json,
+            breaker: CircuitBreaker::new(BreakerConfig::default()),
+            spill: None,
+            sequences: None,
+            webhooks: None,
+            state_topic: None,
+            signer: None,
+            spilling: AtomicBool::new(false),
+            delivered: AtomicU64::new(0),
+            delivery_failures: AtomicU64::new(0),
+        })
+    }
+
+    /// Encode events with `codec` when they're sent (default JSON)
+    pub fn with_codec(mut self, codec: EventCodec) -> Self {
+        self.codec = codec;
+        self
+    }
+
+    /// Number each wallet's events (`sequence`) from wallet_event_sequences
+    pub fn with_sequences(mut self, pool: PgPool) -> Self {
+        self.sequences = Some(pool);
+        self
+    }
+
+    /// Queue every published event for the webhook dispatcher
+    pub fn with_webhooks(mut self, pool: PgPool) -> Self {
+        self.webhooks = Some(pool);
+        self
+    }
+
+    /// Allow `send_state` to the compacted `topic` (see wallet_state.rs)
+    pub fn with_state_topic(mut self, topic: String) -> Self {
+        self.state_topic = Some(topic);
+        self
+    }
+
+    /// Sign every event sent to the events topic (default: unsigned)
+    pub fn with_signer(mut self, signer: EventSigner) -> Self {
+        self.signer = Some(signer);
+        self
+    }
+
+    /// Open the circuit per `config` (default: after one failure, 1s-30s)
+    pub fn with_breaker(mut self, config: BreakerConfig) -> Self {
+        self.breaker = CircuitBreaker::new(config);
+        self
+    }
+
+    pub fn is_transactional(&self) -> bool {
+        self.transactional
+    }
+
+    /// Start a Kafka transaction (transactional producers only)
+    pub async fn begin_transaction(&self) -> WalletResult<()> {
+        self.producer
+            .begin_transaction()
+            .map_err(|e| WalletError::KafkaError(format!("Failed to begin transaction: {}", e)))
+    }
+
+    /// Commit the open transaction - flushes and waits for every send in it
+    ///
+    /// librdkafka blocks here, so it runs on the blocking thread pool.
+    pub async fn commit_transaction(&self) -> WalletResult<()> {
+        let producer = self.producer.clone();
+        tokio::task::spawn_blocking(move || producer.commit_transaction(TRANSACTION_TIMEOUT))
+            .await
+            .map_err(|e| WalletError::InternalError(format!("Commit task failed: {}", e)))?
+            .map_err(|e| WalletError::KafkaError(format!("Failed to commit transaction: {}", e)))
+    }
+
+    /// Abort the open transaction - its sends are never shown to consumers
+    pub async fn abort_transaction(&self) -> WalletResult<()> {
+        let producer = self.producer.clone();
+        tokio::task::spawn_blocking(move || producer.abort_transaction(TRANSACTION_TIMEOUT))
+            .await
+            .map_err(|e| WalletError::InternalError(format!("Abort task failed: {}", e)))?
+            .map_err(|e| WalletError::KafkaError(format!("Failed to abort transaction: {}", e)))
+    }
+
+    /// Write events to the outbox table instead of sending them directly
+    /// 
+    /// The outbox-relay binary then delivers them with retries.
+    pub fn with_outbox(mut self, pool: PgPool) -> Self {
+        self.outbox = Some(pool);
+        self
+    }
+
+    /// Spill events that can't be sent to the event_outbox table
+    ///
+    /// Why?
+    /// - In direct mode a broker outage otherwise loses the event (the
+    ///   wallet change is already committed) and fails the API call
+    /// - Spilled rows are sent by `spawn_spill_flusher` once Kafka is back
+    ///
+    /// Ordering: once anything is spilled, later events are spilled too
+    /// until the backlog drains, so a wallet's events can't overtake each other.
+    pub fn with_spill(mut self, pool: PgPool) -> Self {
+        self.spill = Some(pool);
+        self
+    }
+
+    /// Send spilled events in the background every `interval`, until shutdown
+    ///
+    /// Uses the outbox relay (same table, same advisory lock), so several
+    /// instances - and a separate outbox-relay binary - can run at once.
+    /// A pass in progress at shutdown is finished; what's left stays in the
+    /// table for the next instance.
+    pub fn spawn_spill_flusher(self: Arc<Self>, interval: Duration, shutdown: Shutdown) {
+        let Some(pool) = self.spill.clone() else {
+            return;
+        };
+
+        tokio::spawn(async move {
+            // The producer's own backoff paces retries during an outage, so
+            // rows needn't wait minutes each once Kafka is back
+            let relay = OutboxRelay::new(pool, self.clone(), 100).with_max_backoff(interval * 2);
+            let mut ticker = tokio::time::interval(interval);
+
+            loop {
+                tokio::select! {
+                    _ = ticker.tick() => {}
+                    () = shutdown.wait() => return,
+                }
+                if !self.is_available() {
+                    continue;
+                }
+
+                match relay.run_once().await {
+                    Ok(pass) if pass.published > 0 => {
+                        tracing::info!(published = pass.published, "Flushed spilled events")
+                    }
+                    Ok(_) => {}
+                    Err(e) => tracing::error!(error = %e, "Failed to flush spilled events"),
+                }
+
+                if self.spilling.load(Ordering::Relaxed) {
+                    if let Ok(backlog) = relay.backlog().await {
+                        if backlog.pending == 0 {
+                            tracing::info!("Spill backlog drained, sending directly again");
+                            self.spilling.store(false, Ordering::Relaxed);
+                        }
+                    }
+                }
+            }
+        });
+    }
+
+    /// False while the circuit refuses sends (open, or probing)
+    pub fn is_available(&self) -> bool {
+        self.broker_status().available
+    }
+
+    pub fn broker_status(&self) -> BreakerStatus {
+        self.breaker.status()
+    }
+
+    /// Events handed to the producer but not yet acknowledged by Kafka
+    pub fn in_flight_count(&self) -> i32 {
+        self.producer.in_flight_count()
+    }
+
+    /// How many brokers answer a metadata request for our topic
+    ///
+    /// Blocks the thread - call it from `spawn_blocking`.
+    pub fn fetch_broker_count(&self, timeout: Duration) -> WalletResult<usize> {
+        self.producer
+            .client()
+            .fetch_metadata(Some(&self.topic), timeout)
+            .map(|metadata| metadata.brokers().len())
+            .map_err(|e| WalletError::KafkaError(format!("Failed to fetch metadata: {}", e)))
+    }
+
+    /// Wait up to `timeout` for queued events to be acknowledged (on shutdown)
+    ///
+    /// Blocks the thread - call it from `spawn_blocking`.
+    pub fn flush(&self, timeout: Duration) -> WalletResult<()> {
+        self.producer
+            .flush(timeout)
+            .map_err(|e| WalletError::KafkaError(format!("Failed to flush producer: {}", e)))
+    }
+
+    /// Queue depth, delivery counts, backoff and librdkafka's broker view
+    pub fn diagnostics(&self) -> ProducerDiagnostics {
+        ProducerDiagnostics {
+            topic: self.topic.clone(),
+            in_flight: self.in_flight_count(),
+            delivered_total: self.delivered.load(Ordering::Relaxed),
+            delivery_failures_total: self.delivery_failures.load(Ordering::Relaxed),
+            circuit: self.broker_status(),
+            librdkafka: self.stats.latest(),
+        }
     }
 
     /// Publish an event to Kafka
     /// 
     /// Key points:
     /// - Uses wallet_id as partition key (ordering per wallet)
+    /// - Stamps event, correlation and causation IDs (see correlation.rs)
+    /// - Numbers the event within its wallet (with_sequences)
     /// - Serializes to JSON
     /// - Waits for acknowledgment (up to 5 seconds)
     /// - Returns error if publishing fails
@@ -126,19 +647,139 @@ impl KafkaProducer {
     /// 3. Accept that events might be lost
     pub async fn publish(&self, event: WalletEvent) -> WalletResult<()> {
         let key = event.wallet_id().to_string();
-        let payload = serde_json::to_string(&event).map_err(|e| {
+        let sequence = match &self.sequences {
+            Some(pool) => Some(next_sequence(pool, &key).await?),
+            None => None,
+        };
+        let envelope = EventEnvelope {
+            ids: correlation::next_event_ids(),
+            sequence,
+            event,
+        };
+        let event = &envelope.event;
+        let payload = serde_json::to_string(&envelope).map_err(|e| {
             WalletError::InternalError(format!("Failed to serialize event: {}", e))
         })?;
 
+        // The change is committed, so subscribers hear about it whether or
+        // not Kafka takes the event right now
+        if let Some(pool) = &self.webhooks {
+            if let Err(e) =
+                webhooks::queue_deliveries(pool, &envelope.ids.event_id, event.event_type(), &payload).await
+            {
+                tracing::error!(error = %e, event_id = %envelope.ids.event_id, "Failed to queue webhook deliveries");
+            }
+        }
+
+        if let Some(pool) = &self.outbox {
+            tracing::info!(
+                event_type = event.event_type(),
+                wallet_id = %key,
+                "Writing event to outbox"
+            );
+            return outbox::enqueue(
+                pool,
+                &envelope.ids.event_id,
+                &key,
+                event.event_type(),
+                &payload,
+            )
+            .await;
+        }
+
+        if let Some(pool) = &self.spill {
+            if self.spilling.load(Ordering::Relaxed) {
+                // Keep per-wallet order: queue behind the events already spilled
+                return outbox::enqueue(
+                    pool,
+                    &envelope.ids.event_id,
+                    &key,
+                    event.event_type(),
+                    &payload,
+                )
+                .await;
+            }
+        }
+
         tracing::info!(
             event_type = event.event_type(),
             wallet_id = %key,
+            event_id = %envelope.ids.event_id,
+            correlation_id = %envelope.ids.correlation_id,
+            sequence = ?envelope.sequence,
             "Publishing event to Kafka"
         );
 
-        let record = FutureRecord::to(&self.topic)
-            .key(&key) // Partition by wallet_id
-            .payload(&payload);
+        match (self.send(&key, &payload).await, &self.spill) {
+            (Err(e), Some(pool)) => {
+                tracing::warn!(
+                    error = %e,
+                    event_type = event.event_type(),
+                    wallet_id = %key,
+                    "Kafka unavailable, spilling event to the database"
+                );
+                outbox::enqueue(
+                    pool,
+                    &envelope.ids.event_id,
+                    &key,
+                    event.event_type(),
+                    &payload,
+                )
+                .await?;
+                self.spilling.store(true, Ordering::Relaxed);
+                Ok(())
+            }
+            (result, _) => result,
+        }
+    }
+
+    /// Send an already-serialized (JSON) event and wait for acknowledgment
+    ///
+    /// The payload is re-encoded with the configured codec first, then
+    /// signed (with_signer) - so spilled and outbox events are signed too.
+    pub async fn send(&self, key: &str, payload: &str) -> WalletResult<()> {
+        let payload = self.codec.encode(payload)?;
+        let signature = self.signer.as_ref().map(|signer| signer.sign(key, &payload));
+        self.deliver(&self.topic, key, Some(&payload), signature.as_deref())
+            .await
+    }
+
+    /// Send a wallet snapshot to the state topic (None = tombstone)
+    ///
+    /// Snapshots are always JSON - they're for bootstrapping, not the event
+    /// schema the codec covers.
+    pub async fn send_state(&self, wallet_id: &str, snapshot: Option<&str>) -> WalletResult<()> {
+        let topic = self.state_topic.as_deref().ok_or_else(|| {
+            WalletError::InternalError("No wallet-state topic configured".to_string())
+        })?;
+        self.deliver(topic, wallet_id, snapshot.map(str::as_bytes), None)
+            .await
+    }
+
+    /// One send through the circuit breaker, waiting for the acknowledgment
+    async fn deliver(
+        &self,
+        topic: &str,
+        key: &str,
+        payload: Option<&[u8]>,
+        signature: Option<&str>,
+    ) -> WalletResult<()> {
+        let permit = self.breaker.try_acquire().map_err(|retry_in| {
+            WalletError::EventBusUnavailable {
+                retry_after_secs: retry_in.as_secs_f64().ceil() as u64,
+            }
+        })?;
+
+        let mut record = FutureRecord::<str, [u8]>::to(topic).key(key); // Partition by wallet_id
+        if let Some(payload) = payload {
+            record = record.payload(payload);
+        }
+        if let Some(signature) = signature {
+            record = record.headers(OwnedHeaders::new().insert(Header {
+                key: SIGNATURE_HEADER,
+                value: Some(signature),
+            }));
+        }
 
         // Send and wait for acknowledgment
         let delivery_status = self
@@ -148,7 +789,10 @@ impl KafkaProducer {
 
         match delivery_status {
             Ok((partition, offset)) => {
+                permit.succeeded();
+                self.delivered.fetch_add(1, Ordering::Relaxed);
                 tracing::debug!(
+                    topic = topic,
                     partition = partition,
                     offset = offset,
                     "Event published successfully"
@@ -156,7 +800,9 @@ impl KafkaProducer {
                 Ok(())
             }
             Err((e, _)) => {
-                tracing::error!(error = %e, "Failed to publish event");
+                permit.failed();
+                self.delivery_failures.fetch_add(1, Ordering::Relaxed);
+                tracing::error!(error = %e, topic = topic, "Failed to publish event");
                 Err(WalletError::KafkaError(format!(
                     "Failed to publish event: {}",
                     e
@@ -195,6 +841,48 @@ impl KafkaProducer {
         self.publish(event).await
     }
 
+    /// Publish voucher redeemed event
+    pub async fn publish_voucher_redeemed(
+        &self,
+        wallet: &Wallet,
+        voucher: &Voucher,
+        transaction_id: String,
+    ) -> WalletResult<()> {
+        let event = WalletEvent::VoucherRedeemed {
+            wallet_id: wallet.id.clone(),
+            user_id: wallet.user_id.clone(),
+            voucher_id: voucher.id.clone(),
+            amount: voucher.amount,
+            new_balance: wallet.balance,
+            transaction_id,
+            timestamp: Utc::now(),
+        };
+
+        self.publish(event).await
+    }
+
+    /// Publish wallet adjusted event (`wallet` is after the adjustment)
+    pub async fn publish_wallet_adjusted(
+        &self,
+        wallet: &Wallet,
+        adjustment: &WalletAdjustment,
+    ) -> WalletResult<()> {
+        let event = WalletEvent::WalletAdjusted {
+            wallet_id: wallet.id.clone(),
+            user_id: wallet.user_id.clone(),
+            direction: adjustment.direction.to_string(),
+            amount: adjustment.amount,
+            new_balance: wallet.balance,
+            reason_code: adjustment.reason_code.clone(),
+            actor: adjustment.actor.clone(),
+            adjustment_id: adjustment.id.clone(),
+            transaction_id: adjustment.transaction_id.clone(),
+            timestamp: Utc::now(),
+        };
+
+        self.publish(event).await
+    }
+
     /// Publish transfer completed event
     pub async fn publish_transfer_completed(
         &self,
@@ -217,6 +905,213 @@ impl KafkaProducer {
 
         self.publish(event).await
     }
+
+    /// Publish round-up applied event
+    pub async fn publish_round_up_applied(
+        &self,
+        wallet_id: String,
+        user_id: String,
+        round_up: &RoundUpOutcome,
+        reference_id: String,
+    ) -> WalletResult<()> {
+        let event = WalletEvent::RoundUpApplied {
+            wallet_id,
+            user_id,
+            savings_wallet_id: round_up.savings_wallet_id.clone(),
+            savings_user_id: round_up.savings_user_id.clone(),
+            amount: round_up.out_transaction.amount,
+            out_transaction_id: round_up.out_transaction.id.clone(),
+            in_transaction_id: round_up.in_transaction.id.clone(),
+            reference_id,
+            timestamp: Utc::now(),
+        };
+
+        self.publish(event).await
+    }
+
+    /// Publish pot transfer event (money moved between a wallet and its pot)
+    pub async fn publish_pot_transfer_completed(
+        &self,
+        user_id: String,
+        out_transaction: &WalletTransaction,
+        in_transaction: &WalletTransaction,
+    ) -> WalletResult<()> {
+        let event = WalletEvent::PotTransferCompleted {
+            from_wallet_id: out_transaction.wallet_id.clone(),
+            to_wallet_id: in_transaction.wallet_id.clone(),
+            user_id,
+            amount: out_transaction.amount,
+            out_transaction_id: out_transaction.id.clone(),
+            in_transaction_id: in_transaction.id.clone(),
+            reference_id: out_transaction.reference_id.clone().unwrap_or_default(),
+            timestamp: Utc::now(),
+        };
+
+        self.publish(event).await
+    }
+
+    /// Publish funding failed event (the funding was declined)
+    pub async fn publish_funding_failed(
+        &self,
+        wallet: &Wallet,
+        amount: Decimal,
+        error: &WalletError,
+    ) -> WalletResult<()> {
+        let event = WalletEvent::FundingFailed {
+            wallet_id: wallet.id.clone(),
+            user_id: wallet.user_id.clone(),
+            amount,
+            reason: error.reason().to_string(),
+            timestamp: Utc::now(),
+        };
+
+        self.publish(event).await
+    }
+
+    /// Publish transfer failed event (the transfer was declined)
+    pub async fn publish_transfer_failed(
+        &self,
+        from_wallet: &Wallet,
+        to_wallet_id: String,
+        amount: Decimal,
+        error: &WalletError,
+    ) -> WalletResult<()> {
+        let event = WalletEvent::TransferFailed {
+            from_wallet_id: from_wallet.id.clone(),
+            from_user_id: from_wallet.user_id.clone(),
+            to_wallet_id,
+            amount,
+            reason: error.reason().to_string(),
+            timestamp: Utc::now(),
+        };
+
+        self.publish(event).await
+    }
+
+    /// Publish fraud alert event (a rule flagged, held or rejected something)
+    pub async fn publish_fraud_alert(
+        &self,
+        context: &FraudContext,
+        verdict: &FraudVerdict,
+    ) -> WalletResult<()> {
+        let event = WalletEvent::FraudAlert {
+            wallet_id: context.wallet_id.clone(),
+            user_id: context.user_id.clone(),
+            operation: context.operation.as_str().to_string(),
+            counterparty_wallet_id: context
+                .counterparty
+                .as_ref()
+                .map(|c| c.wallet_id.clone())
+                .unwrap_or_default(),
+            amount: context.amount,
+            action: verdict.action.as_str().to_string(),
+            rules: verdict.rules(),
+            reason: verdict.reason(),
+            timestamp: Utc::now(),
+        };
+
+        self.publish(event).await
+    }
+
+    /// Publish velocity limit exceeded event (the sender's transfer was refused)
+    pub async fn publish_velocity_limit_exceeded(
+        &self,
+        from_wallet: &Wallet,
+        to_wallet_id: String,
+        amount: Decimal,
+        error: &WalletError,
+    ) -> WalletResult<()> {
+        let WalletError::VelocityLimitExceeded {
+            measure,
+            limit,
+            attempted,
+            window_secs,
+        } = error
+        else {
+            return Ok(());
+        };
+
+        let event = WalletEvent::VelocityLimitExceeded {
+            wallet_id: from_wallet.id.clone(),
+            user_id: from_wallet.user_id.clone(),
+            to_wallet_id,
+            amount,
+            measure: measure.to_string(),
+            limit: *limit,
+            attempted: *attempted,
+            window_secs: *window_secs,
+            timestamp: Utc::now(),
+        };
+
+        self.publish(event).await
+    }
+
+    /// Publish blocklist entry added event
+    pub async fn publish_blocklist_entry_added(&self, entry: &BlocklistEntry) -> WalletResult<()> {
+        let event = WalletEvent::BlocklistEntryAdded {
+            entry_id: entry.id.clone(),
+            target_type: entry.target_type.to_string(),
+            target_id: entry.target_id.clone(),
+            reason: entry.reason.clone(),
+            actor: entry.created_by.clone(),
+            timestamp: entry.created_at,
+        };
+
+        self.publish(event).await
+    }
+
+    /// Publish blocklist entry removed event
+    pub async fn publish_blocklist_entry_removed(&self, entry: &BlocklistEntry) -> WalletResult<()> {
+        let event = WalletEvent::BlocklistEntryRemoved {
+            entry_id: entry.id.clone(),
+            target_type: entry.target_type.to_string(),
+            target_id: entry.target_id.clone(),
+            actor: entry.removed_by.clone().unwrap_or_default(),
+            timestamp: entry.removed_at.unwrap_or_else(Utc::now),
+        };
+
+        self.publish(event).await
+    }
+
+    /// Publish user anonymized event
+    pub async fn publish_user_anonymized(&self, anonymization: &UserAnonymization) -> WalletResult<()> {
+        let event = WalletEvent::UserAnonymized {
+            user_id: anonymization.user_id.clone(),
+            anonymized_user_id: anonymization.anonymized_user_id.clone(),
+            timestamp: anonymization.anonymized_at,
+        };
+
+        self.publish(event).await
+    }
+
+    /// Publish verification lockout event (only for `WalletError::LockedOut`)
+    pub async fn publish_verification_lockout(
+        &self,
+        wallet: &Wallet,
+        error: &WalletError,
+    ) -> WalletResult<()> {
+        let WalletError::LockedOut {
+            factor,
+            failed_attempts,
+            lockouts,
+            until,
+        } = error
+        else {
+            return Ok(());
+        };
+
+        let event = WalletEvent::VerificationLockout {
+            wallet_id: wallet.id.clone(),
+            user_id: wallet.user_id.clone(),
+            factor: factor.to_string(),
+            failed_attempts: *failed_attempts,
+            lockouts: *lockouts,
+            locked_until: *until,
+            timestamp: Utc::now(),
+        };
+
+        self.publish(event).await
+    }
 }
 
 // What happens if Kafka publish fails after DB commit?
@@ -248,6 +1143,33 @@ impl KafkaProducer {
 //    - Pros: True atomicity
 //    - Cons: Performance impact, rare in practice
 // 
-// For this learning project: We accept the trade-off
+// For this learning project: We accept the trade-off by default
 // It demonstrates the classic distributed systems problem!
+// 
+// EVENT_DELIVERY=outbox is a step towards (1): events go to the
+// event_outbox table and the outbox-relay binary publishes them.
+// The outbox write still happens after the business commit, so the
+// remaining gap is Postgres-to-Postgres rather than Postgres-to-Kafka.
 
+/// Hand out the wallet's next event number
+///
+/// Numbers are taken when the event is published, so an event that is then
+/// lost (its publish failed with nothing to spill to) leaves a gap for
+/// history-service to report - which is the point.
+async fn next_sequence(pool: &PgPool, wallet_id: &str) -> WalletResult<i64> {
+    let sequence = sqlx::query_scalar::<_, i64>(
+        r#"
+        INSERT INTO wallet_event_sequences (wallet_id, last_sequence)
+        VALUES ($1, 1)
+        ON CONFLICT (wallet_id) DO UPDATE
+            SET last_sequence = wallet_event_sequences.last_sequence + 1,
+                updated_at = NOW()
+        RETURNING last_sequence
+        "#,
+    )
+    .bind(wallet_id)
+    .fetch_one(pool)
+    .await?;
+
+    Ok(sequence)
+}
@@ -0,0 +1,109 @@
+use crate::tls::ClientTls;
+use rdkafka::config::ClientConfig;
+
+/// Kafka connection security (TLS and SASL), shared by every client we create
+///
+/// Everything is optional: with nothing set we connect in plaintext, as in
+/// local dev. For managed clusters (MSK, Confluent Cloud) typically:
+///
+/// ```text
+/// KAFKA_SECURITY_PROTOCOL=SASL_SSL
+/// KAFKA_SASL_MECHANISM=PLAIN          # or SCRAM-SHA-512 for MSK
+/// KAFKA_SASL_USERNAME=<api key>
+/// KAFKA_SASL_PASSWORD=<api secret>
+/// ```
+///
+/// NOTE: TLS and SCRAM need librdkafka built with OpenSSL - enable rdkafka's
+/// `ssl` and `sasl` features for such deployments. Client creation fails
+/// with a clear librdkafka error otherwise.
+#[derive(Clone, Default)]
+pub struct KafkaSecurity {
+    /// PLAINTEXT, SSL, SASL_PLAINTEXT or SASL_SSL
+    pub protocol: Option<String>,
+    pub sasl_mechanism: Option<String>,
+    pub sasl_username: Option<String>,
+    pub sasl_password: Option<String>,
+    /// CA bundle to verify the brokers (system store when unset)
+    pub ssl_ca_location: Option<String>,
+    /// Client certificate and key, for mutual TLS
+    pub ssl_certificate_location: Option<String>,
+    pub ssl_key_location: Option<String>,
+    pub ssl_key_password: Option<String>,
+}
+
+impl KafkaSecurity {
+    /// Read KAFKA_SECURITY_PROTOCOL, KAFKA_SASL_MECHANISM,
+    /// KAFKA_SASL_USERNAME, KAFKA_SASL_PASSWORD, KAFKA_SSL_CA_LOCATION,
+    /// KAFKA_SSL_CERTIFICATE_LOCATION, KAFKA_SSL_KEY_LOCATION and
+    /// KAFKA_SSL_KEY_PASSWORD (empty = unset)
+    ///
+    /// Over SSL/SASL_SSL, unset KAFKA_SSL_* paths fall back to the service's
+    /// own TLS_CLIENT_CERT, TLS_CLIENT_KEY and TLS_CA_BUNDLE, so one client
+    /// certificate covers HTTP and Kafka.
+    pub fn from_env() -> Self {
+        fn var(name: &str) -> Option<String> {
+            std::env::var(name).ok().filter(|v| !v.is_empty())
+        }
+
+        let security = Self {
+            protocol: var("KAFKA_SECURITY_PROTOCOL"),
+            sasl_mechanism: var("KAFKA_SASL_MECHANISM"),
+            sasl_username: var("KAFKA_SASL_USERNAME"),
+            sasl_password: var("KAFKA_SASL_PASSWORD"),
+            ssl_ca_location: var("KAFKA_SSL_CA_LOCATION"),
+            ssl_certificate_location: var("KAFKA_SSL_CERTIFICATE_LOCATION"),
+            ssl_key_location: var("KAFKA_SSL_KEY_LOCATION"),
+            ssl_key_password: var("KAFKA_SSL_KEY_PASSWORD"),
+        };
+        security.with_client_tls(&ClientTls::from_env())
+    }
+
+    /// Fill unset TLS paths from `tls` - only when the protocol uses TLS
+    pub fn with_client_tls(mut self, tls: &ClientTls) -> Self {
+        let uses_tls = matches!(self.protocol.as_deref(), Some(p) if p.ends_with("SSL"));
+        if uses_tls {
+            self.ssl_certificate_location = self.ssl_certificate_location.or(tls.cert_path.clone());
+            self.ssl_key_location = self.ssl_key_location.or(tls.key_path.clone());
+            self.ssl_ca_location = self.ssl_ca_location.or(tls.ca_path.clone());
+        }
+        self
+    }
+
+    /// Add the configured settings to a client config
+    ///
+    /// Credentials without a mechanism default to SASL PLAIN.
+    pub fn apply(&self, config: &mut ClientConfig) {
+        let settings = [
+            ("security.protocol", &self.protocol),
+            ("sasl.username", &self.sasl_username),
+            ("sasl.password", &self.sasl_password),
+            ("ssl.ca.location", &self.ssl_ca_location),
+            ("ssl.certificate.location", &self.ssl_certificate_location),
+            ("ssl.key.location", &self.ssl_key_location),
+            ("ssl.key.password", &self.ssl_key_password),
+        ];
+        for (key, value) in settings {
+            if let Some(value) = value {
+                config.set(key, value);
+            }
+        }
+
+        match (&self.sasl_mechanism, &self.sasl_username) {
+            (Some(mechanism), _) => {
+                config.set("sasl.mechanism", mechanism);
+            }
+            (None, Some(_)) => {
+                config.set("sasl.mechanism", "PLAIN");
+            }
+            (None, None) => {}
+        }
+    }
+}
+
+/// A client config for `brokers` with the security settings from the env
+pub fn client_config(brokers: &str) -> ClientConfig {
+    let mut config = ClientConfig::new();
+    config.set("bootstrap.servers", brokers);
+    KafkaSecurity::from_env().apply(&mut config);
+    config
+}
@@ -0,0 +1,102 @@
+use chrono::{DateTime, Utc};
+use rdkafka::{ClientContext, Statistics};
+use serde::Serialize;
+use std::sync::{Arc, RwLock};
+
+/// How often librdkafka reports its statistics (statistics.interval.ms)
+pub const STATS_INTERVAL_MS: &str = "5000";
+
+/// The parts of librdkafka's statistics that explain slow or failing publishes
+///
+/// - Queue: messages waiting in the producer (msg_cnt vs queue.buffering.max)
+/// - Per broker: connection state, requests queued / awaiting a response,
+///   errors, retries, timeouts and round-trip time
+///
+/// The full dump (hundreds of fields per partition) is in the librdkafka
+/// docs' STATISTICS.md; this is what we actually look at.
+#[derive(Debug, Clone, Serialize)]
+pub struct ProducerStats {
+    pub sampled_at: DateTime<Utc>,
+    pub client_name: String,
+    pub queued_messages: u64,
+    pub queued_bytes: u64,
+    pub queue_max_messages: u64,
+    pub messages_sent: i64,
+    pub brokers: Vec<BrokerStats>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct BrokerStats {
+    pub name: String,
+    pub state: String, // UP, DOWN, CONNECT, AUTH...
+    pub state_age_ms: i64,
+    pub outbuf_messages: i64, // Waiting to be sent
+    pub waitresp_messages: i64, // Sent, waiting for the ack
+    pub tx_errors: u64,
+    pub tx_retries: u64,
+    pub request_timeouts: u64,
+    pub connects: Option<i64>,
+    pub disconnects: Option<i64>,
+    pub rtt_avg_us: Option<i64>,
+    pub rtt_p99_us: Option<i64>,
+}
+
+impl ProducerStats {
+    pub fn from_statistics(statistics: &Statistics) -> Self {
+        let mut brokers: Vec<BrokerStats> = statistics
+            .brokers
+            .values()
+            .map(|broker| BrokerStats {
+                name: broker.name.clone(),
+                state: broker.state.clone(),
+                state_age_ms: broker.stateage / 1000,
+                outbuf_messages: broker.outbuf_msg_cnt,
+                waitresp_messages: broker.waitresp_msg_cnt,
+                tx_errors: broker.txerrs,
+                tx_retries: broker.txretries,
+                request_timeouts: broker.req_timeouts,
+                connects: broker.connects,
+                disconnects: broker.disconnects,
+                rtt_avg_us: broker.rtt.as_ref().map(|rtt| rtt.avg),
+                rtt_p99_us: broker.rtt.as_ref().map(|rtt| rtt.p99),
+            })
+            .collect();
+        brokers.sort_by(|a, b| a.name.cmp(&b.name));
+
+        Self {
+            sampled_at: Utc::now(),
+            client_name: statistics.name.clone(),
+            queued_messages: statistics.msg_cnt,
+            queued_bytes: statistics.msg_size,
+            queue_max_messages: statistics.msg_max,
+            messages_sent: statistics.txmsgs,
+            brokers,
+        }
+    }
+}
+
+/// Client context that keeps the latest statistics report
+///
+/// librdkafka calls `stats` from its own thread every STATS_INTERVAL_MS;
+/// the producer wrapper reads the shared slot when asked.
+#[derive(Clone, Default)]
+pub struct StatsContext {
+    latest: Arc<RwLock<Option<ProducerStats>>>,
+}
+
+impl StatsContext {
+    /// The most recent report (None until librdkafka's first one)
+    pub fn latest(&self) -> Option<ProducerStats> {
+        self.latest
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone()
+    }
+}
+
+impl ClientContext for StatsContext {
+    fn stats(&self, statistics: Statistics) {
+        *self.latest.write().unwrap_or_else(|e| e.into_inner()) =
+            Some(ProducerStats::from_statistics(&statistics));
+    }
+}
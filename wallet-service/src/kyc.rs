@@ -0,0 +1,71 @@
+use crate::errors::{WalletError, WalletResult};
+use crate::models::KycLevel;
+use rust_decimal::Decimal;
+use std::str::FromStr;
+
+/// What users who haven't passed KYC may do
+///
+/// Why caps rather than blocking unverified users outright?
+/// - Sign-up stays instant: a new user can fund and pay small amounts
+///   while their documents are checked
+/// - The caps bound what a fake or stolen identity can move; verified
+///   users have none
+///
+/// Enforced in the repository, inside the DB transaction that moves the
+/// money, so every way in (funding, vouchers, transfers, templates) is
+/// covered.
+#[derive(Debug, Clone, Default)]
+pub struct KycLimits {
+    /// KYC_UNVERIFIED_MAX_BALANCE - most an unverified user may hold,
+    /// across all their wallets (unset: no cap)
+    pub unverified_max_balance: Option<Decimal>,
+    /// KYC_UNVERIFIED_MAX_TRANSFER - largest single transfer an unverified
+    /// user may send (unset: no cap)
+    pub unverified_max_transfer: Option<Decimal>,
+}
+
+impl KycLimits {
+    pub fn from_env() -> Self {
+        fn amount(name: &str) -> Option<Decimal> {
+            std::env::var(name)
+                .ok()
+                .and_then(|v| Decimal::from_str(v.trim()).ok())
+                .filter(|v| *v >= Decimal::ZERO)
+        }
+
+        Self {
+            unverified_max_balance: amount("KYC_UNVERIFIED_MAX_BALANCE"),
+            unverified_max_transfer: amount("KYC_UNVERIFIED_MAX_TRANSFER"),
+        }
+    }
+
+    pub fn is_off(&self) -> bool {
+        self.unverified_max_balance.is_none() && self.unverified_max_transfer.is_none()
+    }
+
+    /// May a user at `level` end up holding `total_balance`?
+    pub fn check_balance(&self, level: KycLevel, total_balance: Decimal) -> WalletResult<()> {
+        match self.unverified_max_balance {
+            Some(cap) if level == KycLevel::Unverified && total_balance > cap => {
+                Err(WalletError::KycLimitExceeded(format!(
+                    "unverified users may hold at most {}",
+                    cap
+                )))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// May a user at `level` send `amount` in one transfer?
+    pub fn check_transfer(&self, level: KycLevel, amount: Decimal) -> WalletResult<()> {
+        match self.unverified_max_transfer {
+            Some(cap) if level == KycLevel::Unverified && amount > cap => {
+                Err(WalletError::KycLimitExceeded(format!(
+                    "unverified users may transfer at most {}",
+                    cap
+                )))
+            }
+            _ => Ok(()),
+        }
+    }
+}
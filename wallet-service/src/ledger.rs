@@ -0,0 +1,181 @@
+use crate::models::{LedgerBreak, LedgerVerification};
+use chrono::{DateTime, SecondsFormat, Utc};
+use rust_decimal::Decimal;
+use sha2::{Digest, Sha256};
+use sqlx::FromRow;
+use std::collections::HashMap;
+
+/// `prev_hash` of the first entry in each wallet's chain
+pub const GENESIS_HASH: &str = "0000000000000000000000000000000000000000000000000000000000000000";
+
+/// Breaks listed in a LedgerVerification (all are counted)
+pub const MAX_REPORTED_BREAKS: usize = 100;
+
+/// Entries read per query when verifying
+pub const VERIFY_BATCH_SIZE: i64 = 1000;
+
+/// One row of transaction_ledger: a copy of a wallet transaction, chained
+/// to the wallet's previous entry by hash
+///
+/// Why a chain per wallet rather than one for the whole ledger?
+/// - Every transaction is written while its wallet's row is locked, so
+///   appends to one chain are already serialized; a single chain would put
+///   every money movement in the system behind one more lock
+/// - A unique (wallet_id, shard, prev_hash) index means two writers can
+///   never fork a chain, whatever the locking
+///
+/// Credits to a sharded wallet are written under their shard's row lock
+/// instead, so each shard has a chain of its own (`shard`; None is the
+/// wallet's own chain).
+///
+/// Memos aren't chained: they're encrypted, re-keyed and erasable, none of
+/// which may break the chain. What's chained is the money trail itself.
+#[derive(Debug, Clone, FromRow)]
+pub struct LedgerEntry {
+    pub seq: i64,
+    pub transaction_id: String,
+    pub wallet_id: String,
+    pub shard: Option<i32>,
+    pub amount: Decimal,
+    pub transaction_type: String,
+    pub status: String,
+    pub reference_id: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub prev_hash: String,
+    pub hash: String,
+}
+
+impl LedgerEntry {
+    /// What `hash` should be, from the entry's own fields
+    pub fn expected_hash(&self) -> String {
+        entry_hash(
+            &self.prev_hash,
+            &self.transaction_id,
+            &self.wallet_id,
+            self.amount,
+            &self.transaction_type,
+            &self.status,
+            self.reference_id.as_deref(),
+            self.created_at,
+        )
+    }
+}
+
+/// SHA-256 (hex) over the previous hash and the transaction's fields
+///
+/// Fields are joined with '|' (none of them can contain one). Amounts are
+/// normalized ("10", not "10.0000") and times are RFC 3339 in microseconds,
+/// UTC - the migration that backfilled the ledger hashes the same way in
+/// SQL, so this format can't change.
+#[allow(clippy::too_many_arguments)]
+pub fn entry_hash(
+    prev_hash: &str,
+    transaction_id: &str,
+    wallet_id: &str,
+    amount: Decimal,
+    transaction_type: &str,
+    status: &str,
+    reference_id: Option<&str>,
+    created_at: DateTime<Utc>,
+) -> String {
+    let canonical = [
+        prev_hash,
+        transaction_id,
+        wallet_id,
+        &amount.normalize().to_string(),
+        transaction_type,
+        status,
+        reference_id.unwrap_or_default(),
+        &created_at.to_rfc3339_opts(SecondsFormat::Micros, true),
+    ]
+    .join("|");
+
+    hex::encode(Sha256::digest(canonical.as_bytes()))
+}
+
+/// Walks ledger entries in (wallet_id, seq) order and collects what's wrong
+///
+/// Per entry:
+/// - `prev_hash` must be the hash of the previous entry in its chain (or
+///   GENESIS_HASH) - catches deleted, inserted or reordered entries
+/// - `hash` must match the entry's fields - catches edited entries
+/// - the transaction it copies must still exist with the same fields -
+///   catches edits to wallet_transactions itself
+#[derive(Debug, Default)]
+pub struct ChainCheck {
+    report: LedgerVerification,
+    /// The wallet being walked
+    wallet_id: Option<String>,
+    /// Hash of the last entry seen in each of its chains
+    heads: HashMap<Option<i32>, String>,
+}
+
+impl ChainCheck {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Check the next entry; `transaction_found` / `transaction_matches`
+    /// compare it with its wallet_transactions (or archived) row
+    pub fn check(&mut self, entry: &LedgerEntry, transaction_found: bool, transaction_matches: bool) {
+        if self.wallet_id.as_deref() != Some(entry.wallet_id.as_str()) {
+            self.report.wallets_checked += 1;
+            self.wallet_id = Some(entry.wallet_id.clone());
+            self.heads.clear();
+        }
+        let expected_prev = self
+            .heads
+            .get(&entry.shard)
+            .map_or(GENESIS_HASH, String::as_str);
+
+        let reason = if entry.prev_hash != expected_prev {
+            Some("prev_hash doesn't match the previous entry's hash")
+        } else if entry.hash != entry.expected_hash() {
+            Some("hash doesn't match the entry")
+        } else if !transaction_found {
+            Some("transaction is missing")
+        } else if !transaction_matches {
+            Some("transaction differs from the entry")
+        } else {
+            None
+        };
+        if let Some(reason) = reason {
+            self.record(LedgerBreak {
+                seq: Some(entry.seq),
+                wallet_id: entry.wallet_id.clone(),
+                transaction_id: entry.transaction_id.clone(),
+                reason: reason.to_string(),
+            });
+        }
+
+        self.report.entries_checked += 1;
+        self.heads.insert(entry.shard, entry.hash.clone());
+    }
+
+    /// Transactions with no ledger entry at all: `total` of them, of which
+    /// `sample` are (wallet_id, transaction_id)
+    pub fn unrecorded(&mut self, total: i64, sample: Vec<(String, String)>) {
+        let listed = sample.len() as i64;
+        for (wallet_id, transaction_id) in sample {
+            self.record(LedgerBreak {
+                seq: None,
+                wallet_id,
+                transaction_id,
+                reason: "transaction has no ledger entry".to_string(),
+            });
+        }
+        self.report.break_count += total - listed;
+    }
+
+    pub fn finish(mut self) -> LedgerVerification {
+        self.report.intact = self.report.break_count == 0;
+        self.report
+    }
+
+    fn record(&mut self, ledger_break: LedgerBreak) {
+        self.report.break_count += 1;
+        if self.report.breaks.len() < MAX_REPORTED_BREAKS {
+            self.report.breaks.push(ledger_break);
+        }
+    }
+}
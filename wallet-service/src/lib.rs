@@ -0,0 +1,45 @@
+pub mod archive;
+pub mod auth;
+pub mod avro;
+pub mod body_limit;
+pub mod circuit_breaker;
+pub mod codec;
+pub mod correlation;
+pub mod db_pool;
+pub mod degradation;
+pub mod encryption;
+pub mod errors;
+pub mod etag;
+pub mod fraud;
+pub mod handlers;
+pub mod health;
+pub mod ip_allowlist;
+pub mod kafka;
+pub mod kafka_security;
+pub mod kafka_stats;
+pub mod kyc;
+pub mod ledger;
+pub mod loadgen;
+pub mod lock_retry;
+pub mod merchant_signing;
+pub mod metrics;
+pub mod models;
+pub mod openapi;
+pub mod outbox;
+pub mod pin;
+pub mod protobuf;
+pub mod rate_limit;
+pub mod read_replica;
+pub mod repository;
+pub mod schema_registry;
+pub mod scrub;
+pub mod shutdown;
+pub mod signing;
+pub mod step_up;
+pub mod timeout;
+pub mod tls;
+pub mod validation;
+pub mod velocity;
+pub mod wallet_cache;
+pub mod wallet_state;
+pub mod webhooks;
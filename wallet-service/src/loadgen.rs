@@ -0,0 +1,176 @@
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::time::Duration;
+
+/// What one load-generator request does
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Operation {
+    Fund,
+    Transfer,
+}
+
+impl Operation {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Operation::Fund => "fund",
+            Operation::Transfer => "transfer",
+        }
+    }
+}
+
+/// The share of each operation in the traffic (`--mix fund=20,transfer=80`)
+///
+/// Weights, not percentages: `fund=1,transfer=3` is the same mix as
+/// `fund=25,transfer=75`. An operation left out gets no traffic.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Mix {
+    pub fund: u32,
+    pub transfer: u32,
+}
+
+impl Default for Mix {
+    fn default() -> Self {
+        Self {
+            fund: 20,
+            transfer: 80,
+        }
+    }
+}
+
+impl Mix {
+    pub fn parse(value: &str) -> Result<Self, String> {
+        let mut mix = Self {
+            fund: 0,
+            transfer: 0,
+        };
+
+        for part in value.split(',').map(str::trim).filter(|p| !p.is_empty()) {
+            let (name, weight) = part
+                .split_once('=')
+                .ok_or_else(|| format!("expected operation=weight, got {:?}", part))?;
+            let weight: u32 = weight
+                .trim()
+                .parse()
+                .map_err(|_| format!("weight of {} must be a whole number, got {:?}", name, weight))?;
+            match name.trim() {
+                "fund" => mix.fund = weight,
+                "transfer" => mix.transfer = weight,
+                other => return Err(format!("unknown operation {} (expected fund or transfer)", other)),
+            }
+        }
+
+        if mix.total() == 0 {
+            return Err("the mix must give some operation a weight above 0".to_string());
+        }
+        Ok(mix)
+    }
+
+    pub fn total(&self) -> u32 {
+        self.fund + self.transfer
+    }
+
+    /// The operation for a roll in [0, total)
+    pub fn pick(&self, roll: u32) -> Operation {
+        if roll < self.fund {
+            Operation::Fund
+        } else {
+            Operation::Transfer
+        }
+    }
+}
+
+/// Latencies and outcomes of one operation's requests
+#[derive(Debug, Clone, Default)]
+pub struct OperationStats {
+    latencies: Vec<Duration>,
+    /// Status code ("201", "429", ...) or "error" when no response came back
+    outcomes: BTreeMap<String, u64>,
+}
+
+impl OperationStats {
+    pub fn record(&mut self, latency: Duration, outcome: &str) {
+        self.latencies.push(latency);
+        *self.outcomes.entry(outcome.to_string()).or_insert(0) += 1;
+    }
+
+    /// Fold in another worker's requests
+    pub fn merge(&mut self, other: OperationStats) {
+        self.latencies.extend(other.latencies);
+        for (outcome, count) in other.outcomes {
+            *self.outcomes.entry(outcome).or_insert(0) += count;
+        }
+    }
+
+    pub fn requests(&self) -> u64 {
+        self.latencies.len() as u64
+    }
+
+    /// Requests that didn't get a 2xx
+    pub fn failures(&self) -> u64 {
+        self.outcomes
+            .iter()
+            .filter(|(outcome, _)| !outcome.starts_with('2'))
+            .map(|(_, count)| count)
+            .sum()
+    }
+
+    pub fn outcomes(&self) -> &BTreeMap<String, u64> {
+        &self.outcomes
+    }
+
+    /// The latency `quantile` (0.0 - 1.0) of the requests were at or under
+    /// (nearest rank)
+    pub fn percentile(&self, quantile: f64) -> Duration {
+        let mut sorted = self.latencies.clone();
+        sorted.sort_unstable();
+        percentile(&sorted, quantile)
+    }
+}
+
+fn percentile(sorted: &[Duration], quantile: f64) -> Duration {
+    if sorted.is_empty() {
+        return Duration::ZERO;
+    }
+    let rank = (quantile.clamp(0.0, 1.0) * sorted.len() as f64).ceil() as usize;
+    sorted[rank.clamp(1, sorted.len()) - 1]
+}
+
+/// One line per operation: throughput, latency percentiles and outcomes
+///
+/// ```text
+/// transfer   8021 req   267.4/s  p50 12.1ms  p95 41.0ms  p99 88.3ms  max 210.4ms  failed 14 (0.17%)  [200: 8007, 429: 14]
+/// ```
+pub fn render_report(stats: &BTreeMap<Operation, OperationStats>, elapsed: Duration) -> String {
+    let mut out = String::new();
+    let seconds = elapsed.as_secs_f64().max(f64::EPSILON);
+    let ms = |d: Duration| d.as_secs_f64() * 1000.0;
+
+    for (operation, stats) in stats {
+        let mut sorted = stats.latencies.clone();
+        sorted.sort_unstable();
+        let requests = stats.requests();
+        let failures = stats.failures();
+        let outcomes: Vec<String> = stats
+            .outcomes
+            .iter()
+            .map(|(outcome, count)| format!("{}: {}", outcome, count))
+            .collect();
+
+        let _ = writeln!(
+            out,
+            "{:<9} {:>7} req {:>8.1}/s  p50 {:.1}ms  p95 {:.1}ms  p99 {:.1}ms  max {:.1}ms  failed {} ({:.2}%)  [{}]",
+            operation.as_str(),
+            requests,
+            requests as f64 / seconds,
+            ms(percentile(&sorted, 0.50)),
+            ms(percentile(&sorted, 0.95)),
+            ms(percentile(&sorted, 0.99)),
+            ms(sorted.last().copied().unwrap_or_default()),
+            failures,
+            failures as f64 * 100.0 / requests.max(1) as f64,
+            outcomes.join(", ")
+        );
+    }
+
+    out
+}
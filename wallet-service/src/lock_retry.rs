@@ -0,0 +1,79 @@
+use rand::Rng;
+use std::time::Duration;
+
+/// How the repository retries an optimistic lock conflict before giving
+/// the client a 409
+///
+/// Why retry here rather than in clients?
+/// - A conflict on routine contention (two edits of one wallet a few ms
+///   apart) says nothing the client can act on - re-running the same
+///   request is all it could do, and every client would have to write that
+/// - Each attempt re-reads the wallet, so an If-Match that is now stale
+///   still fails with 412 rather than being retried past
+///
+/// Delays double from `base_delay` up to `max_delay`, with full jitter (a
+/// random delay between zero and that), so requests that collided once
+/// don't collide again in lockstep.
+#[derive(Debug, Clone)]
+pub struct LockRetry {
+    /// LOCK_RETRY_ATTEMPTS - tries in all, the first included (1: no retry)
+    pub max_attempts: u32,
+    /// LOCK_RETRY_BASE_MS - ceiling of the first delay
+    pub base_delay: Duration,
+    /// LOCK_RETRY_MAX_MS - no delay's ceiling goes above this
+    pub max_delay: Duration,
+}
+
+impl Default for LockRetry {
+    fn default() -> Self {
+        Self {
+            max_attempts: 5,
+            base_delay: Duration::from_millis(10),
+            max_delay: Duration::from_millis(200),
+        }
+    }
+}
+
+impl LockRetry {
+    pub fn from_env() -> Self {
+        fn millis(name: &str) -> Option<Duration> {
+            std::env::var(name)
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .map(Duration::from_millis)
+        }
+
+        let defaults = Self::default();
+        Self {
+            max_attempts: std::env::var("LOCK_RETRY_ATTEMPTS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .filter(|&n| n > 0)
+                .unwrap_or(defaults.max_attempts),
+            base_delay: millis("LOCK_RETRY_BASE_MS").unwrap_or(defaults.base_delay),
+            max_delay: millis("LOCK_RETRY_MAX_MS").unwrap_or(defaults.max_delay),
+        }
+    }
+
+    /// No retries - conflicts go straight back to the client
+    pub fn disabled() -> Self {
+        Self {
+            max_attempts: 1,
+            ..Self::default()
+        }
+    }
+
+    /// The longest delay before retry number `retry` (1 for the first)
+    pub fn ceiling(&self, retry: u32) -> Duration {
+        let doublings = retry.saturating_sub(1).min(16);
+        self.base_delay
+            .saturating_mul(1 << doublings)
+            .min(self.max_delay)
+    }
+
+    /// A random delay up to `ceiling(retry)`
+    pub fn delay(&self, retry: u32) -> Duration {
+        let ceiling = self.ceiling(retry).as_millis() as u64;
+        Duration::from_millis(rand::thread_rng().gen_range(0..=ceiling))
+    }
+}
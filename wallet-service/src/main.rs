@@ -1,20 +1,47 @@
-mod errors;
-mod handlers;
-mod kafka;
-mod models;
-mod repository;
-
-use crate::handlers::AppState;
-use crate::kafka::KafkaProducer;
-use crate::repository::WalletRepository;
 use axum::{
-    routing::{get, post},
+    middleware,
+    routing::{delete, get, post, put},
     Router,
 };
-use sqlx::postgres::PgPoolOptions;
+use std::net::SocketAddr;
 use std::sync::Arc;
 use tower_http::trace::TraceLayer;
 use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
+use wallet_service::archive::{ArchiveConfig, WalletArchiver};
+use wallet_service::auth::{enforce_ownership, require_auth, AuthConfig, Authenticator};
+use wallet_service::body_limit::{require_json_body, BodyLimits};
+use wallet_service::circuit_breaker::BreakerConfig;
+use wallet_service::codec::EventCodec;
+use wallet_service::correlation::with_correlation;
+use wallet_service::db_pool::PoolConfig;
+use wallet_service::degradation::{shed_when_degraded, DegradationConfig, DegradationController};
+use wallet_service::encryption::FieldCipher;
+use wallet_service::fraud::FraudEngine;
+use wallet_service::handlers::{self, AppState};
+use wallet_service::ip_allowlist::{restrict_admin, AdminAllowlist};
+use wallet_service::kafka::KafkaProducer;
+use wallet_service::kyc::KycLimits;
+use wallet_service::lock_retry::LockRetry;
+use wallet_service::merchant_signing::MerchantSigning;
+use wallet_service::metrics::BusinessMetrics;
+use wallet_service::openapi;
+use wallet_service::pin::PinPolicy;
+use wallet_service::rate_limit::{limit_rate, RateLimitConfig, RateLimiter};
+use wallet_service::read_replica::{route_reads, ReadReplica, ReplicaConfig};
+use wallet_service::repository::WalletRepository;
+use wallet_service::shutdown::Shutdown;
+use wallet_service::signing::EventSigner;
+use wallet_service::step_up::{CodeNotifier, PendingTransferSweeper, StepUpConfig};
+use wallet_service::timeout::{enforce_timeout, TimeoutConfig};
+use wallet_service::tls::{graceful_handle, ServerTls};
+use wallet_service::validation::AmountRules;
+use wallet_service::velocity::VelocityLimits;
+use wallet_service::wallet_cache::WalletCache;
+use wallet_service::wallet_state::WalletStatePublisher;
+use wallet_service::webhooks::{HttpSender, WebhookDispatcher};
+
+/// How long shutdown waits for Kafka to acknowledge queued events
+const SHUTDOWN_FLUSH_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(10);
 
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
@@ -40,6 +67,15 @@ async fn main() -> anyhow::Result<()> {
     let kafka_topic = std::env::var("KAFKA_TOPIC")
         .unwrap_or_else(|_| "wallet-events".to_string());
 
+    // Log-compacted topic with the latest snapshot of every wallet
+    let state_topic = std::env::var("WALLET_STATE_TOPIC")
+        .unwrap_or_else(|_| "wallet-state".to_string());
+
+    // Wallets are single-currency; this labels the volume metrics and the
+    // per-user balance totals
+    let currency = std::env::var("WALLET_CURRENCY")
+        .unwrap_or_else(|_| "USD".to_string());
+
     let server_port = std::env::var("PORT")
         .unwrap_or_else(|_| "3000".to_string())
         .parse::<u16>()?;
@@ -48,66 +84,555 @@ async fn main() -> anyhow::Result<()> {
     tracing::info!("Database: {}", database_url);
     tracing::info!("Kafka brokers: {}", kafka_brokers);
     tracing::info!("Kafka topic: {}", kafka_topic);
+    let client_tls = wallet_service::tls::ClientTls::from_env();
+    if let Some(cert) = &client_tls.cert_path {
+        tracing::info!("Outbound mTLS client certificate: {}", cert);
+    }
 
     // Set up database connection pool
     // Why pool?
     // - Reuse connections (expensive to create)
     // - Limit concurrent connections to database
     // - Automatic connection management
-    tracing::info!("Connecting to database...");
-    let pool = PgPoolOptions::new()
-        .max_connections(10)
-        .connect(&database_url)
-        .await?;
+    // (DATABASE_MAX_CONNECTIONS etc. - 10 connections by default)
+    let pool_config = PoolConfig::from_env();
+    tracing::info!(
+        "Connecting to database ({}-{} connections, {}s acquire timeout, statement timeout {})...",
+        pool_config.min_connections,
+        pool_config.max_connections,
+        pool_config.acquire_timeout.as_secs(),
+        pool_config
+            .statement_timeout
+            .map_or("off".to_string(), |t| format!("{}ms", t.as_millis()))
+    );
+    let pool = pool_config.connect(&database_url).await?;
+    // Read-only queries get a pool of their own (DATABASE_QUERY_* - sized
+    // like the mutation pool by default)
+    let query_pool_config = pool_config.queries_from_env();
+    tracing::info!(
+        "Query pool: {}-{} connections, statement timeout {}",
+        query_pool_config.min_connections,
+        query_pool_config.max_connections,
+        query_pool_config
+            .statement_timeout
+            .map_or("off".to_string(), |t| format!("{}ms", t.as_millis()))
+    );
+    let query_pool = query_pool_config.connect(&database_url).await?;
 
     // Run migrations
     tracing::info!("Running database migrations...");
     sqlx::migrate!("./migrations").run(&pool).await?;
     tracing::info!("Migrations completed successfully");
 
-    // Create repository
-    let repository = WalletRepository::new(pool);
+    // Create repository, with per-wallet velocity limits on transfers
+    // (VELOCITY_* - none by default)
+    let velocity = VelocityLimits::from_env();
+    if !velocity.is_off() {
+        tracing::info!(
+            "Velocity limits: {} transfers / {} sent per {}s",
+            velocity.max_transfers.map_or("any".to_string(), |n| n.to_string()),
+            velocity.max_volume.map_or("any".to_string(), |v| v.to_string()),
+            velocity.window.as_secs()
+        );
+    }
+    // ...and KYC caps on unverified users (KYC_UNVERIFIED_* - none by default)
+    let kyc = KycLimits::from_env();
+    if !kyc.is_off() {
+        tracing::info!(
+            "Unverified users: hold at most {}, transfer at most {}",
+            kyc.unverified_max_balance.map_or("any".to_string(), |v| v.to_string()),
+            kyc.unverified_max_transfer.map_or("any".to_string(), |v| v.to_string())
+        );
+    }
+    // ...and memos, notes, nicknames and beneficiary names encrypted at rest
+    // (FIELD_ENCRYPTION_KEYS - stored as written when unset)
+    let cipher = FieldCipher::from_env().map_err(anyhow::Error::msg)?;
+    match cipher.current_key_id() {
+        Some(key_id) => tracing::info!("Field encryption on (current key '{}')", key_id),
+        None => tracing::warn!("Field encryption off - FIELD_ENCRYPTION_KEYS is not set"),
+    }
+    let mut repository = WalletRepository::new(pool.clone())
+        .with_velocity_limits(velocity)
+        .with_kyc_limits(kyc)
+        .with_field_cipher(cipher.clone())
+        // Optimistic lock conflicts retried before a 409 (LOCK_RETRY_*)
+        .with_lock_retry(LockRetry::from_env())
+        .with_query_pool(query_pool.clone());
+    // ...and wallet reads cached in Redis (WALLET_CACHE_URL - off when unset)
+    if let Some(cache) = WalletCache::from_env().await.map_err(anyhow::Error::msg)? {
+        tracing::info!("Wallet cache on (entries live {}s)", cache.ttl().as_secs());
+        repository = repository.with_cache(cache);
+    }
+    // ...and GET requests' queries on a read replica (DATABASE_READ_URL -
+    // the primary when unset)
+    if let Some(config) = ReplicaConfig::from_env() {
+        tracing::info!(
+            "Read replica configured (reads fall back to the primary over {}ms lag)",
+            config.max_lag.as_millis()
+        );
+        let replica = ReadReplica::connect(&config, &query_pool_config).await?;
+        replica.clone().spawn_probe();
+        repository = repository.with_read_replica(replica);
+    }
+
+    // SIGTERM / Ctrl-C: stop accepting requests, finish open ones, flush Kafka
+    let shutdown = Shutdown::new();
+    shutdown.trigger_on_signal();
 
     // Create Kafka producer
+    // EVENT_DELIVERY=outbox: write events to event_outbox, the outbox-relay
+    // binary delivers them (default "direct": send to Kafka in the request)
     tracing::info!("Initializing Kafka producer...");
-    let kafka_producer = Arc::new(KafkaProducer::new(&kafka_brokers, kafka_topic)?);
+    // EVENT_CODEC=avro: Confluent Avro via SCHEMA_REGISTRY_URL (default json)
+    let codec = EventCodec::from_env(&kafka_topic).await?;
+    tracing::info!("Event codec: {}", codec.name());
+    let mut kafka_producer = KafkaProducer::new(&kafka_brokers, kafka_topic)?
+        .with_codec(codec)
+        .with_breaker(BreakerConfig::from_env())
+        // Per-wallet `sequence` on every event
+        .with_sequences(pool.clone())
+        .with_state_topic(state_topic.clone());
+    // EVENT_SIGNING_KEY: sign events for history-service to verify
+    match EventSigner::from_env().map_err(anyhow::Error::msg)? {
+        Some(signer) => {
+            tracing::info!("Event signing on (key '{}')", signer.key_id());
+            kafka_producer = kafka_producer.with_signer(signer);
+        }
+        None => tracing::warn!("EVENT_SIGNING_KEY unset - events are published unsigned"),
+    }
+    // Webhook subscriptions (WEBHOOKS=off to disable queueing and delivery)
+    let webhooks_enabled = std::env::var("WEBHOOKS").as_deref() != Ok("off");
+    if webhooks_enabled {
+        kafka_producer = kafka_producer.with_webhooks(pool.clone());
+    }
+    let spill = if std::env::var("EVENT_DELIVERY").as_deref() == Ok("outbox") {
+        tracing::info!("Event delivery: outbox (run the outbox-relay binary)");
+        kafka_producer = kafka_producer.with_outbox(pool.clone());
+        false
+    } else if std::env::var("EVENT_SPILL").as_deref() == Ok("off") {
+        false
+    } else {
+        // Direct delivery: events that can't be sent wait in event_outbox
+        kafka_producer = kafka_producer.with_spill(pool.clone());
+        true
+    };
+    let kafka_producer = Arc::new(kafka_producer);
+    if spill {
+        kafka_producer
+            .clone()
+            .spawn_spill_flusher(std::time::Duration::from_secs(1), shutdown.clone());
+    }
     tracing::info!("Kafka producer initialized");
 
+    // Wallet snapshots to the compacted topic (WALLET_STATE=off to disable)
+    if std::env::var("WALLET_STATE").as_deref() != Ok("off") {
+        tracing::info!("Wallet state topic: {}", state_topic);
+        WalletStatePublisher::new(pool.clone(), kafka_producer.clone(), 500)
+            .with_field_cipher(cipher)
+            .spawn(std::time::Duration::from_secs(1), shutdown.clone());
+    }
+
+    if webhooks_enabled {
+        WebhookDispatcher::new(pool.clone(), HttpSender::new()?, 20)
+            .spawn(std::time::Duration::from_secs(1), shutdown.clone());
+    }
+
+    // Inactive wallets to the archive tables (WALLET_ARCHIVE=off to disable)
+    let archive = ArchiveConfig::from_env();
+    if std::env::var("WALLET_ARCHIVE").as_deref() != Ok("off") {
+        tracing::info!(
+            "Wallet archiving: after {} days inactive, every {:?}",
+            archive.inactive_days,
+            archive.interval
+        );
+        WalletArchiver::new(repository.clone(), archive.clone()).spawn(shutdown.clone());
+    }
+
+    // Step-up verification for transfers over STEP_UP_THRESHOLD
+    let step_up = StepUpConfig::from_env();
+    if let Some(threshold) = step_up.threshold {
+        tracing::info!("Step-up verification: transfers over {}", threshold);
+        if step_up.notify_url.is_none() {
+            tracing::warn!("STEP_UP_NOTIFY_URL unset - verification codes are only logged");
+        }
+    }
+    // Runs without a threshold too: codes already issued still expire
+    PendingTransferSweeper::new(repository.clone(), step_up.sweep_interval)
+        .spawn(shutdown.clone());
+    let code_notifier = CodeNotifier::new(step_up.notify_url.clone())?;
+
+    // Fraud rules run before every funding and transfer commits
+    let fraud = Arc::new(FraudEngine::from_env());
+    if fraud.is_empty() {
+        tracing::info!("Fraud rules: none configured");
+    } else {
+        tracing::info!("Fraud rules: {}", fraud.rule_names().join(", "));
+    }
+
+    // Degradation controller - probes DB latency and Kafka backlog in the background
+    let degradation = Arc::new(DegradationController::new(DegradationConfig::from_env()));
+    degradation
+        .clone()
+        .spawn_probe(repository.clone(), kafka_producer.clone());
+
     // Create application state
     let state = AppState {
         repository,
-        kafka_producer,
+        kafka_producer: kafka_producer.clone(),
+        metrics: Arc::new(BusinessMetrics::new(currency.clone())),
+        degradation: degradation.clone(),
+        currency,
+        amount_rules: AmountRules::from_env(),
+        archive,
+        pin_policy: PinPolicy::from_env(),
+        step_up,
+        code_notifier,
+        fraud,
     };
 
-    // Build the router with all routes
-    let app = Router::new()
+    // Per-IP, per-wallet and per-user limits on money movement (RATE_LIMIT=off
+    // to disable). Route layers, so they run after require_auth below
+    let rate_limits = RateLimitConfig::from_env();
+    let fund_limiter = Arc::new(RateLimiter::new(
+        "fund",
+        rate_limits.fund,
+        rate_limits.trust_forwarded_for,
+    ));
+    let transfer_limiter = Arc::new(RateLimiter::new(
+        "transfer",
+        rate_limits.transfer,
+        rate_limits.trust_forwarded_for,
+    ));
+    let fund_limit = || middleware::from_fn_with_state(fund_limiter.clone(), limit_rate);
+    let transfer_limit = || middleware::from_fn_with_state(transfer_limiter.clone(), limit_rate);
+
+    // Essential routes - always served, even when degraded
+    // (money movement, balance reads, health/metrics)
+    let essential = Router::new()
         // Health check
         .route("/health", get(handlers::health_check))
+        .route("/health/degradation", get(handlers::get_degradation_status))
+        .route("/metrics", get(handlers::get_metrics))
+        .route("/admin/kafka/producer", get(handlers::get_producer_diagnostics))
+        // API description
+        .route("/openapi.json", get(openapi::openapi_json))
+        .route("/docs", get(openapi::swagger_ui))
         // Wallet management
         .route("/wallets", post(handlers::create_wallet))
-        .route("/wallets/:wallet_id", get(handlers::get_wallet))
+        .route(
+            "/wallets/:wallet_id",
+            get(handlers::get_wallet).patch(handlers::update_wallet),
+        )
+        .route("/wallets/batch-get", post(handlers::batch_get_wallets))
         .route("/users/:user_id/wallets", get(handlers::get_user_wallets))
+        .route("/users/:user_id/balance-summary", get(handlers::get_user_balance_summary))
+        .route("/users/:user_id/data", delete(handlers::erase_user_data))
         // Wallet operations
-        .route("/wallets/:wallet_id/fund", post(handlers::fund_wallet))
-        .route("/wallets/:wallet_id/transfer", post(handlers::transfer))
+        .route(
+            "/wallets/:wallet_id/fund",
+            post(handlers::fund_wallet).route_layer(fund_limit()),
+        )
+        .route(
+            "/wallets/:wallet_id/transfer",
+            post(handlers::transfer).route_layer(transfer_limit()),
+        )
+        .route(
+            "/wallets/:wallet_id/pending-transfers/:pending_id",
+            get(handlers::get_pending_transfer).delete(handlers::cancel_pending_transfer),
+        )
+        .route(
+            "/wallets/:wallet_id/pending-transfers/:pending_id/confirm",
+            post(handlers::confirm_pending_transfer).route_layer(transfer_limit()),
+        )
+        .route(
+            "/wallets/:wallet_id/redeem",
+            post(handlers::redeem_voucher).route_layer(fund_limit()),
+        )
+        .route(
+            "/templates/:template_id/execute",
+            post(handlers::execute_template).route_layer(transfer_limit()),
+        )
+        .route(
+            "/wallets/:wallet_id/pots/:pot_id/deposit",
+            post(handlers::deposit_to_pot),
+        )
+        .route(
+            "/wallets/:wallet_id/pots/:pot_id/withdraw",
+            post(handlers::withdraw_from_pot),
+        );
+
+    // Non-essential routes - shed with 503 + Retry-After while degraded
+    // (configuration, aggregates, admin tooling)
+    let non_essential = Router::new()
+        // Round-up savings
+        .route(
+            "/wallets/:wallet_id/round-up",
+            put(handlers::set_round_up_rule)
+                .get(handlers::get_round_up_rule)
+                .delete(handlers::delete_round_up_rule),
+        )
+        // Transaction PINs
+        .route(
+            "/wallets/:wallet_id/pin",
+            put(handlers::set_pin).get(handlers::get_pin),
+        )
+        // Pots (sub-wallets)
+        .route(
+            "/wallets/:wallet_id/pots",
+            post(handlers::create_pot).get(handlers::get_pots),
+        )
+        // Transfer templates
+        .route(
+            "/wallets/:wallet_id/templates",
+            post(handlers::create_template).get(handlers::get_templates),
+        )
+        .route("/templates/:template_id", delete(handlers::delete_template))
+        // Saved beneficiaries
+        .route(
+            "/users/:user_id/beneficiaries",
+            post(handlers::create_beneficiary).get(handlers::get_beneficiaries),
+        )
+        .route(
+            "/users/:user_id/beneficiaries/:beneficiary_id",
+            delete(handlers::delete_beneficiary),
+        )
+        // Webhook subscriptions and their delivery log
+        .route(
+            "/webhooks",
+            post(handlers::create_webhook).get(handlers::get_webhooks),
+        )
+        .route(
+            "/webhooks/:webhook_id",
+            get(handlers::get_webhook)
+                .put(handlers::update_webhook)
+                .delete(handlers::delete_webhook),
+        )
+        .route(
+            "/webhooks/:webhook_id/deliveries",
+            get(handlers::get_webhook_deliveries),
+        )
+        .route(
+            "/webhooks/:webhook_id/deliveries/:delivery_id/attempts",
+            get(handlers::get_webhook_delivery_attempts),
+        )
+        // Admin: vouchers
+        .route("/admin/vouchers", post(handlers::create_voucher))
+        // Admin: wallet listing and manual balance corrections
+        .route("/admin/wallets", get(handlers::get_admin_wallets))
+        .route(
+            "/admin/wallets/:wallet_id/adjustments",
+            post(handlers::create_adjustment),
+        )
+        .route(
+            "/admin/wallets/:wallet_id/balance-shards",
+            get(handlers::get_balance_shards).put(handlers::set_balance_shards),
+        )
+        // Admin: review of transfers held by the fraud rules
+        .route("/admin/held-transfers", get(handlers::get_held_transfers))
+        .route(
+            "/admin/held-transfers/:pending_id/release",
+            post(handlers::release_held_transfer),
+        )
+        .route(
+            "/admin/held-transfers/:pending_id/reject",
+            post(handlers::reject_held_transfer),
+        )
+        // Admin: API keys for machine clients
+        .route(
+            "/admin/api-keys",
+            get(handlers::get_api_keys).post(handlers::create_api_key),
+        )
+        .route("/admin/api-keys/:key_id/revoke", post(handlers::revoke_api_key))
+        // Admin: KYC levels
+        .route(
+            "/admin/users/:user_id/kyc",
+            get(handlers::get_user_kyc).put(handlers::set_user_kyc),
+        )
+        // Admin: wallets and users that may not transfer
+        .route(
+            "/admin/blocklist",
+            get(handlers::get_blocklist).post(handlers::create_blocklist_entry),
+        )
+        .route(
+            "/admin/blocklist/:entry_id/remove",
+            post(handlers::remove_blocklist_entry),
+        )
+        // Admin: archival of inactive wallets
+        .route("/admin/wallets/archive", post(handlers::archive_wallets))
+        .route(
+            "/admin/archived-wallets/:wallet_id",
+            get(handlers::get_archived_wallet),
+        )
+        // Transaction lookup by reference
+        .route("/transactions", get(handlers::search_transactions))
+        .route(
+            "/transactions/:transaction_id/receipt",
+            get(handlers::get_transaction_receipt),
+        )
+        // Admin: support case linkage
+        .route("/admin/transactions", get(handlers::get_admin_transactions))
+        .route(
+            "/admin/transactions/:transaction_id",
+            get(handlers::get_admin_transaction),
+        )
+        .route(
+            "/admin/transactions/:transaction_id/notes",
+            post(handlers::add_transaction_note),
+        )
+        // Admin: who did what
+        .route("/admin/audit-log", get(handlers::get_audit_log))
+        // Admin: tamper check of the hash-chained transaction ledger
+        .route("/admin/ledger/verify", get(handlers::verify_ledger))
+        .route_layer(middleware::from_fn_with_state(
+            degradation,
+            shed_when_degraded,
+        ));
+
+    // Build the router with all routes
+    // Callers only reach their own wallets (does nothing with AUTH=off)
+    let mut app = essential.merge(non_essential).route_layer(middleware::from_fn_with_state(
+        state.clone(),
+        enforce_ownership,
+    ));
+    // Bearer tokens from AUTH_ISSUER, X-API-Key for services, or merchants'
+    // signed fund/transfer requests (AUTH=off to disable)
+    match AuthConfig::from_env()? {
+        Some(config) => {
+            tracing::info!(issuer = %config.issuer, "JWT authentication on");
+            let mut authenticator = Authenticator::new(config)?.with_api_keys(state.repository.clone());
+            if let Some(merchants) = MerchantSigning::from_env().map_err(anyhow::Error::msg)? {
+                tracing::info!(
+                    merchants = %merchants.merchant_ids().join(", "),
+                    window_secs = merchants.window().as_secs(),
+                    "Signed merchant requests accepted on fund and transfer"
+                );
+                authenticator = authenticator.with_merchants(merchants);
+            }
+            app = app.route_layer(middleware::from_fn_with_state(Arc::new(authenticator), require_auth));
+        }
+        None => tracing::warn!("AUTH=off - any caller can reach any wallet"),
+    }
+    // /admin/* only from ADMIN_ALLOWED_CIDRS - checked before authentication
+    match AdminAllowlist::from_env().map_err(anyhow::Error::msg)? {
+        Some(allowlist) => {
+            let networks: Vec<String> = allowlist.networks().iter().map(ToString::to_string).collect();
+            tracing::info!(networks = %networks.join(", "), "Admin routes restricted by address");
+            app = app.layer(middleware::from_fn_with_state(Arc::new(allowlist), restrict_admin));
+        }
+        None => tracing::info!("ADMIN_ALLOWED_CIDRS unset - admin routes reachable from any address"),
+    }
+    let app = app
+        // 504 for any route slower than its timeout (REQUEST_TIMEOUT_*)
+        .route_layer(middleware::from_fn_with_state(
+            Arc::new(TimeoutConfig::from_env()),
+            enforce_timeout,
+        ))
         // Add state and middleware
         .with_state(state)
-        .layer(TraceLayer::new_for_http()); // Request/response logging
+        // JSON bodies only, at most MAX_BODY_BYTES (415 / 413)
+        .layer(middleware::from_fn_with_state(
+            Arc::new(BodyLimits::from_env()),
+            require_json_body,
+        ))
+        // GET/HEAD queries may use the read replica (X-Read-Consistency: primary opts out)
+        .layer(middleware::from_fn(route_reads))
+        .layer(TraceLayer::new_for_http()) // Request/response logging
+        // Correlation and request IDs - outermost, so every span has them
+        .layer(middleware::from_fn(with_correlation));
 
     // Start the server
     let addr = format!("0.0.0.0:{}", server_port);
     let listener = tokio::net::TcpListener::bind(&addr).await?;
+    let server_tls = ServerTls::from_env().map_err(anyhow::Error::msg)?;
 
-    tracing::info!("🚀 Wallet Service listening on {}", addr);
+    let scheme = if server_tls.is_some() { "https" } else { "http" };
+    tracing::info!("🚀 Wallet Service listening on {}://{}", scheme, addr);
     tracing::info!("📝 API Documentation:");
     tracing::info!("  POST   /wallets                    - Create wallet");
     tracing::info!("  GET    /wallets/:wallet_id         - Get wallet");
+    tracing::info!("  PATCH  /wallets/:wallet_id         - Update nickname, labels, default");
+    tracing::info!("  POST   /wallets/batch-get          - Get up to 100 wallets by ID");
     tracing::info!("  GET    /users/:user_id/wallets     - Get user's wallets");
+    tracing::info!("  GET    /users/:user_id/balance-summary - Wallets and total balance");
+    tracing::info!("  DELETE /users/:user_id/data - Erase a user's personal data (GDPR)");
     tracing::info!("  POST   /wallets/:wallet_id/fund    - Fund wallet");
     tracing::info!("  POST   /wallets/:wallet_id/transfer - Transfer money");
+    tracing::info!("  POST   /wallets/:id/pending-transfers/:pending_id/confirm - Confirm step-up transfer");
+    tracing::info!("  POST   /wallets/:wallet_id/redeem  - Redeem voucher code");
+    tracing::info!("  PUT    /wallets/:wallet_id/round-up  - Configure round-up savings");
+    tracing::info!("  PUT    /wallets/:wallet_id/pin       - Set or change transaction PIN");
+    tracing::info!("  POST   /wallets/:wallet_id/pots      - Create pot");
+    tracing::info!("  GET    /wallets/:wallet_id/pots      - List pots with balances");
+    tracing::info!("  POST   /wallets/:id/pots/:pot_id/deposit  - Move money into pot");
+    tracing::info!("  POST   /wallets/:id/pots/:pot_id/withdraw - Move money out of pot");
+    tracing::info!("  POST   /wallets/:wallet_id/templates - Save transfer template");
+    tracing::info!("  GET    /wallets/:wallet_id/templates - List transfer templates");
+    tracing::info!("  DELETE /templates/:template_id       - Delete transfer template");
+    tracing::info!("  POST   /templates/:template_id/execute - Execute transfer template");
+    tracing::info!("  POST   /users/:user_id/beneficiaries - Save beneficiary");
+    tracing::info!("  GET    /users/:user_id/beneficiaries - List beneficiaries");
+    tracing::info!("  DELETE /users/:user_id/beneficiaries/:id - Delete beneficiary");
+    tracing::info!("  GET    /transactions?reference_id= - Transactions sharing a reference");
+    tracing::info!("  GET    /transactions/:id/receipt   - Payment details");
+    tracing::info!("  POST   /webhooks                   - Register webhook (GET lists them)");
+    tracing::info!("  PUT    /webhooks/:id               - Update or pause webhook (DELETE removes)");
+    tracing::info!("  GET    /webhooks/:id/deliveries    - Recent deliveries and their attempts");
+    tracing::info!("  POST   /admin/vouchers             - Mint voucher");
+    tracing::info!("  GET    /admin/wallets?user_id=&min_balance=&page= - Filtered wallet listing");
+    tracing::info!("  POST   /admin/wallets/:id/adjustments - Manual credit/debit with reason code");
+    tracing::info!("  PUT    /admin/wallets/:id/balance-shards - Shard a hot wallet's balance (GET reads it)");
+    tracing::info!("  GET    /admin/held-transfers       - Transfers held by the fraud rules");
+    tracing::info!("  POST   /admin/held-transfers/:id/release - Let a held transfer go ahead");
+    tracing::info!("  POST   /admin/held-transfers/:id/reject  - Refuse a held transfer");
+    tracing::info!("  POST   /admin/api-keys             - Issue API key (read or transact)");
+    tracing::info!("  GET    /admin/api-keys             - List API keys");
+    tracing::info!("  POST   /admin/api-keys/:id/revoke  - Revoke API key");
+    tracing::info!("  PUT    /admin/users/:id/kyc        - Set a user's KYC level (GET reads it)");
+    tracing::info!("  POST   /admin/blocklist            - Blocklist a wallet or user (GET lists them)");
+    tracing::info!("  POST   /admin/blocklist/:id/remove - Take an entry off the blocklist");
+    tracing::info!("  POST   /admin/wallets/archive      - Archive inactive wallets now");
+    tracing::info!("  GET    /admin/archived-wallets/:id - Archived wallet with its history");
+    tracing::info!("  GET    /admin/transactions?case_id= - Transactions linked to a case");
+    tracing::info!("  GET    /admin/transactions/:id     - Transaction with support notes");
+    tracing::info!("  POST   /admin/transactions/:id/notes - Add support note");
+    tracing::info!("  GET    /admin/audit-log?actor=&target_id= - Audit log of admin actions");
+    tracing::info!("  GET    /admin/ledger/verify?wallet_id= - Check the transaction ledger's hash chains");
+    tracing::info!("  GET    /admin/kafka/producer       - Kafka producer queue and broker stats");
+    tracing::info!("  GET    /metrics                     - Business KPIs (OpenMetrics)");
+    tracing::info!("  GET    /health/degradation          - Degraded-mode state");
     tracing::info!("  GET    /health                      - Health check");
+    tracing::info!("  GET    /openapi.json                - OpenAPI spec (Swagger UI at /docs)");
+
+    // Peer addresses for the per-IP rate limits and the admin allowlist
+    let make_service = app.into_make_service_with_connect_info::<SocketAddr>();
+    match server_tls {
+        Some(tls) => {
+            let config = tls.load().await.map_err(anyhow::Error::msg)?;
+            tls.spawn_reloader(config.clone(), shutdown.clone());
+            axum_server::from_tcp_rustls(listener.into_std()?, config)
+                .handle(graceful_handle(shutdown.clone()))
+                .serve(make_service)
+                .await?;
+        }
+        None => {
+            axum::serve(listener, make_service)
+                .with_graceful_shutdown(async move { shutdown.wait().await })
+                .await?;
+        }
+    }
 
-    axum::serve(listener, app).await?;
+    // Every request has finished - wait for events still queued in the
+    // producer, then close the pools
+    tracing::info!(in_flight = kafka_producer.in_flight_count(), "Flushing Kafka producer");
+    let producer = kafka_producer.clone();
+    match tokio::task::spawn_blocking(move || producer.flush(SHUTDOWN_FLUSH_TIMEOUT)).await {
+        Ok(Ok(())) => {}
+        Ok(Err(e)) => tracing::error!(error = %e, in_flight = kafka_producer.in_flight_count(), "Events left unsent"),
+        Err(e) => tracing::error!(error = %e, "Flush task failed"),
+    }
+    pool.close().await;
+    query_pool.close().await;
+    tracing::info!("Wallet Service stopped");
 
     Ok(())
 }
@@ -0,0 +1,175 @@
+use crate::errors::{WalletError, WalletResult};
+use crate::signing::hmac_sha256;
+use axum::http::Method;
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+use std::collections::HashMap;
+use std::time::Duration;
+
+/// Header naming the merchant whose secret signed the request
+pub const MERCHANT_ID_HEADER: &str = "x-merchant-id";
+
+/// `t=<unix seconds>,v1=<hex HMAC-SHA256 of "<t>.<METHOD>.<path>.<body>">`
+pub const MERCHANT_SIGNATURE_HEADER: &str = "x-merchant-signature";
+
+/// Requests signed by merchants' servers, with a secret per merchant
+///
+/// Why signatures rather than an API key?
+/// - A key travels with every request; a signature is only good for the
+///   one request it was made for, so a logged or intercepted one can't be
+///   turned into another
+/// - Merchant backends get authenticated without running an OAuth client
+///
+/// What's signed: the timestamp, method, path (with the query string) and
+/// the body exactly as sent - none can be changed, and a signature can't
+/// be moved to another route.
+///
+/// Replays: the timestamp must be within `window` of our clock, and each
+/// signature is accepted once (the repository remembers them that long).
+///
+/// Only fund and transfer take signed requests (`is_signed_route`), and
+/// only on the merchant's own wallets - those whose user_id is the
+/// merchant ID (see `Principal::merchant`).
+#[derive(Clone)]
+pub struct MerchantSigning {
+    secrets: HashMap<String, Vec<u8>>,
+    window: Duration,
+}
+
+impl MerchantSigning {
+    /// MERCHANT_SECRETS = `<merchant id>:<secret>,...` (unset: signed
+    /// requests aren't accepted); MERCHANT_SIGNATURE_WINDOW_SECS is the
+    /// replay window (default 300)
+    pub fn from_env() -> Result<Option<Self>, String> {
+        let Some(value) = std::env::var("MERCHANT_SECRETS").ok().filter(|v| !v.trim().is_empty())
+        else {
+            return Ok(None);
+        };
+        let secrets = value
+            .split(',')
+            .filter(|entry| !entry.trim().is_empty())
+            .map(|entry| {
+                let (merchant_id, secret) = entry
+                    .trim()
+                    .split_once(':')
+                    .ok_or("MERCHANT_SECRETS entries must be <merchant id>:<secret>")?;
+                Ok((merchant_id.to_string(), secret.as_bytes().to_vec()))
+            })
+            .collect::<Result<HashMap<_, _>, String>>()?;
+        let window = std::env::var("MERCHANT_SIGNATURE_WINDOW_SECS")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .filter(|&secs| secs > 0)
+            .map(Duration::from_secs)
+            .unwrap_or(DEFAULT_WINDOW);
+
+        Self::new(secrets, window).map(Some)
+    }
+
+    pub fn new(secrets: HashMap<String, Vec<u8>>, window: Duration) -> Result<Self, String> {
+        for (merchant_id, secret) in &secrets {
+            if merchant_id.is_empty() || merchant_id.len() > 64 || merchant_id.contains([':', ',']) {
+                return Err(format!("Invalid merchant id '{}'", merchant_id));
+            }
+            // Shorter than the hash output only makes guessing easier
+            if secret.len() < 32 {
+                return Err(format!("Merchant {}: secrets must be at least 32 bytes", merchant_id));
+            }
+        }
+
+        Ok(Self { secrets, window })
+    }
+
+    /// Merchant IDs accepted, sorted (for the startup log)
+    pub fn merchant_ids(&self) -> Vec<&str> {
+        let mut ids: Vec<&str> = self.secrets.keys().map(String::as_str).collect();
+        ids.sort_unstable();
+        ids
+    }
+
+    /// How far a signature's timestamp may be from now, either way
+    pub fn window(&self) -> Duration {
+        self.window
+    }
+
+    /// Check a request's MERCHANT_SIGNATURE_HEADER at unix time `now`
+    ///
+    /// Returns the signature's hex digest - what replays are recognised
+    /// by. An unknown merchant and a wrong signature get the same answer.
+    pub fn verify(
+        &self,
+        merchant_id: &str,
+        signature: &str,
+        method: &Method,
+        path: &str,
+        body: &[u8],
+        now: i64,
+    ) -> WalletResult<String> {
+        let unauthorized = |reason: &str| WalletError::Unauthorized(reason.to_string());
+
+        let mut timestamp = None;
+        let mut digest = None;
+        for part in signature.split(',') {
+            match part.trim().split_once('=') {
+                Some(("t", value)) => timestamp = value.parse::<i64>().ok(),
+                Some(("v1", value)) => digest = Some(value),
+                _ => {}
+            }
+        }
+        let (Some(timestamp), Some(digest)) = (timestamp, digest) else {
+            return Err(unauthorized("malformed signature"));
+        };
+        let expected = hex::decode(digest).map_err(|_| unauthorized("malformed signature"))?;
+
+        if now.abs_diff(timestamp) > self.window.as_secs() {
+            return Err(unauthorized("signature timestamp outside the replay window"));
+        }
+
+        let secret = self
+            .secrets
+            .get(merchant_id)
+            .ok_or_else(|| unauthorized("invalid signature"))?;
+        // Constant-time comparison
+        mac(secret, timestamp, method, path, body)
+            .verify_slice(&expected)
+            .map_err(|_| unauthorized("invalid signature"))?;
+
+        Ok(digest.to_ascii_lowercase())
+    }
+}
+
+/// Five minutes either way covers clock drift and a slow retry
+const DEFAULT_WINDOW: Duration = Duration::from_secs(300);
+
+/// The MERCHANT_SIGNATURE_HEADER value for a request sent at `timestamp`
+///
+/// What merchants compute on their side: HMAC-SHA256 with their secret
+/// over `"<t>.<METHOD>.<path>.<body>"`.
+pub fn sign(secret: &[u8], timestamp: i64, method: &Method, path: &str, body: &[u8]) -> String {
+    let digest = mac(secret, timestamp, method, path, body).finalize().into_bytes();
+    format!("t={},v1={}", timestamp, hex::encode(digest))
+}
+
+fn mac(secret: &[u8], timestamp: i64, method: &Method, path: &str, body: &[u8]) -> Hmac<Sha256> {
+    let mut mac = hmac_sha256(secret);
+    mac.update(timestamp.to_string().as_bytes());
+    mac.update(b".");
+    mac.update(method.as_str().as_bytes());
+    mac.update(b".");
+    mac.update(path.as_bytes());
+    mac.update(b".");
+    mac.update(body);
+    mac
+}
+
+/// POST /wallets/:wallet_id/fund and /wallets/:wallet_id/transfer
+pub fn is_signed_route(method: &Method, path: &str) -> bool {
+    if *method != Method::POST {
+        return false;
+    }
+    let segments: Vec<&str> = path.trim_start_matches('/').split('/').collect();
+    matches!(
+        segments.as_slice(),
+        ["wallets", wallet_id, "fund" | "transfer"] if !wallet_id.is_empty()
+    )
+}
@@ -0,0 +1,174 @@
+use crate::errors::WalletError;
+use rust_decimal::Decimal;
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+
+/// Content type for the OpenMetrics text format
+pub const OPENMETRICS_CONTENT_TYPE: &str = "application/openmetrics-text; version=1.0.0; charset=utf-8";
+
+/// Business KPIs exposed on /metrics
+///
+/// Why counters only?
+/// - Rates come from the scraper: rate(wallet_transfers_total[1m]) = transfers/min
+/// - Counters survive any scrape interval and never need resetting
+/// - Decline rate = declines / (declines + successes) for an operation
+///
+/// Why a currency label when wallets have no currency?
+/// - The service is single-currency today (see WALLET_CURRENCY)
+/// - Labelling now means dashboards don't change when a second one arrives
+///
+/// Counters are per process - sum across instances in the dashboard.
+pub struct BusinessMetrics {
+    currency: String,
+    wallets_created: AtomicU64,
+    fundings: AtomicU64,
+    transfers: AtomicU64,
+    vouchers_redeemed: AtomicU64,
+    funding_volume: Mutex<Decimal>,
+    transfer_volume: Mutex<Decimal>,
+    voucher_volume: Mutex<Decimal>,
+    declines: Mutex<BTreeMap<(&'static str, &'static str), u64>>,
+}
+
+impl BusinessMetrics {
+    pub fn new(currency: impl Into<String>) -> Self {
+        Self {
+            currency: currency.into(),
+            wallets_created: AtomicU64::new(0),
+            fundings: AtomicU64::new(0),
+            transfers: AtomicU64::new(0),
+            vouchers_redeemed: AtomicU64::new(0),
+            funding_volume: Mutex::new(Decimal::ZERO),
+            transfer_volume: Mutex::new(Decimal::ZERO),
+            voucher_volume: Mutex::new(Decimal::ZERO),
+            declines: Mutex::new(BTreeMap::new()),
+        }
+    }
+
+    pub fn record_wallet_created(&self) {
+        self.wallets_created.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn record_funding(&self, amount: Decimal) {
+        self.fundings.fetch_add(1, Ordering::Relaxed);
+        add_volume(&self.funding_volume, amount);
+    }
+
+    pub fn record_transfer(&self, amount: Decimal) {
+        self.transfers.fetch_add(1, Ordering::Relaxed);
+        add_volume(&self.transfer_volume, amount);
+    }
+
+    pub fn record_voucher_redeemed(&self, amount: Decimal) {
+        self.vouchers_redeemed.fetch_add(1, Ordering::Relaxed);
+        add_volume(&self.voucher_volume, amount);
+    }
+
+    /// Count a failed money movement, labelled by operation and error reason
+    pub fn record_decline(&self, operation: &'static str, error: &WalletError) {
+        *self
+            .declines
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .entry((operation, error.reason()))
+            .or_insert(0) += 1;
+    }
+
+    /// Render all counters in the OpenMetrics text format
+    pub fn render(&self) -> String {
+        let mut out = String::new();
+
+        write_counter(
+            &mut out,
+            "wallet_wallets_created",
+            "Wallets created",
+            "",
+            self.wallets_created.load(Ordering::Relaxed),
+        );
+        write_counter(
+            &mut out,
+            "wallet_fundings",
+            "Successful wallet fundings",
+            "",
+            self.fundings.load(Ordering::Relaxed),
+        );
+        write_counter(
+            &mut out,
+            "wallet_transfers",
+            "Successful transfers",
+            "",
+            self.transfers.load(Ordering::Relaxed),
+        );
+        write_counter(
+            &mut out,
+            "wallet_vouchers_redeemed",
+            "Vouchers redeemed",
+            "",
+            self.vouchers_redeemed.load(Ordering::Relaxed),
+        );
+
+        let currency = format!("{{currency=\"{}\"}}", self.currency);
+        write_counter(
+            &mut out,
+            "wallet_funding_volume",
+            "Total amount funded",
+            &currency,
+            read_volume(&self.funding_volume),
+        );
+        write_counter(
+            &mut out,
+            "wallet_transfer_volume",
+            "Total amount transferred",
+            &currency,
+            read_volume(&self.transfer_volume),
+        );
+        write_counter(
+            &mut out,
+            "wallet_voucher_volume",
+            "Total amount redeemed from vouchers",
+            &currency,
+            read_volume(&self.voucher_volume),
+        );
+
+        let _ = writeln!(out, "# TYPE wallet_declines counter");
+        let _ = writeln!(out, "# HELP wallet_declines Failed money movements by operation and reason");
+        for ((operation, reason), count) in self
+            .declines
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .iter()
+        {
+            let _ = writeln!(
+                out,
+                "wallet_declines_total{{operation=\"{}\",reason=\"{}\"}} {}",
+                operation, reason, count
+            );
+        }
+
+        out.push_str("# EOF\n");
+        out
+    }
+}
+
+fn add_volume(volume: &Mutex<Decimal>, amount: Decimal) {
+    *volume.lock().unwrap_or_else(|e| e.into_inner()) += amount;
+}
+
+fn read_volume(volume: &Mutex<Decimal>) -> Decimal {
+    *volume.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+/// One counter family with a single sample
+fn write_counter(
+    out: &mut String,
+    name: &str,
+    help: &str,
+    labels: &str,
+    value: impl std::fmt::Display,
+) {
+    let _ = writeln!(out, "# TYPE {} counter", name);
+    let _ = writeln!(out, "# HELP {} {}", name, help);
+    let _ = writeln!(out, "{}_total{} {}", name, labels, value);
+}
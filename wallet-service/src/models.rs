@@ -2,6 +2,8 @@ use chrono::{DateTime, Utc};
 use rust_decimal::Decimal;
 use serde::{Deserialize, Serialize};
 use sqlx::FromRow;
+use utoipa::{IntoParams, ToSchema};
+use validator::Validate;
 
 /// Wallet entity - represents a user's digital wallet
 /// 
@@ -9,12 +11,20 @@ use sqlx::FromRow;
 /// - `balance` is Decimal (never f64!) - prevents floating point errors
 /// - `version` enables optimistic locking - prevents lost updates
 /// - Uses String for user_id to keep auth separate from wallet concerns
+/// - `parent_wallet_id` is set for pots (sub-wallets); for a parent,
+///   `balance` is the spendable amount and excludes its pots
+/// - `nickname`, `labels` and `is_default` are metadata (PATCH /wallets/:id);
+///   changing them bumps `version` like a balance change does
 #[derive(Debug, Clone, FromRow, Serialize, Deserialize)]
 pub struct Wallet {
     pub id: String,
     pub user_id: String,
     pub balance: Decimal,
     pub version: i64,
+    pub parent_wallet_id: Option<String>,
+    pub nickname: Option<String>,
+    pub labels: Vec<String>,
+    pub is_default: bool,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
 }
@@ -33,11 +43,428 @@ pub struct WalletTransaction {
     pub transaction_type: TransactionType,
     pub status: TransactionStatus,
     pub reference_id: Option<String>, // For correlating transfers
+    pub memo: Option<String>,
     pub created_at: DateTime<Utc>,
 }
 
+impl WalletTransaction {
+    /// What this transaction did to its wallet's balance (+ credit, - debit)
+    pub fn signed_amount(&self) -> Decimal {
+        // Adjustments are stored signed already
+        if self.transaction_type.is_credit()
+            || matches!(self.transaction_type, TransactionType::Adjustment)
+        {
+            self.amount
+        } else {
+            -self.amount
+        }
+    }
+}
+
+/// Beneficiary - a saved recipient ("contact") owned by a user
+/// 
+/// Exactly one of `wallet_id` / `beneficiary_user_id` is set:
+/// - `wallet_id`: send to that exact wallet
+/// - `beneficiary_user_id`: send to that user's primary (oldest) wallet
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize, ToSchema)]
+pub struct Beneficiary {
+    pub id: String,
+    pub user_id: String,
+    pub name: String,
+    pub wallet_id: Option<String>,
+    pub beneficiary_user_id: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Transfer template - a saved, repeatable transfer from one wallet
+/// 
+/// Exactly one of `to_wallet_id` / `beneficiary_id` is set.
+/// `amount` is the default; callers may override it when executing.
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize, ToSchema)]
+pub struct TransferTemplate {
+    pub id: String,
+    pub wallet_id: String,
+    pub name: String,
+    pub to_wallet_id: Option<String>,
+    pub beneficiary_id: Option<String>,
+    pub amount: Decimal,
+    pub memo: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Internal support note attached to a transaction
+/// 
+/// Stored separately from `WalletTransaction` so the ledger itself is
+/// never modified by investigations
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize, ToSchema)]
+pub struct TransactionNote {
+    pub id: String,
+    pub transaction_id: String,
+    pub case_id: Option<String>,
+    pub note: String,
+    pub author: String,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Round-up rule - sweeps "spare change" from each debit into savings
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize, ToSchema)]
+pub struct RoundUpRule {
+    pub wallet_id: String,
+    pub savings_wallet_id: String,
+    pub increment: Decimal,
+    pub created_at: DateTime<Utc>,
+}
+
+impl RoundUpRule {
+    /// How much to sweep for a debit of `amount`
+    /// 
+    /// Rounds up to the next multiple of `increment`:
+    /// 3.40 with increment 1.00 -> 0.60, exact multiples -> 0
+    pub fn difference_for(&self, amount: Decimal) -> Decimal {
+        (amount / self.increment).ceil() * self.increment - amount
+    }
+}
+
+/// A wallet's transaction PIN settings - the hash itself never leaves
+/// the repository
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize, ToSchema)]
+pub struct WalletPin {
+    pub wallet_id: String,
+    /// Transfers up to this amount go through without the PIN
+    pub threshold: Decimal,
+    pub failed_attempts: i32,
+    /// Lockouts since the last right PIN - each lasts twice as long as the
+    /// one before
+    pub lockouts: i32,
+    /// Set after too many wrong PINs; transfers needing the PIN are refused
+    /// until then
+    pub locked_until: Option<DateTime<Utc>>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// A transfer that hasn't run yet: over STEP_UP_THRESHOLD and waiting for
+/// its one-time code, or held by the fraud rules for review
+///
+/// `status`: PENDING_VERIFICATION until the code comes back, or HELD until
+/// an admin releases it; then COMPLETED (`reference_id` links its
+/// transactions) or FAILED if the transfer itself was refused; CANCELLED
+/// or EXPIRED if it never went ahead. Held transfers have no `expires_at`.
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize, ToSchema)]
+pub struct PendingTransfer {
+    pub id: String,
+    pub from_wallet_id: String,
+    pub to_wallet_id: String,
+    pub amount: Decimal,
+    pub memo: Option<String>,
+    pub status: String,
+    pub failed_attempts: i32,
+    pub expires_at: Option<DateTime<Utc>>,
+    /// Why the fraud rules held it
+    pub hold_reason: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub resolved_at: Option<DateTime<Utc>>,
+    pub reference_id: Option<String>,
+}
+
+/// Voucher - a single-use gift code worth a fixed amount
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize, ToSchema)]
+pub struct Voucher {
+    pub id: String,
+    pub code: String,
+    pub amount: Decimal,
+    pub created_by: String,
+    pub created_at: DateTime<Utc>,
+    pub redeemed_at: Option<DateTime<Utc>>,
+    pub wallet_id: Option<String>,
+    pub transaction_id: Option<String>,
+}
+
+/// Which way an admin adjustment moves the balance
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, sqlx::Type, ToSchema)]
+#[sqlx(type_name = "varchar")]
+#[sqlx(rename_all = "SCREAMING_SNAKE_CASE")]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum AdjustmentDirection {
+    Credit,
+    Debit,
+}
+
+impl std::fmt::Display for AdjustmentDirection {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AdjustmentDirection::Credit => write!(f, "CREDIT"),
+            AdjustmentDirection::Debit => write!(f, "DEBIT"),
+        }
+    }
+}
+
+/// Why a balance was corrected by hand (`reason_code` on adjustments)
+pub const ADJUSTMENT_REASON_CODES: &[&str] = &[
+    "ERROR_CORRECTION", // We got a movement wrong
+    "CHARGEBACK",       // Card scheme reversed a funding
+    "GOODWILL",         // Compensation for the customer
+    "FEE_REFUND",
+    "FRAUD_RECOVERY",   // Clawing back fraudulently obtained money
+    "OTHER",            // Say what in `note`
+];
+
+/// Admin adjustment - a manual credit or debit with who made it and why
+///
+/// `transaction_id` is the ADJUSTMENT row in wallet_transactions.
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize, ToSchema)]
+pub struct WalletAdjustment {
+    pub id: String,
+    pub wallet_id: String,
+    pub transaction_id: String,
+    pub direction: AdjustmentDirection,
+    pub amount: Decimal,
+    pub reason_code: String,
+    pub actor: String,
+    pub note: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+/// One privileged operation, as recorded in admin_audit_log
+///
+/// `before_value` / `after_value` are snapshots of the target (None when
+/// it didn't exist before, e.g. a minted voucher).
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize, ToSchema)]
+pub struct AdminAuditEntry {
+    pub id: i64,
+    pub actor: String,
+    /// One of the AUDIT_* actions
+    pub action: String,
+    /// wallet, voucher, transaction, api_key, pending_transfer,
+    /// blocklist_entry or user
+    pub target_type: String,
+    pub target_id: String,
+    #[schema(value_type = Option<Object>)]
+    pub before_value: Option<serde_json::Value>,
+    #[schema(value_type = Option<Object>)]
+    pub after_value: Option<serde_json::Value>,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Audited admin actions (`action` in admin_audit_log)
+pub const AUDIT_WALLET_ADJUSTED: &str = "WALLET_ADJUSTED";
+pub const AUDIT_VOUCHER_MINTED: &str = "VOUCHER_MINTED";
+pub const AUDIT_TRANSACTION_NOTE_ADDED: &str = "TRANSACTION_NOTE_ADDED";
+pub const AUDIT_WALLET_ARCHIVED: &str = "WALLET_ARCHIVED";
+pub const AUDIT_API_KEY_CREATED: &str = "API_KEY_CREATED";
+pub const AUDIT_API_KEY_REVOKED: &str = "API_KEY_REVOKED";
+pub const AUDIT_HELD_TRANSFER_RELEASED: &str = "HELD_TRANSFER_RELEASED";
+pub const AUDIT_HELD_TRANSFER_REJECTED: &str = "HELD_TRANSFER_REJECTED";
+pub const AUDIT_BLOCKLIST_ENTRY_ADDED: &str = "BLOCKLIST_ENTRY_ADDED";
+pub const AUDIT_BLOCKLIST_ENTRY_REMOVED: &str = "BLOCKLIST_ENTRY_REMOVED";
+pub const AUDIT_KYC_LEVEL_CHANGED: &str = "KYC_LEVEL_CHANGED";
+pub const AUDIT_USER_ANONYMIZED: &str = "USER_ANONYMIZED";
+pub const AUDIT_BALANCE_SHARDS_CHANGED: &str = "BALANCE_SHARDS_CHANGED";
+
+/// Most shards a wallet's balance can be split over
+pub const MAX_BALANCE_SHARDS: i32 = 64;
+
+/// How a hot wallet's balance is split (GET/PUT
+/// /admin/wallets/:id/balance-shards)
+///
+/// `balance` = `row_balance` + the shard balances. Debits fold the shards
+/// back into the row, so they fill up again from the next credit on.
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct BalanceShards {
+    pub wallet_id: String,
+    /// 0: not sharded - transfers in lock the wallet row as usual
+    pub shards: i32,
+    /// The part of the balance on the wallet row
+    pub row_balance: Decimal,
+    /// Each shard's part, shard 0 first
+    pub shard_balances: Vec<Decimal>,
+    pub balance: Decimal,
+}
+
+/// Webhook subscription - where to POST/// Webhook subscription - where to POST which wallet events
+///
+/// `event_types` empty means every type. The signing secret is never
+/// serialized here; it's returned once, on creation (CreatedWebhook).
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize, ToSchema)]
+pub struct WebhookSubscription {
+    pub id: String,
+    pub url: String,
+    pub event_types: Vec<String>,
+    pub description: Option<String>,
+    #[serde(skip)]
+    pub secret: String,
+    pub active: bool,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// A subscription plus its signing secret (POST /webhooks only)
+#[derive(Debug, Serialize, ToSchema)]
+pub struct CreatedWebhook {
+    #[serde(flatten)]
+    pub subscription: WebhookSubscription,
+    /// HMAC-SHA256 key for X-Wallet-Signature - store it, it isn't shown again
+    pub secret: String,
+}
+
+/// What a machine client's API key may do
+///
+/// - `read`: GET requests only
+/// - `transact`: everything outside `/admin/*`, money movement included
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, sqlx::Type, ToSchema)]
+#[sqlx(type_name = "varchar")]
+#[sqlx(rename_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
+pub enum ApiKeyScope {
+    Read,
+    Transact,
+}
+
+impl std::fmt::Display for ApiKeyScope {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ApiKeyScope::Read => write!(f, "read"),
+            ApiKeyScope::Transact => write!(f, "transact"),
+        }
+    }
+}
+
+/// An API key for service-to-service calls
+///
+/// The key itself is never stored, only its SHA-256, so it can't be listed
+/// again; `key_prefix` is enough to tell keys apart.
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize, ToSchema)]
+pub struct ApiKey {
+    pub id: String,
+    pub name: String,
+    pub key_prefix: String,
+    pub scope: ApiKeyScope,
+    pub created_by: String,
+    pub created_at: DateTime<Utc>,
+    pub last_used_at: Option<DateTime<Utc>>,
+    pub revoked_at: Option<DateTime<Utc>>,
+}
+
+/// What a blocklist entry blocks: one wallet, or every wallet of a user
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, sqlx::Type, ToSchema)]
+#[sqlx(type_name = "varchar")]
+#[sqlx(rename_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
+pub enum BlocklistTargetType {
+    Wallet,
+    User,
+}
+
+impl std::fmt::Display for BlocklistTargetType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            BlocklistTargetType::Wallet => write!(f, "wallet"),
+            BlocklistTargetType::User => write!(f, "user"),
+        }
+    }
+}
+
+/// A wallet or user that may not send or receive transfers
+///
+/// Removed entries are kept (`removed_at` set), like revoked API keys.
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize, ToSchema)]
+pub struct BlocklistEntry {
+    pub id: String,
+    pub target_type: BlocklistTargetType,
+    pub target_id: String,
+    pub reason: String,
+    pub created_by: String,
+    pub created_at: DateTime<Utc>,
+    pub removed_by: Option<String>,
+    pub removed_at: Option<DateTime<Utc>>,
+}
+
+/// How far a user's identity has been checked (see kyc.rs for the caps)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, sqlx::Type, ToSchema)]
+#[sqlx(type_name = "varchar")]
+#[sqlx(rename_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
+pub enum KycLevel {
+    Unverified,
+    Verified,
+}
+
+impl std::fmt::Display for KycLevel {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            KycLevel::Unverified => write!(f, "unverified"),
+            KycLevel::Verified => write!(f, "verified"),
+        }
+    }
+}
+
+/// A user's KYC level; users nobody has set one for are unverified
+/// (`updated_by` / `updated_at` None)
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize, ToSchema)]
+pub struct UserKyc {
+    pub user_id: String,
+    pub level: KycLevel,
+    pub updated_by: Option<String>,
+    pub updated_at: Option<DateTime<Utc>>,
+}
+
+/// A new API key and the key itself (POST /admin/api-keys only)
+#[derive(Debug, Serialize, ToSchema)]
+pub struct CreatedApiKey {
+    #[serde(flatten)]
+    pub api_key: ApiKey,
+    /// Send as `X-API-Key` - store it, it isn't shown again
+    pub key: String,
+}
+
+/// One event queued for one subscription
+///
+/// `status`: PENDING (waiting or retrying), DELIVERED (2xx), FAILED (gave
+/// up after the last attempt)
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize, ToSchema)]
+pub struct WebhookDelivery {
+    pub id: i64,
+    pub subscription_id: String,
+    pub event_id: String,
+    pub event_type: String,
+    pub status: String,
+    pub attempts: i32,
+    pub next_attempt_at: DateTime<Utc>,
+    pub last_status_code: Option<i32>,
+    pub last_error: Option<String>,
+    pub delivered_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+}
+
+/// One HTTP attempt at a delivery (`status_code` None: no response)
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize, ToSchema)]
+pub struct WebhookDeliveryAttempt {
+    pub id: i64,
+    pub delivery_id: i64,
+    pub attempted_at: DateTime<Utc>,
+    pub status_code: Option<i32>,
+    pub error: Option<String>,
+    pub duration_ms: i32,
+}
+
+/// Everything a transfer wrote - both legs plus an optional round-up
+#[derive(Debug, Clone)]
+pub struct TransferOutcome {
+    pub out_transaction: WalletTransaction,
+    pub in_transaction: WalletTransaction,
+    pub round_up: Option<RoundUpOutcome>,
+}
+
+/// The round-up legs recorded as part of a transfer
+#[derive(Debug, Clone)]
+pub struct RoundUpOutcome {
+    pub savings_wallet_id: String,
+    pub savings_user_id: String,
+    pub out_transaction: WalletTransaction,
+    pub in_transaction: WalletTransaction,
+}
+
 /// Transaction type - what kind of operation happened
-#[derive(Debug, Clone, Serialize, Deserialize, sqlx::Type)]
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::Type, ToSchema)]
 #[sqlx(type_name = "varchar")]
 #[sqlx(rename_all = "SCREAMING_SNAKE_CASE")]
 pub enum TransactionType {
@@ -49,6 +476,41 @@ pub enum TransactionType {
     
     #[serde(rename = "TRANSFER_IN")]
     TransferIn,     // Receiving money
+
+    #[serde(rename = "ROUND_UP_OUT")]
+    RoundUpOut,     // Spare change swept out of a wallet
+
+    #[serde(rename = "ROUND_UP_IN")]
+    RoundUpIn,      // Spare change landing in savings
+
+    #[serde(rename = "POT_TRANSFER_OUT")]
+    PotTransferOut, // Moved into / out of a pot (debit side)
+
+    #[serde(rename = "POT_TRANSFER_IN")]
+    PotTransferIn,  // Moved into / out of a pot (credit side)
+
+    #[serde(rename = "VOUCHER_REDEEM")]
+    VoucherRedeem,  // Gift code redeemed into the wallet
+
+    #[serde(rename = "ADJUSTMENT")]
+    Adjustment,     // Admin correction; amount is signed (negative = debit)
+}
+
+impl TransactionType {
+    /// Does this transaction add money to its wallet?
+    ///
+    /// False for adjustments, which go either way - use
+    /// `WalletTransaction::signed_amount` for those.
+    pub fn is_credit(&self) -> bool {
+        matches!(
+            self,
+            TransactionType::Fund
+                | TransactionType::TransferIn
+                | TransactionType::RoundUpIn
+                | TransactionType::PotTransferIn
+                | TransactionType::VoucherRedeem
+        )
+    }
 }
 
 impl std::fmt::Display for TransactionType {
@@ -57,12 +519,18 @@ impl std::fmt::Display for TransactionType {
             TransactionType::Fund => write!(f, "FUND"),
             TransactionType::TransferOut => write!(f, "TRANSFER_OUT"),
             TransactionType::TransferIn => write!(f, "TRANSFER_IN"),
+            TransactionType::RoundUpOut => write!(f, "ROUND_UP_OUT"),
+            TransactionType::RoundUpIn => write!(f, "ROUND_UP_IN"),
+            TransactionType::PotTransferOut => write!(f, "POT_TRANSFER_OUT"),
+            TransactionType::PotTransferIn => write!(f, "POT_TRANSFER_IN"),
+            TransactionType::VoucherRedeem => write!(f, "VOUCHER_REDEEM"),
+            TransactionType::Adjustment => write!(f, "ADJUSTMENT"),
         }
     }
 }
 
 /// Transaction status - did it work or not?
-#[derive(Debug, Clone, Serialize, Deserialize, sqlx::Type)]
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::Type, ToSchema)]
 #[sqlx(type_name = "varchar")]
 #[sqlx(rename_all = "SCREAMING_SNAKE_CASE")]
 pub enum TransactionStatus {
@@ -85,28 +553,400 @@ impl std::fmt::Display for TransactionStatus {
 // === API Request/Response Models ===
 
 /// Request to create a new wallet
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, ToSchema, Validate)]
 pub struct CreateWalletRequest {
+    #[validate(length(min = 1, max = 100))]
     pub user_id: String,
 }
 
+/// Request to fetch several wallets at once (at most 100 IDs)
+#[derive(Debug, Deserialize, ToSchema, Validate)]
+pub struct BatchGetWalletsRequest {
+    #[validate(
+        length(min = 1, max = 100, message = "must list 1 to 100 wallet IDs"),
+        custom(function = "crate::validation::all_uuid_shaped")
+    )]
+    pub wallet_ids: Vec<String>,
+}
+
 /// Request to fund a wallet
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, ToSchema, Validate)]
 pub struct FundWalletRequest {
-    #[serde(with = "rust_decimal::serde::str")]
+    #[serde(with = "crate::validation::amount")]
     pub amount: Decimal,
 }
 
 /// Request to transfer money between wallets
-#[derive(Debug, Deserialize)]
+/// 
+/// The recipient is given either directly (`to_wallet_id`) or via one of
+/// the sender's saved beneficiaries (`beneficiary_id`) - never both
+#[derive(Debug, Deserialize, ToSchema, Validate)]
 pub struct TransferRequest {
-    pub to_wallet_id: String,
-    #[serde(with = "rust_decimal::serde::str")]
+    #[validate(custom(function = "crate::validation::uuid_shaped"))]
+    pub to_wallet_id: Option<String>,
+    #[validate(custom(function = "crate::validation::uuid_shaped"))]
+    pub beneficiary_id: Option<String>,
+    #[serde(with = "crate::validation::amount")]
+    pub amount: Decimal,
+    #[validate(length(max = 255))]
+    pub memo: Option<String>,
+    /// Required when the wallet has a PIN and `amount` is over its threshold
+    #[validate(custom(function = "crate::validation::pin_format"))]
+    pub pin: Option<String>,
+}
+
+/// Request to set or change a wallet's transaction PIN
+///
+/// Changing an existing PIN needs the current one (`current_pin`).
+#[derive(Debug, Deserialize, ToSchema, Validate)]
+pub struct SetPinRequest {
+    #[validate(custom(function = "crate::validation::pin_format"))]
+    pub pin: String,
+    #[validate(custom(function = "crate::validation::pin_format"))]
+    pub current_pin: Option<String>,
+    /// Transfers up to this amount skip the PIN - default 0, every transfer
+    /// needs it
+    #[serde(default, with = "crate::validation::amount_option")]
+    pub threshold: Option<Decimal>,
+}
+
+/// Request to confirm a pending transfer with the code sent to the user
+#[derive(Debug, Deserialize, ToSchema, Validate)]
+pub struct ConfirmTransferRequest {
+    #[validate(custom(function = "crate::validation::verification_code"))]
+    pub code: String,
+}
+
+/// Request to save a beneficiary
+#[derive(Debug, Deserialize, ToSchema, Validate)]
+pub struct CreateBeneficiaryRequest {
+    #[validate(length(min = 1, max = 100))]
+    pub name: String,
+    #[validate(custom(function = "crate::validation::uuid_shaped"))]
+    pub wallet_id: Option<String>,
+    #[validate(length(min = 1, max = 100))]
+    pub beneficiary_user_id: Option<String>,
+}
+
+/// Request to save a transfer template
+#[derive(Debug, Deserialize, ToSchema, Validate)]
+pub struct CreateTemplateRequest {
+    #[validate(length(min = 1, max = 100))]
+    pub name: String,
+    #[validate(custom(function = "crate::validation::uuid_shaped"))]
+    pub to_wallet_id: Option<String>,
+    #[validate(custom(function = "crate::validation::uuid_shaped"))]
+    pub beneficiary_id: Option<String>,
+    #[serde(with = "crate::validation::amount")]
+    pub amount: Decimal,
+    #[validate(length(max = 255))]
+    pub memo: Option<String>,
+}
+
+/// Request to execute a transfer template
+/// 
+/// The body is optional - an empty body uses the template's default amount
+#[derive(Debug, Default, Deserialize, ToSchema, Validate)]
+pub struct ExecuteTemplateRequest {
+    #[serde(default, with = "crate::validation::amount_option")]
+    pub amount: Option<Decimal>,
+    /// As for a direct transfer
+    #[validate(custom(function = "crate::validation::pin_format"))]
+    pub pin: Option<String>,
+}
+
+/// Request to create a pot under a wallet
+#[derive(Debug, Deserialize, ToSchema, Validate)]
+pub struct CreatePotRequest {
+    #[validate(length(min = 1, max = 100))]
+    pub name: String,
+}
+
+/// Request to change a wallet's metadata (PATCH /wallets/:id)
+///
+/// Absent fields are left as they are; `"nickname": null` clears the
+/// nickname. There is deliberately no way to touch the balance here.
+#[derive(Debug, Default, Deserialize, ToSchema, Validate)]
+#[serde(deny_unknown_fields)]
+pub struct UpdateWalletRequest {
+    #[serde(default, deserialize_with = "crate::validation::nullable::deserialize")]
+    #[schema(value_type = Option<String>, nullable)]
+    #[validate(length(min = 1, max = 100))]
+    pub nickname: Option<Option<String>>,
+    /// Replaces every label - up to 20, each 1 to 50 characters
+    #[validate(custom(function = "crate::validation::wallet_labels"))]
+    pub labels: Option<Vec<String>>,
+    /// true makes this the user's default wallet (unsetting the old one)
+    pub is_default: Option<bool>,
+}
+
+/// Request to move money between a wallet and one of its pots
+#[derive(Debug, Deserialize, ToSchema, Validate)]
+pub struct PotTransferRequest {
+    #[serde(with = "crate::validation::amount")]
     pub amount: Decimal,
 }
 
+/// Request to configure a wallet's round-up rule
+#[derive(Debug, Deserialize, ToSchema, Validate)]
+pub struct SetRoundUpRuleRequest {
+    #[validate(custom(function = "crate::validation::uuid_shaped"))]
+    pub savings_wallet_id: String,
+    #[serde(with = "crate::validation::amount")]
+    pub increment: Decimal,
+}
+
+/// Admin request to mint a voucher
+#[derive(Debug, Deserialize, ToSchema, Validate)]
+pub struct CreateVoucherRequest {
+    #[serde(with = "crate::validation::amount")]
+    pub amount: Decimal,
+}
+
+/// Admin request to issue an API key
+#[derive(Debug, Deserialize, ToSchema, Validate)]
+pub struct CreateApiKeyRequest {
+    /// What the key is for ("payouts-service")
+    #[validate(length(min = 1, max = 100))]
+    pub name: String,
+    pub scope: ApiKeyScope,
+}
+
+/// Admin request to blocklist a wallet or user
+#[derive(Debug, Deserialize, ToSchema, Validate)]
+pub struct CreateBlocklistEntryRequest {
+    pub target_type: BlocklistTargetType,
+    #[validate(length(min = 1, max = 100))]
+    pub target_id: String,
+    #[validate(length(min = 1, max = 500))]
+    pub reason: String,
+}
+
+/// Admin request to set a user's KYC level
+#[derive(Debug, Deserialize, ToSchema, Validate)]
+pub struct SetKycLevelRequest {
+    pub level: KycLevel,
+}
+
+/// Admin request to split a wallet's balance over `shards` rows (0 turns
+/// sharding off)
+#[derive(Debug, Deserialize, ToSchema, Validate)]
+pub struct SetBalanceShardsRequest {
+    #[validate(range(min = 0, max = 64, message = "must be 0 to 64"))]
+    pub shards: i32,
+}
+
+/// Admin request to credit or debit a wallet by hand
+#[derive(Debug, Deserialize, ToSchema, Validate)]
+pub struct AdjustmentRequest {
+    pub direction: AdjustmentDirection,
+    #[serde(with = "crate::validation::amount")]
+    pub amount: Decimal,
+    /// One of ADJUSTMENT_REASON_CODES
+    #[validate(custom(function = "crate::validation::adjustment_reason_code"))]
+    pub reason_code: String,
+    #[validate(length(max = 2000))]
+    pub note: Option<String>,
+}
+
+/// The adjustment and the wallet after it
+#[derive(Debug, Serialize, ToSchema)]
+pub struct AdjustmentResponse {
+    pub adjustment: WalletAdjustment,
+    pub wallet: WalletResponse,
+}
+
+/// Admin request to archive inactive wallets now (instead of waiting for
+/// the background job)
+#[derive(Debug, Deserialize, ToSchema, Validate)]
+pub struct ArchiveWalletsRequest {
+    /// Untouched for at least this many days (default WALLET_ARCHIVE_AFTER_DAYS)
+    #[validate(range(min = 1, message = "must be at least 1 day"))]
+    pub inactive_days: Option<i64>,
+    /// Most wallets to archive in this call (default WALLET_ARCHIVE_BATCH)
+    #[validate(range(min = 1, max = 1000, message = "must be 1 to 1000"))]
+    pub limit: Option<i64>,
+}
+
+/// What an archive run moved
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ArchiveRunResponse {
+    /// Wallets last changed before this were eligible
+    pub cutoff: DateTime<Utc>,
+    pub archived_wallet_ids: Vec<String>,
+}
+
+/// An archived wallet with its full transaction history, oldest first
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ArchivedWalletResponse {
+    pub wallet: WalletResponse,
+    pub archived_at: DateTime<Utc>,
+    pub transactions: Vec<TransactionResponse>,
+}
+
+/// Request to redeem a voucher into a wallet
+#[derive(Debug, Deserialize, ToSchema, Validate)]
+pub struct RedeemVoucherRequest {
+    #[validate(length(min = 1, max = 32))]
+    pub code: String,
+}
+
+/// Request to attach a support note to a transaction
+#[derive(Debug, Deserialize, ToSchema, Validate)]
+pub struct CreateTransactionNoteRequest {
+    #[validate(length(min = 1, max = 100))]
+    pub case_id: Option<String>,
+    #[validate(length(min = 1, max = 2000))]
+    pub note: String,
+}
+
+/// Request to register a webhook (`event_types` absent or empty = all)
+#[derive(Debug, Deserialize, ToSchema, Validate)]
+pub struct CreateWebhookRequest {
+    #[validate(length(min = 1, max = 2048))]
+    pub url: String,
+    #[serde(default)]
+    pub event_types: Vec<String>,
+    #[validate(length(max = 200))]
+    pub description: Option<String>,
+}
+
+/// Request to change a webhook - absent fields are left as they are
+#[derive(Debug, Default, Deserialize, ToSchema, Validate)]
+pub struct UpdateWebhookRequest {
+    #[validate(length(min = 1, max = 2048))]
+    pub url: Option<String>,
+    pub event_types: Option<Vec<String>>,
+    #[validate(length(max = 200))]
+    pub description: Option<String>,
+    /// false pauses deliveries (queued ones wait, new events are skipped)
+    pub active: Option<bool>,
+}
+
+/// Query parameters for the admin transaction listing
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct AdminTransactionQuery {
+    pub case_id: String,
+}
+
+/// Query parameters for GET /admin/wallets (every filter optional, ANDed)
+#[derive(Debug, Default, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct AdminWalletQuery {
+    pub user_id: Option<String>,
+    /// Only `ACTIVE` so far - wallets have no other lifecycle state yet
+    pub status: Option<String>,
+    /// Balance at least this much (a decimal string, e.g. "100.00")
+    #[serde(default, with = "crate::validation::amount_option")]
+    pub min_balance: Option<Decimal>,
+    /// Created strictly after this time (RFC 3339)
+    pub created_after: Option<DateTime<Utc>>,
+    /// 1-based (default 1)
+    pub page: Option<i64>,
+    /// Wallets per page (default 50, at most 200)
+    pub page_size: Option<i64>,
+}
+
+/// One page of the admin wallet listing, newest first
+#[derive(Debug, Serialize, ToSchema)]
+pub struct AdminWalletPage {
+    pub wallets: Vec<WalletResponse>,
+    pub page: i64,
+    pub page_size: i64,
+    /// Wallets matching the filters, across every page
+    pub total: i64,
+}
+
+/// Query parameters for GET /admin/audit-log (every filter optional, ANDed)
+#[derive(Debug, Default, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct AuditLogQuery {
+    pub actor: Option<String>,
+    pub action: Option<String>,
+    /// A wallet, voucher or transaction ID
+    pub target_id: Option<String>,
+    /// Only entries after this time (RFC 3339)
+    pub since: Option<DateTime<Utc>>,
+    /// Newest entries returned (default 100, at most 500)
+    pub limit: Option<i64>,
+}
+
+/// Query parameters for GET /admin/ledger/verify
+#[derive(Debug, Default, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct LedgerVerifyQuery {
+    /// Check only this wallet's chain (default: every wallet)
+    pub wallet_id: Option<String>,
+}
+
+/// Result of checking the transaction ledger's hash chains
+#[derive(Debug, Default, Clone, Serialize, Deserialize, ToSchema)]
+pub struct LedgerVerification {
+    /// No breaks anywhere
+    pub intact: bool,
+    pub entries_checked: i64,
+    pub wallets_checked: i64,
+    /// Every break found
+    pub break_count: i64,
+    /// The first 100 breaks, in chain order
+    pub breaks: Vec<LedgerBreak>,
+}
+
+/// Something in the ledger that doesn't add up
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct LedgerBreak {
+    /// The ledger entry (None for a transaction with no entry)
+    pub seq: Option<i64>,
+    pub wallet_id: String,
+    pub transaction_id: String,
+    pub reason: String,
+}
+
+/// Result of DELETE /users/:user_id/data
+///
+/// `anonymized_user_id` replaces the user's ID everywhere it was stored;
+/// nothing maps it back to `user_id` afterwards.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct UserAnonymization {
+    pub user_id: String,
+    pub anonymized_user_id: String,
+    /// Live and archived wallets that were the user's (IDs unchanged)
+    pub wallet_ids: Vec<String>,
+    pub anonymized_at: DateTime<Utc>,
+}
+
+/// Query parameters for GET /wallets/:id
+#[derive(Debug, Default, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct WalletQuery {
+    /// Extra data to embed, comma-separated; only `recent_transactions` so far
+    pub include: Option<String>,
+    /// How many recent transactions (default 10, at most 50)
+    pub transactions_limit: Option<i64>,
+}
+
+/// A wallet, plus what `?include=` asked for
+#[derive(Debug, Serialize, ToSchema)]
+pub struct WalletDetailResponse {
+    #[serde(flatten)]
+    pub wallet: WalletResponse,
+    /// Newest first; only present with `?include=recent_transactions`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub recent_transactions: Option<Vec<TransactionResponse>>,
+}
+
+/// Query parameters for GET /transactions
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct TransactionSearchQuery {
+    /// Shared by both legs of a transfer (or pot move); a voucher's ID on
+    /// its redemption
+    pub reference_id: String,
+}
+
 /// Generic API response
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, ToSchema)]
 pub struct ApiResponse<T> {
     pub success: bool,
     pub data: Option<T>,
@@ -132,11 +972,17 @@ impl<T> ApiResponse<T> {
 }
 
 /// Response for wallet operations
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, ToSchema)]
 pub struct WalletResponse {
     pub id: String,
     pub user_id: String,
     pub balance: Decimal,
+    pub parent_wallet_id: Option<String>,
+    pub nickname: Option<String>,
+    pub labels: Vec<String>,
+    pub is_default: bool,
+    /// The ETag value: send it back as If-Match to PATCH or move money
+    pub version: i64,
     pub created_at: DateTime<Utc>,
 }
 
@@ -146,13 +992,91 @@ impl From<Wallet> for WalletResponse {
             id: wallet.id,
             user_id: wallet.user_id,
             balance: wallet.balance,
+            parent_wallet_id: wallet.parent_wallet_id,
+            nickname: wallet.nickname,
+            labels: wallet.labels,
+            is_default: wallet.is_default,
+            version: wallet.version,
             created_at: wallet.created_at,
         }
     }
 }
 
+/// A wallet's pots with the rolled-up balances
+/// 
+/// - spendable: the parent's own balance (what transfers can use)
+/// - allocated: money sitting in pots
+/// - total: spendable + allocated
+#[derive(Debug, Serialize, ToSchema)]
+pub struct PotsResponse {
+    pub wallet_id: String,
+    pub spendable_balance: Decimal,
+    pub allocated_balance: Decimal,
+    pub total_balance: Decimal,
+    pub pots: Vec<WalletResponse>,
+}
+
+/// Wallets found by POST /wallets/batch-get, in the order asked for
+///
+/// IDs that matched no wallet are listed in `not_found` rather than failing
+/// the whole batch.
+#[derive(Debug, Serialize, ToSchema)]
+pub struct BatchGetWalletsResponse {
+    pub wallets: Vec<WalletResponse>,
+    pub not_found: Vec<String>,
+}
+
+/// What a user holds in one currency, across all their wallets
+///
+/// - spendable: top-level wallets' own balances
+/// - allocated: money sitting in pots
+/// - total: spendable + allocated
+#[derive(Debug, Serialize, ToSchema)]
+pub struct CurrencyTotal {
+    pub currency: String,
+    pub spendable_balance: Decimal,
+    pub allocated_balance: Decimal,
+    pub total_balance: Decimal,
+    pub wallet_count: usize,
+}
+
+/// Every wallet of a user plus their totals (the "total balance" widget)
+///
+/// Wallets are single-currency today (WALLET_CURRENCY), so `totals` has one
+/// entry. It's a list so a second currency doesn't change the shape.
+#[derive(Debug, Serialize, ToSchema)]
+pub struct UserBalanceSummary {
+    pub user_id: String,
+    pub wallets: Vec<WalletResponse>,
+    pub totals: Vec<CurrencyTotal>,
+}
+
+impl UserBalanceSummary {
+    /// A user with no wallets gets a zero total rather than an empty list
+    pub fn new(user_id: String, currency: &str, wallets: Vec<Wallet>) -> Self {
+        let (pots, top_level): (Vec<&Wallet>, Vec<&Wallet>) =
+            wallets.iter().partition(|w| w.parent_wallet_id.is_some());
+        let spendable_balance: Decimal = top_level.iter().map(|w| w.balance).sum();
+        let allocated_balance: Decimal = pots.iter().map(|w| w.balance).sum();
+
+        let totals = vec![CurrencyTotal {
+            currency: currency.to_string(),
+            spendable_balance,
+            allocated_balance,
+            total_balance: spendable_balance + allocated_balance,
+            wallet_count: wallets.len(),
+        }];
+
+        Self {
+            user_id,
+            wallets: wallets.into_iter().map(WalletResponse::from).collect(),
+            totals,
+        }
+    }
+}
+
 /// Response for transaction operations
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, ToSchema)]
 pub struct TransactionResponse {
     pub transaction_id: String,
     pub wallet_id: String,
@@ -160,6 +1084,7 @@ pub struct TransactionResponse {
     #[serde(rename = "type")]
     pub transaction_type: TransactionType,
     pub status: TransactionStatus,
+    pub memo: Option<String>,
     pub created_at: DateTime<Utc>,
 }
 
@@ -171,7 +1096,59 @@ impl From<WalletTransaction> for TransactionResponse {
             amount: txn.amount,
             transaction_type: txn.transaction_type,
             status: txn.status,
+            memo: txn.memo,
             created_at: txn.created_at,
         }
     }
 }
+
+/// Admin view of a transaction - the ledger record plus support notes
+#[derive(Debug, Serialize, ToSchema)]
+pub struct AdminTransactionResponse {
+    #[serde(flatten)]
+    pub transaction: TransactionResponse,
+    pub notes: Vec<TransactionNote>,
+}
+
+/// A "payment details" view of one transaction (GET /transactions/:id/receipt)
+///
+/// Everything in one place: the transaction itself, every leg sharing its
+/// reference (both sides of a transfer, plus any round-up), who is on the
+/// other side, and what the balance was afterwards.
+#[derive(Debug, Serialize, ToSchema)]
+pub struct TransactionReceipt {
+    pub transaction_id: String,
+    pub reference_id: Option<String>,
+    pub wallet_id: String,
+    pub user_id: String,
+    #[serde(rename = "type")]
+    pub transaction_type: TransactionType,
+    pub status: TransactionStatus,
+    pub amount: Decimal,
+    /// Nothing is charged yet, so always 0 - here so receipts keep their
+    /// shape when fees arrive
+    pub fee: Decimal,
+    pub memo: Option<String>,
+    /// This wallet's balance right after the transaction
+    pub balance_after: Decimal,
+    /// Users on the other legs (the recipient of a transfer, the sender of
+    /// an incoming one); empty when there is no other side
+    pub counterparty_user_ids: Vec<String>,
+    /// Every leg, this one included, in the order they were written
+    pub legs: Vec<ReceiptLeg>,
+    pub created_at: DateTime<Utc>,
+}
+
+/// One leg of a receipt
+#[derive(Debug, Clone, FromRow, Serialize, ToSchema)]
+pub struct ReceiptLeg {
+    pub transaction_id: String,
+    pub wallet_id: String,
+    pub user_id: String,
+    #[serde(rename = "type")]
+    pub transaction_type: TransactionType,
+    pub amount: Decimal,
+    /// Only on legs of the receipt's own wallet - a receipt never shows
+    /// someone else's balance
+    pub balance_after: Option<Decimal>,
+}
@@ -0,0 +1,133 @@
+use crate::handlers;
+use axum::{response::Html, Json};
+use utoipa::OpenApi;
+
+/// The API description served at /openapi.json
+///
+/// Why generate it from the handlers?
+/// - Client teams get a spec instead of reading the route list `main.rs`
+///   logs at startup
+/// - `#[utoipa::path]` sits on each handler and the schemas are derived from
+///   the request/response types, so the spec can't drift far from the code
+///
+/// Every success body is wrapped in `{"success": true, "data": ...}`;
+/// errors are `{"success": false, "error": "..."}` (ErrorResponse), and a
+/// 422 adds the offending fields as `errors`.
+#[derive(OpenApi)]
+#[openapi(
+    info(
+        title = "Wallet Service",
+        description = "Wallets, balances and money movement. Every change is published to Kafka (wallet-events)."
+    ),
+    paths(
+        handlers::create_wallet,
+        handlers::get_wallet,
+        handlers::update_wallet,
+        handlers::get_user_wallets,
+        handlers::batch_get_wallets,
+        handlers::get_user_balance_summary,
+        handlers::erase_user_data,
+        handlers::fund_wallet,
+        handlers::transfer,
+        handlers::get_pending_transfer,
+        handlers::confirm_pending_transfer,
+        handlers::cancel_pending_transfer,
+        handlers::create_beneficiary,
+        handlers::get_beneficiaries,
+        handlers::delete_beneficiary,
+        handlers::create_template,
+        handlers::get_templates,
+        handlers::delete_template,
+        handlers::execute_template,
+        handlers::set_round_up_rule,
+        handlers::get_round_up_rule,
+        handlers::delete_round_up_rule,
+        handlers::set_pin,
+        handlers::get_pin,
+        handlers::create_pot,
+        handlers::get_pots,
+        handlers::deposit_to_pot,
+        handlers::withdraw_from_pot,
+        handlers::create_voucher,
+        handlers::redeem_voucher,
+        handlers::create_adjustment,
+        handlers::get_balance_shards,
+        handlers::set_balance_shards,
+        handlers::get_held_transfers,
+        handlers::release_held_transfer,
+        handlers::reject_held_transfer,
+        handlers::add_transaction_note,
+        handlers::get_admin_transaction,
+        handlers::get_admin_transactions,
+        handlers::get_admin_wallets,
+        handlers::get_audit_log,
+        handlers::verify_ledger,
+        handlers::archive_wallets,
+        handlers::get_archived_wallet,
+        handlers::search_transactions,
+        handlers::get_transaction_receipt,
+        handlers::create_webhook,
+        handlers::get_webhooks,
+        handlers::get_webhook,
+        handlers::update_webhook,
+        handlers::delete_webhook,
+        handlers::get_webhook_deliveries,
+        handlers::get_webhook_delivery_attempts,
+        handlers::create_api_key,
+        handlers::get_api_keys,
+        handlers::revoke_api_key,
+        handlers::get_user_kyc,
+        handlers::set_user_kyc,
+        handlers::create_blocklist_entry,
+        handlers::get_blocklist,
+        handlers::remove_blocklist_entry,
+        handlers::get_metrics,
+        handlers::get_degradation_status,
+        handlers::get_producer_diagnostics,
+        handlers::health_check
+    ),
+    tags(
+        (name = "wallets", description = "Create and read wallets"),
+        (name = "operations", description = "Money movement: funding, transfers, voucher redemption"),
+        (name = "transactions", description = "Transaction lookup"),
+        (name = "pots", description = "Sub-wallets for setting money aside"),
+        (name = "round-ups", description = "Spare-change savings rules"),
+        (name = "pins", description = "Transaction PINs guarding transfers"),
+        (name = "templates", description = "Saved, repeatable transfers"),
+        (name = "beneficiaries", description = "Saved recipients"),
+        (name = "admin", description = "Vouchers, balance adjustments, fraud review, KYC, blocklist, audit log, ledger verification, archival, API keys and support tooling"),
+        (name = "webhooks", description = "Signed event deliveries to your own URLs"),
+        (name = "service", description = "Health, metrics and diagnostics")
+    )
+)]
+pub struct ApiDoc;
+
+/// GET /openapi.json
+pub async fn openapi_json() -> Json<utoipa::openapi::OpenApi> {
+    Json(ApiDoc::openapi())
+}
+
+/// GET /docs - Swagger UI for /openapi.json
+///
+/// The page loads Swagger UI's scripts from a CDN rather than bundling them,
+/// so it needs internet access in the browser; /openapi.json itself doesn't.
+pub async fn swagger_ui() -> Html<&'static str> {
+    Html(SWAGGER_UI)
+}
+
+const SWAGGER_UI: &str = r##"<!doctype html>
+<html>
+<head>
+  <meta charset="utf-8">
+  <title>Wallet Service API</title>
+  <link rel="stylesheet" href="https://unpkg.com/swagger-ui-dist@5/swagger-ui.css">
+</head>
+<body>
+  <div id="swagger-ui"></div>
+  <script src="https://unpkg.com/swagger-ui-dist@5/swagger-ui-bundle.js"></script>
+  <script>
+    window.ui = SwaggerUIBundle({ url: "/openapi.json", dom_id: "#swagger-ui" });
+  </script>
+</body>
+</html>
+"##;
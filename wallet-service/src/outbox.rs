@@ -0,0 +1,391 @@
+use crate::errors::WalletResult;
+use crate::kafka::KafkaProducer;
+use chrono::{DateTime, Utc};
+use sqlx::{FromRow, PgPool, Postgres, Transaction};
+use std::collections::HashSet;
+use std::fmt::Write;
+use std::future::Future;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Advisory lock key held by the active relay (any constant works, it just
+/// has to be the same for every relay instance)
+const RELAY_LOCK_KEY: i64 = 0x6f75_7462_6f78; // "outbox" in ASCII
+
+/// Longest wait between retries of one event (default)
+const MAX_BACKOFF_SECS: i64 = 300;
+
+/// Written to every row's aggregate_type; Debezium's outbox router turns it
+/// into the topic name ("wallet" -> wallet-events)
+pub const AGGREGATE_TYPE: &str = "wallet";
+
+/// A pending outbox row
+#[derive(Debug, Clone, FromRow)]
+pub struct OutboxEvent {
+    pub id: i64,
+    /// The wallet ID - the Kafka key, so per-wallet order survives retries
+    pub aggregate_id: String,
+    pub event_type: String,
+    pub payload: String,
+    pub attempts: i32,
+    pub next_attempt_at: DateTime<Utc>,
+}
+
+/// Where the relay sends events (Kafka in production, a fake in tests)
+///
+/// A transactional publisher makes each relay pass one unit: sends between
+/// `begin_batch` and `commit_batch` become visible together, and
+/// `abort_batch` discards them. The defaults are for plain publishers, where
+/// every acknowledged send is already visible.
+pub trait OutboxPublisher {
+    fn send(&self, key: &str, payload: &str) -> impl Future<Output = WalletResult<()>> + Send;
+
+    fn is_transactional(&self) -> bool {
+        false
+    }
+
+    fn begin_batch(&self) -> impl Future<Output = WalletResult<()>> + Send {
+        async { Ok(()) }
+    }
+
+    fn commit_batch(&self) -> impl Future<Output = WalletResult<()>> + Send {
+        async { Ok(()) }
+    }
+
+    fn abort_batch(&self) -> impl Future<Output = WalletResult<()>> + Send {
+        async { Ok(()) }
+    }
+}
+
+impl OutboxPublisher for KafkaProducer {
+    fn send(&self, key: &str, payload: &str) -> impl Future<Output = WalletResult<()>> + Send {
+        KafkaProducer::send(self, key, payload)
+    }
+
+    fn is_transactional(&self) -> bool {
+        KafkaProducer::is_transactional(self)
+    }
+
+    fn begin_batch(&self) -> impl Future<Output = WalletResult<()>> + Send {
+        self.begin_transaction()
+    }
+
+    fn commit_batch(&self) -> impl Future<Output = WalletResult<()>> + Send {
+        self.commit_transaction()
+    }
+
+    fn abort_batch(&self) -> impl Future<Output = WalletResult<()>> + Send {
+        self.abort_transaction()
+    }
+}
+
+/// For the in-process spill flusher, which shares the producer
+impl OutboxPublisher for Arc<KafkaProducer> {
+    fn send(&self, key: &str, payload: &str) -> impl Future<Output = WalletResult<()>> + Send {
+        KafkaProducer::send(self, key, payload)
+    }
+}
+
+/// Queue a serialized event for the relay
+///
+/// The row has the columns Debezium's outbox event router expects (event_id,
+/// aggregate_type, aggregate_id, event_type, payload), so CDC can replace
+/// the relay without touching the table - see the README.
+pub async fn enqueue(
+    pool: &PgPool,
+    event_id: &str,
+    aggregate_id: &str,
+    event_type: &str,
+    payload: &str,
+) -> WalletResult<()> {
+    sqlx::query(
+        r#"
+        INSERT INTO event_outbox (event_id, aggregate_type, aggregate_id, event_type, payload)
+        VALUES ($1::uuid, $2, $3, $4, $5)
+        "#,
+    )
+    .bind(event_id)
+    .bind(AGGREGATE_TYPE)
+    .bind(aggregate_id)
+    .bind(event_type)
+    .bind(payload)
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+/// Result of one relay pass
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct RelayPass {
+    pub published: usize,
+    pub failed: usize,
+    /// Rows held back because an earlier event for the same wallet is pending
+    pub deferred: usize,
+    /// Another relay holds the lock - this pass did nothing
+    pub skipped: bool,
+}
+
+/// Backlog gauges for /metrics
+#[derive(Debug, Default)]
+pub struct Backlog {
+    pub pending: i64,
+    pub oldest_age_secs: f64,
+}
+
+/// Outbox relay - moves events from event_outbox to Kafka
+///
+/// Guarantees:
+/// - At-least-once: a row is marked sent only after Kafka acknowledges it
+///   (a crash in between re-sends it; history-service dedupes by transaction ID)
+/// - Per-wallet order: rows go out in ID order, and once one event for a
+///   wallet fails, later events for that wallet wait behind it
+///
+/// Why an advisory lock?
+/// - Two relays working the same rows in parallel would reorder a wallet's events
+/// - `pg_try_advisory_xact_lock` makes one relay active; the others stand by
+///   and take over as soon as it stops
+///
+/// Retries back off exponentially per row: 2s, 4s, 8s ... capped at 5 minutes.
+/// A pass stops at its first failure, so an outage costs one timeout per pass.
+///
+/// With a transactional publisher (RELAY_TRANSACTIONAL_ID) each pass is one
+/// Kafka transaction, committed BEFORE the rows are marked sent:
+/// - A failure or crash mid-pass aborts the transaction - read_committed
+///   consumers see none of the batch, and the rows are sent again next pass
+/// - No partial batch, so no gap in a wallet's events
+/// - The one remaining window is a crash between the Kafka commit and the
+///   DB commit, which re-sends a whole batch (history-service dedupes it)
+pub struct OutboxRelay<P> {
+    pool: PgPool,
+    publisher: P,
+    batch_size: i64,
+    max_backoff_secs: i64,
+    published_total: AtomicU64,
+    failures_total: AtomicU64,
+}
+
+impl<P: OutboxPublisher> OutboxRelay<P> {
+    pub fn new(pool: PgPool, publisher: P, batch_size: i64) -> Self {
+        Self {
+            pool,
+            publisher,
+            batch_size,
+            max_backoff_secs: MAX_BACKOFF_SECS,
+            published_total: AtomicU64::new(0),
+            failures_total: AtomicU64::new(0),
+        }
+    }
+
+    /// Cap per-row backoff at `max` instead of 5 minutes
+    pub fn with_max_backoff(mut self, max: Duration) -> Self {
+        self.max_backoff_secs = (max.as_secs() as i64).max(1);
+        self
+    }
+
+    pub fn publisher(&self) -> &P {
+        &self.publisher
+    }
+
+    /// Publish one batch of pending events
+    pub async fn run_once(&self) -> WalletResult<RelayPass> {
+        let mut tx = self.pool.begin().await?;
+
+        let acquired = sqlx::query_scalar::<_, bool>("SELECT pg_try_advisory_xact_lock($1)")
+            .bind(RELAY_LOCK_KEY)
+            .fetch_one(&mut *tx)
+            .await?;
+        if !acquired {
+            return Ok(RelayPass {
+                skipped: true,
+                ..RelayPass::default()
+            });
+        }
+
+        // Oldest first, INCLUDING rows still in backoff - they block later
+        // rows for the same wallet
+        let rows = sqlx::query_as::<_, OutboxEvent>(
+            r#"
+            SELECT id, aggregate_id, event_type, payload, attempts, next_attempt_at
+            FROM event_outbox
+            WHERE published_at IS NULL
+            ORDER BY id ASC
+            LIMIT $1
+            FOR UPDATE
+            "#,
+        )
+        .bind(self.batch_size)
+        .fetch_all(&mut *tx)
+        .await?;
+
+        // Nothing to do - don't open a Kafka transaction for it
+        if rows.is_empty() {
+            tx.commit().await?;
+            return Ok(RelayPass::default());
+        }
+
+        let transactional = self.publisher.is_transactional();
+        if transactional {
+            self.publisher.begin_batch().await?;
+        }
+
+        let result = self.publish_rows(&mut tx, rows).await;
+
+        let (mut pass, mut sent) = match result {
+            Ok(outcome) => outcome,
+            Err(e) => {
+                if transactional {
+                    self.abort_quietly().await;
+                }
+                return Err(e);
+            }
+        };
+
+        if transactional {
+            if pass.failed > 0 {
+                // Nothing from this pass becomes visible - resend it all
+                self.abort_quietly().await;
+                sent.clear();
+            } else if let Err(e) = self.publisher.commit_batch().await {
+                // Kafka first: the DB rollback keeps the rows pending
+                self.abort_quietly().await;
+                return Err(e);
+            }
+        }
+
+        if !sent.is_empty() {
+            sqlx::query("UPDATE event_outbox SET published_at = NOW() WHERE id = ANY($1)")
+                .bind(&sent)
+                .execute(&mut *tx)
+                .await?;
+        }
+
+        tx.commit().await?;
+
+        self.published_total
+            .fetch_add(sent.len() as u64, Ordering::Relaxed);
+        pass.published = sent.len();
+
+        Ok(pass)
+    }
+
+    /// Send rows in order; returns the pass counters and the IDs sent
+    async fn publish_rows(
+        &self,
+        tx: &mut Transaction<'_, Postgres>,
+        rows: Vec<OutboxEvent>,
+    ) -> WalletResult<(RelayPass, Vec<i64>)> {
+        let now = Utc::now();
+        let mut blocked: HashSet<String> = HashSet::new();
+        let mut pass = RelayPass::default();
+        let mut sent: Vec<i64> = Vec::new();
+
+        for row in rows {
+            if blocked.contains(&row.aggregate_id) || row.next_attempt_at > now {
+                blocked.insert(row.aggregate_id);
+                pass.deferred += 1;
+                continue;
+            }
+
+            match self.publisher.send(&row.aggregate_id, &row.payload).await {
+                Ok(()) => sent.push(row.id),
+                Err(e) => {
+                    let backoff_secs = 2_i64
+                        .saturating_pow((row.attempts + 1) as u32)
+                        .min(self.max_backoff_secs);
+
+                    tracing::warn!(
+                        outbox_id = row.id,
+                        event_type = %row.event_type,
+                        attempts = row.attempts + 1,
+                        backoff_secs = backoff_secs,
+                        error = %e,
+                        "Outbox publish failed, will retry"
+                    );
+
+                    sqlx::query(
+                        r#"
+                        UPDATE event_outbox
+                        SET attempts = attempts + 1,
+                            last_error = $2,
+                            next_attempt_at = NOW() + make_interval(secs => $3)
+                        WHERE id = $1
+                        "#,
+                    )
+                    .bind(row.id)
+                    .bind(e.to_string())
+                    .bind(backoff_secs as f64)
+                    .execute(&mut **tx)
+                    .await?;
+
+                    self.failures_total.fetch_add(1, Ordering::Relaxed);
+                    pass.failed += 1;
+
+                    // Usually the broker is down - stop instead of holding the
+                    // lock through a timeout per row. Next pass this row is in
+                    // backoff, so other wallets' events still flow.
+                    break;
+                }
+            }
+        }
+
+        Ok((pass, sent))
+    }
+
+    /// Abort the open Kafka transaction, logging (not returning) a failure
+    async fn abort_quietly(&self) {
+        if let Err(e) = self.publisher.abort_batch().await {
+            tracing::error!(error = %e, "Failed to abort outbox transaction");
+        }
+    }
+
+    /// How far behind the relay is
+    pub async fn backlog(&self) -> WalletResult<Backlog> {
+        let (pending, oldest_age_secs) = sqlx::query_as::<_, (i64, Option<f64>)>(
+            r#"
+            SELECT COUNT(*),
+                   EXTRACT(EPOCH FROM NOW() - MIN(created_at))::float8
+            FROM event_outbox
+            WHERE published_at IS NULL
+            "#,
+        )
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(Backlog {
+            pending,
+            oldest_age_secs: oldest_age_secs.unwrap_or(0.0),
+        })
+    }
+
+    /// Relay metrics in the OpenMetrics text format
+    pub async fn render_metrics(&self) -> WalletResult<String> {
+        let backlog = self.backlog().await?;
+        let mut out = String::new();
+
+        let _ = writeln!(out, "# TYPE wallet_outbox_backlog gauge");
+        let _ = writeln!(out, "# HELP wallet_outbox_backlog Events waiting to be relayed");
+        let _ = writeln!(out, "wallet_outbox_backlog {}", backlog.pending);
+        let _ = writeln!(out, "# TYPE wallet_outbox_oldest_age_seconds gauge");
+        let _ = writeln!(out, "# HELP wallet_outbox_oldest_age_seconds Age of the oldest pending event");
+        let _ = writeln!(out, "wallet_outbox_oldest_age_seconds {:.3}", backlog.oldest_age_secs);
+        let _ = writeln!(out, "# TYPE wallet_outbox_published counter");
+        let _ = writeln!(out, "# HELP wallet_outbox_published Events relayed to Kafka");
+        let _ = writeln!(
+            out,
+            "wallet_outbox_published_total {}",
+            self.published_total.load(Ordering::Relaxed)
+        );
+        let _ = writeln!(out, "# TYPE wallet_outbox_failures counter");
+        let _ = writeln!(out, "# HELP wallet_outbox_failures Failed publish attempts");
+        let _ = writeln!(
+            out,
+            "wallet_outbox_failures_total {}",
+            self.failures_total.load(Ordering::Relaxed)
+        );
+        out.push_str("# EOF\n");
+
+        Ok(out)
+    }
+}
@@ -0,0 +1,76 @@
+use argon2::password_hash::{PasswordHash, PasswordHasher, PasswordVerifier, SaltString};
+use argon2::Argon2;
+use std::time::Duration;
+
+/// How many wrong PINs lock a wallet, and for how long
+///
+/// Why lock at all?
+/// - A 4-digit PIN is 10,000 guesses; the slow hash alone doesn't stop a
+///   script from walking through them
+/// - Locking only refuses transfers that need the PIN - the wallet still
+///   takes money in and small transfers under the threshold still work
+///
+/// Each lockout since the last right PIN lasts twice as long as the one
+/// before, up to `max_lockout` - so guessing on after a lockout buys
+/// fewer and fewer tries a day instead of the same number forever.
+#[derive(Debug, Clone)]
+pub struct PinPolicy {
+    /// PIN_MAX_ATTEMPTS - wrong PINs in a row before the lockout
+    pub max_attempts: u32,
+    /// PIN_LOCKOUT_SECS - how long the first lockout lasts
+    pub lockout: Duration,
+    /// PIN_MAX_LOCKOUT_SECS - the longest a lockout gets
+    pub max_lockout: Duration,
+}
+
+impl Default for PinPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 5,
+            lockout: Duration::from_secs(15 * 60),
+            max_lockout: Duration::from_secs(24 * 60 * 60),
+        }
+    }
+}
+
+impl PinPolicy {
+    pub fn from_env() -> Self {
+        let defaults = Self::default();
+        Self {
+            max_attempts: std::env::var("PIN_MAX_ATTEMPTS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .filter(|&n| n > 0)
+                .unwrap_or(defaults.max_attempts),
+            lockout: std::env::var("PIN_LOCKOUT_SECS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .map(Duration::from_secs)
+                .unwrap_or(defaults.lockout),
+            max_lockout: std::env::var("PIN_MAX_LOCKOUT_SECS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .map(Duration::from_secs)
+                .unwrap_or(defaults.max_lockout),
+        }
+    }
+}
+
+/// Argon2id hash of `pin`, as a PHC string (salt and parameters included)
+///
+/// Tens of milliseconds of CPU on purpose - call from `spawn_blocking`.
+pub fn hash_pin(pin: &str) -> Result<String, String> {
+    let salt = SaltString::encode_b64(&rand::random::<[u8; 16]>()).map_err(|e| e.to_string())?;
+    Argon2::default()
+        .hash_password(pin.as_bytes(), &salt)
+        .map(|hash| hash.to_string())
+        .map_err(|e| e.to_string())
+}
+
+/// Whether `pin` matches a hash from `hash_pin` (an unparseable hash never
+/// matches)
+pub fn verify_pin(pin: &str, hash: &str) -> bool {
+    PasswordHash::new(hash)
+        .map(|parsed| Argon2::default().verify_password(pin.as_bytes(), &parsed).is_ok())
+        .unwrap_or(false)
+}
@@ -0,0 +1,210 @@
+use crate::errors::{WalletError, WalletResult};
+use chrono::{DateTime, Utc};
+use serde_json::Value;
+use std::collections::HashMap;
+
+/// The wallet event .proto, shared with history-service
+pub const WALLET_EVENT_PROTO: &str = include_str!("../../proto/wallet_event.proto");
+
+const WIRE_VARINT: u32 = 0;
+const WIRE_LEN: u32 = 2;
+
+/// One event message: its number in the `WalletEvent.event` oneof and fields
+#[derive(Debug, Clone)]
+pub struct EventMessage {
+    pub oneof_number: u32,
+    /// The JSON tag (WalletCreated -> WALLET_CREATED)
+    pub event_type: String,
+    pub fields: Vec<ProtoField>,
+}
+
+#[derive(Debug, Clone)]
+pub struct ProtoField {
+    pub number: u32,
+    pub name: String,
+    pub kind: FieldKind,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FieldKind {
+    String,
+    Int64,
+    Timestamp, // google.protobuf.Timestamp
+}
+
+/// Protobuf encoding for wallet events, driven by proto/wallet_event.proto
+///
+/// Why not generated code?
+/// - The events are flat messages of strings and timestamps; the wire format
+///   for that is a few lines, and reading the .proto at startup keeps ONE
+///   definition for both services
+/// - Other languages generate their types from the same file
+///
+/// Only `string`, `int64` and `google.protobuf.Timestamp` fields are
+/// understood; `parse` rejects anything else.
+#[derive(Debug, Clone)]
+pub struct ProtoSchema {
+    by_event_type: HashMap<String, EventMessage>,
+}
+
+impl ProtoSchema {
+    /// Read the messages out of a .proto file (the subset ours uses)
+    pub fn parse(text: &str) -> WalletResult<Self> {
+        let mut messages: HashMap<String, Vec<(String, String, u32)>> = HashMap::new();
+        let mut current: Option<String> = None;
+
+        for line in text.lines() {
+            let line = line.split("//").next().unwrap_or_default().trim();
+
+            if let Some(rest) = line.strip_prefix("message ") {
+                let name = rest.trim_end_matches('{').trim().to_string();
+                messages.entry(name.clone()).or_default();
+                current = Some(name);
+            } else if line == "}" || line.starts_with("oneof ") {
+                // Oneof members are listed as ordinary fields of the message
+            } else if let (Some(message), Some(field)) = (&current, parse_field(line)) {
+                messages.entry(message.clone()).or_default().push(field?);
+            }
+        }
+
+        let envelope = messages
+            .get("WalletEvent")
+            .ok_or_else(|| invalid("no WalletEvent message"))?;
+
+        let mut by_event_type = HashMap::new();
+        for (type_name, _, oneof_number) in envelope {
+            let fields = messages
+                .get(type_name)
+                .ok_or_else(|| invalid(&format!("message {} not defined", type_name)))?
+                .iter()
+                .map(|(field_type, name, number)| {
+                    let kind = match field_type.as_str() {
+                        "string" => FieldKind::String,
+                        "int64" => FieldKind::Int64,
+                        "google.protobuf.Timestamp" => FieldKind::Timestamp,
+                        other => return Err(invalid(&format!("unsupported field type {}", other))),
+                    };
+                    Ok(ProtoField {
+                        number: *number,
+                        name: name.clone(),
+                        kind,
+                    })
+                })
+                .collect::<WalletResult<_>>()?;
+
+            let event_type = screaming_snake(type_name);
+            by_event_type.insert(
+                event_type.clone(),
+                EventMessage {
+                    oneof_number: *oneof_number,
+                    event_type,
+                    fields,
+                },
+            );
+        }
+
+        Ok(Self { by_event_type })
+    }
+
+    /// Encode a JSON event (as serde produces it for a WalletEvent)
+    ///
+    /// Fields absent from the JSON are left unset (the proto3 default);
+    /// present ones must be strings (numbers for int64 fields).
+    pub fn encode(&self, value: &Value) -> WalletResult<Vec<u8>> {
+        let event_type = value
+            .get("eventType")
+            .and_then(Value::as_str)
+            .ok_or_else(|| invalid("event has no eventType"))?;
+        let message = self
+            .by_event_type
+            .get(event_type)
+            .ok_or_else(|| invalid(&format!("no message for {}", event_type)))?;
+
+        let mut body = Vec::new();
+        for field in &message.fields {
+            let Some(field_value) = value.get(&field.name) else {
+                continue;
+            };
+
+            if field.kind == FieldKind::Int64 {
+                let n = field_value
+                    .as_i64()
+                    .ok_or_else(|| invalid(&format!("{}.{} is not an integer", event_type, field.name)))?;
+                // Two's complement, as protobuf writes negative int64s
+                write_tag(field.number, WIRE_VARINT, &mut body);
+                write_varint(n as u64, &mut body);
+                continue;
+            }
+
+            let text = field_value
+                .as_str()
+                .ok_or_else(|| invalid(&format!("{}.{} is not a string", event_type, field.name)))?;
+
+            if field.kind == FieldKind::Timestamp {
+                let ts = DateTime::parse_from_rfc3339(text)
+                    .map_err(|e| invalid(&format!("bad timestamp {}: {}", text, e)))?
+                    .with_timezone(&Utc);
+                let mut nested = Vec::new();
+                write_tag(1, WIRE_VARINT, &mut nested);
+                write_varint(ts.timestamp() as u64, &mut nested);
+                write_tag(2, WIRE_VARINT, &mut nested);
+                write_varint(u64::from(ts.timestamp_subsec_nanos()), &mut nested);
+                write_bytes(field.number, &nested, &mut body);
+            } else {
+                write_bytes(field.number, text.as_bytes(), &mut body);
+            }
+        }
+
+        let mut out = Vec::with_capacity(body.len() + 4);
+        write_bytes(message.oneof_number, &body, &mut out);
+        Ok(out)
+    }
+}
+
+/// `Type name = 3;` -> (Type, name, 3)
+fn parse_field(line: &str) -> Option<WalletResult<(String, String, u32)>> {
+    let (decl, number) = line.strip_suffix(';')?.split_once('=')?;
+    let mut parts = decl.split_whitespace();
+    let (field_type, name) = (parts.next()?, parts.next()?);
+    Some(
+        number
+            .trim()
+            .parse()
+            .map(|n| (field_type.to_string(), name.to_string(), n))
+            .map_err(|_| invalid(&format!("bad field number in '{}'", line))),
+    )
+}
+
+/// WalletCreated -> WALLET_CREATED
+fn screaming_snake(name: &str) -> String {
+    let mut out = String::new();
+    for (i, c) in name.chars().enumerate() {
+        if c.is_uppercase() && i > 0 {
+            out.push('_');
+        }
+        out.push(c.to_ascii_uppercase());
+    }
+    out
+}
+
+fn write_tag(number: u32, wire_type: u32, out: &mut Vec<u8>) {
+    write_varint(u64::from((number << 3) | wire_type), out);
+}
+
+fn write_varint(mut n: u64, out: &mut Vec<u8>) {
+    while n >= 0x80 {
+        out.push((n as u8) | 0x80);
+        n >>= 7;
+    }
+    out.push(n as u8);
+}
+
+fn write_bytes(number: u32, bytes: &[u8], out: &mut Vec<u8>) {
+    write_tag(number, WIRE_LEN, out);
+    write_varint(bytes.len() as u64, out);
+    out.extend_from_slice(bytes);
+}
+
+fn invalid(what: &str) -> WalletError {
+    WalletError::InternalError(format!("Protobuf: {}", what))
+}
@@ -0,0 +1,255 @@
+use crate::errors::ErrorResponse;
+use axum::{
+    extract::{ConnectInfo, FromRequestParts, RawPathParams, Request, State},
+    http::{header, HeaderMap, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+    Json,
+};
+use std::collections::HashMap;
+use std::net::{IpAddr, SocketAddr};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// Buckets kept per limiter before full (idle) ones are dropped
+const MAX_BUCKETS: usize = 10_000;
+
+/// One route's limit: `burst` requests at once, refilled at `per_minute`
+///
+/// `per_minute` 0 = unlimited.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RateLimit {
+    pub per_minute: u32,
+    pub burst: u32,
+}
+
+impl RateLimit {
+    pub const UNLIMITED: RateLimit = RateLimit {
+        per_minute: 0,
+        burst: 0,
+    };
+
+    /// RATE_LIMIT_{NAME}_PER_MIN and RATE_LIMIT_{NAME}_BURST, or `default`
+    pub fn from_env(name: &str, default: RateLimit) -> Self {
+        fn env_or(name: &str, default: u32) -> u32 {
+            std::env::var(name)
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(default)
+        }
+
+        Self {
+            per_minute: env_or(&format!("RATE_LIMIT_{}_PER_MIN", name), default.per_minute),
+            burst: env_or(&format!("RATE_LIMIT_{}_BURST", name), default.burst).max(1),
+        }
+    }
+
+    pub fn is_unlimited(&self) -> bool {
+        self.per_minute == 0
+    }
+}
+
+/// Limits for the money-movement routes
+#[derive(Debug, Clone)]
+pub struct RateLimitConfig {
+    /// RATE_LIMIT_FUND_* - funding and voucher redemption
+    pub fund: RateLimit,
+    /// RATE_LIMIT_TRANSFER_* - transfers, including template executions
+    pub transfer: RateLimit,
+    /// RATE_LIMIT_TRUST_FORWARDED=on - take the client IP from the last
+    /// X-Forwarded-For entry (only behind a proxy that sets it)
+    pub trust_forwarded_for: bool,
+}
+
+impl Default for RateLimitConfig {
+    fn default() -> Self {
+        let money = RateLimit {
+            per_minute: 60,
+            burst: 10,
+        };
+        Self {
+            fund: money,
+            transfer: money,
+            trust_forwarded_for: false,
+        }
+    }
+}
+
+impl RateLimitConfig {
+    /// RATE_LIMIT=off turns every limit off
+    pub fn from_env() -> Self {
+        if std::env::var("RATE_LIMIT").as_deref() == Ok("off") {
+            return Self {
+                fund: RateLimit::UNLIMITED,
+                transfer: RateLimit::UNLIMITED,
+                trust_forwarded_for: false,
+            };
+        }
+
+        let defaults = Self::default();
+        Self {
+            fund: RateLimit::from_env("FUND", defaults.fund),
+            transfer: RateLimit::from_env("TRANSFER", defaults.transfer),
+            trust_forwarded_for: std::env::var("RATE_LIMIT_TRUST_FORWARDED").as_deref()
+                == Ok("on"),
+        }
+    }
+}
+
+/// Who is making the request, for the per-user bucket
+///
+/// The layer that authenticates a request puts this in its extensions.
+/// Requests without one get no user bucket.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Caller(pub String);
+
+#[derive(Debug, Clone, Copy)]
+struct TokenBucket {
+    tokens: f64,
+    updated: Instant,
+}
+
+/// Token-bucket rate limiting for one group of routes
+///
+/// Why per IP, per wallet *and* per user?
+/// - Per IP: one client can't hammer fund/transfer across many wallets
+/// - Per wallet: many IPs together can't drain one wallet faster than the
+///   limit
+/// - Per user: the authenticated caller (`Caller`) can't get around both
+///   by spreading requests over IPs and their own wallets. Needs the
+///   limiter inside the authenticating layer, which `route_layer` on a
+///   route gives; unauthenticated requests have no user bucket
+///
+/// How it works:
+/// 1. Each key has a bucket of `burst` tokens, refilled at `per_minute`
+/// 2. A request takes one token from every bucket it belongs to - if any
+///    is empty, none is taken and the answer is 429 + Retry-After
+/// 3. Buckets live in memory, so limits are per instance
+pub struct RateLimiter {
+    name: &'static str,
+    limit: RateLimit,
+    trust_forwarded_for: bool,
+    buckets: Mutex<HashMap<String, TokenBucket>>,
+    rejected: AtomicU64,
+}
+
+impl RateLimiter {
+    pub fn new(name: &'static str, limit: RateLimit, trust_forwarded_for: bool) -> Self {
+        Self {
+            name,
+            limit,
+            trust_forwarded_for,
+            buckets: Mutex::new(HashMap::new()),
+            rejected: AtomicU64::new(0),
+        }
+    }
+
+    /// Take a token for every key, or say how long until one is available
+    pub fn check(&self, keys: &[String], now: Instant) -> Result<(), Duration> {
+        if self.limit.is_unlimited() {
+            return Ok(());
+        }
+        let burst = f64::from(self.limit.burst);
+        let per_second = f64::from(self.limit.per_minute) / 60.0;
+
+        let mut buckets = self.buckets.lock().unwrap_or_else(|e| e.into_inner());
+        if buckets.len() > MAX_BUCKETS {
+            // Full buckets carry no state - same as a new one
+            buckets.retain(|_, b| {
+                b.tokens + now.saturating_duration_since(b.updated).as_secs_f64() * per_second < burst
+            });
+        }
+
+        let mut wait = Duration::ZERO;
+        for key in keys {
+            let bucket = buckets.entry(key.clone()).or_insert(TokenBucket {
+                tokens: burst,
+                updated: now,
+            });
+            let elapsed = now.saturating_duration_since(bucket.updated).as_secs_f64();
+            bucket.tokens = (bucket.tokens + elapsed * per_second).min(burst);
+            bucket.updated = now;
+            if bucket.tokens < 1.0 {
+                wait = wait.max(Duration::from_secs_f64((1.0 - bucket.tokens) / per_second));
+            }
+        }
+
+        if !wait.is_zero() {
+            self.rejected.fetch_add(1, Ordering::Relaxed);
+            return Err(wait);
+        }
+        for key in keys {
+            if let Some(bucket) = buckets.get_mut(key) {
+                bucket.tokens -= 1.0;
+            }
+        }
+        Ok(())
+    }
+
+    /// Requests refused so far
+    pub fn rejected(&self) -> u64 {
+        self.rejected.load(Ordering::Relaxed)
+    }
+
+    /// The client address: the peer, or the proxy's X-Forwarded-For entry
+    fn client_ip(&self, headers: &HeaderMap, peer: Option<SocketAddr>) -> Option<IpAddr> {
+        if self.trust_forwarded_for {
+            let forwarded = headers
+                .get("x-forwarded-for")
+                .and_then(|v| v.to_str().ok())
+                .and_then(|v| v.rsplit(',').next())
+                .and_then(|v| v.trim().parse().ok());
+            if forwarded.is_some() {
+                return forwarded;
+            }
+        }
+        peer.map(|addr| addr.ip())
+    }
+}
+
+/// Middleware for a route group (add with `route_layer`)
+pub async fn limit_rate(
+    State(limiter): State<Arc<RateLimiter>>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let (mut parts, body) = request.into_parts();
+
+    let peer = parts
+        .extensions
+        .get::<ConnectInfo<SocketAddr>>()
+        .map(|ConnectInfo(addr)| *addr);
+    let mut keys = Vec::with_capacity(3);
+    if let Some(ip) = limiter.client_ip(&parts.headers, peer) {
+        keys.push(format!("ip:{}", ip));
+    }
+    // The resource the route names (e.g. wallet_id:<uuid>)
+    if let Ok(params) = RawPathParams::from_request_parts(&mut parts, &()).await {
+        if let Some((name, value)) = params.iter().next() {
+            keys.push(format!("{}:{}", name, value));
+        }
+    }
+    // The caller, once authenticated (a /users/:user_id route names the
+    // same bucket - one token, not two)
+    if let Some(Caller(caller)) = parts.extensions.get::<Caller>() {
+        let user = format!("user_id:{}", caller);
+        if !keys.contains(&user) {
+            keys.push(user);
+        }
+    }
+
+    if let Err(wait) = limiter.check(&keys, Instant::now()) {
+        tracing::debug!(limiter = limiter.name, keys = ?keys, "Rate limited");
+        // Whole seconds, rounded up - Retry-After: 0 would invite a retry storm
+        let retry_after = wait.as_secs() + u64::from(wait.subsec_nanos() > 0);
+        return (
+            StatusCode::TOO_MANY_REQUESTS,
+            [(header::RETRY_AFTER, retry_after.to_string())],
+            Json(ErrorResponse::new("Too many requests; slow down and retry later")),
+        )
+            .into_response();
+    }
+
+    next.run(Request::from_parts(parts, body)).await
+}
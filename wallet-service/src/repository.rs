@@ -1,10 +1,55 @@
+use crate::auth;
+use crate::db_pool::PoolStats;
+use crate::encryption::{FieldCipher, Sealed};
 use crate::errors::{WalletError, WalletResult};
-use crate::models::{TransactionStatus, TransactionType, Wallet, WalletTransaction};
-use chrono::Utc;
+use crate::models::{
+    AdjustmentDirection, AdjustmentRequest, AdminAuditEntry, AdminWalletQuery, ApiKey, ApiKeyScope,
+    AuditLogQuery, BalanceShards, Beneficiary, BlocklistEntry, BlocklistTargetType,
+    CreateApiKeyRequest, CreateBlocklistEntryRequest,
+    KycLevel, LedgerVerification, PendingTransfer, ReceiptLeg, RoundUpOutcome, RoundUpRule, TransactionNote,
+    TransactionReceipt, TransactionStatus, TransactionType, TransferOutcome, TransferTemplate,
+    UpdateWalletRequest, UpdateWebhookRequest, UserAnonymization, UserKyc, Voucher, Wallet, WalletAdjustment,
+    WalletPin, WalletTransaction, WebhookDelivery, WebhookDeliveryAttempt, WebhookSubscription,
+    AUDIT_API_KEY_CREATED, AUDIT_API_KEY_REVOKED, AUDIT_BALANCE_SHARDS_CHANGED, AUDIT_BLOCKLIST_ENTRY_ADDED,
+    AUDIT_BLOCKLIST_ENTRY_REMOVED, AUDIT_HELD_TRANSFER_REJECTED, AUDIT_HELD_TRANSFER_RELEASED,
+    AUDIT_KYC_LEVEL_CHANGED, AUDIT_TRANSACTION_NOTE_ADDED, AUDIT_USER_ANONYMIZED, AUDIT_VOUCHER_MINTED,
+    AUDIT_WALLET_ADJUSTED, AUDIT_WALLET_ARCHIVED, MAX_BALANCE_SHARDS,
+};
+use crate::kyc::KycLimits;
+use crate::ledger::{self, ChainCheck, LedgerEntry, GENESIS_HASH, MAX_REPORTED_BREAKS};
+use crate::lock_retry::LockRetry;
+use crate::pin::{self, PinPolicy};
+use crate::read_replica::{self, ReadReplica};
+use crate::step_up::{self, StepUpConfig};
+use crate::velocity::VelocityLimits;
+use crate::wallet_cache::{CacheKey, WalletCache};
+use crate::webhooks;
+use chrono::{DateTime, Utc};
+use rand::Rng;
 use rust_decimal::Decimal;
+use serde_json::json;
 use sqlx::{PgPool, Postgres, Transaction};
+use std::collections::HashMap;
 use uuid::Uuid;
 
+/// The `Wallet` in a `query!` record that selected its columns and more
+macro_rules! wallet_from_record {
+    ($row:expr) => {
+        Wallet {
+            id: $row.id,
+            user_id: $row.user_id,
+            balance: $row.balance,
+            version: $row.version,
+            parent_wallet_id: $row.parent_wallet_id,
+            nickname: $row.nickname,
+            labels: $row.labels,
+            is_default: $row.is_default,
+            created_at: $row.created_at,
+            updated_at: $row.updated_at,
+        }
+    };
+}
+
 /// Repository for wallet database operations
 /// 
 /// Design principle: All database logic lives here
@@ -14,11 +59,145 @@ use uuid::Uuid;
 #[derive(Clone)]
 pub struct WalletRepository {
     pool: PgPool,
+    velocity: VelocityLimits,
+    kyc: KycLimits,
+    cipher: FieldCipher,
+    lock_retry: LockRetry,
+    cache: Option<WalletCache>,
+    queries: Option<PgPool>,
+    replica: Option<ReadReplica>,
 }
 
 impl WalletRepository {
     pub fn new(pool: PgPool) -> Self {
-        Self { pool }
+        Self {
+            pool,
+            velocity: VelocityLimits::default(),
+            kyc: KycLimits::default(),
+            cipher: FieldCipher::default(),
+            lock_retry: LockRetry::default(),
+            cache: None,
+            queries: None,
+            replica: None,
+        }
+    }
+
+    /// Enforce per-wallet velocity limits on every transfer (none by default)
+    pub fn with_velocity_limits(mut self, velocity: VelocityLimits) -> Self {
+        self.velocity = velocity;
+        self
+    }
+
+    /// Cap what unverified users may hold and send (no caps by default)
+    pub fn with_kyc_limits(mut self, kyc: KycLimits) -> Self {
+        self.kyc = kyc;
+        self
+    }
+
+    /// Encrypt memos, notes, nicknames, labels and beneficiary names at rest
+    /// (stored as written by default)
+    pub fn with_field_cipher(mut self, cipher: FieldCipher) -> Self {
+        self.cipher = cipher;
+        self
+    }
+
+    /// Retry optimistic lock conflicts this way (LockRetry::default() unless
+    /// set; LockRetry::disabled() hands every conflict to the client)
+    pub fn with_lock_retry(mut self, lock_retry: LockRetry) -> Self {
+        self.lock_retry = lock_retry;
+        self
+    }
+
+    /// Serve `find_by_id` and `find_by_user_id` from Redis, invalidated on
+    /// every commit that changes a wallet (no cache by default)
+    pub fn with_cache(mut self, cache: WalletCache) -> Self {
+        self.cache = Some(cache);
+        self
+    }
+
+    /// Run read-only queries on their own pool, so they can't take the
+    /// connections mutations need (see db_pool.rs; one pool by default)
+    pub fn with_query_pool(mut self, queries: PgPool) -> Self {
+        self.queries = Some(queries);
+        self
+    }
+
+    /// Send the read-only queries of GET requests to a replica while it
+    /// keeps up (see read_replica.rs; everything reads the primary by default)
+    pub fn with_read_replica(mut self, replica: ReadReplica) -> Self {
+        self.replica = Some(replica);
+        self
+    }
+
+    /// Connections in use per pool, for /metrics
+    pub fn pool_stats(&self) -> Vec<PoolStats> {
+        let mut stats = vec![PoolStats::of("primary", &self.pool)];
+        if let Some(queries) = &self.queries {
+            stats.push(PoolStats::of("queries", queries));
+        }
+        if let Some(replica) = &self.replica {
+            stats.push(PoolStats::of("replica", replica.pool()));
+        }
+        stats
+    }
+
+    /// Where a read-only query goes: the replica inside a GET request
+    /// while it's within its lag limit, the primary's query pool otherwise
+    fn reader(&self) -> &PgPool {
+        match &self.replica {
+            Some(replica) if replica.is_usable() && read_replica::replica_reads_allowed() => {
+                replica.pool()
+            }
+            _ => self.queries.as_ref().unwrap_or(&self.pool),
+        }
+    }
+
+    /// Drop cached copies of `wallets` - call right after the commit that
+    /// changed them
+    async fn invalidate_cached<'a>(&self, wallets: impl IntoIterator<Item = &'a Wallet>) {
+        if let Some(cache) = &self.cache {
+            cache.invalidate(wallets).await;
+        }
+    }
+
+    /// `invalidate_cached` by key
+    async fn invalidate_cached_keys(&self, keys: &[CacheKey<'_>]) {
+        if let Some(cache) = &self.cache {
+            cache.invalidate_keys(keys).await;
+        }
+    }
+
+    /// Run `attempt` until it doesn't end in OptimisticLockError, up to
+    /// `lock_retry.max_attempts` times, sleeping a jittered backoff between
+    ///
+    /// Each attempt must be its own DB transaction - a conflict rolls back
+    /// everything it did.
+    async fn retry_on_conflict<T, F, Fut>(&self, mut attempt: F) -> WalletResult<T>
+    where
+        F: FnMut() -> Fut,
+        Fut: std::future::Future<Output = WalletResult<T>>,
+    {
+        let mut tries = 1;
+        loop {
+            match attempt().await {
+                Err(WalletError::OptimisticLockError) if tries < self.lock_retry.max_attempts => {
+                    let delay = self.lock_retry.delay(tries);
+                    tracing::debug!(
+                        attempt = tries,
+                        delay_ms = delay.as_millis() as u64,
+                        "Optimistic lock conflict, retrying"
+                    );
+                    tokio::time::sleep(delay).await;
+                    tries += 1;
+                }
+                result => return result,
+            }
+        }
+    }
+
+    /// Decrypt the encrypted columns of rows just read
+    fn open<T: Sealed>(&self, rows: T) -> WalletResult<T> {
+        rows.open_with(&self.cipher)
     }
 
     /// Create a new wallet for a user
@@ -31,75 +210,276 @@ impl WalletRepository {
         let wallet_id = Uuid::new_v4().to_string();
         let now = Utc::now();
 
-        let wallet = sqlx::query_as::<_, Wallet>(
+        let wallet = sqlx::query_as!(
+            Wallet,
             r#"
             INSERT INTO wallets (id, user_id, balance, version, created_at, updated_at)
             VALUES ($1, $2, 0, 0, $3, $3)
-            RETURNING id, user_id, balance, version, created_at, updated_at
+            RETURNING id, user_id, balance, version, parent_wallet_id, nickname, labels, is_default,
+                      created_at, updated_at
             "#,
+            &wallet_id,
+            user_id,
+            now
         )
-        .bind(&wallet_id)
-        .bind(user_id)
-        .bind(now)
         .fetch_one(&self.pool)
         .await?;
+        self.invalidate_cached_keys(&[CacheKey::UserWallets(user_id)]).await;
 
-        Ok(wallet)
+        self.open(wallet)
     }
 
     /// Find a wallet by ID
     pub async fn find_by_id(&self, wallet_id: &str) -> WalletResult<Wallet> {
-        let wallet = sqlx::query_as::<_, Wallet>(
+        // The cache fills from the primary: a replica's lag could outlast
+        // the invalidation and put an old row back
+        let wallet = match &self.cache {
+            Some(cache) => {
+                cache
+                    .get_or_load(CacheKey::Wallet(wallet_id), || {
+                        self.load_wallet(&self.pool, wallet_id)
+                    })
+                    .await?
+            }
+            None => self.load_wallet(self.reader(), wallet_id).await?,
+        };
+
+        self.open(wallet)
+    }
+
+    /// The wallet row as stored (encrypted fields sealed)
+    async fn load_wallet(&self, pool: &PgPool, wallet_id: &str) -> WalletResult<Wallet> {
+        sqlx::query_as!(
+            Wallet,
             r#"
-            SELECT id, user_id, balance, version, created_at, updated_at
+            SELECT id, user_id, total_balance(wallets) AS "balance!", version, parent_wallet_id,
+                   nickname, labels, is_default, created_at, updated_at
             FROM wallets
             WHERE id = $1
             "#,
+            wallet_id
         )
-        .bind(wallet_id)
-        .fetch_optional(&self.pool)
+        .fetch_optional(pool)
         .await?
-        .ok_or_else(|| WalletError::WalletNotFound(wallet_id.to_string()))?;
-
-        Ok(wallet)
+        .ok_or_else(|| WalletError::WalletNotFound(wallet_id.to_string()))
     }
 
     /// Find all wallets for a user
     pub async fn find_by_user_id(&self, user_id: &str) -> WalletResult<Vec<Wallet>> {
-        let wallets = sqlx::query_as::<_, Wallet>(
+        let wallets = match &self.cache {
+            Some(cache) => {
+                cache
+                    .get_or_load(CacheKey::UserWallets(user_id), || {
+                        self.load_user_wallets(&self.pool, user_id)
+                    })
+                    .await?
+            }
+            None => self.load_user_wallets(self.reader(), user_id).await?,
+        };
+
+        self.open(wallets)
+    }
+
+    /// A user's wallet rows as stored, newest first
+    async fn load_user_wallets(&self, pool: &PgPool, user_id: &str) -> WalletResult<Vec<Wallet>> {
+        let wallets = sqlx::query_as!(
+            Wallet,
             r#"
-            SELECT id, user_id, balance, version, created_at, updated_at
+            SELECT id, user_id, total_balance(wallets) AS "balance!", version, parent_wallet_id,
+                   nickname, labels, is_default, created_at, updated_at
             FROM wallets
             WHERE user_id = $1
             ORDER BY created_at DESC
             "#,
+            user_id
         )
-        .bind(user_id)
-        .fetch_all(&self.pool)
+        .fetch_all(pool)
         .await?;
 
         Ok(wallets)
     }
 
+    /// Every wallet among `wallet_ids`, in one query (unknown IDs are skipped)
+    pub async fn find_by_ids(&self, wallet_ids: &[String]) -> WalletResult<Vec<Wallet>> {
+        let wallets = sqlx::query_as!(
+            Wallet,
+            r#"
+            SELECT id, user_id, total_balance(wallets) AS "balance!", version, parent_wallet_id,
+                   nickname, labels, is_default, created_at, updated_at
+            FROM wallets
+            WHERE id = ANY($1)
+            "#,
+            wallet_ids
+        )
+        .fetch_all(self.reader())
+        .await?;
+
+        self.open(wallets)
+    }
+
+    /// One page of wallets matching the admin filters, newest first, plus
+    /// how many match in total
+    ///
+    /// Absent filters are NULL and match everything, so one query covers
+    /// every combination.
+    pub async fn find_wallets(
+        &self,
+        query: &AdminWalletQuery,
+        limit: i64,
+        offset: i64,
+    ) -> WalletResult<(Vec<Wallet>, i64)> {
+        let wallets = sqlx::query_as!(
+            Wallet,
+            r#"
+            SELECT id, user_id, total_balance(wallets) AS "balance!", version, parent_wallet_id,
+                   nickname, labels, is_default, created_at, updated_at
+            FROM wallets
+            WHERE ($1::VARCHAR IS NULL OR user_id = $1)
+              AND ($2::DECIMAL IS NULL OR total_balance(wallets) >= $2)
+              AND ($3::TIMESTAMPTZ IS NULL OR created_at > $3)
+            ORDER BY created_at DESC, id
+            LIMIT $4 OFFSET $5
+            "#,
+            query.user_id.as_deref(),
+            query.min_balance,
+            query.created_after,
+            limit,
+            offset
+        )
+        .fetch_all(self.reader())
+        .await?;
+
+        let total = sqlx::query_scalar!(
+            r#"
+            SELECT COUNT(*) AS "count!"
+            FROM wallets
+            WHERE ($1::VARCHAR IS NULL OR user_id = $1)
+              AND ($2::DECIMAL IS NULL OR total_balance(wallets) >= $2)
+              AND ($3::TIMESTAMPTZ IS NULL OR created_at > $3)
+            "#,
+            query.user_id.as_deref(),
+            query.min_balance,
+            query.created_after
+        )
+        .fetch_one(self.reader())
+        .await?;
+
+        Ok((self.open(wallets)?, total))
+    }
+
+    /// Cheap round-trip to the database (used by the degradation probe)
+    pub async fn ping(&self) -> WalletResult<()> {
+        sqlx::query("SELECT 1").execute(&self.pool).await?;
+        Ok(())
+    }
+
+    /// Every migration the database has run: `(version, success)`
+    pub async fn applied_migrations(&self) -> WalletResult<Vec<(i64, bool)>> {
+        let applied = sqlx::query!("SELECT version, success FROM _sqlx_migrations ORDER BY version")
+            .fetch_all(&self.pool)
+            .await?;
+
+        Ok(applied.into_iter().map(|row| (row.version, row.success)).collect())
+    }
+
+    /// Pick a random sample of top-level wallets (for scrubbed exports)
+    pub async fn sample_wallets(&self, limit: i64) -> WalletResult<Vec<Wallet>> {
+        let wallets = sqlx::query_as!(
+            Wallet,
+            r#"
+            SELECT id, user_id, total_balance(wallets) AS "balance!", version, parent_wallet_id,
+                   nickname, labels, is_default, created_at, updated_at
+            FROM wallets
+            WHERE parent_wallet_id IS NULL
+            ORDER BY random()
+            LIMIT $1
+            "#,
+            limit
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        self.open(wallets)
+    }
+
+    /// A wallet's latest `limit` transactions, newest first
+    pub async fn find_recent_transactions(
+        &self,
+        wallet_id: &str,
+        limit: i64,
+    ) -> WalletResult<Vec<WalletTransaction>> {
+        let transactions = sqlx::query_as!(
+            WalletTransaction,
+            r#"
+            SELECT id, wallet_id, amount, type AS "transaction_type: TransactionType",
+                   status AS "status: TransactionStatus", reference_id, memo, created_at
+            FROM wallet_transactions
+            WHERE wallet_id = $1
+            ORDER BY created_at DESC, id DESC
+            LIMIT $2
+            "#,
+            wallet_id,
+            limit
+        )
+        .fetch_all(self.reader())
+        .await?;
+
+        self.open(transactions)
+    }
+
+    /// All transactions of a wallet, oldest first
+    pub async fn find_wallet_transactions(
+        &self,
+        wallet_id: &str,
+    ) -> WalletResult<Vec<WalletTransaction>> {
+        let transactions = sqlx::query_as!(
+            WalletTransaction,
+            r#"
+            SELECT id, wallet_id, amount, type AS "transaction_type: TransactionType",
+                   status AS "status: TransactionStatus", reference_id, memo, created_at
+            FROM wallet_transactions
+            WHERE wallet_id = $1
+            ORDER BY created_at ASC
+            "#,
+            wallet_id
+        )
+        .fetch_all(self.reader())
+        .await?;
+
+        self.open(transactions)
+    }
+
     /// Fund a wallet - Add money to wallet balance
     /// 
-    /// CRITICAL: This uses optimistic locking!
+    /// One statement: `UPDATE ... SET balance = balance + amount,
+    /// version = version + 1 ... RETURNING`.
     /// 
     /// How it works:
-    /// 1. Read wallet with current version
-    /// 2. Calculate new balance
-    /// 3. Update WHERE version matches what we read
-    /// 4. If version changed (concurrent update), UPDATE affects 0 rows
-    /// 5. Return OptimisticLockError - client should retry
+    /// 1. The database adds the amount to whatever the balance is when the
+    ///    row lock is granted - there is no read-then-write to go stale
+    /// 2. Concurrent fundings of one wallet queue on that lock and all
+    ///    succeed; none gets OptimisticLockError
+    /// 3. The version still goes up, so ETags and If-Match keep working
     /// 
     /// Why this matters:
-    /// - Prevents lost updates in concurrent scenarios
-    /// - No row-level locks needed
-    /// - Better performance under contention
+    /// - Parallel top-ups are additive, so refusing them protected nothing
+    /// - The lock is held only until commit (one UPDATE and the
+    ///   transaction record)
     pub async fn fund_wallet(
         &self,
         wallet_id: &str,
         amount: Decimal,
+    ) -> WalletResult<(Wallet, WalletTransaction)> {
+        self.fund_wallet_expecting(wallet_id, amount, None).await
+    }
+
+    /// `fund_wallet`, but only if the wallet is still at `expected_version`
+    /// (the client's If-Match); PreconditionFailed otherwise
+    pub async fn fund_wallet_expecting(
+        &self,
+        wallet_id: &str,
+        amount: Decimal,
+        expected_version: Option<i64>,
     ) -> WalletResult<(Wallet, WalletTransaction)> {
         // Validate amount
         if amount <= Decimal::ZERO {
@@ -111,31 +491,33 @@ impl WalletRepository {
         // Start a transaction - all or nothing
         let mut tx = self.pool.begin().await?;
 
-        // Get current wallet state
-        let wallet = self.find_by_id_in_tx(&mut tx, wallet_id).await?;
-        let new_balance = wallet.balance + amount;
-        let new_version = wallet.version + 1;
-
-        // Update with optimistic lock check
-        let rows_affected = sqlx::query(
+        // Money only enters a pot through its parent
+        let updated = sqlx::query_as!(
+            Wallet,
             r#"
             UPDATE wallets
-            SET balance = $1, version = $2
-            WHERE id = $3 AND version = $4
+            SET balance = balance + $1, version = version + 1
+            WHERE id = $2
+              AND parent_wallet_id IS NULL
+              AND ($3::BIGINT IS NULL OR version = $3)
+            RETURNING id, user_id, total_balance(wallets) AS "balance!", version, parent_wallet_id,
+                      nickname, labels, is_default, created_at, updated_at
             "#,
+            amount,
+            wallet_id,
+            expected_version
         )
-        .bind(new_balance)
-        .bind(new_version)
-        .bind(wallet_id)
-        .bind(wallet.version) // This is the optimistic lock!
-        .execute(&mut *tx)
-        .await?
-        .rows_affected();
+        .fetch_optional(&mut *tx)
+        .await?;
 
-        if rows_affected == 0 {
-            // Someone else updated this wallet between our read and write
-            return Err(WalletError::OptimisticLockError);
-        }
+        let wallet = match updated {
+            Some(wallet) => self.open(wallet)?,
+            None => return Err(self.fund_refusal(&mut tx, wallet_id, expected_version).await),
+        };
+
+        // Unverified users' balances are capped - the amount is already in,
+        // so this checks what they now hold
+        self.check_kyc_credit_in_tx(&mut tx, &wallet.user_id, Decimal::ZERO).await?;
 
         // Record the transaction
         let transaction = self
@@ -146,25 +528,155 @@ impl WalletRepository {
                 TransactionType::Fund,
                 TransactionStatus::Completed,
                 None,
+                None,
             )
             .await?;
 
         // Commit the transaction
         tx.commit().await?;
+        self.invalidate_cached([&wallet]).await;
 
-        // Fetch updated wallet
-        let updated_wallet = self.find_by_id(wallet_id).await?;
+        Ok((wallet, transaction))
+    }
+
+    /// Why a funding's UPDATE matched no row: no such wallet, a stale
+    /// If-Match, or a pot
+    async fn fund_refusal(
+        &self,
+        tx: &mut Transaction<'_, Postgres>,
+        wallet_id: &str,
+        expected_version: Option<i64>,
+    ) -> WalletError {
+        let wallet = match self.find_by_id_in_tx(tx, wallet_id).await {
+            Ok(wallet) => wallet,
+            Err(e) => return e,
+        };
+        if let Err(e) = check_version(&wallet, expected_version) {
+            return e;
+        }
+        if wallet.parent_wallet_id.is_some() {
+            return WalletError::InvalidRequest(
+                "Pots can only be funded from their parent wallet".to_string(),
+            );
+        }
+        // Only if the wallet changed between the UPDATE and this read
+        WalletError::PreconditionFailed {
+            current_version: wallet.version,
+        }
+    }
+
+    /// Change a wallet's nickname, labels or default flag - never its balance
+    ///
+    /// How it works:
+    /// 1. Optimistic lock: the version must still be the one we read (and
+    ///    the client's If-Match, when sent)
+    /// 2. Making a wallet the default unsets the user's previous default in
+    ///    the same transaction (the old one's version goes up too)
+    /// 3. A patch that changes nothing leaves the version alone
+    /// 4. A conflict with a concurrent change is retried (see LockRetry);
+    ///    OptimisticLockError only once those run out
+    pub async fn update_wallet_metadata(
+        &self,
+        wallet_id: &str,
+        update: &UpdateWalletRequest,
+        expected_version: Option<i64>,
+    ) -> WalletResult<Wallet> {
+        self.retry_on_conflict(|| self.try_update_wallet_metadata(wallet_id, update, expected_version))
+            .await
+    }
+
+    async fn try_update_wallet_metadata(
+        &self,
+        wallet_id: &str,
+        update: &UpdateWalletRequest,
+        expected_version: Option<i64>,
+    ) -> WalletResult<Wallet> {
+        let mut tx = self.pool.begin().await?;
+
+        let wallet = self.find_by_id_in_tx(&mut tx, wallet_id).await?;
+        check_version(&wallet, expected_version)?;
+
+        let nickname = match &update.nickname {
+            Some(nickname) => nickname.as_deref().map(str::trim).map(str::to_string),
+            None => wallet.nickname.clone(),
+        };
+        let labels = match &update.labels {
+            Some(labels) => labels.iter().map(|l| l.trim().to_string()).collect(),
+            None => wallet.labels.clone(),
+        };
+        let is_default = update.is_default.unwrap_or(wallet.is_default);
+
+        if is_default && wallet.parent_wallet_id.is_some() {
+            return Err(WalletError::InvalidRequest(
+                "A pot can't be the default wallet".to_string(),
+            ));
+        }
+        if nickname == wallet.nickname && labels == wallet.labels && is_default == wallet.is_default
+        {
+            return Ok(wallet);
+        }
+
+        let mut previous_defaults = Vec::new();
+        if is_default && !wallet.is_default {
+            previous_defaults = sqlx::query_scalar!(
+                r#"
+                UPDATE wallets
+                SET is_default = FALSE, version = version + 1
+                WHERE user_id = $1 AND is_default AND id <> $2
+                RETURNING id
+                "#,
+                &wallet.user_id,
+                wallet_id
+            )
+            .fetch_all(&mut *tx)
+            .await?;
+        }
+
+        let sealed_labels = labels
+            .iter()
+            .map(|label| self.cipher.seal(label))
+            .collect::<WalletResult<Vec<_>>>()?;
+        let updated = sqlx::query_as!(
+            Wallet,
+            r#"
+            UPDATE wallets
+            SET nickname = $1, labels = $2, is_default = $3, version = version + 1
+            WHERE id = $4 AND version = $5
+            RETURNING id, user_id, total_balance(wallets) AS "balance!", version, parent_wallet_id,
+                      nickname, labels, is_default, created_at, updated_at
+            "#,
+            self.cipher.seal_opt(nickname.as_deref())?,
+            &sealed_labels,
+            is_default,
+            wallet_id,
+            wallet.version // The optimistic lock
+        )
+        .fetch_optional(&mut *tx)
+        .await
+        .map_err(|e| match e {
+            // Another wallet of this user became the default meanwhile
+            sqlx::Error::Database(ref db) if db.is_unique_violation() => {
+                WalletError::OptimisticLockError
+            }
+            e => WalletError::DatabaseError(e),
+        })?
+        .ok_or(WalletError::OptimisticLockError)?;
+
+        tx.commit().await?;
+        let mut stale = vec![CacheKey::Wallet(wallet_id), CacheKey::UserWallets(&wallet.user_id)];
+        stale.extend(previous_defaults.iter().map(|id| CacheKey::Wallet(id)));
+        self.invalidate_cached_keys(&stale).await;
 
-        Ok((updated_wallet, transaction))
+        self.open(updated)
     }
 
     /// Transfer money between wallets
     /// 
     /// This is the most complex operation - it must:
-    /// 1. Lock BOTH wallets in a consistent order (prevent deadlock)
+    /// 1. Lock ALL involved wallets in a consistent order (prevent deadlock)
     /// 2. Validate sender has enough balance
-    /// 3. Update both balances
-    /// 4. Create TWO transaction records
+    /// 3. Update balances
+    /// 4. Create TWO transaction records (four with a round-up)
     /// 5. All in a single database transaction
     /// 
     /// Deadlock prevention:
@@ -172,12 +684,38 @@ impl WalletRepository {
     /// - If thread A locks wallet-1 then wallet-2
     /// - And thread B locks wallet-1 then wallet-2 (same order)
     /// - No circular wait = no deadlock
+    /// 
+    /// Round-ups:
+    /// - If the sender has a round-up rule, the difference to the next
+    ///   increment moves to their savings wallet in the same transaction
+    /// - Skipped (not an error) when the balance can't cover it
+    ///
+    /// Velocity limits (see velocity.rs) are counted after the sender is
+    /// locked, so concurrent transfers from one wallet can't both squeeze
+    /// under them
+    ///
+    /// A sharded recipient unsharded meanwhile is a conflict, retried (see
+    /// LockRetry) like any other
     pub async fn transfer(
         &self,
         from_wallet_id: &str,
         to_wallet_id: &str,
         amount: Decimal,
-    ) -> WalletResult<(WalletTransaction, WalletTransaction)> {
+        memo: Option<&str>,
+    ) -> WalletResult<TransferOutcome> {
+        self.transfer_expecting(from_wallet_id, to_wallet_id, amount, memo, None)
+            .await
+    }
+
+    /// `transfer`, but only if the sender is still at `expected_version`
+    pub async fn transfer_expecting(
+        &self,
+        from_wallet_id: &str,
+        to_wallet_id: &str,
+        amount: Decimal,
+        memo: Option<&str>,
+        expected_version: Option<i64>,
+    ) -> WalletResult<TransferOutcome> {
         // Validate amount
         if amount <= Decimal::ZERO {
             return Err(WalletError::InvalidAmount(
@@ -192,144 +730,3503 @@ impl WalletRepository {
             ));
         }
 
+        self.retry_on_conflict(|| {
+            self.try_transfer(from_wallet_id, to_wallet_id, amount, memo, expected_version)
+        })
+        .await
+    }
+
+    async fn try_transfer(
+        &self,
+        from_wallet_id: &str,
+        to_wallet_id: &str,
+        amount: Decimal,
+        memo: Option<&str>,
+        expected_version: Option<i64>,
+    ) -> WalletResult<TransferOutcome> {
         // Start transaction
         let mut tx = self.pool.begin().await?;
 
+        // A round-up rule pulls the savings wallet into the transaction
+        let round_up_rule = self.find_round_up_rule_in_tx(&mut tx, from_wallet_id).await?;
+
         // Lock wallets in consistent order to prevent deadlock
-        let (first_id, second_id) = if from_wallet_id < to_wallet_id {
-            (from_wallet_id, to_wallet_id)
-        } else {
-            (to_wallet_id, from_wallet_id)
-        };
+        let mut lock_order = vec![from_wallet_id, to_wallet_id];
+        if let Some(rule) = &round_up_rule {
+            lock_order.push(&rule.savings_wallet_id);
+        }
+        lock_order.sort();
+        lock_order.dedup();
+
+        // A sharded recipient (see set_balance_shards) isn't locked: the
+        // credit goes to one of its shards, so transfers into it don't
+        // queue behind each other. Not when it also takes a round-up
+        let shard_credit = self.balance_shards_in_tx(&mut tx, to_wallet_id).await? > 0
+            && round_up_rule
+                .as_ref()
+                .is_none_or(|rule| rule.savings_wallet_id != to_wallet_id);
+        let mut recipient_shards = 0;
 
-        // Lock both wallets with SELECT ... FOR UPDATE
+        // Lock every wallet with SELECT ... FOR UPDATE
         // This ensures no one else can modify them until we commit
-        let from_wallet = self.lock_wallet_in_tx(&mut tx, first_id).await?;
-        let to_wallet = self.lock_wallet_in_tx(&mut tx, second_id).await?;
+        let mut wallets = HashMap::new();
+        for wallet_id in lock_order {
+            let wallet = if shard_credit && wallet_id == to_wallet_id {
+                let (wallet, shards) = self.share_sharded_wallet_in_tx(&mut tx, wallet_id).await?;
+                recipient_shards = shards;
+                wallet
+            } else {
+                self.lock_wallet_in_tx(&mut tx, wallet_id).await?
+            };
+            wallets.insert(wallet.id.clone(), wallet);
+        }
+        check_version(&wallets[from_wallet_id], expected_version)?;
 
-        // Figure out which is which (we may have locked them in reverse order)
-        let (mut from_wallet, mut to_wallet) = if from_wallet.id == from_wallet_id {
-            (from_wallet, to_wallet)
-        } else {
-            (to_wallet, from_wallet)
-        };
+        // Pots only exchange money with their parent (see move_to_pot)
+        // The savings wallet of a round-up MAY be a pot
+        if wallets[from_wallet_id].parent_wallet_id.is_some()
+            || wallets[to_wallet_id].parent_wallet_id.is_some()
+        {
+            return Err(WalletError::InvalidRequest(
+                "Pots can't send or receive transfers; move money via the parent wallet".to_string(),
+            ));
+        }
+
+        // Neither side (wallet or owner) may be on the blocklist
+        self.check_blocklist_in_tx(&mut tx, &wallets[from_wallet_id], &wallets[to_wallet_id])
+            .await?;
+
+        // KYC caps: on what an unverified sender sends, and on what an
+        // unverified recipient holds (moving between one's own wallets
+        // changes neither)
+        let sender = &wallets[from_wallet_id].user_id;
+        let recipient = &wallets[to_wallet_id].user_id;
+        if sender != recipient {
+            self.check_kyc_transfer_in_tx(&mut tx, sender, amount).await?;
+            self.check_kyc_credit_in_tx(&mut tx, recipient, amount).await?;
+        }
+
+        // Velocity limits - what the sender already sent in the window
+        self.check_velocity_in_tx(&mut tx, from_wallet_id, amount).await?;
 
         // Check sufficient balance
-        if from_wallet.balance < amount {
+        // The parent's balance is already net of its pots, so it IS the spendable amount
+        let available = wallets[from_wallet_id].balance;
+        if available < amount {
             return Err(WalletError::InsufficientBalance {
                 required: amount,
-                available: from_wallet.balance,
+                available,
             });
         }
 
         // Calculate new balances
-        from_wallet.balance -= amount;
-        to_wallet.balance += amount;
+        let mut changed = vec![from_wallet_id.to_string(), to_wallet_id.to_string()];
+        if let Some(w) = wallets.get_mut(from_wallet_id) {
+            w.balance -= amount;
+        }
+        if let Some(w) = wallets.get_mut(to_wallet_id) {
+            w.balance += amount;
+        }
 
-        // Update both wallets
-        sqlx::query(
-            r#"
-            UPDATE wallets
-            SET balance = $1, version = version + 1
-            WHERE id = $2
-            "#,
-        )
-        .bind(from_wallet.balance)
-        .bind(&from_wallet.id)
-        .execute(&mut *tx)
-        .await?;
+        // Work out the round-up (if the sender can still afford it)
+        let round_up = round_up_rule.and_then(|rule| {
+            let difference = rule.difference_for(amount);
+            (difference > Decimal::ZERO && wallets[from_wallet_id].balance >= difference)
+                .then_some((rule.savings_wallet_id, difference))
+        });
 
-        sqlx::query(
-            r#"
-            UPDATE wallets
-            SET balance = $1, version = version + 1
-            WHERE id = $2
-            "#,
-        )
-        .bind(to_wallet.balance)
-        .bind(&to_wallet.id)
-        .execute(&mut *tx)
-        .await?;
+        if let Some((savings_wallet_id, difference)) = &round_up {
+            if let Some(w) = wallets.get_mut(from_wallet_id) {
+                w.balance -= *difference;
+            }
+            if let Some(w) = wallets.get_mut(savings_wallet_id) {
+                w.balance += *difference;
+            }
+            if !changed.contains(savings_wallet_id) {
+                changed.push(savings_wallet_id.clone());
+            }
+        }
+
+        // Update every wallet whose balance changed (a shard, for a sharded
+        // recipient)
+        let recipient_shard = if shard_credit {
+            changed.retain(|wallet_id| wallet_id != to_wallet_id);
+            Some(
+                self.credit_shard_in_tx(&mut tx, to_wallet_id, recipient_shards, amount)
+                    .await?,
+            )
+        } else {
+            None
+        };
+        for wallet_id in &changed {
+            sqlx::query!(
+                r#"
+                UPDATE wallets
+                SET balance = $1, version = version + 1
+                WHERE id = $2
+                "#,
+                wallets[wallet_id].balance,
+                wallet_id
+            )
+            .execute(&mut *tx)
+            .await?;
+        }
 
-        // Create a reference ID to link these two transactions
+        // Create a reference ID to link these transactions
         let reference_id = Uuid::new_v4().to_string();
 
         // Record outgoing transaction
         let out_transaction = self
             .create_transaction_in_tx(
                 &mut tx,
-                &from_wallet.id,
+                from_wallet_id,
                 amount,
                 TransactionType::TransferOut,
                 TransactionStatus::Completed,
                 Some(&reference_id),
+                memo,
             )
             .await?;
 
         // Record incoming transaction
         let in_transaction = self
-            .create_transaction_in_tx(
+            .create_shard_transaction_in_tx(
                 &mut tx,
-                &to_wallet.id,
+                to_wallet_id,
+                recipient_shard,
                 amount,
                 TransactionType::TransferIn,
                 TransactionStatus::Completed,
                 Some(&reference_id),
+                memo,
             )
             .await?;
 
+        // Record the round-up legs (same reference - they belong to this transfer)
+        let round_up = match round_up {
+            Some((savings_wallet_id, difference)) => {
+                let out_transaction = self
+                    .create_transaction_in_tx(
+                        &mut tx,
+                        from_wallet_id,
+                        difference,
+                        TransactionType::RoundUpOut,
+                        TransactionStatus::Completed,
+                        Some(&reference_id),
+                        None,
+                    )
+                    .await?;
+
+                let in_transaction = self
+                    .create_transaction_in_tx(
+                        &mut tx,
+                        &savings_wallet_id,
+                        difference,
+                        TransactionType::RoundUpIn,
+                        TransactionStatus::Completed,
+                        Some(&reference_id),
+                        None,
+                    )
+                    .await?;
+
+                Some(RoundUpOutcome {
+                    savings_user_id: wallets[&savings_wallet_id].user_id.clone(),
+                    savings_wallet_id,
+                    out_transaction,
+                    in_transaction,
+                })
+            }
+            None => None,
+        };
+
         // Commit everything
         tx.commit().await?;
+        self.invalidate_cached(wallets.values()).await;
 
-        Ok((out_transaction, in_transaction))
+        Ok(TransferOutcome {
+            out_transaction,
+            in_transaction,
+            round_up,
+        })
     }
 
-    // === Helper methods for working within transactions ===
+    // === Beneficiaries (saved contacts) ===
 
-    /// Find wallet within an existing transaction
-    async fn find_by_id_in_tx(
+    /// Save a named recipient for a user
+    ///
+    /// Business rules:
+    /// - Exactly one of wallet_id / beneficiary_user_id must be given
+    /// - A wallet reference must point at an existing wallet
+    /// - Names are unique per owner (DuplicateBeneficiary otherwise)
+    pub async fn create_beneficiary(
         &self,
-        tx: &mut Transaction<'_, Postgres>,
-        wallet_id: &str,
-    ) -> WalletResult<Wallet> {
-        let wallet = sqlx::query_as::<_, Wallet>(
+        user_id: &str,
+        name: &str,
+        wallet_id: Option<&str>,
+        beneficiary_user_id: Option<&str>,
+    ) -> WalletResult<Beneficiary> {
+        if name.trim().is_empty() {
+            return Err(WalletError::InvalidRequest(
+                "Beneficiary name must not be empty".to_string(),
+            ));
+        }
+
+        match (wallet_id, beneficiary_user_id) {
+            (Some(wallet_id), None) => {
+                // Fail early with a 404 rather than a foreign key violation
+                self.find_by_id(wallet_id).await?;
+            }
+            (None, Some(_)) => {}
+            _ => {
+                return Err(WalletError::InvalidRequest(
+                    "Provide exactly one of wallet_id or beneficiary_user_id".to_string(),
+                ));
+            }
+        }
+
+        let beneficiary_id = Uuid::new_v4().to_string();
+
+        let beneficiary = sqlx::query_as!(
+            Beneficiary,
             r#"
-            SELECT id, user_id, balance, version, created_at, updated_at
-            FROM wallets
-            WHERE id = $1
+            INSERT INTO beneficiaries
+                (id, user_id, name, name_hash, wallet_id, beneficiary_user_id, created_at)
+            VALUES ($1, $2, $3, $4, $5, $6, $7)
+            RETURNING id, user_id, name, wallet_id, beneficiary_user_id, created_at
             "#,
+            &beneficiary_id,
+            user_id,
+            self.cipher.seal(name)?,
+            // Names are encrypted; uniqueness per user is enforced on this
+            self.cipher.blind_index(name),
+            wallet_id,
+            beneficiary_user_id,
+            Utc::now()
         )
-        .bind(wallet_id)
-        .fetch_optional(&mut **tx)
-        .await?
-        .ok_or_else(|| WalletError::WalletNotFound(wallet_id.to_string()))?;
+        .fetch_one(&self.pool)
+        .await
+        .map_err(|e| match e {
+            sqlx::Error::Database(ref db) if db.is_unique_violation() => {
+                WalletError::DuplicateBeneficiary(name.to_string())
+            }
+            e => WalletError::DatabaseError(e),
+        })?;
 
-        Ok(wallet)
+        self.open(beneficiary)
     }
 
-    /// Lock a wallet for update (prevents concurrent modifications)
-    async fn lock_wallet_in_tx(
-        &self,
-        tx: &mut Transaction<'_, Postgres>,
-        wallet_id: &str,
-    ) -> WalletResult<Wallet> {
-        let wallet = sqlx::query_as::<_, Wallet>(
+    /// List all beneficiaries saved by a user
+    pub async fn find_beneficiaries(&self, user_id: &str) -> WalletResult<Vec<Beneficiary>> {
+        let beneficiaries = sqlx::query_as!(
+            Beneficiary,
             r#"
-            SELECT id, user_id, balance, version, created_at, updated_at
-            FROM wallets
-            WHERE id = $1
-            FOR UPDATE  -- This is the lock!
+            SELECT id, user_id, name, wallet_id, beneficiary_user_id, created_at
+            FROM beneficiaries
+            WHERE user_id = $1
             "#,
+            user_id
+        )
+        .fetch_all(self.reader())
+        .await?;
+
+        // By name - which only exists once decrypted
+        let mut beneficiaries = self.open(beneficiaries)?;
+        beneficiaries.sort_by(|a, b| a.name.cmp(&b.name));
+        Ok(beneficiaries)
+    }
+
+    /// Delete a beneficiary owned by a user
+    ///
+    /// Scoped by user_id so one user can't delete another user's contacts
+    pub async fn delete_beneficiary(&self, user_id: &str, beneficiary_id: &str) -> WalletResult<()> {
+        let rows_affected = sqlx::query!(
+            r#"
+            DELETE FROM beneficiaries
+            WHERE id = $1 AND user_id = $2
+            "#,
+            beneficiary_id,
+            user_id
+        )
+        .execute(&self.pool)
+        .await?
+        .rows_affected();
+
+        if rows_affected == 0 {
+            return Err(WalletError::BeneficiaryNotFound(beneficiary_id.to_string()));
+        }
+
+        Ok(())
+    }
+
+    /// Resolve a beneficiary to the wallet ID money should be sent to
+    ///
+    /// - Wallet references resolve to that wallet
+    /// - User references resolve to the user's oldest wallet
+    pub async fn resolve_beneficiary(
+        &self,
+        user_id: &str,
+        beneficiary_id: &str,
+    ) -> WalletResult<String> {
+        let beneficiary = sqlx::query_as!(
+            Beneficiary,
+            r#"
+            SELECT id, user_id, name, wallet_id, beneficiary_user_id, created_at
+            FROM beneficiaries
+            WHERE id = $1 AND user_id = $2
+            "#,
+            beneficiary_id,
+            user_id
+        )
+        .fetch_optional(&self.pool)
+        .await?
+        .ok_or_else(|| WalletError::BeneficiaryNotFound(beneficiary_id.to_string()))?;
+
+        if let Some(wallet_id) = beneficiary.wallet_id {
+            return Ok(wallet_id);
+        }
+
+        let recipient_user_id = beneficiary.beneficiary_user_id.unwrap_or_default();
+
+        let wallet_id = sqlx::query_scalar!(
+            r#"
+            SELECT id
+            FROM wallets
+            WHERE user_id = $1 AND parent_wallet_id IS NULL
+            ORDER BY created_at ASC
+            LIMIT 1
+            "#,
+            &recipient_user_id
+        )
+        .fetch_optional(&self.pool)
+        .await?
+        .ok_or_else(|| {
+            WalletError::WalletNotFound(format!("no wallet for user {}", recipient_user_id))
+        })?;
+
+        Ok(wallet_id)
+    }
+
+    // === Transfer templates ===
+
+    /// Save a transfer template on a source wallet
+    ///
+    /// Business rules:
+    /// - Source wallet must exist
+    /// - Exactly one of to_wallet_id / beneficiary_id must be given
+    /// - Default amount must be positive
+    pub async fn create_template(
+        &self,
+        wallet_id: &str,
+        name: &str,
+        to_wallet_id: Option<&str>,
+        beneficiary_id: Option<&str>,
+        amount: Decimal,
+        memo: Option<&str>,
+    ) -> WalletResult<TransferTemplate> {
+        if amount <= Decimal::ZERO {
+            return Err(WalletError::InvalidAmount(
+                "Template amount must be positive".to_string(),
+            ));
+        }
+
+        let wallet = self.find_by_id(wallet_id).await?;
+
+        match (to_wallet_id, beneficiary_id) {
+            (Some(to_wallet_id), None) => {
+                self.find_by_id(to_wallet_id).await?;
+            }
+            (None, Some(beneficiary_id)) => {
+                // Must be one of the wallet owner's beneficiaries
+                self.resolve_beneficiary(&wallet.user_id, beneficiary_id)
+                    .await?;
+            }
+            _ => {
+                return Err(WalletError::InvalidRequest(
+                    "Provide exactly one of to_wallet_id or beneficiary_id".to_string(),
+                ));
+            }
+        }
+
+        let template_id = Uuid::new_v4().to_string();
+
+        let template = sqlx::query_as!(
+            TransferTemplate,
+            r#"
+            INSERT INTO transfer_templates (id, wallet_id, name, to_wallet_id, beneficiary_id, amount, memo, created_at)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+            RETURNING id, wallet_id, name, to_wallet_id, beneficiary_id, amount, memo, created_at
+            "#,
+            &template_id,
+            wallet_id,
+            name,
+            to_wallet_id,
+            beneficiary_id,
+            amount,
+            self.cipher.seal_opt(memo)?,
+            Utc::now()
+        )
+        .fetch_one(&self.pool)
+        .await?;
+
+        self.open(template)
+    }
+
+    /// Find a transfer template by ID
+    pub async fn find_template(&self, template_id: &str) -> WalletResult<TransferTemplate> {
+        let template = sqlx::query_as!(
+            TransferTemplate,
+            r#"
+            SELECT id, wallet_id, name, to_wallet_id, beneficiary_id, amount, memo, created_at
+            FROM transfer_templates
+            WHERE id = $1
+            "#,
+            template_id
+        )
+        .fetch_optional(self.reader())
+        .await?
+        .ok_or_else(|| WalletError::TemplateNotFound(template_id.to_string()))?;
+
+        self.open(template)
+    }
+
+    /// List all templates saved on a wallet
+    pub async fn find_templates(&self, wallet_id: &str) -> WalletResult<Vec<TransferTemplate>> {
+        let templates = sqlx::query_as!(
+            TransferTemplate,
+            r#"
+            SELECT id, wallet_id, name, to_wallet_id, beneficiary_id, amount, memo, created_at
+            FROM transfer_templates
+            WHERE wallet_id = $1
+            ORDER BY name
+            "#,
+            wallet_id
+        )
+        .fetch_all(self.reader())
+        .await?;
+
+        self.open(templates)
+    }
+
+    /// Delete a transfer template
+    pub async fn delete_template(&self, template_id: &str) -> WalletResult<()> {
+        let rows_affected = sqlx::query!(
+            "DELETE FROM transfer_templates WHERE id = $1",
+            template_id
+        )
+        .execute(&self.pool)
+        .await?
+        .rows_affected();
+
+        if rows_affected == 0 {
+            return Err(WalletError::TemplateNotFound(template_id.to_string()));
+        }
+
+        Ok(())
+    }
+
+    // === Pots (sub-wallets) ===
+
+    /// Create a pot under a wallet
+    ///
+    /// Business rules:
+    /// - Parent must exist and must not itself be a pot (one level only)
+    /// - The pot belongs to the same user and starts empty
+    pub async fn create_pot(&self, parent_wallet_id: &str, name: &str) -> WalletResult<Wallet> {
+        if name.trim().is_empty() {
+            return Err(WalletError::InvalidRequest(
+                "Pot name must not be empty".to_string(),
+            ));
+        }
+
+        let parent = self.find_by_id(parent_wallet_id).await?;
+        if parent.parent_wallet_id.is_some() {
+            return Err(WalletError::InvalidRequest(
+                "Pots can't contain other pots".to_string(),
+            ));
+        }
+
+        let pot_id = Uuid::new_v4().to_string();
+        let now = Utc::now();
+
+        let pot = sqlx::query_as!(
+            Wallet,
+            r#"
+            INSERT INTO wallets (id, user_id, balance, version, parent_wallet_id, nickname, created_at, updated_at)
+            VALUES ($1, $2, 0, 0, $3, $4, $5, $5)
+            RETURNING id, user_id, balance, version, parent_wallet_id, nickname, labels, is_default,
+                      created_at, updated_at
+            "#,
+            &pot_id,
+            &parent.user_id,
+            parent_wallet_id,
+            self.cipher.seal(name)?,
+            now
+        )
+        .fetch_one(&self.pool)
+        .await?;
+        self.invalidate_cached_keys(&[CacheKey::UserWallets(&parent.user_id)]).await;
+
+        self.open(pot)
+    }
+
+    /// List the pots under a wallet
+    pub async fn find_pots(&self, parent_wallet_id: &str) -> WalletResult<Vec<Wallet>> {
+        let pots = sqlx::query_as!(
+            Wallet,
+            r#"
+            SELECT id, user_id, total_balance(wallets) AS "balance!", version, parent_wallet_id,
+                   nickname, labels, is_default, created_at, updated_at
+            FROM wallets
+            WHERE parent_wallet_id = $1
+            ORDER BY created_at ASC
+            "#,
+            parent_wallet_id
+        )
+        .fetch_all(self.reader())
+        .await?;
+
+        self.open(pots)
+    }
+
+    /// Move spendable money from a wallet into one of its pots
+    pub async fn move_to_pot(
+        &self,
+        parent_wallet_id: &str,
+        pot_id: &str,
+        amount: Decimal,
+    ) -> WalletResult<(WalletTransaction, WalletTransaction)> {
+        self.move_between_pot(parent_wallet_id, pot_id, amount, true, None)
+            .await
+    }
+
+    /// Move money from a pot back into its parent's spendable balance
+    pub async fn move_from_pot(
+        &self,
+        parent_wallet_id: &str,
+        pot_id: &str,
+        amount: Decimal,
+    ) -> WalletResult<(WalletTransaction, WalletTransaction)> {
+        self.move_between_pot(parent_wallet_id, pot_id, amount, false, None)
+            .await
+    }
+
+    /// A pot move, but only if the parent is still at `expected_version`
+    pub async fn move_pot_expecting(
+        &self,
+        parent_wallet_id: &str,
+        pot_id: &str,
+        amount: Decimal,
+        into_pot: bool,
+        expected_version: Option<i64>,
+    ) -> WalletResult<(WalletTransaction, WalletTransaction)> {
+        self.move_between_pot(parent_wallet_id, pot_id, amount, into_pot, expected_version)
+            .await
+    }
+
+    /// Shared implementation of pot moves
+    ///
+    /// Same locking discipline as transfers: both rows locked in ID order,
+    /// both legs recorded under one reference, all in one DB transaction.
+    /// Returns (debit leg, credit leg).
+    async fn move_between_pot(
+        &self,
+        parent_wallet_id: &str,
+        pot_id: &str,
+        amount: Decimal,
+        into_pot: bool,
+        expected_version: Option<i64>,
+    ) -> WalletResult<(WalletTransaction, WalletTransaction)> {
+        if amount <= Decimal::ZERO {
+            return Err(WalletError::InvalidAmount(
+                "Amount must be positive".to_string(),
+            ));
+        }
+
+        let mut tx = self.pool.begin().await?;
+
+        // Lock in consistent order to prevent deadlock with transfers
+        let (first_id, second_id) = if parent_wallet_id < pot_id {
+            (parent_wallet_id, pot_id)
+        } else {
+            (pot_id, parent_wallet_id)
+        };
+        let first = self.lock_wallet_in_tx(&mut tx, first_id).await?;
+        let second = self.lock_wallet_in_tx(&mut tx, second_id).await?;
+        let (parent, pot) = if first.id == parent_wallet_id {
+            (first, second)
+        } else {
+            (second, first)
+        };
+
+        if pot.parent_wallet_id.as_deref() != Some(parent_wallet_id) {
+            return Err(WalletError::PotNotFound(pot_id.to_string()));
+        }
+        check_version(&parent, expected_version)?;
+
+        let (mut from, mut to) = if into_pot { (parent, pot) } else { (pot, parent) };
+
+        if from.balance < amount {
+            return Err(WalletError::InsufficientBalance {
+                required: amount,
+                available: from.balance,
+            });
+        }
+
+        from.balance -= amount;
+        to.balance += amount;
+
+        for wallet in [&from, &to] {
+            sqlx::query!(
+                r#"
+                UPDATE wallets
+                SET balance = $1, version = version + 1
+                WHERE id = $2
+                "#,
+                wallet.balance,
+                &wallet.id
+            )
+            .execute(&mut *tx)
+            .await?;
+        }
+
+        let reference_id = Uuid::new_v4().to_string();
+
+        let out_transaction = self
+            .create_transaction_in_tx(
+                &mut tx,
+                &from.id,
+                amount,
+                TransactionType::PotTransferOut,
+                TransactionStatus::Completed,
+                Some(&reference_id),
+                None,
+            )
+            .await?;
+
+        let in_transaction = self
+            .create_transaction_in_tx(
+                &mut tx,
+                &to.id,
+                amount,
+                TransactionType::PotTransferIn,
+                TransactionStatus::Completed,
+                Some(&reference_id),
+                None,
+            )
+            .await?;
+
+        tx.commit().await?;
+        self.invalidate_cached([&from, &to]).await;
+
+        Ok((out_transaction, in_transaction))
+    }
+
+    // === Vouchers (gift codes) ===
+
+    /// Mint a single-use voucher worth `amount`
+    /// 
+    /// Codes are 16 characters from an alphabet without look-alikes
+    /// (no 0/O, 1/I/L), so they survive being read out over the phone.
+    pub async fn create_voucher(&self, amount: Decimal, created_by: &str) -> WalletResult<Voucher> {
+        if amount <= Decimal::ZERO {
+            return Err(WalletError::InvalidAmount(
+                "Amount must be positive".to_string(),
+            ));
+        }
+
+        let mut tx = self.pool.begin().await?;
+
+        let voucher = sqlx::query_as!(
+            Voucher,
+            r#"
+            INSERT INTO vouchers (id, code, amount, created_by, created_at)
+            VALUES ($1, $2, $3, $4, $5)
+            RETURNING id, code, amount, created_by, created_at, redeemed_at, wallet_id, transaction_id
+            "#,
+            Uuid::new_v4().to_string(),
+            generate_voucher_code(),
+            amount,
+            created_by,
+            Utc::now()
+        )
+        .fetch_one(&mut *tx)
+        .await?;
+
+        // Never the code - the log is read by more people than could spend it
+        self.record_admin_action_in_tx(
+            &mut tx,
+            AuditRecord {
+                actor: created_by,
+                action: AUDIT_VOUCHER_MINTED,
+                target_type: "voucher",
+                target_id: &voucher.id,
+                before: None,
+                after: Some(json!({"amount": voucher.amount})),
+            },
+        )
+        .await?;
+
+        tx.commit().await?;
+
+        Ok(voucher)
+    }
+
+    /// Redeem a voucher into a wallet
+    /// 
+    /// Atomicity:
+    /// - The voucher row is claimed with `WHERE redeemed_at IS NULL`, so two
+    ///   concurrent redemptions can't both succeed - the loser updates 0 rows
+    /// - Claiming, funding and recording happen in ONE DB transaction
+    /// 
+    /// Codes are matched case-insensitively, ignoring spaces and dashes.
+    pub async fn redeem_voucher(
+        &self,
+        wallet_id: &str,
+        code: &str,
+    ) -> WalletResult<(Wallet, WalletTransaction, Voucher)> {
+        let code: String = code
+            .chars()
+            .filter(|c| !c.is_whitespace() && *c != '-')
+            .collect::<String>()
+            .to_uppercase();
+
+        let mut tx = self.pool.begin().await?;
+
+        let wallet = self.lock_wallet_in_tx(&mut tx, wallet_id).await?;
+
+        // Money only enters a pot through its parent
+        if wallet.parent_wallet_id.is_some() {
+            return Err(WalletError::InvalidRequest(
+                "Vouchers can't be redeemed into a pot".to_string(),
+            ));
+        }
+
+        let now = Utc::now();
+
+        let claimed = sqlx::query_as!(
+            Voucher,
+            r#"
+            UPDATE vouchers
+            SET redeemed_at = $2, wallet_id = $3
+            WHERE code = $1 AND redeemed_at IS NULL
+            RETURNING id, code, amount, created_by, created_at, redeemed_at, wallet_id, transaction_id
+            "#,
+            &code,
+            now,
+            wallet_id
+        )
+        .fetch_optional(&mut *tx)
+        .await?;
+
+        let Some(mut voucher) = claimed else {
+            // Tell "used" apart from "never existed"
+            let exists = sqlx::query_scalar!(
+                r#"SELECT EXISTS (SELECT 1 FROM vouchers WHERE code = $1) AS "exists!""#,
+                &code
+            )
+            .fetch_one(&mut *tx)
+            .await?;
+
+            return Err(if exists {
+                WalletError::VoucherAlreadyRedeemed
+            } else {
+                WalletError::VoucherNotFound
+            });
+        };
+
+        // Unverified users' balances are capped (the claim rolls back with it)
+        self.check_kyc_credit_in_tx(&mut tx, &wallet.user_id, voucher.amount)
+            .await?;
+
+        sqlx::query!(
+            r#"
+            UPDATE wallets
+            SET balance = $1, version = version + 1
+            WHERE id = $2
+            "#,
+            wallet.balance + voucher.amount,
+            wallet_id
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        // reference_id = voucher ID, linking the credit back to the code
+        let transaction = self
+            .create_transaction_in_tx(
+                &mut tx,
+                wallet_id,
+                voucher.amount,
+                TransactionType::VoucherRedeem,
+                TransactionStatus::Completed,
+                Some(&voucher.id),
+                None,
+            )
+            .await?;
+
+        sqlx::query!(
+            "UPDATE vouchers SET transaction_id = $1 WHERE id = $2",
+            &transaction.id,
+            &voucher.id
+        )
+        .execute(&mut *tx)
+        .await?;
+        voucher.transaction_id = Some(transaction.id.clone());
+
+        tx.commit().await?;
+        self.invalidate_cached([&wallet]).await;
+
+        let updated_wallet = self.find_by_id(wallet_id).await?;
+
+        Ok((updated_wallet, transaction, voucher))
+    }
+
+    // === Admin adjustments ===
+
+    /// Credit or debit a wallet by hand, recording who did it and why
+    ///
+    /// One DB transaction:
+    /// 1. Lock the wallet (a debit can't take it below zero)
+    /// 2. Update the balance and bump the version
+    /// 3. Record an ADJUSTMENT transaction (signed: a debit is negative)
+    /// 4. Record the adjustment itself (reason code, actor, note)
+    pub async fn adjust_balance(
+        &self,
+        wallet_id: &str,
+        request: &AdjustmentRequest,
+        actor: &str,
+    ) -> WalletResult<(Wallet, WalletAdjustment)> {
+        if request.amount <= Decimal::ZERO {
+            return Err(WalletError::InvalidAmount(
+                "Amount must be positive".to_string(),
+            ));
+        }
+
+        let mut tx = self.pool.begin().await?;
+
+        let wallet = self.lock_wallet_in_tx(&mut tx, wallet_id).await?;
+
+        // A pot's money is part of its parent's story - correct the parent
+        if wallet.parent_wallet_id.is_some() {
+            return Err(WalletError::InvalidRequest(
+                "Pots can't be adjusted; adjust the parent wallet".to_string(),
+            ));
+        }
+
+        let signed_amount = match request.direction {
+            AdjustmentDirection::Credit => request.amount,
+            AdjustmentDirection::Debit => -request.amount,
+        };
+        if wallet.balance + signed_amount < Decimal::ZERO {
+            return Err(WalletError::InsufficientBalance {
+                required: request.amount,
+                available: wallet.balance,
+            });
+        }
+
+        sqlx::query!(
+            r#"
+            UPDATE wallets
+            SET balance = $1, version = version + 1
+            WHERE id = $2
+            "#,
+            wallet.balance + signed_amount,
+            wallet_id
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        let transaction = self
+            .create_transaction_in_tx(
+                &mut tx,
+                wallet_id,
+                signed_amount,
+                TransactionType::Adjustment,
+                TransactionStatus::Completed,
+                None,
+                None,
+            )
+            .await?;
+
+        let adjustment = sqlx::query_as!(
+            WalletAdjustment,
+            r#"
+            INSERT INTO wallet_adjustments
+                (id, wallet_id, transaction_id, direction, amount, reason_code, actor, note, created_at)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)
+            RETURNING id, wallet_id, transaction_id, direction AS "direction: AdjustmentDirection",
+                      amount, reason_code, actor, note, created_at
+            "#,
+            Uuid::new_v4().to_string(),
+            wallet_id,
+            &transaction.id,
+            request.direction.to_string(),
+            request.amount,
+            &request.reason_code,
+            actor,
+            request.note.as_deref(),
+            transaction.created_at
+        )
+        .fetch_one(&mut *tx)
+        .await?;
+
+        let before = json!({"balance": wallet.balance, "version": wallet.version});
+        let after = json!({
+            "balance": wallet.balance + signed_amount,
+            "version": wallet.version + 1,
+            "adjustment": &adjustment,
+        });
+        self.record_admin_action_in_tx(
+            &mut tx,
+            AuditRecord {
+                actor,
+                action: AUDIT_WALLET_ADJUSTED,
+                target_type: "wallet",
+                target_id: wallet_id,
+                before: Some(before),
+                after: Some(after),
+            },
+        )
+        .await?;
+
+        tx.commit().await?;
+        self.invalidate_cached([&wallet]).await;
+
+        let updated_wallet = self.find_by_id(wallet_id).await?;
+
+        Ok((updated_wallet, adjustment))
+    }
+
+    // === Balance shards (hot wallets) ===
+
+    /// How a wallet's balance is split over its shards
+    pub async fn find_balance_shards(&self, wallet_id: &str) -> WalletResult<BalanceShards> {
+        let row = sqlx::query!(
+            r#"
+            SELECT w.balance_shards, w.balance,
+                   COALESCE(array_agg(s.balance ORDER BY s.shard) FILTER (WHERE s.shard IS NOT NULL), '{}')
+                       AS "shard_balances!"
+            FROM wallets w
+            LEFT JOIN wallet_balance_shards s ON s.wallet_id = w.id
+            WHERE w.id = $1
+            GROUP BY w.id
+            "#,
+            wallet_id
+        )
+        .fetch_optional(self.reader())
+        .await?
+        .ok_or_else(|| WalletError::WalletNotFound(wallet_id.to_string()))?;
+
+        Ok(BalanceShards {
+            wallet_id: wallet_id.to_string(),
+            shards: row.balance_shards,
+            balance: row.balance + row.shard_balances.iter().sum::<Decimal>(),
+            row_balance: row.balance,
+            shard_balances: row.shard_balances,
+        })
+    }
+
+    /// Split a hot wallet's balance over `shards` rows (0: back to one)
+    ///
+    /// One DB transaction:
+    /// 1. Lock the wallet, folding any current shards into its row
+    /// 2. Drop the shard rows past the new count, add the missing ones (at
+    ///    zero)
+    /// 3. Record the change in the audit log
+    ///
+    /// The balance doesn't change, so neither does the version.
+    pub async fn set_balance_shards(
+        &self,
+        wallet_id: &str,
+        shards: i32,
+        actor: &str,
+    ) -> WalletResult<BalanceShards> {
+        if !(0..=MAX_BALANCE_SHARDS).contains(&shards) {
+            return Err(WalletError::InvalidRequest(format!(
+                "shards must be 0 to {}",
+                MAX_BALANCE_SHARDS
+            )));
+        }
+
+        let mut tx = self.pool.begin().await?;
+
+        let wallet = self.lock_wallet_in_tx(&mut tx, wallet_id).await?;
+        // Pots only move money to and from their parent - never hot
+        if wallet.parent_wallet_id.is_some() {
+            return Err(WalletError::InvalidRequest(
+                "Pots can't be sharded".to_string(),
+            ));
+        }
+        let previous = self.balance_shards_in_tx(&mut tx, wallet_id).await?;
+
+        sqlx::query!(
+            "DELETE FROM wallet_balance_shards WHERE wallet_id = $1 AND shard >= $2",
+            wallet_id,
+            shards
+        )
+        .execute(&mut *tx)
+        .await?;
+        sqlx::query!(
+            r#"
+            INSERT INTO wallet_balance_shards (wallet_id, shard)
+            SELECT $1, generate_series(0, $2 - 1)
+            ON CONFLICT DO NOTHING
+            "#,
+            wallet_id,
+            shards
+        )
+        .execute(&mut *tx)
+        .await?;
+        sqlx::query!("UPDATE wallets SET balance_shards = $1 WHERE id = $2", shards, wallet_id)
+            .execute(&mut *tx)
+            .await?;
+
+        self.record_admin_action_in_tx(
+            &mut tx,
+            AuditRecord {
+                actor,
+                action: AUDIT_BALANCE_SHARDS_CHANGED,
+                target_type: "wallet",
+                target_id: wallet_id,
+                before: Some(json!({"balance_shards": previous})),
+                after: Some(json!({"balance_shards": shards})),
+            },
+        )
+        .await?;
+
+        tx.commit().await?;
+        self.invalidate_cached([&wallet]).await;
+
+        self.find_balance_shards(wallet_id).await
+    }
+
+    // === Round-up savings rules ===
+
+    /// Create or replace the round-up rule for a wallet
+    ///
+    /// Business rules:
+    /// - Increment must be positive (e.g. 1.00 rounds 3.40 up to 4.00)
+    /// - Savings wallet must be a different wallet owned by the same user
+    pub async fn set_round_up_rule(
+        &self,
+        wallet_id: &str,
+        savings_wallet_id: &str,
+        increment: Decimal,
+    ) -> WalletResult<RoundUpRule> {
+        if increment <= Decimal::ZERO {
+            return Err(WalletError::InvalidAmount(
+                "Round-up increment must be positive".to_string(),
+            ));
+        }
+
+        if wallet_id == savings_wallet_id {
+            return Err(WalletError::InvalidRequest(
+                "Savings wallet must differ from the source wallet".to_string(),
+            ));
+        }
+
+        let wallet = self.find_by_id(wallet_id).await?;
+        let savings_wallet = self.find_by_id(savings_wallet_id).await?;
+
+        if wallet.user_id != savings_wallet.user_id {
+            return Err(WalletError::InvalidRequest(
+                "Savings wallet must belong to the same user".to_string(),
+            ));
+        }
+
+        let rule = sqlx::query_as!(
+            RoundUpRule,
+            r#"
+            INSERT INTO round_up_rules (wallet_id, savings_wallet_id, increment, created_at)
+            VALUES ($1, $2, $3, $4)
+            ON CONFLICT (wallet_id)
+            DO UPDATE SET savings_wallet_id = EXCLUDED.savings_wallet_id,
+                          increment = EXCLUDED.increment
+            RETURNING wallet_id, savings_wallet_id, increment, created_at
+            "#,
+            wallet_id,
+            savings_wallet_id,
+            increment,
+            Utc::now()
+        )
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(rule)
+    }
+
+    /// Get the round-up rule configured on a wallet
+    pub async fn find_round_up_rule(&self, wallet_id: &str) -> WalletResult<RoundUpRule> {
+        let rule = sqlx::query_as!(
+            RoundUpRule,
+            r#"
+            SELECT wallet_id, savings_wallet_id, increment, created_at
+            FROM round_up_rules
+            WHERE wallet_id = $1
+            "#,
+            wallet_id
+        )
+        .fetch_optional(self.reader())
+        .await?
+        .ok_or_else(|| WalletError::RoundUpRuleNotFound(wallet_id.to_string()))?;
+
+        Ok(rule)
+    }
+
+    /// Remove the round-up rule from a wallet
+    pub async fn delete_round_up_rule(&self, wallet_id: &str) -> WalletResult<()> {
+        let rows_affected = sqlx::query!(
+            "DELETE FROM round_up_rules WHERE wallet_id = $1",
+            wallet_id
+        )
+        .execute(&self.pool)
+        .await?
+        .rows_affected();
+
+        if rows_affected == 0 {
+            return Err(WalletError::RoundUpRuleNotFound(wallet_id.to_string()));
+        }
+
+        Ok(())
+    }
+
+    // === Transaction PINs ===
+
+    /// Set or change a wallet's PIN
+    ///
+    /// Business rules:
+    /// - Changing an existing PIN needs the current one, checked (and
+    ///   counted towards the lockout) like a transfer's
+    /// - A new PIN clears failed attempts, any lockout and the lockout
+    ///   count
+    pub async fn set_pin(
+        &self,
+        wallet_id: &str,
+        pin: &str,
+        current_pin: Option<&str>,
+        threshold: Decimal,
+        policy: &PinPolicy,
+    ) -> WalletResult<WalletPin> {
+        self.find_by_id(wallet_id).await?;
+        if let Some(existing) = self.find_pin_record(wallet_id).await? {
+            self.verify_pin_record(wallet_id, &existing, current_pin, policy)
+                .await?;
+        }
+
+        let pin = pin.to_string();
+        let pin_hash = tokio::task::spawn_blocking(move || pin::hash_pin(&pin))
+            .await
+            .map_err(|e| WalletError::InternalError(e.to_string()))?
+            .map_err(WalletError::InternalError)?;
+
+        let settings = sqlx::query_as!(
+            WalletPin,
+            r#"
+            INSERT INTO wallet_pins (wallet_id, pin_hash, threshold, updated_at)
+            VALUES ($1, $2, $3, NOW())
+            ON CONFLICT (wallet_id)
+            DO UPDATE SET pin_hash = EXCLUDED.pin_hash,
+                          threshold = EXCLUDED.threshold,
+                          failed_attempts = 0,
+                          lockouts = 0,
+                          locked_until = NULL,
+                          updated_at = EXCLUDED.updated_at
+            RETURNING wallet_id, threshold, failed_attempts, lockouts, locked_until, updated_at
+            "#,
+            wallet_id,
+            pin_hash,
+            threshold
+        )
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(settings)
+    }
+
+    /// A wallet's PIN settings (threshold, lockout) - never the hash
+    pub async fn find_pin(&self, wallet_id: &str) -> WalletResult<WalletPin> {
+        let settings = sqlx::query_as!(
+            WalletPin,
+            r#"
+            SELECT wallet_id, threshold, failed_attempts, lockouts, locked_until, updated_at
+            FROM wallet_pins
+            WHERE wallet_id = $1
+            "#,
+            wallet_id
+        )
+        .fetch_optional(self.reader())
+        .await?
+        .ok_or_else(|| WalletError::PinNotSet(wallet_id.to_string()))?;
+
+        Ok(settings)
+    }
+
+    /// Check the PIN for a transfer of `amount` out of a wallet
+    ///
+    /// Passes straight through when the wallet has no PIN or the amount is
+    /// within its threshold. Otherwise the PIN must be given and right;
+    /// each wrong one counts towards the lockout.
+    pub async fn check_pin(
+        &self,
+        wallet_id: &str,
+        pin: Option<&str>,
+        amount: Decimal,
+        policy: &PinPolicy,
+    ) -> WalletResult<()> {
+        match self.find_pin_record(wallet_id).await? {
+            Some(record) if amount > record.threshold => {
+                self.verify_pin_record(wallet_id, &record, pin, policy).await
+            }
+            _ => Ok(()),
+        }
+    }
+
+    async fn find_pin_record(&self, wallet_id: &str) -> WalletResult<Option<PinRecord>> {
+        let record = sqlx::query_as!(
+            PinRecord,
+            "SELECT pin_hash, threshold, locked_until FROM wallet_pins WHERE wallet_id = $1",
+            wallet_id
+        )
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(record)
+    }
+
+    /// Lockout first, then the PIN itself
+    ///
+    /// The failure count is bumped in one UPDATE, so concurrent guesses
+    /// are all counted; the one that reaches the limit sets the lockout
+    /// (twice as long as the last one, up to `max_lockout`), restarts the
+    /// count for afterwards and comes back as `LockedOut`.
+    async fn verify_pin_record(
+        &self,
+        wallet_id: &str,
+        record: &PinRecord,
+        pin: Option<&str>,
+        policy: &PinPolicy,
+    ) -> WalletResult<()> {
+        if let Some(until) = record.locked_until.filter(|until| *until > Utc::now()) {
+            return Err(WalletError::PinLocked { until });
+        }
+        let pin = pin.ok_or(WalletError::PinRequired)?.to_string();
+
+        let pin_hash = record.pin_hash.clone();
+        let matches = tokio::task::spawn_blocking(move || pin::verify_pin(&pin, &pin_hash))
+            .await
+            .map_err(|e| WalletError::InternalError(e.to_string()))?;
+
+        if matches {
+            sqlx::query!(
+                r#"
+                UPDATE wallet_pins SET failed_attempts = 0, lockouts = 0
+                WHERE wallet_id = $1 AND (failed_attempts > 0 OR lockouts > 0)
+                "#,
+                wallet_id
+            )
+            .execute(&self.pool)
+            .await?;
+            return Ok(());
+        }
+
+        let counted = sqlx::query!(
+            r#"
+            UPDATE wallet_pins
+            SET failed_attempts = CASE WHEN failed_attempts + 1 >= $2 THEN 0
+                                       ELSE failed_attempts + 1 END,
+                lockouts = CASE WHEN failed_attempts + 1 >= $2 THEN lockouts + 1
+                                ELSE lockouts END,
+                locked_until = CASE WHEN failed_attempts + 1 >= $2
+                                    THEN NOW() + make_interval(secs => LEAST(
+                                        $3 * power(2, LEAST(lockouts, 30)), $4))
+                                    ELSE locked_until END
+            WHERE wallet_id = $1
+            RETURNING failed_attempts, lockouts, locked_until
+            "#,
+            wallet_id,
+            policy.max_attempts as i32,
+            policy.lockout.as_secs_f64(),
+            policy.max_lockout.as_secs_f64()
+        )
+        .fetch_one(&self.pool)
+        .await?;
+        let (failed_attempts, lockouts, locked_until) =
+            (counted.failed_attempts, counted.lockouts, counted.locked_until);
+
+        match locked_until.filter(|until| *until > Utc::now()) {
+            // Only the guess that reached the limit restarts the count
+            Some(until) if failed_attempts == 0 => {
+                tracing::warn!(
+                    wallet_id = %wallet_id,
+                    lockouts,
+                    until = %until,
+                    "PIN locked after failed attempts"
+                );
+                Err(WalletError::LockedOut {
+                    factor: "pin",
+                    failed_attempts: policy.max_attempts,
+                    lockouts: lockouts as u32,
+                    until,
+                })
+            }
+            // A concurrent guess got there first
+            Some(until) => Err(WalletError::PinLocked { until }),
+            None => Err(WalletError::IncorrectPin {
+                attempts_left: policy.max_attempts.saturating_sub(failed_attempts as u32),
+            }),
+        }
+    }
+
+    // === Step-up verification ===
+
+    /// Park a transfer until its one-time code is confirmed
+    ///
+    /// Only the code's hash is stored; nothing is debited yet. Refused
+    /// while step-up is locked for the wallet - no new codes to guess.
+    pub async fn create_pending_transfer(
+        &self,
+        from_wallet_id: &str,
+        to_wallet_id: &str,
+        amount: Decimal,
+        memo: Option<&str>,
+        code: &str,
+        ttl: std::time::Duration,
+    ) -> WalletResult<PendingTransfer> {
+        if let Some(until) = self.step_up_locked_until(from_wallet_id).await? {
+            return Err(WalletError::VerificationLocked { until });
+        }
+
+        let pending = sqlx::query_as!(
+            PendingTransfer,
+            r#"
+            INSERT INTO pending_transfers
+                (id, from_wallet_id, to_wallet_id, amount, memo, code_hash, expires_at)
+            VALUES ($1, $2, $3, $4, $5, $6, NOW() + make_interval(secs => $7))
+            RETURNING id, from_wallet_id, to_wallet_id, amount, memo, status,
+                      failed_attempts, expires_at, hold_reason, created_at, resolved_at, reference_id
+            "#,
+            Uuid::new_v4().to_string(),
+            from_wallet_id,
+            to_wallet_id,
+            amount,
+            self.cipher.seal_opt(memo)?,
+            step_up::hash_code(code),
+            ttl.as_secs_f64()
+        )
+        .fetch_one(&self.pool)
+        .await?;
+
+        self.open(pending)
+    }
+
+    /// A pending transfer out of `wallet_id`
+    pub async fn find_pending_transfer(
+        &self,
+        wallet_id: &str,
+        pending_id: &str,
+    ) -> WalletResult<PendingTransfer> {
+        let pending = sqlx::query_as!(
+            PendingTransfer,
+            r#"
+            SELECT id, from_wallet_id, to_wallet_id, amount, memo, status,
+                   failed_attempts, expires_at, hold_reason, created_at, resolved_at, reference_id
+            FROM pending_transfers
+            WHERE id = $1 AND from_wallet_id = $2
+            "#,
+            pending_id,
+            wallet_id
+        )
+        .fetch_optional(self.reader())
+        .await?
+        .ok_or_else(|| WalletError::PendingTransferNotFound(pending_id.to_string()))?;
+
+        self.open(pending)
+    }
+
+    /// Check a pending transfer's code and mark it CONFIRMED
+    ///
+    /// Business rules:
+    /// - Only a PENDING_VERIFICATION transfer can be confirmed, and only
+    ///   once - concurrent confirms wait on the row lock
+    /// - Past `expires_at` it is marked EXPIRED instead
+    /// - Each wrong code is counted; the `max_attempts`th cancels it
+    /// - Wrong codes are counted for the wallet too: the
+    ///   `wallet_max_failures`th locks step-up for it (`LockedOut`) and
+    ///   cancels everything still waiting for a code
+    ///
+    /// The caller runs the transfer itself and records how it went with
+    /// `resolve_pending_transfer`.
+    pub async fn confirm_pending_transfer(
+        &self,
+        wallet_id: &str,
+        pending_id: &str,
+        code: &str,
+        config: &StepUpConfig,
+    ) -> WalletResult<PendingTransfer> {
+        let mut tx = self.pool.begin().await?;
+
+        let pending = sqlx::query!(
+            r#"
+            SELECT status, code_hash, failed_attempts, expires_at
+            FROM pending_transfers
+            WHERE id = $1 AND from_wallet_id = $2
+            FOR UPDATE
+            "#,
+            pending_id,
+            wallet_id
+        )
+        .fetch_optional(&mut *tx)
+        .await?
+        .ok_or_else(|| WalletError::PendingTransferNotFound(pending_id.to_string()))?;
+        let (status, code_hash, failed_attempts, expires_at) =
+            (pending.status, pending.code_hash, pending.failed_attempts, pending.expires_at);
+
+        if let Some(until) = self.step_up_locked_until(wallet_id).await? {
+            return Err(WalletError::VerificationLocked { until });
+        }
+
+        if status != "PENDING_VERIFICATION" {
+            return Err(WalletError::PendingTransferClosed(status));
+        }
+
+        if expires_at.is_some_and(|at| at <= Utc::now()) {
+            sqlx::query!(
+                "UPDATE pending_transfers SET status = 'EXPIRED', resolved_at = NOW() WHERE id = $1",
+                pending_id
+            )
+            .execute(&mut *tx)
+            .await?;
+            tx.commit().await?;
+            return Err(WalletError::VerificationExpired);
+        }
+
+        if code_hash.as_deref() != Some(step_up::hash_code(code).as_str()) {
+            let failed_attempts = failed_attempts + 1;
+            let attempts_left = config.max_attempts.saturating_sub(failed_attempts as u32);
+            sqlx::query!(
+                r#"
+                UPDATE pending_transfers
+                SET failed_attempts = $2,
+                    status = CASE WHEN $3 THEN 'CANCELLED' ELSE status END,
+                    resolved_at = CASE WHEN $3 THEN NOW() ELSE resolved_at END
+                WHERE id = $1
+                "#,
+                pending_id,
+                failed_attempts,
+                attempts_left == 0
+            )
+            .execute(&mut *tx)
+            .await?;
+
+            let lockout = self.count_step_up_failure(&mut tx, wallet_id, config).await?;
+            tx.commit().await?;
+
+            if let Some(lockout) = lockout {
+                return Err(lockout);
+            }
+            if attempts_left == 0 {
+                tracing::warn!(pending_transfer_id = %pending_id, "Pending transfer cancelled after wrong codes");
+            }
+            return Err(WalletError::IncorrectVerificationCode { attempts_left });
+        }
+
+        sqlx::query!(
+            r#"
+            UPDATE step_up_lockouts SET failed_attempts = 0, lockouts = 0, updated_at = NOW()
+            WHERE wallet_id = $1 AND (failed_attempts > 0 OR lockouts > 0)
+            "#,
+            wallet_id
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        let pending = sqlx::query_as!(
+            PendingTransfer,
+            r#"
+            UPDATE pending_transfers
+            SET status = 'CONFIRMED'
+            WHERE id = $1
+            RETURNING id, from_wallet_id, to_wallet_id, amount, memo, status,
+                      failed_attempts, expires_at, hold_reason, created_at, resolved_at, reference_id
+            "#,
+            pending_id
+        )
+        .fetch_one(&mut *tx)
+        .await?;
+        tx.commit().await?;
+
+        self.open(pending)
+    }
+
+    /// Until when step-up is locked for a wallet (None if it isn't)
+    async fn step_up_locked_until(&self, wallet_id: &str) -> WalletResult<Option<DateTime<Utc>>> {
+        let until = sqlx::query_scalar!(
+            r#"
+            SELECT locked_until AS "locked_until!"
+            FROM step_up_lockouts
+            WHERE wallet_id = $1 AND locked_until > NOW()
+            "#,
+            wallet_id
+        )
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(until)
+    }
+
+    /// Count a wrong code against the wallet; the one that reaches
+    /// `wallet_max_failures` locks step-up (twice as long as last time, up
+    /// to `max_lockout`), cancels the wallet's other pending transfers and
+    /// comes back as the `LockedOut` error
+    async fn count_step_up_failure(
+        &self,
+        tx: &mut Transaction<'_, Postgres>,
+        wallet_id: &str,
+        config: &StepUpConfig,
+    ) -> WalletResult<Option<WalletError>> {
+        sqlx::query!(
+            "INSERT INTO step_up_lockouts (wallet_id) VALUES ($1) ON CONFLICT (wallet_id) DO NOTHING",
+            wallet_id
+        )
+        .execute(&mut **tx)
+        .await?;
+
+        let counted = sqlx::query!(
+            r#"
+            UPDATE step_up_lockouts
+            SET failed_attempts = CASE WHEN failed_attempts + 1 >= $2 THEN 0
+                                       ELSE failed_attempts + 1 END,
+                lockouts = CASE WHEN failed_attempts + 1 >= $2 THEN lockouts + 1
+                                ELSE lockouts END,
+                locked_until = CASE WHEN failed_attempts + 1 >= $2
+                                    THEN NOW() + make_interval(secs => LEAST(
+                                        $3 * power(2, LEAST(lockouts, 30)), $4))
+                                    ELSE locked_until END,
+                updated_at = NOW()
+            WHERE wallet_id = $1
+            RETURNING failed_attempts, lockouts, locked_until
+            "#,
+            wallet_id,
+            config.wallet_max_failures as i32,
+            config.lockout.as_secs_f64(),
+            config.max_lockout.as_secs_f64()
+        )
+        .fetch_one(&mut **tx)
+        .await?;
+        let (failed_attempts, lockouts, locked_until) =
+            (counted.failed_attempts, counted.lockouts, counted.locked_until);
+
+        let Some(until) = locked_until.filter(|until| failed_attempts == 0 && *until > Utc::now())
+        else {
+            return Ok(None);
+        };
+
+        let cancelled = sqlx::query!(
+            r#"
+            UPDATE pending_transfers SET status = 'CANCELLED', resolved_at = NOW()
+            WHERE from_wallet_id = $1 AND status = 'PENDING_VERIFICATION'
+            "#,
+            wallet_id
+        )
+        .execute(&mut **tx)
+        .await?
+        .rows_affected();
+
+        tracing::warn!(
+            wallet_id = %wallet_id,
+            lockouts,
+            until = %until,
+            cancelled,
+            "Step-up verification locked after wrong codes"
+        );
+        Ok(Some(WalletError::LockedOut {
+            factor: "otp",
+            failed_attempts: config.wallet_max_failures,
+            lockouts: lockouts as u32,
+            until,
+        }))
+    }
+
+    /// Record how a confirmed transfer went: COMPLETED with the transfer's
+    /// `reference_id`, or FAILED
+    pub async fn resolve_pending_transfer(
+        &self,
+        pending_id: &str,
+        reference_id: Option<&str>,
+    ) -> WalletResult<()> {
+        sqlx::query!(
+            r#"
+            UPDATE pending_transfers
+            SET status = CASE WHEN $2::text IS NULL THEN 'FAILED' ELSE 'COMPLETED' END,
+                reference_id = $2,
+                resolved_at = NOW()
+            WHERE id = $1 AND status = 'CONFIRMED'
+            "#,
+            pending_id,
+            reference_id
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Cancel a transfer still waiting for its code
+    pub async fn cancel_pending_transfer(
+        &self,
+        wallet_id: &str,
+        pending_id: &str,
+    ) -> WalletResult<PendingTransfer> {
+        let cancelled = sqlx::query_as!(
+            PendingTransfer,
+            r#"
+            UPDATE pending_transfers
+            SET status = 'CANCELLED', resolved_at = NOW()
+            WHERE id = $1 AND from_wallet_id = $2 AND status = 'PENDING_VERIFICATION'
+            RETURNING id, from_wallet_id, to_wallet_id, amount, memo, status,
+                      failed_attempts, expires_at, hold_reason, created_at, resolved_at, reference_id
+            "#,
+            pending_id,
+            wallet_id
+        )
+        .fetch_optional(&self.pool)
+        .await?;
+
+        match cancelled {
+            Some(pending) => self.open(pending),
+            // Unknown (404), or already past waiting (409)
+            None => Err(WalletError::PendingTransferClosed(
+                self.find_pending_transfer(wallet_id, pending_id).await?.status,
+            )),
+        }
+    }
+
+    /// Mark every unconfirmed transfer past its `expires_at` EXPIRED;
+    /// returns how many were
+    pub async fn expire_pending_transfers(&self) -> WalletResult<u64> {
+        let expired = sqlx::query!(
+            r#"
+            UPDATE pending_transfers
+            SET status = 'EXPIRED', resolved_at = NOW()
+            WHERE status = 'PENDING_VERIFICATION' AND expires_at <= NOW()
+            "#
+        )
+        .execute(&self.pool)
+        .await?
+        .rows_affected();
+
+        Ok(expired)
+    }
+
+    // === Fraud holds ===
+
+    /// Has `from_wallet_id` ever completed a transfer to `to_wallet_id`?
+    pub async fn has_transferred_to(&self, from_wallet_id: &str, to_wallet_id: &str) -> WalletResult<bool> {
+        let seen: bool = sqlx::query_scalar!(
+            r#"
+            SELECT EXISTS (
+                SELECT 1
+                FROM wallet_transactions sent
+                JOIN wallet_transactions received ON received.reference_id = sent.reference_id
+                WHERE sent.wallet_id = $1 AND sent.type = 'TRANSFER_OUT'
+                  AND received.wallet_id = $2 AND received.type = 'TRANSFER_IN'
+            ) AS "exists!"
+            "#,
+            from_wallet_id,
+            to_wallet_id
+        )
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(seen)
+    }
+
+    /// Park a transfer the fraud rules held, for an admin to review
+    pub async fn create_held_transfer(
+        &self,
+        from_wallet_id: &str,
+        to_wallet_id: &str,
+        amount: Decimal,
+        memo: Option<&str>,
+        hold_reason: &str,
+    ) -> WalletResult<PendingTransfer> {
+        let held = sqlx::query_as!(
+            PendingTransfer,
+            r#"
+            INSERT INTO pending_transfers
+                (id, from_wallet_id, to_wallet_id, amount, memo, status, hold_reason)
+            VALUES ($1, $2, $3, $4, $5, 'HELD', $6)
+            RETURNING id, from_wallet_id, to_wallet_id, amount, memo, status,
+                      failed_attempts, expires_at, hold_reason, created_at, resolved_at, reference_id
+            "#,
+            Uuid::new_v4().to_string(),
+            from_wallet_id,
+            to_wallet_id,
+            amount,
+            self.cipher.seal_opt(memo)?,
+            hold_reason
+        )
+        .fetch_one(&self.pool)
+        .await?;
+
+        self.open(held)
+    }
+
+    /// Held transfers waiting for review, oldest first
+    pub async fn find_held_transfers(&self, limit: i64) -> WalletResult<Vec<PendingTransfer>> {
+        let held = sqlx::query_as!(
+            PendingTransfer,
+            r#"
+            SELECT id, from_wallet_id, to_wallet_id, amount, memo, status,
+                   failed_attempts, expires_at, hold_reason, created_at, resolved_at, reference_id
+            FROM pending_transfers
+            WHERE status = 'HELD'
+            ORDER BY created_at
+            LIMIT $1
+            "#,
+            limit
+        )
+        .fetch_all(self.reader())
+        .await?;
+
+        self.open(held)
+    }
+
+    /// Let a held transfer go ahead: it is marked CONFIRMED, and the caller
+    /// runs it and records how it went (`resolve_pending_transfer`)
+    pub async fn release_held_transfer(&self, pending_id: &str, actor: &str) -> WalletResult<PendingTransfer> {
+        self.review_held_transfer(pending_id, actor, "CONFIRMED", AUDIT_HELD_TRANSFER_RELEASED)
+            .await
+    }
+
+    /// Refuse a held transfer (CANCELLED); nothing ever moved
+    pub async fn reject_held_transfer(&self, pending_id: &str, actor: &str) -> WalletResult<PendingTransfer> {
+        self.review_held_transfer(pending_id, actor, "CANCELLED", AUDIT_HELD_TRANSFER_REJECTED)
+            .await
+    }
+
+    /// Move a HELD transfer on and audit who did it, in one DB transaction
+    async fn review_held_transfer(
+        &self,
+        pending_id: &str,
+        actor: &str,
+        status: &str,
+        action: &'static str,
+    ) -> WalletResult<PendingTransfer> {
+        let mut tx = self.pool.begin().await?;
+
+        let reviewed = sqlx::query_as!(
+            PendingTransfer,
+            r#"
+            UPDATE pending_transfers
+            SET status = $2::VARCHAR,
+                resolved_at = CASE WHEN $2::VARCHAR = 'CANCELLED' THEN NOW() ELSE resolved_at END
+            WHERE id = $1 AND status = 'HELD'
+            RETURNING id, from_wallet_id, to_wallet_id, amount, memo, status,
+                      failed_attempts, expires_at, hold_reason, created_at, resolved_at, reference_id
+            "#,
+            pending_id,
+            status
+        )
+        .fetch_optional(&mut *tx)
+        .await?;
+
+        let Some(reviewed) = reviewed else {
+            // Unknown (404), or already reviewed (409)
+            let current: Option<String> =
+                sqlx::query_scalar!(
+                    "SELECT status FROM pending_transfers WHERE id = $1",
+                    pending_id
+                )
+                .fetch_optional(&mut *tx)
+                .await?;
+            return Err(match current {
+                Some(current) => WalletError::PendingTransferClosed(current),
+                None => WalletError::PendingTransferNotFound(pending_id.to_string()),
+            });
+        };
+
+        self.record_admin_action_in_tx(
+            &mut tx,
+            AuditRecord {
+                actor,
+                action,
+                target_type: "pending_transfer",
+                target_id: &reviewed.id,
+                before: None,
+                after: Some(json!(&reviewed)),
+            },
+        )
+        .await?;
+
+        tx.commit().await?;
+
+        self.open(reviewed)
+    }
+
+    // === Transactions & support notes ===
+
+    /// Find a single transaction record by ID
+    pub async fn find_transaction(&self, transaction_id: &str) -> WalletResult<WalletTransaction> {
+        let transaction = sqlx::query_as!(
+            WalletTransaction,
+            r#"
+            SELECT id, wallet_id, amount, type AS "transaction_type: TransactionType",
+                   status AS "status: TransactionStatus", reference_id, memo, created_at
+            FROM wallet_transactions
+            WHERE id = $1
+            "#,
+            transaction_id
+        )
+        .fetch_optional(self.reader())
+        .await?
+        .ok_or_else(|| WalletError::TransactionNotFound(transaction_id.to_string()))?;
+
+        self.open(transaction)
+    }
+
+    /// Every transaction sharing a reference_id, oldest first (both legs of
+    /// a transfer come back together)
+    pub async fn find_transactions_by_reference(
+        &self,
+        reference_id: &str,
+    ) -> WalletResult<Vec<WalletTransaction>> {
+        let transactions = sqlx::query_as!(
+            WalletTransaction,
+            r#"
+            SELECT id, wallet_id, amount, type AS "transaction_type: TransactionType",
+                   status AS "status: TransactionStatus", reference_id, memo, created_at
+            FROM wallet_transactions
+            WHERE reference_id = $1
+            ORDER BY created_at ASC, id ASC
+            "#,
+            reference_id
+        )
+        .fetch_all(self.reader())
+        .await?;
+
+        self.open(transactions)
+    }
+
+    /// A receipt for one transaction: its legs, their users, and the
+    /// balance it left behind
+    ///
+    /// How it works:
+    /// - Legs are every transaction with the same reference_id (just this
+    ///   one when it has none, e.g. a funding)
+    /// - Balances aren't stored per transaction, so `balance_after` is the
+    ///   wallet's ledger summed up to and including the leg - the same
+    ///   ledger the balance itself is kept in step with
+    pub async fn find_transaction_receipt(
+        &self,
+        transaction_id: &str,
+    ) -> WalletResult<TransactionReceipt> {
+        let transaction = self.find_transaction(transaction_id).await?;
+
+        let legs = sqlx::query_as!(
+            ReceiptLeg,
+            r#"
+            SELECT t.id AS transaction_id, t.wallet_id, w.user_id,
+                   t.type AS "transaction_type: TransactionType", t.amount,
+                   ledger.balance AS balance_after
+            FROM wallet_transactions t
+            JOIN wallets w ON w.id = t.wallet_id
+            LEFT JOIN LATERAL (
+                SELECT COALESCE(SUM(CASE
+                           WHEN h.type IN ('FUND', 'TRANSFER_IN', 'ROUND_UP_IN', 'POT_TRANSFER_IN',
+                                           'VOUCHER_REDEEM', 'ADJUSTMENT') THEN h.amount
+                           ELSE -h.amount
+                       END), 0) AS balance
+                FROM wallet_transactions h
+                WHERE h.wallet_id = t.wallet_id
+                  AND h.status = 'COMPLETED'
+                  AND (h.created_at, h.id) <= (t.created_at, t.id)
+            ) ledger ON t.wallet_id = $3
+            WHERE t.id = $1 OR t.reference_id = $2
+            ORDER BY t.created_at ASC, t.id ASC
+            "#,
+            &transaction.id,
+            transaction.reference_id.as_deref(),
+            &transaction.wallet_id
+        )
+        .fetch_all(self.reader())
+        .await?;
+
+        let own = legs
+            .iter()
+            .find(|leg| leg.transaction_id == transaction.id)
+            .ok_or_else(|| WalletError::TransactionNotFound(transaction.id.clone()))?;
+        // The last leg on this wallet - a transfer's round-up comes out too
+        let balance_after = legs
+            .iter()
+            .rev()
+            .find_map(|leg| leg.balance_after)
+            .unwrap_or_default();
+        let user_id = own.user_id.clone();
+
+        // A round-up into your own savings isn't a counterparty
+        let mut counterparty_user_ids: Vec<String> = Vec::new();
+        for leg in legs.iter().filter(|leg| leg.user_id != user_id) {
+            if !counterparty_user_ids.contains(&leg.user_id) {
+                counterparty_user_ids.push(leg.user_id.clone());
+            }
+        }
+
+        Ok(TransactionReceipt {
+            transaction_id: transaction.id,
+            reference_id: transaction.reference_id,
+            wallet_id: transaction.wallet_id,
+            user_id,
+            transaction_type: transaction.transaction_type,
+            status: transaction.status,
+            amount: transaction.amount,
+            fee: Decimal::ZERO,
+            memo: transaction.memo,
+            balance_after,
+            counterparty_user_ids,
+            legs,
+            created_at: transaction.created_at,
+        })
+    }
+
+    /// Attach a support note (optionally linked to a case) to a transaction
+    pub async fn add_transaction_note(
+        &self,
+        transaction_id: &str,
+        case_id: Option<&str>,
+        note: &str,
+        author: &str,
+    ) -> WalletResult<TransactionNote> {
+        if note.trim().is_empty() {
+            return Err(WalletError::InvalidRequest("Note must not be empty".to_string()));
+        }
+
+        // 404 for unknown transactions instead of a foreign key violation
+        self.find_transaction(transaction_id).await?;
+
+        let note_id = Uuid::new_v4().to_string();
+
+        let mut tx = self.pool.begin().await?;
+
+        let note = sqlx::query_as!(
+            TransactionNote,
+            r#"
+            INSERT INTO transaction_notes (id, transaction_id, case_id, note, author, created_at)
+            VALUES ($1, $2, $3, $4, $5, $6)
+            RETURNING id, transaction_id, case_id, note, author, created_at
+            "#,
+            &note_id,
+            transaction_id,
+            case_id,
+            self.cipher.seal(note)?,
+            author,
+            Utc::now()
+        )
+        .fetch_one(&mut *tx)
+        .await?;
+
+        self.record_admin_action_in_tx(
+            &mut tx,
+            AuditRecord {
+                actor: author,
+                action: AUDIT_TRANSACTION_NOTE_ADDED,
+                target_type: "transaction",
+                target_id: transaction_id,
+                before: None,
+                after: Some(json!(&note)),
+            },
+        )
+        .await?;
+
+        tx.commit().await?;
+
+        self.open(note)
+    }
+
+    /// All notes on a transaction, oldest first
+    pub async fn find_transaction_notes(
+        &self,
+        transaction_id: &str,
+    ) -> WalletResult<Vec<TransactionNote>> {
+        let notes = sqlx::query_as!(
+            TransactionNote,
+            r#"
+            SELECT id, transaction_id, case_id, note, author, created_at
+            FROM transaction_notes
+            WHERE transaction_id = $1
+            ORDER BY created_at ASC
+            "#,
+            transaction_id
+        )
+        .fetch_all(self.reader())
+        .await?;
+
+        self.open(notes)
+    }
+
+    /// All transactions linked to a support case
+    pub async fn find_transactions_by_case(
+        &self,
+        case_id: &str,
+    ) -> WalletResult<Vec<WalletTransaction>> {
+        let transactions = sqlx::query_as!(
+            WalletTransaction,
+            r#"
+            SELECT id, wallet_id, amount, type AS "transaction_type: TransactionType",
+                   status AS "status: TransactionStatus", reference_id, memo, created_at
+            FROM wallet_transactions
+            WHERE id IN (
+                SELECT transaction_id FROM transaction_notes WHERE case_id = $1
+            )
+            ORDER BY created_at DESC
+            "#,
+            case_id
+        )
+        .fetch_all(self.reader())
+        .await?;
+
+        self.open(transactions)
+    }
+
+    // === Admin audit log ===
+
+    /// Audit entries matching the filters, newest first
+    pub async fn find_audit_entries(
+        &self,
+        query: &AuditLogQuery,
+        limit: i64,
+    ) -> WalletResult<Vec<AdminAuditEntry>> {
+        let entries = sqlx::query_as!(
+            AdminAuditEntry,
+            r#"
+            SELECT id, actor, action, target_type, target_id, before_value, after_value, created_at
+            FROM admin_audit_log
+            WHERE ($1::VARCHAR IS NULL OR actor = $1)
+              AND ($2::VARCHAR IS NULL OR action = $2)
+              AND ($3::VARCHAR IS NULL OR target_id = $3)
+              AND ($4::TIMESTAMPTZ IS NULL OR created_at > $4)
+            ORDER BY id DESC
+            LIMIT $5
+            "#,
+            query.actor.as_deref(),
+            query.action.as_deref(),
+            query.target_id.as_deref(),
+            query.since,
+            limit
+        )
+        .fetch_all(self.reader())
+        .await?;
+
+        Ok(entries)
+    }
+
+    /// Append an audit entry within the transaction making the change
+    async fn record_admin_action_in_tx(
+        &self,
+        tx: &mut Transaction<'_, Postgres>,
+        record: AuditRecord<'_>,
+    ) -> WalletResult<()> {
+        sqlx::query!(
+            r#"
+            INSERT INTO admin_audit_log
+                (actor, action, target_type, target_id, before_value, after_value, created_at)
+            VALUES ($1, $2, $3, $4, $5, $6, $7)
+            "#,
+            record.actor,
+            record.action,
+            record.target_type,
+            record.target_id,
+            record.before,
+            record.after,
+            Utc::now()
+        )
+        .execute(&mut **tx)
+        .await?;
+
+        Ok(())
+    }
+
+    // === Archival of inactive wallets ===
+
+    /// Move up to `limit` wallets untouched since `cutoff` into the archive
+    /// tables; returns the IDs moved
+    ///
+    /// Eligible wallets:
+    /// - have a zero balance - money is never archived
+    /// - haven't changed since `cutoff` (`updated_at` moves with every
+    ///   balance change)
+    /// - aren't pots and have no pots - a wallet and its pots stay together
+    ///
+    /// One DB transaction copies each wallet, its transactions and their
+    /// notes and adjustments, writes an audit entry, then deletes the
+    /// originals. Candidates are locked FOR UPDATE SKIP LOCKED: two archivers
+    /// never take the same wallet, and a fund racing the archiver either
+    /// commits first (so the wallet no longer qualifies) or waits and gets
+    /// a 404.
+    pub async fn archive_inactive_wallets(
+        &self,
+        cutoff: DateTime<Utc>,
+        limit: i64,
+        actor: &str,
+    ) -> WalletResult<Vec<String>> {
+        let mut tx = self.pool.begin().await?;
+
+        let wallets = sqlx::query_as!(
+            Wallet,
+            r#"
+            SELECT id, user_id, total_balance(w) AS "balance!", version, parent_wallet_id,
+                   nickname, labels, is_default, created_at, updated_at
+            FROM wallets w
+            WHERE total_balance(w) = 0
+              AND w.updated_at < $1
+              AND w.parent_wallet_id IS NULL
+              AND NOT EXISTS (SELECT 1 FROM wallets pot WHERE pot.parent_wallet_id = w.id)
+            ORDER BY w.updated_at, w.id
+            LIMIT $2
+            FOR UPDATE SKIP LOCKED
+            "#,
+            cutoff,
+            limit
+        )
+        .fetch_all(&mut *tx)
+        .await?;
+
+        if wallets.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let ids: Vec<String> = wallets.iter().map(|w| w.id.clone()).collect();
+        let archived_at = Utc::now();
+
+        sqlx::query!(
+            r#"
+            INSERT INTO archived_wallets
+                (id, user_id, balance, version, parent_wallet_id, nickname, labels, is_default,
+                 created_at, updated_at, archived_at)
+            SELECT id, user_id, balance, version, parent_wallet_id, nickname, labels, is_default,
+                   created_at, updated_at, $2
+            FROM wallets
+            WHERE id = ANY($1)
+            "#,
+            &ids,
+            archived_at
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        sqlx::query!(
+            r#"
+            INSERT INTO archived_wallet_transactions
+                (id, wallet_id, amount, type, status, reference_id, memo, created_at, archived_at)
+            SELECT id, wallet_id, amount, type, status, reference_id, memo, created_at, $2
+            FROM wallet_transactions
+            WHERE wallet_id = ANY($1)
+            "#,
+            &ids,
+            archived_at
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        sqlx::query!(
+            r#"
+            INSERT INTO archived_transaction_notes
+                (id, transaction_id, case_id, note, author, created_at, archived_at)
+            SELECT n.id, n.transaction_id, n.case_id, n.note, n.author, n.created_at, $2
+            FROM transaction_notes n
+            JOIN wallet_transactions t ON t.id = n.transaction_id
+            WHERE t.wallet_id = ANY($1)
+            "#,
+            &ids,
+            archived_at
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        sqlx::query!(
+            r#"
+            INSERT INTO archived_wallet_adjustments
+                (id, wallet_id, transaction_id, direction, amount, reason_code, actor, note, created_at,
+                 archived_at)
+            SELECT id, wallet_id, transaction_id, direction, amount, reason_code, actor, note, created_at, $2
+            FROM wallet_adjustments
+            WHERE wallet_id = ANY($1)
+            "#,
+            &ids,
+            archived_at
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        for wallet in &wallets {
+            self.record_admin_action_in_tx(
+                &mut tx,
+                AuditRecord {
+                    actor,
+                    action: AUDIT_WALLET_ARCHIVED,
+                    target_type: "wallet",
+                    target_id: &wallet.id,
+                    before: Some(json!(wallet)),
+                    after: None,
+                },
+            )
+            .await?;
+        }
+
+        // Cascades to the hot transactions, notes, adjustments and config
+        sqlx::query!("DELETE FROM wallets WHERE id = ANY($1)", &ids)
+            .execute(&mut *tx)
+            .await?;
+
+        tx.commit().await?;
+        self.invalidate_cached(&wallets).await;
+
+        Ok(ids)
+    }
+
+    /// An archived wallet, when it was archived, and its transactions
+    /// (oldest first)
+    pub async fn find_archived_wallet(
+        &self,
+        wallet_id: &str,
+    ) -> WalletResult<(Wallet, DateTime<Utc>, Vec<WalletTransaction>)> {
+        let row = sqlx::query!(
+            r#"
+            SELECT id, user_id, balance, version, parent_wallet_id, nickname, labels, is_default,
+                   created_at, updated_at,
+                   archived_at
+            FROM archived_wallets
+            WHERE id = $1
+            "#,
+            wallet_id
+        )
+        .fetch_optional(self.reader())
+        .await?
+        .ok_or_else(|| WalletError::WalletNotFound(wallet_id.to_string()))?;
+
+        let archived_at = row.archived_at;
+        let wallet = wallet_from_record!(row);
+
+        let transactions = sqlx::query_as!(
+            WalletTransaction,
+            r#"
+            SELECT id, wallet_id, amount, type AS "transaction_type: TransactionType",
+                   status AS "status: TransactionStatus", reference_id, memo, created_at
+            FROM archived_wallet_transactions
+            WHERE wallet_id = $1
+            ORDER BY created_at, id
+            "#,
+            wallet_id
+        )
+        .fetch_all(self.reader())
+        .await?;
+
+        Ok((self.open(wallet)?, archived_at, self.open(transactions)?))
+    }
+
+    // === Webhook subscriptions ===
+
+    /// Register a webhook URL with a fresh signing secret
+    ///
+    /// `event_types` empty = every event type; unknown types and non-http(s)
+    /// URLs are rejected (InvalidRequest)
+    pub async fn create_webhook(
+        &self,
+        url: &str,
+        event_types: &[String],
+        description: Option<&str>,
+    ) -> WalletResult<WebhookSubscription> {
+        webhooks::validate_subscription(url, event_types)?;
+
+        let webhook = sqlx::query_as!(
+            WebhookSubscription,
+            r#"
+            INSERT INTO webhook_subscriptions (id, url, event_types, description, secret)
+            VALUES ($1, $2, $3, $4, $5)
+            RETURNING id, url, event_types, description, secret, active, created_at, updated_at
+            "#,
+            Uuid::new_v4().to_string(),
+            url.trim(),
+            event_types,
+            description,
+            webhooks::generate_secret()
+        )
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(webhook)
+    }
+
+    /// Every webhook subscription, oldest first
+    pub async fn find_webhooks(&self) -> WalletResult<Vec<WebhookSubscription>> {
+        let webhooks = sqlx::query_as!(
+            WebhookSubscription,
+            r#"
+            SELECT id, url, event_types, description, secret, active, created_at, updated_at
+            FROM webhook_subscriptions
+            ORDER BY created_at ASC
+            "#
+        )
+        .fetch_all(self.reader())
+        .await?;
+
+        Ok(webhooks)
+    }
+
+    pub async fn find_webhook(&self, webhook_id: &str) -> WalletResult<WebhookSubscription> {
+        sqlx::query_as!(
+            WebhookSubscription,
+            r#"
+            SELECT id, url, event_types, description, secret, active, created_at, updated_at
+            FROM webhook_subscriptions
+            WHERE id = $1
+            "#,
+            webhook_id
+        )
+        .fetch_optional(self.reader())
+        .await?
+        .ok_or_else(|| WalletError::WebhookNotFound(webhook_id.to_string()))
+    }
+
+    /// Change a webhook's URL, event types, description or active flag
+    ///
+    /// Absent fields keep their value; the secret never changes here
+    pub async fn update_webhook(
+        &self,
+        webhook_id: &str,
+        update: &UpdateWebhookRequest,
+    ) -> WalletResult<WebhookSubscription> {
+        let current = self.find_webhook(webhook_id).await?;
+        let url = update.url.as_deref().unwrap_or(&current.url);
+        let event_types = update.event_types.as_deref().unwrap_or(&current.event_types);
+        webhooks::validate_subscription(url, event_types)?;
+
+        let webhook = sqlx::query_as!(
+            WebhookSubscription,
+            r#"
+            UPDATE webhook_subscriptions
+            SET url = $2,
+                event_types = $3,
+                description = COALESCE($4, description),
+                active = COALESCE($5, active),
+                updated_at = NOW()
+            WHERE id = $1
+            RETURNING id, url, event_types, description, secret, active, created_at, updated_at
+            "#,
+            webhook_id,
+            url.trim(),
+            event_types,
+            update.description.as_deref(),
+            update.active
+        )
+        .fetch_optional(&self.pool)
+        .await?
+        .ok_or_else(|| WalletError::WebhookNotFound(webhook_id.to_string()))?;
+
+        Ok(webhook)
+    }
+
+    /// Delete a webhook with its queued deliveries and their attempt log
+    pub async fn delete_webhook(&self, webhook_id: &str) -> WalletResult<()> {
+        let rows_affected = sqlx::query!(
+            "DELETE FROM webhook_subscriptions WHERE id = $1",
+            webhook_id
+        )
+        .execute(&self.pool)
+        .await?
+        .rows_affected();
+
+        if rows_affected == 0 {
+            return Err(WalletError::WebhookNotFound(webhook_id.to_string()));
+        }
+
+        Ok(())
+    }
+
+    /// A webhook's most recent deliveries, newest first
+    pub async fn find_webhook_deliveries(
+        &self,
+        webhook_id: &str,
+        limit: i64,
+    ) -> WalletResult<Vec<WebhookDelivery>> {
+        // 404 for an unknown webhook rather than an empty list
+        self.find_webhook(webhook_id).await?;
+
+        let deliveries = sqlx::query_as!(
+            WebhookDelivery,
+            r#"
+            SELECT id, subscription_id, event_id::text AS "event_id!", event_type, status, attempts,
+                   next_attempt_at, last_status_code, last_error, delivered_at, created_at
+            FROM webhook_deliveries
+            WHERE subscription_id = $1
+            ORDER BY id DESC
+            LIMIT $2
+            "#,
+            webhook_id,
+            limit
+        )
+        .fetch_all(self.reader())
+        .await?;
+
+        Ok(deliveries)
+    }
+
+    /// Every HTTP attempt at one of a webhook's deliveries, oldest first
+    pub async fn find_webhook_delivery_attempts(
+        &self,
+        webhook_id: &str,
+        delivery_id: i64,
+    ) -> WalletResult<Vec<WebhookDeliveryAttempt>> {
+        let attempts = sqlx::query_as!(
+            WebhookDeliveryAttempt,
+            r#"
+            SELECT a.id, a.delivery_id, a.attempted_at, a.status_code, a.error, a.duration_ms
+            FROM webhook_delivery_attempts a
+            JOIN webhook_deliveries d ON d.id = a.delivery_id
+            WHERE d.subscription_id = $1 AND a.delivery_id = $2
+            ORDER BY a.id ASC
+            "#,
+            webhook_id,
+            delivery_id
+        )
+        .fetch_all(self.reader())
+        .await?;
+
+        Ok(attempts)
+    }
+
+    // === API keys (machine clients) ===
+
+    /// Issue a key; returns it with the key itself, which isn't stored
+    pub async fn create_api_key(
+        &self,
+        request: &CreateApiKeyRequest,
+        created_by: &str,
+    ) -> WalletResult<(ApiKey, String)> {
+        let key = auth::generate_api_key();
+        let mut tx = self.pool.begin().await?;
+
+        let api_key = sqlx::query_as!(
+            ApiKey,
+            r#"
+            INSERT INTO api_keys (id, name, key_prefix, key_hash, scope, created_by)
+            VALUES ($1, $2, $3, $4, $5, $6)
+            RETURNING id, name, key_prefix, scope AS "scope: ApiKeyScope", created_by, created_at,
+                      last_used_at, revoked_at
+            "#,
+            Uuid::new_v4().to_string(),
+            request.name.trim(),
+            auth::api_key_prefix(&key),
+            auth::hash_api_key(&key),
+            request.scope as ApiKeyScope,
+            created_by
+        )
+        .fetch_one(&mut *tx)
+        .await?;
+
+        self.record_admin_action_in_tx(
+            &mut tx,
+            AuditRecord {
+                actor: created_by,
+                action: AUDIT_API_KEY_CREATED,
+                target_type: "api_key",
+                target_id: &api_key.id,
+                before: None,
+                after: Some(json!(&api_key)),
+            },
+        )
+        .await?;
+
+        tx.commit().await?;
+
+        Ok((api_key, key))
+    }
+
+    /// Every API key, revoked ones included, oldest first
+    pub async fn find_api_keys(&self) -> WalletResult<Vec<ApiKey>> {
+        let keys = sqlx::query_as!(
+            ApiKey,
+            r#"
+            SELECT id, name, key_prefix, scope AS "scope: ApiKeyScope", created_by, created_at,
+                   last_used_at, revoked_at
+            FROM api_keys
+            ORDER BY created_at ASC
+            "#
+        )
+        .fetch_all(self.reader())
+        .await?;
+
+        Ok(keys)
+    }
+
+    /// Revoke a key; it stops working immediately. Revoking twice keeps the
+    /// first revocation time.
+    pub async fn revoke_api_key(&self, key_id: &str, actor: &str) -> WalletResult<ApiKey> {
+        let mut tx = self.pool.begin().await?;
+
+        let api_key = sqlx::query_as!(
+            ApiKey,
+            r#"
+            UPDATE api_keys
+            SET revoked_at = COALESCE(revoked_at, NOW())
+            WHERE id = $1
+            RETURNING id, name, key_prefix, scope AS "scope: ApiKeyScope", created_by, created_at,
+                      last_used_at, revoked_at
+            "#,
+            key_id
+        )
+        .fetch_optional(&mut *tx)
+        .await?
+        .ok_or_else(|| WalletError::ApiKeyNotFound(key_id.to_string()))?;
+
+        self.record_admin_action_in_tx(
+            &mut tx,
+            AuditRecord {
+                actor,
+                action: AUDIT_API_KEY_REVOKED,
+                target_type: "api_key",
+                target_id: &api_key.id,
+                before: None,
+                after: Some(json!(&api_key)),
+            },
+        )
+        .await?;
+
+        tx.commit().await?;
+
+        Ok(api_key)
+    }
+
+    /// The live (not revoked) key with this hash, if any
+    ///
+    /// Also bumps `last_used_at`, but only when it's over a minute old - a
+    /// busy client shouldn't turn every request into a write.
+    pub async fn find_active_api_key(&self, key_hash: &str) -> WalletResult<Option<ApiKey>> {
+        let api_key = sqlx::query_as!(
+            ApiKey,
+            r#"
+            WITH touched AS (
+                UPDATE api_keys
+                SET last_used_at = NOW()
+                WHERE key_hash = $1
+                  AND revoked_at IS NULL
+                  AND (last_used_at IS NULL OR last_used_at < NOW() - INTERVAL '1 minute')
+            )
+            SELECT id, name, key_prefix, scope AS "scope: ApiKeyScope", created_by, created_at,
+                   last_used_at, revoked_at
+            FROM api_keys
+            WHERE key_hash = $1 AND revoked_at IS NULL
+            "#,
+            key_hash
+        )
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(api_key)
+    }
+
+    // === Merchant request signatures ===
+
+    /// Remember a merchant request's signature; false if it was already used
+    ///
+    /// Kept for `remember` (twice the replay window - a timestamp may be
+    /// that far ahead of us too); expired ones are cleared on the way.
+    pub async fn record_merchant_signature(
+        &self,
+        merchant_id: &str,
+        signature: &str,
+        remember: std::time::Duration,
+    ) -> WalletResult<bool> {
+        sqlx::query!("DELETE FROM merchant_request_signatures WHERE expires_at < NOW()")
+            .execute(&self.pool)
+            .await?;
+
+        let inserted = sqlx::query!(
+            r#"
+            INSERT INTO merchant_request_signatures (signature, merchant_id, expires_at)
+            VALUES ($1, $2, NOW() + make_interval(secs => $3))
+            ON CONFLICT (signature) DO NOTHING
+            "#,
+            signature,
+            merchant_id,
+            remember.as_secs_f64()
+        )
+        .execute(&self.pool)
+        .await?
+        .rows_affected();
+
+        Ok(inserted == 1)
+    }
+
+    // === Blocklist ===
+
+    /// Blocklist a wallet or user; transfers to and from it stop at once
+    ///
+    /// The target doesn't have to exist (yet) - a known-bad wallet or user
+    /// ID can be blocked ahead of time.
+    pub async fn create_blocklist_entry(
+        &self,
+        request: &CreateBlocklistEntryRequest,
+        created_by: &str,
+    ) -> WalletResult<BlocklistEntry> {
+        let target_id = request.target_id.trim();
+        let mut tx = self.pool.begin().await?;
+
+        let entry = sqlx::query_as!(
+            BlocklistEntry,
+            r#"
+            INSERT INTO blocklist_entries (id, target_type, target_id, reason, created_by)
+            VALUES ($1, $2, $3, $4, $5)
+            RETURNING id, target_type AS "target_type: BlocklistTargetType", target_id, reason,
+                      created_by, created_at, removed_by, removed_at
+            "#,
+            Uuid::new_v4().to_string(),
+            request.target_type as BlocklistTargetType,
+            target_id,
+            request.reason.trim(),
+            created_by
+        )
+        .fetch_one(&mut *tx)
+        .await
+        .map_err(|e| match e {
+            sqlx::Error::Database(ref db) if db.is_unique_violation() => {
+                WalletError::DuplicateBlocklistEntry(format!("{} {}", request.target_type, target_id))
+            }
+            e => WalletError::DatabaseError(e),
+        })?;
+
+        self.record_admin_action_in_tx(
+            &mut tx,
+            AuditRecord {
+                actor: created_by,
+                action: AUDIT_BLOCKLIST_ENTRY_ADDED,
+                target_type: "blocklist_entry",
+                target_id: &entry.id,
+                before: None,
+                after: Some(json!(&entry)),
+            },
+        )
+        .await?;
+
+        tx.commit().await?;
+
+        Ok(entry)
+    }
+
+    /// Every blocklist entry, removed ones included, newest first
+    pub async fn find_blocklist_entries(&self) -> WalletResult<Vec<BlocklistEntry>> {
+        let entries = sqlx::query_as!(
+            BlocklistEntry,
+            r#"
+            SELECT id, target_type AS "target_type: BlocklistTargetType", target_id, reason,
+                   created_by, created_at, removed_by, removed_at
+            FROM blocklist_entries
+            ORDER BY created_at DESC
+            "#
+        )
+        .fetch_all(self.reader())
+        .await?;
+
+        Ok(entries)
+    }
+
+    /// Take an entry off the blocklist. Removing twice keeps the first
+    /// removal (time and actor).
+    pub async fn remove_blocklist_entry(&self, entry_id: &str, actor: &str) -> WalletResult<BlocklistEntry> {
+        let mut tx = self.pool.begin().await?;
+
+        let entry = sqlx::query_as!(
+            BlocklistEntry,
+            r#"
+            UPDATE blocklist_entries
+            SET removed_at = COALESCE(removed_at, NOW()),
+                removed_by = COALESCE(removed_by, $2)
+            WHERE id = $1
+            RETURNING id, target_type AS "target_type: BlocklistTargetType", target_id, reason,
+                      created_by, created_at, removed_by, removed_at
+            "#,
+            entry_id,
+            actor
+        )
+        .fetch_optional(&mut *tx)
+        .await?
+        .ok_or_else(|| WalletError::BlocklistEntryNotFound(entry_id.to_string()))?;
+
+        self.record_admin_action_in_tx(
+            &mut tx,
+            AuditRecord {
+                actor,
+                action: AUDIT_BLOCKLIST_ENTRY_REMOVED,
+                target_type: "blocklist_entry",
+                target_id: &entry.id,
+                before: None,
+                after: Some(json!(&entry)),
+            },
+        )
+        .await?;
+
+        tx.commit().await?;
+
+        Ok(entry)
+    }
+
+    // === KYC ===
+
+    /// A user's KYC level (unverified if never set)
+    pub async fn find_user_kyc(&self, user_id: &str) -> WalletResult<UserKyc> {
+        let kyc = sqlx::query_as!(
+            UserKyc,
+            r#"
+            SELECT user_id, level AS "level: KycLevel", updated_by, updated_at
+            FROM user_kyc
+            WHERE user_id = $1
+            "#,
+            user_id
+        )
+        .fetch_optional(self.reader())
+        .await?;
+
+        Ok(kyc.unwrap_or_else(|| UserKyc {
+            user_id: user_id.to_string(),
+            level: KycLevel::Unverified,
+            updated_by: None,
+            updated_at: None,
+        }))
+    }
+
+    /// Set a user's KYC level; the caps follow from the next operation on
+    pub async fn set_user_kyc(&self, user_id: &str, level: KycLevel, actor: &str) -> WalletResult<UserKyc> {
+        let before = self.find_user_kyc(user_id).await?;
+        let mut tx = self.pool.begin().await?;
+
+        let kyc = sqlx::query_as!(
+            UserKyc,
+            r#"
+            INSERT INTO user_kyc (user_id, level, updated_by, updated_at)
+            VALUES ($1, $2, $3, NOW())
+            ON CONFLICT (user_id) DO UPDATE
+            SET level = EXCLUDED.level,
+                updated_by = EXCLUDED.updated_by,
+                updated_at = EXCLUDED.updated_at
+            RETURNING user_id, level AS "level: KycLevel", updated_by, updated_at
+            "#,
+            user_id,
+            level as KycLevel,
+            actor
+        )
+        .fetch_one(&mut *tx)
+        .await?;
+
+        self.record_admin_action_in_tx(
+            &mut tx,
+            AuditRecord {
+                actor,
+                action: AUDIT_KYC_LEVEL_CHANGED,
+                target_type: "user",
+                target_id: user_id,
+                before: Some(json!(&before)),
+                after: Some(json!(&kyc)),
+            },
+        )
+        .await?;
+
+        tx.commit().await?;
+
+        Ok(kyc)
+    }
+
+    // === Field encryption ===
+
+    /// Re-encrypt every encrypted column with the current key, `batch_size`
+    /// rows at a time; returns how many rows were rewritten
+    ///
+    /// Covers values sealed with an older key and plaintext written before
+    /// encryption was turned on. Safe to run while the service is up (each
+    /// row is only replaced if it still holds the value read) and to re-run
+    /// (rows already on the current key are skipped). Wallet versions and
+    /// `updated_at` are left alone - nothing a client can see changes.
+    pub async fn rekey_fields(&self, batch_size: i64) -> WalletResult<u64> {
+        let current = self.cipher.current_key_id().ok_or_else(|| {
+            WalletError::InvalidRequest("Field encryption is not configured".to_string())
+        })?;
+        let sealed_with_current = format!("enc:{}:%", current);
+        let mut rewritten = 0;
+
+        for (table, column, hash_column) in ENCRYPTED_COLUMNS {
+            loop {
+                let rows = sqlx::query_as::<_, (String, String)>(&format!(
+                    "SELECT id, {column} FROM {table} \
+                     WHERE {column} IS NOT NULL AND {column} NOT LIKE $1 LIMIT $2"
+                ))
+                .bind(&sealed_with_current)
+                .bind(batch_size)
+                .fetch_all(&self.pool)
+                .await?;
+                if rows.is_empty() {
+                    break;
+                }
+
+                for (id, stored) in rows {
+                    let plaintext = self.cipher.open(&stored)?;
+                    let sealed = self.cipher.seal(&plaintext)?;
+                    let updated = match hash_column {
+                        Some(hash_column) => {
+                            sqlx::query(&format!(
+                                "UPDATE {table} SET {column} = $2, {hash_column} = $4 \
+                                 WHERE id = $1 AND {column} = $3"
+                            ))
+                            .bind(&id)
+                            .bind(&sealed)
+                            .bind(&stored)
+                            .bind(self.cipher.blind_index(&plaintext))
+                            .execute(&self.pool)
+                            .await?
+                        }
+                        None => {
+                            sqlx::query(&format!(
+                                "UPDATE {table} SET {column} = $2 WHERE id = $1 AND {column} = $3"
+                            ))
+                            .bind(&id)
+                            .bind(&sealed)
+                            .bind(&stored)
+                            .execute(&self.pool)
+                            .await?
+                        }
+                    };
+                    rewritten += updated.rows_affected();
+                }
+            }
+        }
+
+        for table in ["wallets", "archived_wallets"] {
+            loop {
+                let rows = sqlx::query_as::<_, (String, Vec<String>)>(&format!(
+                    "SELECT id, labels FROM {table} \
+                     WHERE EXISTS (SELECT 1 FROM unnest(labels) AS label WHERE label NOT LIKE $1) \
+                     LIMIT $2"
+                ))
+                .bind(&sealed_with_current)
+                .bind(batch_size)
+                .fetch_all(&self.pool)
+                .await?;
+                if rows.is_empty() {
+                    break;
+                }
+
+                for (id, stored) in rows {
+                    let labels = stored
+                        .iter()
+                        .map(|label| self.cipher.seal(&self.cipher.open(label)?))
+                        .collect::<WalletResult<Vec<_>>>()?;
+                    rewritten += sqlx::query(&format!(
+                        "UPDATE {table} SET labels = $2 WHERE id = $1 AND labels = $3"
+                    ))
+                    .bind(&id)
+                    .bind(&labels)
+                    .bind(&stored)
+                    .execute(&self.pool)
+                    .await?
+                    .rows_affected();
+                }
+            }
+        }
+
+        Ok(rewritten)
+    }
+
+    // === Transaction ledger ===
+
+    /// Check every hash chain in transaction_ledger (or just `wallet_id`'s),
+    /// reading `batch_size` entries at a time - see ledger::ChainCheck
+    ///
+    /// Also lists transactions that never got a ledger entry. Read-only, so
+    /// safe against a live service; entries appended while it runs are
+    /// checked if the walk hasn't passed their wallet yet.
+    pub async fn verify_ledger(
+        &self,
+        wallet_id: Option<&str>,
+        batch_size: i64,
+    ) -> WalletResult<LedgerVerification> {
+        let mut check = ChainCheck::new();
+        let mut after = (String::new(), 0i64);
+
+        loop {
+            let rows: Vec<LedgerRow> = sqlx::query!(
+                r#"
+                SELECT l.seq, l.transaction_id, l.wallet_id, l.shard, l.amount,
+                       l.type as transaction_type, l.status, l.reference_id, l.created_at,
+                       l.prev_hash, l.hash,
+                       t.id IS NOT NULL AS "transaction_found!",
+                       COALESCE(
+                           t.wallet_id = l.wallet_id AND t.amount = l.amount AND t.type = l.type
+                           AND t.status = l.status AND t.created_at = l.created_at
+                           AND t.reference_id IS NOT DISTINCT FROM l.reference_id,
+                           FALSE
+                       ) AS "transaction_matches!"
+                FROM transaction_ledger l
+                LEFT JOIN (
+                    SELECT id, wallet_id, amount, type, status, reference_id, created_at
+                    FROM wallet_transactions
+                    UNION ALL
+                    SELECT id, wallet_id, amount, type, status, reference_id, created_at
+                    FROM archived_wallet_transactions
+                ) t ON t.id = l.transaction_id
+                WHERE ($1::varchar IS NULL OR l.wallet_id = $1)
+                  AND (l.wallet_id, l.seq) > ($2, $3)
+                ORDER BY l.wallet_id, l.seq
+                LIMIT $4
+                "#,
+                wallet_id,
+                &after.0,
+                after.1,
+                batch_size
+            )
+            .fetch_all(self.reader())
+            .await?
+            .into_iter()
+            .map(|row| LedgerRow {
+                entry: LedgerEntry {
+                    seq: row.seq,
+                    transaction_id: row.transaction_id,
+                    wallet_id: row.wallet_id,
+                    shard: row.shard,
+                    amount: row.amount,
+                    transaction_type: row.transaction_type,
+                    status: row.status,
+                    reference_id: row.reference_id,
+                    created_at: row.created_at,
+                    prev_hash: row.prev_hash,
+                    hash: row.hash,
+                },
+                transaction_found: row.transaction_found,
+                transaction_matches: row.transaction_matches,
+            })
+            .collect();
+            let Some(last) = rows.last() else {
+                break;
+            };
+            after = (last.entry.wallet_id.clone(), last.entry.seq);
+
+            for row in &rows {
+                check.check(&row.entry, row.transaction_found, row.transaction_matches);
+            }
+        }
+
+        let unrecorded = sqlx::query!(
+            r#"
+            SELECT t.wallet_id AS "wallet_id!", t.id AS "id!", COUNT(*) OVER () AS "total!"
+            FROM (
+                SELECT id, wallet_id, created_at FROM wallet_transactions
+                UNION ALL
+                SELECT id, wallet_id, created_at FROM archived_wallet_transactions
+            ) t
+            WHERE ($1::varchar IS NULL OR t.wallet_id = $1)
+              AND NOT EXISTS (SELECT 1 FROM transaction_ledger l WHERE l.transaction_id = t.id)
+            ORDER BY t.wallet_id, t.created_at
+            LIMIT $2
+            "#,
+            wallet_id,
+            MAX_REPORTED_BREAKS as i64
+        )
+        .fetch_all(self.reader())
+        .await?;
+        let total = unrecorded.first().map(|row| row.total).unwrap_or(0);
+        check.unrecorded(
+            total,
+            unrecorded.into_iter().map(|row| (row.wallet_id, row.id)).collect(),
+        );
+
+        Ok(check.finish())
+    }
+
+    // === Erasure (GDPR) ===
+
+    /// Replace a user's ID with a pseudonym everywhere it's stored, and
+    /// drop the free text they wrote
+    ///
+    /// Amounts, types and times stay: the books still have to add up, and
+    /// the transaction ledger (which never held user IDs or memos) stays
+    /// intact. In one DB transaction:
+    /// - wallets and archived wallets: user_id becomes the pseudonym,
+    ///   nicknames and labels are cleared
+    /// - memos on the user's transactions (both legs of a transfer) and
+    ///   pending transfers are cleared
+    /// - the user's beneficiaries, other users' beneficiaries naming them,
+    ///   and the user's transfer templates are deleted
+    /// - KYC and blocklist entries move to the pseudonym
+    /// - queued event and webhook payloads naming the user are rewritten
+    ///
+    /// Support notes, adjustments and admin_audit_log entries are kept as
+    /// they are (compliance records, written by staff). Every wallet has to
+    /// be empty first: nobody could claim an anonymous balance.
+    pub async fn anonymize_user(&self, user_id: &str, actor: &str) -> WalletResult<UserAnonymization> {
+        let mut tx = self.pool.begin().await?;
+
+        let wallets = sqlx::query!(
+            r#"
+            SELECT id, total_balance(wallets) AS "balance!"
+            FROM wallets
+            WHERE user_id = $1
+            ORDER BY id
+            FOR UPDATE
+            "#,
+            user_id
+        )
+        .fetch_all(&mut *tx)
+        .await?;
+        if let Some(wallet) = wallets.iter().find(|wallet| !wallet.balance.is_zero()) {
+            return Err(WalletError::BalanceNotZero(wallet.id.clone()));
+        }
+        let archived = sqlx::query_scalar!(
+            "SELECT id FROM archived_wallets WHERE user_id = $1 ORDER BY id",
+            user_id
+        )
+        .fetch_all(&mut *tx)
+        .await?;
+        let has_kyc = sqlx::query_scalar!(
+            r#"SELECT EXISTS (SELECT 1 FROM user_kyc WHERE user_id = $1) AS "exists!""#,
+            user_id
+        )
+        .fetch_one(&mut *tx)
+        .await?;
+
+        let wallet_ids: Vec<String> = wallets
+            .into_iter()
+            .map(|wallet| wallet.id)
+            .chain(archived)
+            .collect();
+        if wallet_ids.is_empty() && !has_kyc {
+            return Err(WalletError::UserNotFound(user_id.to_string()));
+        }
+
+        let anonymized_user_id = format!("anon-{}", Uuid::new_v4().simple());
+
+        sqlx::query!(
+            "UPDATE wallets SET user_id = $2, nickname = NULL, labels = '{}', version = version + 1 \
+             WHERE user_id = $1",
+            user_id,
+            &anonymized_user_id
+        )
+        .execute(&mut *tx)
+        .await?;
+        sqlx::query!(
+            "UPDATE archived_wallets SET user_id = $2, nickname = NULL, labels = '{}' WHERE user_id = $1",
+            user_id,
+            &anonymized_user_id
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        // The other leg of a transfer carries the same memo
+        for table in ["wallet_transactions", "archived_wallet_transactions"] {
+            sqlx::query(&format!(
+                "UPDATE {table} SET memo = NULL \
+                 WHERE memo IS NOT NULL AND (wallet_id = ANY($1) OR reference_id IN ( \
+                     SELECT reference_id FROM wallet_transactions WHERE wallet_id = ANY($1) \
+                     UNION \
+                     SELECT reference_id FROM archived_wallet_transactions WHERE wallet_id = ANY($1)))"
+            ))
+            .bind(&wallet_ids)
+            .execute(&mut *tx)
+            .await?;
+        }
+        sqlx::query!(
+            "UPDATE pending_transfers SET memo = NULL \
+             WHERE memo IS NOT NULL AND (from_wallet_id = ANY($1) OR to_wallet_id = ANY($1))",
+            &wallet_ids
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        // Templates paying a deleted beneficiary go with it (ON DELETE CASCADE)
+        sqlx::query!("DELETE FROM transfer_templates WHERE wallet_id = ANY($1)", &wallet_ids)
+            .execute(&mut *tx)
+            .await?;
+        sqlx::query!(
+            "DELETE FROM beneficiaries \
+             WHERE user_id = $1 OR beneficiary_user_id = $1 OR wallet_id = ANY($2)",
+            user_id,
+            &wallet_ids
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        sqlx::query!(
+            "UPDATE user_kyc SET user_id = $2 WHERE user_id = $1",
+            user_id,
+            &anonymized_user_id
+        )
+        .execute(&mut *tx)
+        .await?;
+        sqlx::query!(
+            "UPDATE blocklist_entries SET target_id = $2 WHERE target_type = 'user' AND target_id = $1",
+            user_id,
+            &anonymized_user_id
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        // Payloads are JSON text: swap the ID only where it's a whole string
+        let quoted = |id: &str| serde_json::to_string(id).unwrap_or_default();
+        for table in ["event_outbox", "webhook_deliveries"] {
+            sqlx::query(&format!(
+                "UPDATE {table} SET payload = replace(payload, $1, $2) WHERE strpos(payload, $1) > 0"
+            ))
+            .bind(quoted(user_id))
+            .bind(quoted(&anonymized_user_id))
+            .execute(&mut *tx)
+            .await?;
+        }
+
+        let anonymization = UserAnonymization {
+            user_id: user_id.to_string(),
+            anonymized_user_id,
+            wallet_ids,
+            anonymized_at: Utc::now(),
+        };
+
+        // Logged under the pseudonym - the log mustn't keep the mapping
+        self.record_admin_action_in_tx(
+            &mut tx,
+            AuditRecord {
+                actor,
+                action: AUDIT_USER_ANONYMIZED,
+                target_type: "user",
+                target_id: &anonymization.anonymized_user_id,
+                before: None,
+                after: Some(json!({ "wallet_ids": &anonymization.wallet_ids })),
+            },
+        )
+        .await?;
+
+        tx.commit().await?;
+        let mut stale: Vec<CacheKey> = anonymization
+            .wallet_ids
+            .iter()
+            .map(|id| CacheKey::Wallet(id))
+            .collect();
+        stale.push(CacheKey::UserWallets(user_id));
+        stale.push(CacheKey::UserWallets(&anonymization.anonymized_user_id));
+        self.invalidate_cached_keys(&stale).await;
+
+        Ok(anonymization)
+    }
+
+    // === Helper methods for working within transactions ===
+
+    /// Find wallet within an existing transaction
+    async fn find_by_id_in_tx(
+        &self,
+        tx: &mut Transaction<'_, Postgres>,
+        wallet_id: &str,
+    ) -> WalletResult<Wallet> {
+        let wallet = sqlx::query_as!(
+            Wallet,
+            r#"
+            SELECT id, user_id, total_balance(wallets) AS "balance!", version, parent_wallet_id,
+                   nickname, labels, is_default, created_at, updated_at
+            FROM wallets
+            WHERE id = $1
+            "#,
+            wallet_id
+        )
+        .fetch_optional(&mut **tx)
+        .await?
+        .ok_or_else(|| WalletError::WalletNotFound(wallet_id.to_string()))?;
+
+        self.open(wallet)
+    }
+
+    /// Find the round-up rule for a wallet within an existing transaction
+    async fn find_round_up_rule_in_tx(
+        &self,
+        tx: &mut Transaction<'_, Postgres>,
+        wallet_id: &str,
+    ) -> WalletResult<Option<RoundUpRule>> {
+        let rule = sqlx::query_as!(
+            RoundUpRule,
+            r#"
+            SELECT wallet_id, savings_wallet_id, increment, created_at
+            FROM round_up_rules
+            WHERE wallet_id = $1
+            "#,
+            wallet_id
+        )
+        .fetch_optional(&mut **tx)
+        .await?;
+
+        Ok(rule)
+    }
+
+    /// Refuse a transfer if either wallet, or either owner, is blocklisted
+    ///
+    /// The error only says which side - the sender isn't told why the
+    /// recipient is blocked.
+    async fn check_blocklist_in_tx(
+        &self,
+        tx: &mut Transaction<'_, Postgres>,
+        from_wallet: &Wallet,
+        to_wallet: &Wallet,
+    ) -> WalletResult<()> {
+        let side: Option<String> = sqlx::query_scalar!(
+            r#"
+            SELECT CASE WHEN (target_type = 'wallet' AND target_id = $1)
+                          OR (target_type = 'user' AND target_id = $2)
+                        THEN 'sender' ELSE 'recipient' END AS "side!"
+            FROM blocklist_entries
+            WHERE removed_at IS NULL
+              AND ((target_type = 'wallet' AND target_id IN ($1, $3))
+                   OR (target_type = 'user' AND target_id IN ($2, $4)))
+            ORDER BY 1 DESC  -- sender first
+            LIMIT 1
+            "#,
+            &from_wallet.id,
+            &from_wallet.user_id,
+            &to_wallet.id,
+            &to_wallet.user_id
+        )
+        .fetch_optional(&mut **tx)
+        .await?;
+
+        match side.as_deref() {
+            Some("sender") => Err(WalletError::Blocklisted("sender")),
+            Some(_) => Err(WalletError::Blocklisted("recipient")),
+            None => Ok(()),
+        }
+    }
+
+    /// A user's KYC level, read inside a transaction (no row = unverified)
+    async fn kyc_level_in_tx(
+        &self,
+        tx: &mut Transaction<'_, Postgres>,
+        user_id: &str,
+    ) -> WalletResult<KycLevel> {
+        let level = sqlx::query_scalar!(
+            r#"SELECT level AS "level: KycLevel" FROM user_kyc WHERE user_id = $1"#,
+            user_id
+        )
+        .fetch_optional(&mut **tx)
+        .await?;
+
+        Ok(level.unwrap_or(KycLevel::Unverified))
+    }
+
+    /// Refuse crediting `amount` to a user it would take over their KYC
+    /// balance cap (summed over all their wallets, pots included)
+    async fn check_kyc_credit_in_tx(
+        &self,
+        tx: &mut Transaction<'_, Postgres>,
+        user_id: &str,
+        amount: Decimal,
+    ) -> WalletResult<()> {
+        if self.kyc.unverified_max_balance.is_none() {
+            return Ok(());
+        }
+        let level = self.kyc_level_in_tx(tx, user_id).await?;
+        if level != KycLevel::Unverified {
+            return Ok(());
+        }
+
+        let total = sqlx::query_scalar!(
+            r#"SELECT COALESCE(SUM(total_balance(wallets)), 0) AS "total!" FROM wallets WHERE user_id = $1"#,
+            user_id
+        )
+        .fetch_one(&mut **tx)
+        .await?;
+
+        self.kyc.check_balance(level, total + amount)
+    }
+
+    /// Refuse a transfer over the sender's KYC transfer cap
+    async fn check_kyc_transfer_in_tx(
+        &self,
+        tx: &mut Transaction<'_, Postgres>,
+        user_id: &str,
+        amount: Decimal,
+    ) -> WalletResult<()> {
+        if self.kyc.unverified_max_transfer.is_none() {
+            return Ok(());
+        }
+        let level = self.kyc_level_in_tx(tx, user_id).await?;
+
+        self.kyc.check_transfer(level, amount)
+    }
+
+    /// Refuse a transfer of `amount` that would break a velocity limit
+    ///
+    /// Call with the sender locked. Counts outgoing transfers only -
+    /// round-ups and pot moves stay within the user's own wallets.
+    async fn check_velocity_in_tx(
+        &self,
+        tx: &mut Transaction<'_, Postgres>,
+        wallet_id: &str,
+        amount: Decimal,
+    ) -> WalletResult<()> {
+        if self.velocity.is_off() {
+            return Ok(());
+        }
+
+        let sent = sqlx::query!(
+            r#"
+            SELECT COUNT(*) AS "count!", COALESCE(SUM(amount), 0) AS "volume!"
+            FROM wallet_transactions
+            WHERE wallet_id = $1
+              AND type = 'TRANSFER_OUT'
+              AND created_at > NOW() - make_interval(secs => $2)
+            "#,
+            wallet_id,
+            self.velocity.window.as_secs_f64()
+        )
+        .fetch_one(&mut **tx)
+        .await?;
+
+        self.velocity.check(sent.count, sent.volume, amount)
+    }
+
+    /// Lock a wallet for update (prevents concurrent modifications)
+    async fn lock_wallet_in_tx(
+        &self,
+        tx: &mut Transaction<'_, Postgres>,
+        wallet_id: &str,
+    ) -> WalletResult<Wallet> {
+        let row = sqlx::query!(
+            r#"
+            SELECT id, user_id, balance, version, parent_wallet_id, nickname, labels, is_default,
+                   created_at, updated_at, balance_shards
+            FROM wallets
+            WHERE id = $1
+            FOR UPDATE  -- This is the lock!
+            "#,
+            wallet_id
+        )
+        .fetch_optional(&mut **tx)
+        .await?
+        .ok_or_else(|| WalletError::WalletNotFound(wallet_id.to_string()))?;
+
+        let shards = row.balance_shards;
+        let mut wallet = wallet_from_record!(row);
+        if shards > 0 {
+            wallet.balance = self.fold_shards_in_tx(tx, wallet_id).await?;
+        }
+
+        self.open(wallet)
+    }
+
+    /// Move a sharded wallet's shard balances back into its row; returns
+    /// the row's balance, now the whole balance
+    ///
+    /// Callers hold the wallet's row lock, which every shard credit waits
+    /// for (FOR KEY SHARE), so no credit lands in between. The shard rows
+    /// stay, at zero.
+    async fn fold_shards_in_tx(
+        &self,
+        tx: &mut Transaction<'_, Postgres>,
+        wallet_id: &str,
+    ) -> WalletResult<Decimal> {
+        let balance = sqlx::query_scalar!(
+            r#"
+            WITH drained AS (
+                UPDATE wallet_balance_shards s
+                SET balance = 0
+                FROM (
+                    SELECT shard, balance
+                    FROM wallet_balance_shards
+                    WHERE wallet_id = $1 AND balance <> 0
+                    FOR UPDATE
+                ) held
+                WHERE s.wallet_id = $1 AND s.shard = held.shard
+                RETURNING held.balance
+            )
+            UPDATE wallets
+            SET balance = balance + (SELECT COALESCE(SUM(balance), 0) FROM drained)
+            WHERE id = $1
+            RETURNING balance
+            "#,
+            wallet_id
+        )
+        .fetch_one(&mut **tx)
+        .await?;
+
+        Ok(balance)
+    }
+
+    /// Read a sharded transfer recipient without taking its row lock
+    ///
+    /// FOR KEY SHARE: other credits go ahead at the same time, while
+    /// whatever folds the shards (FOR UPDATE) waits for them. Conflict if
+    /// the wallet was unsharded since the caller looked.
+    async fn share_sharded_wallet_in_tx(
+        &self,
+        tx: &mut Transaction<'_, Postgres>,
+        wallet_id: &str,
+    ) -> WalletResult<(Wallet, i32)> {
+        let row = sqlx::query!(
+            r#"
+            SELECT id, user_id, total_balance(wallets) AS "balance!", version, parent_wallet_id,
+                   nickname, labels, is_default, created_at, updated_at, balance_shards
+            FROM wallets
+            WHERE id = $1
+            FOR KEY SHARE
+            "#,
+            wallet_id
         )
-        .bind(wallet_id)
         .fetch_optional(&mut **tx)
         .await?
         .ok_or_else(|| WalletError::WalletNotFound(wallet_id.to_string()))?;
 
-        Ok(wallet)
+        let shards = row.balance_shards;
+        if shards == 0 {
+            return Err(WalletError::OptimisticLockError);
+        }
+        Ok((self.open(wallet_from_record!(row))?, shards))
+    }
+
+    /// How many shards a wallet's balance is split over (0: not sharded,
+    /// or no such wallet)
+    async fn balance_shards_in_tx(
+        &self,
+        tx: &mut Transaction<'_, Postgres>,
+        wallet_id: &str,
+    ) -> WalletResult<i32> {
+        let shards = sqlx::query_scalar!(
+            "SELECT balance_shards FROM wallets WHERE id = $1",
+            wallet_id
+        )
+        .fetch_optional(&mut **tx)
+        .await?;
+
+        Ok(shards.unwrap_or(0))
+    }
+
+    /// Add `amount` to the next of a sharded wallet's shards (round-robin);
+    /// returns the shard
+    async fn credit_shard_in_tx(
+        &self,
+        tx: &mut Transaction<'_, Postgres>,
+        wallet_id: &str,
+        shards: i32,
+        amount: Decimal,
+    ) -> WalletResult<i32> {
+        let shard = sqlx::query_scalar!(
+            r#"
+            UPDATE wallet_balance_shards
+            SET balance = balance + $2
+            WHERE wallet_id = $1
+              AND shard = (SELECT nextval('wallet_balance_shard_picks') % $3)::INTEGER
+            RETURNING shard
+            "#,
+            wallet_id,
+            amount,
+            shards as i64
+        )
+        .fetch_optional(&mut **tx)
+        .await?
+        .ok_or(WalletError::OptimisticLockError)?;
+
+        Ok(shard)
     }
 
     /// Create a transaction record within an existing database transaction
+    #[allow(clippy::too_many_arguments)]
     async fn create_transaction_in_tx(
         &self,
         tx: &mut Transaction<'_, Postgres>,
@@ -338,27 +4235,183 @@ impl WalletRepository {
         transaction_type: TransactionType,
         status: TransactionStatus,
         reference_id: Option<&str>,
+        memo: Option<&str>,
+    ) -> WalletResult<WalletTransaction> {
+        self.create_shard_transaction_in_tx(
+            tx,
+            wallet_id,
+            None,
+            amount,
+            transaction_type,
+            status,
+            reference_id,
+            memo,
+        )
+        .await
+    }
+
+    /// `create_transaction_in_tx`, chained onto `shard`'s ledger chain
+    /// (None: the wallet's own)
+    #[allow(clippy::too_many_arguments)]
+    async fn create_shard_transaction_in_tx(
+        &self,
+        tx: &mut Transaction<'_, Postgres>,
+        wallet_id: &str,
+        shard: Option<i32>,
+        amount: Decimal,
+        transaction_type: TransactionType,
+        status: TransactionStatus,
+        reference_id: Option<&str>,
+        memo: Option<&str>,
     ) -> WalletResult<WalletTransaction> {
         let transaction_id = Uuid::new_v4().to_string();
         let now = Utc::now();
 
-        let transaction = sqlx::query_as::<_, WalletTransaction>(
+        let transaction = sqlx::query_as!(
+            WalletTransaction,
             r#"
-            INSERT INTO wallet_transactions (id, wallet_id, amount, type, status, reference_id, created_at)
-            VALUES ($1, $2, $3, $4, $5, $6, $7)
-            RETURNING id, wallet_id, amount, type as transaction_type, status, reference_id, created_at
+            INSERT INTO wallet_transactions (id, wallet_id, amount, type, status, reference_id, memo, created_at)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+            RETURNING id, wallet_id, amount, type AS "transaction_type: TransactionType",
+                      status AS "status: TransactionStatus", reference_id, memo, created_at
             "#,
+            &transaction_id,
+            wallet_id,
+            amount,
+            transaction_type.to_string(),
+            status.to_string(),
+            reference_id,
+            self.cipher.seal_opt(memo)?,
+            now
         )
-        .bind(&transaction_id)
-        .bind(wallet_id)
-        .bind(amount)
-        .bind(transaction_type.to_string())
-        .bind(status.to_string())
-        .bind(reference_id)
-        .bind(now)
         .fetch_one(&mut **tx)
         .await?;
 
-        Ok(transaction)
+        self.append_ledger_in_tx(tx, &transaction, shard).await?;
+
+        self.open(transaction)
+    }
+
+    /// Chain `transaction` onto its wallet's ledger, or its shard's chain
+    /// (see ledger.rs)
+    ///
+    /// Callers hold the wallet's row lock (the shard's, for a shard
+    /// credit), so nobody appends in between; if one ever didn't, the
+    /// unique (wallet_id, shard, prev_hash) index fails the second writer
+    /// instead of forking the chain.
+    async fn append_ledger_in_tx(
+        &self,
+        tx: &mut Transaction<'_, Postgres>,
+        transaction: &WalletTransaction,
+        shard: Option<i32>,
+    ) -> WalletResult<()> {
+        let prev_hash = sqlx::query_scalar!(
+            r#"
+            SELECT hash FROM transaction_ledger
+            WHERE wallet_id = $1 AND COALESCE(shard, -1) = COALESCE($2, -1)
+            ORDER BY seq DESC
+            LIMIT 1
+            "#,
+            &transaction.wallet_id,
+            shard
+        )
+        .fetch_optional(&mut **tx)
+        .await?
+        .unwrap_or_else(|| GENESIS_HASH.to_string());
+
+        let transaction_type = transaction.transaction_type.to_string();
+        let status = transaction.status.to_string();
+        let hash = ledger::entry_hash(
+            &prev_hash,
+            &transaction.id,
+            &transaction.wallet_id,
+            transaction.amount,
+            &transaction_type,
+            &status,
+            transaction.reference_id.as_deref(),
+            transaction.created_at,
+        );
+
+        sqlx::query!(
+            r#"
+            INSERT INTO transaction_ledger
+                (transaction_id, wallet_id, shard, amount, type, status, reference_id, created_at,
+                 prev_hash, hash)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10)
+            "#,
+            &transaction.id,
+            &transaction.wallet_id,
+            shard,
+            transaction.amount,
+            &transaction_type,
+            &status,
+            transaction.reference_id.as_deref(),
+            transaction.created_at,
+            &prev_hash,
+            &hash
+        )
+        .execute(&mut **tx)
+        .await?;
+
+        Ok(())
+    }
+}
+
+/// Text columns `FieldCipher` seals, for `rekey_fields`: (table, column,
+/// blind index column to recompute). Wallet labels are handled separately.
+const ENCRYPTED_COLUMNS: &[(&str, &str, Option<&str>)] = &[
+    ("wallet_transactions", "memo", None),
+    ("archived_wallet_transactions", "memo", None),
+    ("transfer_templates", "memo", None),
+    ("pending_transfers", "memo", None),
+    ("transaction_notes", "note", None),
+    ("archived_transaction_notes", "note", None),
+    ("wallets", "nickname", None),
+    ("archived_wallets", "nickname", None),
+    ("beneficiaries", "name", Some("name_hash")),
+];
+
+/// A ledger entry plus how it compares with its transaction, for
+/// `verify_ledger`
+struct LedgerRow {
+    entry: LedgerEntry,
+    transaction_found: bool,
+    transaction_matches: bool,
+}
+
+/// What an admin did, for `record_admin_action_in_tx`
+struct AuditRecord<'a> {
+    actor: &'a str,
+    action: &'static str,
+    target_type: &'static str,
+    target_id: &'a str,
+    before: Option<serde_json::Value>,
+    after: Option<serde_json::Value>,
+}
+
+/// A wallet_pins row with the hash, for checking - never leaves here
+struct PinRecord {
+    pin_hash: String,
+    threshold: Decimal,
+    locked_until: Option<DateTime<Utc>>,
+}
+
+/// Random voucher code, e.g. "K7QX4M2PZR9WHT3C"
+fn generate_voucher_code() -> String {
+    const ALPHABET: &[u8] = b"ABCDEFGHJKMNPQRSTUVWXYZ23456789";
+    let mut rng = rand::thread_rng();
+
+    (0..16)
+        .map(|_| ALPHABET[rng.gen_range(0..ALPHABET.len())] as char)
+        .collect()
+}
+
+/// Refuse the change if the client's If-Match version is out of date
+fn check_version(wallet: &Wallet, expected_version: Option<i64>) -> WalletResult<()> {
+    match expected_version {
+        Some(expected) if expected != wallet.version => Err(WalletError::PreconditionFailed {
+            current_version: wallet.version,
+        }),
+        _ => Ok(()),
     }
 }
@@ -0,0 +1,124 @@
+use crate::errors::{WalletError, WalletResult};
+use serde_json::{json, Value};
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpStream;
+
+const CONTENT_TYPE: &str = "application/vnd.schemaregistry.v1+json";
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Minimal Confluent Schema Registry client
+///
+/// Only the two calls the producer needs at startup:
+/// - set the subject's compatibility level (the evolution rule)
+/// - register the schema and get its ID for the wire format
+///
+/// Plain `http://` only - the registry is expected on the internal network.
+pub struct SchemaRegistryClient {
+    host: String,
+    port: u16,
+}
+
+impl SchemaRegistryClient {
+    /// `url` like `http://localhost:8081`
+    pub fn new(url: &str) -> WalletResult<Self> {
+        let authority = url
+            .strip_prefix("http://")
+            .ok_or_else(|| registry_error("only http:// registry URLs are supported"))?
+            .trim_end_matches('/');
+
+        let (host, port) = match authority.rsplit_once(':') {
+            Some((host, port)) => (
+                host.to_string(),
+                port.parse()
+                    .map_err(|_| registry_error(&format!("invalid port in {}", url)))?,
+            ),
+            None => (authority.to_string(), 80),
+        };
+
+        Ok(Self { host, port })
+    }
+
+    /// Set the compatibility rule (BACKWARD, FORWARD, FULL, NONE, ...)
+    ///
+    /// BACKWARD (the default) means consumers on the new schema can read
+    /// events written with the previous one: add fields only with defaults.
+    pub async fn set_compatibility(&self, subject: &str, level: &str) -> WalletResult<()> {
+        self.request(
+            "PUT",
+            &format!("/config/{}", subject),
+            &json!({ "compatibility": level }),
+        )
+        .await?;
+        Ok(())
+    }
+
+    /// Register a schema under a subject and return its ID
+    ///
+    /// Registering an unchanged schema returns the existing ID. An
+    /// incompatible change is rejected by the registry (409).
+    pub async fn register(&self, subject: &str, schema: &str) -> WalletResult<u32> {
+        let response = self
+            .request(
+                "POST",
+                &format!("/subjects/{}/versions", subject),
+                &json!({ "schema": schema }),
+            )
+            .await?;
+
+        response
+            .get("id")
+            .and_then(Value::as_u64)
+            .map(|id| id as u32)
+            .ok_or_else(|| registry_error(&format!("no schema id in response: {}", response)))
+    }
+
+    /// One HTTP/1.0 request (so the reply is never chunked)
+    async fn request(&self, method: &str, path: &str, body: &Value) -> WalletResult<Value> {
+        let body = body.to_string();
+        let request = format!(
+            "{} {} HTTP/1.0\r\nHost: {}:{}\r\nContent-Type: {}\r\nAccept: {}\r\nContent-Length: {}\r\n\r\n{}",
+            method,
+            path,
+            self.host,
+            self.port,
+            CONTENT_TYPE,
+            CONTENT_TYPE,
+            body.len(),
+            body
+        );
+
+        let exchange = async {
+            let mut stream = TcpStream::connect((self.host.as_str(), self.port)).await?;
+            stream.write_all(request.as_bytes()).await?;
+            let mut response = Vec::new();
+            stream.read_to_end(&mut response).await?;
+            Ok::<_, std::io::Error>(response)
+        };
+
+        let response = tokio::time::timeout(REQUEST_TIMEOUT, exchange)
+            .await
+            .map_err(|_| registry_error("request timed out"))?
+            .map_err(|e| registry_error(&e.to_string()))?;
+
+        let response = String::from_utf8_lossy(&response);
+        let (head, body) = response
+            .split_once("\r\n\r\n")
+            .ok_or_else(|| registry_error("malformed HTTP response"))?;
+
+        let status: u16 = head
+            .split_whitespace()
+            .nth(1)
+            .and_then(|code| code.parse().ok())
+            .ok_or_else(|| registry_error("malformed HTTP status line"))?;
+        if !(200..300).contains(&status) {
+            return Err(registry_error(&format!("{} {} returned {}: {}", method, path, status, body)));
+        }
+
+        serde_json::from_str(body).map_err(|e| registry_error(&format!("invalid JSON reply: {}", e)))
+    }
+}
+
+fn registry_error(message: &str) -> WalletError {
+    WalletError::InternalError(format!("Schema registry: {}", message))
+}
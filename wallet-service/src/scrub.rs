@@ -0,0 +1,211 @@
+use crate::errors::{WalletError, WalletResult};
+use crate::models::{Wallet, WalletTransaction};
+use crate::repository::WalletRepository;
+use crate::signing::hmac_sha256;
+use hmac::Mac;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use rust_decimal::Decimal;
+use std::io::Write;
+use uuid::Uuid;
+
+/// Scrubs production data so it can be loaded into staging
+/// 
+/// What gets changed:
+/// - User IDs -> keyed hash ("user_3f9a..."), same input = same output
+/// - Wallet / transaction / reference IDs -> keyed hash shaped like a UUID
+/// - Amounts and balances -> multiplied by one secret scale factor
+/// - Memos, nicknames and labels -> dropped (free text is where PII hides)
+/// 
+/// Why ONE scale factor instead of per-row noise?
+/// - Per-row noise breaks accounting (transfer legs stop matching,
+///   balances stop equalling the sum of their transactions)
+/// - A global factor keeps the shape of the distribution exactly
+/// - Without the seed the real amounts can't be recovered
+/// 
+/// Why keyed hashes?
+/// - Deterministic, so references between rows survive
+/// - Without the secret, pseudonyms can't be brute-forced back
+pub struct Scrubber {
+    secret: Vec<u8>,
+    scale: Decimal,
+}
+
+impl Scrubber {
+    /// Create a scrubber from a secret and a seed for the scale factor
+    /// 
+    /// The scale factor is drawn from [0.5000, 1.5000)
+    pub fn new(secret: &[u8], seed: u64) -> Self {
+        let mut rng = StdRng::seed_from_u64(seed);
+        let scale = Decimal::new(rng.gen_range(5_000..15_000), 4);
+
+        Self {
+            secret: secret.to_vec(),
+            scale,
+        }
+    }
+
+    /// The factor every amount is multiplied by
+    pub fn scale(&self) -> Decimal {
+        self.scale
+    }
+
+    fn digest(&self, value: &str) -> [u8; 32] {
+        let mut mac = hmac_sha256(&self.secret);
+        mac.update(value.as_bytes());
+        mac.finalize().into_bytes().into()
+    }
+
+    /// Pseudonymize a user ID
+    pub fn user_id(&self, user_id: &str) -> String {
+        let digest = self.digest(user_id);
+        format!("user_{}", hex::encode(&digest[..8]))
+    }
+
+    /// Pseudonymize a UUID-shaped identifier (wallet, transaction, reference)
+    pub fn id(&self, id: &str) -> String {
+        let digest = self.digest(id);
+        let mut bytes = [0u8; 16];
+        bytes.copy_from_slice(&digest[..16]);
+        Uuid::from_bytes(bytes).to_string()
+    }
+
+    /// Perturb an amount (4 decimal places, like the DB column)
+    pub fn amount(&self, amount: Decimal) -> Decimal {
+        (amount * self.scale).round_dp(4)
+    }
+
+    /// Scrub a wallet together with its full transaction history
+    /// 
+    /// The balance is recomputed from the scrubbed transactions so that
+    /// balance == sum(credits) - sum(debits) still holds after rounding
+    pub fn scrub_wallet(
+        &self,
+        wallet: &Wallet,
+        transactions: &[WalletTransaction],
+    ) -> (Wallet, Vec<WalletTransaction>) {
+        let scrubbed_transactions: Vec<WalletTransaction> = transactions
+            .iter()
+            .map(|txn| WalletTransaction {
+                id: self.id(&txn.id),
+                wallet_id: self.id(&txn.wallet_id),
+                amount: self.amount(txn.amount),
+                transaction_type: txn.transaction_type.clone(),
+                status: txn.status.clone(),
+                reference_id: txn.reference_id.as_deref().map(|r| self.id(r)),
+                memo: None,
+                created_at: txn.created_at,
+            })
+            .collect();
+
+        let balance = scrubbed_transactions
+            .iter()
+            .fold(Decimal::ZERO, |balance, txn| balance + txn.signed_amount())
+            .max(Decimal::ZERO);
+
+        let scrubbed_wallet = Wallet {
+            id: self.id(&wallet.id),
+            user_id: self.user_id(&wallet.user_id),
+            balance,
+            version: wallet.version,
+            parent_wallet_id: wallet.parent_wallet_id.as_deref().map(|p| self.id(p)),
+            nickname: None,
+            labels: Vec::new(),
+            is_default: wallet.is_default,
+            created_at: wallet.created_at,
+            updated_at: wallet.updated_at,
+        };
+
+        (scrubbed_wallet, scrubbed_transactions)
+    }
+}
+
+/// Counts reported after an export
+#[derive(Debug, Default)]
+pub struct ExportStats {
+    pub wallets: usize,
+    pub transactions: usize,
+}
+
+/// Export a scrubbed random sample of wallets as SQL INSERT statements
+/// 
+/// Referential integrity:
+/// - Each sampled wallet is exported with ALL of its transactions
+/// - Pots are exported right after their parent wallet
+/// - Counterparty legs of transfers only appear if that wallet was sampled too
+/// - The output runs in one transaction against an empty schema
+pub async fn export_sql(
+    repository: &WalletRepository,
+    scrubber: &Scrubber,
+    sample_size: i64,
+    out: &mut impl Write,
+) -> WalletResult<ExportStats> {
+    let mut stats = ExportStats::default();
+    let io_error = |e: std::io::Error| WalletError::InternalError(format!("Export write failed: {}", e));
+
+    writeln!(out, "-- Scrubbed wallet data export (amount scale is secret)").map_err(io_error)?;
+    writeln!(out, "BEGIN;").map_err(io_error)?;
+
+    for parent in repository.sample_wallets(sample_size).await? {
+        let pots = repository.find_pots(&parent.id).await?;
+
+        for wallet in std::iter::once(parent).chain(pots) {
+            let transactions = repository.find_wallet_transactions(&wallet.id).await?;
+            let (wallet, transactions) = scrubber.scrub_wallet(&wallet, &transactions);
+            write_wallet_sql(out, &wallet, &transactions).map_err(io_error)?;
+
+            stats.wallets += 1;
+            stats.transactions += transactions.len();
+        }
+    }
+
+    writeln!(out, "COMMIT;").map_err(io_error)?;
+
+    Ok(stats)
+}
+
+/// Write the INSERT statements for one scrubbed wallet and its transactions
+fn write_wallet_sql(
+    out: &mut impl Write,
+    wallet: &Wallet,
+    transactions: &[WalletTransaction],
+) -> std::io::Result<()> {
+    let parent_wallet_id = sql_nullable(wallet.parent_wallet_id.as_deref());
+
+    writeln!(
+        out,
+        "INSERT INTO wallets (id, user_id, balance, version, parent_wallet_id, created_at, updated_at) \
+         VALUES ('{}', '{}', {}, {}, {}, '{}', '{}');",
+        wallet.id,
+        wallet.user_id,
+        wallet.balance,
+        wallet.version,
+        parent_wallet_id,
+        wallet.created_at.to_rfc3339(),
+        wallet.updated_at.to_rfc3339(),
+    )?;
+
+    for txn in transactions {
+        writeln!(
+            out,
+            "INSERT INTO wallet_transactions (id, wallet_id, amount, type, status, reference_id, created_at) \
+             VALUES ('{}', '{}', {}, '{}', '{}', {}, '{}');",
+            txn.id,
+            txn.wallet_id,
+            txn.amount,
+            txn.transaction_type,
+            txn.status,
+            sql_nullable(txn.reference_id.as_deref()),
+            txn.created_at.to_rfc3339(),
+        )?;
+    }
+
+    Ok(())
+}
+
+/// Quote a scrubbed (hex/UUID-only) value, or NULL
+fn sql_nullable(value: Option<&str>) -> String {
+    value
+        .map(|v| format!("'{}'", v))
+        .unwrap_or_else(|| "NULL".to_string())
+}
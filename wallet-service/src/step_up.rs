@@ -0,0 +1,213 @@
+use crate::errors::{WalletError, WalletResult};
+use crate::models::PendingTransfer;
+use crate::repository::WalletRepository;
+use crate::shutdown::Shutdown;
+use rand::Rng;
+use rust_decimal::Decimal;
+use sha2::{Digest, Sha256};
+use std::str::FromStr;
+use std::time::Duration;
+
+/// Longest wait for STEP_UP_NOTIFY_URL to take a code
+const NOTIFY_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Which transfers need a one-time code, and how long the code lives
+///
+/// Why step up at all?
+/// - A stolen token (or PIN) shouldn't be enough to empty a wallet: over
+///   the threshold, a code sent to the owner out of band has to come back
+/// - No money moves until the code is confirmed, so an abandoned or
+///   refused transfer needs no reversal
+///
+/// Wrong codes are also counted per wallet, across its transfers: starting
+/// a fresh transfer for each batch of guesses would otherwise get round
+/// `max_attempts`. `wallet_max_failures` of them lock step-up for the
+/// wallet, each lockout twice as long as the last (up to `max_lockout`).
+#[derive(Debug, Clone)]
+pub struct StepUpConfig {
+    /// STEP_UP_THRESHOLD - transfers over this wait for a code (unset: none do)
+    pub threshold: Option<Decimal>,
+    /// STEP_UP_CODE_TTL_SECS - how long a code can be confirmed
+    pub code_ttl: Duration,
+    /// STEP_UP_MAX_ATTEMPTS - wrong codes before the transfer is cancelled
+    pub max_attempts: u32,
+    /// STEP_UP_WALLET_MAX_FAILURES - wrong codes on a wallet's transfers
+    /// before step-up is locked for it
+    pub wallet_max_failures: u32,
+    /// STEP_UP_LOCKOUT_SECS - how long the first lockout lasts
+    pub lockout: Duration,
+    /// STEP_UP_MAX_LOCKOUT_SECS - the longest a lockout gets
+    pub max_lockout: Duration,
+    /// STEP_UP_NOTIFY_URL - where codes are POSTed for delivery to the user
+    pub notify_url: Option<String>,
+    /// STEP_UP_SWEEP_SECS - time between expiry passes
+    pub sweep_interval: Duration,
+}
+
+impl Default for StepUpConfig {
+    fn default() -> Self {
+        Self {
+            threshold: None,
+            code_ttl: Duration::from_secs(5 * 60),
+            max_attempts: 3,
+            wallet_max_failures: 10,
+            lockout: Duration::from_secs(15 * 60),
+            max_lockout: Duration::from_secs(24 * 60 * 60),
+            notify_url: None,
+            sweep_interval: Duration::from_secs(60),
+        }
+    }
+}
+
+impl StepUpConfig {
+    pub fn from_env() -> Self {
+        fn var(name: &str) -> Option<String> {
+            std::env::var(name).ok().filter(|v| !v.is_empty())
+        }
+
+        let defaults = Self::default();
+        Self {
+            threshold: var("STEP_UP_THRESHOLD")
+                .and_then(|v| Decimal::from_str(&v).ok())
+                .filter(|t| *t >= Decimal::ZERO),
+            code_ttl: var("STEP_UP_CODE_TTL_SECS")
+                .and_then(|v| v.parse().ok())
+                .filter(|&secs| secs > 0)
+                .map(Duration::from_secs)
+                .unwrap_or(defaults.code_ttl),
+            max_attempts: var("STEP_UP_MAX_ATTEMPTS")
+                .and_then(|v| v.parse().ok())
+                .filter(|&n| n > 0)
+                .unwrap_or(defaults.max_attempts),
+            wallet_max_failures: var("STEP_UP_WALLET_MAX_FAILURES")
+                .and_then(|v| v.parse().ok())
+                .filter(|&n| n > 0)
+                .unwrap_or(defaults.wallet_max_failures),
+            lockout: var("STEP_UP_LOCKOUT_SECS")
+                .and_then(|v| v.parse().ok())
+                .map(Duration::from_secs)
+                .unwrap_or(defaults.lockout),
+            max_lockout: var("STEP_UP_MAX_LOCKOUT_SECS")
+                .and_then(|v| v.parse().ok())
+                .map(Duration::from_secs)
+                .unwrap_or(defaults.max_lockout),
+            notify_url: var("STEP_UP_NOTIFY_URL"),
+            sweep_interval: var("STEP_UP_SWEEP_SECS")
+                .and_then(|v| v.parse().ok())
+                .filter(|&secs| secs > 0)
+                .map(Duration::from_secs)
+                .unwrap_or(defaults.sweep_interval),
+        }
+    }
+
+    /// Does a transfer of `amount` have to wait for a code?
+    pub fn applies_to(&self, amount: Decimal) -> bool {
+        self.threshold.is_some_and(|threshold| amount > threshold)
+    }
+}
+
+/// A fresh 6-digit code, e.g. "042917"
+pub fn generate_code() -> String {
+    format!("{:06}", rand::thread_rng().gen_range(0..1_000_000))
+}
+
+/// SHA-256 of a code as hex - what pending_transfers stores
+///
+/// A plain hash is enough here: the code lives minutes and only
+/// STEP_UP_MAX_ATTEMPTS guesses are allowed against it.
+pub fn hash_code(code: &str) -> String {
+    hex::encode(Sha256::digest(code.as_bytes()))
+}
+
+/// Gets a code to the wallet's owner
+///
+/// POSTs `{"pending_transfer_id", "user_id", "wallet_id", "amount", "code",
+/// "expires_at"}` to STEP_UP_NOTIFY_URL (the notification service sends
+/// the SMS / email / push). Without a URL the code is only logged - local
+/// development only.
+#[derive(Clone)]
+pub struct CodeNotifier {
+    client: reqwest::Client,
+    url: Option<String>,
+}
+
+impl CodeNotifier {
+    pub fn new(url: Option<String>) -> WalletResult<Self> {
+        let client = crate::tls::client_builder()
+            .map_err(|e| WalletError::InternalError(format!("Step-up client TLS: {}", e)))?
+            .timeout(NOTIFY_TIMEOUT)
+            .redirect(reqwest::redirect::Policy::none())
+            .user_agent("wallet-service-step-up")
+            .build()
+            .map_err(|e| WalletError::InternalError(format!("Failed to build HTTP client: {}", e)))?;
+        Ok(Self { client, url })
+    }
+
+    pub async fn send(&self, pending: &PendingTransfer, user_id: &str, code: &str) -> WalletResult<()> {
+        let Some(url) = &self.url else {
+            tracing::warn!(
+                pending_transfer_id = %pending.id,
+                code = %code,
+                "STEP_UP_NOTIFY_URL unset - verification code logged instead of sent"
+            );
+            return Ok(());
+        };
+
+        let body = serde_json::json!({
+            "pending_transfer_id": pending.id,
+            "user_id": user_id,
+            "wallet_id": pending.from_wallet_id,
+            "amount": pending.amount,
+            "code": code,
+            "expires_at": pending.expires_at,
+        });
+        let response = self
+            .client
+            .post(url)
+            .json(&body)
+            .send()
+            .await
+            .map_err(|e| WalletError::InternalError(format!("Failed to send verification code: {}", e)))?;
+
+        if response.status().is_success() {
+            Ok(())
+        } else {
+            Err(WalletError::InternalError(format!(
+                "Verification code not accepted: {}",
+                response.status()
+            )))
+        }
+    }
+}
+
+/// Expires unconfirmed transfers in the background
+pub struct PendingTransferSweeper {
+    repository: WalletRepository,
+    interval: Duration,
+}
+
+impl PendingTransferSweeper {
+    pub fn new(repository: WalletRepository, interval: Duration) -> Self {
+        Self { repository, interval }
+    }
+
+    /// Run a pass every `interval` in the background, until shutdown
+    pub fn spawn(self, shutdown: Shutdown) {
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(self.interval);
+
+            loop {
+                tokio::select! {
+                    _ = ticker.tick() => {}
+                    () = shutdown.wait() => return,
+                }
+
+                match self.repository.expire_pending_transfers().await {
+                    Ok(0) => {}
+                    Ok(expired) => tracing::info!(expired, "Expired unconfirmed transfers"),
+                    Err(e) => tracing::error!(error = %e, "Failed to expire unconfirmed transfers"),
+                }
+            }
+        });
+    }
+}
@@ -0,0 +1,132 @@
+use crate::correlation;
+use crate::errors::ErrorResponse;
+use axum::{
+    extract::{MatchedPath, Request, State},
+    http::StatusCode,
+    middleware::Next,
+    response::{IntoResponse, Response},
+    Json,
+};
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+
+/// How long a request may take before the client gets a 504
+#[derive(Debug, Clone)]
+pub struct TimeoutConfig {
+    /// REQUEST_TIMEOUT_MS - every route without its own timeout
+    pub default: Duration,
+    /// REQUEST_TIMEOUT_ROUTES - per-route overrides, keyed by route pattern
+    pub routes: HashMap<String, Duration>,
+}
+
+impl Default for TimeoutConfig {
+    fn default() -> Self {
+        Self {
+            default: Duration::from_secs(10),
+            // Moves up to 1000 wallets in one DB transaction
+            routes: HashMap::from([(
+                "/admin/wallets/archive".to_string(),
+                Duration::from_secs(60),
+            )]),
+        }
+    }
+}
+
+impl TimeoutConfig {
+    /// REQUEST_TIMEOUT_ROUTES is `pattern=ms` pairs, comma separated, e.g.
+    /// `/wallets/:wallet_id/transfer=5000,/admin/audit-log=30000`
+    pub fn from_env() -> Self {
+        let mut config = Self::default();
+        if let Some(ms) = std::env::var("REQUEST_TIMEOUT_MS")
+            .ok()
+            .and_then(|v| v.parse().ok())
+        {
+            config.default = Duration::from_millis(ms);
+        }
+        if let Ok(routes) = std::env::var("REQUEST_TIMEOUT_ROUTES") {
+            config.routes.extend(parse_routes(&routes));
+        }
+        config
+    }
+
+    /// The timeout for a route pattern (as matched, e.g. `/wallets/:wallet_id`)
+    pub fn for_route(&self, route: &str) -> Duration {
+        self.routes.get(route).copied().unwrap_or(self.default)
+    }
+}
+
+/// `pattern=ms` pairs; malformed entries are logged and skipped
+pub fn parse_routes(value: &str) -> HashMap<String, Duration> {
+    value
+        .split(',')
+        .map(str::trim)
+        .filter(|entry| !entry.is_empty())
+        .filter_map(|entry| {
+            let parsed = entry
+                .rsplit_once('=')
+                .and_then(|(route, ms)| Some((route.trim(), ms.trim().parse().ok()?)));
+            match parsed {
+                Some((route, ms)) if route.starts_with('/') => {
+                    Some((route.to_string(), Duration::from_millis(ms)))
+                }
+                _ => {
+                    tracing::warn!(entry, "Ignoring malformed REQUEST_TIMEOUT_ROUTES entry");
+                    None
+                }
+            }
+        })
+        .collect()
+}
+
+/// Middleware: answer 504 when a route takes longer than its timeout
+///
+/// Why not just drop the handler (tower's Timeout)?
+/// - Dropping it between the DB commit and the Kafka publish would leave
+///   a committed transfer with no event - the dual-write gap, on demand
+/// - So the handler runs in its own task and is left to finish; only the
+///   client stops waiting. 504 means "outcome unknown": check the wallet
+///   (or the transaction's reference_id) before retrying
+///
+/// Added with `route_layer` so the matched route pattern is known.
+pub async fn enforce_timeout(
+    State(config): State<Arc<TimeoutConfig>>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let route = request
+        .extensions()
+        .get::<MatchedPath>()
+        .map(|path| path.as_str().to_string())
+        .unwrap_or_else(|| request.uri().path().to_string());
+    let timeout = config.for_route(&route);
+
+    let handler = tokio::spawn(correlation::carry_over(next.run(request)));
+
+    match tokio::time::timeout(timeout, handler).await {
+        Ok(Ok(response)) => response,
+        Ok(Err(e)) => {
+            tracing::error!(route = %route, error = %e, "Request handler panicked");
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(ErrorResponse::new("Internal server error")),
+            )
+                .into_response()
+        }
+        Err(_) => {
+            tracing::warn!(
+                route = %route,
+                timeout_ms = timeout.as_millis() as u64,
+                "Request timed out"
+            );
+            (
+                StatusCode::GATEWAY_TIMEOUT,
+                Json(ErrorResponse::new(format!(
+                    "Request timed out after {}ms; it may still complete - check before retrying",
+                    timeout.as_millis()
+                ))),
+            )
+                .into_response()
+        }
+    }
+}
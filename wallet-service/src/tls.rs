@@ -0,0 +1,221 @@
+use crate::shutdown::Shutdown;
+use axum_server::tls_rustls::RustlsConfig;
+use axum_server::Handle;
+use std::time::{Duration, SystemTime};
+
+/// Client certificates for outbound HTTPS (mutual TLS)
+///
+/// For zero-trust deployments, where every hop - the identity provider, a
+/// mesh egress gateway, webhook receivers behind it - wants to see who is
+/// calling. Point these at PEM files:
+///
+/// ```text
+/// TLS_CLIENT_CERT=/etc/wallet/tls/client.crt   # chain, leaf first
+/// TLS_CLIENT_KEY=/etc/wallet/tls/client.key    # unencrypted PEM: PKCS#8, RSA or EC
+/// TLS_CA_BUNDLE=/etc/wallet/tls/ca.pem         # private CA(s) to trust
+/// ```
+///
+/// Everything is optional: with nothing set, clients are plain rustls ones
+/// trusting the bundled web roots. The CA bundle is added to those roots,
+/// not swapped in, so public webhook URLs still verify.
+///
+/// Kafka reuses the same files when KAFKA_SECURITY_PROTOCOL is SSL or
+/// SASL_SSL and no KAFKA_SSL_* path is set (see `kafka_security`).
+#[derive(Clone, Default)]
+pub struct ClientTls {
+    pub cert_path: Option<String>,
+    pub key_path: Option<String>,
+    pub ca_path: Option<String>,
+}
+
+impl ClientTls {
+    /// Read TLS_CLIENT_CERT, TLS_CLIENT_KEY and TLS_CA_BUNDLE (empty = unset)
+    pub fn from_env() -> Self {
+        Self {
+            cert_path: var("TLS_CLIENT_CERT"),
+            key_path: var("TLS_CLIENT_KEY"),
+            ca_path: var("TLS_CA_BUNDLE"),
+        }
+    }
+
+    /// Add the identity and CA bundle to a client builder
+    ///
+    /// Reads the files now, so a bad path fails at startup rather than on
+    /// the first request. A certificate without a key (or the reverse) is
+    /// an error - half-configured mTLS would silently call out anonymously.
+    pub fn apply(
+        &self,
+        mut builder: reqwest::ClientBuilder,
+    ) -> Result<reqwest::ClientBuilder, String> {
+        match (&self.cert_path, &self.key_path) {
+            (Some(cert_path), Some(key_path)) => {
+                // rustls wants key and chain in one PEM buffer
+                let mut pem = read(key_path)?;
+                pem.push(b'\n');
+                pem.extend(read(cert_path)?);
+                let identity = reqwest::Identity::from_pem(&pem)
+                    .map_err(|e| format!("{} / {}: {}", cert_path, key_path, e))?;
+                builder = builder.identity(identity);
+            }
+            (Some(_), None) => return Err("TLS_CLIENT_CERT is set without TLS_CLIENT_KEY".into()),
+            (None, Some(_)) => return Err("TLS_CLIENT_KEY is set without TLS_CLIENT_CERT".into()),
+            (None, None) => {}
+        }
+
+        if let Some(ca_path) = &self.ca_path {
+            let bundle = reqwest::Certificate::from_pem_bundle(&read(ca_path)?)
+                .map_err(|e| format!("{}: {}", ca_path, e))?;
+            if bundle.is_empty() {
+                return Err(format!("{}: no certificates in bundle", ca_path));
+            }
+            for certificate in bundle {
+                builder = builder.add_root_certificate(certificate);
+            }
+        }
+
+        Ok(builder)
+    }
+}
+
+/// A client builder with the TLS settings from the env - start every
+/// outbound HTTP client from this
+pub fn client_builder() -> Result<reqwest::ClientBuilder, String> {
+    ClientTls::from_env().apply(reqwest::Client::builder().use_rustls_tls())
+}
+
+fn var(name: &str) -> Option<String> {
+    std::env::var(name).ok().filter(|v| !v.is_empty())
+}
+
+fn read(path: &str) -> Result<Vec<u8>, String> {
+    std::fs::read(path).map_err(|e| format!("{}: {}", path, e))
+}
+
+/// TLS on our own listener, for deployments without a load balancer in
+/// front to terminate it
+///
+/// ```text
+/// TLS_SERVER_CERT=/etc/wallet/tls/server.crt   # PEM chain, leaf first
+/// TLS_SERVER_KEY=/etc/wallet/tls/server.key
+/// TLS_RELOAD_SECS=30                           # how often to look for new files
+/// ```
+///
+/// Hot reload: the files' modification times are checked every
+/// TLS_RELOAD_SECS and the certificate is swapped in place when either
+/// changes - cert-manager renewals and rotated Kubernetes secrets are
+/// picked up without a restart. Open connections keep their handshake,
+/// new ones get the new certificate. A pair that doesn't load (say the
+/// cert was written but not the key yet) is logged and retried on the next
+/// check; the old certificate stays in use meanwhile.
+#[derive(Clone, Debug)]
+pub struct ServerTls {
+    pub cert_path: String,
+    pub key_path: String,
+    pub reload_interval: Duration,
+}
+
+impl ServerTls {
+    /// Read TLS_SERVER_CERT, TLS_SERVER_KEY and TLS_RELOAD_SECS
+    ///
+    /// `None` when neither path is set - plain HTTP. Only one of them is
+    /// an error.
+    pub fn from_env() -> Result<Option<Self>, String> {
+        let reload_interval = Duration::from_secs(
+            var("TLS_RELOAD_SECS")
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(30)
+                .max(1),
+        );
+
+        match (var("TLS_SERVER_CERT"), var("TLS_SERVER_KEY")) {
+            (Some(cert_path), Some(key_path)) => Ok(Some(Self {
+                cert_path,
+                key_path,
+                reload_interval,
+            })),
+            (Some(_), None) => Err("TLS_SERVER_CERT is set without TLS_SERVER_KEY".into()),
+            (None, Some(_)) => Err("TLS_SERVER_KEY is set without TLS_SERVER_CERT".into()),
+            (None, None) => Ok(None),
+        }
+    }
+
+    /// Load the certificate and key
+    pub async fn load(&self) -> Result<RustlsConfig, String> {
+        // reqwest pulls in rustls' ring backend; make it the process
+        // default so server configs don't have to pick one (an error just
+        // means it's already installed)
+        let _ = rustls::crypto::ring::default_provider().install_default();
+
+        RustlsConfig::from_pem_file(&self.cert_path, &self.key_path)
+            .await
+            .map_err(|e| format!("{} / {}: {}", self.cert_path, self.key_path, e))
+    }
+
+    /// Watch for changed files until shutdown, reloading `config` in place
+    pub fn spawn_reloader(&self, config: RustlsConfig, shutdown: Shutdown) {
+        let mut watcher = CertWatcher::new(self.clone());
+        let interval = self.reload_interval;
+        tokio::spawn(async move {
+            while !shutdown.is_triggered() {
+                tokio::select! {
+                    _ = tokio::time::sleep(interval) => {}
+                    _ = shutdown.wait() => break,
+                }
+                match watcher.poll(&config).await {
+                    Ok(true) => tracing::info!(cert = %watcher.tls.cert_path, "TLS certificate reloaded"),
+                    Ok(false) => {}
+                    Err(e) => tracing::warn!(error = %e, "TLS certificate reload failed, keeping the old one"),
+                }
+            }
+        });
+    }
+}
+
+/// Remembers the modification times the current certificate was loaded at
+pub struct CertWatcher {
+    tls: ServerTls,
+    loaded: (Option<SystemTime>, Option<SystemTime>),
+}
+
+impl CertWatcher {
+    /// Starts from the files as they are now - call right after `load`
+    pub fn new(tls: ServerTls) -> Self {
+        let loaded = (modified(&tls.cert_path), modified(&tls.key_path));
+        Self { tls, loaded }
+    }
+
+    /// Reload `config` if either file changed since the last load
+    ///
+    /// `Ok(true)` when a new certificate is in use. On error the times
+    /// aren't updated, so the next poll tries again.
+    pub async fn poll(&mut self, config: &RustlsConfig) -> Result<bool, String> {
+        let current = (modified(&self.tls.cert_path), modified(&self.tls.key_path));
+        if current == self.loaded {
+            return Ok(false);
+        }
+
+        config
+            .reload_from_pem_file(&self.tls.cert_path, &self.tls.key_path)
+            .await
+            .map_err(|e| format!("{} / {}: {}", self.tls.cert_path, self.tls.key_path, e))?;
+        self.loaded = current;
+        Ok(true)
+    }
+}
+
+/// A handle that stops the TLS server gracefully on shutdown - stops
+/// accepting, then waits for open requests, like `axum::serve` does
+pub fn graceful_handle(shutdown: Shutdown) -> Handle {
+    let handle = Handle::new();
+    let stopping = handle.clone();
+    tokio::spawn(async move {
+        shutdown.wait().await;
+        stopping.graceful_shutdown(None);
+    });
+    handle
+}
+
+/// Follows symlinks, which is how Kubernetes swaps mounted secrets
+fn modified(path: &str) -> Option<SystemTime> {
+    std::fs::metadata(path).and_then(|m| m.modified()).ok()
+}
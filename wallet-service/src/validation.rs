@@ -0,0 +1,388 @@
+use crate::errors::{FieldError, WalletError, WalletResult};
+use crate::handlers::AppState;
+use crate::models::*;
+use axum::{
+    extract::{FromRequest, Request},
+    response::{IntoResponse, Response},
+    Json,
+};
+use rust_decimal::Decimal;
+use serde::de::DeserializeOwned;
+use std::str::FromStr;
+use uuid::Uuid;
+use validator::{Validate, ValidationError, ValidationErrors};
+
+/// Decimal places the database keeps (DECIMAL(19,4))
+pub const DB_SCALE: u32 = 4;
+
+/// Most digits a decimal string may have (rust_decimal's precision)
+const MAX_DIGITS: usize = 28;
+
+/// Limits every request amount is checked against before it reaches the DB
+///
+/// Why?
+/// - Postgres would silently round 10.12345 to 10.1235 - money appearing
+///   from nowhere; we'd rather refuse it
+/// - A typo'd 1000000000000 should be a 422, not a very large transfer
+#[derive(Debug, Clone)]
+pub struct AmountRules {
+    /// AMOUNT_MAX_DECIMALS - at most DB_SCALE
+    pub max_decimals: u32,
+    /// AMOUNT_MAX - largest amount of any one operation
+    pub max: Decimal,
+}
+
+impl Default for AmountRules {
+    fn default() -> Self {
+        Self {
+            max_decimals: 2,
+            max: Decimal::from(1_000_000_000),
+        }
+    }
+}
+
+impl AmountRules {
+    pub fn from_env() -> Self {
+        let defaults = Self::default();
+        Self {
+            max_decimals: std::env::var("AMOUNT_MAX_DECIMALS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(defaults.max_decimals)
+                .min(DB_SCALE),
+            max: std::env::var("AMOUNT_MAX")
+                .ok()
+                .and_then(|v| Decimal::from_str(&v).ok())
+                .unwrap_or(defaults.max),
+        }
+    }
+
+    /// Record what's wrong with `amount` (if anything) under `field`
+    pub fn check(&self, field: &str, amount: Decimal, errors: &mut Vec<FieldError>) {
+        if amount <= Decimal::ZERO {
+            errors.push(FieldError::new(field, "must be greater than 0"));
+        } else if amount.normalize().scale() > self.max_decimals {
+            errors.push(FieldError::new(
+                field,
+                format!("must have at most {} decimal places", self.max_decimals),
+            ));
+        } else if amount > self.max {
+            errors.push(FieldError::new(field, format!("must be at most {}", self.max)));
+        }
+    }
+}
+
+/// A request body: its `#[validate(...)]` attributes plus, for bodies with
+/// amounts, the (configurable) amount rules
+pub trait ValidateRequest: Validate {
+    fn check_amounts(&self, _rules: &AmountRules, _errors: &mut Vec<FieldError>) {}
+}
+
+/// Run every check on `payload` and turn any failures into one 422
+pub fn validate<T: ValidateRequest>(payload: &T, rules: &AmountRules) -> WalletResult<()> {
+    let mut errors = match payload.validate() {
+        Ok(()) => Vec::new(),
+        Err(e) => field_errors(&e),
+    };
+    payload.check_amounts(rules, &mut errors);
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(WalletError::ValidationFailed(errors))
+    }
+}
+
+/// Deserialize a JSON body, naming the field that didn't parse
+///
+/// `{"amount": "NaN"}` is a 422 on `amount`, not a bare "failed to
+/// deserialize" for the whole body.
+pub fn from_json<T: DeserializeOwned>(value: serde_json::Value) -> WalletResult<T> {
+    serde_path_to_error::deserialize(value).map_err(|e| {
+        let field = match e.path().to_string() {
+            path if path == "." => "body".to_string(),
+            path => path,
+        };
+        WalletError::ValidationFailed(vec![FieldError::new(field, e.inner().to_string())])
+    })
+}
+
+/// `validator`'s errors as ours, ordered by field so responses are stable
+fn field_errors(errors: &ValidationErrors) -> Vec<FieldError> {
+    let mut fields: Vec<_> = errors.field_errors().into_iter().collect();
+    fields.sort_by(|a, b| a.0.cmp(b.0));
+
+    fields
+        .into_iter()
+        .flat_map(|(field, errors)| {
+            errors
+                .iter()
+                .map(move |e| FieldError::new(field.to_string(), describe(e)))
+        })
+        .collect()
+}
+
+fn describe(error: &ValidationError) -> String {
+    if let Some(message) = &error.message {
+        return message.to_string();
+    }
+    let param = |name: &str| error.params.get(name).and_then(|v| v.as_u64());
+
+    match (error.code.as_ref(), param("min"), param("max")) {
+        ("length", Some(1), Some(max)) => format!("must be 1 to {} characters", max),
+        ("length", Some(min), Some(max)) => format!("must be {} to {} characters", min, max),
+        ("length", None, Some(max)) => format!("must be at most {} characters", max),
+        (code, _, _) => format!("is invalid ({})", code),
+    }
+}
+
+/// A wallet (or beneficiary, template...) ID as we issue them: a hyphenated UUID
+///
+/// Catches a user ID or a truncated paste before it turns into a 404 that
+/// looks like "that wallet doesn't exist".
+pub fn uuid_shaped(value: &str) -> Result<(), ValidationError> {
+    if value.len() == 36 && Uuid::parse_str(value).is_ok() {
+        Ok(())
+    } else {
+        let mut error = ValidationError::new("uuid");
+        error.message = Some("must be a UUID like \"1b4e28ba-2fa1-11d2-883f-0016d3cca427\"".into());
+        Err(error)
+    }
+}
+
+/// `uuid_shaped` for every ID in a list
+pub fn all_uuid_shaped(values: &[String]) -> Result<(), ValidationError> {
+    match values.iter().find(|v| uuid_shaped(v).is_err()) {
+        None => Ok(()),
+        Some(bad) => {
+            let mut error = ValidationError::new("uuid");
+            error.message = Some(format!("must all be UUIDs; {:?} isn't", bad).into());
+            Err(error)
+        }
+    }
+}
+
+/// A `reason_code` from ADJUSTMENT_REASON_CODES
+pub fn adjustment_reason_code(value: &str) -> Result<(), ValidationError> {
+    if ADJUSTMENT_REASON_CODES.contains(&value) {
+        Ok(())
+    } else {
+        let mut error = ValidationError::new("reason_code");
+        let codes = ADJUSTMENT_REASON_CODES.join(", ");
+        error.message = Some(format!("must be one of {}", codes).into());
+        Err(error)
+    }
+}
+
+/// A transaction PIN: 4 to 8 digits
+pub fn pin_format(value: &str) -> Result<(), ValidationError> {
+    if (4..=8).contains(&value.len()) && value.bytes().all(|b| b.is_ascii_digit()) {
+        Ok(())
+    } else {
+        let mut error = ValidationError::new("pin");
+        error.message = Some("must be 4 to 8 digits".into());
+        Err(error)
+    }
+}
+
+/// A step-up verification code: 6 digits
+pub fn verification_code(value: &str) -> Result<(), ValidationError> {
+    if value.len() == 6 && value.bytes().all(|b| b.is_ascii_digit()) {
+        Ok(())
+    } else {
+        let mut error = ValidationError::new("code");
+        error.message = Some("must be 6 digits".into());
+        Err(error)
+    }
+}
+
+/// Most labels a wallet may carry, and their longest
+pub const MAX_WALLET_LABELS: usize = 20;
+pub const MAX_WALLET_LABEL_LEN: usize = 50;
+
+/// A wallet's labels: at most MAX_WALLET_LABELS, none blank or too long
+pub fn wallet_labels(labels: &[String]) -> Result<(), ValidationError> {
+    let mut error = ValidationError::new("labels");
+    if labels.len() > MAX_WALLET_LABELS {
+        error.message = Some(format!("at most {} labels", MAX_WALLET_LABELS).into());
+        return Err(error);
+    }
+    match labels
+        .iter()
+        .find(|l| l.trim().is_empty() || l.chars().count() > MAX_WALLET_LABEL_LEN)
+    {
+        None => Ok(()),
+        Some(bad) => {
+            error.message = Some(
+                format!("must each be 1 to {} characters; {:?} isn't", MAX_WALLET_LABEL_LEN, bad)
+                    .into(),
+            );
+            Err(error)
+        }
+    }
+}
+
+/// `Json<T>` that also runs `validate` - handlers only see valid bodies
+///
+/// Malformed JSON is still axum's 400; non-JSON and oversized bodies are
+/// refused before this (`body_limit`).
+pub struct ValidJson<T>(pub T);
+
+#[axum::async_trait]
+impl<T> FromRequest<AppState> for ValidJson<T>
+where
+    T: DeserializeOwned + ValidateRequest,
+{
+    type Rejection = Response;
+
+    async fn from_request(req: Request, state: &AppState) -> Result<Self, Self::Rejection> {
+        let Json(value) = Json::<serde_json::Value>::from_request(req, state)
+            .await
+            .map_err(IntoResponse::into_response)?;
+        let payload: T = from_json(value).map_err(IntoResponse::into_response)?;
+        validate(&payload, &state.amount_rules).map_err(IntoResponse::into_response)?;
+        Ok(ValidJson(payload))
+    }
+}
+
+/// Parse a plain decimal string: digits, optionally a `.` and more digits
+///
+/// rust_decimal alone would also take "1_000", "+5" and silently round
+/// past 28 digits; "NaN", "inf" and "1e9" are refused here too.
+pub fn parse_amount(value: &str) -> Result<Decimal, String> {
+    let unsigned = value.strip_prefix('-').unwrap_or(value);
+    let (whole, fraction) = match unsigned.split_once('.') {
+        Some((whole, fraction)) => (whole, Some(fraction)),
+        None => (unsigned, None),
+    };
+    let is_digits = |s: &str| !s.is_empty() && s.bytes().all(|b| b.is_ascii_digit());
+
+    if !is_digits(whole) || !fraction.is_none_or(is_digits) {
+        return Err(format!("expected a decimal string like \"10.50\", got {:?}", value));
+    }
+    if whole.len() + fraction.map_or(0, str::len) > MAX_DIGITS {
+        return Err(format!("has more than {} digits", MAX_DIGITS));
+    }
+    Decimal::from_str(value).map_err(|e| e.to_string())
+}
+
+/// `#[serde(with = "crate::validation::amount")]` - a strictly parsed amount
+pub mod amount {
+    use rust_decimal::Decimal;
+    use serde::{de::Error, Deserialize, Deserializer};
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Decimal, D::Error> {
+        let value = String::deserialize(deserializer)?;
+        super::parse_amount(&value).map_err(D::Error::custom)
+    }
+}
+
+/// Like `amount`, for an optional field (use with `#[serde(default)]`)
+pub mod amount_option {
+    use rust_decimal::Decimal;
+    use serde::{de::Error, Deserialize, Deserializer};
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Option<Decimal>, D::Error> {
+        Option::<String>::deserialize(deserializer)?
+            .map(|value| super::parse_amount(&value).map_err(D::Error::custom))
+            .transpose()
+    }
+}
+
+/// `Option<Option<T>>` that tells a `null` from an absent field (use with
+/// `#[serde(default)]`): absent -> None, null -> Some(None)
+pub mod nullable {
+    use serde::{Deserialize, Deserializer};
+
+    pub fn deserialize<'de, T, D>(deserializer: D) -> Result<Option<Option<T>>, D::Error>
+    where
+        T: Deserialize<'de>,
+        D: Deserializer<'de>,
+    {
+        Option::<T>::deserialize(deserializer).map(Some)
+    }
+}
+
+// Bodies with amounts
+
+impl ValidateRequest for FundWalletRequest {
+    fn check_amounts(&self, rules: &AmountRules, errors: &mut Vec<FieldError>) {
+        rules.check("amount", self.amount, errors);
+    }
+}
+
+impl ValidateRequest for TransferRequest {
+    fn check_amounts(&self, rules: &AmountRules, errors: &mut Vec<FieldError>) {
+        rules.check("amount", self.amount, errors);
+    }
+}
+
+impl ValidateRequest for CreateTemplateRequest {
+    fn check_amounts(&self, rules: &AmountRules, errors: &mut Vec<FieldError>) {
+        rules.check("amount", self.amount, errors);
+    }
+}
+
+impl ValidateRequest for ExecuteTemplateRequest {
+    fn check_amounts(&self, rules: &AmountRules, errors: &mut Vec<FieldError>) {
+        if let Some(amount) = self.amount {
+            rules.check("amount", amount, errors);
+        }
+    }
+}
+
+impl ValidateRequest for PotTransferRequest {
+    fn check_amounts(&self, rules: &AmountRules, errors: &mut Vec<FieldError>) {
+        rules.check("amount", self.amount, errors);
+    }
+}
+
+impl ValidateRequest for SetRoundUpRuleRequest {
+    fn check_amounts(&self, rules: &AmountRules, errors: &mut Vec<FieldError>) {
+        rules.check("increment", self.increment, errors);
+    }
+}
+
+impl ValidateRequest for CreateVoucherRequest {
+    fn check_amounts(&self, rules: &AmountRules, errors: &mut Vec<FieldError>) {
+        rules.check("amount", self.amount, errors);
+    }
+}
+
+impl ValidateRequest for SetPinRequest {
+    fn check_amounts(&self, rules: &AmountRules, errors: &mut Vec<FieldError>) {
+        // 0 is a valid threshold: every transfer needs the PIN
+        match self.threshold {
+            Some(threshold) if threshold < Decimal::ZERO => {
+                errors.push(FieldError::new("threshold", "must be 0 or more"));
+            }
+            Some(threshold) if threshold > Decimal::ZERO => rules.check("threshold", threshold, errors),
+            _ => {}
+        }
+    }
+}
+
+impl ValidateRequest for AdjustmentRequest {
+    fn check_amounts(&self, rules: &AmountRules, errors: &mut Vec<FieldError>) {
+        rules.check("amount", self.amount, errors);
+    }
+}
+
+// Bodies checked by their attributes alone
+
+impl ValidateRequest for CreateWalletRequest {}
+impl ValidateRequest for BatchGetWalletsRequest {}
+impl ValidateRequest for CreateBeneficiaryRequest {}
+impl ValidateRequest for CreatePotRequest {}
+impl ValidateRequest for RedeemVoucherRequest {}
+impl ValidateRequest for CreateTransactionNoteRequest {}
+impl ValidateRequest for CreateWebhookRequest {}
+impl ValidateRequest for ArchiveWalletsRequest {}
+impl ValidateRequest for UpdateWebhookRequest {}
+impl ValidateRequest for UpdateWalletRequest {}
+impl ValidateRequest for CreateApiKeyRequest {}
+impl ValidateRequest for ConfirmTransferRequest {}
+impl ValidateRequest for CreateBlocklistEntryRequest {}
+impl ValidateRequest for SetKycLevelRequest {}
+impl ValidateRequest for SetBalanceShardsRequest {}
@@ -0,0 +1,89 @@
+use crate::errors::{WalletError, WalletResult};
+use rust_decimal::Decimal;
+use std::str::FromStr;
+use std::time::Duration;
+
+/// How many transfers, and how much money, a wallet may send per window
+///
+/// Why in the repository rather than a middleware like rate_limit.rs?
+/// - The count has to include transfers that just committed; it's read
+///   inside the transfer's DB transaction, under the sender's row lock,
+///   so two concurrent transfers can't both slip under the limit
+/// - Rate limits protect the service; these protect the user's money, so
+///   every way a transfer runs (templates, confirmed step-ups, released
+///   holds) goes through them
+#[derive(Debug, Clone)]
+pub struct VelocityLimits {
+    /// VELOCITY_MAX_TRANSFERS - outgoing transfers per window (unset: no limit)
+    pub max_transfers: Option<i64>,
+    /// VELOCITY_MAX_VOLUME - total sent per window (unset: no limit)
+    pub max_volume: Option<Decimal>,
+    /// VELOCITY_WINDOW_SECS - the sliding window both count over
+    pub window: Duration,
+}
+
+impl Default for VelocityLimits {
+    fn default() -> Self {
+        Self {
+            max_transfers: None,
+            max_volume: None,
+            window: Duration::from_secs(60 * 60),
+        }
+    }
+}
+
+impl VelocityLimits {
+    pub fn from_env() -> Self {
+        fn var(name: &str) -> Option<String> {
+            std::env::var(name).ok().filter(|v| !v.is_empty())
+        }
+
+        let defaults = Self::default();
+        Self {
+            max_transfers: var("VELOCITY_MAX_TRANSFERS")
+                .and_then(|v| v.parse().ok())
+                .filter(|&n| n > 0),
+            max_volume: var("VELOCITY_MAX_VOLUME")
+                .and_then(|v| Decimal::from_str(&v).ok())
+                .filter(|v| *v > Decimal::ZERO),
+            window: var("VELOCITY_WINDOW_SECS")
+                .and_then(|v| v.parse().ok())
+                .filter(|&secs| secs > 0)
+                .map(Duration::from_secs)
+                .unwrap_or(defaults.window),
+        }
+    }
+
+    pub fn is_off(&self) -> bool {
+        self.max_transfers.is_none() && self.max_volume.is_none()
+    }
+
+    /// Would one more transfer of `amount` break a limit, given what the
+    /// wallet already sent in the window?
+    pub fn check(&self, sent_count: i64, sent_volume: Decimal, amount: Decimal) -> WalletResult<()> {
+        let window_secs = self.window.as_secs();
+
+        if let Some(limit) = self.max_transfers {
+            if sent_count + 1 > limit {
+                return Err(WalletError::VelocityLimitExceeded {
+                    measure: "transfers",
+                    limit: Decimal::from(limit),
+                    attempted: Decimal::from(sent_count + 1),
+                    window_secs,
+                });
+            }
+        }
+        if let Some(limit) = self.max_volume {
+            if sent_volume + amount > limit {
+                return Err(WalletError::VelocityLimitExceeded {
+                    measure: "volume",
+                    limit,
+                    attempted: sent_volume + amount,
+                    window_secs,
+                });
+            }
+        }
+
+        Ok(())
+    }
+}
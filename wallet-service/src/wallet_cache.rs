@@ -0,0 +1,204 @@
+use crate::errors::WalletResult;
+use crate::models::Wallet;
+use redis::aio::ConnectionManager;
+use redis::Script;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::future::Future;
+use std::time::Duration;
+
+/// Redis read-through cache for `find_by_id` and `find_by_user_id`
+///
+/// Why cache at all?
+/// - Hot wallets (a merchant's, a payroll account's) are read on every
+///   request that touches them; those reads are cheap but they add up on
+///   the primary
+///
+/// Staying correct:
+/// - The repository invalidates every wallet a commit changed (balance,
+///   version, metadata), and its owner's wallet list, right after COMMIT
+/// - A read that missed and loaded from Postgres only fills the cache if
+///   nothing was invalidated meanwhile: each key has a generation counter,
+///   read before the load and compared (in a Lua script) when storing.
+///   Without it, a load that raced a commit could put the old row back
+///   after the invalidation
+/// - Entries expire after `ttl` anyway - the bound on staleness if Redis
+///   missed an invalidation (it was down, or something outside the
+///   repository wrote the row, like the rekey-fields binary)
+/// - Rows are cached as stored: encrypted fields stay sealed in Redis
+///
+/// Redis failures never fail a request: reads fall back to Postgres and a
+/// lost invalidation is logged.
+#[derive(Clone)]
+pub struct WalletCache {
+    redis: ConnectionManager,
+    ttl: Duration,
+}
+
+/// What the cache holds
+#[derive(Debug, Clone, Copy)]
+pub enum CacheKey<'a> {
+    /// One wallet row, by wallet ID
+    Wallet(&'a str),
+    /// All of a user's wallet rows, by user ID
+    UserWallets(&'a str),
+}
+
+impl CacheKey<'_> {
+    fn value_key(&self) -> String {
+        match self {
+            CacheKey::Wallet(wallet_id) => format!("wallet:{}", wallet_id),
+            CacheKey::UserWallets(user_id) => format!("wallets:user:{}", user_id),
+        }
+    }
+
+    fn generation_key(&self) -> String {
+        format!("{}:gen", self.value_key())
+    }
+}
+
+/// Store `ARGV[1]` under `KEYS[1]` only if `KEYS[2]` (the generation) is
+/// still `ARGV[2]` ("" when it didn't exist)
+const STORE_IF_CURRENT: &str = r#"
+local generation = redis.call('GET', KEYS[2]) or ''
+if generation == ARGV[2] then
+    redis.call('SET', KEYS[1], ARGV[1], 'PX', ARGV[3])
+    return 1
+end
+return 0
+"#;
+
+impl WalletCache {
+    /// WALLET_CACHE_URL = `redis://host:port/db` (unset: no cache);
+    /// WALLET_CACHE_TTL_SECS is how long entries live (default 30)
+    pub async fn from_env() -> Result<Option<Self>, String> {
+        let Some(url) = std::env::var("WALLET_CACHE_URL").ok().filter(|v| !v.is_empty()) else {
+            return Ok(None);
+        };
+        let ttl = std::env::var("WALLET_CACHE_TTL_SECS")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .filter(|&secs| secs > 0)
+            .map(Duration::from_secs)
+            .unwrap_or(DEFAULT_TTL);
+
+        Self::connect(&url, ttl).await.map(Some)
+    }
+
+    pub async fn connect(url: &str, ttl: Duration) -> Result<Self, String> {
+        let client = redis::Client::open(url).map_err(|e| format!("WALLET_CACHE_URL: {}", e))?;
+        let redis = ConnectionManager::new(client)
+            .await
+            .map_err(|e| format!("Wallet cache: can't connect to Redis: {}", e))?;
+
+        Ok(Self { redis, ttl })
+    }
+
+    pub fn ttl(&self) -> Duration {
+        self.ttl
+    }
+
+    /// The cached value of `key`, or `load()`'s - cached for next time
+    ///
+    /// Errors from `load` are passed through and never cached.
+    pub async fn get_or_load<T, F, Fut>(&self, key: CacheKey<'_>, load: F) -> WalletResult<T>
+    where
+        T: Serialize + DeserializeOwned,
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = WalletResult<T>>,
+    {
+        let (cached, generation) = match self.lookup(&key).await {
+            Ok(found) => found,
+            Err(e) => {
+                tracing::warn!(error = %e, "Wallet cache read failed, using the database");
+                return load().await;
+            }
+        };
+        if let Some(value) = cached.and_then(|json| serde_json::from_str(&json).ok()) {
+            return Ok(value);
+        }
+
+        let value = load().await?;
+        if let Err(e) = self.store(&key, &value, generation.as_deref()).await {
+            tracing::warn!(error = %e, "Wallet cache write failed");
+        }
+        Ok(value)
+    }
+
+    /// Drop the cached rows of `wallets` and their owners' wallet lists
+    ///
+    /// Call after the commit that changed them - before it, a concurrent
+    /// read could cache the old rows again.
+    pub async fn invalidate<'a>(&self, wallets: impl IntoIterator<Item = &'a Wallet>) {
+        let mut keys: Vec<CacheKey<'a>> = Vec::new();
+        for wallet in wallets {
+            keys.push(CacheKey::Wallet(&wallet.id));
+            keys.push(CacheKey::UserWallets(&wallet.user_id));
+        }
+        self.invalidate_keys(&keys).await;
+    }
+
+    /// `invalidate` by key, for when the rows themselves aren't at hand
+    pub async fn invalidate_keys(&self, keys: &[CacheKey<'_>]) {
+        if keys.is_empty() {
+            return;
+        }
+
+        // Generations outlive the entries they guard
+        let generation_ttl = self.ttl.as_millis() as u64 * 2;
+        let mut pipe = redis::pipe();
+        pipe.atomic();
+        for key in keys {
+            pipe.del(key.value_key()).ignore();
+            pipe.incr(key.generation_key(), 1).ignore();
+            pipe.pexpire(key.generation_key(), generation_ttl as i64).ignore();
+        }
+
+        let mut redis = self.redis.clone();
+        if let Err(e) = pipe.query_async::<_, ()>(&mut redis).await {
+            tracing::error!(
+                error = %e,
+                ttl_secs = self.ttl.as_secs(),
+                "Wallet cache invalidation failed - entries may be stale until they expire"
+            );
+        }
+    }
+
+    /// The cached JSON (if any) and the key's generation (if any)
+    async fn lookup(
+        &self,
+        key: &CacheKey<'_>,
+    ) -> redis::RedisResult<(Option<String>, Option<String>)> {
+        let mut redis = self.redis.clone();
+        redis::cmd("MGET")
+            .arg(key.value_key())
+            .arg(key.generation_key())
+            .query_async(&mut redis)
+            .await
+    }
+
+    async fn store<T: Serialize>(
+        &self,
+        key: &CacheKey<'_>,
+        value: &T,
+        generation: Option<&str>,
+    ) -> redis::RedisResult<()> {
+        let Ok(json) = serde_json::to_string(value) else {
+            return Ok(());
+        };
+        let mut redis = self.redis.clone();
+        Script::new(STORE_IF_CURRENT)
+            .key(key.value_key())
+            .key(key.generation_key())
+            .arg(json)
+            .arg(generation.unwrap_or(""))
+            .arg(self.ttl.as_millis() as u64)
+            .invoke_async::<_, i64>(&mut redis)
+            .await
+            .map(|_| ())
+    }
+}
+
+/// Short enough that a missed invalidation is soon forgotten, long enough
+/// to absorb bursts on a hot wallet
+const DEFAULT_TTL: Duration = Duration::from_secs(30);
@@ -0,0 +1,264 @@
+use crate::encryption::{FieldCipher, Sealed};
+use crate::errors::{WalletError, WalletResult};
+use crate::kafka::KafkaProducer;
+use crate::models::Wallet;
+use crate::shutdown::Shutdown;
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Advisory lock key held by the active state publisher (distinct from the
+/// outbox relay's, so one of each can run)
+const STATE_LOCK_KEY: i64 = 0x7374_6174_6573; // "states" in ASCII
+
+/// Wallets have no lifecycle yet - every wallet that exists is active
+pub const STATUS_ACTIVE: &str = "ACTIVE";
+
+/// Latest state of one wallet, as published to the wallet-state topic
+///
+/// Keyed by wallet ID, so compaction keeps only the newest snapshot per
+/// wallet; a deleted wallet gets a tombstone (null value) instead.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct WalletSnapshot {
+    pub wallet_id: String,
+    pub user_id: String,
+    pub balance: Decimal,
+    /// The wallet's optimistic-lock version - only ever goes up, so consumers
+    /// can drop a snapshot older than the one they hold
+    pub version: i64,
+    pub status: String,
+    pub parent_wallet_id: Option<String>,
+    pub nickname: Option<String>,
+    pub labels: Vec<String>,
+    pub is_default: bool,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl From<&Wallet> for WalletSnapshot {
+    fn from(wallet: &Wallet) -> Self {
+        Self {
+            wallet_id: wallet.id.clone(),
+            user_id: wallet.user_id.clone(),
+            balance: wallet.balance,
+            version: wallet.version,
+            status: STATUS_ACTIVE.to_string(),
+            parent_wallet_id: wallet.parent_wallet_id.clone(),
+            nickname: wallet.nickname.clone(),
+            labels: wallet.labels.clone(),
+            is_default: wallet.is_default,
+            created_at: wallet.created_at,
+            updated_at: wallet.updated_at,
+        }
+    }
+}
+
+/// Where snapshots go (Kafka in production, a fake in tests)
+pub trait StatePublisher {
+    /// Send `snapshot` (JSON) keyed by `wallet_id`; None is a tombstone
+    fn send_state(
+        &self,
+        wallet_id: &str,
+        snapshot: Option<&str>,
+    ) -> impl Future<Output = WalletResult<()>> + Send;
+}
+
+impl StatePublisher for Arc<KafkaProducer> {
+    fn send_state(
+        &self,
+        wallet_id: &str,
+        snapshot: Option<&str>,
+    ) -> impl Future<Output = WalletResult<()>> + Send {
+        KafkaProducer::send_state(self, wallet_id, snapshot)
+    }
+}
+
+/// Result of one publisher pass
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct StatePass {
+    /// Snapshots (and tombstones) sent
+    pub published: usize,
+    /// Queued changes covered by them (several updates, one snapshot)
+    pub changes: usize,
+    pub failed: usize,
+    /// Another publisher holds the lock - this pass did nothing
+    pub skipped: bool,
+}
+
+/// Publishes wallet snapshots to the log-compacted wallet-state topic
+///
+/// Why?
+/// - A new downstream service can read the compacted topic from the start
+///   and have every wallet's current balance, instead of replaying all of
+///   wallet-events
+/// - The event topic stays the record of what happened; this is only "what
+///   is true now"
+///
+/// How it works:
+/// 1. A trigger on wallets queues the wallet ID in wallet_state_changes on
+///    every insert, update and delete
+/// 2. Each pass takes the oldest queued IDs, reads those wallets' CURRENT
+///    rows and sends one snapshot per wallet (a tombstone if it's gone)
+/// 3. Acknowledged changes are deleted; after a failure the rest wait for
+///    the next pass
+///
+/// Why read the row at send time rather than publish from the request?
+/// - Two updates to one wallet can finish their Kafka sends in either
+///   order; the later send would leave the older balance as the compacted
+///   value. The publisher always sends the newest committed row, and holds
+///   an advisory lock (like the outbox relay) so only one instance sends.
+pub struct WalletStatePublisher<P> {
+    pool: PgPool,
+    publisher: P,
+    batch_size: i64,
+    cipher: FieldCipher,
+}
+
+impl<P: StatePublisher> WalletStatePublisher<P> {
+    pub fn new(pool: PgPool, publisher: P, batch_size: i64) -> Self {
+        Self {
+            pool,
+            publisher,
+            batch_size,
+            cipher: FieldCipher::default(),
+        }
+    }
+
+    /// Decrypt nicknames and labels before they're published (the
+    /// repository's cipher - snapshots carry them in the clear)
+    pub fn with_field_cipher(mut self, cipher: FieldCipher) -> Self {
+        self.cipher = cipher;
+        self
+    }
+
+    /// Publish snapshots for one batch of queued changes
+    pub async fn run_once(&self) -> WalletResult<StatePass> {
+        let mut tx = self.pool.begin().await?;
+
+        let acquired = sqlx::query_scalar::<_, bool>("SELECT pg_try_advisory_xact_lock($1)")
+            .bind(STATE_LOCK_KEY)
+            .fetch_one(&mut *tx)
+            .await?;
+        if !acquired {
+            return Ok(StatePass {
+                skipped: true,
+                ..StatePass::default()
+            });
+        }
+
+        let changes = sqlx::query_as::<_, (i64, String)>(
+            "SELECT id, wallet_id FROM wallet_state_changes ORDER BY id ASC LIMIT $1",
+        )
+        .bind(self.batch_size)
+        .fetch_all(&mut *tx)
+        .await?;
+
+        if changes.is_empty() {
+            tx.commit().await?;
+            return Ok(StatePass::default());
+        }
+
+        // Wallets in the order they first changed, with all their change IDs
+        let mut order: Vec<String> = Vec::new();
+        let mut change_ids: HashMap<String, Vec<i64>> = HashMap::new();
+        for (id, wallet_id) in changes {
+            change_ids
+                .entry(wallet_id.clone())
+                .or_insert_with(|| {
+                    order.push(wallet_id);
+                    Vec::new()
+                })
+                .push(id);
+        }
+
+        let wallets = sqlx::query_as::<_, Wallet>(
+            r#"
+            SELECT id, user_id, balance, version, parent_wallet_id, nickname, labels, is_default,
+                   created_at, updated_at
+            FROM wallets
+            WHERE id = ANY($1)
+            "#,
+        )
+        .bind(&order)
+        .fetch_all(&mut *tx)
+        .await?;
+        let wallets: HashMap<String, Wallet> = wallets
+            .open_with(&self.cipher)?
+            .into_iter()
+            .map(|wallet| (wallet.id.clone(), wallet))
+            .collect();
+
+        let mut pass = StatePass::default();
+        let mut sent: Vec<i64> = Vec::new();
+
+        for wallet_id in &order {
+            let snapshot = match wallets.get(wallet_id) {
+                Some(wallet) => Some(serde_json::to_string(&WalletSnapshot::from(wallet)).map_err(
+                    |e| WalletError::InternalError(format!("Failed to serialize snapshot: {}", e)),
+                )?),
+                None => None,
+            };
+
+            match self.publisher.send_state(wallet_id, snapshot.as_deref()).await {
+                Ok(()) => {
+                    pass.published += 1;
+                    sent.extend(&change_ids[wallet_id]);
+                }
+                Err(e) => {
+                    tracing::warn!(wallet_id = %wallet_id, error = %e, "Wallet snapshot not sent, will retry");
+                    pass.failed += 1;
+                    // Usually the broker is down - don't wait out a timeout per wallet
+                    break;
+                }
+            }
+        }
+
+        if !sent.is_empty() {
+            sqlx::query("DELETE FROM wallet_state_changes WHERE id = ANY($1)")
+                .bind(&sent)
+                .execute(&mut *tx)
+                .await?;
+        }
+        tx.commit().await?;
+
+        pass.changes = sent.len();
+        Ok(pass)
+    }
+
+    /// Changes waiting to be published
+    pub async fn pending(&self) -> WalletResult<i64> {
+        let pending = sqlx::query_scalar::<_, i64>("SELECT COUNT(*) FROM wallet_state_changes")
+            .fetch_one(&self.pool)
+            .await?;
+        Ok(pending)
+    }
+}
+
+impl<P: StatePublisher + Send + Sync + 'static> WalletStatePublisher<P> {
+    /// Run a pass every `interval` in the background, until shutdown
+    pub fn spawn(self, interval: Duration, shutdown: Shutdown) {
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(interval);
+
+            loop {
+                tokio::select! {
+                    _ = ticker.tick() => {}
+                    () = shutdown.wait() => return,
+                }
+
+                match self.run_once().await {
+                    Ok(pass) if pass.published > 0 => {
+                        tracing::debug!(published = pass.published, changes = pass.changes, "Published wallet snapshots")
+                    }
+                    Ok(_) => {}
+                    Err(e) => tracing::error!(error = %e, "Failed to publish wallet snapshots"),
+                }
+            }
+        });
+    }
+}
@@ -0,0 +1,386 @@
+use crate::errors::{WalletError, WalletResult};
+use crate::kafka::EVENT_TYPES;
+use crate::shutdown::Shutdown;
+use crate::signing::hmac_sha256;
+use chrono::Utc;
+use hmac::Mac;
+use rand::Rng;
+use sqlx::{FromRow, PgPool};
+use std::future::Future;
+use std::time::{Duration, Instant};
+
+/// `t=<unix seconds>,v1=<hex HMAC-SHA256 of "<t>.<body>">`
+pub const SIGNATURE_HEADER: &str = "X-Wallet-Signature";
+pub const EVENT_TYPE_HEADER: &str = "X-Wallet-Event-Type";
+pub const EVENT_ID_HEADER: &str = "X-Wallet-Event-Id";
+/// The same on every retry of one delivery - consumers dedupe on it
+pub const DELIVERY_ID_HEADER: &str = "X-Wallet-Delivery-Id";
+
+/// Attempts before a delivery is marked FAILED (the last is ~4 hours after the first)
+pub const MAX_ATTEMPTS: i32 = 10;
+
+/// First retry after 30s, doubling, capped at 6 hours
+const BASE_BACKOFF_SECS: i64 = 30;
+const MAX_BACKOFF_SECS: i64 = 6 * 60 * 60;
+
+/// Longest wait for a consumer's endpoint to answer
+pub const DELIVERY_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Recorded `last_error` / attempt errors are cut to this many characters
+const MAX_ERROR_LEN: usize = 500;
+
+/// Check a subscription's URL and event types before saving it
+pub fn validate_subscription(url: &str, event_types: &[String]) -> WalletResult<()> {
+    let url = url.trim();
+    if !(url.starts_with("https://") || url.starts_with("http://")) || url.len() < 10 {
+        return Err(WalletError::InvalidRequest(
+            "url must be an absolute http(s) URL".to_string(),
+        ));
+    }
+    for event_type in event_types {
+        if !EVENT_TYPES.contains(&event_type.as_str()) {
+            return Err(WalletError::InvalidRequest(format!(
+                "unknown event type {} (expected one of {})",
+                event_type,
+                EVENT_TYPES.join(", ")
+            )));
+        }
+    }
+    Ok(())
+}
+
+/// A new signing secret: `whsec_` + 32 random bytes as hex
+pub fn generate_secret() -> String {
+    let bytes: [u8; 32] = rand::thread_rng().gen();
+    format!("whsec_{}", hex::encode(bytes))
+}
+
+/// The X-Wallet-Signature value for `body` sent at `timestamp`
+///
+/// Consumers recompute the HMAC over `"<t>.<body>"` with their secret and
+/// compare; checking `t` is recent stops old deliveries being replayed.
+pub fn sign(secret: &str, timestamp: i64, body: &str) -> String {
+    let mut mac = hmac_sha256(secret.as_bytes());
+    mac.update(timestamp.to_string().as_bytes());
+    mac.update(b".");
+    mac.update(body.as_bytes());
+    format!("t={},v1={}", timestamp, hex::encode(mac.finalize().into_bytes()))
+}
+
+/// Wait before attempt `attempts + 1`: 30s, 60s, 120s ... capped at 6h
+pub fn retry_delay_secs(attempts: i32) -> i64 {
+    let exponent = (attempts.max(1) - 1).min(30) as u32;
+    BASE_BACKOFF_SECS
+        .saturating_mul(2_i64.saturating_pow(exponent))
+        .min(MAX_BACKOFF_SECS)
+}
+
+/// Queue `payload` for every active subscription to `event_type`
+///
+/// Called by the producer as each event is published. Returns how many
+/// deliveries were queued; an event already queued (published twice) is
+/// skipped.
+pub async fn queue_deliveries(
+    pool: &PgPool,
+    event_id: &str,
+    event_type: &str,
+    payload: &str,
+) -> WalletResult<u64> {
+    let queued = sqlx::query(
+        r#"
+        INSERT INTO webhook_deliveries (subscription_id, event_id, event_type, payload)
+        SELECT id, $1::uuid, $2, $3
+        FROM webhook_subscriptions
+        WHERE active AND (cardinality(event_types) = 0 OR $2 = ANY(event_types))
+        ON CONFLICT (subscription_id, event_id) DO NOTHING
+        "#,
+    )
+    .bind(event_id)
+    .bind(event_type)
+    .bind(payload)
+    .execute(pool)
+    .await?
+    .rows_affected();
+
+    Ok(queued)
+}
+
+/// How a delivery attempt went
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SendOutcome {
+    /// The endpoint answered (any status)
+    Response(u16),
+    /// No answer: connection refused, DNS, TLS, timeout...
+    Error(String),
+}
+
+/// Where deliveries are POSTed (HTTP in production, a fake in tests)
+pub trait WebhookSender {
+    fn post(
+        &self,
+        url: &str,
+        headers: &[(&'static str, String)],
+        body: &str,
+    ) -> impl Future<Output = SendOutcome> + Send;
+}
+
+/// POSTs over HTTP(S) with a 10s timeout; redirects aren't followed
+///
+/// Presents the TLS_CLIENT_CERT identity when one is configured (`tls`).
+#[derive(Clone)]
+pub struct HttpSender {
+    client: reqwest::Client,
+}
+
+impl HttpSender {
+    pub fn new() -> WalletResult<Self> {
+        let client = crate::tls::client_builder()
+            .map_err(|e| WalletError::InternalError(format!("Webhook client TLS: {}", e)))?
+            .timeout(DELIVERY_TIMEOUT)
+            .redirect(reqwest::redirect::Policy::none())
+            .user_agent("wallet-service-webhooks")
+            .build()
+            .map_err(|e| WalletError::InternalError(format!("Failed to build HTTP client: {}", e)))?;
+        Ok(Self { client })
+    }
+}
+
+impl WebhookSender for HttpSender {
+    fn post(
+        &self,
+        url: &str,
+        headers: &[(&'static str, String)],
+        body: &str,
+    ) -> impl Future<Output = SendOutcome> + Send {
+        let mut request = self
+            .client
+            .post(url)
+            .header(reqwest::header::CONTENT_TYPE, "application/json")
+            .body(body.to_string());
+        for (name, value) in headers {
+            request = request.header(*name, value);
+        }
+
+        async move {
+            match request.send().await {
+                Ok(response) => SendOutcome::Response(response.status().as_u16()),
+                Err(e) => SendOutcome::Error(e.to_string()),
+            }
+        }
+    }
+}
+
+/// A due delivery with what's needed to send it
+#[derive(Debug, Clone, FromRow)]
+struct DueDelivery {
+    id: i64,
+    event_id: String,
+    event_type: String,
+    payload: String,
+    attempts: i32,
+    url: String,
+    secret: String,
+}
+
+/// Result of one dispatcher pass
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct DispatchPass {
+    pub delivered: usize,
+    /// Failed attempts that will be retried
+    pub retrying: usize,
+    /// Deliveries that used their last attempt
+    pub gave_up: usize,
+}
+
+/// Sends queued webhook deliveries (webhook_deliveries) to subscribers
+///
+/// How it works:
+/// 1. Each pass claims the due PENDING rows with `FOR UPDATE SKIP LOCKED`,
+///    so several instances can dispatch at once without sending a row twice
+/// 2. Each is POSTed with the signature headers; 2xx marks it DELIVERED,
+///    anything else (or no answer) schedules a retry with backoff
+/// 3. After MAX_ATTEMPTS it's marked FAILED; every attempt is logged in
+///    webhook_delivery_attempts either way
+///
+/// Why not in order like the outbox relay?
+/// - One slow or broken endpoint would hold up every other subscriber.
+///   Deliveries are independent; consumers order by the event's `sequence`
+///   and dedupe on X-Wallet-Delivery-Id.
+///
+/// Deliveries for a paused (inactive) subscription stay queued until it's
+/// resumed.
+pub struct WebhookDispatcher<S> {
+    pool: PgPool,
+    sender: S,
+    batch_size: i64,
+    max_attempts: i32,
+}
+
+impl<S: WebhookSender> WebhookDispatcher<S> {
+    pub fn new(pool: PgPool, sender: S, batch_size: i64) -> Self {
+        Self {
+            pool,
+            sender,
+            batch_size,
+            max_attempts: MAX_ATTEMPTS,
+        }
+    }
+
+    /// Give up after `max` attempts instead of MAX_ATTEMPTS
+    pub fn with_max_attempts(mut self, max: i32) -> Self {
+        self.max_attempts = max.max(1);
+        self
+    }
+
+    /// Send one batch of due deliveries
+    pub async fn run_once(&self) -> WalletResult<DispatchPass> {
+        let mut tx = self.pool.begin().await?;
+
+        let due = sqlx::query_as::<_, DueDelivery>(
+            r#"
+            SELECT d.id, d.event_id::text AS event_id, d.event_type, d.payload, d.attempts,
+                   s.url, s.secret
+            FROM webhook_deliveries d
+            JOIN webhook_subscriptions s ON s.id = d.subscription_id
+            WHERE d.status = 'PENDING' AND d.next_attempt_at <= NOW() AND s.active
+            ORDER BY d.next_attempt_at, d.id
+            LIMIT $1
+            FOR UPDATE OF d SKIP LOCKED
+            "#,
+        )
+        .bind(self.batch_size)
+        .fetch_all(&mut *tx)
+        .await?;
+
+        let mut pass = DispatchPass::default();
+
+        for delivery in due {
+            let headers = [
+                (SIGNATURE_HEADER, sign(&delivery.secret, Utc::now().timestamp(), &delivery.payload)),
+                (EVENT_TYPE_HEADER, delivery.event_type.clone()),
+                (EVENT_ID_HEADER, delivery.event_id.clone()),
+                (DELIVERY_ID_HEADER, delivery.id.to_string()),
+            ];
+
+            let started = Instant::now();
+            let outcome = self.sender.post(&delivery.url, &headers, &delivery.payload).await;
+            let duration_ms = started.elapsed().as_millis().min(i32::MAX as u128) as i32;
+
+            let (status_code, error) = match &outcome {
+                SendOutcome::Response(status) if (200..300).contains(status) => (Some(*status as i32), None),
+                SendOutcome::Response(status) => (Some(*status as i32), Some(format!("HTTP {}", status))),
+                SendOutcome::Error(e) => (None, Some(truncate(e))),
+            };
+
+            sqlx::query(
+                r#"
+                INSERT INTO webhook_delivery_attempts (delivery_id, status_code, error, duration_ms)
+                VALUES ($1, $2, $3, $4)
+                "#,
+            )
+            .bind(delivery.id)
+            .bind(status_code)
+            .bind(&error)
+            .bind(duration_ms)
+            .execute(&mut *tx)
+            .await?;
+
+            let attempts = delivery.attempts + 1;
+            let status = match &error {
+                None => "DELIVERED",
+                Some(_) if attempts >= self.max_attempts => "FAILED",
+                Some(_) => "PENDING",
+            };
+
+            sqlx::query(
+                r#"
+                UPDATE webhook_deliveries
+                SET status = $2,
+                    attempts = $3,
+                    last_status_code = $4,
+                    last_error = $5,
+                    next_attempt_at = NOW() + make_interval(secs => $6),
+                    delivered_at = CASE WHEN $2 = 'DELIVERED' THEN NOW() END
+                WHERE id = $1
+                "#,
+            )
+            .bind(delivery.id)
+            .bind(status)
+            .bind(attempts)
+            .bind(status_code)
+            .bind(&error)
+            .bind(retry_delay_secs(attempts) as f64)
+            .execute(&mut *tx)
+            .await?;
+
+            match status {
+                "DELIVERED" => pass.delivered += 1,
+                "FAILED" => {
+                    tracing::warn!(
+                        delivery_id = delivery.id,
+                        url = %delivery.url,
+                        attempts,
+                        error = ?error,
+                        "Webhook delivery failed, giving up"
+                    );
+                    pass.gave_up += 1;
+                }
+                _ => {
+                    tracing::info!(
+                        delivery_id = delivery.id,
+                        url = %delivery.url,
+                        attempts,
+                        error = ?error,
+                        retry_in_secs = retry_delay_secs(attempts),
+                        "Webhook delivery failed, will retry"
+                    );
+                    pass.retrying += 1;
+                }
+            }
+        }
+
+        tx.commit().await?;
+        Ok(pass)
+    }
+
+    /// Deliveries still PENDING (due or backing off)
+    pub async fn pending(&self) -> WalletResult<i64> {
+        let pending = sqlx::query_scalar::<_, i64>(
+            "SELECT COUNT(*) FROM webhook_deliveries WHERE status = 'PENDING'",
+        )
+        .fetch_one(&self.pool)
+        .await?;
+        Ok(pending)
+    }
+}
+
+impl<S: WebhookSender + Send + Sync + 'static> WebhookDispatcher<S> {
+    /// Run a pass every `interval` in the background, until shutdown
+    pub fn spawn(self, interval: Duration, shutdown: Shutdown) {
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(interval);
+
+            loop {
+                tokio::select! {
+                    _ = ticker.tick() => {}
+                    () = shutdown.wait() => return,
+                }
+
+                match self.run_once().await {
+                    Ok(pass) if pass != DispatchPass::default() => tracing::debug!(
+                        delivered = pass.delivered,
+                        retrying = pass.retrying,
+                        gave_up = pass.gave_up,
+                        "Dispatched webhooks"
+                    ),
+                    Ok(_) => {}
+                    Err(e) => tracing::error!(error = %e, "Failed to dispatch webhooks"),
+                }
+            }
+        });
+    }
+}
+
+fn truncate(error: &str) -> String {
+    error.chars().take(MAX_ERROR_LEN).collect()
+}
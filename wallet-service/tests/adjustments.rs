@@ -0,0 +1,126 @@
+//! Integration tests for admin balance adjustments
+//!
+//! Run with: cargo test --test adjustments -- --test-threads=1
+
+mod common;
+
+use common::{cleanup_test_data, setup_test_db};
+use rust_decimal::Decimal;
+use rust_decimal_macros::dec;
+use serde_json::json;
+use wallet_service::errors::WalletError;
+use wallet_service::models::{AdjustmentDirection, AdjustmentRequest, TransactionType};
+use wallet_service::repository::WalletRepository;
+use wallet_service::validation::{self, AmountRules};
+
+fn adjustment(direction: AdjustmentDirection, amount: Decimal) -> AdjustmentRequest {
+    AdjustmentRequest {
+        direction,
+        amount,
+        reason_code: "ERROR_CORRECTION".to_string(),
+        note: Some("Duplicate funding on 3 March".to_string()),
+    }
+}
+
+#[test]
+fn test_reason_code_is_required() {
+    let request: AdjustmentRequest = validation::from_json(json!({
+        "direction": "CREDIT",
+        "amount": "10.00",
+        "reason_code": "BECAUSE"
+    }))
+    .unwrap();
+
+    match validation::validate(&request, &AmountRules::default()) {
+        Err(WalletError::ValidationFailed(errors)) => {
+            let fields: Vec<&str> = errors.iter().map(|e| e.field.as_str()).collect();
+            assert_eq!(fields, ["reason_code"]);
+        }
+        other => panic!("Expected ValidationFailed, got {:?}", other),
+    }
+
+    // Direction is CREDIT or DEBIT, nothing else
+    let result: Result<AdjustmentRequest, _> = validation::from_json(json!({
+        "direction": "REFUND",
+        "amount": "10.00",
+        "reason_code": "OTHER"
+    }));
+    assert!(matches!(result, Err(WalletError::ValidationFailed(_))));
+}
+
+#[tokio::test]
+async fn test_credit_and_debit_are_recorded_as_signed_adjustments() {
+    let pool = setup_test_db().await;
+    cleanup_test_data(&pool).await;
+    let repo = WalletRepository::new(pool.clone());
+
+    let wallet = repo.create_wallet("alice").await.unwrap();
+
+    let (credited, credit) = repo
+        .adjust_balance(
+            &wallet.id,
+            &adjustment(AdjustmentDirection::Credit, dec!(30)),
+            "ops@example.com",
+        )
+        .await
+        .expect("Failed to credit");
+    assert_eq!(credited.balance, dec!(30));
+    assert_eq!(credited.version, wallet.version + 1);
+    assert_eq!(credit.reason_code, "ERROR_CORRECTION");
+    assert_eq!(credit.actor, "ops@example.com");
+
+    let (debited, debit) = repo
+        .adjust_balance(
+            &wallet.id,
+            &adjustment(AdjustmentDirection::Debit, dec!(12.5)),
+            "ops@example.com",
+        )
+        .await
+        .expect("Failed to debit");
+    assert_eq!(debited.balance, dec!(17.5));
+    // The adjustment says which way; its ledger row carries the sign
+    assert_eq!(debit.amount, dec!(12.5));
+
+    let transactions = repo.find_recent_transactions(&wallet.id, 10).await.unwrap();
+    assert!(transactions
+        .iter()
+        .all(|t| matches!(t.transaction_type, TransactionType::Adjustment)));
+    let debit_row = transactions.iter().find(|t| t.id == debit.transaction_id).unwrap();
+    assert_eq!(debit_row.amount, dec!(-12.5));
+    let total: Decimal = transactions.iter().map(|t| t.signed_amount()).sum();
+    assert_eq!(total, debited.balance);
+
+    cleanup_test_data(&pool).await;
+}
+
+#[tokio::test]
+async fn test_debit_cannot_overdraw_or_touch_a_pot() {
+    let pool = setup_test_db().await;
+    cleanup_test_data(&pool).await;
+    let repo = WalletRepository::new(pool.clone());
+
+    let wallet = repo.create_wallet("alice").await.unwrap();
+    repo.fund_wallet(&wallet.id, dec!(5)).await.unwrap();
+
+    let result = repo
+        .adjust_balance(
+            &wallet.id,
+            &adjustment(AdjustmentDirection::Debit, dec!(5.01)),
+            "ops@example.com",
+        )
+        .await;
+    assert!(matches!(result, Err(WalletError::InsufficientBalance { .. })));
+    assert_eq!(repo.find_by_id(&wallet.id).await.unwrap().balance, dec!(5));
+
+    let pot = repo.create_pot(&wallet.id, "Rainy day").await.unwrap();
+    let result = repo
+        .adjust_balance(
+            &pot.id,
+            &adjustment(AdjustmentDirection::Credit, dec!(1)),
+            "ops@example.com",
+        )
+        .await;
+    assert!(matches!(result, Err(WalletError::InvalidRequest(_))));
+
+    cleanup_test_data(&pool).await;
+}
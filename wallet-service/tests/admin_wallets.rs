@@ -0,0 +1,74 @@
+//! Integration tests for the admin wallet listing
+//!
+//! Run with: cargo test --test admin_wallets -- --test-threads=1
+
+mod common;
+
+use common::{cleanup_test_data, setup_test_db};
+use rust_decimal_macros::dec;
+use wallet_service::errors::WalletError;
+use wallet_service::handlers::{admin_wallet_paging, ADMIN_WALLETS_PAGE_SIZE_DEFAULT};
+use wallet_service::models::AdminWalletQuery;
+use wallet_service::repository::WalletRepository;
+
+#[test]
+fn test_paging_defaults_and_limits() {
+    assert_eq!(
+        admin_wallet_paging(&AdminWalletQuery::default()).unwrap(),
+        (1, ADMIN_WALLETS_PAGE_SIZE_DEFAULT)
+    );
+
+    for query in [
+        AdminWalletQuery {
+            page: Some(0),
+            ..AdminWalletQuery::default()
+        },
+        AdminWalletQuery {
+            page_size: Some(201),
+            ..AdminWalletQuery::default()
+        },
+        // Wallets have no other status yet - say so rather than match nothing
+        AdminWalletQuery {
+            status: Some("FROZEN".to_string()),
+            ..AdminWalletQuery::default()
+        },
+    ] {
+        assert!(matches!(admin_wallet_paging(&query), Err(WalletError::InvalidRequest(_))));
+    }
+}
+
+#[tokio::test]
+async fn test_filters_combine_and_pages_are_newest_first() {
+    let pool = setup_test_db().await;
+    cleanup_test_data(&pool).await;
+    let repo = WalletRepository::new(pool.clone());
+
+    let first = repo.create_wallet("alice").await.unwrap();
+    let second = repo.create_wallet("alice").await.unwrap();
+    let third = repo.create_wallet("alice").await.unwrap();
+    repo.create_wallet("bob").await.unwrap();
+    repo.fund_wallet(&first.id, dec!(100)).await.unwrap();
+    repo.fund_wallet(&third.id, dec!(20)).await.unwrap();
+
+    let alice = AdminWalletQuery {
+        user_id: Some("alice".to_string()),
+        ..AdminWalletQuery::default()
+    };
+    let (page_one, total) = repo.find_wallets(&alice, 2, 0).await.unwrap();
+    assert_eq!(total, 3);
+    let ids: Vec<&str> = page_one.iter().map(|w| w.id.as_str()).collect();
+    assert_eq!(ids, [third.id.as_str(), second.id.as_str()]);
+    let (page_two, _) = repo.find_wallets(&alice, 2, 2).await.unwrap();
+    assert_eq!(page_two[0].id, first.id);
+
+    let rich_and_recent = AdminWalletQuery {
+        min_balance: Some(dec!(20)),
+        created_after: Some(first.created_at),
+        ..alice
+    };
+    let (wallets, total) = repo.find_wallets(&rich_and_recent, 50, 0).await.unwrap();
+    assert_eq!(total, 1);
+    assert_eq!(wallets[0].id, third.id);
+
+    cleanup_test_data(&pool).await;
+}
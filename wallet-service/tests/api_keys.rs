@@ -0,0 +1,91 @@
+//! Integration tests for API keys
+//!
+//! Run with: cargo test --test api_keys -- --test-threads=1
+
+mod common;
+
+use common::{cleanup_test_data, setup_test_db};
+use std::collections::HashMap;
+use wallet_service::auth::{AuthConfig, Authenticator, ClaimMapping};
+use wallet_service::errors::WalletError;
+use wallet_service::models::{ApiKeyScope, CreateApiKeyRequest};
+use wallet_service::repository::WalletRepository;
+
+fn authenticator(repo: &WalletRepository) -> Authenticator {
+    let config = AuthConfig {
+        issuer: "https://id.example.com/".to_string(),
+        audience: None,
+        jwks_url: None,
+        leeway_secs: 0,
+        claims: ClaimMapping {
+            roles_claim: "realm_access.roles".to_string(),
+            ..ClaimMapping::default()
+        },
+    };
+    Authenticator::with_keys(config, HashMap::new()).with_api_keys(repo.clone())
+}
+
+#[tokio::test]
+async fn test_issued_key_authenticates_until_revoked() {
+    let pool = setup_test_db().await;
+    cleanup_test_data(&pool).await;
+    let repo = WalletRepository::new(pool.clone());
+
+    let request = CreateApiKeyRequest {
+        name: "payouts-service".to_string(),
+        scope: ApiKeyScope::Transact,
+    };
+    let (api_key, key) = repo.create_api_key(&request, "ops@example.com").await.unwrap();
+    assert!(key.starts_with(&api_key.key_prefix));
+
+    // Only the hash is stored
+    let stored: String = sqlx::query_scalar("SELECT key_hash FROM api_keys WHERE id = $1")
+        .bind(&api_key.id)
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+    assert!(!stored.contains(&key[3..]));
+
+    let auth = authenticator(&repo);
+    let principal = auth.verify_api_key(&key).await.unwrap();
+    assert_eq!(principal.subject, format!("api-key:{}", api_key.id));
+    assert_eq!(principal.api_key_scope, Some(ApiKeyScope::Transact));
+    // Services act for every user
+    assert!(principal.owns("anyone"));
+
+    let listed = repo.find_api_keys().await.unwrap();
+    assert_eq!(listed.len(), 1);
+    assert!(listed[0].last_used_at.is_some());
+
+    let revoked = repo.revoke_api_key(&api_key.id, "ops@example.com").await.unwrap();
+    assert!(revoked.revoked_at.is_some());
+    assert!(matches!(
+        auth.verify_api_key(&key).await,
+        Err(WalletError::Unauthorized(_))
+    ));
+
+    let audited: i64 = sqlx::query_scalar(
+        "SELECT COUNT(*) FROM admin_audit_log WHERE target_type = 'api_key' AND target_id = $1",
+    )
+    .bind(&api_key.id)
+    .fetch_one(&pool)
+    .await
+    .unwrap();
+    assert_eq!(audited, 2);
+}
+
+#[tokio::test]
+async fn test_unknown_keys_are_rejected() {
+    let pool = setup_test_db().await;
+    cleanup_test_data(&pool).await;
+    let repo = WalletRepository::new(pool.clone());
+
+    let auth = authenticator(&repo);
+    assert!(matches!(
+        auth.verify_api_key("wk_not-a-real-key").await,
+        Err(WalletError::Unauthorized(_))
+    ));
+
+    let missing = repo.revoke_api_key("no-such-key", "ops@example.com").await;
+    assert!(matches!(missing, Err(WalletError::ApiKeyNotFound(_))));
+}
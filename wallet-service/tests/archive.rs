@@ -0,0 +1,118 @@
+//! Integration tests for archiving inactive wallets
+//!
+//! Run with: cargo test --test archive -- --test-threads=1
+
+mod common;
+
+use chrono::{Duration, Utc};
+use common::{cleanup_test_data, setup_test_db};
+use rust_decimal::Decimal;
+use rust_decimal_macros::dec;
+use serde_json::json;
+use wallet_service::archive;
+use wallet_service::errors::WalletError;
+use wallet_service::models::{
+    AdjustmentDirection, AdjustmentRequest, ArchiveWalletsRequest, AuditLogQuery,
+    AUDIT_WALLET_ARCHIVED,
+};
+use wallet_service::repository::WalletRepository;
+use wallet_service::validation::{self, AmountRules};
+
+#[test]
+fn test_request_limits_and_cutoff() {
+    let request: ArchiveWalletsRequest =
+        validation::from_json(json!({"inactive_days": 0, "limit": 5000})).unwrap();
+    match validation::validate(&request, &AmountRules::default()) {
+        Err(WalletError::ValidationFailed(errors)) => {
+            let fields: Vec<&str> = errors.iter().map(|e| e.field.as_str()).collect();
+            assert_eq!(fields, ["inactive_days", "limit"]);
+        }
+        other => panic!("Expected ValidationFailed, got {:?}", other),
+    }
+
+    let now = Utc::now();
+    assert_eq!(archive::cutoff(now, 30), now - Duration::days(30));
+}
+
+#[tokio::test]
+async fn test_empty_inactive_wallet_moves_with_its_history() {
+    let pool = setup_test_db().await;
+    cleanup_test_data(&pool).await;
+    let repo = WalletRepository::new(pool.clone());
+
+    let wallet = repo.create_wallet("alice").await.unwrap();
+    repo.fund_wallet(&wallet.id, dec!(10)).await.unwrap();
+    let emptied = AdjustmentRequest {
+        direction: AdjustmentDirection::Debit,
+        amount: dec!(10),
+        reason_code: "OTHER".to_string(),
+        note: None,
+    };
+    repo.adjust_balance(&wallet.id, &emptied, "ops@example.com").await.unwrap();
+
+    // Everything just happened - only a cutoff in the future makes it "inactive"
+    let cutoff = Utc::now() + Duration::days(1);
+    let archived = repo
+        .archive_inactive_wallets(cutoff, 100, "ops@example.com")
+        .await
+        .expect("Failed to archive");
+    assert_eq!(archived, std::slice::from_ref(&wallet.id));
+
+    assert!(matches!(
+        repo.find_by_id(&wallet.id).await,
+        Err(WalletError::WalletNotFound(_))
+    ));
+
+    let (stored, _, transactions) = repo.find_archived_wallet(&wallet.id).await.unwrap();
+    assert_eq!(stored.user_id, "alice");
+    assert_eq!(transactions.len(), 2);
+    // Still a ledger that adds up to the balance
+    let total: Decimal = transactions.iter().map(|t| t.signed_amount()).sum();
+    assert_eq!(total, stored.balance);
+
+    let query = AuditLogQuery {
+        action: Some(AUDIT_WALLET_ARCHIVED.to_string()),
+        ..AuditLogQuery::default()
+    };
+    let entries = repo.find_audit_entries(&query, 100).await.unwrap();
+    assert_eq!(entries.len(), 1);
+    assert_eq!(entries[0].target_id, wallet.id);
+
+    cleanup_test_data(&pool).await;
+}
+
+#[tokio::test]
+async fn test_funded_wallets_and_pots_stay() {
+    let pool = setup_test_db().await;
+    cleanup_test_data(&pool).await;
+    let repo = WalletRepository::new(pool.clone());
+
+    let funded = repo.create_wallet("alice").await.unwrap();
+    repo.fund_wallet(&funded.id, dec!(1)).await.unwrap();
+    let with_pot = repo.create_wallet("bob").await.unwrap();
+    repo.create_pot(&with_pot.id, "Holiday").await.unwrap();
+    let empty = repo.create_wallet("carol").await.unwrap();
+
+    // Nothing is old enough under the default retention period
+    let archived = repo
+        .archive_inactive_wallets(Utc::now() - Duration::days(730), 100, "ops")
+        .await
+        .unwrap();
+    assert!(archived.is_empty());
+
+    // Everything old: pots and their parents stay together, funds stay put
+    let archived = repo
+        .archive_inactive_wallets(Utc::now() + Duration::days(1), 100, "ops")
+        .await
+        .unwrap();
+    assert!(!archived.contains(&funded.id));
+    assert!(!archived.contains(&with_pot.id));
+    assert!(archived.contains(&empty.id));
+
+    assert!(matches!(
+        repo.find_archived_wallet(&funded.id).await,
+        Err(WalletError::WalletNotFound(_))
+    ));
+
+    cleanup_test_data(&pool).await;
+}
@@ -0,0 +1,74 @@
+//! Integration tests for the admin audit log
+//!
+//! Run with: cargo test --test audit_log -- --test-threads=1
+
+mod common;
+
+use common::{cleanup_test_data, setup_test_db};
+use rust_decimal_macros::dec;
+use wallet_service::models::{
+    AdjustmentDirection, AdjustmentRequest, AuditLogQuery, AUDIT_VOUCHER_MINTED,
+    AUDIT_WALLET_ADJUSTED,
+};
+use wallet_service::repository::WalletRepository;
+
+#[tokio::test]
+async fn test_admin_actions_are_recorded_with_before_and_after() {
+    let pool = setup_test_db().await;
+    cleanup_test_data(&pool).await;
+    let repo = WalletRepository::new(pool.clone());
+
+    let wallet = repo.create_wallet("alice").await.unwrap();
+    repo.fund_wallet(&wallet.id, dec!(50)).await.unwrap();
+    let request = AdjustmentRequest {
+        direction: AdjustmentDirection::Debit,
+        amount: dec!(20),
+        reason_code: "CHARGEBACK".to_string(),
+        note: None,
+    };
+    repo.adjust_balance(&wallet.id, &request, "ops@example.com").await.unwrap();
+    let voucher = repo.create_voucher(dec!(10), "finance@example.com").await.unwrap();
+
+    let entries = repo.find_audit_entries(&AuditLogQuery::default(), 100).await.unwrap();
+    assert_eq!(entries.len(), 2);
+    // Newest first
+    assert_eq!(entries[0].action, AUDIT_VOUCHER_MINTED);
+    assert_eq!(entries[0].target_id, voucher.id);
+    assert!(entries[0].before_value.is_none());
+    // The code is a bearer secret - it never reaches the log
+    assert!(!entries[0].after_value.as_ref().unwrap().to_string().contains(&voucher.code));
+
+    let adjusted = &entries[1];
+    assert_eq!(adjusted.action, AUDIT_WALLET_ADJUSTED);
+    assert_eq!(adjusted.actor, "ops@example.com");
+    assert_eq!(adjusted.target_type, "wallet");
+    assert_eq!(adjusted.before_value.as_ref().unwrap()["version"], wallet.version + 1);
+    assert_eq!(adjusted.after_value.as_ref().unwrap()["version"], wallet.version + 2);
+
+    let by_actor = AuditLogQuery {
+        actor: Some("ops@example.com".to_string()),
+        ..AuditLogQuery::default()
+    };
+    assert_eq!(repo.find_audit_entries(&by_actor, 100).await.unwrap().len(), 1);
+
+    cleanup_test_data(&pool).await;
+}
+
+#[tokio::test]
+async fn test_entries_cannot_be_changed_or_removed() {
+    let pool = setup_test_db().await;
+    cleanup_test_data(&pool).await;
+    let repo = WalletRepository::new(pool.clone());
+
+    repo.create_voucher(dec!(10), "finance@example.com").await.unwrap();
+
+    let update = sqlx::query("UPDATE admin_audit_log SET actor = 'someone-else'")
+        .execute(&pool)
+        .await;
+    assert!(update.is_err());
+    let delete = sqlx::query("DELETE FROM admin_audit_log").execute(&pool).await;
+    assert!(delete.is_err());
+    assert_eq!(repo.find_audit_entries(&AuditLogQuery::default(), 100).await.unwrap().len(), 1);
+
+    cleanup_test_data(&pool).await;
+}
@@ -0,0 +1,299 @@
+//! Tests for bearer token authentication (no database needed)
+
+use axum::body::Body;
+use axum::http::{header, HeaderMap, Method, Request, StatusCode};
+use axum::{middleware, routing::get, Extension, Router};
+use jsonwebtoken::jwk::JwkSet;
+use jsonwebtoken::{encode, Algorithm, DecodingKey, EncodingKey, Header};
+use serde_json::json;
+use std::collections::HashMap;
+use std::sync::Arc;
+use tower::ServiceExt;
+use wallet_service::auth::{
+    actor, api_key_allows, api_key_prefix, authorize, bearer_token, discover_jwks_url,
+    generate_api_key, hash_api_key, may_access, may_read, require_auth, verifying_keys, AuthConfig, Authenticator,
+    ClaimMapping, Principal, Role, VerifyingKey,
+};
+use wallet_service::errors::WalletError;
+use wallet_service::models::ApiKeyScope;
+
+const SECRET: &[u8] = b"test-secret";
+const ISSUER: &str = "https://id.example.com/";
+
+fn authenticator() -> Authenticator {
+    let config = AuthConfig {
+        issuer: ISSUER.to_string(),
+        audience: Some("wallet-api".to_string()),
+        jwks_url: None,
+        leeway_secs: 0,
+        claims: ClaimMapping {
+            roles_claim: "realm_access.roles".to_string(),
+            ..ClaimMapping::default()
+        },
+    };
+    let key = VerifyingKey {
+        key: DecodingKey::from_secret(SECRET),
+        algorithm: Algorithm::HS256,
+    };
+    Authenticator::with_keys(config, HashMap::from([("k1".to_string(), key)]))
+}
+
+fn token(kid: &str, alg: Algorithm, claims: serde_json::Value) -> String {
+    let mut header = Header::new(alg);
+    header.kid = Some(kid.to_string());
+    encode(&header, &claims, &EncodingKey::from_secret(SECRET)).unwrap()
+}
+
+fn claims(sub: &str, iss: &str, exp_in_secs: i64) -> serde_json::Value {
+    json!({
+        "sub": sub,
+        "iss": iss,
+        "aud": "wallet-api",
+        "exp": chrono::Utc::now().timestamp() + exp_in_secs,
+    })
+}
+
+fn is_unauthorized(result: Result<Principal, WalletError>, reason: &str) -> bool {
+    matches!(result, Err(WalletError::Unauthorized(ref r)) if r == reason)
+}
+
+#[tokio::test]
+async fn test_valid_token_names_the_caller() {
+    let token = token("k1", Algorithm::HS256, claims("alice", ISSUER, 300));
+    let principal = authenticator().verify(&token).await.unwrap();
+
+    assert_eq!(principal.subject, "alice");
+    assert_eq!(principal.role, Role::User);
+    assert!(principal.owns("alice"));
+    assert!(!principal.owns("bob"));
+    assert!(may_access(Some(&principal), "alice"));
+    assert!(!may_access(Some(&principal), "bob"));
+    // AUTH=off: nobody to check against
+    assert!(may_access(None, "bob"));
+    // Admin actions are recorded against the token, not the request body
+    assert_eq!(actor(Some(&principal)), "alice");
+    assert_eq!(actor(None), "unauthenticated");
+}
+
+#[tokio::test]
+async fn test_roles_come_from_the_configured_claim() {
+    let mut with_roles = claims("carol", ISSUER, 300);
+    with_roles["realm_access"] = json!({"roles": ["offline_access", "support"]});
+    let support_token = token("k1", Algorithm::HS256, with_roles);
+    let support = authenticator().verify(&support_token).await.unwrap();
+    assert_eq!(support.role, Role::Support);
+
+    // Support reads anyone's wallets but only moves money in their own
+    assert!(support.can_read("alice"));
+    assert!(!support.owns("alice"));
+    assert!(may_read(Some(&support), "alice"));
+    assert!(!may_access(Some(&support), "alice"));
+
+    // Roles elsewhere in the token don't count
+    let mut elsewhere = claims("mallory", ISSUER, 300);
+    elsewhere["roles"] = json!(["admin"]);
+    let elsewhere_token = token("k1", Algorithm::HS256, elsewhere);
+    assert_eq!(authenticator().verify(&elsewhere_token).await.unwrap().role, Role::User);
+
+    let admin = Principal::user("root").with_role(Role::Admin);
+    assert!(admin.owns("alice"));
+}
+
+#[test]
+fn test_roles_guard_admin_and_webhook_routes() {
+    let user = Principal::user("alice");
+    let support = Principal::user("carol").with_role(Role::Support);
+    let admin = Principal::user("root").with_role(Role::Admin);
+    let adjust = "/admin/wallets/w-1/adjustments";
+
+    for principal in [&user, &support, &admin] {
+        assert!(authorize(principal, &Method::POST, "/wallets/w-1/fund").is_ok());
+    }
+
+    assert!(matches!(
+        authorize(&user, &Method::GET, "/admin/audit-log"),
+        Err(WalletError::Forbidden(_))
+    ));
+    assert!(authorize(&user, &Method::GET, "/webhooks").is_err());
+
+    assert!(authorize(&support, &Method::GET, "/admin/audit-log").is_ok());
+    assert!(authorize(&support, &Method::GET, "/webhooks").is_ok());
+    assert!(authorize(&support, &Method::POST, "/admin/transactions/t-1/notes").is_ok());
+    assert!(authorize(&support, &Method::POST, adjust).is_err());
+    assert!(authorize(&support, &Method::POST, "/webhooks").is_err());
+
+    assert!(authorize(&admin, &Method::POST, adjust).is_ok());
+    assert!(authorize(&admin, &Method::POST, "/admin/api-keys").is_ok());
+}
+
+#[tokio::test]
+async fn test_bad_tokens_are_unauthorized() {
+    let auth = authenticator();
+
+    let wrong_issuer = token("k1", Algorithm::HS256, claims("alice", "https://evil/", 300));
+    assert!(is_unauthorized(auth.verify(&wrong_issuer).await, "invalid token"));
+
+    let expired = token("k1", Algorithm::HS256, claims("alice", ISSUER, -60));
+    assert!(is_unauthorized(auth.verify(&expired).await, "token expired"));
+
+    let mut wrong_audience = claims("alice", ISSUER, 300);
+    wrong_audience["aud"] = json!("other-api");
+    let wrong_audience = token("k1", Algorithm::HS256, wrong_audience);
+    assert!(is_unauthorized(auth.verify(&wrong_audience).await, "invalid token"));
+
+    // The key only signs HS256
+    let wrong_alg = token("k1", Algorithm::HS384, claims("alice", ISSUER, 300));
+    assert!(is_unauthorized(auth.verify(&wrong_alg).await, "wrong algorithm for this key"));
+
+    let unknown_kid = token("k2", Algorithm::HS256, claims("alice", ISSUER, 300));
+    assert!(is_unauthorized(auth.verify(&unknown_kid).await, "unknown signing key"));
+
+    assert!(is_unauthorized(auth.verify("not-a-jwt").await, "malformed token"));
+}
+
+#[test]
+fn test_jwks_keys_get_an_algorithm_and_symmetric_ones_are_dropped() {
+    let jwks: JwkSet = serde_json::from_value(json!({
+        "keys": [
+            {"kty": "RSA", "kid": "rsa", "n": "sXchDaQebHnPiGvyDOAT4saGEUetSyo9MKLOoWFsueri", "e": "AQAB"},
+            {"kty": "RSA", "kid": "pss", "alg": "PS256", "n": "sXchDaQebHnPiGvyDOAT4saGEUetSyo9MKLOoWFsueri", "e": "AQAB"},
+            {"kty": "oct", "kid": "shared", "k": "c2VjcmV0"},
+            {"kty": "RSA", "n": "sXchDaQebHnPiGvyDOAT4saGEUetSyo9MKLOoWFsueri", "e": "AQAB"}
+        ]
+    }))
+    .unwrap();
+
+    let keys = verifying_keys(&jwks);
+    assert_eq!(keys.len(), 2);
+    assert_eq!(keys["rsa"].algorithm, Algorithm::RS256);
+    assert_eq!(keys["pss"].algorithm, Algorithm::PS256);
+}
+
+/// An OpenID provider on localhost whose discovery document names `issuer`
+async fn provider(issuer: Option<&str>) -> String {
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let base = format!("http://{}", listener.local_addr().unwrap());
+    let document = json!({
+        "issuer": issuer.unwrap_or(&base),
+        "jwks_uri": format!("{}/protocol/openid-connect/certs", base),
+    });
+    let app = Router::new().route(
+        "/.well-known/openid-configuration",
+        get(move || async move { axum::Json(document) }),
+    );
+    tokio::spawn(async move { axum::serve(listener, app).await });
+    base
+}
+
+#[tokio::test]
+async fn test_jwks_url_comes_from_the_discovery_document() {
+    let client = reqwest::Client::new();
+
+    let issuer = provider(None).await;
+    let jwks_url = discover_jwks_url(&client, &issuer).await.unwrap();
+    assert_eq!(jwks_url, format!("{}/protocol/openid-connect/certs", issuer));
+
+    // A document describing some other issuer is not trusted
+    let impostor = provider(Some("https://id.example.com")).await;
+    let error = discover_jwks_url(&client, &impostor).await.unwrap_err();
+    assert!(error.contains("describes issuer https://id.example.com"), "{}", error);
+}
+
+#[test]
+fn test_api_keys_are_random_and_stored_hashed() {
+    let key = generate_api_key();
+    assert!(key.starts_with("wk_"));
+    assert_eq!(key.len(), 3 + 64);
+    assert_ne!(key, generate_api_key());
+
+    assert_eq!(api_key_prefix(&key), key[..11]);
+    let hash = hash_api_key(&key);
+    assert_eq!(hash.len(), 64);
+    assert_eq!(hash, hash_api_key(&key));
+    assert_ne!(hash, hash_api_key(&generate_api_key()));
+}
+
+#[test]
+fn test_api_key_scopes() {
+    use ApiKeyScope::{Read, Transact};
+
+    assert!(api_key_allows(Read, &Method::GET, "/wallets/w-1"));
+    assert!(api_key_allows(Read, &Method::POST, "/wallets/batch-get"));
+    assert!(!api_key_allows(Read, &Method::POST, "/wallets/w-1/fund"));
+    assert!(!api_key_allows(Read, &Method::PATCH, "/wallets/w-1"));
+
+    assert!(api_key_allows(Transact, &Method::POST, "/wallets/w-1/fund"));
+    assert!(api_key_allows(Transact, &Method::POST, "/transfers"));
+
+    // Keys are never admins, whatever their scope
+    assert!(!api_key_allows(Transact, &Method::POST, "/admin/api-keys"));
+    assert!(!api_key_allows(Read, &Method::GET, "/admin/audit-log"));
+    // ...and can't erase a user
+    assert!(!api_key_allows(Transact, &Method::DELETE, "/users/u-1/data"));
+    assert!(api_key_allows(Read, &Method::GET, "/users/u-1/wallets"));
+}
+
+#[test]
+fn test_bearer_token_parsing() {
+    let headers = |value: &str| {
+        let mut headers = HeaderMap::new();
+        headers.insert(header::AUTHORIZATION, value.parse().unwrap());
+        headers
+    };
+
+    assert_eq!(bearer_token(&headers("Bearer abc.def")), Some("abc.def"));
+    assert_eq!(bearer_token(&headers("bearer abc.def")), Some("abc.def"));
+    assert_eq!(bearer_token(&headers("Basic dXNlcjpwdw==")), None);
+    assert_eq!(bearer_token(&headers("Bearer")), None);
+    assert_eq!(bearer_token(&HeaderMap::new()), None);
+}
+
+fn app() -> Router {
+    Router::new()
+        .route("/health", get(|| async { "ok" }))
+        .route(
+            "/me",
+            get(|Extension(principal): Extension<Principal>| async move { principal.subject }),
+        )
+        .layer(middleware::from_fn_with_state(Arc::new(authenticator()), require_auth))
+}
+
+fn get_with(path: &str, token: Option<&str>) -> Request<Body> {
+    let mut request = Request::get(path);
+    if let Some(token) = token {
+        request = request.header(header::AUTHORIZATION, format!("Bearer {}", token));
+    }
+    request.body(Body::empty()).unwrap()
+}
+
+#[tokio::test]
+async fn test_middleware_rejects_missing_tokens_but_not_on_public_paths() {
+    let response = app().oneshot(get_with("/me", None)).await.unwrap();
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    assert_eq!(
+        response.headers()[header::WWW_AUTHENTICATE],
+        "Bearer error=\"invalid_token\""
+    );
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let body: serde_json::Value = serde_json::from_slice(&body).unwrap();
+    assert_eq!(body["error"], "Unauthorized: missing bearer token");
+
+    let response = app().oneshot(get_with("/health", None)).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let token = token("k1", Algorithm::HS256, claims("alice", ISSUER, 300));
+    let response = app().oneshot(get_with("/me", Some(&token))).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    assert_eq!(&body[..], b"alice");
+}
+
+#[tokio::test]
+async fn test_api_keys_need_a_key_store() {
+    let request = Request::get("/me")
+        .header("X-API-Key", generate_api_key())
+        .body(Body::empty())
+        .unwrap();
+    let response = app().oneshot(request).await.unwrap();
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+}
@@ -0,0 +1,126 @@
+//! Integration tests for hot-wallet balance sharding
+//!
+//! Run with: cargo test --test balance_shards -- --test-threads=1
+
+mod common;
+
+use common::{cleanup_test_data, setup_test_db};
+use rust_decimal::Decimal;
+use rust_decimal_macros::dec;
+use wallet_service::errors::WalletError;
+use wallet_service::models::{AuditLogQuery, AUDIT_BALANCE_SHARDS_CHANGED};
+use wallet_service::repository::WalletRepository;
+
+#[tokio::test]
+async fn test_transfers_into_a_sharded_wallet_credit_its_shards() {
+    let pool = setup_test_db().await;
+    cleanup_test_data(&pool).await;
+    let repo = WalletRepository::new(pool.clone());
+
+    let merchant = repo.create_wallet("merchant").await.unwrap();
+    repo.fund_wallet(&merchant.id, dec!(10)).await.unwrap();
+    let shards = repo.set_balance_shards(&merchant.id, 4, "ops@example.com").await.unwrap();
+    assert_eq!(shards.shards, 4);
+    assert_eq!(shards.shard_balances, vec![dec!(0); 4]);
+    assert_eq!((shards.row_balance, shards.balance), (dec!(10), dec!(10)));
+
+    let mut customers = Vec::new();
+    for i in 0..8 {
+        let customer = repo.create_wallet(&format!("customer_{}", i)).await.unwrap();
+        repo.fund_wallet(&customer.id, dec!(100)).await.unwrap();
+        customers.push(customer);
+    }
+
+    // Paid by every customer at once
+    let handles: Vec<_> = customers
+        .iter()
+        .map(|customer| {
+            let repo = repo.clone();
+            let (from, to) = (customer.id.clone(), merchant.id.clone());
+            tokio::spawn(async move { repo.transfer(&from, &to, dec!(5), None).await })
+        })
+        .collect();
+    for result in futures::future::join_all(handles).await {
+        result.unwrap().unwrap();
+    }
+
+    // The credits went to the shards, round-robin; reads see the total
+    let shards = repo.find_balance_shards(&merchant.id).await.unwrap();
+    assert_eq!(shards.row_balance, dec!(10));
+    assert_eq!(shards.shard_balances.iter().sum::<Decimal>(), dec!(40));
+    assert!(shards.shard_balances.iter().all(|balance| *balance > dec!(0)));
+    assert_eq!(shards.balance, dec!(50));
+    assert_eq!(repo.find_by_id(&merchant.id).await.unwrap().balance, dec!(50));
+
+    // Every shard chain still verifies
+    let report = repo.verify_ledger(Some(&merchant.id), 3).await.unwrap();
+    assert!(report.intact);
+    assert_eq!(report.entries_checked, 9);
+
+    cleanup_test_data(&pool).await;
+}
+
+#[tokio::test]
+async fn test_debits_fold_the_shards_into_the_wallet() {
+    let pool = setup_test_db().await;
+    cleanup_test_data(&pool).await;
+    let repo = WalletRepository::new(pool.clone());
+
+    let merchant = repo.create_wallet("merchant").await.unwrap();
+    let customer = repo.create_wallet("customer").await.unwrap();
+    repo.fund_wallet(&customer.id, dec!(100)).await.unwrap();
+    repo.set_balance_shards(&merchant.id, 2, "ops@example.com").await.unwrap();
+    repo.transfer(&customer.id, &merchant.id, dec!(30), None).await.unwrap();
+    repo.transfer(&customer.id, &merchant.id, dec!(20), None).await.unwrap();
+
+    // Spending more than the wallet row holds works: the shards fold first
+    repo.transfer(&merchant.id, &customer.id, dec!(45), None).await.unwrap();
+    let shards = repo.find_balance_shards(&merchant.id).await.unwrap();
+    assert_eq!(shards.shard_balances, vec![dec!(0), dec!(0)]);
+    assert_eq!((shards.row_balance, shards.balance), (dec!(5), dec!(5)));
+
+    let overdraw = repo.transfer(&merchant.id, &customer.id, dec!(6), None).await;
+    assert!(matches!(overdraw, Err(WalletError::InsufficientBalance { .. })));
+
+    let report = repo.verify_ledger(Some(&merchant.id), 100).await.unwrap();
+    assert!(report.intact);
+
+    cleanup_test_data(&pool).await;
+}
+
+#[tokio::test]
+async fn test_unsharding_keeps_the_balance_and_is_audited() {
+    let pool = setup_test_db().await;
+    cleanup_test_data(&pool).await;
+    let repo = WalletRepository::new(pool.clone());
+
+    let merchant = repo.create_wallet("merchant").await.unwrap();
+    let customer = repo.create_wallet("customer").await.unwrap();
+    repo.fund_wallet(&customer.id, dec!(100)).await.unwrap();
+    repo.set_balance_shards(&merchant.id, 3, "ops@example.com").await.unwrap();
+    repo.transfer(&customer.id, &merchant.id, dec!(25), None).await.unwrap();
+
+    let off = repo.set_balance_shards(&merchant.id, 0, "ops@example.com").await.unwrap();
+    assert_eq!(off.shards, 0);
+    assert!(off.shard_balances.is_empty());
+    assert_eq!((off.row_balance, off.balance), (dec!(25), dec!(25)));
+
+    // Transfers in lock the row again
+    repo.transfer(&customer.id, &merchant.id, dec!(5), None).await.unwrap();
+    assert_eq!(repo.find_by_id(&merchant.id).await.unwrap().balance, dec!(30));
+
+    let entries = repo.find_audit_entries(&AuditLogQuery::default(), 100).await.unwrap();
+    assert_eq!(entries.len(), 2);
+    assert!(entries.iter().all(|e| e.action == AUDIT_BALANCE_SHARDS_CHANGED));
+    assert_eq!(entries[0].before_value.as_ref().unwrap()["balance_shards"], 3);
+    assert_eq!(entries[0].after_value.as_ref().unwrap()["balance_shards"], 0);
+
+    // Pots can't be sharded; nor can a wallet be split too far
+    let pot = repo.create_pot(&merchant.id, "Taxes").await.unwrap();
+    let pot_shards = repo.set_balance_shards(&pot.id, 2, "ops@example.com").await;
+    assert!(matches!(pot_shards, Err(WalletError::InvalidRequest(_))));
+    let too_many = repo.set_balance_shards(&merchant.id, 65, "ops@example.com").await;
+    assert!(matches!(too_many, Err(WalletError::InvalidRequest(_))));
+
+    cleanup_test_data(&pool).await;
+}
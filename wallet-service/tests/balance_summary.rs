@@ -0,0 +1,51 @@
+//! Tests for the per-user balance summary (no database needed)
+
+use chrono::Utc;
+use rust_decimal::Decimal;
+use wallet_service::models::{UserBalanceSummary, Wallet};
+
+fn wallet(id: &str, balance: i64, parent: Option<&str>) -> Wallet {
+    Wallet {
+        id: id.to_string(),
+        user_id: "alice".to_string(),
+        balance: Decimal::new(balance, 2),
+        version: 1,
+        parent_wallet_id: parent.map(str::to_string),
+        nickname: None,
+        labels: Vec::new(),
+        is_default: false,
+        created_at: Utc::now(),
+        updated_at: Utc::now(),
+    }
+}
+
+#[test]
+fn test_totals_split_spendable_and_pots() {
+    let summary = UserBalanceSummary::new(
+        "alice".to_string(),
+        "USD",
+        vec![
+            wallet("main", 10_000, None),
+            wallet("holiday", 2_550, Some("main")),
+            wallet("second", 500, None),
+        ],
+    );
+
+    assert_eq!(summary.wallets.len(), 3);
+    assert_eq!(summary.totals.len(), 1);
+    let total = &summary.totals[0];
+    assert_eq!(total.currency, "USD");
+    assert_eq!(total.spendable_balance, Decimal::new(10_500, 2));
+    assert_eq!(total.allocated_balance, Decimal::new(2_550, 2));
+    assert_eq!(total.total_balance, Decimal::new(13_050, 2));
+    assert_eq!(total.wallet_count, 3);
+}
+
+#[test]
+fn test_user_without_wallets_has_zero_total() {
+    let summary = UserBalanceSummary::new("nobody".to_string(), "EUR", Vec::new());
+
+    assert!(summary.wallets.is_empty());
+    assert_eq!(summary.totals[0].currency, "EUR");
+    assert_eq!(summary.totals[0].total_balance, Decimal::ZERO);
+}
@@ -0,0 +1,118 @@
+//! Integration tests for saved beneficiaries
+//!
+//! Run with: cargo test --test beneficiaries -- --test-threads=1
+
+mod common;
+
+use common::{cleanup_test_data, setup_test_db};
+use rust_decimal_macros::dec;
+use wallet_service::{errors::WalletError, repository::WalletRepository};
+
+#[tokio::test]
+async fn test_create_and_list_beneficiaries() {
+    let pool = setup_test_db().await;
+    let repo = WalletRepository::new(pool.clone());
+
+    let bob_wallet = repo.create_wallet("bob").await.unwrap();
+
+    repo.create_beneficiary("alice", "Bob", Some(&bob_wallet.id), None)
+        .await
+        .expect("Failed to save wallet beneficiary");
+    repo.create_beneficiary("alice", "Carol", None, Some("carol"))
+        .await
+        .expect("Failed to save user beneficiary");
+
+    let beneficiaries = repo.find_beneficiaries("alice").await.unwrap();
+    assert_eq!(beneficiaries.len(), 2);
+    assert_eq!(beneficiaries[0].name, "Bob");
+    assert_eq!(beneficiaries[0].wallet_id.as_deref(), Some(bob_wallet.id.as_str()));
+    assert_eq!(beneficiaries[1].beneficiary_user_id.as_deref(), Some("carol"));
+
+    // Other users don't see Alice's contacts
+    assert!(repo.find_beneficiaries("bob").await.unwrap().is_empty());
+
+    cleanup_test_data(&pool).await;
+}
+
+#[tokio::test]
+async fn test_beneficiary_requires_exactly_one_reference() {
+    let pool = setup_test_db().await;
+    let repo = WalletRepository::new(pool.clone());
+
+    let result = repo.create_beneficiary("alice", "Nobody", None, None).await;
+    assert!(matches!(result, Err(WalletError::InvalidRequest(_))));
+
+    cleanup_test_data(&pool).await;
+}
+
+#[tokio::test]
+async fn test_duplicate_beneficiary_name() {
+    let pool = setup_test_db().await;
+    let repo = WalletRepository::new(pool.clone());
+
+    repo.create_beneficiary("alice", "Bob", None, Some("bob"))
+        .await
+        .unwrap();
+    let result = repo.create_beneficiary("alice", "Bob", None, Some("bob")).await;
+
+    assert!(matches!(result, Err(WalletError::DuplicateBeneficiary(_))));
+
+    cleanup_test_data(&pool).await;
+}
+
+#[tokio::test]
+async fn test_transfer_via_user_beneficiary() {
+    let pool = setup_test_db().await;
+    let repo = WalletRepository::new(pool.clone());
+
+    let alice_wallet = repo.create_wallet("alice").await.unwrap();
+    let bob_primary = repo.create_wallet("bob").await.unwrap();
+    let _bob_secondary = repo.create_wallet("bob").await.unwrap();
+    repo.fund_wallet(&alice_wallet.id, dec!(100)).await.unwrap();
+
+    let beneficiary = repo
+        .create_beneficiary("alice", "Bob", None, Some("bob"))
+        .await
+        .unwrap();
+
+    // User references resolve to the recipient's oldest wallet
+    let to_wallet_id = repo
+        .resolve_beneficiary("alice", &beneficiary.id)
+        .await
+        .unwrap();
+    assert_eq!(to_wallet_id, bob_primary.id);
+
+    repo.transfer(&alice_wallet.id, &to_wallet_id, dec!(25), None)
+        .await
+        .unwrap();
+    let bob_final = repo.find_by_id(&bob_primary.id).await.unwrap();
+    assert_eq!(bob_final.balance, dec!(25));
+
+    // Someone else can't use Alice's beneficiary
+    let result = repo.resolve_beneficiary("mallory", &beneficiary.id).await;
+    assert!(matches!(result, Err(WalletError::BeneficiaryNotFound(_))));
+
+    cleanup_test_data(&pool).await;
+}
+
+#[tokio::test]
+async fn test_delete_beneficiary() {
+    let pool = setup_test_db().await;
+    let repo = WalletRepository::new(pool.clone());
+
+    let beneficiary = repo
+        .create_beneficiary("alice", "Bob", None, Some("bob"))
+        .await
+        .unwrap();
+
+    // Only the owner can delete it
+    let result = repo.delete_beneficiary("bob", &beneficiary.id).await;
+    assert!(matches!(result, Err(WalletError::BeneficiaryNotFound(_))));
+
+    repo.delete_beneficiary("alice", &beneficiary.id)
+        .await
+        .expect("Failed to delete beneficiary");
+    assert!(repo.find_beneficiaries("alice").await.unwrap().is_empty());
+
+    cleanup_test_data(&pool).await;
+}
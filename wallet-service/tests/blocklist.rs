@@ -0,0 +1,103 @@
+//! Integration tests for the wallet/user blocklist
+//!
+//! Run with: cargo test --test blocklist -- --test-threads=1
+
+mod common;
+
+use common::{cleanup_test_data, setup_test_db};
+use rust_decimal_macros::dec;
+use wallet_service::errors::WalletError;
+use wallet_service::models::{
+    AuditLogQuery, BlocklistTargetType, CreateBlocklistEntryRequest, AUDIT_BLOCKLIST_ENTRY_ADDED,
+    AUDIT_BLOCKLIST_ENTRY_REMOVED,
+};
+use wallet_service::repository::WalletRepository;
+
+fn block(target_type: BlocklistTargetType, target_id: &str) -> CreateBlocklistEntryRequest {
+    CreateBlocklistEntryRequest {
+        target_type,
+        target_id: target_id.to_string(),
+        reason: "confirmed mule account".to_string(),
+    }
+}
+
+#[tokio::test]
+async fn test_transfers_to_or_from_blocklisted_targets_are_refused() {
+    let pool = setup_test_db().await;
+    let repo = WalletRepository::new(pool.clone());
+    let alice = repo.create_wallet("alice").await.unwrap();
+    let bob = repo.create_wallet("bob").await.unwrap();
+    repo.fund_wallet(&alice.id, dec!(100)).await.unwrap();
+    repo.fund_wallet(&bob.id, dec!(100)).await.unwrap();
+
+    // A blocked wallet can't receive...
+    let entry = repo
+        .create_blocklist_entry(&block(BlocklistTargetType::Wallet, &bob.id), "fraud@example.com")
+        .await
+        .expect("Failed to blocklist wallet");
+    let to_blocked = repo.transfer(&alice.id, &bob.id, dec!(10), None).await;
+    assert!(matches!(to_blocked, Err(WalletError::Blocklisted("recipient"))));
+
+    // ...or send
+    let from_blocked = repo.transfer(&bob.id, &alice.id, dec!(10), None).await;
+    assert!(matches!(from_blocked, Err(WalletError::Blocklisted("sender"))));
+    assert_eq!(repo.find_by_id(&alice.id).await.unwrap().balance, dec!(100));
+
+    // Once removed, transfers go through again
+    let removed = repo.remove_blocklist_entry(&entry.id, "ops@example.com").await.unwrap();
+    assert!(removed.removed_at.is_some());
+    repo.transfer(&alice.id, &bob.id, dec!(10), None).await.unwrap();
+
+    // A blocked user covers every wallet they own
+    repo.create_blocklist_entry(&block(BlocklistTargetType::User, "alice"), "fraud@example.com")
+        .await
+        .unwrap();
+    let user_blocked = repo.transfer(&bob.id, &alice.id, dec!(10), None).await;
+    assert!(matches!(user_blocked, Err(WalletError::Blocklisted("recipient"))));
+
+    cleanup_test_data(&pool).await;
+}
+
+#[tokio::test]
+async fn test_one_live_entry_per_target_and_changes_are_audited() {
+    let pool = setup_test_db().await;
+    let repo = WalletRepository::new(pool.clone());
+
+    let entry = repo
+        .create_blocklist_entry(&block(BlocklistTargetType::User, "mallory"), "fraud@example.com")
+        .await
+        .unwrap();
+    let again = repo
+        .create_blocklist_entry(&block(BlocklistTargetType::User, "mallory"), "fraud@example.com")
+        .await;
+    assert!(matches!(again, Err(WalletError::DuplicateBlocklistEntry(_))));
+
+    // Removing twice keeps the first removal
+    let removed = repo.remove_blocklist_entry(&entry.id, "ops@example.com").await.unwrap();
+    let twice = repo.remove_blocklist_entry(&entry.id, "someone@example.com").await.unwrap();
+    assert_eq!(twice.removed_by.as_deref(), Some("ops@example.com"));
+    assert_eq!(twice.removed_at, removed.removed_at);
+    let unknown = repo.remove_blocklist_entry("no-such-entry", "ops@example.com").await;
+    assert!(matches!(unknown, Err(WalletError::BlocklistEntryNotFound(_))));
+
+    // Blocking again after removal is fine; removed entries stay listed
+    repo.create_blocklist_entry(&block(BlocklistTargetType::User, "mallory"), "fraud@example.com")
+        .await
+        .unwrap();
+    assert_eq!(repo.find_blocklist_entries().await.unwrap().len(), 2);
+
+    let entries = repo.find_audit_entries(&AuditLogQuery::default(), 100).await.unwrap();
+    let actions: Vec<&str> = entries.iter().map(|e| e.action.as_str()).collect();
+    assert_eq!(
+        actions,
+        vec![
+            AUDIT_BLOCKLIST_ENTRY_ADDED,
+            AUDIT_BLOCKLIST_ENTRY_REMOVED,
+            AUDIT_BLOCKLIST_ENTRY_REMOVED,
+            AUDIT_BLOCKLIST_ENTRY_ADDED,
+        ]
+    );
+    assert_eq!(entries[3].target_type, "blocklist_entry");
+
+    cleanup_test_data(&pool).await;
+}
@@ -0,0 +1,80 @@
+//! Tests for request body limits (no database needed)
+
+use axum::body::Body;
+use axum::http::{header, Request, StatusCode};
+use axum::{middleware, routing::post, Router};
+use std::sync::Arc;
+use tower::ServiceExt;
+use wallet_service::body_limit::{is_json_content_type, require_json_body, BodyLimits};
+
+fn app() -> Router {
+    Router::new()
+        .route("/echo", post(|body: String| async move { body }))
+        .layer(middleware::from_fn_with_state(
+            Arc::new(BodyLimits { max_bytes: 16 }),
+            require_json_body,
+        ))
+}
+
+fn post_body(content_type: Option<&str>, body: &str) -> Request<Body> {
+    let mut request = Request::post("/echo").header(header::CONTENT_LENGTH, body.len());
+    if let Some(content_type) = content_type {
+        request = request.header(header::CONTENT_TYPE, content_type);
+    }
+    request.body(Body::from(body.to_string())).unwrap()
+}
+
+#[test]
+fn test_json_content_types() {
+    let headers = |value: &str| {
+        let mut headers = axum::http::HeaderMap::new();
+        headers.insert(header::CONTENT_TYPE, value.parse().unwrap());
+        headers
+    };
+
+    assert!(is_json_content_type(&headers("application/json")));
+    assert!(is_json_content_type(&headers("Application/JSON; charset=utf-8")));
+    assert!(is_json_content_type(&headers("application/merge-patch+json")));
+    assert!(!is_json_content_type(&headers("text/plain")));
+    assert!(!is_json_content_type(&headers("application/x-www-form-urlencoded")));
+    assert!(!is_json_content_type(&axum::http::HeaderMap::new()));
+}
+
+#[tokio::test]
+async fn test_json_within_the_limit_passes_through() {
+    let response = app()
+        .oneshot(post_body(Some("application/json"), r#"{"a":1}"#))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    assert_eq!(&body[..], br#"{"a":1}"#);
+
+    // No body, no Content-Type needed
+    let response = app().oneshot(post_body(None, "")).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+}
+
+#[tokio::test]
+async fn test_wrong_type_is_415_and_oversized_is_413() {
+    let response = app().oneshot(post_body(Some("text/plain"), "hello")).await.unwrap();
+    assert_eq!(response.status(), StatusCode::UNSUPPORTED_MEDIA_TYPE);
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let body: serde_json::Value = serde_json::from_slice(&body).unwrap();
+    assert_eq!(body["success"], false);
+
+    let response = app()
+        .oneshot(post_body(Some("application/json"), &format!(r#"{{"a":"{}"}}"#, "x".repeat(20))))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::PAYLOAD_TOO_LARGE);
+
+    // Chunked - no Content-Length to check up front
+    let chunked = Request::post("/echo")
+        .header(header::CONTENT_TYPE, "application/json")
+        .header(header::TRANSFER_ENCODING, "chunked")
+        .body(Body::from("x".repeat(20)))
+        .unwrap();
+    let response = app().oneshot(chunked).await.unwrap();
+    assert_eq!(response.status(), StatusCode::PAYLOAD_TOO_LARGE);
+}
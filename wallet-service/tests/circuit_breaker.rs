@@ -0,0 +1,105 @@
+//! Tests for the Kafka circuit breaker (no Kafka needed)
+
+use std::time::Duration;
+use wallet_service::circuit_breaker::{BreakerConfig, CircuitBreaker, CircuitState};
+
+fn breaker(failure_threshold: u32) -> CircuitBreaker {
+    CircuitBreaker::new(BreakerConfig {
+        failure_threshold,
+        open_for: Duration::from_millis(50),
+        max_open_for: Duration::from_millis(150),
+    })
+}
+
+#[test]
+fn test_opens_after_threshold_failures() {
+    let breaker = breaker(3);
+
+    for _ in 0..2 {
+        breaker.try_acquire().unwrap().failed();
+    }
+    assert_eq!(breaker.status().state, CircuitState::Closed);
+    assert!(breaker.status().available);
+
+    breaker.try_acquire().unwrap().failed();
+    let status = breaker.status();
+    assert_eq!(status.state, CircuitState::Open);
+    assert!(!status.available);
+    assert_eq!(status.consecutive_failures, 3);
+    assert!(breaker.try_acquire().is_err());
+}
+
+#[test]
+fn test_success_resets_failure_count() {
+    let breaker = breaker(2);
+
+    breaker.try_acquire().unwrap().failed();
+    breaker.try_acquire().unwrap().succeeded();
+    breaker.try_acquire().unwrap().failed();
+
+    assert_eq!(breaker.status().state, CircuitState::Closed);
+    assert_eq!(breaker.status().consecutive_failures, 1);
+}
+
+#[test]
+fn test_only_one_probe_when_half_open() {
+    let breaker = breaker(1);
+    breaker.try_acquire().unwrap().failed();
+    std::thread::sleep(Duration::from_millis(60));
+
+    let probe = breaker.try_acquire().expect("Cooldown over, expected a probe");
+    assert_eq!(breaker.status().state, CircuitState::HalfOpen);
+    // Everyone else still fails fast while the probe is out
+    assert!(breaker.try_acquire().is_err());
+
+    probe.succeeded();
+    assert_eq!(breaker.status().state, CircuitState::Closed);
+    assert!(breaker.try_acquire().is_ok());
+}
+
+#[test]
+fn test_failed_probe_reopens_for_longer() {
+    let breaker = breaker(1);
+    breaker.try_acquire().unwrap().failed();
+    std::thread::sleep(Duration::from_millis(60));
+
+    breaker.try_acquire().unwrap().failed();
+    let status = breaker.status();
+    assert_eq!(status.state, CircuitState::Open);
+    assert_eq!(status.consecutive_failures, 2);
+
+    // Second opening lasts 100ms, so still open after the first cooldown
+    std::thread::sleep(Duration::from_millis(60));
+    assert!(breaker.try_acquire().is_err());
+    std::thread::sleep(Duration::from_millis(60));
+    assert!(breaker.try_acquire().is_ok());
+}
+
+#[test]
+fn test_dropped_probe_lets_the_next_call_probe() {
+    let breaker = breaker(1);
+    breaker.try_acquire().unwrap().failed();
+    std::thread::sleep(Duration::from_millis(60));
+
+    // Caller gave up without reporting (request cancelled)
+    drop(breaker.try_acquire().unwrap());
+
+    let probe = breaker.try_acquire().expect("Expected the probe slot to be free");
+    probe.succeeded();
+    assert_eq!(breaker.status().state, CircuitState::Closed);
+}
+
+#[test]
+fn test_late_failure_does_not_extend_open_circuit() {
+    let breaker = breaker(1);
+    // Two sends started while closed...
+    let first = breaker.try_acquire().unwrap();
+    let second = breaker.try_acquire().unwrap();
+
+    first.failed();
+    std::thread::sleep(Duration::from_millis(60));
+    // ...the second fails after the circuit opened: no longer cooldown
+    second.failed();
+
+    assert!(breaker.try_acquire().is_ok());
+}
@@ -0,0 +1,117 @@
+//! Tests for event encoding (no Kafka or Schema Registry needed)
+
+use chrono::{TimeZone, Utc};
+use rust_decimal_macros::dec;
+use wallet_service::avro::{Schema, WALLET_EVENT_SCHEMA};
+use wallet_service::codec::EventCodec;
+use wallet_service::kafka::{EventEnvelope, EventIds, WalletEvent};
+
+fn wallet_created_json() -> String {
+    serde_json::to_string(&EventEnvelope {
+        ids: EventIds {
+            event_id: "e1".to_string(),
+            correlation_id: "c1".to_string(),
+            causation_id: "c1".to_string(),
+            request_id: None,
+        },
+        sequence: Some(3),
+        event: WalletEvent::WalletCreated {
+            wallet_id: "w1".to_string(),
+            user_id: "u1".to_string(),
+            timestamp: Utc.timestamp_opt(1, 0).unwrap(),
+        },
+    })
+    .unwrap()
+}
+
+#[test]
+fn test_json_codec_passes_payload_through() {
+    let json = wallet_created_json();
+    assert_eq!(EventCodec::Json.encode(&json).unwrap(), json.as_bytes());
+}
+
+#[test]
+fn test_avro_codec_uses_confluent_framing() {
+    let codec = EventCodec::avro(7).unwrap();
+    let encoded = codec.encode(&wallet_created_json()).unwrap();
+
+    assert_eq!(
+        encoded,
+        vec![
+            0, 0, 0, 0, 7, // magic byte + schema ID 7
+            0, // union branch 0 = WalletCreated
+            4, b'w', b'1', // wallet_id (zig-zag length 2)
+            4, b'u', b'1', // user_id
+            0x80, 0x89, 0x7a, // timestamp: 1_000_000 micros
+            4, b'e', b'1', // event_id
+            4, b'c', b'1', // correlation_id
+            4, b'c', b'1', // causation_id
+            6, // sequence 3 (zig-zag)
+            0, // request_id: none, so the schema default ""
+        ]
+    );
+}
+
+#[test]
+fn test_every_event_type_has_an_avro_record() {
+    let schema = Schema::parse(WALLET_EVENT_SCHEMA).unwrap();
+    let funded = serde_json::to_value(WalletEvent::WalletFunded {
+        wallet_id: "w1".to_string(),
+        user_id: "u1".to_string(),
+        amount: dec!(10.50),
+        new_balance: dec!(20.00),
+        transaction_id: "t1".to_string(),
+        timestamp: Utc::now(),
+    })
+    .unwrap();
+
+    let mut out = Vec::new();
+    schema.encode(&funded, &mut out).unwrap();
+    assert_eq!(out[0], 2); // zig-zag 1 = second record
+
+    // A missing field fails loudly instead of writing a broken record
+    let mut broken = funded.clone();
+    broken.as_object_mut().unwrap().remove("amount");
+    assert!(schema.encode(&broken, &mut Vec::new()).is_err());
+}
+
+#[test]
+fn test_protobuf_codec_matches_proto_field_numbers() {
+    let codec = EventCodec::protobuf().unwrap();
+    let json = wallet_created_json();
+    let encoded = codec.encode(&json).unwrap();
+
+    assert_eq!(
+        encoded,
+        vec![
+            0x0a, 28, // field 1 (wallet_created), 28 bytes
+            0x0a, 2, b'w', b'1', // wallet_id = 1
+            0x12, 2, b'u', b'1', // user_id = 2
+            0x1a, 4, 0x08, 1, 0x10, 0, // timestamp = 3 { seconds: 1, nanos: 0 }
+            0x22, 2, b'e', b'1', // event_id = 4
+            0x2a, 2, b'c', b'1', // correlation_id = 5
+            0x32, 2, b'c', b'1', // causation_id = 6
+            0x38, 3, // sequence = 7 (varint)
+        ]
+    );
+    assert!(encoded.len() < json.len());
+}
+
+#[test]
+fn test_decline_events_encode_in_every_codec() {
+    let declined = serde_json::to_string(&WalletEvent::TransferFailed {
+        from_wallet_id: "w1".to_string(),
+        from_user_id: "u1".to_string(),
+        to_wallet_id: "w2".to_string(),
+        amount: dec!(500.00),
+        reason: "insufficient_balance".to_string(),
+        timestamp: Utc::now(),
+    })
+    .unwrap();
+
+    let avro = EventCodec::avro(7).unwrap().encode(&declined).unwrap();
+    assert_eq!(avro[5], 14); // zig-zag 7 = eighth record
+
+    let proto = EventCodec::protobuf().unwrap().encode(&declined).unwrap();
+    assert_eq!(proto[0], 0x42); // field 8 (transfer_failed), length-delimited
+}
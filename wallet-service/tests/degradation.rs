@@ -0,0 +1,51 @@
+//! Tests for the degradation controller (no database needed)
+
+use std::time::Duration;
+use wallet_service::degradation::{DegradationConfig, DegradationController};
+
+fn controller() -> DegradationController {
+    DegradationController::new(DegradationConfig {
+        db_latency_threshold: Duration::from_millis(100),
+        kafka_in_flight_threshold: 50,
+        probe_interval: Duration::from_secs(5),
+    })
+}
+
+#[test]
+fn test_enters_and_leaves_degraded_mode() {
+    let controller = controller();
+    assert!(!controller.is_degraded());
+
+    controller.evaluate(Some(Duration::from_millis(20)), 10);
+    assert!(!controller.is_degraded());
+
+    // Slow database
+    controller.evaluate(Some(Duration::from_millis(300)), 10);
+    assert!(controller.is_degraded());
+    let since = controller.status().since.expect("Expected degraded-since time");
+
+    // Still degraded (Kafka backlog now) - keeps the original start time
+    controller.evaluate(Some(Duration::from_millis(20)), 500);
+    let status = controller.status();
+    assert!(status.degraded);
+    assert_eq!(status.since, Some(since));
+    assert_eq!(status.reasons.len(), 1);
+
+    // Both healthy again
+    controller.evaluate(Some(Duration::from_millis(20)), 0);
+    assert!(!controller.is_degraded());
+    assert!(controller.status().since.is_none());
+}
+
+#[test]
+fn test_failed_probe_degrades_and_shows_in_metrics() {
+    let controller = controller();
+
+    controller.evaluate(None, 0);
+    assert!(controller.is_degraded());
+    assert_eq!(controller.retry_after_secs(), 5);
+
+    let output = controller.render_metrics();
+    assert!(output.contains("wallet_degraded 1\n"));
+    assert!(output.contains("wallet_db_probe_latency_ms -1\n"));
+}
@@ -0,0 +1,168 @@
+//! Tests for field-level encryption and key rotation
+//!
+//! Run with: cargo test --test encryption -- --test-threads=1
+
+mod common;
+
+use common::{cleanup_test_data, setup_test_db};
+use rust_decimal_macros::dec;
+use wallet_service::{
+    encryption::FieldCipher, errors::WalletError, models::UpdateWalletRequest,
+    repository::WalletRepository,
+};
+
+fn cipher(keys: &[(&str, u8)]) -> FieldCipher {
+    FieldCipher::new(
+        keys.iter().map(|(id, byte)| (id.to_string(), [*byte; 32])).collect(),
+        vec![7; 32],
+    )
+    .unwrap()
+}
+
+#[test]
+fn test_values_round_trip_under_any_configured_key() {
+    let old = cipher(&[("k1", 1)]);
+    let rotated = cipher(&[("k2", 2), ("k1", 1)]);
+
+    let sealed = old.seal("rent for March").unwrap();
+    assert!(sealed.starts_with("enc:k1:"));
+    assert!(!sealed.contains("rent"));
+    // A fresh nonce each time
+    assert_ne!(old.seal("rent for March").unwrap(), sealed);
+
+    // The rotated keyring still reads the old key, but seals with the new one
+    assert_eq!(rotated.open(&sealed).unwrap(), "rent for March");
+    assert!(!rotated.is_current(&sealed));
+    assert!(rotated.seal("x").unwrap().starts_with("enc:k2:"));
+    assert!(matches!(cipher(&[("k2", 2)]).open(&sealed), Err(WalletError::InternalError(_))));
+
+    // Plaintext from before encryption reads as it is
+    assert_eq!(rotated.open("dinner").unwrap(), "dinner");
+
+    // Tampering is caught
+    let mut tampered = sealed.clone();
+    let flipped = if sealed.ends_with('0') { "1" } else { "0" };
+    tampered.replace_range(sealed.len() - 1.., flipped);
+    assert!(old.open(&tampered).is_err());
+
+    // Without keys, nothing changes
+    let off = FieldCipher::default();
+    assert!(!off.is_enabled());
+    assert_eq!(off.seal("dinner").unwrap(), "dinner");
+    assert!(off.open(&sealed).is_err());
+}
+
+#[test]
+fn test_blind_index_is_keyed_and_stable() {
+    let keyed = cipher(&[("k1", 1)]);
+    assert_eq!(keyed.blind_index("Mum"), keyed.blind_index("Mum"));
+    assert_ne!(keyed.blind_index("Mum"), keyed.blind_index("Dad"));
+    // Rotating the data key leaves the index alone
+    assert_eq!(cipher(&[("k2", 2), ("k1", 1)]).blind_index("Mum"), keyed.blind_index("Mum"));
+    assert_ne!(FieldCipher::default().blind_index("Mum"), keyed.blind_index("Mum"));
+
+    assert!(FieldCipher::new(vec![("bad:id".to_string(), [1; 32])], vec![]).is_err());
+    assert!(FieldCipher::new(Vec::new(), vec![]).is_err());
+}
+
+#[tokio::test]
+async fn test_sensitive_columns_hold_only_ciphertext() {
+    let pool = setup_test_db().await;
+    let repo = WalletRepository::new(pool.clone()).with_field_cipher(cipher(&[("k1", 1)]));
+    let alice = repo.create_wallet("alice").await.unwrap();
+    let bob = repo.create_wallet("bob").await.unwrap();
+    repo.fund_wallet(&alice.id, dec!(100)).await.unwrap();
+
+    let outcome = repo.transfer(&alice.id, &bob.id, dec!(10), Some("rent")).await.unwrap();
+    assert_eq!(outcome.out_transaction.memo.as_deref(), Some("rent"));
+    repo.create_beneficiary("alice", "Bob", Some(&bob.id), None).await.unwrap();
+    let update = UpdateWalletRequest {
+        nickname: Some(Some("Bills".to_string())),
+        labels: Some(vec!["home".to_string()]),
+        is_default: None,
+    };
+    repo.update_wallet_metadata(&alice.id, &update, None).await.unwrap();
+
+    let memos: Vec<Option<String>> =
+        sqlx::query_scalar("SELECT memo FROM wallet_transactions WHERE memo IS NOT NULL")
+            .fetch_all(&pool)
+            .await
+            .unwrap();
+    assert_eq!(memos.len(), 2);
+    assert!(memos.iter().flatten().all(|memo| memo.starts_with("enc:k1:")));
+    let (nickname, labels): (String, Vec<String>) =
+        sqlx::query_as("SELECT nickname, labels FROM wallets WHERE id = $1")
+            .bind(&alice.id)
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+    assert!(nickname.starts_with("enc:") && labels[0].starts_with("enc:"));
+    let name: String = sqlx::query_scalar("SELECT name FROM beneficiaries")
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+    assert!(name.starts_with("enc:"));
+
+    // Reads decrypt
+    let wallet = repo.find_by_id(&alice.id).await.unwrap();
+    assert_eq!(wallet.nickname.as_deref(), Some("Bills"));
+    assert_eq!(wallet.labels, vec!["home"]);
+    let transactions = repo.find_wallet_transactions(&bob.id).await.unwrap();
+    assert_eq!(transactions[0].memo.as_deref(), Some("rent"));
+    assert_eq!(repo.find_beneficiaries("alice").await.unwrap()[0].name, "Bob");
+
+    // Uniqueness still holds on names the database can't read
+    let duplicate = repo.create_beneficiary("alice", "Bob", None, Some("bob")).await;
+    assert!(matches!(duplicate, Err(WalletError::DuplicateBeneficiary(_))));
+
+    cleanup_test_data(&pool).await;
+}
+
+#[tokio::test]
+async fn test_rekey_moves_old_and_plaintext_values_to_the_current_key() {
+    let pool = setup_test_db().await;
+    let plain = WalletRepository::new(pool.clone());
+    let alice = plain.create_wallet("alice").await.unwrap();
+    let bob = plain.create_wallet("bob").await.unwrap();
+    plain.fund_wallet(&alice.id, dec!(100)).await.unwrap();
+    plain.transfer(&alice.id, &bob.id, dec!(10), Some("before")).await.unwrap();
+    plain.create_beneficiary("alice", "Bob", Some(&bob.id), None).await.unwrap();
+    let update = UpdateWalletRequest {
+        nickname: Some(Some("Bills".to_string())),
+        labels: None,
+        is_default: None,
+    };
+    plain.update_wallet_metadata(&alice.id, &update, None).await.unwrap();
+
+    let old = WalletRepository::new(pool.clone()).with_field_cipher(cipher(&[("k1", 1)]));
+    old.transfer(&alice.id, &bob.id, dec!(10), Some("during")).await.unwrap();
+
+    let rotated =
+        WalletRepository::new(pool.clone()).with_field_cipher(cipher(&[("k2", 2), ("k1", 1)]));
+    let before = rotated.find_by_id(&alice.id).await.unwrap();
+    // 4 memos (2 per transfer), the beneficiary and the nickname
+    assert_eq!(rotated.rekey_fields(2).await.unwrap(), 6);
+    assert_eq!(rotated.rekey_fields(2).await.unwrap(), 0);
+
+    // Only the new key is needed now
+    let new_only = WalletRepository::new(pool.clone()).with_field_cipher(cipher(&[("k2", 2)]));
+    let memos: Vec<Option<String>> = new_only
+        .find_wallet_transactions(&bob.id)
+        .await
+        .unwrap()
+        .into_iter()
+        .map(|transaction| transaction.memo)
+        .collect();
+    assert_eq!(memos, vec![Some("before".to_string()), Some("during".to_string())]);
+
+    // The plaintext-era name hash was replaced by the keyed one
+    let duplicate = new_only.create_beneficiary("alice", "Bob", None, Some("bob")).await;
+    assert!(matches!(duplicate, Err(WalletError::DuplicateBeneficiary(_))));
+
+    // Re-encrypting isn't a change to the wallet
+    let after = new_only.find_by_id(&alice.id).await.unwrap();
+    assert_eq!(after.nickname.as_deref(), Some("Bills"));
+    assert_eq!((after.version, after.updated_at), (before.version, before.updated_at));
+
+    cleanup_test_data(&pool).await;
+}
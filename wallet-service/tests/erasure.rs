@@ -0,0 +1,90 @@
+//! Integration tests for erasing a user's data (GDPR)
+//!
+//! Run with: cargo test --test erasure -- --test-threads=1
+
+mod common;
+
+use common::{cleanup_test_data, setup_test_db};
+use rust_decimal_macros::dec;
+use wallet_service::errors::WalletError;
+use wallet_service::models::{AuditLogQuery, KycLevel, UpdateWalletRequest, AUDIT_USER_ANONYMIZED};
+use wallet_service::repository::WalletRepository;
+
+#[tokio::test]
+async fn test_user_is_anonymized_but_the_books_still_add_up() {
+    let pool = setup_test_db().await;
+    cleanup_test_data(&pool).await;
+    let repo = WalletRepository::new(pool.clone());
+
+    let alice = repo.create_wallet("alice").await.unwrap();
+    let bob = repo.create_wallet("bob").await.unwrap();
+    let update = UpdateWalletRequest {
+        nickname: Some(Some("Alice's spending".to_string())),
+        labels: Some(vec!["bills".to_string()]),
+        ..Default::default()
+    };
+    repo.update_wallet_metadata(&alice.id, &update, None).await.unwrap();
+    repo.create_beneficiary("alice", "Bob Smith", Some(&bob.id), None).await.unwrap();
+    repo.create_beneficiary("bob", "Alice Jones", None, Some("alice")).await.unwrap();
+    repo.set_user_kyc("alice", KycLevel::Verified, "kyc@example.com").await.unwrap();
+
+    repo.fund_wallet(&alice.id, dec!(100)).await.unwrap();
+    let transfer = repo
+        .transfer(&alice.id, &bob.id, dec!(100), Some("for Alice's rent"))
+        .await
+        .unwrap();
+
+    let anonymization = repo.anonymize_user("alice", "user").await.unwrap();
+    let pseudonym = anonymization.anonymized_user_id.as_str();
+    assert!(pseudonym.starts_with("anon-"));
+    assert_eq!(anonymization.wallet_ids, vec![alice.id.clone()]);
+
+    // Nothing is left under the old ID...
+    assert!(repo.find_by_user_id("alice").await.unwrap().is_empty());
+    assert!(repo.find_beneficiaries("alice").await.unwrap().is_empty());
+    assert!(repo.find_beneficiaries("bob").await.unwrap().is_empty());
+    assert_eq!(repo.find_user_kyc("alice").await.unwrap().level, KycLevel::Unverified);
+
+    // ...the wallet and KYC level are the pseudonym's, without the free text
+    let wallet = repo.find_by_id(&alice.id).await.unwrap();
+    assert_eq!(wallet.user_id, pseudonym);
+    assert_eq!(wallet.nickname, None);
+    assert!(wallet.labels.is_empty());
+    assert_eq!(repo.find_user_kyc(pseudonym).await.unwrap().level, KycLevel::Verified);
+
+    // Amounts stay, memos go - on both legs
+    let reference_id = transfer.out_transaction.reference_id.unwrap();
+    let legs = repo.find_transactions_by_reference(&reference_id).await.unwrap();
+    assert_eq!(legs.len(), 2);
+    assert!(legs.iter().all(|leg| leg.memo.is_none() && leg.amount == dec!(100)));
+    assert!(repo.verify_ledger(None, 100).await.unwrap().intact);
+
+    // Audited under the pseudonym, not the user's ID
+    let entries = repo.find_audit_entries(&AuditLogQuery::default(), 100).await.unwrap();
+    let erasure = entries
+        .iter()
+        .find(|e| e.action == AUDIT_USER_ANONYMIZED)
+        .expect("erasure not audited");
+    assert_eq!((erasure.target_type.as_str(), erasure.target_id.as_str()), ("user", pseudonym));
+
+    cleanup_test_data(&pool).await;
+}
+
+#[tokio::test]
+async fn test_erasure_needs_empty_wallets_and_a_known_user() {
+    let pool = setup_test_db().await;
+    cleanup_test_data(&pool).await;
+    let repo = WalletRepository::new(pool.clone());
+
+    let alice = repo.create_wallet("alice").await.unwrap();
+    repo.fund_wallet(&alice.id, dec!(5)).await.unwrap();
+
+    let result = repo.anonymize_user("alice", "user").await;
+    assert!(matches!(result, Err(WalletError::BalanceNotZero(ref id)) if *id == alice.id));
+    assert_eq!(repo.find_by_id(&alice.id).await.unwrap().user_id, "alice");
+
+    let result = repo.anonymize_user("nobody", "user").await;
+    assert!(matches!(result, Err(WalletError::UserNotFound(_))));
+
+    cleanup_test_data(&pool).await;
+}
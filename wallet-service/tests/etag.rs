@@ -0,0 +1,95 @@
+//! Integration tests for ETag / If-Match compare-and-set
+//!
+//! Run with: cargo test --test etag -- --test-threads=1
+
+mod common;
+
+use axum::http::{HeaderValue, StatusCode};
+use axum::response::IntoResponse;
+use common::{cleanup_test_data, setup_test_db};
+use rust_decimal_macros::dec;
+use wallet_service::errors::WalletError;
+use wallet_service::etag::{parse_if_match, wallet_etag};
+use wallet_service::repository::WalletRepository;
+
+#[test]
+fn test_etag_is_the_quoted_version() {
+    assert_eq!(wallet_etag(7), "\"7\"");
+}
+
+#[test]
+fn test_if_match_parsing() {
+    let parse = |value: &'static str| parse_if_match(Some(&HeaderValue::from_static(value)));
+
+    assert_eq!(parse_if_match(None).unwrap(), None);
+    assert_eq!(parse("*").unwrap(), None);
+    assert_eq!(parse("\"7\"").unwrap(), Some(7));
+
+    // Can't be evaluated, so can't be ignored either
+    for bad in ["7", "W/\"7\"", "\"7\", \"8\"", "\"seven\""] {
+        assert!(matches!(parse(bad), Err(WalletError::InvalidRequest(_))), "{} accepted", bad);
+    }
+}
+
+#[test]
+fn test_stale_version_is_a_412() {
+    let response = WalletError::PreconditionFailed { current_version: 3 }.into_response();
+    assert_eq!(response.status(), StatusCode::PRECONDITION_FAILED);
+}
+
+#[tokio::test]
+async fn test_fund_only_applies_at_the_expected_version() {
+    let pool = setup_test_db().await;
+    cleanup_test_data(&pool).await;
+    let repo = WalletRepository::new(pool.clone());
+
+    let wallet = repo.create_wallet("alice").await.unwrap();
+    let (funded, _) = repo
+        .fund_wallet_expecting(&wallet.id, dec!(10), Some(wallet.version))
+        .await
+        .unwrap();
+    assert_eq!(funded.version, wallet.version + 1);
+
+    // A second client still holding the old ETag is refused
+    let result = repo
+        .fund_wallet_expecting(&wallet.id, dec!(10), Some(wallet.version))
+        .await;
+    match result {
+        Err(WalletError::PreconditionFailed { current_version }) => {
+            assert_eq!(current_version, funded.version)
+        }
+        other => panic!("Expected PreconditionFailed, got {:?}", other),
+    }
+    assert_eq!(repo.find_by_id(&wallet.id).await.unwrap().balance, dec!(10));
+
+    cleanup_test_data(&pool).await;
+}
+
+#[tokio::test]
+async fn test_transfer_and_pot_moves_check_the_senders_version() {
+    let pool = setup_test_db().await;
+    cleanup_test_data(&pool).await;
+    let repo = WalletRepository::new(pool.clone());
+
+    let alice = repo.create_wallet("alice").await.unwrap();
+    let bob = repo.create_wallet("bob").await.unwrap();
+    let (alice, _) = repo.fund_wallet(&alice.id, dec!(50)).await.unwrap();
+    let pot = repo.create_pot(&alice.id, "Rainy day").await.unwrap();
+
+    let stale = repo
+        .transfer_expecting(&alice.id, &bob.id, dec!(5), None, Some(alice.version - 1))
+        .await;
+    assert!(matches!(stale, Err(WalletError::PreconditionFailed { .. })));
+    repo.transfer_expecting(&alice.id, &bob.id, dec!(5), None, Some(alice.version))
+        .await
+        .unwrap();
+
+    // The transfer moved alice on, so her old ETag no longer works for pots
+    let stale = repo
+        .move_pot_expecting(&alice.id, &pot.id, dec!(5), true, Some(alice.version))
+        .await;
+    assert!(matches!(stale, Err(WalletError::PreconditionFailed { .. })));
+    assert_eq!(repo.find_by_id(&alice.id).await.unwrap().balance, dec!(45));
+
+    cleanup_test_data(&pool).await;
+}
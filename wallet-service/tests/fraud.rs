@@ -0,0 +1,154 @@
+//! Tests for the fraud rules engine and the review of held transfers
+//!
+//! Run with: cargo test --test fraud -- --test-threads=1
+
+mod common;
+
+use common::{cleanup_test_data, setup_test_db};
+use rust_decimal::Decimal;
+use rust_decimal_macros::dec;
+use std::collections::HashSet;
+use wallet_service::{
+    errors::WalletError,
+    fraud::{
+        AmountRule, BlocklistRule, Counterparty, FraudAction, FraudContext, FraudEngine,
+        NewCounterpartyRule, Operation,
+    },
+    models::{AuditLogQuery, AUDIT_HELD_TRANSFER_RELEASED, AUDIT_HELD_TRANSFER_REJECTED},
+    repository::WalletRepository,
+    step_up::StepUpConfig,
+};
+
+fn transfer(amount: Decimal, is_new: bool) -> FraudContext {
+    FraudContext {
+        operation: Operation::Transfer,
+        wallet_id: "w1".to_string(),
+        user_id: "alice".to_string(),
+        amount,
+        counterparty: Some(Counterparty {
+            wallet_id: "w2".to_string(),
+            user_id: "bob".to_string(),
+            is_new,
+        }),
+    }
+}
+
+#[test]
+fn test_the_strictest_rule_wins() {
+    let engine = FraudEngine::new()
+        .with_rule(AmountRule {
+            flag_over: Some(dec!(100)),
+            hold_over: None,
+            reject_over: Some(dec!(10000)),
+        })
+        .with_rule(NewCounterpartyRule { hold_over: dec!(500) });
+    assert_eq!(engine.rule_names(), vec!["large_amount", "new_counterparty"]);
+
+    assert_eq!(engine.evaluate(&transfer(dec!(50), true)).action, FraudAction::Allow);
+    assert!(engine.evaluate(&transfer(dec!(50), true)).hits.is_empty());
+
+    let flagged = engine.evaluate(&transfer(dec!(600), false));
+    assert_eq!(flagged.action, FraudAction::Flag);
+    assert_eq!(flagged.rules(), "large_amount");
+
+    // Both fire; the hold wins and only its reason is given
+    let held = engine.evaluate(&transfer(dec!(600), true));
+    assert_eq!(held.action, FraudAction::Hold);
+    assert_eq!(held.rules(), "large_amount,new_counterparty");
+    assert_eq!(held.reason(), "first transfer to this wallet and over 500");
+
+    let rejected = engine.evaluate(&transfer(dec!(20000), true));
+    assert_eq!(rejected.action, FraudAction::Reject);
+    assert_eq!(rejected.reason(), "amount over 10000");
+}
+
+#[test]
+fn test_blocklist_covers_both_sides_and_fundings() {
+    let engine = FraudEngine::new().with_rule(BlocklistRule {
+        wallets: HashSet::new(),
+        users: HashSet::from(["bob".to_string()]),
+    });
+
+    // Paying a blocked user
+    assert_eq!(engine.evaluate(&transfer(dec!(1), false)).action, FraudAction::Reject);
+
+    // New-counterparty rules don't apply to fundings
+    let funding = FraudContext {
+        operation: Operation::Fund,
+        counterparty: None,
+        ..transfer(dec!(1), false)
+    };
+    assert_eq!(engine.evaluate(&funding).action, FraudAction::Allow);
+    let new_counterparty = FraudEngine::new().with_rule(NewCounterpartyRule { hold_over: dec!(0) });
+    assert_eq!(new_counterparty.evaluate(&funding).action, FraudAction::Allow);
+}
+
+#[tokio::test]
+async fn test_a_wallet_paid_before_is_not_a_new_counterparty() {
+    let pool = setup_test_db().await;
+    let repo = WalletRepository::new(pool.clone());
+    let alice = repo.create_wallet("alice").await.unwrap();
+    let bob = repo.create_wallet("bob").await.unwrap();
+    repo.fund_wallet(&alice.id, dec!(100)).await.unwrap();
+
+    assert!(!repo.has_transferred_to(&alice.id, &bob.id).await.unwrap());
+    repo.transfer(&alice.id, &bob.id, dec!(10), None).await.unwrap();
+    assert!(repo.has_transferred_to(&alice.id, &bob.id).await.unwrap());
+    // Only that direction
+    assert!(!repo.has_transferred_to(&bob.id, &alice.id).await.unwrap());
+
+    cleanup_test_data(&pool).await;
+}
+
+#[tokio::test]
+async fn test_held_transfers_are_released_or_rejected_once_and_audited() {
+    let pool = setup_test_db().await;
+    let repo = WalletRepository::new(pool.clone());
+    let from = repo.create_wallet("alice").await.unwrap();
+    let to = repo.create_wallet("bob").await.unwrap();
+
+    let released = repo
+        .create_held_transfer(&from.id, &to.id, dec!(900), Some("rent"), "amount over 500")
+        .await
+        .expect("Failed to hold transfer");
+    assert_eq!(released.status, "HELD");
+    assert!(released.expires_at.is_none());
+    let rejected = repo
+        .create_held_transfer(&from.id, &to.id, dec!(50), None, "blocked user")
+        .await
+        .unwrap();
+
+    let queue = repo.find_held_transfers(100).await.unwrap();
+    assert_eq!(queue.len(), 2);
+    assert_eq!(queue[0].id, released.id); // oldest first
+    assert_eq!(queue[0].hold_reason.as_deref(), Some("amount over 500"));
+
+    // A held transfer can't be confirmed with a code - there isn't one
+    let confirm = repo
+        .confirm_pending_transfer(&from.id, &released.id, "123456", &StepUpConfig::default())
+        .await;
+    assert!(matches!(confirm, Err(WalletError::PendingTransferClosed(ref s)) if s == "HELD"));
+
+    let confirmed = repo.release_held_transfer(&released.id, "fraud@example.com").await.unwrap();
+    assert_eq!(confirmed.status, "CONFIRMED");
+    let cancelled = repo.reject_held_transfer(&rejected.id, "fraud@example.com").await.unwrap();
+    assert_eq!(cancelled.status, "CANCELLED");
+    assert!(cancelled.resolved_at.is_some());
+    assert!(repo.find_held_transfers(100).await.unwrap().is_empty());
+
+    let twice = repo.reject_held_transfer(&released.id, "fraud@example.com").await;
+    assert!(matches!(twice, Err(WalletError::PendingTransferClosed(ref s)) if s == "CONFIRMED"));
+    let unknown = repo
+        .release_held_transfer("00000000-0000-0000-0000-000000000000", "fraud@example.com")
+        .await;
+    assert!(matches!(unknown, Err(WalletError::PendingTransferNotFound(_))));
+
+    let entries = repo.find_audit_entries(&AuditLogQuery::default(), 100).await.unwrap();
+    assert_eq!(entries.len(), 2);
+    assert_eq!(entries[0].action, AUDIT_HELD_TRANSFER_REJECTED);
+    assert_eq!(entries[1].action, AUDIT_HELD_TRANSFER_RELEASED);
+    assert_eq!(entries[1].target_type, "pending_transfer");
+    assert_eq!(entries[1].actor, "fraud@example.com");
+
+    cleanup_test_data(&pool).await;
+}
@@ -0,0 +1,61 @@
+//! Tests for the health report (no database needed)
+
+use std::collections::BTreeMap;
+use wallet_service::health::{
+    migration_check, CheckStatus, DependencyCheck, HealthReport, HealthStatus,
+};
+
+fn report(checks: &[(&str, DependencyCheck)]) -> HealthReport {
+    HealthReport::from_checks(
+        checks
+            .iter()
+            .map(|(name, check)| (name.to_string(), check.clone()))
+            .collect::<BTreeMap<_, _>>(),
+    )
+}
+
+#[test]
+fn test_overall_status_follows_the_worst_check() {
+    let up = report(&[
+        ("database", DependencyCheck::up(true)),
+        ("kafka", DependencyCheck::up(false)),
+    ]);
+    assert_eq!(up.status, HealthStatus::Healthy);
+
+    // Kafka down: events wait in the outbox, we keep serving
+    let kafka_down = DependencyCheck::up(false).with_status(CheckStatus::Down, "timed out");
+    let degraded = report(&[
+        ("database", DependencyCheck::up(true)),
+        ("kafka", kafka_down.clone()),
+    ]);
+    assert_eq!(degraded.status, HealthStatus::Degraded);
+
+    let db_down = DependencyCheck::up(true).with_status(CheckStatus::Down, "connection refused");
+    let unhealthy = report(&[("database", db_down), ("kafka", kafka_down)]);
+    assert_eq!(unhealthy.status, HealthStatus::Unhealthy);
+
+    let json = serde_json::to_value(&unhealthy).unwrap();
+    assert_eq!(json["status"], "unhealthy");
+    assert_eq!(json["checks"]["database"]["status"], "down");
+    assert_eq!(json["checks"]["database"]["detail"], "connection refused");
+}
+
+#[test]
+fn test_migrations_must_all_be_applied_and_clean() {
+    let expected = [1, 2, 3];
+
+    let current = migration_check(&expected, &[(1, true), (2, true), (3, true)]);
+    assert_eq!(current.status, CheckStatus::Up);
+    assert_eq!(current.detail.as_deref(), Some("at 3"));
+
+    let behind = migration_check(&expected, &[(1, true)]);
+    assert_eq!(behind.status, CheckStatus::Down);
+    assert_eq!(behind.detail.as_deref(), Some("2 pending: 2, 3"));
+
+    let dirty = migration_check(&expected, &[(1, true), (2, true), (3, false)]);
+    assert_eq!(dirty.status, CheckStatus::Down);
+
+    // A newer instance already ran 4 - fine during a rolling deploy
+    let ahead = migration_check(&expected, &[(1, true), (2, true), (3, true), (4, true)]);
+    assert_eq!(ahead.status, CheckStatus::Up);
+}
@@ -0,0 +1,91 @@
+//! Tests for the admin IP allowlist (no database needed)
+
+use axum::body::Body;
+use axum::extract::ConnectInfo;
+use axum::http::{Request, StatusCode};
+use axum::{middleware, routing::get, Router};
+use std::net::{IpAddr, SocketAddr};
+use std::sync::Arc;
+use tower::ServiceExt;
+use wallet_service::ip_allowlist::{restrict_admin, AdminAllowlist, IpNetwork};
+
+fn allowlist(networks: &[&str], trust_forwarded_for: bool) -> AdminAllowlist {
+    AdminAllowlist::new(
+        networks.iter().map(|n| n.parse().unwrap()).collect(),
+        trust_forwarded_for,
+    )
+}
+
+fn ip(ip: &str) -> IpAddr {
+    ip.parse().unwrap()
+}
+
+#[test]
+fn test_networks_match_by_prefix() {
+    let allowlist = allowlist(&["10.0.0.0/8", "192.168.1.7", "fd00::/8"], false);
+
+    assert!(allowlist.allows(ip("10.20.30.40")));
+    assert!(!allowlist.allows(ip("11.0.0.1")));
+    assert!(allowlist.allows(ip("192.168.1.7")));
+    assert!(!allowlist.allows(ip("192.168.1.8")));
+    assert!(allowlist.allows(ip("fd12::1")));
+    assert!(!allowlist.allows(ip("fe80::1")));
+    // IPv4 as seen on a dual-stack listener
+    assert!(allowlist.allows(ip("::ffff:10.1.2.3")));
+
+    assert!("0.0.0.0/0".parse::<IpNetwork>().unwrap().contains(ip("8.8.8.8")));
+    assert_eq!("192.168.1.7".parse::<IpNetwork>().unwrap().to_string(), "192.168.1.7/32");
+    assert!("10.0.0.0/33".parse::<IpNetwork>().is_err());
+    assert!("not-an-ip/8".parse::<IpNetwork>().is_err());
+}
+
+#[tokio::test]
+async fn test_only_admin_routes_are_restricted() {
+    let app = |allowlist: AdminAllowlist| {
+        Router::new()
+            .route("/admin/audit-log", get(|| async { "ok" }))
+            .route("/wallets/w-1", get(|| async { "ok" }))
+            .layer(middleware::from_fn_with_state(Arc::new(allowlist), restrict_admin))
+    };
+    let request = |path: &str, peer: &str, forwarded_for: Option<&str>| {
+        let mut request = Request::get(path);
+        if let Some(forwarded_for) = forwarded_for {
+            request = request.header("x-forwarded-for", forwarded_for);
+        }
+        let mut request = request.body(Body::empty()).unwrap();
+        let peer: SocketAddr = peer.parse().unwrap();
+        request.extensions_mut().insert(ConnectInfo(peer));
+        request
+    };
+    let status = |app: Router, request: Request<Body>| async move { app.oneshot(request).await.unwrap().status() };
+
+    let direct = app(allowlist(&["10.0.0.0/8"], false));
+    assert_eq!(
+        status(direct.clone(), request("/admin/audit-log", "10.0.0.5:5000", None)).await,
+        StatusCode::OK
+    );
+    assert_eq!(
+        status(direct.clone(), request("/admin/audit-log", "203.0.113.9:5000", None)).await,
+        StatusCode::FORBIDDEN
+    );
+    assert_eq!(
+        status(direct.clone(), request("/wallets/w-1", "203.0.113.9:5000", None)).await,
+        StatusCode::OK
+    );
+    // X-Forwarded-For is ignored unless trusted
+    assert_eq!(
+        status(direct, request("/admin/audit-log", "203.0.113.9:5000", Some("10.0.0.5"))).await,
+        StatusCode::FORBIDDEN
+    );
+
+    // Behind a proxy: the last X-Forwarded-For entry is the client
+    let proxied = app(allowlist(&["10.0.0.0/8"], true));
+    assert_eq!(
+        status(proxied.clone(), request("/admin/audit-log", "172.16.0.2:5000", Some("1.2.3.4, 10.0.0.5"))).await,
+        StatusCode::OK
+    );
+    assert_eq!(
+        status(proxied, request("/admin/audit-log", "172.16.0.2:5000", Some("10.0.0.5, 1.2.3.4"))).await,
+        StatusCode::FORBIDDEN
+    );
+}
@@ -0,0 +1,95 @@
+//! Tests for producer behaviour while Kafka is unreachable
+//!
+//! Nothing listens on the broker address, so the first send times out
+//! (5s) and later sends fail fast.
+//!
+//! Run with: cargo test --test kafka_outage -- --test-threads=1
+
+mod common;
+
+use chrono::Utc;
+use common::{cleanup_test_data, setup_test_db};
+use wallet_service::errors::WalletError;
+use wallet_service::kafka::{KafkaProducer, WalletEvent};
+
+#[tokio::test]
+async fn test_unreachable_broker_fails_fast_after_first_timeout() {
+    // Creating the client doesn't need a broker
+    let producer = KafkaProducer::new("127.0.0.1:1", "wallet-events".to_string()).unwrap();
+    assert!(producer.is_available());
+
+    let first = producer.send("wallet-1", "{}").await.unwrap_err();
+    assert!(matches!(first, WalletError::KafkaError(_)));
+
+    let status = producer.broker_status();
+    assert!(!status.available);
+    assert_eq!(status.consecutive_failures, 1);
+
+    // In backoff: no 5s wait, a clear error instead
+    let started = std::time::Instant::now();
+    let second = producer.send("wallet-1", "{}").await.unwrap_err();
+    assert!(matches!(second, WalletError::EventBusUnavailable { .. }));
+    assert!(started.elapsed().as_millis() < 100);
+}
+
+#[tokio::test]
+async fn test_events_spill_to_database_during_outage() {
+    let pool = setup_test_db().await;
+    let producer = KafkaProducer::new("127.0.0.1:1", "wallet-events".to_string())
+        .unwrap()
+        .with_spill(pool.clone());
+
+    let event = |wallet_id: &str| WalletEvent::WalletCreated {
+        wallet_id: wallet_id.to_string(),
+        user_id: "user-1".to_string(),
+        timestamp: Utc::now(),
+    };
+
+    // The failed send is spilled, so the caller sees success
+    producer.publish(event("wallet-1")).await.unwrap();
+    // Later events queue behind it without touching Kafka
+    let started = std::time::Instant::now();
+    producer.publish(event("wallet-2")).await.unwrap();
+    assert!(started.elapsed().as_millis() < 1000);
+
+    let keys = sqlx::query_scalar::<_, String>(
+        "SELECT aggregate_id FROM event_outbox WHERE published_at IS NULL ORDER BY id",
+    )
+    .fetch_all(&pool)
+    .await
+    .unwrap();
+    assert_eq!(keys, vec!["wallet-1", "wallet-2"]);
+
+    cleanup_test_data(&pool).await;
+}
+
+#[tokio::test]
+async fn test_spilled_events_keep_their_wallet_sequence() {
+    let pool = setup_test_db().await;
+    cleanup_test_data(&pool).await;
+    let producer = KafkaProducer::new("127.0.0.1:1", "wallet-events".to_string())
+        .unwrap()
+        .with_spill(pool.clone())
+        .with_sequences(pool.clone());
+
+    let event = |wallet_id: &str| WalletEvent::WalletCreated {
+        wallet_id: wallet_id.to_string(),
+        user_id: "user-1".to_string(),
+        timestamp: Utc::now(),
+    };
+
+    producer.publish(event("wallet-1")).await.unwrap();
+    producer.publish(event("wallet-1")).await.unwrap();
+    producer.publish(event("wallet-2")).await.unwrap();
+
+    // Numbered per wallet, and the number is in the stored payload
+    let sequences = sqlx::query_scalar::<_, i64>(
+        "SELECT (payload::jsonb->>'sequence')::BIGINT FROM event_outbox ORDER BY id",
+    )
+    .fetch_all(&pool)
+    .await
+    .unwrap();
+    assert_eq!(sequences, vec![1, 2, 1]);
+
+    cleanup_test_data(&pool).await;
+}
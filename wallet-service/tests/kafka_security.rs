@@ -0,0 +1,59 @@
+//! Tests for Kafka security settings (no Kafka needed)
+
+use rdkafka::config::ClientConfig;
+use wallet_service::kafka_security::KafkaSecurity;
+use wallet_service::tls::ClientTls;
+
+#[test]
+fn test_nothing_configured_means_plaintext() {
+    let mut config = ClientConfig::new();
+    KafkaSecurity::default().apply(&mut config);
+
+    assert_eq!(config.get("security.protocol"), None);
+    assert_eq!(config.get("sasl.mechanism"), None);
+}
+
+#[test]
+fn test_sasl_ssl_settings_are_passed_through() {
+    let security = KafkaSecurity {
+        protocol: Some("SASL_SSL".to_string()),
+        sasl_username: Some("api-key".to_string()),
+        sasl_password: Some("api-secret".to_string()),
+        ssl_ca_location: Some("/etc/ssl/kafka-ca.pem".to_string()),
+        ..KafkaSecurity::default()
+    };
+
+    let mut config = ClientConfig::new();
+    security.apply(&mut config);
+
+    assert_eq!(config.get("security.protocol"), Some("SASL_SSL"));
+    assert_eq!(config.get("sasl.username"), Some("api-key"));
+    assert_eq!(config.get("sasl.password"), Some("api-secret"));
+    assert_eq!(config.get("ssl.ca.location"), Some("/etc/ssl/kafka-ca.pem"));
+    // Credentials without a mechanism mean PLAIN
+    assert_eq!(config.get("sasl.mechanism"), Some("PLAIN"));
+}
+
+#[test]
+fn test_ssl_falls_back_to_the_service_client_certificate() {
+    let tls = ClientTls {
+        cert_path: Some("/etc/wallet/tls/client.crt".to_string()),
+        key_path: Some("/etc/wallet/tls/client.key".to_string()),
+        ca_path: Some("/etc/wallet/tls/ca.pem".to_string()),
+    };
+
+    let ssl = KafkaSecurity {
+        protocol: Some("SSL".to_string()),
+        ssl_ca_location: Some("/etc/ssl/kafka-ca.pem".to_string()),
+        ..KafkaSecurity::default()
+    }
+    .with_client_tls(&tls);
+    assert_eq!(ssl.ssl_certificate_location.as_deref(), Some("/etc/wallet/tls/client.crt"));
+    assert_eq!(ssl.ssl_key_location.as_deref(), Some("/etc/wallet/tls/client.key"));
+    // An explicit KAFKA_SSL_* setting wins
+    assert_eq!(ssl.ssl_ca_location.as_deref(), Some("/etc/ssl/kafka-ca.pem"));
+
+    // Plaintext never picks up certificates
+    let plaintext = KafkaSecurity::default().with_client_tls(&tls);
+    assert_eq!(plaintext.ssl_certificate_location, None);
+}
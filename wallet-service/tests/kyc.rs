@@ -0,0 +1,92 @@
+//! Integration tests for KYC levels and the caps on unverified users
+//!
+//! Run with: cargo test --test kyc -- --test-threads=1
+
+mod common;
+
+use common::{cleanup_test_data, setup_test_db};
+use rust_decimal_macros::dec;
+use wallet_service::errors::WalletError;
+use wallet_service::kyc::KycLimits;
+use wallet_service::models::{AuditLogQuery, KycLevel, AUDIT_KYC_LEVEL_CHANGED};
+use wallet_service::repository::WalletRepository;
+
+fn limits() -> KycLimits {
+    KycLimits {
+        unverified_max_balance: Some(dec!(1000)),
+        unverified_max_transfer: Some(dec!(200)),
+    }
+}
+
+#[test]
+fn test_only_unverified_users_are_capped() {
+    let limits = limits();
+    assert!(KycLimits::default().is_off());
+
+    assert!(limits.check_balance(KycLevel::Unverified, dec!(1000)).is_ok());
+    assert!(matches!(
+        limits.check_balance(KycLevel::Unverified, dec!(1000.01)),
+        Err(WalletError::KycLimitExceeded(_))
+    ));
+    assert!(limits.check_balance(KycLevel::Verified, dec!(1000000)).is_ok());
+
+    assert!(matches!(
+        limits.check_transfer(KycLevel::Unverified, dec!(200.01)),
+        Err(WalletError::KycLimitExceeded(_))
+    ));
+    assert!(limits.check_transfer(KycLevel::Verified, dec!(5000)).is_ok());
+}
+
+#[tokio::test]
+async fn test_unverified_users_are_capped_until_verified() {
+    let pool = setup_test_db().await;
+    let repo = WalletRepository::new(pool.clone()).with_kyc_limits(limits());
+    let alice = repo.create_wallet("alice").await.unwrap();
+    let savings = repo.create_wallet("alice").await.unwrap();
+    let bob = repo.create_wallet("bob").await.unwrap();
+
+    // The balance cap counts all of a user's wallets
+    repo.fund_wallet(&alice.id, dec!(800)).await.unwrap();
+    let over = repo.fund_wallet(&savings.id, dec!(300)).await;
+    assert!(matches!(over, Err(WalletError::KycLimitExceeded(_))));
+
+    // Sending is capped per transfer - but not between one's own wallets
+    let too_big = repo.transfer(&alice.id, &bob.id, dec!(250), None).await;
+    assert!(matches!(too_big, Err(WalletError::KycLimitExceeded(_))));
+    repo.transfer(&alice.id, &savings.id, dec!(250), None).await.unwrap();
+
+    // Receiving counts against the recipient's cap
+    repo.set_user_kyc("alice", KycLevel::Verified, "kyc@example.com").await.unwrap();
+    repo.fund_wallet(&alice.id, dec!(5000)).await.unwrap();
+    repo.transfer(&alice.id, &bob.id, dec!(900), None).await.unwrap();
+    let bob_over = repo.transfer(&alice.id, &bob.id, dec!(200), None).await;
+    assert!(matches!(bob_over, Err(WalletError::KycLimitExceeded(_))));
+    assert_eq!(repo.find_by_id(&bob.id).await.unwrap().balance, dec!(900));
+
+    cleanup_test_data(&pool).await;
+}
+
+#[tokio::test]
+async fn test_kyc_changes_are_audited() {
+    let pool = setup_test_db().await;
+    let repo = WalletRepository::new(pool.clone());
+
+    let unset = repo.find_user_kyc("carol").await.unwrap();
+    assert_eq!(unset.level, KycLevel::Unverified);
+    assert!(unset.updated_at.is_none());
+
+    let verified = repo.set_user_kyc("carol", KycLevel::Verified, "kyc@example.com").await.unwrap();
+    assert_eq!(verified.level, KycLevel::Verified);
+    assert_eq!(verified.updated_by.as_deref(), Some("kyc@example.com"));
+    assert_eq!(repo.find_user_kyc("carol").await.unwrap().level, KycLevel::Verified);
+
+    let entries = repo.find_audit_entries(&AuditLogQuery::default(), 100).await.unwrap();
+    assert_eq!(entries.len(), 1);
+    assert_eq!(entries[0].action, AUDIT_KYC_LEVEL_CHANGED);
+    assert_eq!(entries[0].target_type, "user");
+    assert_eq!(entries[0].target_id, "carol");
+    assert_eq!(entries[0].before_value.as_ref().unwrap()["level"], "unverified");
+    assert_eq!(entries[0].after_value.as_ref().unwrap()["level"], "verified");
+
+    cleanup_test_data(&pool).await;
+}
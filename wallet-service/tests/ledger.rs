@@ -0,0 +1,138 @@
+//! Integration tests for the hash-chained transaction ledger
+//!
+//! Run with: cargo test --test ledger -- --test-threads=1
+
+mod common;
+
+use chrono::{TimeZone, Utc};
+use common::{cleanup_test_data, setup_test_db};
+use rust_decimal_macros::dec;
+use wallet_service::ledger::{entry_hash, GENESIS_HASH};
+use wallet_service::repository::WalletRepository;
+
+#[test]
+fn test_entry_hash_format_is_fixed() {
+    // The migration's SQL backfill hashes the same string - this pins it
+    let created_at = Utc.timestamp_micros(1_738_404_000_123_456).unwrap();
+    let hash = entry_hash(
+        GENESIS_HASH,
+        "tx-1",
+        "wallet-1",
+        dec!(10.5000),
+        "FUND",
+        "COMPLETED",
+        None,
+        created_at,
+    );
+    assert_eq!(hash, "6b39289d8e832920e651319ba124078969d2c6fce579a8f3ad9acafff0422837");
+
+    assert_ne!(
+        entry_hash(&hash, "tx-1", "wallet-1", dec!(10.5), "FUND", "COMPLETED", None, created_at),
+        hash
+    );
+}
+
+#[tokio::test]
+async fn test_every_transaction_is_chained_per_wallet() {
+    let pool = setup_test_db().await;
+    cleanup_test_data(&pool).await;
+    let repo = WalletRepository::new(pool.clone());
+
+    let alice = repo.create_wallet("alice").await.unwrap();
+    let bob = repo.create_wallet("bob").await.unwrap();
+    repo.fund_wallet(&alice.id, dec!(100)).await.unwrap();
+    repo.transfer(&alice.id, &bob.id, dec!(30), Some("rent")).await.unwrap();
+    repo.transfer(&alice.id, &bob.id, dec!(5), None).await.unwrap();
+
+    let chain: Vec<(String, String)> = sqlx::query_as(
+        "SELECT prev_hash, hash FROM transaction_ledger WHERE wallet_id = $1 ORDER BY seq",
+    )
+    .bind(&alice.id)
+    .fetch_all(&pool)
+    .await
+    .unwrap();
+    assert_eq!(chain.len(), 3);
+    assert_eq!(chain[0].0, GENESIS_HASH);
+    assert_eq!(chain[1].0, chain[0].1);
+    assert_eq!(chain[2].0, chain[1].1);
+
+    let report = repo.verify_ledger(None, 2).await.unwrap();
+    assert!(report.intact);
+    assert_eq!((report.entries_checked, report.wallets_checked), (5, 2));
+
+    let report = repo.verify_ledger(Some(&bob.id), 2).await.unwrap();
+    assert!(report.intact);
+    assert_eq!((report.entries_checked, report.wallets_checked), (2, 1));
+
+    // Append-only
+    let update = sqlx::query("UPDATE transaction_ledger SET amount = 1")
+        .execute(&pool)
+        .await;
+    assert!(update.is_err());
+    let delete = sqlx::query("DELETE FROM transaction_ledger").execute(&pool).await;
+    assert!(delete.is_err());
+
+    cleanup_test_data(&pool).await;
+}
+
+#[tokio::test]
+async fn test_verification_finds_tampering() {
+    let pool = setup_test_db().await;
+    cleanup_test_data(&pool).await;
+    let repo = WalletRepository::new(pool.clone());
+
+    let alice = repo.create_wallet("alice").await.unwrap();
+    let bob = repo.create_wallet("bob").await.unwrap();
+    repo.fund_wallet(&alice.id, dec!(100)).await.unwrap();
+    let first = repo.transfer(&alice.id, &bob.id, dec!(30), None).await.unwrap();
+    repo.transfer(&alice.id, &bob.id, dec!(5), None).await.unwrap();
+
+    // Someone edits a transaction...
+    sqlx::query("UPDATE wallet_transactions SET amount = 3 WHERE id = $1")
+        .bind(&first.in_transaction.id)
+        .execute(&pool)
+        .await
+        .unwrap();
+    let report = repo.verify_ledger(None, 100).await.unwrap();
+    assert!(!report.intact);
+    assert_eq!(report.break_count, 1);
+    assert_eq!(report.breaks[0].transaction_id, first.in_transaction.id);
+    assert_eq!(report.breaks[0].reason, "transaction differs from the entry");
+
+    // ...and, going round the trigger, its ledger entry too
+    sqlx::query("ALTER TABLE transaction_ledger DISABLE TRIGGER transaction_ledger_append_only")
+        .execute(&pool)
+        .await
+        .unwrap();
+    sqlx::query("UPDATE transaction_ledger SET amount = 3 WHERE transaction_id = $1")
+        .bind(&first.in_transaction.id)
+        .execute(&pool)
+        .await
+        .unwrap();
+    sqlx::query("DELETE FROM transaction_ledger WHERE transaction_id = $1")
+        .bind(&first.out_transaction.id)
+        .execute(&pool)
+        .await
+        .unwrap();
+    sqlx::query("ALTER TABLE transaction_ledger ENABLE TRIGGER transaction_ledger_append_only")
+        .execute(&pool)
+        .await
+        .unwrap();
+
+    let report = repo.verify_ledger(None, 100).await.unwrap();
+    assert_eq!(report.break_count, 3);
+    let reasons: Vec<(&str, &str)> = report
+        .breaks
+        .iter()
+        .map(|b| (b.transaction_id.as_str(), b.reason.as_str()))
+        .collect();
+    // The edited entry no longer hashes to what's stored
+    assert!(reasons.contains(&(first.in_transaction.id.as_str(), "hash doesn't match the entry")));
+    // The entry after the deleted one points at a hash that's gone
+    assert!(reasons
+        .iter()
+        .any(|(_, reason)| *reason == "prev_hash doesn't match the previous entry's hash"));
+    assert!(reasons.contains(&(first.out_transaction.id.as_str(), "transaction has no ledger entry")));
+
+    cleanup_test_data(&pool).await;
+}
@@ -0,0 +1,48 @@
+//! Tests for the load generator's traffic mix and report (no server needed)
+
+use std::collections::BTreeMap;
+use std::time::Duration;
+use wallet_service::loadgen::{render_report, Mix, Operation, OperationStats};
+
+#[test]
+fn test_mix_weights() {
+    let mix = Mix::parse("fund=1, transfer=3").unwrap();
+    assert_eq!(mix, Mix { fund: 1, transfer: 3 });
+    assert_eq!(mix.pick(0), Operation::Fund);
+    assert_eq!(mix.pick(1), Operation::Transfer);
+    assert_eq!(mix.pick(3), Operation::Transfer);
+
+    // Left out = no traffic
+    let transfers_only = Mix::parse("transfer=5").unwrap();
+    assert_eq!(transfers_only.pick(0), Operation::Transfer);
+
+    assert!(Mix::parse("fund=0").is_err());
+    assert!(Mix::parse("fund=10,withdraw=5").is_err());
+    assert!(Mix::parse("fund").is_err());
+    assert!(Mix::parse("fund=-1").is_err());
+}
+
+#[test]
+fn test_percentiles_and_failures() {
+    let mut stats = OperationStats::default();
+    for ms in 1..=100 {
+        stats.record(Duration::from_millis(ms), if ms <= 97 { "200" } else { "429" });
+    }
+    let mut other = OperationStats::default();
+    other.record(Duration::from_millis(500), "error");
+    stats.merge(other);
+
+    assert_eq!(stats.requests(), 101);
+    assert_eq!(stats.failures(), 4);
+    assert_eq!(stats.percentile(0.5), Duration::from_millis(51));
+    assert_eq!(stats.percentile(1.0), Duration::from_millis(500));
+    assert_eq!(OperationStats::default().percentile(0.99), Duration::ZERO);
+
+    let report = render_report(
+        &BTreeMap::from([(Operation::Transfer, stats)]),
+        Duration::from_secs(10),
+    );
+    assert!(report.starts_with("transfer"));
+    assert!(report.contains("failed 4 (3.96%)"));
+    assert!(report.contains("[200: 97, 429: 3, error: 1]"));
+}
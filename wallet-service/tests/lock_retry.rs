@@ -0,0 +1,126 @@
+//! Tests for retrying optimistic lock conflicts in the repository
+//!
+//! Run with: cargo test --test lock_retry -- --test-threads=1
+
+mod common;
+
+use common::{cleanup_test_data, setup_test_db};
+use rust_decimal_macros::dec;
+use serde_json::json;
+use std::sync::Arc;
+use std::time::Duration;
+use wallet_service::errors::WalletError;
+use wallet_service::lock_retry::LockRetry;
+use wallet_service::models::UpdateWalletRequest;
+use wallet_service::repository::WalletRepository;
+use wallet_service::validation::from_json;
+
+#[test]
+fn test_delays_double_up_to_the_cap_with_jitter() {
+    let retry = LockRetry {
+        max_attempts: 10,
+        base_delay: Duration::from_millis(10),
+        max_delay: Duration::from_millis(50),
+    };
+    assert_eq!(retry.ceiling(1), Duration::from_millis(10));
+    assert_eq!(retry.ceiling(2), Duration::from_millis(20));
+    assert_eq!(retry.ceiling(3), Duration::from_millis(40));
+    assert_eq!(retry.ceiling(4), Duration::from_millis(50));
+    assert_eq!(retry.ceiling(u32::MAX), Duration::from_millis(50));
+
+    for retry_number in 1..10 {
+        assert!(retry.delay(retry_number) <= retry.ceiling(retry_number));
+    }
+}
+
+fn rename(nickname: String) -> UpdateWalletRequest {
+    from_json(json!({ "nickname": nickname })).unwrap()
+}
+
+#[tokio::test]
+async fn test_concurrent_patches_are_retried_not_refused() {
+    let pool = setup_test_db().await;
+    cleanup_test_data(&pool).await;
+    let repo = Arc::new(WalletRepository::new(pool.clone()).with_lock_retry(LockRetry {
+        max_attempts: 50,
+        ..LockRetry::default()
+    }));
+    let wallet = repo.create_wallet("alice").await.unwrap();
+
+    let handles: Vec<_> = (0..10)
+        .map(|i| {
+            let repo = Arc::clone(&repo);
+            let wallet_id = wallet.id.clone();
+            tokio::spawn(async move {
+                repo.update_wallet_metadata(&wallet_id, &rename(format!("Wallet {}", i)), None)
+                    .await
+            })
+        })
+        .collect();
+    for result in futures::future::join_all(handles).await {
+        assert!(result.unwrap().is_ok());
+    }
+    let wallet = repo.find_by_id(&wallet.id).await.unwrap();
+    assert_eq!(wallet.version, 10);
+
+    // A stale If-Match is still refused, retries or not
+    let result = repo
+        .update_wallet_metadata(&wallet.id, &rename("Late".to_string()), Some(3))
+        .await;
+    assert!(matches!(
+        result,
+        Err(WalletError::PreconditionFailed { current_version: 10 })
+    ));
+
+    cleanup_test_data(&pool).await;
+}
+
+#[tokio::test]
+async fn test_transfers_retry_when_the_recipient_is_unsharded() {
+    let pool = setup_test_db().await;
+    cleanup_test_data(&pool).await;
+    let repo = WalletRepository::new(pool.clone()).with_lock_retry(LockRetry {
+        max_attempts: 3,
+        ..LockRetry::default()
+    });
+    let merchant = repo.create_wallet("merchant").await.unwrap();
+    repo.set_balance_shards(&merchant.id, 4, "ops@example.com").await.unwrap();
+    let customer = repo.create_wallet("customer").await.unwrap();
+    repo.fund_wallet(&customer.id, dec!(100)).await.unwrap();
+
+    // Unsharding takes the wallet's row lock...
+    let mut unsharding = pool.begin().await.unwrap();
+    sqlx::query("SELECT id FROM wallets WHERE id = $1 FOR UPDATE")
+        .bind(&merchant.id)
+        .execute(&mut *unsharding)
+        .await
+        .unwrap();
+
+    // ...while a transfer has already seen the shards and waits for it
+    let transfer = {
+        let repo = repo.clone();
+        let (from, to) = (customer.id.clone(), merchant.id.clone());
+        tokio::spawn(async move { repo.transfer(&from, &to, dec!(5), None).await })
+    };
+    tokio::time::sleep(Duration::from_millis(200)).await;
+
+    sqlx::query("DELETE FROM wallet_balance_shards WHERE wallet_id = $1")
+        .bind(&merchant.id)
+        .execute(&mut *unsharding)
+        .await
+        .unwrap();
+    sqlx::query("UPDATE wallets SET balance_shards = 0 WHERE id = $1")
+        .bind(&merchant.id)
+        .execute(&mut *unsharding)
+        .await
+        .unwrap();
+    unsharding.commit().await.unwrap();
+
+    // The conflict is retried, and the credit lands on the wallet itself
+    transfer.await.unwrap().unwrap();
+    let shards = repo.find_balance_shards(&merchant.id).await.unwrap();
+    assert_eq!(shards.shards, 0);
+    assert_eq!((shards.row_balance, shards.balance), (dec!(5), dec!(5)));
+
+    cleanup_test_data(&pool).await;
+}
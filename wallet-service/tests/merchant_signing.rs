@@ -0,0 +1,219 @@
+//! Tests for merchants' signed requests
+//!
+//! Run with: cargo test --test merchant_signing -- --test-threads=1
+
+mod common;
+
+use axum::body::Body;
+use axum::http::{Method, Request, StatusCode};
+use axum::{middleware, routing::post, Extension, Router};
+use common::{cleanup_test_data, setup_test_db};
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+use tower::ServiceExt;
+use wallet_service::archive::ArchiveConfig;
+use wallet_service::auth::{
+    enforce_ownership, require_auth, AuthConfig, Authenticator, ClaimMapping, Principal,
+};
+use wallet_service::degradation::{DegradationConfig, DegradationController};
+use wallet_service::errors::WalletError;
+use wallet_service::fraud::FraudEngine;
+use wallet_service::handlers::AppState;
+use wallet_service::kafka::KafkaProducer;
+use wallet_service::merchant_signing::{self, is_signed_route, MerchantSigning};
+use wallet_service::metrics::BusinessMetrics;
+use wallet_service::pin::PinPolicy;
+use wallet_service::repository::WalletRepository;
+use wallet_service::step_up::{CodeNotifier, StepUpConfig};
+use wallet_service::validation::AmountRules;
+
+const SECRET: &[u8] = b"acme-secret-acme-secret-acme-secret!";
+const PATH: &str = "/wallets/w-1/transfer";
+const BODY: &[u8] = br#"{"to_wallet_id":"w-2","amount":"25.00"}"#;
+
+fn merchants() -> MerchantSigning {
+    MerchantSigning::new(
+        HashMap::from([("acme".to_string(), SECRET.to_vec())]),
+        Duration::from_secs(300),
+    )
+    .unwrap()
+}
+
+fn is_unauthorized<T>(result: Result<T, WalletError>, reason: &str) -> bool {
+    matches!(result, Err(WalletError::Unauthorized(ref r)) if r == reason)
+}
+
+#[test]
+fn test_signatures_cover_the_whole_request() {
+    let merchants = merchants();
+    let now = 1_738_404_000;
+    let signature = merchant_signing::sign(SECRET, now, &Method::POST, PATH, BODY);
+    assert!(signature.starts_with(&format!("t={},v1=", now)));
+
+    let digest = merchants
+        .verify("acme", &signature, &Method::POST, PATH, BODY, now + 10)
+        .expect("Right signature should pass");
+    assert_eq!(digest.len(), 64);
+
+    // Changing any part of the request breaks it...
+    let other_body = br#"{"to_wallet_id":"w-2","amount":"2500.00"}"#;
+    for (method, path, body) in [
+        (Method::POST, PATH, &other_body[..]),
+        (Method::POST, "/wallets/w-1/fund", BODY),
+        (Method::PUT, PATH, BODY),
+    ] {
+        let result = merchants.verify("acme", &signature, &method, path, body, now);
+        assert!(is_unauthorized(result, "invalid signature"));
+    }
+    // ...as does another merchant's name or secret
+    let result = merchants.verify("shop", &signature, &Method::POST, PATH, BODY, now);
+    assert!(is_unauthorized(result, "invalid signature"));
+    let forged = merchant_signing::sign(b"not-the-secret", now, &Method::POST, PATH, BODY);
+    let result = merchants.verify("acme", &forged, &Method::POST, PATH, BODY, now);
+    assert!(is_unauthorized(result, "invalid signature"));
+
+    // Outside the window either way
+    for at in [now + 301, now - 301] {
+        let result = merchants.verify("acme", &signature, &Method::POST, PATH, BODY, at);
+        assert!(is_unauthorized(result, "signature timestamp outside the replay window"));
+    }
+
+    let result = merchants.verify("acme", "v1=abc", &Method::POST, PATH, BODY, now);
+    assert!(is_unauthorized(result, "malformed signature"));
+}
+
+#[test]
+fn test_only_fund_and_transfer_take_signed_requests() {
+    assert!(is_signed_route(&Method::POST, "/wallets/w-1/fund"));
+    assert!(is_signed_route(&Method::POST, "/wallets/w-1/transfer"));
+    assert!(!is_signed_route(&Method::GET, "/wallets/w-1/transfer"));
+    assert!(!is_signed_route(&Method::POST, "/wallets/w-1/pending-transfers/p-1/confirm"));
+    assert!(!is_signed_route(&Method::POST, "/admin/wallets/w-1/adjust"));
+    assert!(!is_signed_route(&Method::POST, "/wallets//fund"));
+
+    let short = MerchantSigning::new(
+        HashMap::from([("acme".to_string(), b"short".to_vec())]),
+        Duration::from_secs(300),
+    );
+    assert!(short.is_err());
+}
+
+fn authenticator(repository: WalletRepository) -> Arc<Authenticator> {
+    let config = AuthConfig {
+        issuer: "https://id.example.com/".to_string(),
+        audience: None,
+        jwks_url: None,
+        leeway_secs: 0,
+        claims: ClaimMapping::default(),
+    };
+    Arc::new(
+        Authenticator::with_keys(config, HashMap::new())
+            .with_api_keys(repository)
+            .with_merchants(merchants()),
+    )
+}
+
+fn app(repository: WalletRepository) -> Router {
+    let handler = |Extension(principal): Extension<Principal>, body: String| async move {
+        format!("{} {}", principal.subject, body)
+    };
+    Router::new()
+        .route("/wallets/:wallet_id/transfer", post(handler))
+        .route("/wallets/:wallet_id/pots", post(handler))
+        .layer(middleware::from_fn_with_state(authenticator(repository), require_auth))
+}
+
+/// Signed requests through the ownership check too, as in main
+fn ownership_app(repository: WalletRepository) -> Router {
+    let state = AppState {
+        repository: repository.clone(),
+        kafka_producer: Arc::new(KafkaProducer::new("localhost:9092", "wallet-events".to_string()).unwrap()),
+        metrics: Arc::new(BusinessMetrics::new("USD")),
+        degradation: Arc::new(DegradationController::new(DegradationConfig::from_env())),
+        currency: "USD".to_string(),
+        amount_rules: AmountRules::default(),
+        archive: ArchiveConfig::default(),
+        pin_policy: PinPolicy::from_env(),
+        step_up: StepUpConfig::default(),
+        code_notifier: CodeNotifier::new(None).unwrap(),
+        fraud: Arc::new(FraudEngine::new()),
+    };
+    let handler = |Extension(principal): Extension<Principal>| async move { principal.subject };
+    Router::new()
+        .route("/wallets/:wallet_id/transfer", post(handler))
+        .route_layer(middleware::from_fn_with_state(state, enforce_ownership))
+        .route_layer(middleware::from_fn_with_state(authenticator(repository), require_auth))
+}
+
+fn signed(path: &str, signature: &str) -> Request<Body> {
+    Request::post(path)
+        .header("content-type", "application/json")
+        .header("X-Merchant-Id", "acme")
+        .header("X-Merchant-Signature", signature)
+        .body(Body::from(BODY))
+        .unwrap()
+}
+
+#[tokio::test]
+async fn test_signed_requests_are_accepted_once() {
+    let pool = setup_test_db().await;
+    cleanup_test_data(&pool).await;
+    let app = app(WalletRepository::new(pool.clone()));
+
+    let now = chrono::Utc::now().timestamp();
+    let signature = merchant_signing::sign(SECRET, now, &Method::POST, PATH, BODY);
+
+    let response = app.clone().oneshot(signed(PATH, &signature)).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    // The handler still gets the body
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    assert_eq!(&body[..], [&b"merchant:acme "[..], BODY].concat().as_slice());
+
+    // The same request again is a replay
+    let replay = app.clone().oneshot(signed(PATH, &signature)).await.unwrap();
+    assert_eq!(replay.status(), StatusCode::UNAUTHORIZED);
+
+    // Signatures aren't taken on other routes
+    let path = "/wallets/w-1/pots";
+    let signature = merchant_signing::sign(SECRET, now, &Method::POST, path, BODY);
+    let response = app.oneshot(signed(path, &signature)).await.unwrap();
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+
+    cleanup_test_data(&pool).await;
+}
+
+#[test]
+fn test_merchants_own_only_their_own_wallets() {
+    let merchant = Principal::merchant("acme");
+    assert_eq!(merchant.subject, "merchant:acme");
+    assert!(merchant.owns("acme"));
+    assert!(!merchant.owns("alice"));
+    assert!(!merchant.can_read("alice"));
+    // Not an API key: it doesn't get a key's run of every wallet
+    assert_eq!(merchant.api_key_scope, None);
+}
+
+#[tokio::test]
+async fn test_merchants_cannot_move_other_wallets_money() {
+    let pool = setup_test_db().await;
+    cleanup_test_data(&pool).await;
+    let repo = WalletRepository::new(pool.clone());
+    let own = repo.create_wallet("acme").await.unwrap();
+    let customer = repo.create_wallet("alice").await.unwrap();
+    let app = ownership_app(repo);
+
+    let now = chrono::Utc::now().timestamp();
+    let path = format!("/wallets/{}/transfer", own.id);
+    let signature = merchant_signing::sign(SECRET, now, &Method::POST, &path, BODY);
+    let response = app.clone().oneshot(signed(&path, &signature)).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+
+    // A customer's wallet is someone else's: it's as if it didn't exist
+    let path = format!("/wallets/{}/transfer", customer.id);
+    let signature = merchant_signing::sign(SECRET, now, &Method::POST, &path, BODY);
+    let response = app.oneshot(signed(&path, &signature)).await.unwrap();
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+    cleanup_test_data(&pool).await;
+}
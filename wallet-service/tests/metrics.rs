@@ -0,0 +1,36 @@
+//! Tests for the business KPI counters (no database needed)
+
+use rust_decimal_macros::dec;
+use wallet_service::{errors::WalletError, metrics::BusinessMetrics};
+
+#[test]
+fn test_render_openmetrics() {
+    let metrics = BusinessMetrics::new("USD");
+
+    metrics.record_wallet_created();
+    metrics.record_transfer(dec!(10.50));
+    metrics.record_transfer(dec!(4.50));
+    metrics.record_decline(
+        "transfer",
+        &WalletError::InsufficientBalance {
+            required: dec!(100),
+            available: dec!(5),
+        },
+    );
+    metrics.record_decline("transfer", &WalletError::WalletNotFound("w".to_string()));
+    metrics.record_decline("transfer", &WalletError::WalletNotFound("w".to_string()));
+
+    let output = metrics.render();
+
+    assert!(output.contains("# TYPE wallet_transfers counter\n"));
+    assert!(output.contains("wallet_transfers_total 2\n"));
+    assert!(output.contains("wallet_wallets_created_total 1\n"));
+    assert!(output.contains("wallet_transfer_volume_total{currency=\"USD\"} 15.00\n"));
+    assert!(output.contains(
+        "wallet_declines_total{operation=\"transfer\",reason=\"insufficient_balance\"} 1\n"
+    ));
+    assert!(output.contains(
+        "wallet_declines_total{operation=\"transfer\",reason=\"wallet_not_found\"} 2\n"
+    ));
+    assert!(output.ends_with("# EOF\n"));
+}
@@ -0,0 +1,50 @@
+//! Tests for the generated OpenAPI spec (no database needed)
+
+use utoipa::OpenApi;
+use wallet_service::openapi::ApiDoc;
+
+#[test]
+fn test_spec_covers_the_money_movement_routes() {
+    let spec = ApiDoc::openapi();
+
+    for path in [
+        "/wallets",
+        "/wallets/{wallet_id}",
+        "/wallets/{wallet_id}/fund",
+        "/wallets/{wallet_id}/transfer",
+        "/wallets/{wallet_id}/pots/{pot_id}/deposit",
+        "/transactions",
+        "/health",
+    ] {
+        assert!(spec.paths.paths.contains_key(path), "{} missing from the spec", path);
+    }
+}
+
+#[test]
+fn test_request_and_error_schemas_are_included() {
+    let json = serde_json::to_value(ApiDoc::openapi()).unwrap();
+    let schemas = &json["components"]["schemas"];
+
+    for schema in ["TransferRequest", "TransactionResponse", "ErrorResponse", "TransactionType"] {
+        assert!(schemas.get(schema).is_some(), "{} missing from components", schema);
+    }
+    // Amounts are strings on the wire, never floats
+    assert_eq!(schemas["FundWalletRequest"]["properties"]["amount"]["type"], "string");
+}
+
+#[test]
+fn test_every_operation_is_tagged() {
+    let json = serde_json::to_value(ApiDoc::openapi()).unwrap();
+
+    for (path, item) in json["paths"].as_object().unwrap() {
+        for (method, operation) in item.as_object().unwrap() {
+            let tags = operation["tags"].as_array();
+            assert!(
+                tags.is_some_and(|tags| !tags.is_empty()),
+                "{} {} has no tag",
+                method,
+                path
+            );
+        }
+    }
+}
@@ -0,0 +1,179 @@
+//! Integration tests for the outbox relay
+//!
+//! Run with: cargo test --test outbox -- --test-threads=1
+
+mod common;
+
+use common::{cleanup_test_data, setup_test_db};
+use std::collections::HashSet;
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+use wallet_service::errors::{WalletError, WalletResult};
+use uuid::Uuid;
+use wallet_service::outbox::{enqueue, OutboxPublisher, OutboxRelay};
+
+fn event_id() -> String {
+    Uuid::new_v4().to_string()
+}
+
+/// Records what was sent; fails for keys in `failing`
+#[derive(Clone, Default)]
+struct FakePublisher {
+    sent: Arc<Mutex<Vec<String>>>,
+    failing: Arc<Mutex<HashSet<String>>>,
+}
+
+impl OutboxPublisher for FakePublisher {
+    fn send(&self, key: &str, payload: &str) -> impl Future<Output = WalletResult<()>> + Send {
+        let result = if self.failing.lock().unwrap().contains(key) {
+            Err(WalletError::KafkaError("broker unavailable".to_string()))
+        } else {
+            self.sent.lock().unwrap().push(payload.to_string());
+            Ok(())
+        };
+        async move { result }
+    }
+}
+
+/// Kafka-transaction stand-in: sends are staged until commit, dropped on abort
+#[derive(Clone, Default)]
+struct TransactionalFake {
+    inner: FakePublisher,
+    staged: Arc<Mutex<Vec<String>>>,
+}
+
+impl OutboxPublisher for TransactionalFake {
+    fn send(&self, key: &str, payload: &str) -> impl Future<Output = WalletResult<()>> + Send {
+        let result = if self.inner.failing.lock().unwrap().contains(key) {
+            Err(WalletError::KafkaError("broker unavailable".to_string()))
+        } else {
+            self.staged.lock().unwrap().push(payload.to_string());
+            Ok(())
+        };
+        async move { result }
+    }
+
+    fn is_transactional(&self) -> bool {
+        true
+    }
+
+    fn commit_batch(&self) -> impl Future<Output = WalletResult<()>> + Send {
+        let staged: Vec<String> = self.staged.lock().unwrap().drain(..).collect();
+        self.inner.sent.lock().unwrap().extend(staged);
+        async { Ok(()) }
+    }
+
+    fn abort_batch(&self) -> impl Future<Output = WalletResult<()>> + Send {
+        self.staged.lock().unwrap().clear();
+        async { Ok(()) }
+    }
+}
+
+#[tokio::test]
+async fn test_relay_publishes_in_order_and_marks_sent() {
+    let pool = setup_test_db().await;
+    let publisher = FakePublisher::default();
+    let relay = OutboxRelay::new(pool.clone(), publisher.clone(), 100);
+
+    enqueue(&pool, &event_id(), "wallet-1", "WALLET_CREATED", "a").await.unwrap();
+    enqueue(&pool, &event_id(), "wallet-2", "WALLET_CREATED", "b").await.unwrap();
+    enqueue(&pool, &event_id(), "wallet-1", "WALLET_FUNDED", "c").await.unwrap();
+
+    let pass = relay.run_once().await.unwrap();
+    assert_eq!(pass.published, 3);
+    assert_eq!(*publisher.sent.lock().unwrap(), vec!["a", "b", "c"]);
+
+    // Nothing left - a second pass sends nothing again
+    assert_eq!(relay.run_once().await.unwrap().published, 0);
+    assert_eq!(relay.backlog().await.unwrap().pending, 0);
+
+    cleanup_test_data(&pool).await;
+}
+
+#[tokio::test]
+async fn test_failed_event_holds_back_same_wallet_only() {
+    let pool = setup_test_db().await;
+    let publisher = FakePublisher::default();
+    publisher.failing.lock().unwrap().insert("wallet-1".to_string());
+    let relay = OutboxRelay::new(pool.clone(), publisher.clone(), 100);
+
+    enqueue(&pool, &event_id(), "wallet-1", "WALLET_CREATED", "a").await.unwrap();
+    enqueue(&pool, &event_id(), "wallet-2", "WALLET_CREATED", "b").await.unwrap();
+    enqueue(&pool, &event_id(), "wallet-1", "WALLET_FUNDED", "c").await.unwrap();
+
+    // First failure stops the pass
+    let pass = relay.run_once().await.unwrap();
+    assert_eq!((pass.published, pass.failed), (0, 1));
+
+    // "a" is backing off: wallet-2 flows, wallet-1's later event waits
+    let pass = relay.run_once().await.unwrap();
+    assert_eq!((pass.published, pass.deferred), (1, 2));
+    assert_eq!(*publisher.sent.lock().unwrap(), vec!["b"]);
+    assert_eq!(relay.backlog().await.unwrap().pending, 2);
+
+    // Broker back, backoff over: wallet-1's events go out in order
+    publisher.failing.lock().unwrap().clear();
+    sqlx::query("UPDATE event_outbox SET next_attempt_at = NOW()")
+        .execute(&pool)
+        .await
+        .unwrap();
+    relay.run_once().await.unwrap();
+    assert_eq!(*publisher.sent.lock().unwrap(), vec!["b", "a", "c"]);
+
+    let metrics = relay.render_metrics().await.unwrap();
+    assert!(metrics.contains("wallet_outbox_backlog 0\n"));
+    assert!(metrics.contains("wallet_outbox_failures_total 1\n"));
+
+    cleanup_test_data(&pool).await;
+}
+
+#[tokio::test]
+async fn test_transactional_relay_aborts_whole_pass_on_failure() {
+    let pool = setup_test_db().await;
+    let publisher = TransactionalFake::default();
+    publisher.inner.failing.lock().unwrap().insert("wallet-2".to_string());
+    let relay = OutboxRelay::new(pool.clone(), publisher.clone(), 100);
+
+    enqueue(&pool, &event_id(), "wallet-1", "WALLET_CREATED", "a").await.unwrap();
+    enqueue(&pool, &event_id(), "wallet-2", "WALLET_CREATED", "b").await.unwrap();
+
+    // "a" was sent before "b" failed, but the abort hides it and keeps it pending
+    let pass = relay.run_once().await.unwrap();
+    assert_eq!((pass.published, pass.failed), (0, 1));
+    assert!(publisher.inner.sent.lock().unwrap().is_empty());
+    assert_eq!(relay.backlog().await.unwrap().pending, 2);
+
+    // Recovered: the next pass delivers each event exactly once, in order
+    publisher.inner.failing.lock().unwrap().clear();
+    sqlx::query("UPDATE event_outbox SET next_attempt_at = NOW()")
+        .execute(&pool)
+        .await
+        .unwrap();
+    assert_eq!(relay.run_once().await.unwrap().published, 2);
+    assert_eq!(*publisher.inner.sent.lock().unwrap(), vec!["a", "b"]);
+    assert_eq!(relay.backlog().await.unwrap().pending, 0);
+
+    cleanup_test_data(&pool).await;
+}
+
+#[tokio::test]
+async fn test_rows_have_debezium_outbox_columns() {
+    let pool = setup_test_db().await;
+    let id = event_id();
+
+    enqueue(&pool, &id, "wallet-1", "WALLET_CREATED", "{}").await.unwrap();
+
+    let (row_event_id, aggregate_type, aggregate_id, event_type) =
+        sqlx::query_as::<_, (String, String, String, String)>(
+            "SELECT event_id::text, aggregate_type, aggregate_id, event_type FROM event_outbox",
+        )
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+    assert_eq!(row_event_id, id);
+    assert_eq!(aggregate_type, "wallet");
+    assert_eq!(aggregate_id, "wallet-1");
+    assert_eq!(event_type, "WALLET_CREATED");
+
+    cleanup_test_data(&pool).await;
+}
@@ -0,0 +1,174 @@
+//! Integration tests for transaction PINs
+//!
+//! Run with: cargo test --test pins -- --test-threads=1
+
+mod common;
+
+use chrono::Utc;
+use common::{cleanup_test_data, setup_test_db};
+use rust_decimal_macros::dec;
+use std::time::Duration;
+use wallet_service::{errors::WalletError, pin::PinPolicy, repository::WalletRepository};
+
+fn policy() -> PinPolicy {
+    PinPolicy {
+        max_attempts: 3,
+        lockout: Duration::from_secs(600),
+        ..PinPolicy::default()
+    }
+}
+
+#[tokio::test]
+async fn test_transfers_over_the_threshold_need_the_pin() {
+    let pool = setup_test_db().await;
+    let repo = WalletRepository::new(pool.clone());
+    let wallet = repo.create_wallet("alice").await.unwrap();
+
+    // No PIN set - nothing to check
+    repo.check_pin(&wallet.id, None, dec!(500), &policy())
+        .await
+        .expect("Wallets without a PIN don't need one");
+
+    let settings = repo
+        .set_pin(&wallet.id, "4321", None, dec!(20), &policy())
+        .await
+        .expect("Failed to set PIN");
+    assert_eq!(settings.threshold, dec!(20));
+    assert_eq!(settings.failed_attempts, 0);
+
+    // Up to the threshold: no PIN needed
+    repo.check_pin(&wallet.id, None, dec!(20), &policy()).await.unwrap();
+
+    let missing = repo.check_pin(&wallet.id, None, dec!(20.01), &policy()).await;
+    assert!(matches!(missing, Err(WalletError::PinRequired)));
+
+    repo.check_pin(&wallet.id, Some("4321"), dec!(20.01), &policy())
+        .await
+        .expect("Right PIN should pass");
+
+    // Only the hash is stored
+    let stored: String = sqlx::query_scalar("SELECT pin_hash FROM wallet_pins WHERE wallet_id = $1")
+        .bind(&wallet.id)
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+    assert!(stored.starts_with("$argon2id$"));
+    assert!(!stored.contains("4321"));
+
+    cleanup_test_data(&pool).await;
+}
+
+#[tokio::test]
+async fn test_wrong_pins_count_down_to_a_lockout() {
+    let pool = setup_test_db().await;
+    let repo = WalletRepository::new(pool.clone());
+    let wallet = repo.create_wallet("alice").await.unwrap();
+    repo.set_pin(&wallet.id, "4321", None, dec!(0), &policy())
+        .await
+        .unwrap();
+
+    let first = repo.check_pin(&wallet.id, Some("0000"), dec!(1), &policy()).await;
+    assert!(matches!(first, Err(WalletError::IncorrectPin { attempts_left: 2 })));
+
+    // A right PIN resets the count
+    repo.check_pin(&wallet.id, Some("4321"), dec!(1), &policy()).await.unwrap();
+    assert_eq!(repo.find_pin(&wallet.id).await.unwrap().failed_attempts, 0);
+
+    for attempts_left in [2, 1] {
+        let wrong = repo.check_pin(&wallet.id, Some("0000"), dec!(1), &policy()).await;
+        assert!(matches!(wrong, Err(WalletError::IncorrectPin { attempts_left: left }) if left == attempts_left));
+    }
+    let third = repo.check_pin(&wallet.id, Some("0000"), dec!(1), &policy()).await;
+    assert!(matches!(
+        third,
+        Err(WalletError::LockedOut { factor: "pin", failed_attempts: 3, lockouts: 1, .. })
+    ));
+
+    // Locked: even the right PIN is refused...
+    let locked = repo.check_pin(&wallet.id, Some("4321"), dec!(1), &policy()).await;
+    assert!(matches!(locked, Err(WalletError::PinLocked { .. })));
+    assert!(repo.find_pin(&wallet.id).await.unwrap().locked_until.is_some());
+
+    // ...until the lockout passes
+    sqlx::query("UPDATE wallet_pins SET locked_until = NOW() - INTERVAL '1 second'")
+        .execute(&pool)
+        .await
+        .unwrap();
+    repo.check_pin(&wallet.id, Some("4321"), dec!(1), &policy()).await.unwrap();
+
+    cleanup_test_data(&pool).await;
+}
+
+#[tokio::test]
+async fn test_each_lockout_lasts_twice_as_long() {
+    let pool = setup_test_db().await;
+    let repo = WalletRepository::new(pool.clone());
+    let wallet = repo.create_wallet("alice").await.unwrap();
+    let policy = PinPolicy {
+        max_attempts: 1,
+        lockout: Duration::from_secs(600),
+        max_lockout: Duration::from_secs(1800),
+    };
+    repo.set_pin(&wallet.id, "4321", None, dec!(0), &policy)
+        .await
+        .unwrap();
+
+    let mut seen = Vec::new();
+    for _ in 0..3 {
+        let locked = repo.check_pin(&wallet.id, Some("0000"), dec!(1), &policy).await;
+        let Err(WalletError::LockedOut { lockouts, until, .. }) = locked else {
+            panic!("expected a lockout, got {:?}", locked);
+        };
+        seen.push((lockouts, (until - Utc::now()).num_seconds()));
+        sqlx::query("UPDATE wallet_pins SET locked_until = NOW() - INTERVAL '1 second'")
+            .execute(&pool)
+            .await
+            .unwrap();
+    }
+    // 10 minutes, 20, then capped at 30
+    assert_eq!(seen.iter().map(|(n, _)| *n).collect::<Vec<_>>(), vec![1, 2, 3]);
+    for ((_, secs), max) in seen.iter().zip([600, 1200, 1800]) {
+        assert!(*secs > max - 60 && *secs <= max, "{} not just under {}", secs, max);
+    }
+
+    // The right PIN forgets the lockouts
+    repo.check_pin(&wallet.id, Some("4321"), dec!(1), &policy).await.unwrap();
+    assert_eq!(repo.find_pin(&wallet.id).await.unwrap().lockouts, 0);
+
+    cleanup_test_data(&pool).await;
+}
+
+#[tokio::test]
+async fn test_changing_the_pin_needs_the_current_one() {
+    let pool = setup_test_db().await;
+    let repo = WalletRepository::new(pool.clone());
+    let wallet = repo.create_wallet("alice").await.unwrap();
+    repo.set_pin(&wallet.id, "4321", None, dec!(0), &policy())
+        .await
+        .unwrap();
+
+    let without = repo.set_pin(&wallet.id, "9999", None, dec!(0), &policy()).await;
+    assert!(matches!(without, Err(WalletError::PinRequired)));
+
+    let wrong = repo
+        .set_pin(&wallet.id, "9999", Some("0000"), dec!(0), &policy())
+        .await;
+    assert!(matches!(wrong, Err(WalletError::IncorrectPin { .. })));
+
+    let changed = repo
+        .set_pin(&wallet.id, "9999", Some("4321"), dec!(50), &policy())
+        .await
+        .expect("Failed to change PIN");
+    // The wrong attempt above is forgotten with the new PIN
+    assert_eq!(changed.failed_attempts, 0);
+    assert_eq!(changed.threshold, dec!(50));
+
+    repo.check_pin(&wallet.id, Some("9999"), dec!(51), &policy()).await.unwrap();
+    let old = repo.check_pin(&wallet.id, Some("4321"), dec!(51), &policy()).await;
+    assert!(matches!(old, Err(WalletError::IncorrectPin { .. })));
+
+    let unknown = repo.find_pin("00000000-0000-0000-0000-000000000000").await;
+    assert!(matches!(unknown, Err(WalletError::PinNotSet(_))));
+
+    cleanup_test_data(&pool).await;
+}
@@ -0,0 +1,78 @@
+//! Integration tests for pots (sub-wallets)
+//!
+//! Run with: cargo test --test pots -- --test-threads=1
+
+mod common;
+
+use common::{cleanup_test_data, setup_test_db};
+use rust_decimal_macros::dec;
+use wallet_service::{errors::WalletError, models::TransactionType, repository::WalletRepository};
+
+#[tokio::test]
+async fn test_create_pot_and_move_money() {
+    let pool = setup_test_db().await;
+    let repo = WalletRepository::new(pool.clone());
+
+    let wallet = repo.create_wallet("alice").await.unwrap();
+    repo.fund_wallet(&wallet.id, dec!(100)).await.unwrap();
+
+    let pot = repo
+        .create_pot(&wallet.id, "Holiday")
+        .await
+        .expect("Failed to create pot");
+    assert_eq!(pot.user_id, "alice");
+    assert_eq!(pot.parent_wallet_id.as_deref(), Some(wallet.id.as_str()));
+    assert_eq!(pot.nickname.as_deref(), Some("Holiday"));
+
+    let (out_txn, in_txn) = repo
+        .move_to_pot(&wallet.id, &pot.id, dec!(30))
+        .await
+        .expect("Failed to move money into pot");
+    assert!(matches!(out_txn.transaction_type, TransactionType::PotTransferOut));
+    assert!(matches!(in_txn.transaction_type, TransactionType::PotTransferIn));
+    assert_eq!(out_txn.reference_id, in_txn.reference_id);
+
+    repo.move_from_pot(&wallet.id, &pot.id, dec!(10))
+        .await
+        .expect("Failed to move money out of pot");
+
+    // Spendable balance is the parent's own; the pot holds the rest
+    assert_eq!(repo.find_by_id(&wallet.id).await.unwrap().balance, dec!(80));
+    assert_eq!(repo.find_by_id(&pot.id).await.unwrap().balance, dec!(20));
+
+    let pots = repo.find_pots(&wallet.id).await.unwrap();
+    assert_eq!(pots.len(), 1);
+
+    cleanup_test_data(&pool).await;
+}
+
+#[tokio::test]
+async fn test_pot_rules() {
+    let pool = setup_test_db().await;
+    let repo = WalletRepository::new(pool.clone());
+
+    let wallet = repo.create_wallet("alice").await.unwrap();
+    let other = repo.create_wallet("bob").await.unwrap();
+    repo.fund_wallet(&wallet.id, dec!(50)).await.unwrap();
+    let pot = repo.create_pot(&wallet.id, "Rainy day").await.unwrap();
+
+    // One level only
+    let result = repo.create_pot(&pot.id, "Nested").await;
+    assert!(matches!(result, Err(WalletError::InvalidRequest(_))));
+
+    // Pots only move money via their parent
+    let result = repo.fund_wallet(&pot.id, dec!(10)).await;
+    assert!(matches!(result, Err(WalletError::InvalidRequest(_))));
+    let result = repo.transfer(&wallet.id, &pot.id, dec!(10), None).await;
+    assert!(matches!(result, Err(WalletError::InvalidRequest(_))));
+
+    // Someone else's wallet can't reach the pot
+    let result = repo.move_to_pot(&other.id, &pot.id, dec!(10)).await;
+    assert!(matches!(result, Err(WalletError::PotNotFound(_))));
+
+    // Can't allocate more than is spendable
+    let result = repo.move_to_pot(&wallet.id, &pot.id, dec!(60)).await;
+    assert!(matches!(result, Err(WalletError::InsufficientBalance { .. })));
+
+    cleanup_test_data(&pool).await;
+}
@@ -0,0 +1,119 @@
+//! Tests for the rate limiter (no database needed)
+
+use axum::body::Body;
+use axum::extract::ConnectInfo;
+use axum::http::{header, Request, StatusCode};
+use axum::{middleware, routing::post, Router};
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tower::ServiceExt;
+use wallet_service::rate_limit::{limit_rate, Caller, RateLimit, RateLimiter};
+
+fn limiter(per_minute: u32, burst: u32) -> RateLimiter {
+    RateLimiter::new("test", RateLimit { per_minute, burst }, false)
+}
+
+fn keys(keys: &[&str]) -> Vec<String> {
+    keys.iter().map(|k| k.to_string()).collect()
+}
+
+#[test]
+fn test_burst_then_refill() {
+    let limiter = limiter(60, 3);
+    let start = Instant::now();
+    let ip = keys(&["ip:10.0.0.1"]);
+
+    for _ in 0..3 {
+        assert!(limiter.check(&ip, start).is_ok());
+    }
+    // One token a second
+    let wait = limiter.check(&ip, start).unwrap_err();
+    assert_eq!(wait, Duration::from_secs(1));
+    assert_eq!(limiter.rejected(), 1);
+
+    assert!(limiter.check(&ip, start + Duration::from_secs(1)).is_ok());
+    assert!(limiter.check(&ip, start + Duration::from_secs(1)).is_err());
+}
+
+#[test]
+fn test_every_bucket_needs_a_token_and_a_refusal_takes_none() {
+    let limiter = limiter(60, 1);
+    let now = Instant::now();
+
+    assert!(limiter.check(&keys(&["ip:10.0.0.1", "wallet_id:a"]), now).is_ok());
+    // Same wallet from another IP: the wallet's bucket is empty
+    assert!(limiter.check(&keys(&["ip:10.0.0.2", "wallet_id:a"]), now).is_err());
+    // ...and that refusal didn't spend 10.0.0.2's token
+    assert!(limiter.check(&keys(&["ip:10.0.0.2", "wallet_id:b"]), now).is_ok());
+}
+
+#[test]
+fn test_zero_per_minute_is_unlimited() {
+    let limiter = limiter(0, 1);
+    let now = Instant::now();
+    for _ in 0..100 {
+        assert!(limiter.check(&keys(&["ip:10.0.0.1"]), now).is_ok());
+    }
+}
+
+#[tokio::test]
+async fn test_middleware_answers_429_with_retry_after() {
+    let limiter = Arc::new(limiter(60, 1));
+    let app = Router::new().route(
+        "/wallets/:wallet_id/fund",
+        post(|| async { "ok" })
+            .route_layer(middleware::from_fn_with_state(limiter.clone(), limit_rate)),
+    );
+    let request = |wallet: &str| {
+        let mut request = Request::post(format!("/wallets/{}/fund", wallet))
+            .body(Body::empty())
+            .unwrap();
+        let peer: SocketAddr = "10.0.0.1:5000".parse().unwrap();
+        request.extensions_mut().insert(ConnectInfo(peer));
+        request
+    };
+
+    let response = app.clone().oneshot(request("a")).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let response = app.clone().oneshot(request("a")).await.unwrap();
+    assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+    // Whole seconds, rounded up
+    assert_eq!(response.headers()[header::RETRY_AFTER], "1");
+
+    // Per IP too: another wallet from the same client is refused
+    let response = app.oneshot(request("b")).await.unwrap();
+    assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+}
+
+#[tokio::test]
+async fn test_one_user_is_limited_across_ips_and_wallets() {
+    let limiter = Arc::new(limiter(60, 1));
+    let app = Router::new().route(
+        "/wallets/:wallet_id/transfer",
+        post(|| async { "ok" })
+            .route_layer(middleware::from_fn_with_state(limiter.clone(), limit_rate)),
+    );
+    let request = |user: &str, wallet: &str, ip: &str| {
+        let mut request = Request::post(format!("/wallets/{}/transfer", wallet))
+            .body(Body::empty())
+            .unwrap();
+        let peer: SocketAddr = format!("{}:5000", ip).parse().unwrap();
+        request.extensions_mut().insert(ConnectInfo(peer));
+        // What the authenticating layer leaves for the layers inside it
+        request.extensions_mut().insert(Caller(user.to_string()));
+        request
+    };
+
+    let response = app.clone().oneshot(request("alice", "a", "10.0.0.1")).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+
+    // New IP, new wallet - still alice
+    let response = app.clone().oneshot(request("alice", "b", "10.0.0.2")).await.unwrap();
+    assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+
+    // Someone else isn't held to alice's limit
+    let response = app.oneshot(request("bob", "c", "10.0.0.3")).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+}
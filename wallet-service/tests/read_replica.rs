@@ -0,0 +1,84 @@
+//! Tests for routing reads to a replica
+//!
+//! Run with: cargo test --test read_replica -- --test-threads=1
+
+mod common;
+
+use axum::body::Body;
+use axum::http::{Request, StatusCode};
+use axum::{middleware, routing::any, Router};
+use common::{cleanup_test_data, setup_test_db};
+use rust_decimal_macros::dec;
+use std::time::Duration;
+use tower::ServiceExt;
+use wallet_service::read_replica::{self, route_reads, ReadReplica};
+use wallet_service::repository::WalletRepository;
+
+#[tokio::test]
+async fn test_only_get_and_head_may_read_the_replica() {
+    let app = Router::new()
+        .route(
+            "/",
+            any(|| async { read_replica::replica_reads_allowed().to_string() }),
+        )
+        .layer(middleware::from_fn(route_reads));
+    let allowed = |request: Request<Body>| {
+        let app = app.clone();
+        async move {
+            let response = app.oneshot(request).await.unwrap();
+            assert_eq!(response.status(), StatusCode::OK);
+            let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+            &body[..] == b"true"
+        }
+    };
+
+    assert!(allowed(Request::get("/").body(Body::empty()).unwrap()).await);
+    assert!(!allowed(Request::post("/").body(Body::empty()).unwrap()).await);
+    assert!(!allowed(Request::delete("/").body(Body::empty()).unwrap()).await);
+    let read_your_writes = Request::get("/")
+        .header("X-Read-Consistency", "primary")
+        .body(Body::empty())
+        .unwrap();
+    assert!(!allowed(read_your_writes).await);
+
+    // Outside a request: the primary
+    assert!(!read_replica::replica_reads_allowed());
+}
+
+#[tokio::test]
+async fn test_reads_use_the_replica_only_while_it_keeps_up() {
+    let pool = setup_test_db().await;
+    cleanup_test_data(&pool).await;
+
+    // The test database stands in for the replica: a primary reports no lag
+    let replica_pool = setup_test_db().await;
+    let replica = ReadReplica::new(replica_pool.clone(), Duration::from_secs(1), Duration::from_secs(5));
+    assert!(!replica.is_usable(), "unused until probed");
+    replica.probe().await;
+    assert!(replica.is_usable());
+    assert_eq!(replica.lag(), Some(Duration::ZERO));
+
+    let repo = WalletRepository::new(pool.clone()).with_read_replica(replica.clone());
+    let wallet = repo.create_wallet("alice").await.unwrap();
+
+    // With the replica gone, GET-scoped reads fail - they went there...
+    replica_pool.close().await;
+    let result = read_replica::with_replica_reads(repo.find_by_id(&wallet.id)).await;
+    assert!(result.is_err());
+    // ...while mutations and other reads still use the primary
+    read_replica::with_replica_reads(repo.fund_wallet(&wallet.id, dec!(10)))
+        .await
+        .unwrap();
+    assert_eq!(repo.find_by_id(&wallet.id).await.unwrap().balance, dec!(10));
+
+    // The next probe notices and reads fall back to the primary
+    replica.probe().await;
+    assert!(!replica.is_usable());
+    assert_eq!(replica.lag(), None);
+    let wallet = read_replica::with_replica_reads(repo.find_by_id(&wallet.id))
+        .await
+        .unwrap();
+    assert_eq!(wallet.balance, dec!(10));
+
+    cleanup_test_data(&pool).await;
+}
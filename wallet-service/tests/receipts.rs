@@ -0,0 +1,71 @@
+//! Integration tests for transaction receipts
+//!
+//! Run with: cargo test --test receipts -- --test-threads=1
+
+mod common;
+
+use common::{cleanup_test_data, setup_test_db};
+use rust_decimal_macros::dec;
+use wallet_service::errors::WalletError;
+use wallet_service::repository::WalletRepository;
+
+#[tokio::test]
+async fn test_transfer_receipt_has_both_legs_and_the_senders_balance() {
+    let pool = setup_test_db().await;
+    cleanup_test_data(&pool).await;
+    let repo = WalletRepository::new(pool.clone());
+
+    let alice = repo.create_wallet("alice").await.unwrap();
+    let bob = repo.create_wallet("bob").await.unwrap();
+    repo.fund_wallet(&alice.id, dec!(50)).await.unwrap();
+    repo.fund_wallet(&bob.id, dec!(5)).await.unwrap();
+
+    let outcome = repo
+        .transfer(&alice.id, &bob.id, dec!(20), Some("dinner"))
+        .await
+        .unwrap();
+
+    let receipt = repo
+        .find_transaction_receipt(&outcome.out_transaction.id)
+        .await
+        .unwrap();
+    assert_eq!(receipt.user_id, "alice");
+    assert_eq!(receipt.memo.as_deref(), Some("dinner"));
+    assert_eq!(receipt.fee, dec!(0));
+    assert_eq!(receipt.balance_after, dec!(30));
+    assert_eq!(receipt.counterparty_user_ids, vec!["bob".to_string()]);
+    assert_eq!(receipt.legs.len(), 2);
+
+    // Bob's balance is his business
+    let bob_leg = receipt.legs.iter().find(|leg| leg.wallet_id == bob.id).unwrap();
+    assert_eq!(bob_leg.balance_after, None);
+
+    // ...and on his own receipt it's shown
+    let receipt = repo
+        .find_transaction_receipt(&outcome.in_transaction.id)
+        .await
+        .unwrap();
+    assert_eq!(receipt.balance_after, dec!(25));
+    assert_eq!(receipt.counterparty_user_ids, vec!["alice".to_string()]);
+}
+
+#[tokio::test]
+async fn test_funding_receipt_stands_alone() {
+    let pool = setup_test_db().await;
+    cleanup_test_data(&pool).await;
+    let repo = WalletRepository::new(pool.clone());
+
+    let wallet = repo.create_wallet("alice").await.unwrap();
+    repo.fund_wallet(&wallet.id, dec!(10)).await.unwrap();
+    let (_, second) = repo.fund_wallet(&wallet.id, dec!(2.50)).await.unwrap();
+    repo.fund_wallet(&wallet.id, dec!(100)).await.unwrap();
+
+    let receipt = repo.find_transaction_receipt(&second.id).await.unwrap();
+    assert_eq!(receipt.legs.len(), 1);
+    assert!(receipt.counterparty_user_ids.is_empty());
+    // As it was then, not as it is now
+    assert_eq!(receipt.balance_after, dec!(12.50));
+
+    let missing = repo.find_transaction_receipt("no-such-transaction").await;
+    assert!(matches!(missing, Err(WalletError::TransactionNotFound(_))));
+}
@@ -0,0 +1,58 @@
+//! Tests for request IDs (no database needed)
+
+use axum::body::Body;
+use axum::http::{Request, StatusCode};
+use axum::response::IntoResponse;
+use axum::{middleware, routing::get, Json, Router};
+use tower::ServiceExt;
+use wallet_service::correlation::{next_event_ids, with_correlation, REQUEST_ID_HEADER};
+use wallet_service::errors::WalletError;
+
+fn app() -> Router {
+    Router::new()
+        .route("/ids", get(|| async { Json(next_event_ids()) }))
+        .route(
+            "/missing",
+            get(|| async { WalletError::WalletNotFound("w1".to_string()).into_response() }),
+        )
+        .layer(middleware::from_fn(with_correlation))
+}
+
+async fn json_body(response: axum::response::Response) -> serde_json::Value {
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    serde_json::from_slice(&body).unwrap()
+}
+
+#[tokio::test]
+async fn test_incoming_request_id_is_echoed_and_stamped_on_events() {
+    let request = Request::get("/ids")
+        .header(REQUEST_ID_HEADER, "req-123")
+        .body(Body::empty())
+        .unwrap();
+    let response = app().oneshot(request).await.unwrap();
+
+    assert_eq!(response.headers()[REQUEST_ID_HEADER], "req-123");
+    let ids = json_body(response).await;
+    assert_eq!(ids["request_id"], "req-123");
+}
+
+#[tokio::test]
+async fn test_generated_request_id_is_in_error_bodies() {
+    let request = Request::get("/missing").body(Body::empty()).unwrap();
+    let response = app().oneshot(request).await.unwrap();
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+    let header = response.headers()[REQUEST_ID_HEADER].to_str().unwrap().to_string();
+    assert_eq!(header.len(), 36);
+    let body = json_body(response).await;
+    assert_eq!(body["request_id"], header);
+    assert_eq!(body["success"], false);
+}
+
+#[test]
+fn test_events_outside_a_request_have_none() {
+    assert_eq!(next_event_ids().request_id, None);
+    // ...so the field is left out of the payload entirely
+    let json = serde_json::to_value(next_event_ids()).unwrap();
+    assert!(json.get("request_id").is_none());
+}
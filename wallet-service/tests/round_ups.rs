@@ -0,0 +1,89 @@
+//! Integration tests for round-up savings rules
+//!
+//! Run with: cargo test --test round_ups -- --test-threads=1
+
+mod common;
+
+use common::{cleanup_test_data, setup_test_db};
+use rust_decimal_macros::dec;
+use wallet_service::{errors::WalletError, models::TransactionType, repository::WalletRepository};
+
+#[tokio::test]
+async fn test_transfer_sweeps_round_up_into_savings() {
+    let pool = setup_test_db().await;
+    let repo = WalletRepository::new(pool.clone());
+
+    let spending = repo.create_wallet("alice").await.unwrap();
+    let savings = repo.create_wallet("alice").await.unwrap();
+    let bob = repo.create_wallet("bob").await.unwrap();
+    repo.fund_wallet(&spending.id, dec!(10)).await.unwrap();
+
+    repo.set_round_up_rule(&spending.id, &savings.id, dec!(1))
+        .await
+        .expect("Failed to set round-up rule");
+
+    let outcome = repo
+        .transfer(&spending.id, &bob.id, dec!(3.40), None)
+        .await
+        .unwrap();
+
+    let round_up = outcome.round_up.expect("Expected a round-up");
+    assert_eq!(round_up.out_transaction.amount, dec!(0.60));
+    assert!(matches!(round_up.out_transaction.transaction_type, TransactionType::RoundUpOut));
+    assert!(matches!(round_up.in_transaction.transaction_type, TransactionType::RoundUpIn));
+    assert_eq!(round_up.in_transaction.reference_id, outcome.out_transaction.reference_id);
+
+    assert_eq!(repo.find_by_id(&spending.id).await.unwrap().balance, dec!(6));
+    assert_eq!(repo.find_by_id(&savings.id).await.unwrap().balance, dec!(0.60));
+    assert_eq!(repo.find_by_id(&bob.id).await.unwrap().balance, dec!(3.40));
+
+    cleanup_test_data(&pool).await;
+}
+
+#[tokio::test]
+async fn test_round_up_skipped_for_exact_amounts_and_low_balance() {
+    let pool = setup_test_db().await;
+    let repo = WalletRepository::new(pool.clone());
+
+    let spending = repo.create_wallet("alice").await.unwrap();
+    let savings = repo.create_wallet("alice").await.unwrap();
+    let bob = repo.create_wallet("bob").await.unwrap();
+    repo.fund_wallet(&spending.id, dec!(5.50)).await.unwrap();
+    repo.set_round_up_rule(&spending.id, &savings.id, dec!(1))
+        .await
+        .unwrap();
+
+    // Exact multiple - nothing to round up
+    let outcome = repo.transfer(&spending.id, &bob.id, dec!(2), None).await.unwrap();
+    assert!(outcome.round_up.is_none());
+
+    // 3.40 leaves 0.10, can't cover the 0.60 round-up - transfer still succeeds
+    let outcome = repo
+        .transfer(&spending.id, &bob.id, dec!(3.40), None)
+        .await
+        .unwrap();
+    assert!(outcome.round_up.is_none());
+    assert_eq!(repo.find_by_id(&spending.id).await.unwrap().balance, dec!(0.10));
+
+    cleanup_test_data(&pool).await;
+}
+
+#[tokio::test]
+async fn test_round_up_rule_validation() {
+    let pool = setup_test_db().await;
+    let repo = WalletRepository::new(pool.clone());
+
+    let alice = repo.create_wallet("alice").await.unwrap();
+    let bob = repo.create_wallet("bob").await.unwrap();
+
+    let result = repo.set_round_up_rule(&alice.id, &bob.id, dec!(1)).await;
+    assert!(matches!(result, Err(WalletError::InvalidRequest(_))));
+
+    let result = repo.set_round_up_rule(&alice.id, &alice.id, dec!(1)).await;
+    assert!(matches!(result, Err(WalletError::InvalidRequest(_))));
+
+    let result = repo.find_round_up_rule(&alice.id).await;
+    assert!(matches!(result, Err(WalletError::RoundUpRuleNotFound(_))));
+
+    cleanup_test_data(&pool).await;
+}
@@ -0,0 +1,81 @@
+//! Integration tests for the scrubbed data export
+//!
+//! Run with: cargo test --test scrub -- --test-threads=1
+
+mod common;
+
+use common::{cleanup_test_data, setup_test_db};
+use rust_decimal::Decimal;
+use rust_decimal_macros::dec;
+use wallet_service::{
+    repository::WalletRepository,
+    scrub::{export_sql, Scrubber},
+};
+
+#[test]
+fn test_pseudonyms_are_deterministic_and_keyed() {
+    let scrubber = Scrubber::new(b"secret-a", 1);
+    let other = Scrubber::new(b"secret-b", 1);
+
+    assert_eq!(scrubber.user_id("alice"), scrubber.user_id("alice"));
+    assert_ne!(scrubber.user_id("alice"), scrubber.user_id("bob"));
+    assert_ne!(scrubber.user_id("alice"), other.user_id("alice"));
+    assert!(!scrubber.user_id("alice").contains("alice"));
+
+    // Same seed, same scale factor; always within [0.5, 1.5)
+    assert_eq!(scrubber.scale(), other.scale());
+    assert!(scrubber.scale() >= dec!(0.5) && scrubber.scale() < dec!(1.5));
+}
+
+#[tokio::test]
+async fn test_scrubbed_wallet_stays_consistent() {
+    let pool = setup_test_db().await;
+    let repo = WalletRepository::new(pool.clone());
+
+    let alice = repo.create_wallet("alice").await.unwrap();
+    let bob = repo.create_wallet("bob").await.unwrap();
+    repo.fund_wallet(&alice.id, dec!(100)).await.unwrap();
+    repo.transfer(&alice.id, &bob.id, dec!(33.33), Some("secret memo"))
+        .await
+        .unwrap();
+
+    let scrubber = Scrubber::new(b"test-secret", 7);
+    let wallet = repo.find_by_id(&alice.id).await.unwrap();
+    let transactions = repo.find_wallet_transactions(&alice.id).await.unwrap();
+    let (scrubbed, scrubbed_txns) = scrubber.scrub_wallet(&wallet, &transactions);
+
+    assert_ne!(scrubbed.user_id, "alice");
+    assert!(scrubbed_txns.iter().all(|t| t.wallet_id == scrubbed.id));
+    assert!(scrubbed_txns.iter().all(|t| t.memo.is_none()));
+
+    // balance == credits - debits still holds
+    let expected: Decimal = scrubbed_txns
+        .iter()
+        .map(|t| if t.transaction_type.is_credit() { t.amount } else { -t.amount })
+        .sum();
+    assert_eq!(scrubbed.balance, expected);
+
+    cleanup_test_data(&pool).await;
+}
+
+#[tokio::test]
+async fn test_export_sql_contains_no_raw_identifiers() {
+    let pool = setup_test_db().await;
+    let repo = WalletRepository::new(pool.clone());
+
+    let alice = repo.create_wallet("alice").await.unwrap();
+    repo.fund_wallet(&alice.id, dec!(10)).await.unwrap();
+
+    let scrubber = Scrubber::new(b"test-secret", 7);
+    let mut out = Vec::new();
+    let stats = export_sql(&repo, &scrubber, 10, &mut out).await.unwrap();
+    let sql = String::from_utf8(out).unwrap();
+
+    assert_eq!(stats.wallets, 1);
+    assert_eq!(stats.transactions, 1);
+    assert!(!sql.contains("alice"));
+    assert!(!sql.contains(&alice.id));
+    assert!(sql.contains(&scrubber.id(&alice.id)));
+
+    cleanup_test_data(&pool).await;
+}
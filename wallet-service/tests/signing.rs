@@ -0,0 +1,33 @@
+//! Tests for Kafka event signing (no Kafka needed)
+
+use wallet_service::signing::EventSigner;
+
+const SECRET: &[u8] = b"0123456789abcdef0123456789abcdef";
+const PAYLOAD: &[u8] = br#"{"amount":"10.00"}"#;
+
+#[test]
+fn test_signature_covers_key_payload_and_secret() {
+    let signer = EventSigner::new("k1", SECRET).unwrap();
+    let signature = signer.sign("wallet-1", PAYLOAD);
+
+    // history-service's tests check the same vector
+    assert_eq!(
+        signature,
+        "k1:95c5976490b6d5eb123b2bbae2811a2e266de64fca8591c824e7602c297329a2"
+    );
+    assert_eq!(signer.sign("wallet-1", PAYLOAD), signature);
+
+    assert_ne!(signer.sign("wallet-2", PAYLOAD), signature);
+    assert_ne!(signer.sign("wallet-1", br#"{"amount":"99.00"}"#), signature);
+    let other = EventSigner::new("k1", b"fedcba9876543210fedcba9876543210").unwrap();
+    assert_ne!(other.sign("wallet-1", PAYLOAD), signature);
+}
+
+#[test]
+fn test_signer_rejects_weak_secrets_and_bad_key_ids() {
+    assert!(EventSigner::new("k1", b"too-short").is_err());
+    assert!(EventSigner::new("", SECRET).is_err());
+    assert!(EventSigner::new("k:1", SECRET).is_err());
+    assert!(EventSigner::new("k1,k2", SECRET).is_err());
+    assert_eq!(EventSigner::new("2025-02", SECRET).unwrap().key_id(), "2025-02");
+}
@@ -0,0 +1,215 @@
+//! Integration tests for step-up verification of large transfers
+//!
+//! Run with: cargo test --test step_up -- --test-threads=1
+
+mod common;
+
+use common::{cleanup_test_data, setup_test_db};
+use rust_decimal_macros::dec;
+use std::time::Duration;
+use wallet_service::{
+    errors::WalletError,
+    repository::WalletRepository,
+    step_up::{self, StepUpConfig},
+};
+
+const TTL: Duration = Duration::from_secs(300);
+
+fn config() -> StepUpConfig {
+    StepUpConfig::default()
+}
+
+#[test]
+fn test_only_amounts_over_the_threshold_step_up() {
+    let off = StepUpConfig::default();
+    assert!(!off.applies_to(dec!(1000000)));
+
+    let config = StepUpConfig {
+        threshold: Some(dec!(1000)),
+        ..StepUpConfig::default()
+    };
+    assert!(!config.applies_to(dec!(1000)));
+    assert!(config.applies_to(dec!(1000.01)));
+
+    let code = step_up::generate_code();
+    assert_eq!(code.len(), 6);
+    assert!(code.bytes().all(|b| b.is_ascii_digit()));
+    assert_eq!(step_up::hash_code(&code).len(), 64);
+}
+
+#[tokio::test]
+async fn test_confirming_with_the_code_marks_it_confirmed_once() {
+    let pool = setup_test_db().await;
+    let repo = WalletRepository::new(pool.clone());
+    let from = repo.create_wallet("alice").await.unwrap();
+    let to = repo.create_wallet("bob").await.unwrap();
+
+    let pending = repo
+        .create_pending_transfer(&from.id, &to.id, dec!(5000), Some("rent"), "123456", TTL)
+        .await
+        .expect("Failed to create pending transfer");
+    assert_eq!(pending.status, "PENDING_VERIFICATION");
+
+    // Only the hash is stored
+    let stored: String = sqlx::query_scalar("SELECT code_hash FROM pending_transfers WHERE id = $1")
+        .bind(&pending.id)
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+    assert_eq!(stored, step_up::hash_code("123456"));
+
+    // Someone else's wallet can't see it
+    let other = repo.find_pending_transfer(&to.id, &pending.id).await;
+    assert!(matches!(other, Err(WalletError::PendingTransferNotFound(_))));
+
+    let confirmed = repo
+        .confirm_pending_transfer(&from.id, &pending.id, "123456", &config())
+        .await
+        .expect("Right code should confirm");
+    assert_eq!(confirmed.status, "CONFIRMED");
+    assert_eq!(confirmed.memo.as_deref(), Some("rent"));
+
+    let again = repo.confirm_pending_transfer(&from.id, &pending.id, "123456", &config()).await;
+    assert!(matches!(again, Err(WalletError::PendingTransferClosed(ref s)) if s == "CONFIRMED"));
+
+    repo.resolve_pending_transfer(&pending.id, Some("ref-1")).await.unwrap();
+    let resolved = repo.find_pending_transfer(&from.id, &pending.id).await.unwrap();
+    assert_eq!(resolved.status, "COMPLETED");
+    assert_eq!(resolved.reference_id.as_deref(), Some("ref-1"));
+    assert!(resolved.resolved_at.is_some());
+
+    cleanup_test_data(&pool).await;
+}
+
+#[tokio::test]
+async fn test_wrong_codes_cancel_the_transfer() {
+    let pool = setup_test_db().await;
+    let repo = WalletRepository::new(pool.clone());
+    let from = repo.create_wallet("alice").await.unwrap();
+    let to = repo.create_wallet("bob").await.unwrap();
+    let pending = repo
+        .create_pending_transfer(&from.id, &to.id, dec!(5000), None, "123456", TTL)
+        .await
+        .unwrap();
+
+    for attempts_left in [2, 1, 0] {
+        let wrong = repo.confirm_pending_transfer(&from.id, &pending.id, "000000", &config()).await;
+        assert!(matches!(wrong, Err(WalletError::IncorrectVerificationCode { attempts_left: left }) if left == attempts_left));
+    }
+
+    // Cancelled: even the right code is refused now
+    let late = repo.confirm_pending_transfer(&from.id, &pending.id, "123456", &config()).await;
+    assert!(matches!(late, Err(WalletError::PendingTransferClosed(ref s)) if s == "CANCELLED"));
+
+    cleanup_test_data(&pool).await;
+}
+
+#[tokio::test]
+async fn test_unconfirmed_transfers_expire_or_can_be_cancelled() {
+    let pool = setup_test_db().await;
+    let repo = WalletRepository::new(pool.clone());
+    let from = repo.create_wallet("alice").await.unwrap();
+    let to = repo.create_wallet("bob").await.unwrap();
+
+    let stale = repo
+        .create_pending_transfer(&from.id, &to.id, dec!(5000), None, "123456", TTL)
+        .await
+        .unwrap();
+    sqlx::query("UPDATE pending_transfers SET expires_at = NOW() - INTERVAL '1 second' WHERE id = $1")
+        .bind(&stale.id)
+        .execute(&pool)
+        .await
+        .unwrap();
+
+    // Confirming past expiry fails, and marks it
+    let expired = repo.confirm_pending_transfer(&from.id, &stale.id, "123456", &config()).await;
+    assert!(matches!(expired, Err(WalletError::VerificationExpired)));
+    assert_eq!(repo.find_pending_transfer(&from.id, &stale.id).await.unwrap().status, "EXPIRED");
+
+    let swept = repo
+        .create_pending_transfer(&from.id, &to.id, dec!(5000), None, "123456", TTL)
+        .await
+        .unwrap();
+    let open = repo
+        .create_pending_transfer(&from.id, &to.id, dec!(5000), None, "654321", TTL)
+        .await
+        .unwrap();
+    sqlx::query("UPDATE pending_transfers SET expires_at = NOW() - INTERVAL '1 second' WHERE id = $1")
+        .bind(&swept.id)
+        .execute(&pool)
+        .await
+        .unwrap();
+    assert_eq!(repo.expire_pending_transfers().await.unwrap(), 1);
+
+    let cancelled = repo.cancel_pending_transfer(&from.id, &open.id).await.unwrap();
+    assert_eq!(cancelled.status, "CANCELLED");
+    let twice = repo.cancel_pending_transfer(&from.id, &open.id).await;
+    assert!(matches!(twice, Err(WalletError::PendingTransferClosed(_))));
+    let unknown = repo.cancel_pending_transfer(&from.id, "00000000-0000-0000-0000-000000000000").await;
+    assert!(matches!(unknown, Err(WalletError::PendingTransferNotFound(_))));
+
+    cleanup_test_data(&pool).await;
+}
+
+#[tokio::test]
+async fn test_wrong_codes_across_transfers_lock_the_wallet() {
+    let pool = setup_test_db().await;
+    let repo = WalletRepository::new(pool.clone());
+    let from = repo.create_wallet("alice").await.unwrap();
+    let to = repo.create_wallet("bob").await.unwrap();
+    let config = StepUpConfig {
+        wallet_max_failures: 4,
+        lockout: Duration::from_secs(600),
+        ..StepUpConfig::default()
+    };
+
+    // A fresh transfer per guess doesn't start the count again
+    let mut waiting = Vec::new();
+    for _ in 0..3 {
+        let pending = repo
+            .create_pending_transfer(&from.id, &to.id, dec!(5000), None, "123456", TTL)
+            .await
+            .unwrap();
+        let wrong = repo.confirm_pending_transfer(&from.id, &pending.id, "000000", &config).await;
+        assert!(matches!(wrong, Err(WalletError::IncorrectVerificationCode { attempts_left: 2 })));
+        waiting.push(pending);
+    }
+    let last = repo
+        .confirm_pending_transfer(&from.id, &waiting[0].id, "000000", &config)
+        .await;
+    assert!(matches!(
+        last,
+        Err(WalletError::LockedOut { factor: "otp", failed_attempts: 4, lockouts: 1, .. })
+    ));
+
+    // Everything waiting is cancelled, and nothing new can start
+    for pending in &waiting {
+        let status = repo.find_pending_transfer(&from.id, &pending.id).await.unwrap().status;
+        assert_eq!(status, "CANCELLED");
+    }
+    let blocked = repo
+        .create_pending_transfer(&from.id, &to.id, dec!(5000), None, "123456", TTL)
+        .await;
+    assert!(matches!(blocked, Err(WalletError::VerificationLocked { .. })));
+
+    // The next lockout lasts twice as long
+    sqlx::query("UPDATE step_up_lockouts SET locked_until = NOW() - INTERVAL '1 second'")
+        .execute(&pool)
+        .await
+        .unwrap();
+    let mut second = None;
+    for _ in 0..4 {
+        let pending = repo
+            .create_pending_transfer(&from.id, &to.id, dec!(5000), None, "123456", TTL)
+            .await
+            .unwrap();
+        second = Some(repo.confirm_pending_transfer(&from.id, &pending.id, "000000", &config).await);
+    }
+    let Some(Err(WalletError::LockedOut { lockouts: 2, until, .. })) = second else {
+        panic!("expected a second lockout, got {:?}", second);
+    };
+    let remaining = until - chrono::Utc::now();
+    assert!(remaining > chrono::Duration::seconds(1100) && remaining <= chrono::Duration::seconds(1200));
+
+    cleanup_test_data(&pool).await;
+}
@@ -0,0 +1,119 @@
+//! Integration tests for transfer templates
+//!
+//! Run with: cargo test --test templates -- --test-threads=1
+
+mod common;
+
+use common::{cleanup_test_data, setup_test_db};
+use rust_decimal_macros::dec;
+use wallet_service::{errors::WalletError, repository::WalletRepository};
+
+#[tokio::test]
+async fn test_create_and_list_templates() {
+    let pool = setup_test_db().await;
+    let repo = WalletRepository::new(pool.clone());
+
+    let alice_wallet = repo.create_wallet("alice").await.unwrap();
+    let bob_wallet = repo.create_wallet("bob").await.unwrap();
+
+    let template = repo
+        .create_template(
+            &alice_wallet.id,
+            "Rent",
+            Some(&bob_wallet.id),
+            None,
+            dec!(750),
+            Some("Monthly rent"),
+        )
+        .await
+        .expect("Failed to create template");
+
+    assert_eq!(template.wallet_id, alice_wallet.id);
+    assert_eq!(template.amount, dec!(750));
+
+    let templates = repo.find_templates(&alice_wallet.id).await.unwrap();
+    assert_eq!(templates.len(), 1);
+    assert_eq!(templates[0].memo.as_deref(), Some("Monthly rent"));
+
+    let found = repo.find_template(&template.id).await.unwrap();
+    assert_eq!(found.to_wallet_id.as_deref(), Some(bob_wallet.id.as_str()));
+
+    cleanup_test_data(&pool).await;
+}
+
+#[tokio::test]
+async fn test_template_beneficiary_must_belong_to_owner() {
+    let pool = setup_test_db().await;
+    let repo = WalletRepository::new(pool.clone());
+
+    let alice_wallet = repo.create_wallet("alice").await.unwrap();
+    let bobs_contact = repo
+        .create_beneficiary("bob", "Carol", None, Some("carol"))
+        .await
+        .unwrap();
+
+    let result = repo
+        .create_template(&alice_wallet.id, "Carol", None, Some(&bobs_contact.id), dec!(10), None)
+        .await;
+
+    assert!(matches!(result, Err(WalletError::BeneficiaryNotFound(_))));
+
+    cleanup_test_data(&pool).await;
+}
+
+#[tokio::test]
+async fn test_template_rejects_non_positive_amount() {
+    let pool = setup_test_db().await;
+    let repo = WalletRepository::new(pool.clone());
+
+    let alice_wallet = repo.create_wallet("alice").await.unwrap();
+    let bob_wallet = repo.create_wallet("bob").await.unwrap();
+
+    let result = repo
+        .create_template(&alice_wallet.id, "Zero", Some(&bob_wallet.id), None, dec!(0), None)
+        .await;
+
+    assert!(matches!(result, Err(WalletError::InvalidAmount(_))));
+
+    cleanup_test_data(&pool).await;
+}
+
+#[tokio::test]
+async fn test_transfer_records_memo_on_both_legs() {
+    let pool = setup_test_db().await;
+    let repo = WalletRepository::new(pool.clone());
+
+    let alice_wallet = repo.create_wallet("alice").await.unwrap();
+    let bob_wallet = repo.create_wallet("bob").await.unwrap();
+    repo.fund_wallet(&alice_wallet.id, dec!(100)).await.unwrap();
+
+    let outcome = repo
+        .transfer(&alice_wallet.id, &bob_wallet.id, dec!(20), Some("Dinner"))
+        .await
+        .unwrap();
+
+    assert_eq!(outcome.out_transaction.memo.as_deref(), Some("Dinner"));
+    assert_eq!(outcome.in_transaction.memo.as_deref(), Some("Dinner"));
+
+    cleanup_test_data(&pool).await;
+}
+
+#[tokio::test]
+async fn test_delete_template() {
+    let pool = setup_test_db().await;
+    let repo = WalletRepository::new(pool.clone());
+
+    let alice_wallet = repo.create_wallet("alice").await.unwrap();
+    let bob_wallet = repo.create_wallet("bob").await.unwrap();
+    let template = repo
+        .create_template(&alice_wallet.id, "Rent", Some(&bob_wallet.id), None, dec!(5), None)
+        .await
+        .unwrap();
+
+    repo.delete_template(&template.id).await.unwrap();
+
+    let result = repo.find_template(&template.id).await;
+    assert!(matches!(result, Err(WalletError::TemplateNotFound(_))));
+
+    cleanup_test_data(&pool).await;
+}
@@ -0,0 +1,58 @@
+//! Tests for request timeouts (no database needed)
+
+use axum::body::Body;
+use axum::http::{Request, StatusCode};
+use axum::{middleware, routing::get, Router};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tower::ServiceExt;
+use wallet_service::timeout::{enforce_timeout, parse_routes, TimeoutConfig};
+
+#[test]
+fn test_route_overrides_fall_back_to_the_default() {
+    let mut config = TimeoutConfig::default();
+    config.routes.extend(parse_routes(
+        "/wallets/:wallet_id/transfer=5000, not-a-route=1, /admin/audit-log=abc",
+    ));
+
+    assert_eq!(config.for_route("/wallets/:wallet_id/transfer"), Duration::from_secs(5));
+    assert_eq!(config.for_route("/admin/wallets/archive"), Duration::from_secs(60));
+    assert_eq!(config.for_route("/admin/audit-log"), Duration::from_secs(10));
+    assert_eq!(config.routes.len(), 2);
+}
+
+#[tokio::test]
+async fn test_slow_route_is_a_504_but_still_finishes() {
+    let finished = Arc::new(AtomicBool::new(false));
+    let config = TimeoutConfig {
+        default: Duration::from_millis(50),
+        routes: Default::default(),
+    };
+
+    let done = finished.clone();
+    let app = Router::new()
+        .route(
+            "/slow",
+            get(move || async move {
+                tokio::time::sleep(Duration::from_millis(150)).await;
+                done.store(true, Ordering::SeqCst);
+                "ok"
+            }),
+        )
+        .route("/fast", get(|| async { "ok" }))
+        .route_layer(middleware::from_fn_with_state(Arc::new(config), enforce_timeout));
+
+    let request = |path: &str| Request::get(path).body(Body::empty()).unwrap();
+
+    let response = app.clone().oneshot(request("/fast")).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let response = app.oneshot(request("/slow")).await.unwrap();
+    assert_eq!(response.status(), StatusCode::GATEWAY_TIMEOUT);
+    assert!(!finished.load(Ordering::SeqCst));
+
+    // Not cancelled - a committed change still gets to publish its event
+    tokio::time::sleep(Duration::from_millis(300)).await;
+    assert!(finished.load(Ordering::SeqCst));
+}
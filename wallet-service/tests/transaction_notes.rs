@@ -0,0 +1,72 @@
+//! Integration tests for support notes on transactions
+//!
+//! Run with: cargo test --test transaction_notes -- --test-threads=1
+
+mod common;
+
+use common::{cleanup_test_data, setup_test_db};
+use rust_decimal_macros::dec;
+use wallet_service::{errors::WalletError, repository::WalletRepository};
+
+#[tokio::test]
+async fn test_add_and_list_notes() {
+    let pool = setup_test_db().await;
+    let repo = WalletRepository::new(pool.clone());
+
+    let wallet = repo.create_wallet("alice").await.unwrap();
+    let (_, txn) = repo.fund_wallet(&wallet.id, dec!(100)).await.unwrap();
+
+    repo.add_transaction_note(&txn.id, Some("CASE-42"), "Customer disputes top-up", "agent.smith")
+        .await
+        .expect("Failed to add note");
+    repo.add_transaction_note(&txn.id, None, "Bank confirmed receipt", "agent.smith")
+        .await
+        .unwrap();
+
+    let notes = repo.find_transaction_notes(&txn.id).await.unwrap();
+    assert_eq!(notes.len(), 2);
+    assert_eq!(notes[0].case_id.as_deref(), Some("CASE-42"));
+
+    // The ledger row itself is untouched
+    let stored = repo.find_transaction(&txn.id).await.unwrap();
+    assert_eq!(stored.amount, dec!(100));
+
+    cleanup_test_data(&pool).await;
+}
+
+#[tokio::test]
+async fn test_filter_transactions_by_case() {
+    let pool = setup_test_db().await;
+    let repo = WalletRepository::new(pool.clone());
+
+    let wallet = repo.create_wallet("alice").await.unwrap();
+    let (_, first) = repo.fund_wallet(&wallet.id, dec!(10)).await.unwrap();
+    let (_, second) = repo.fund_wallet(&wallet.id, dec!(20)).await.unwrap();
+
+    repo.add_transaction_note(&first.id, Some("CASE-1"), "Flagged", "ops")
+        .await
+        .unwrap();
+    repo.add_transaction_note(&second.id, Some("CASE-2"), "Unrelated", "ops")
+        .await
+        .unwrap();
+
+    let linked = repo.find_transactions_by_case("CASE-1").await.unwrap();
+    assert_eq!(linked.len(), 1);
+    assert_eq!(linked[0].id, first.id);
+
+    cleanup_test_data(&pool).await;
+}
+
+#[tokio::test]
+async fn test_note_on_unknown_transaction() {
+    let pool = setup_test_db().await;
+    let repo = WalletRepository::new(pool.clone());
+
+    let result = repo
+        .add_transaction_note("does-not-exist", None, "Hello", "ops")
+        .await;
+
+    assert!(matches!(result, Err(WalletError::TransactionNotFound(_))));
+
+    cleanup_test_data(&pool).await;
+}
@@ -0,0 +1,155 @@
+//! Tests for request body validation (no database needed)
+
+use axum::http::StatusCode;
+use axum::response::IntoResponse;
+use rust_decimal_macros::dec;
+use serde_json::json;
+use wallet_service::errors::{FieldError, WalletError};
+use wallet_service::models::{
+    BatchGetWalletsRequest, CreateBeneficiaryRequest, CreateWalletRequest, ExecuteTemplateRequest,
+    FundWalletRequest, TransferRequest,
+};
+use wallet_service::validation::{self, parse_amount, AmountRules};
+
+#[test]
+fn test_only_plain_decimal_strings_parse() {
+    assert_eq!(parse_amount("10.50").unwrap(), dec!(10.50));
+    assert_eq!(parse_amount("7").unwrap(), dec!(7));
+    assert_eq!(parse_amount("-3.25").unwrap(), dec!(-3.25));
+
+    for bad in ["NaN", "inf", "-Infinity", "1e9", "1_000", "+5", " 5", "5.", ".5", "", "-"] {
+        assert!(parse_amount(bad).is_err(), "{:?} should not parse", bad);
+    }
+    assert!(parse_amount(&"9".repeat(29)).is_err());
+}
+
+#[test]
+fn test_rules_reject_zero_extra_decimals_and_huge_amounts() {
+    let rules = AmountRules::default();
+    let mut errors = Vec::new();
+
+    rules.check("amount", dec!(0.01), &mut errors);
+    // Trailing zeros aren't extra precision
+    rules.check("amount", dec!(10.5000), &mut errors);
+    assert!(errors.is_empty());
+
+    rules.check("zero", dec!(0), &mut errors);
+    rules.check("negative", dec!(-1), &mut errors);
+    rules.check("precise", dec!(10.125), &mut errors);
+    rules.check("huge", dec!(1000000000.01), &mut errors);
+
+    let fields: Vec<&str> = errors.iter().map(|e| e.field.as_str()).collect();
+    assert_eq!(fields, ["zero", "negative", "precise", "huge"]);
+    assert_eq!(errors[2].message, "must have at most 2 decimal places");
+}
+
+#[test]
+fn test_unparseable_amount_is_reported_on_its_field() {
+    let result: Result<FundWalletRequest, _> = validation::from_json(json!({"amount": "NaN"}));
+
+    match result {
+        Err(WalletError::ValidationFailed(errors)) => {
+            assert_eq!(errors.len(), 1);
+            assert_eq!(errors[0].field, "amount");
+        }
+        other => panic!("Expected ValidationFailed, got {:?}", other),
+    }
+
+    // Numbers lose precision as floats - amounts must be strings
+    let result: Result<FundWalletRequest, _> = validation::from_json(json!({"amount": 10.5}));
+    assert!(matches!(result, Err(WalletError::ValidationFailed(_))));
+}
+
+#[test]
+fn test_valid_bodies_pass_and_optional_amounts_are_checked_when_present() {
+    let rules = AmountRules::default();
+
+    let transfer: TransferRequest =
+        validation::from_json(json!({"beneficiary_id": "1b4e28ba-2fa1-11d2-883f-0016d3cca427", "amount": "25.00"}))
+            .unwrap();
+    assert!(validation::validate(&transfer, &rules).is_ok());
+
+    let defaults: ExecuteTemplateRequest = validation::from_json(json!({})).unwrap();
+    assert!(validation::validate(&defaults, &rules).is_ok());
+
+    let too_precise: ExecuteTemplateRequest =
+        validation::from_json(json!({"amount": "1.001"})).unwrap();
+    assert!(validation::validate(&too_precise, &rules).is_err());
+}
+
+fn field_errors<T: validation::ValidateRequest>(payload: &T) -> Vec<FieldError> {
+    match validation::validate(payload, &AmountRules::default()) {
+        Err(WalletError::ValidationFailed(errors)) => errors,
+        other => panic!("Expected ValidationFailed, got {:?}", other),
+    }
+}
+
+#[test]
+fn test_empty_and_overlong_strings_are_rejected() {
+    let empty: CreateWalletRequest = validation::from_json(json!({"user_id": ""})).unwrap();
+    assert_eq!(
+        field_errors(&empty),
+        [FieldError::new("user_id", "must be 1 to 100 characters")]
+    );
+
+    let long_name: CreateBeneficiaryRequest =
+        validation::from_json(json!({"name": "x".repeat(101), "beneficiary_user_id": "user-2"})).unwrap();
+    assert_eq!(field_errors(&long_name)[0].field, "name");
+}
+
+#[test]
+fn test_wallet_ids_must_be_uuids() {
+    let ok: TransferRequest = validation::from_json(json!({
+        "to_wallet_id": "1b4e28ba-2fa1-11d2-883f-0016d3cca427",
+        "amount": "5.00"
+    }))
+    .unwrap();
+    assert!(validation::validate(&ok, &AmountRules::default()).is_ok());
+
+    let bad: TransferRequest =
+        validation::from_json(json!({"to_wallet_id": "user-2", "amount": "0", "memo": "m".repeat(300)})).unwrap();
+    // Every problem at once: field checks by name, then amounts
+    let fields: Vec<String> = field_errors(&bad).into_iter().map(|e| e.field).collect();
+    assert_eq!(fields, ["memo", "to_wallet_id", "amount"]);
+}
+
+#[test]
+fn test_batch_get_takes_1_to_100_uuids() {
+    let id = "1b4e28ba-2fa1-11d2-883f-0016d3cca427";
+    let ok: BatchGetWalletsRequest = validation::from_json(json!({"wallet_ids": [id, id]})).unwrap();
+    assert!(validation::validate(&ok, &AmountRules::default()).is_ok());
+
+    let empty: BatchGetWalletsRequest = validation::from_json(json!({"wallet_ids": []})).unwrap();
+    assert_eq!(field_errors(&empty)[0].field, "wallet_ids");
+
+    let too_many: BatchGetWalletsRequest =
+        validation::from_json(json!({"wallet_ids": vec![id; 101]})).unwrap();
+    assert_eq!(field_errors(&too_many)[0].message, "must list 1 to 100 wallet IDs");
+}
+
+#[test]
+fn test_missing_field_is_reported_against_the_body() {
+    let result: Result<CreateWalletRequest, _> = validation::from_json(json!({}));
+
+    match result {
+        Err(WalletError::ValidationFailed(errors)) => {
+            assert_eq!(errors[0].field, "body");
+            assert!(errors[0].message.contains("user_id"));
+        }
+        other => panic!("Expected ValidationFailed, got {:?}", other),
+    }
+}
+
+#[tokio::test]
+async fn test_validation_failure_is_a_422_listing_the_fields() {
+    let error = WalletError::ValidationFailed(vec![FieldError::new("amount", "must be greater than 0")]);
+
+    let response = error.into_response();
+    assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let body: serde_json::Value = serde_json::from_slice(&body).unwrap();
+    assert_eq!(body["success"], false);
+    assert_eq!(body["errors"][0]["field"], "amount");
+    assert_eq!(body["errors"][0]["message"], "must be greater than 0");
+}
@@ -0,0 +1,76 @@
+//! Integration tests for per-wallet velocity limits on transfers
+//!
+//! Run with: cargo test --test velocity -- --test-threads=1
+
+mod common;
+
+use common::{cleanup_test_data, setup_test_db};
+use rust_decimal_macros::dec;
+use std::time::Duration;
+use wallet_service::{errors::WalletError, repository::WalletRepository, velocity::VelocityLimits};
+
+#[test]
+fn test_limits_count_the_transfer_being_made() {
+    let limits = VelocityLimits {
+        max_transfers: Some(2),
+        max_volume: Some(dec!(100)),
+        window: Duration::from_secs(3600),
+    };
+    assert!(!limits.is_off());
+    assert!(VelocityLimits::default().is_off());
+
+    assert!(limits.check(1, dec!(40), dec!(60)).is_ok());
+
+    let count = limits.check(2, dec!(0), dec!(1));
+    assert!(matches!(
+        count,
+        Err(WalletError::VelocityLimitExceeded { measure: "transfers", attempted, window_secs: 3600, .. })
+            if attempted == dec!(3)
+    ));
+
+    let volume = limits.check(0, dec!(40), dec!(60.01));
+    assert!(matches!(
+        volume,
+        Err(WalletError::VelocityLimitExceeded { measure: "volume", limit, attempted, .. })
+            if limit == dec!(100) && attempted == dec!(100.01)
+    ));
+}
+
+#[tokio::test]
+async fn test_transfers_over_the_limit_are_refused_and_move_nothing() {
+    let pool = setup_test_db().await;
+    let repo = WalletRepository::new(pool.clone()).with_velocity_limits(VelocityLimits {
+        max_transfers: Some(2),
+        max_volume: Some(dec!(150)),
+        window: Duration::from_secs(3600),
+    });
+    let alice = repo.create_wallet("alice").await.unwrap();
+    let bob = repo.create_wallet("bob").await.unwrap();
+    repo.fund_wallet(&alice.id, dec!(1000)).await.unwrap();
+
+    repo.transfer(&alice.id, &bob.id, dec!(100), None).await.unwrap();
+
+    // Volume: 100 + 60 > 150
+    let volume = repo.transfer(&alice.id, &bob.id, dec!(60), None).await;
+    assert!(matches!(volume, Err(WalletError::VelocityLimitExceeded { measure: "volume", .. })));
+
+    repo.transfer(&alice.id, &bob.id, dec!(50), None).await.unwrap();
+
+    // Count: a third transfer, however small
+    let count = repo.transfer(&alice.id, &bob.id, dec!(0.01), None).await;
+    assert!(matches!(count, Err(WalletError::VelocityLimitExceeded { measure: "transfers", .. })));
+    assert_eq!(repo.find_by_id(&alice.id).await.unwrap().balance, dec!(850));
+
+    // Incoming transfers don't count against the receiver
+    repo.transfer(&bob.id, &alice.id, dec!(10), None).await.unwrap();
+
+    // Transfers older than the window no longer count
+    sqlx::query("UPDATE wallet_transactions SET created_at = NOW() - INTERVAL '2 hours' WHERE wallet_id = $1")
+        .bind(&alice.id)
+        .execute(&pool)
+        .await
+        .unwrap();
+    repo.transfer(&alice.id, &bob.id, dec!(150), None).await.unwrap();
+
+    cleanup_test_data(&pool).await;
+}
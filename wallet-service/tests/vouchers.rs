@@ -0,0 +1,82 @@
+//! Integration tests for voucher redemption
+//!
+//! Run with: cargo test --test vouchers -- --test-threads=1
+
+mod common;
+
+use common::{cleanup_test_data, setup_test_db};
+use rust_decimal_macros::dec;
+use wallet_service::{errors::WalletError, models::TransactionType, repository::WalletRepository};
+
+#[tokio::test]
+async fn test_redeem_voucher_funds_wallet_once() {
+    let pool = setup_test_db().await;
+    let repo = WalletRepository::new(pool.clone());
+
+    let wallet = repo.create_wallet("alice").await.unwrap();
+    let other = repo.create_wallet("bob").await.unwrap();
+    let voucher = repo
+        .create_voucher(dec!(25), "admin")
+        .await
+        .expect("Failed to mint voucher");
+    assert_eq!(voucher.code.len(), 16);
+    assert!(voucher.redeemed_at.is_none());
+
+    // Codes are matched case-insensitively, ignoring dashes
+    let typed = format!("{}-{}", &voucher.code[..8], &voucher.code[8..]).to_lowercase();
+    let (updated, transaction, redeemed) = repo
+        .redeem_voucher(&wallet.id, &typed)
+        .await
+        .expect("Failed to redeem voucher");
+
+    assert_eq!(updated.balance, dec!(25));
+    assert!(matches!(transaction.transaction_type, TransactionType::VoucherRedeem));
+    assert_eq!(transaction.reference_id.as_deref(), Some(voucher.id.as_str()));
+    assert_eq!(redeemed.wallet_id.as_deref(), Some(wallet.id.as_str()));
+    assert_eq!(redeemed.transaction_id.as_deref(), Some(transaction.id.as_str()));
+
+    // Single use - even for a different wallet
+    let result = repo.redeem_voucher(&other.id, &voucher.code).await;
+    assert!(matches!(result, Err(WalletError::VoucherAlreadyRedeemed)));
+    assert_eq!(repo.find_by_id(&other.id).await.unwrap().balance, dec!(0));
+
+    cleanup_test_data(&pool).await;
+}
+
+#[tokio::test]
+async fn test_redeem_unknown_voucher() {
+    let pool = setup_test_db().await;
+    let repo = WalletRepository::new(pool.clone());
+
+    let wallet = repo.create_wallet("alice").await.unwrap();
+
+    let result = repo.redeem_voucher(&wallet.id, "NOTAREALCODE2345").await;
+    assert!(matches!(result, Err(WalletError::VoucherNotFound)));
+
+    let result = repo.create_voucher(dec!(0), "admin").await;
+    assert!(matches!(result, Err(WalletError::InvalidAmount(_))));
+
+    cleanup_test_data(&pool).await;
+}
+
+#[tokio::test]
+async fn test_concurrent_redemptions_credit_once() {
+    let pool = setup_test_db().await;
+    let repo = WalletRepository::new(pool.clone());
+
+    let wallet_a = repo.create_wallet("alice").await.unwrap();
+    let wallet_b = repo.create_wallet("bob").await.unwrap();
+    let voucher = repo.create_voucher(dec!(10), "admin").await.unwrap();
+
+    let (a, b) = tokio::join!(
+        repo.redeem_voucher(&wallet_a.id, &voucher.code),
+        repo.redeem_voucher(&wallet_b.id, &voucher.code),
+    );
+    assert_eq!(a.is_ok() as u8 + b.is_ok() as u8, 1);
+
+    let total = repo.find_by_id(&wallet_a.id).await.unwrap().balance
+        + repo.find_by_id(&wallet_b.id).await.unwrap().balance;
+    assert_eq!(total, dec!(10));
+
+    cleanup_test_data(&pool).await;
+}
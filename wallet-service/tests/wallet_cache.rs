@@ -0,0 +1,93 @@
+//! Integration tests for the Redis wallet cache
+//!
+//! Need Postgres and Redis (TEST_REDIS_URL, default db 15 - flushed); they
+//! pass without checking anything when Redis isn't reachable.
+//! Run with: cargo test --test wallet_cache -- --test-threads=1
+
+mod common;
+
+use common::{cleanup_test_data, setup_test_cache, setup_test_db};
+use rust_decimal_macros::dec;
+use serde_json::json;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use wallet_service::errors::WalletError;
+use wallet_service::models::UpdateWalletRequest;
+use wallet_service::repository::WalletRepository;
+use wallet_service::validation::from_json;
+use wallet_service::wallet_cache::CacheKey;
+
+#[tokio::test]
+async fn test_commits_invalidate_cached_wallets() {
+    let Some(cache) = setup_test_cache().await else {
+        return;
+    };
+    let pool = setup_test_db().await;
+    cleanup_test_data(&pool).await;
+    let repo = WalletRepository::new(pool.clone()).with_cache(cache);
+
+    let alice = repo.create_wallet("alice").await.unwrap();
+    let bob = repo.create_wallet("bob").await.unwrap();
+    // Cache both, and alice's list
+    repo.find_by_id(&alice.id).await.unwrap();
+    repo.find_by_id(&bob.id).await.unwrap();
+    assert_eq!(repo.find_by_user_id("alice").await.unwrap().len(), 1);
+
+    repo.fund_wallet(&alice.id, dec!(100)).await.unwrap();
+    assert_eq!(repo.find_by_id(&alice.id).await.unwrap().balance, dec!(100));
+    assert_eq!(repo.find_by_user_id("alice").await.unwrap()[0].balance, dec!(100));
+
+    repo.transfer(&alice.id, &bob.id, dec!(40), None).await.unwrap();
+    assert_eq!(repo.find_by_id(&alice.id).await.unwrap().balance, dec!(60));
+    assert_eq!(repo.find_by_id(&bob.id).await.unwrap().balance, dec!(40));
+
+    // A new pot shows up in the owner's list
+    repo.create_pot(&alice.id, "Holiday").await.unwrap();
+    assert_eq!(repo.find_by_user_id("alice").await.unwrap().len(), 2);
+
+    // Changing the default wallet changes the previous default too
+    let second = repo.create_wallet("alice").await.unwrap();
+    let make_default: UpdateWalletRequest = from_json(json!({"is_default": true})).unwrap();
+    repo.update_wallet_metadata(&alice.id, &make_default, None).await.unwrap();
+    assert!(repo.find_by_id(&alice.id).await.unwrap().is_default);
+    repo.update_wallet_metadata(&second.id, &make_default, None).await.unwrap();
+    assert!(!repo.find_by_id(&alice.id).await.unwrap().is_default);
+
+    cleanup_test_data(&pool).await;
+}
+
+#[tokio::test]
+async fn test_loads_that_race_an_invalidation_are_not_cached() {
+    let Some(cache) = setup_test_cache().await else {
+        return;
+    };
+    let loads = AtomicUsize::new(0);
+    let load = || async {
+        loads.fetch_add(1, Ordering::SeqCst);
+        Ok::<_, WalletError>(dec!(5))
+    };
+
+    // A commit lands while the first read is loading
+    cache
+        .get_or_load(CacheKey::Wallet("w-1"), || async {
+            cache.invalidate_keys(&[CacheKey::Wallet("w-1")]).await;
+            load().await
+        })
+        .await
+        .unwrap();
+    cache.get_or_load(CacheKey::Wallet("w-1"), load).await.unwrap();
+    assert_eq!(loads.load(Ordering::SeqCst), 2, "the raced load must not be cached");
+
+    // That second, undisturbed load was
+    cache.get_or_load(CacheKey::Wallet("w-1"), load).await.unwrap();
+    assert_eq!(loads.load(Ordering::SeqCst), 2);
+
+    // Errors aren't cached
+    let missing = cache
+        .get_or_load(CacheKey::Wallet("w-2"), || async {
+            Err::<rust_decimal::Decimal, _>(WalletError::WalletNotFound("w-2".to_string()))
+        })
+        .await;
+    assert!(matches!(missing, Err(WalletError::WalletNotFound(_))));
+    cache.get_or_load(CacheKey::Wallet("w-2"), load).await.unwrap();
+    assert_eq!(loads.load(Ordering::SeqCst), 3);
+}
@@ -0,0 +1,54 @@
+//! Tests for GET /wallets/:id?include= (no database needed)
+
+use chrono::{DateTime, Utc};
+use rust_decimal_macros::dec;
+use wallet_service::errors::WalletError;
+use wallet_service::handlers::includes_recent_transactions;
+use wallet_service::models::{Wallet, WalletDetailResponse, WalletResponse};
+
+fn wallet() -> Wallet {
+    // Fixed, so two calls build equal wallets
+    let at: DateTime<Utc> = "2025-03-01T00:00:00Z".parse().unwrap();
+    Wallet {
+        id: "w-1".to_string(),
+        user_id: "alice".to_string(),
+        balance: dec!(12.50),
+        version: 3,
+        parent_wallet_id: None,
+        nickname: None,
+        labels: Vec::new(),
+        is_default: false,
+        created_at: at,
+        updated_at: at,
+    }
+}
+
+#[test]
+fn test_include_parsing() {
+    assert!(!includes_recent_transactions(None).unwrap());
+    assert!(!includes_recent_transactions(Some("")).unwrap());
+    assert!(includes_recent_transactions(Some("recent_transactions")).unwrap());
+    assert!(includes_recent_transactions(Some(" recent_transactions ,")).unwrap());
+
+    let typo = includes_recent_transactions(Some("recent_transaction"));
+    assert!(matches!(typo, Err(WalletError::InvalidRequest(_))));
+}
+
+#[test]
+fn test_detail_is_the_plain_wallet_unless_transactions_were_asked_for() {
+    let plain = serde_json::to_value(WalletDetailResponse {
+        wallet: WalletResponse::from(wallet()),
+        recent_transactions: None,
+    })
+    .unwrap();
+    // Same shape as before ?include= existed
+    assert_eq!(plain, serde_json::to_value(WalletResponse::from(wallet())).unwrap());
+
+    let detailed = serde_json::to_value(WalletDetailResponse {
+        wallet: WalletResponse::from(wallet()),
+        recent_transactions: Some(Vec::new()),
+    })
+    .unwrap();
+    assert_eq!(detailed["id"], "w-1");
+    assert_eq!(detailed["recent_transactions"], serde_json::json!([]));
+}
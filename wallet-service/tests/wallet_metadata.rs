@@ -0,0 +1,111 @@
+//! Integration tests for wallet metadata (PATCH /wallets/:id)
+//!
+//! Run with: cargo test --test wallet_metadata -- --test-threads=1
+
+mod common;
+
+use common::{cleanup_test_data, setup_test_db};
+use rust_decimal_macros::dec;
+use serde_json::json;
+use wallet_service::errors::WalletError;
+use wallet_service::models::UpdateWalletRequest;
+use wallet_service::repository::WalletRepository;
+use wallet_service::validation::{from_json, validate, AmountRules};
+
+fn update(body: serde_json::Value) -> UpdateWalletRequest {
+    from_json(body).unwrap()
+}
+
+#[test]
+fn test_null_clears_and_absent_leaves_alone() {
+    let cleared = update(json!({"nickname": null}));
+    assert_eq!(cleared.nickname, Some(None));
+    assert_eq!(cleared.labels, None);
+
+    let untouched = update(json!({"is_default": true}));
+    assert_eq!(untouched.nickname, None);
+
+    // A typo'd field is an error, not a silent no-op
+    assert!(from_json::<UpdateWalletRequest>(json!({"nick_name": "x"})).is_err());
+}
+
+#[test]
+fn test_labels_are_validated() {
+    let rules = AmountRules::default();
+    let check = |body| validate(&update(body), &rules);
+
+    assert!(check(json!({"labels": ["bills", "travel"]})).is_ok());
+    assert!(check(json!({"labels": [" "]})).is_err());
+    assert!(check(json!({"labels": ["x".repeat(51)]})).is_err());
+    let too_many: Vec<String> = (0..21).map(|i| i.to_string()).collect();
+    assert!(check(json!({ "labels": too_many })).is_err());
+    assert!(check(json!({"nickname": ""})).is_err());
+}
+
+#[tokio::test]
+async fn test_patch_changes_metadata_and_bumps_the_version() {
+    let pool = setup_test_db().await;
+    cleanup_test_data(&pool).await;
+    let repo = WalletRepository::new(pool.clone());
+
+    let wallet = repo.create_wallet("alice").await.unwrap();
+    repo.fund_wallet(&wallet.id, dec!(25)).await.unwrap();
+    let wallet = repo.find_by_id(&wallet.id).await.unwrap();
+
+    let updated = repo
+        .update_wallet_metadata(
+            &wallet.id,
+            &update(json!({"nickname": " Bills ", "labels": ["monthly"]})),
+            Some(wallet.version),
+        )
+        .await
+        .unwrap();
+    assert_eq!(updated.nickname.as_deref(), Some("Bills"));
+    assert_eq!(updated.labels, vec!["monthly".to_string()]);
+    assert_eq!(updated.version, wallet.version + 1);
+    assert_eq!(updated.balance, dec!(25));
+
+    let cleared = repo
+        .update_wallet_metadata(&wallet.id, &update(json!({"nickname": null})), None)
+        .await
+        .unwrap();
+    assert_eq!(cleared.nickname, None);
+    assert_eq!(cleared.labels, vec!["monthly".to_string()]);
+
+    // Nothing to change, nothing to bump
+    let same = repo
+        .update_wallet_metadata(&wallet.id, &update(json!({"labels": ["monthly"]})), None)
+        .await
+        .unwrap();
+    assert_eq!(same.version, cleared.version);
+
+    // Stale If-Match
+    let stale = repo
+        .update_wallet_metadata(&wallet.id, &update(json!({"nickname": "x"})), Some(wallet.version))
+        .await;
+    assert!(matches!(stale, Err(WalletError::PreconditionFailed { .. })));
+}
+
+#[tokio::test]
+async fn test_one_default_wallet_per_user() {
+    let pool = setup_test_db().await;
+    cleanup_test_data(&pool).await;
+    let repo = WalletRepository::new(pool.clone());
+
+    let first = repo.create_wallet("alice").await.unwrap();
+    let second = repo.create_wallet("alice").await.unwrap();
+    let make_default = update(json!({"is_default": true}));
+
+    repo.update_wallet_metadata(&first.id, &make_default, None).await.unwrap();
+    let second = repo.update_wallet_metadata(&second.id, &make_default, None).await.unwrap();
+    assert!(second.is_default);
+
+    let first = repo.find_by_id(&first.id).await.unwrap();
+    assert!(!first.is_default);
+    assert_eq!(first.version, 2);
+
+    // Pots can't be the default
+    let pot = repo.create_pot(&second.id, "Holiday").await.unwrap();
+    let result = repo.update_wallet_metadata(&pot.id, &make_default, None).await;
+    assert!(matches!(result, Err(WalletError::InvalidRequest(_))));
+}
@@ -0,0 +1,121 @@
+//! Integration tests for the wallet-state publisher
+//!
+//! Run with: cargo test --test wallet_state -- --test-threads=1
+
+mod common;
+
+use common::{cleanup_test_data, setup_test_db};
+use rust_decimal_macros::dec;
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+use wallet_service::errors::{WalletError, WalletResult};
+use wallet_service::repository::WalletRepository;
+use wallet_service::wallet_state::{StatePublisher, WalletSnapshot, WalletStatePublisher};
+
+/// (key, snapshot) pairs sent; a None snapshot is a tombstone
+type Sent = Vec<(String, Option<WalletSnapshot>)>;
+
+/// Records (key, snapshot) pairs; fails everything while `down`
+#[derive(Clone, Default)]
+struct FakePublisher {
+    sent: Arc<Mutex<Sent>>,
+    down: Arc<Mutex<bool>>,
+}
+
+impl StatePublisher for FakePublisher {
+    fn send_state(
+        &self,
+        wallet_id: &str,
+        snapshot: Option<&str>,
+    ) -> impl Future<Output = WalletResult<()>> + Send {
+        let result = if *self.down.lock().unwrap() {
+            Err(WalletError::KafkaError("broker unavailable".to_string()))
+        } else {
+            let snapshot = snapshot.map(|s| serde_json::from_str(s).unwrap());
+            self.sent.lock().unwrap().push((wallet_id.to_string(), snapshot));
+            Ok(())
+        };
+        async move { result }
+    }
+}
+
+#[tokio::test]
+async fn test_publishes_latest_state_once_per_wallet() {
+    let pool = setup_test_db().await;
+    cleanup_test_data(&pool).await;
+    let repo = WalletRepository::new(pool.clone());
+    let fake = FakePublisher::default();
+    let publisher = WalletStatePublisher::new(pool.clone(), fake.clone(), 100);
+
+    let wallet = repo.create_wallet("state_user_1").await.unwrap();
+    repo.fund_wallet(&wallet.id, dec!(10)).await.unwrap();
+    repo.fund_wallet(&wallet.id, dec!(5)).await.unwrap();
+
+    let pass = publisher.run_once().await.unwrap();
+    assert_eq!(pass.published, 1);
+    assert_eq!(pass.changes, 3);
+
+    let sent = fake.sent.lock().unwrap().clone();
+    let (key, snapshot) = &sent[0];
+    let snapshot = snapshot.as_ref().unwrap();
+    assert_eq!(key, &wallet.id);
+    assert_eq!(snapshot.balance, dec!(15));
+    assert_eq!(snapshot.version, 2);
+    assert_eq!(snapshot.status, "ACTIVE");
+    assert_eq!(publisher.pending().await.unwrap(), 0);
+
+    cleanup_test_data(&pool).await;
+}
+
+#[tokio::test]
+async fn test_failed_send_keeps_changes_queued() {
+    let pool = setup_test_db().await;
+    cleanup_test_data(&pool).await;
+    let repo = WalletRepository::new(pool.clone());
+    let fake = FakePublisher::default();
+    let publisher = WalletStatePublisher::new(pool.clone(), fake.clone(), 100);
+
+    let wallet = repo.create_wallet("state_user_2").await.unwrap();
+    *fake.down.lock().unwrap() = true;
+
+    let pass = publisher.run_once().await.unwrap();
+    assert_eq!(pass.failed, 1);
+    assert_eq!(publisher.pending().await.unwrap(), 1);
+
+    // Funded during the outage: the retry sends the newer balance
+    repo.fund_wallet(&wallet.id, dec!(20)).await.unwrap();
+    *fake.down.lock().unwrap() = false;
+
+    let pass = publisher.run_once().await.unwrap();
+    assert_eq!(pass.published, 1);
+    let sent = fake.sent.lock().unwrap().clone();
+    assert_eq!(sent.len(), 1);
+    assert_eq!(sent[0].1.as_ref().unwrap().balance, dec!(20));
+
+    cleanup_test_data(&pool).await;
+}
+
+#[tokio::test]
+async fn test_deleted_wallet_gets_a_tombstone() {
+    let pool = setup_test_db().await;
+    cleanup_test_data(&pool).await;
+    let repo = WalletRepository::new(pool.clone());
+    let fake = FakePublisher::default();
+    let publisher = WalletStatePublisher::new(pool.clone(), fake.clone(), 100);
+
+    let wallet = repo.create_wallet("state_user_3").await.unwrap();
+    publisher.run_once().await.unwrap();
+
+    sqlx::query("DELETE FROM wallets WHERE id = $1")
+        .bind(&wallet.id)
+        .execute(&pool)
+        .await
+        .unwrap();
+    publisher.run_once().await.unwrap();
+
+    let sent = fake.sent.lock().unwrap().clone();
+    assert_eq!(sent.len(), 2);
+    assert_eq!(sent[1], (wallet.id.clone(), None));
+
+    cleanup_test_data(&pool).await;
+}
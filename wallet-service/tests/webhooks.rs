@@ -0,0 +1,243 @@
+//! Integration tests for webhook subscriptions and the dispatcher
+//!
+//! Run with: cargo test --test webhooks -- --test-threads=1
+
+mod common;
+
+use common::{cleanup_test_data, setup_test_db};
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+use uuid::Uuid;
+use wallet_service::errors::WalletError;
+use wallet_service::models::UpdateWebhookRequest;
+use wallet_service::repository::WalletRepository;
+use wallet_service::webhooks::{
+    queue_deliveries, retry_delay_secs, sign, DispatchPass, SendOutcome, WebhookDispatcher,
+    WebhookSender, DELIVERY_ID_HEADER, SIGNATURE_HEADER,
+};
+
+/// (url, headers, body) of each request
+type Sent = Vec<(String, Vec<(&'static str, String)>, String)>;
+
+/// Records requests; answers every one with `outcome`
+#[derive(Clone)]
+struct FakeSender {
+    sent: Arc<Mutex<Sent>>,
+    outcome: Arc<Mutex<SendOutcome>>,
+}
+
+impl FakeSender {
+    fn answering(outcome: SendOutcome) -> Self {
+        Self {
+            sent: Arc::default(),
+            outcome: Arc::new(Mutex::new(outcome)),
+        }
+    }
+}
+
+impl WebhookSender for FakeSender {
+    fn post(
+        &self,
+        url: &str,
+        headers: &[(&'static str, String)],
+        body: &str,
+    ) -> impl Future<Output = SendOutcome> + Send {
+        self.sent
+            .lock()
+            .unwrap()
+            .push((url.to_string(), headers.to_vec(), body.to_string()));
+        let outcome = self.outcome.lock().unwrap().clone();
+        async move { outcome }
+    }
+}
+
+fn header<'a>(headers: &'a [(&'static str, String)], name: &str) -> &'a str {
+    headers
+        .iter()
+        .find(|(n, _)| *n == name)
+        .map(|(_, v)| v.as_str())
+        .unwrap()
+}
+
+#[test]
+fn test_signature_is_hmac_of_timestamp_and_body() {
+    let signature = sign("whsec_test", 1_700_000_000, r#"{"eventType":"WALLET_FUNDED"}"#);
+
+    assert!(signature.starts_with("t=1700000000,v1="));
+    assert_eq!(signature.len(), "t=1700000000,v1=".len() + 64);
+    // Any change to the body, time or secret changes it
+    assert_ne!(signature, sign("whsec_test", 1_700_000_001, r#"{"eventType":"WALLET_FUNDED"}"#));
+    assert_ne!(signature, sign("whsec_other", 1_700_000_000, r#"{"eventType":"WALLET_FUNDED"}"#));
+}
+
+#[test]
+fn test_retry_delay_doubles_up_to_the_cap() {
+    assert_eq!(retry_delay_secs(1), 30);
+    assert_eq!(retry_delay_secs(2), 60);
+    assert_eq!(retry_delay_secs(5), 480);
+    assert_eq!(retry_delay_secs(50), 6 * 60 * 60);
+}
+
+#[tokio::test]
+async fn test_webhook_crud_hides_the_secret_after_creation() {
+    let pool = setup_test_db().await;
+    cleanup_test_data(&pool).await;
+    let repo = WalletRepository::new(pool.clone());
+
+    let created = repo
+        .create_webhook("https://example.com/hooks", &["WALLET_FUNDED".to_string()], Some("ops"))
+        .await
+        .unwrap();
+    assert!(created.secret.starts_with("whsec_"));
+    assert!(created.active);
+
+    let listed = serde_json::to_value(repo.find_webhooks().await.unwrap()).unwrap();
+    assert_eq!(listed[0]["id"], created.id.as_str());
+    assert!(listed[0].get("secret").is_none());
+
+    let paused = repo
+        .update_webhook(
+            &created.id,
+            &UpdateWebhookRequest {
+                active: Some(false),
+                ..UpdateWebhookRequest::default()
+            },
+        )
+        .await
+        .unwrap();
+    assert!(!paused.active);
+    assert_eq!(paused.url, "https://example.com/hooks");
+    assert_eq!(paused.event_types, vec!["WALLET_FUNDED".to_string()]);
+
+    repo.delete_webhook(&created.id).await.unwrap();
+    let result = repo.find_webhook(&created.id).await;
+    assert!(matches!(result, Err(WalletError::WebhookNotFound(_))));
+
+    cleanup_test_data(&pool).await;
+}
+
+#[tokio::test]
+async fn test_invalid_url_and_event_type_are_rejected() {
+    let pool = setup_test_db().await;
+    cleanup_test_data(&pool).await;
+    let repo = WalletRepository::new(pool.clone());
+
+    let bad_url = repo.create_webhook("ftp://example.com", &[], None).await;
+    assert!(matches!(bad_url, Err(WalletError::InvalidRequest(_))));
+
+    let bad_type = repo
+        .create_webhook("https://example.com/hooks", &["WALLET_EXPLODED".to_string()], None)
+        .await;
+    assert!(matches!(bad_type, Err(WalletError::InvalidRequest(_))));
+
+    cleanup_test_data(&pool).await;
+}
+
+#[tokio::test]
+async fn test_events_are_queued_for_matching_active_subscriptions_only() {
+    let pool = setup_test_db().await;
+    cleanup_test_data(&pool).await;
+    let repo = WalletRepository::new(pool.clone());
+
+    repo.create_webhook("https://a.example.com/all", &[], None).await.unwrap();
+    repo.create_webhook("https://b.example.com/funding", &["WALLET_FUNDED".to_string()], None)
+        .await
+        .unwrap();
+    let paused = repo.create_webhook("https://c.example.com/paused", &[], None).await.unwrap();
+    repo.update_webhook(
+        &paused.id,
+        &UpdateWebhookRequest {
+            active: Some(false),
+            ..UpdateWebhookRequest::default()
+        },
+    )
+    .await
+    .unwrap();
+
+    let funded = Uuid::new_v4().to_string();
+    assert_eq!(queue_deliveries(&pool, &funded, "WALLET_FUNDED", "{}").await.unwrap(), 2);
+    let created = Uuid::new_v4().to_string();
+    assert_eq!(queue_deliveries(&pool, &created, "WALLET_CREATED", "{}").await.unwrap(), 1);
+    // Published twice, queued once
+    assert_eq!(queue_deliveries(&pool, &funded, "WALLET_FUNDED", "{}").await.unwrap(), 0);
+
+    cleanup_test_data(&pool).await;
+}
+
+#[tokio::test]
+async fn test_dispatcher_signs_and_marks_delivered() {
+    let pool = setup_test_db().await;
+    cleanup_test_data(&pool).await;
+    let repo = WalletRepository::new(pool.clone());
+    let webhook = repo.create_webhook("https://example.com/hooks", &[], None).await.unwrap();
+    let body = r#"{"eventType":"WALLET_FUNDED","amount":"10.00"}"#;
+    queue_deliveries(&pool, &Uuid::new_v4().to_string(), "WALLET_FUNDED", body)
+        .await
+        .unwrap();
+
+    let sender = FakeSender::answering(SendOutcome::Response(204));
+    let dispatcher = WebhookDispatcher::new(pool.clone(), sender.clone(), 10);
+    let pass = dispatcher.run_once().await.unwrap();
+    assert_eq!(pass.delivered, 1);
+    assert_eq!(dispatcher.pending().await.unwrap(), 0);
+
+    let sent = sender.sent.lock().unwrap().clone();
+    let (url, headers, sent_body) = &sent[0];
+    assert_eq!(url, "https://example.com/hooks");
+    assert_eq!(sent_body, body);
+    let signature = header(headers, SIGNATURE_HEADER);
+    let timestamp: i64 = signature[2..signature.find(',').unwrap()].parse().unwrap();
+    assert_eq!(signature, sign(&webhook.secret, timestamp, body));
+
+    let deliveries = repo.find_webhook_deliveries(&webhook.id, 10).await.unwrap();
+    assert_eq!(deliveries[0].status, "DELIVERED");
+    assert_eq!(deliveries[0].id.to_string(), header(headers, DELIVERY_ID_HEADER));
+    let attempts = repo
+        .find_webhook_delivery_attempts(&webhook.id, deliveries[0].id)
+        .await
+        .unwrap();
+    assert_eq!(attempts.len(), 1);
+    assert_eq!(attempts[0].status_code, Some(204));
+
+    cleanup_test_data(&pool).await;
+}
+
+#[tokio::test]
+async fn test_failures_back_off_then_give_up() {
+    let pool = setup_test_db().await;
+    cleanup_test_data(&pool).await;
+    let repo = WalletRepository::new(pool.clone());
+    let webhook = repo.create_webhook("https://example.com/hooks", &[], None).await.unwrap();
+    queue_deliveries(&pool, &Uuid::new_v4().to_string(), "WALLET_FUNDED", "{}")
+        .await
+        .unwrap();
+
+    let sender = FakeSender::answering(SendOutcome::Response(500));
+    let dispatcher = WebhookDispatcher::new(pool.clone(), sender.clone(), 10).with_max_attempts(2);
+
+    let first = dispatcher.run_once().await.unwrap();
+    assert_eq!(first.retrying, 1);
+    // In backoff - nothing due
+    assert_eq!(dispatcher.run_once().await.unwrap(), DispatchPass::default());
+
+    sqlx::query("UPDATE webhook_deliveries SET next_attempt_at = NOW()")
+        .execute(&pool)
+        .await
+        .unwrap();
+    *sender.outcome.lock().unwrap() = SendOutcome::Error("connection refused".to_string());
+    let second = dispatcher.run_once().await.unwrap();
+    assert_eq!(second.gave_up, 1);
+
+    let delivery = &repo.find_webhook_deliveries(&webhook.id, 10).await.unwrap()[0];
+    assert_eq!(delivery.status, "FAILED");
+    assert_eq!(delivery.attempts, 2);
+    assert_eq!(delivery.last_error.as_deref(), Some("connection refused"));
+    let attempts = repo
+        .find_webhook_delivery_attempts(&webhook.id, delivery.id)
+        .await
+        .unwrap();
+    assert_eq!(attempts[0].status_code, Some(500));
+    assert_eq!(attempts[1].status_code, None);
+
+    cleanup_test_data(&pool).await;
+}